@@ -0,0 +1,5 @@
+ALTER TABLE sources DROP COLUMN last_modified_header;
+ALTER TABLE sources DROP COLUMN last_etag;
+ALTER TABLE sources DROP COLUMN last_fetched_at;
+
+DROP TABLE IF EXISTS settings;
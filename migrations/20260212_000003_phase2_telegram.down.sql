@@ -0,0 +1,12 @@
+DROP TABLE IF EXISTS tg_channel_state;
+DROP TABLE IF EXISTS tg_update_state;
+DROP TABLE IF EXISTS tg_peer_info;
+DROP TABLE IF EXISTS tg_dc_option;
+DROP TABLE IF EXISTS tg_dc_home;
+DROP TABLE IF EXISTS tg_folder_channels;
+
+ALTER TABLE sources DROP COLUMN tg_exclude;
+ALTER TABLE sources DROP COLUMN tg_folder_name;
+ALTER TABLE sources DROP COLUMN tg_folder_id;
+ALTER TABLE sources DROP COLUMN tg_username;
+ALTER TABLE sources DROP COLUMN tg_id;
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_article_feedback_channel;
+DROP TABLE IF EXISTS article_feedback;
@@ -0,0 +1,12 @@
+-- Reader feedback notes on generated articles (`pail feedback`).
+-- Notes outlive their article: retention cleanup only clears the article reference.
+CREATE TABLE article_feedback (
+    id TEXT PRIMARY KEY,
+    output_channel_id TEXT NOT NULL REFERENCES output_channels(id) ON DELETE CASCADE,
+    article_id TEXT REFERENCES generated_articles(id) ON DELETE SET NULL,
+    article_title TEXT NOT NULL,
+    note TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
+
+CREATE INDEX idx_article_feedback_channel ON article_feedback(output_channel_id, created_at);
@@ -0,0 +1,8 @@
+-- Cached content item embeddings for relevance filtering.
+-- Keyed by model so switching [embeddings].model recomputes instead of mixing vector spaces.
+CREATE TABLE content_item_embeddings (
+    content_item_id TEXT NOT NULL REFERENCES content_items(id) ON DELETE CASCADE,
+    model TEXT NOT NULL,
+    embedding BLOB NOT NULL,
+    PRIMARY KEY (content_item_id, model)
+);
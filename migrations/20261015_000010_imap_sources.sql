@@ -0,0 +1 @@
+ALTER TABLE sources ADD COLUMN imap_config TEXT;
@@ -0,0 +1,2 @@
+-- Approval workflow: 'draft' (awaiting review), 'published' (in feeds), 'rejected'
+ALTER TABLE generated_articles ADD COLUMN status TEXT NOT NULL DEFAULT 'published';
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_fetched_pages_fetched_at;
+DROP TABLE IF EXISTS fetched_pages;
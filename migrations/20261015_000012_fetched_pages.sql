@@ -0,0 +1,13 @@
+-- Full-text cache for linked articles, fetched once before generation and shared by all
+-- channels and retries. Keyed by URL (not content item) since the same link often
+-- appears in several sources.
+CREATE TABLE fetched_pages (
+    url TEXT PRIMARY KEY,
+    fetched_at TEXT NOT NULL,
+    status TEXT NOT NULL,          -- 'ok' | 'disallowed' (robots.txt) | 'failed' (HTTP 4xx / not extractable)
+    title TEXT,
+    text TEXT,
+    error TEXT
+);
+
+CREATE INDEX idx_fetched_pages_fetched_at ON fetched_pages(fetched_at);
@@ -0,0 +1 @@
+ALTER TABLE output_channels DROP COLUMN paused;
@@ -0,0 +1,3 @@
+-- Runtime pause flag set by `pail ctl pause` / `resume`. Not part of the config file:
+-- config sync leaves it untouched, and a paused channel stays paused across restarts.
+ALTER TABLE output_channels ADD COLUMN paused INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+ALTER TABLE sources DROP COLUMN read_later_config;
@@ -0,0 +1 @@
+ALTER TABLE sources ADD COLUMN read_later_config TEXT;
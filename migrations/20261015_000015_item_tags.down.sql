@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS idx_content_items_untagged;
+ALTER TABLE content_items DROP COLUMN tagged_at;
+
+DROP INDEX IF EXISTS idx_content_item_tags_tag;
+DROP TABLE IF EXISTS content_item_tags;
+DROP TABLE IF EXISTS tags;
@@ -0,0 +1,20 @@
+-- Tags assigned to content items by the tagging pass (see docs/specs/tagging.md)
+CREATE TABLE tags (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
+
+CREATE TABLE content_item_tags (
+    content_item_id TEXT NOT NULL REFERENCES content_items(id) ON DELETE CASCADE,
+    tag_id TEXT NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (content_item_id, tag_id)
+);
+
+CREATE INDEX idx_content_item_tags_tag ON content_item_tags(tag_id);
+
+-- When the tagging pass processed the item (NULL = not yet tagged). Items the model
+-- gave no tags are still marked, so they aren't sent again.
+ALTER TABLE content_items ADD COLUMN tagged_at TEXT;
+
+CREATE INDEX idx_content_items_untagged ON content_items(ingested_at) WHERE tagged_at IS NULL;
@@ -0,0 +1 @@
+ALTER TABLE sources DROP COLUMN tg_account;
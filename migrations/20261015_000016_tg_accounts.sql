@@ -0,0 +1,4 @@
+-- Telegram account a source is read with (NULL = the default [telegram] account).
+-- Sessions of additional accounts live in their own tg_acct_<name>_* tables, created
+-- on first connect (see docs/specs/telegram.md "Multiple Accounts").
+ALTER TABLE sources ADD COLUMN tg_account TEXT;
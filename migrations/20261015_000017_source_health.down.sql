@@ -0,0 +1,5 @@
+ALTER TABLE sources DROP COLUMN health_alerted;
+ALTER TABLE sources DROP COLUMN last_item_at;
+ALTER TABLE sources DROP COLUMN last_success_at;
+ALTER TABLE sources DROP COLUMN last_error;
+ALTER TABLE sources DROP COLUMN consecutive_failures;
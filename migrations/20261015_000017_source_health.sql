@@ -0,0 +1,8 @@
+-- Dead-source detection: fetch outcome tracking per source (see docs/specs/source-health.md)
+ALTER TABLE sources ADD COLUMN consecutive_failures INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE sources ADD COLUMN last_error TEXT;
+ALTER TABLE sources ADD COLUMN last_success_at TEXT;
+-- Newest item date seen in any fetch (not only newly stored items)
+ALTER TABLE sources ADD COLUMN last_item_at TEXT;
+-- Health status last alerted ('failing' / 'stale'); NULL while healthy
+ALTER TABLE sources ADD COLUMN health_alerted TEXT;
@@ -0,0 +1,2 @@
+ALTER TABLE sources DROP COLUMN gone_at;
+ALTER TABLE sources DROP COLUMN moved_url;
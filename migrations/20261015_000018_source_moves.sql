@@ -0,0 +1,5 @@
+-- Moved and gone feeds (see docs/specs/rss-sources.md "Moved and Gone Feeds")
+-- Target of a permanent redirect (301/308) from url; fetched instead of url
+ALTER TABLE sources ADD COLUMN moved_url TEXT;
+-- When the feed answered 410 Gone; the poller skips the source while set
+ALTER TABLE sources ADD COLUMN gone_at TEXT;
@@ -0,0 +1,2 @@
+-- Lemmy / Lobsters sources: JSON-encoded AggregatorSourceConfig (see docs/specs/aggregator-sources.md)
+ALTER TABLE sources ADD COLUMN aggregator_config TEXT;
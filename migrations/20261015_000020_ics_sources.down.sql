@@ -0,0 +1 @@
+ALTER TABLE sources DROP COLUMN ics_config;
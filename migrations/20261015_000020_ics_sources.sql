@@ -0,0 +1,2 @@
+-- Calendar sources: JSON-encoded IcsSourceConfig (see docs/specs/ics-sources.md)
+ALTER TABLE sources ADD COLUMN ics_config TEXT;
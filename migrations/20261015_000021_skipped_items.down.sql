@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_skipped_items_source;
+DROP INDEX IF EXISTS idx_skipped_items_article;
+DROP TABLE IF EXISTS skipped_items;
@@ -0,0 +1,17 @@
+-- Entries of an article's `## Skipped` section (see docs/specs/generation-engine.md "Skipped Items").
+-- `source_id` / `content_item_id` are resolved at parse time and NULL when the entry matched nothing.
+CREATE TABLE skipped_items (
+    id TEXT PRIMARY KEY,
+    article_id TEXT NOT NULL REFERENCES generated_articles(id) ON DELETE CASCADE,
+    output_channel_id TEXT NOT NULL REFERENCES output_channels(id) ON DELETE CASCADE,
+    source_id TEXT REFERENCES sources(id) ON DELETE SET NULL,
+    content_item_id TEXT,
+    title TEXT NOT NULL,
+    url TEXT,
+    reason TEXT NOT NULL,
+    item_count INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
+
+CREATE INDEX idx_skipped_items_article ON skipped_items(article_id);
+CREATE INDEX idx_skipped_items_source ON skipped_items(source_id, created_at);
@@ -0,0 +1,2 @@
+-- X/Twitter sources: JSON-encoded TwitterSourceConfig (see docs/specs/twitter-sources.md)
+ALTER TABLE sources ADD COLUMN twitter_config TEXT;
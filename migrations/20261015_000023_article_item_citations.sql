@@ -0,0 +1,9 @@
+-- Items provided to each article and whether the article links to them (see
+-- docs/specs/generation-engine.md "Citations"). `content_item_id` has no foreign key:
+-- retention deletes items while the article and its coverage stay.
+CREATE TABLE article_item_citations (
+    article_id TEXT NOT NULL REFERENCES generated_articles(id) ON DELETE CASCADE,
+    content_item_id TEXT NOT NULL,
+    cited INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (article_id, content_item_id)
+);
@@ -0,0 +1 @@
+ALTER TABLE generated_articles DROP COLUMN read_at;
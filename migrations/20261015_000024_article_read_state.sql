@@ -0,0 +1,4 @@
+-- When the reader marked a published article read (see docs/specs/atom-feed.md "Read State").
+-- NULL means unread. Articles from before read tracking start out read.
+ALTER TABLE generated_articles ADD COLUMN read_at TEXT;
+UPDATE generated_articles SET read_at = generated_at WHERE status = 'published';
@@ -0,0 +1,2 @@
+-- Slack sources: JSON-encoded SlackSourceConfig (see docs/specs/slack-sources.md)
+ALTER TABLE sources ADD COLUMN slack_config TEXT;
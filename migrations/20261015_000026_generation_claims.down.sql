@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS generation_requests;
+DROP TABLE IF EXISTS generation_claims;
@@ -0,0 +1,20 @@
+-- Coordination between daemon instances sharing the database (see docs/specs/daemon.md "Roles").
+-- A claim marks a channel's generation as running on one instance until `expires_at`; the
+-- instance extends it while the run lasts and deletes it when done.
+CREATE TABLE generation_claims (
+    channel_id TEXT PRIMARY KEY REFERENCES output_channels(id) ON DELETE CASCADE,
+    claimed_by TEXT NOT NULL,
+    claimed_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL
+);
+
+-- On-demand generations queued by a `server` instance for a worker to pick up.
+CREATE TABLE generation_requests (
+    id TEXT PRIMARY KEY,
+    slug TEXT NOT NULL,
+    window_from TEXT,
+    window_to TEXT,
+    since_secs INTEGER,
+    replaces_draft TEXT,
+    requested_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
@@ -0,0 +1,2 @@
+-- arXiv sources: JSON-encoded ArxivSourceConfig (see docs/specs/arxiv-sources.md)
+ALTER TABLE sources ADD COLUMN arxiv_config TEXT;
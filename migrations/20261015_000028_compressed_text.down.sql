@@ -0,0 +1,8 @@
+-- Older versions can't read compressed rows: refuse while any remain.
+-- Run `pail db compress --undo` first.
+CREATE TEMP TABLE compressed_rows (n INTEGER CONSTRAINT run_pail_db_compress_undo_first CHECK (n = 0));
+INSERT INTO compressed_rows SELECT COUNT(*) FROM content_items WHERE body_compressed = 1;
+INSERT INTO compressed_rows SELECT COUNT(*) FROM generated_articles WHERE log_compressed = 1;
+DROP TABLE compressed_rows;
+ALTER TABLE content_items DROP COLUMN body_compressed;
+ALTER TABLE generated_articles DROP COLUMN log_compressed;
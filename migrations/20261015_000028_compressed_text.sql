@@ -0,0 +1,4 @@
+-- Compressed item bodies and generation logs: the value is a zstd frame stored as a BLOB in
+-- the same column when the flag is set (see docs/core.md "Compression")
+ALTER TABLE content_items ADD COLUMN body_compressed INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE generated_articles ADD COLUMN log_compressed INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+ALTER TABLE generated_articles DROP COLUMN eval_details;
+ALTER TABLE generated_articles DROP COLUMN eval_score;
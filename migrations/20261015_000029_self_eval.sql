@@ -0,0 +1,4 @@
+-- Self-evaluation of generated articles (see docs/specs/self-evaluation.md): the overall score
+-- (0.0-1.0) and the rubric result as JSON. NULL for articles that weren't evaluated.
+ALTER TABLE generated_articles ADD COLUMN eval_score REAL;
+ALTER TABLE generated_articles ADD COLUMN eval_details TEXT;
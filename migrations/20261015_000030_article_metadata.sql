@@ -0,0 +1,3 @@
+-- Free-form JSON metadata of generated articles, e.g. the link check report
+-- (see docs/specs/link-verification.md)
+ALTER TABLE generated_articles ADD COLUMN metadata TEXT NOT NULL DEFAULT '{}';
@@ -0,0 +1,3 @@
+-- Per-source proxy, resolved from the source's `proxy` and [network].proxy on config sync
+-- (see docs/specs/network.md)
+ALTER TABLE sources ADD COLUMN proxy TEXT;
@@ -0,0 +1,3 @@
+-- Short summary of generated articles for feed list views: the model's `summary:`
+-- frontmatter field, else the first paragraph (see docs/specs/atom-feed.md)
+ALTER TABLE generated_articles ADD COLUMN summary TEXT;
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_feed_access_log_time;
+DROP TABLE IF EXISTS feed_access_log;
+DROP TABLE IF EXISTS feed_token_channels;
+DROP TABLE IF EXISTS feed_tokens;
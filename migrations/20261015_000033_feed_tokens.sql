@@ -0,0 +1,31 @@
+-- Named feed tokens (`pail tokens`), each limited to some channels or allowed all of them.
+-- Only a SHA-256 of the token is stored; the token itself is shown once at creation.
+CREATE TABLE feed_tokens (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    token_hash TEXT NOT NULL UNIQUE,
+    all_channels INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    last_used_at TEXT,
+    revoked_at TEXT
+);
+
+-- Channels a token without all_channels may read. A deleted channel drops out of the scope.
+CREATE TABLE feed_token_channels (
+    token_id TEXT NOT NULL REFERENCES feed_tokens(id) ON DELETE CASCADE,
+    output_channel_id TEXT NOT NULL REFERENCES output_channels(id) ON DELETE CASCADE,
+    PRIMARY KEY (token_id, output_channel_id)
+);
+
+-- Feed requests, one row per authenticated request. token_id is NULL for the main feed token.
+CREATE TABLE feed_access_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    token_id TEXT REFERENCES feed_tokens(id) ON DELETE SET NULL,
+    token_name TEXT NOT NULL,
+    channel_slug TEXT NOT NULL,
+    status INTEGER NOT NULL,
+    user_agent TEXT,
+    accessed_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_feed_access_log_time ON feed_access_log(accessed_at);
@@ -0,0 +1,12 @@
+-- Progress of `pail tg backfill`, per source and chat (folders have one row per chat), so an
+-- interrupted backfill resumes below the oldest message it stored.
+CREATE TABLE tg_backfill_progress (
+    source_id TEXT NOT NULL REFERENCES sources(id) ON DELETE CASCADE,
+    peer_id INTEGER NOT NULL,
+    -- Oldest message stored so far; the next request continues below it
+    oldest_message_id INTEGER,
+    -- History is complete back to this time (NULL until a backfill finishes)
+    covered_since TEXT,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (source_id, peer_id)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE output_channels DROP COLUMN skipped_from;
+DROP TABLE IF EXISTS generation_runs;
@@ -0,0 +1,19 @@
+-- One row per generation attempt, including the ones that stored nothing.
+CREATE TABLE generation_runs (
+    id TEXT PRIMARY KEY,
+    channel_id TEXT NOT NULL REFERENCES output_channels(id) ON DELETE CASCADE,
+    started_at TEXT NOT NULL,
+    finished_at TEXT NOT NULL,
+    -- generated / skipped / failed
+    status TEXT NOT NULL,
+    -- no_items / min_items / duplicate_window / cancelled
+    skip_reason TEXT,
+    error TEXT,
+    -- Items in the window after filters; NULL if the run failed before counting them
+    item_count INTEGER,
+    article_id TEXT
+);
+CREATE INDEX idx_generation_runs_channel ON generation_runs(channel_id, started_at);
+
+-- Start of the windows skipped for `min_items`; the next `since_last` window starts here
+ALTER TABLE output_channels ADD COLUMN skipped_from TEXT;
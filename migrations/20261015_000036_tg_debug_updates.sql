@@ -0,0 +1,16 @@
+-- Updates received by each Telegram account, recorded with `[telegram].debug_updates` for
+-- `pail tg debug gaps`. Kept for `debug_updates_keep`.
+CREATE TABLE tg_debug_updates (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    account TEXT NOT NULL,
+    received_at TEXT NOT NULL,
+    -- Server container: updates / updatesCombined / updateShort / updateShortMessage / ...
+    container TEXT NOT NULL,
+    update_type TEXT NOT NULL,
+    -- Channel whose pts sequence the update belongs to; NULL for the account's common one
+    channel_id INTEGER,
+    pts INTEGER,
+    pts_count INTEGER,
+    message_id INTEGER
+);
+CREATE INDEX idx_tg_debug_updates_account ON tg_debug_updates(account, received_at);
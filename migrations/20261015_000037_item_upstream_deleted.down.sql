@@ -0,0 +1 @@
+ALTER TABLE content_items DROP COLUMN deleted_upstream_at;
@@ -0,0 +1,3 @@
+-- When the message behind an item was deleted upstream (Telegram, `deleted_messages = "mark"`);
+-- marked items stay stored but are left out of generations
+ALTER TABLE content_items ADD COLUMN deleted_upstream_at TEXT;
@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS idx_generation_runs_started;
+ALTER TABLE generation_runs DROP COLUMN workspace;
+ALTER TABLE generation_runs DROP COLUMN log_compressed;
+ALTER TABLE generation_runs DROP COLUMN log;
+ALTER TABLE generation_runs DROP COLUMN exit_code;
+ALTER TABLE generation_runs DROP COLUMN attempts;
@@ -0,0 +1,9 @@
+-- Model invocations of the run (retries included), and the last one's opencode exit code
+ALTER TABLE generation_runs ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE generation_runs ADD COLUMN exit_code INTEGER;
+-- opencode log of a failed run's last attempt; generated runs have theirs on the article
+ALTER TABLE generation_runs ADD COLUMN log TEXT;
+ALTER TABLE generation_runs ADD COLUMN log_compressed INTEGER NOT NULL DEFAULT 0;
+-- Kept workspace of a failed run (`[opencode].keep_workspace`)
+ALTER TABLE generation_runs ADD COLUMN workspace TEXT;
+CREATE INDEX idx_generation_runs_started ON generation_runs(started_at);
@@ -0,0 +1,2 @@
+-- Per-source dedup strategy of feed sources (see docs/specs/rss-sources.md "Deduplication")
+ALTER TABLE sources ADD COLUMN dedup TEXT;
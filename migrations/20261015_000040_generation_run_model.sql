@@ -0,0 +1,3 @@
+-- Model of a run's last attempt, with per-channel model fallback chains
+-- (see docs/specs/generation-engine.md "Model Fallback")
+ALTER TABLE generation_runs ADD COLUMN model TEXT;
@@ -0,0 +1,22 @@
+-- Items moved out of content_items by the retention cleanup with `[pail].archive = true`
+-- (see docs/specs/daemon.md "Archive"): the item's columns, its tags as a JSON array, and
+-- when it was archived
+CREATE TABLE content_items_archive (
+    id TEXT PRIMARY KEY NOT NULL,
+    source_id TEXT NOT NULL REFERENCES sources(id) ON DELETE CASCADE,
+    ingested_at TEXT NOT NULL,
+    original_date TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    title TEXT,
+    body TEXT NOT NULL,
+    body_compressed INTEGER NOT NULL DEFAULT 0,
+    url TEXT,
+    author TEXT,
+    metadata TEXT NOT NULL DEFAULT '{}',
+    dedup_key TEXT NOT NULL,
+    upstream_changed INTEGER NOT NULL DEFAULT 0,
+    deleted_upstream_at TEXT,
+    tags TEXT NOT NULL DEFAULT '[]',
+    archived_at TEXT NOT NULL
+);
+CREATE INDEX idx_content_items_archive_source_date ON content_items_archive(source_id, original_date);
@@ -0,0 +1,12 @@
+-- Translated variants of generated articles, one per language (see docs/specs/translation.md).
+-- Served with `?lang=` next to the original, which stays the article of record.
+CREATE TABLE article_translations (
+    article_id TEXT NOT NULL REFERENCES generated_articles(id) ON DELETE CASCADE,
+    lang TEXT NOT NULL,
+    title TEXT NOT NULL,
+    body_markdown TEXT NOT NULL,
+    body_html TEXT NOT NULL,
+    model TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (article_id, lang)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE sources DROP COLUMN fetch_timeout;
+ALTER TABLE sources DROP COLUMN headers;
@@ -0,0 +1,4 @@
+-- Per-source request headers (JSON object) and fetch timeout of HTTP sources (see
+-- docs/specs/rss-sources.md "Headers and Timeout")
+ALTER TABLE sources ADD COLUMN headers TEXT;
+ALTER TABLE sources ADD COLUMN fetch_timeout TEXT;
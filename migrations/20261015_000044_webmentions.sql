@@ -0,0 +1,17 @@
+-- Outgoing Webmentions: one per link of a published article, sent by the daemon with
+-- retries (see docs/specs/webmention.md).
+CREATE TABLE webmentions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    article_id TEXT NOT NULL REFERENCES generated_articles(id) ON DELETE CASCADE,
+    target TEXT NOT NULL,
+    -- pending, sent, no_endpoint or failed
+    status TEXT NOT NULL DEFAULT 'pending',
+    endpoint TEXT,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TEXT NOT NULL,
+    last_error TEXT,
+    created_at TEXT NOT NULL,
+    sent_at TEXT,
+    UNIQUE (article_id, target)
+);
+CREATE INDEX idx_webmentions_due ON webmentions(status, next_attempt_at);
@@ -0,0 +1,10 @@
+-- Generations in progress, with the recent output of their opencode process, served at
+-- /runs/<id>/live. A row is deleted when its run is recorded in generation_runs.
+CREATE TABLE live_runs (
+    run_id TEXT PRIMARY KEY,
+    channel_id TEXT NOT NULL REFERENCES output_channels(id) ON DELETE CASCADE,
+    started_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    last_output_at TEXT,
+    tail TEXT NOT NULL DEFAULT ''
+);
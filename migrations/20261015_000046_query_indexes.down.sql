@@ -0,0 +1,2 @@
+-- The indexes from the initial schema stay
+DROP INDEX IF EXISTS idx_generated_articles_generated;
@@ -0,0 +1,11 @@
+-- Indexes of the window and recency queries (see docs/specs/daemon.md "Query Plans").
+-- The first three have been in the schema since the start; they're declared again so a
+-- database that lost one (restored from a dump without indexes, or dropped by hand)
+-- gets it back.
+CREATE INDEX IF NOT EXISTS idx_content_items_source_date ON content_items(source_id, original_date);
+CREATE INDEX IF NOT EXISTS idx_content_items_ingested ON content_items(ingested_at);
+CREATE INDEX IF NOT EXISTS idx_generated_articles_channel ON generated_articles(output_channel_id, generated_at DESC);
+
+-- Listings across channels (`pail articles list`, the admin API) and the article retention
+-- sweep order or filter by generated_at alone, which the channel index can't serve
+CREATE INDEX idx_generated_articles_generated ON generated_articles(generated_at);
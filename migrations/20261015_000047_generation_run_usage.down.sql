@@ -0,0 +1,2 @@
+ALTER TABLE generation_runs DROP COLUMN tokens;
+ALTER TABLE generation_runs DROP COLUMN cost_usd;
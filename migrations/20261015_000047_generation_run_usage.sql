@@ -0,0 +1,4 @@
+-- opencode's reported cost and tokens of a run, summed over its attempts; NULL if no
+-- attempt reported usage (see docs/specs/stats-source.md)
+ALTER TABLE generation_runs ADD COLUMN cost_usd REAL;
+ALTER TABLE generation_runs ADD COLUMN tokens INTEGER;
@@ -0,0 +1,10 @@
+-- Photos of Telegram messages downloaded with `[telegram.media] download = true`, served at
+-- /media/<id> (see docs/specs/telegram.md "Media")
+CREATE TABLE tg_media (
+    id TEXT PRIMARY KEY NOT NULL,
+    chat_id INTEGER NOT NULL,
+    message_id INTEGER NOT NULL,
+    data BLOB NOT NULL,
+    downloaded_at TEXT NOT NULL
+);
+CREATE INDEX idx_tg_media_downloaded ON tg_media(downloaded_at);
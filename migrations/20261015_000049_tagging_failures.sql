@@ -0,0 +1,3 @@
+-- Failed tagging attempts of an item whose batch-mates were tagged (see docs/specs/tagging.md).
+-- The daemon pass skips items that reached the limit.
+ALTER TABLE content_items ADD COLUMN tagging_failures INTEGER NOT NULL DEFAULT 0;
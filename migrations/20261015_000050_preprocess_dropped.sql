@@ -0,0 +1,9 @@
+-- Items a source's pre-processor dropped (empty output), kept so the command doesn't run on
+-- them again on every poll (see docs/specs/preprocessing.md). Cleared with content retention.
+CREATE TABLE preprocess_dropped (
+    source_id TEXT NOT NULL REFERENCES sources(id) ON DELETE CASCADE,
+    dedup_key TEXT NOT NULL,
+    dropped_at TEXT NOT NULL,
+    PRIMARY KEY (source_id, dedup_key)
+);
+CREATE INDEX idx_preprocess_dropped_at ON preprocess_dropped(dropped_at);
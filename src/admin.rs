@@ -0,0 +1,445 @@
+//! Browser admin pages under `/admin` (see docs/specs/admin-ui.md): edit channel prompts,
+//! toggle sources and channels, trigger generations, read drafts. Writes go through the same
+//! config edit path as the admin API.
+
+use std::collections::{HashMap, HashSet};
+
+use axum::Router;
+use axum::extract::{Form, Path, Query, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::routing::{get, post};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+use tracing::{info, warn};
+
+use crate::api::{self, ApiError};
+use crate::config::Config;
+use crate::models::{GeneratedArticleRow, OutputChannel, Source};
+use crate::scheduler::GenerationRequest;
+use crate::server::{AppState, constant_time_eq, csrf_nonce, html_escape};
+use crate::source_health::{self, SourceStatus};
+use crate::{config_edit, store};
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(dashboard))
+        .route("/channels/{slug}", get(channel_page).post(save_channel))
+        .route("/channels/{slug}/{action}", post(channel_action))
+        .route("/sources/{name}/{action}", post(source_action))
+}
+
+#[derive(serde::Deserialize)]
+pub struct AdminForm {
+    csrf: String,
+    prompt: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct NoticeQuery {
+    /// Result of the last form post, shown once above the dashboard.
+    notice: Option<String>,
+}
+
+/// Cookie holding the browser's admin session, which the forms' CSRF nonce is bound to.
+const SESSION_COOKIE: &str = "pail_admin_session";
+
+/// Pages: HTTP Basic Auth with `[pail].admin_token` as the password (any user name).
+/// Without an admin token the pages don't exist (404). Returns the rejection, if any.
+fn reject_page(state: &AppState, headers: &HeaderMap) -> Option<Response> {
+    let config = state.config.borrow();
+    let Some(ref admin_token) = config.pail.admin_token else {
+        return Some((StatusCode::NOT_FOUND, "Admin UI is disabled (set [pail].admin_token)").into_response());
+    };
+    let password = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Basic "))
+        .and_then(|encoded| base64::engine::general_purpose::STANDARD.decode(encoded.trim()).ok())
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+        .and_then(|credentials| credentials.split_once(':').map(|(_, password)| password.to_string()));
+    if password.is_some_and(|password| constant_time_eq(&password, admin_token)) {
+        return None;
+    }
+    Some(
+        (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Basic realm=\"pail admin\"")],
+            "Unauthorized",
+        )
+            .into_response(),
+    )
+}
+
+/// Form posts: authenticated like pages, plus the CSRF nonce of the browser's admin session
+/// in the form body, since browsers attach Basic Auth to cross-site posts too.
+fn reject_form(state: &AppState, headers: &HeaderMap, form: &AdminForm) -> Option<Response> {
+    if let Some(response) = reject_page(state, headers) {
+        return Some(response);
+    }
+    let valid = session_cookie(headers)
+        .is_some_and(|session| constant_time_eq(&form.csrf, &csrf_nonce(&state.csrf_key, &session)));
+    (!valid).then(|| (StatusCode::FORBIDDEN, "Expired or invalid form, reload the page").into_response())
+}
+
+/// The admin session from the request's cookies.
+fn session_cookie(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .find_map(|pair| pair.trim().strip_prefix(SESSION_COOKIE)?.strip_prefix('='))
+        .filter(|session| !session.is_empty() && session.chars().all(|c| c.is_ascii_alphanumeric()))
+        .map(str::to_string)
+}
+
+/// The browser's admin session, or a new one, and the nonce its forms carry.
+fn session(state: &AppState, headers: &HeaderMap) -> (String, String) {
+    let session = session_cookie(headers).unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+    let csrf = csrf_nonce(&state.csrf_key, &session);
+    (session, csrf)
+}
+
+fn error_page(e: ApiError) -> Response {
+    (e.status, e.message).into_response()
+}
+
+fn back_to_dashboard(notice: &str) -> Response {
+    let mut url = reqwest::Url::parse("http://localhost/admin").expect("static URL");
+    url.query_pairs_mut().append_pair("notice", notice);
+    Redirect::to(&format!("/admin?{}", url.query().unwrap_or_default())).into_response()
+}
+
+/// A page, (re)setting the admin session cookie its forms are bound to.
+fn page(title: &str, body: &str, session: &str) -> Response {
+    let cookie = format!("{SESSION_COOKIE}={session}; Path=/admin; HttpOnly; SameSite=Strict");
+    let html = Html(format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title} — pail admin</title>
+<style>
+body {{ max-width: 60rem; margin: 2rem auto; padding: 0 1rem; font-family: system-ui, sans-serif; line-height: 1.6; color: #222; }}
+section {{ border-top: 1px solid #ddd; padding-top: 1rem; margin-top: 1.5rem; }}
+table {{ border-collapse: collapse; width: 100%; }}
+th, td {{ text-align: left; padding: 0.25rem 0.5rem; border-bottom: 1px solid #eee; vertical-align: top; }}
+td form {{ display: inline; margin-right: 0.25rem; }}
+iframe {{ width: 100%; height: 30rem; border: 1px solid #eee; }}
+textarea {{ width: 100%; font-family: ui-monospace, monospace; font-size: 0.9rem; }}
+.muted {{ color: #666; }}
+.notice {{ background: #f1f8ff; border: 1px solid #c8e1ff; padding: 0.5rem 1rem; }}
+a {{ color: #0366d6; }}
+blockquote {{ border-left: 3px solid #ddd; margin-left: 0; padding-left: 1rem; color: #555; }}
+</style>
+</head>
+<body>
+{body}
+</body>
+</html>"#,
+        title = html_escape(title),
+    ));
+    ([(header::SET_COOKIE, cookie)], html).into_response()
+}
+
+/// A one-button form posting to `action`.
+fn button(action: &str, label: &str, csrf: &str) -> String {
+    format!(
+        r#"<form method="post" action="{action}"><input type="hidden" name="csrf" value="{csrf}"><button>{label}</button></form>"#,
+        action = html_escape(action),
+    )
+}
+
+/// Path segment for a source name or slug (`Hacker News` → `Hacker%20News`).
+fn path_segment(s: &str) -> String {
+    let mut url = reqwest::Url::parse("http://localhost/").expect("static URL");
+    url.path_segments_mut().expect("base URL").pop_if_empty().push(s);
+    url.path().trim_start_matches('/').to_string()
+}
+
+async fn dashboard(State(state): State<AppState>, Query(query): Query<NoticeQuery>, headers: HeaderMap) -> Response {
+    if let Some(response) = reject_page(&state, &headers) {
+        return response;
+    }
+    let config = state.config.borrow().clone();
+    let (channels, paused, sources, drafts) = match (
+        store::get_all_channels(&state.pool).await,
+        store::get_paused_channel_ids(&state.pool).await,
+        store::get_all_sources(&state.pool).await,
+        store::get_draft_articles(&state.pool).await,
+    ) {
+        (Ok(c), Ok(p), Ok(s), Ok(d)) => (c, p, s, d),
+        (Err(e), ..) | (_, Err(e), ..) | (_, _, Err(e), _) | (.., Err(e)) => {
+            warn!(error = %e, "failed to query admin dashboard");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+        }
+    };
+    let (session, csrf) = session(&state, &headers);
+    let fmt = |dt: &DateTime<Utc>| dt.with_timezone(&state.timezone).format("%b %-d %Y, %H:%M").to_string();
+
+    let mut body = String::from("<h1>pail admin</h1>\n");
+    if let Some(ref notice) = query.notice {
+        body.push_str(&format!(r#"<p class="notice">{}</p>"#, html_escape(notice)));
+    }
+    body.push_str(&channels_table(&config, &channels, &paused, &csrf, &fmt));
+    body.push_str(&sources_table(&config, &sources, &csrf, &fmt));
+    body.push_str(&drafts_section(&channels, &drafts, &fmt));
+    page("Dashboard", &body, &session)
+}
+
+fn channels_table(
+    config: &Config,
+    channels: &[OutputChannel],
+    paused: &HashSet<String>,
+    csrf: &str,
+    fmt: &dyn Fn(&DateTime<Utc>) -> String,
+) -> String {
+    let stored: HashMap<&str, &OutputChannel> = channels.iter().map(|c| (c.slug.as_str(), c)).collect();
+    let mut rows = String::new();
+    for channel in &config.output_channel {
+        let db = stored.get(channel.slug.as_str());
+        let enabled = channel.enabled.unwrap_or(true);
+        let state = match (enabled, db.is_some_and(|c| paused.contains(&c.id))) {
+            (false, _) => "disabled",
+            (true, true) => "paused",
+            (true, false) => "enabled",
+        };
+        let base = format!("/admin/channels/{}", path_segment(&channel.slug));
+        let mut actions = button(&format!("{base}/generate"), "Generate now", csrf);
+        actions.push_str(&if enabled {
+            button(&format!("{base}/disable"), "Disable", csrf)
+        } else {
+            button(&format!("{base}/enable"), "Enable", csrf)
+        });
+        rows.push_str(&format!(
+            r#"<tr><td><a href="{base}">{name}</a><br><span class="muted">{slug}</span></td><td>{schedule}</td><td>{state}</td><td>{last}</td><td>{actions}</td></tr>
+"#,
+            base = html_escape(&base),
+            name = html_escape(&channel.name),
+            slug = html_escape(&channel.slug),
+            schedule = html_escape(channel.schedule.as_deref().unwrap_or("manual")),
+            last = db.and_then(|c| c.last_generated.as_ref()).map(fmt).unwrap_or_else(|| "never".to_string()),
+        ));
+    }
+    format!(
+        r#"<section>
+<h2>Output channels</h2>
+<table>
+<tr><th>Channel</th><th>Schedule</th><th>State</th><th>Last generated</th><th></th></tr>
+{rows}</table>
+</section>
+"#
+    )
+}
+
+fn sources_table(config: &Config, sources: &[Source], csrf: &str, fmt: &dyn Fn(&DateTime<Utc>) -> String) -> String {
+    let stored: HashMap<&str, &Source> = sources.iter().map(|s| (s.name.as_str(), s)).collect();
+    let now = Utc::now();
+    let mut rows = String::new();
+    for source in &config.source {
+        let db = stored.get(source.name.as_str());
+        let enabled = source.enabled.unwrap_or(true);
+        let health = db
+            .map(|s| source_health::evaluate(config, s, now))
+            .filter(|s| !matches!(s, SourceStatus::Untracked));
+        let base = format!("/admin/sources/{}", path_segment(&source.name));
+        let toggle = if enabled {
+            button(&format!("{base}/disable"), "Disable", csrf)
+        } else {
+            button(&format!("{base}/enable"), "Enable", csrf)
+        };
+        rows.push_str(&format!(
+            "<tr><td>{name}</td><td>{source_type}</td><td>{state}</td><td>{health}</td><td>{last}</td><td>{toggle}</td></tr>\n",
+            name = html_escape(&source.name),
+            source_type = html_escape(&source.source_type),
+            state = if enabled { "enabled" } else { "disabled" },
+            health = health.as_ref().map(|s| s.label()).unwrap_or(""),
+            last = db
+                .and_then(|s| s.last_fetched_at.as_ref())
+                .map(fmt)
+                .unwrap_or_else(|| "never".to_string()),
+        ));
+    }
+    format!(
+        r#"<section>
+<h2>Sources</h2>
+<table>
+<tr><th>Source</th><th>Type</th><th>State</th><th>Health</th><th>Last fetch</th><th></th></tr>
+{rows}</table>
+</section>
+"#
+    )
+}
+
+/// Read-only: approving and rejecting stays on `/drafts`, behind the feed token. Drafts are
+/// model output, so each renders in a sandboxed frame: no scripts, and no access to this page.
+fn drafts_section(
+    channels: &[OutputChannel],
+    drafts: &[GeneratedArticleRow],
+    fmt: &dyn Fn(&DateTime<Utc>) -> String,
+) -> String {
+    let mut entries = String::new();
+    for draft in drafts {
+        let channel = channels
+            .iter()
+            .find(|c| c.id == draft.output_channel_id)
+            .map(|c| c.name.as_str())
+            .unwrap_or(draft.output_channel_id.as_str());
+        entries.push_str(&format!(
+            r#"<details><summary>{title} <span class="muted">· {channel} · {generated}</span></summary><iframe sandbox srcdoc="{body}"></iframe></details>
+"#,
+            title = html_escape(&draft.title),
+            channel = html_escape(channel),
+            generated = fmt(&draft.generated_at),
+            body = html_escape(&draft.body_html),
+        ));
+    }
+    if drafts.is_empty() {
+        entries.push_str("<p>No drafts awaiting review.</p>");
+    } else {
+        entries.push_str(r#"<p class="muted">Approve or reject drafts on <a href="/drafts">/drafts</a>.</p>"#);
+    }
+    format!("<section>\n<h2>Drafts</h2>\n{entries}\n</section>\n")
+}
+
+/// A channel's prompt in an editable text area.
+async fn channel_page(State(state): State<AppState>, Path(slug): Path<String>, headers: HeaderMap) -> Response {
+    if let Some(response) = reject_page(&state, &headers) {
+        return response;
+    }
+    let config = state.config.borrow().clone();
+    let Some(channel) = config.output_channel.iter().find(|c| c.slug == slug) else {
+        return (StatusCode::NOT_FOUND, format!("No channel '{slug}'")).into_response();
+    };
+    let (session, csrf) = session(&state, &headers);
+
+    let template = channel
+        .prompt_template
+        .as_deref()
+        .or(config.pail.prompt_template.as_deref());
+    let template_note = match template {
+        Some(path) => format!(
+            r#"<p class="muted">This channel renders its directive from <code>{}</code>; the prompt below is available to the template as <code>{{{{ prompt }}}}</code>.</p>"#,
+            html_escape(&path.display().to_string())
+        ),
+        None => String::new(),
+    };
+    let body = format!(
+        r#"<p><a href="/admin">← Dashboard</a></p>
+<h1>{name}</h1>
+<p class="muted">{slug} · {schedule}</p>
+{template_note}
+<form method="post" action="/admin/channels/{path}">
+<input type="hidden" name="csrf" value="{csrf}">
+<textarea name="prompt" rows="20">{prompt}</textarea>
+<p><button>Save prompt</button></p>
+</form>
+"#,
+        name = html_escape(&channel.name),
+        slug = html_escape(&channel.slug),
+        schedule = html_escape(channel.schedule.as_deref().unwrap_or("manual")),
+        path = html_escape(&path_segment(&channel.slug)),
+        prompt = html_escape(&channel.prompt),
+    );
+    page(&channel.name, &body, &session)
+}
+
+/// Save the prompt to the config file and apply it. An empty prompt removes the setting
+/// (valid only with a prompt template).
+async fn save_channel(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    headers: HeaderMap,
+    Form(form): Form<AdminForm>,
+) -> Response {
+    if let Some(response) = reject_form(&state, &headers, &form) {
+        return response;
+    }
+    // Browsers submit text areas with CRLF line endings
+    let prompt = form.prompt.unwrap_or_default().replace("\r\n", "\n");
+    let value = if prompt.trim().is_empty() {
+        Value::Null
+    } else {
+        Value::String(prompt)
+    };
+    let fields = Map::from_iter([("prompt".to_string(), value)]);
+    if let Err(e) = api::edit_config(&state, |doc| api::update_channel_table(doc, &slug, &fields)).await {
+        return error_page(e);
+    }
+    info!(slug = %slug, "channel prompt updated via admin UI");
+    back_to_dashboard(&format!("Saved the prompt of '{slug}'."))
+}
+
+/// `generate`, `enable` or `disable` a channel.
+async fn channel_action(
+    State(state): State<AppState>,
+    Path((slug, action)): Path<(String, String)>,
+    headers: HeaderMap,
+    Form(form): Form<AdminForm>,
+) -> Response {
+    if let Some(response) = reject_form(&state, &headers, &form) {
+        return response;
+    }
+    if action == "generate" {
+        if !state.config.borrow().output_channel.iter().any(|c| c.slug == slug) {
+            return (StatusCode::NOT_FOUND, format!("No channel '{slug}'")).into_response();
+        }
+        let request = GenerationRequest {
+            slug: slug.clone(),
+            window: None,
+            replaces_draft: None,
+        };
+        if state.generation_tx.try_send(request).is_err() {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Generation queue is full, try again later",
+            )
+                .into_response();
+        }
+        info!(slug = %slug, "generation requested via admin UI");
+        return back_to_dashboard(&format!("Generation of '{slug}' queued."));
+    }
+
+    let enabled = match api::parse_action(&action) {
+        Ok(enabled) => enabled,
+        Err(e) => return error_page(e),
+    };
+    let fields = Map::from_iter([("enabled".to_string(), Value::Bool(enabled))]);
+    if let Err(e) = api::edit_config(&state, |doc| api::update_channel_table(doc, &slug, &fields)).await {
+        return error_page(e);
+    }
+    info!(slug = %slug, enabled, "output channel toggled via admin UI");
+    back_to_dashboard(&format!("{} '{slug}'.", if enabled { "Enabled" } else { "Disabled" }))
+}
+
+/// `enable` or `disable` a source.
+async fn source_action(
+    State(state): State<AppState>,
+    Path((name, action)): Path<(String, String)>,
+    headers: HeaderMap,
+    Form(form): Form<AdminForm>,
+) -> Response {
+    if let Some(response) = reject_form(&state, &headers, &form) {
+        return response;
+    }
+    let enabled = match api::parse_action(&action) {
+        Ok(enabled) => enabled,
+        Err(e) => return error_page(e),
+    };
+    let result = api::edit_config(&state, |doc| {
+        if config_edit::set_source_enabled(doc, &name, enabled) {
+            Ok(())
+        } else {
+            Err(ApiError::not_found(format!("no source named '{name}'")))
+        }
+    })
+    .await;
+    if let Err(e) = result {
+        return error_page(e);
+    }
+    info!(source = %name, enabled, "source toggled via admin UI");
+    back_to_dashboard(&format!("{} '{name}'.", if enabled { "Enabled" } else { "Disabled" }))
+}
@@ -0,0 +1,763 @@
+//! Admin JSON API under `/api/v1` (see docs/specs/admin-api.md). Sources and channels are
+//! defined by the config file, so changes are written there (comments and formatting kept)
+//! and applied like `pail ctl reload`.
+
+use std::collections::HashMap;
+
+use anyhow::Context;
+use axum::Router;
+use axum::body::{Body, Bytes};
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{AppendHeaders, IntoResponse, Response};
+use axum::routing::{get, post};
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value, json};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+use toml_edit::DocumentMut;
+use tracing::{info, warn};
+
+use crate::models::{GeneratedArticleRow, OutputChannel};
+use crate::server::{AppState, constant_time_eq};
+use crate::source_health::{self, SourceStatus};
+use crate::{article_log, config_edit, control, store};
+
+/// Articles returned by `GET /articles` without `limit`, and the most it returns.
+const DEFAULT_ARTICLE_LIMIT: i64 = 50;
+const MAX_ARTICLE_LIMIT: i64 = 500;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/sources", get(list_sources).post(create_source))
+        .route("/sources/{name}", axum::routing::patch(update_source))
+        .route("/sources/{name}/{action}", post(source_action))
+        .route("/channels", get(list_channels).post(create_channel))
+        .route("/channels/{slug}", axum::routing::patch(update_channel))
+        .route("/channels/{slug}/{action}", post(channel_action))
+        .route("/articles", get(list_articles))
+        .route("/articles/{id}", get(get_article))
+        .route("/articles/{id}/log", get(get_article_log))
+        .route("/runs", get(list_runs))
+}
+
+/// An error response: `{"error": "..."}` with the status.
+pub(crate) struct ApiError {
+    pub(crate) status: StatusCode,
+    pub(crate) message: String,
+}
+
+impl ApiError {
+    pub(crate) fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    pub(crate) fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, message)
+    }
+}
+
+/// Unexpected failures are logged; the client only learns that something went wrong.
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        warn!(error = format!("{e:#}"), "admin API request failed");
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal error")
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = axum::Json(json!({ "error": self.message }));
+        if self.status == StatusCode::UNAUTHORIZED {
+            return (self.status, [(header::WWW_AUTHENTICATE, "Bearer")], body).into_response();
+        }
+        (self.status, body).into_response()
+    }
+}
+
+type ApiResult = Result<Response, ApiError>;
+
+/// `Authorization: Bearer <[pail].admin_token>`. Only the header is accepted: unlike feed
+/// readers, API clients can always send one, and query strings end up in access logs.
+/// Without an admin token the API doesn't exist (404).
+fn authorize(state: &AppState, headers: &HeaderMap) -> Result<(), ApiError> {
+    let config = state.config.borrow();
+    let Some(ref admin_token) = config.pail.admin_token else {
+        return Err(ApiError::not_found("admin API is disabled (set [pail].admin_token)"));
+    };
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim);
+    if bearer.is_some_and(|token| constant_time_eq(token, admin_token)) {
+        Ok(())
+    } else {
+        Err(ApiError::new(StatusCode::UNAUTHORIZED, "missing or wrong admin token"))
+    }
+}
+
+/// Parsed after authorizing, so an unauthenticated request learns nothing from parse errors.
+fn parse_object(body: &[u8]) -> Result<Map<String, Value>, ApiError> {
+    match serde_json::from_slice(body) {
+        Ok(Value::Object(fields)) => Ok(fields),
+        Ok(_) => Err(ApiError::bad_request("expected a JSON object")),
+        Err(e) => Err(ApiError::bad_request(format!("invalid JSON: {e}"))),
+    }
+}
+
+fn rfc3339(dt: Option<DateTime<Utc>>) -> Option<String> {
+    dt.map(|d| d.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+}
+
+/// Apply `edit` to the config file, write it, and reload it. Edits are serialized; if the
+/// result doesn't validate or can't be applied, the file is restored and the running config
+/// stays as it was. Returns the changed settings that need a restart.
+pub(crate) async fn edit_config(
+    state: &AppState,
+    edit: impl FnOnce(&mut DocumentMut) -> Result<(), ApiError>,
+) -> Result<Vec<&'static str>, ApiError> {
+    let _guard = state.config_edit_lock.lock().await;
+    let path = &state.config_path;
+    let original = std::fs::read_to_string(path).map_err(|e| anyhow::anyhow!("reading config file: {e}"))?;
+    let updated = {
+        let mut doc = config_edit::parse_document(&original)?;
+        edit(&mut doc)?;
+        config_edit::render(&doc)
+    };
+
+    config_edit::write_validated(path, &original, &updated).map_err(|e| ApiError::bad_request(format!("{e:#}")))?;
+    match control::apply_config(&state.pool, path, &state.registry, &state.config_tx).await {
+        Ok(restart_required) => Ok(restart_required),
+        Err(e) => {
+            if let Err(restore) = std::fs::write(path, &original) {
+                warn!(error = %restore, "failed to restore config after a rejected admin API edit");
+            }
+            Err(ApiError::bad_request(format!("{e:#}")))
+        }
+    }
+}
+
+/// `channels` of a create/update body: output channel slugs.
+fn take_channels(fields: &mut Map<String, Value>) -> Result<Option<Vec<String>>, ApiError> {
+    match fields.remove("channels") {
+        None => Ok(None),
+        Some(value) => serde_json::from_value(value)
+            .map(Some)
+            .map_err(|_| ApiError::bad_request("'channels' must be an array of output channel slugs")),
+    }
+}
+
+fn add_to_channels(doc: &mut DocumentMut, name: &str, channels: &[String]) -> Result<(), ApiError> {
+    for slug in channels {
+        if !config_edit::add_source_to_channel(doc, slug, name) {
+            return Err(ApiError::bad_request(format!("no output channel with slug '{slug}'")));
+        }
+    }
+    Ok(())
+}
+
+// --- Sources ---
+
+/// Config settings plus fetch state. Secrets (`auth`, mailbox passwords, webhook tokens) are
+/// never returned.
+async fn list_sources(State(state): State<AppState>, headers: HeaderMap) -> ApiResult {
+    authorize(&state, &headers)?;
+    let config = state.config.borrow().clone();
+    let stored: HashMap<String, _> = store::get_all_sources(&state.pool)
+        .await?
+        .into_iter()
+        .map(|s| (s.name.clone(), s))
+        .collect();
+    let now = Utc::now();
+
+    let sources: Vec<Value> = config
+        .source
+        .iter()
+        .map(|source| {
+            let channels: Vec<&str> = config
+                .output_channel
+                .iter()
+                .filter(|c| c.sources.contains(&source.name))
+                .map(|c| c.slug.as_str())
+                .collect();
+            let db = stored.get(&source.name);
+            let status = db
+                .map(|s| source_health::evaluate(&config, s, now))
+                .filter(|s| !matches!(s, SourceStatus::Untracked));
+            json!({
+                "name": source.name,
+                "type": source.source_type,
+                "url": source.url,
+                "enabled": source.enabled.unwrap_or(true),
+                "description": source.description,
+                "poll_interval": source.poll_interval,
+                "max_items": source.max_items,
+                "tg_account": source.tg_account,
+                "tg_username": source.tg_username,
+                "tg_folder_name": source.tg_folder_name,
+                "languages": source.languages,
+                "channels": channels,
+                "status": status.as_ref().map(|s| s.label()),
+                "status_detail": status.as_ref().map(|s| s.detail(now)).filter(|d| !d.is_empty()),
+                "last_fetched_at": rfc3339(db.and_then(|s| s.last_fetched_at)),
+                "last_success_at": rfc3339(db.and_then(|s| s.last_success_at)),
+                "last_item_at": rfc3339(db.and_then(|s| s.last_item_at)),
+                "consecutive_failures": db.map(|s| s.consecutive_failures),
+                "last_error": db.and_then(|s| s.last_error.clone()),
+            })
+        })
+        .collect();
+    Ok(axum::Json(json!({ "sources": sources })).into_response())
+}
+
+/// Body: the `[[source]]` fields as JSON (`{"name": ..., "type": ..., "url": ...}`, nested
+/// tables as objects), plus `channels` to add the source to.
+async fn create_source(State(state): State<AppState>, headers: HeaderMap, body: Bytes) -> ApiResult {
+    authorize(&state, &headers)?;
+    let mut fields = parse_object(&body)?;
+    let channels = take_channels(&mut fields)?.unwrap_or_default();
+    let Some(name) = fields.get("name").and_then(|v| v.as_str()).map(str::to_string) else {
+        return Err(ApiError::bad_request("'name' is required"));
+    };
+    if state.config.borrow().source.iter().any(|s| s.name == name) {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            format!("source '{name}' already exists"),
+        ));
+    }
+
+    let restart_required = edit_config(&state, |doc| {
+        config_edit::add_table_from_json(doc, "source", &["name", "type"], &fields)
+            .map_err(|e| ApiError::bad_request(format!("{e:#}")))?;
+        add_to_channels(doc, &name, &channels)
+    })
+    .await?;
+    info!(source = %name, "source created via admin API");
+    Ok((
+        StatusCode::CREATED,
+        axum::Json(json!({ "name": name, "restart_required": restart_required })),
+    )
+        .into_response())
+}
+
+/// Body: fields to set (`null` removes one). `channels`, if given, replaces the output
+/// channels the source feeds. `name` and `type` can't change.
+async fn update_source(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ApiResult {
+    authorize(&state, &headers)?;
+    let mut fields = parse_object(&body)?;
+    if fields.contains_key("name") || fields.contains_key("type") {
+        return Err(ApiError::bad_request(
+            "a source's 'name' and 'type' can't be changed; create a new source instead",
+        ));
+    }
+    let channels = take_channels(&mut fields)?;
+
+    let restart_required = edit_config(&state, |doc| {
+        let found = config_edit::update_table_from_json(doc, "source", "name", &name, &fields)
+            .map_err(|e| ApiError::bad_request(format!("{e:#}")))?;
+        if !found {
+            return Err(ApiError::not_found(format!("no source named '{name}'")));
+        }
+        if let Some(channels) = channels {
+            config_edit::remove_source_from_channels(doc, &name);
+            add_to_channels(doc, &name, &channels)?;
+        }
+        Ok(())
+    })
+    .await?;
+    info!(source = %name, "source updated via admin API");
+    Ok(axum::Json(json!({ "name": name, "restart_required": restart_required })).into_response())
+}
+
+/// `POST /sources/<name>/enable` or `/disable`.
+async fn source_action(
+    State(state): State<AppState>,
+    Path((name, action)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> ApiResult {
+    authorize(&state, &headers)?;
+    let enabled = parse_action(&action)?;
+    edit_config(&state, |doc| {
+        if config_edit::set_source_enabled(doc, &name, enabled) {
+            Ok(())
+        } else {
+            Err(ApiError::not_found(format!("no source named '{name}'")))
+        }
+    })
+    .await?;
+    info!(source = %name, enabled, "source toggled via admin API");
+    Ok(axum::Json(json!({ "name": name, "enabled": enabled })).into_response())
+}
+
+pub(crate) fn parse_action(action: &str) -> Result<bool, ApiError> {
+    match action {
+        "enable" => Ok(true),
+        "disable" => Ok(false),
+        _ => Err(ApiError::not_found(format!(
+            "unknown action '{action}' (use enable or disable)"
+        ))),
+    }
+}
+
+// --- Channels ---
+
+async fn list_channels(State(state): State<AppState>, headers: HeaderMap) -> ApiResult {
+    authorize(&state, &headers)?;
+    let config = state.config.borrow().clone();
+    let stored: HashMap<String, OutputChannel> = store::get_all_channels(&state.pool)
+        .await?
+        .into_iter()
+        .map(|c| (c.slug.clone(), c))
+        .collect();
+    let paused = store::get_paused_channel_ids(&state.pool).await?;
+
+    let channels: Vec<Value> = config
+        .output_channel
+        .iter()
+        .map(|channel| {
+            let db = stored.get(&channel.slug);
+            json!({
+                "slug": channel.slug,
+                "name": channel.name,
+                "schedule": channel.schedule,
+                "sources": channel.sources,
+                "prompt": channel.prompt,
+                "model": channel.model,
+                "models": channel.models,
+                "strategy": channel.strategy,
+                "language": channel.language,
+                "priority": channel.priority,
+                "enabled": channel.enabled.unwrap_or(true),
+                "require_approval": channel.require_approval,
+                "paused": db.is_some_and(|c| paused.contains(&c.id)),
+                "last_generated": rfc3339(db.and_then(|c| c.last_generated)),
+            })
+        })
+        .collect();
+    Ok(axum::Json(json!({ "channels": channels })).into_response())
+}
+
+/// Body: the `[[output_channel]]` fields as JSON.
+async fn create_channel(State(state): State<AppState>, headers: HeaderMap, body: Bytes) -> ApiResult {
+    authorize(&state, &headers)?;
+    let fields = parse_object(&body)?;
+    let Some(slug) = fields.get("slug").and_then(|v| v.as_str()).map(str::to_string) else {
+        return Err(ApiError::bad_request("'slug' is required"));
+    };
+    if state.config.borrow().output_channel.iter().any(|c| c.slug == slug) {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            format!("output channel '{slug}' already exists"),
+        ));
+    }
+
+    let restart_required = edit_config(&state, |doc| {
+        config_edit::add_table_from_json(
+            doc,
+            "output_channel",
+            &["name", "slug", "schedule", "sources", "prompt"],
+            &fields,
+        )
+        .map_err(|e| ApiError::bad_request(format!("{e:#}")))
+    })
+    .await?;
+    info!(slug = %slug, "output channel created via admin API");
+    Ok((
+        StatusCode::CREATED,
+        axum::Json(json!({ "slug": slug, "restart_required": restart_required })),
+    )
+        .into_response())
+}
+
+/// Body: fields to set (`null` removes one). `slug` can't change.
+async fn update_channel(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ApiResult {
+    authorize(&state, &headers)?;
+    let fields = parse_object(&body)?;
+    if fields.contains_key("slug") {
+        return Err(ApiError::bad_request(
+            "a channel's 'slug' can't be changed; it's part of the feed URL",
+        ));
+    }
+
+    let restart_required = edit_config(&state, |doc| update_channel_table(doc, &slug, &fields)).await?;
+    info!(slug = %slug, "output channel updated via admin API");
+    Ok(axum::Json(json!({ "slug": slug, "restart_required": restart_required })).into_response())
+}
+
+pub(crate) fn update_channel_table(
+    doc: &mut DocumentMut,
+    slug: &str,
+    fields: &Map<String, Value>,
+) -> Result<(), ApiError> {
+    match config_edit::update_table_from_json(doc, "output_channel", "slug", slug, fields) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(ApiError::not_found(format!("no output channel '{slug}'"))),
+        Err(e) => Err(ApiError::bad_request(format!("{e:#}"))),
+    }
+}
+
+/// `POST /channels/<slug>/enable` or `/disable`: the channel's `enabled` setting, unlike
+/// `pail ctl pause`, which only holds its schedule until resumed.
+async fn channel_action(
+    State(state): State<AppState>,
+    Path((slug, action)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> ApiResult {
+    authorize(&state, &headers)?;
+    let enabled = parse_action(&action)?;
+    let fields = Map::from_iter([("enabled".to_string(), Value::Bool(enabled))]);
+    edit_config(&state, |doc| update_channel_table(doc, &slug, &fields)).await?;
+    info!(slug = %slug, enabled, "output channel toggled via admin API");
+    Ok(axum::Json(json!({ "slug": slug, "enabled": enabled })).into_response())
+}
+
+// --- Articles and runs ---
+
+#[derive(serde::Deserialize)]
+struct ArticlesQuery {
+    channel: Option<String>,
+    status: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(serde::Deserialize)]
+struct ArticleQuery {
+    /// Return the translation into this language instead of the original.
+    lang: Option<String>,
+}
+
+fn article_summary(article: &GeneratedArticleRow, slugs: &HashMap<String, String>) -> Value {
+    let topics: Vec<String> = serde_json::from_str(&article.topics).unwrap_or_default();
+    let item_ids: Vec<String> = serde_json::from_str(&article.content_item_ids).unwrap_or_default();
+    json!({
+        "id": article.id,
+        "channel": slugs.get(&article.output_channel_id),
+        "title": article.title,
+        "summary": article.summary,
+        "status": article.status,
+        "topics": topics,
+        "generated_at": rfc3339(Some(article.generated_at)),
+        "covers_from": rfc3339(Some(article.covers_from)),
+        "covers_to": rfc3339(Some(article.covers_to)),
+        "items": item_ids.len(),
+        "model": article.model_used,
+        "strategy": article.strategy_used,
+        "token_count": article.token_count,
+        "read_at": rfc3339(article.read_at),
+        "eval_score": article.eval_score,
+    })
+}
+
+async fn channel_slugs(state: &AppState) -> Result<HashMap<String, String>, ApiError> {
+    Ok(store::get_all_channels(&state.pool)
+        .await?
+        .into_iter()
+        .map(|c| (c.id, c.slug))
+        .collect())
+}
+
+/// Newest first, any status. `?channel=<slug>`, `?status=draft`, `?limit=` narrow it down.
+async fn list_articles(
+    State(state): State<AppState>,
+    Query(query): Query<ArticlesQuery>,
+    headers: HeaderMap,
+) -> ApiResult {
+    authorize(&state, &headers)?;
+    let channel_id = match query.channel {
+        Some(ref slug) => match store::get_channel_by_slug(&state.pool, slug).await? {
+            Some(channel) => Some(channel.id),
+            None => return Err(ApiError::not_found(format!("no output channel '{slug}'"))),
+        },
+        None => None,
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_ARTICLE_LIMIT).clamp(1, MAX_ARTICLE_LIMIT);
+    let articles = store::list_articles(&state.pool, channel_id.as_deref(), query.status.as_deref(), limit).await?;
+    let slugs = channel_slugs(&state).await?;
+    let articles: Vec<Value> = articles.iter().map(|a| article_summary(a, &slugs)).collect();
+    Ok(axum::Json(json!({ "articles": articles })).into_response())
+}
+
+/// One article with its Markdown and HTML body and the generation log (cut to its head and
+/// tail if it was too large to store inline; the full log is at `/articles/{id}/log`).
+async fn get_article(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<ArticleQuery>,
+    headers: HeaderMap,
+) -> ApiResult {
+    authorize(&state, &headers)?;
+    let Some(mut article) = store::get_article_by_id(&state.pool, &id).await? else {
+        return Err(ApiError::not_found(format!("no article with ID '{id}'")));
+    };
+    if let Some(ref lang) = query.lang {
+        let Some(translation) = store::get_article_translation(&state.pool, &id, lang).await? else {
+            return Err(ApiError::not_found(format!(
+                "article '{id}' has no translation into '{lang}'"
+            )));
+        };
+        article.title = translation.title;
+        article.body_markdown = translation.body_markdown;
+        article.body_html = translation.body_html;
+    }
+    let translations = store::get_article_translation_langs(&state.pool, &id).await?;
+    let log_path = article_log::log_path(&state.config.borrow(), &article.id);
+    let log_truncated = tokio::fs::try_exists(&log_path).await.unwrap_or(false);
+    let mut value = article_summary(&article, &channel_slugs(&state).await?);
+    if let Value::Object(ref mut fields) = value {
+        fields.insert("body_markdown".to_string(), json!(article.body_markdown));
+        fields.insert("body_html".to_string(), json!(article.body_html));
+        fields.insert("generation_log".to_string(), json!(article.generation_log));
+        fields.insert("generation_log_truncated".to_string(), json!(log_truncated));
+        fields.insert("lang".to_string(), json!(query.lang));
+        fields.insert("translations".to_string(), json!(translations));
+        let evaluation: Option<Value> = article
+            .eval_details
+            .as_deref()
+            .and_then(|d| serde_json::from_str(d).ok());
+        fields.insert("evaluation".to_string(), json!(evaluation));
+        let metadata: Value = serde_json::from_str(&article.metadata).unwrap_or_default();
+        fields.insert("metadata".to_string(), metadata);
+    }
+    Ok(axum::Json(value).into_response())
+}
+
+/// The full generation log of an article as plain text: streamed from its log file if the
+/// inline copy was cut, else the inline copy. A single `Range: bytes=` range is honored, so
+/// clients can page through or tail a large log.
+async fn get_article_log(State(state): State<AppState>, Path(id): Path<String>, headers: HeaderMap) -> ApiResult {
+    authorize(&state, &headers)?;
+    let Some(article) = store::get_article_by_id(&state.pool, &id).await? else {
+        return Err(ApiError::not_found(format!("no article with ID '{id}'")));
+    };
+    let path = article_log::log_path(&state.config.borrow(), &article.id);
+
+    let (source, len) = match tokio::fs::File::open(&path).await {
+        Ok(file) => {
+            let len = file.metadata().await.context("reading log file size")?.len();
+            (LogSource::File(file), len)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            if article.generation_log.is_empty() {
+                return Err(ApiError::not_found("no log stored for this article"));
+            }
+            let log = Bytes::from(article.generation_log);
+            let len = log.len() as u64;
+            (LogSource::Inline(log), len)
+        }
+        Err(e) => {
+            return Err(anyhow::Error::new(e)
+                .context(format!("opening {}", path.display()))
+                .into());
+        }
+    };
+
+    let (status, start, count) = match log_range(&headers, len) {
+        LogRange::Whole => (StatusCode::OK, 0, len),
+        LogRange::Part { start, end } => (StatusCode::PARTIAL_CONTENT, start, end - start + 1),
+        LogRange::Unsatisfiable => {
+            return Ok((
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                [(header::CONTENT_RANGE, format!("bytes */{len}"))],
+            )
+                .into_response());
+        }
+    };
+    let body = match source {
+        LogSource::File(mut file) => {
+            file.seek(std::io::SeekFrom::Start(start))
+                .await
+                .context("seeking log file")?;
+            Body::from_stream(ReaderStream::new(file.take(count)))
+        }
+        LogSource::Inline(log) => Body::from(log.slice(start as usize..(start + count) as usize)),
+    };
+    let content_range = (status == StatusCode::PARTIAL_CONTENT).then(|| {
+        (
+            header::CONTENT_RANGE,
+            format!("bytes {start}-{}/{len}", start + count - 1),
+        )
+    });
+    Ok((
+        status,
+        [
+            (header::CONTENT_TYPE, "text/plain; charset=utf-8".to_string()),
+            (header::ACCEPT_RANGES, "bytes".to_string()),
+            (header::CONTENT_LENGTH, count.to_string()),
+        ],
+        AppendHeaders(content_range),
+        body,
+    )
+        .into_response())
+}
+
+/// Where an article's full log is read from.
+enum LogSource {
+    File(tokio::fs::File),
+    Inline(Bytes),
+}
+
+/// What part of a log a request asked for.
+#[derive(Debug, PartialEq)]
+enum LogRange {
+    Whole,
+    /// Inclusive byte offsets, within the log.
+    Part {
+        start: u64,
+        end: u64,
+    },
+    /// Starts past the end of the log (`416`).
+    Unsatisfiable,
+}
+
+/// The range of a `len`-byte log a request asked for: `bytes=a-b`, `bytes=a-` or a `bytes=-n`
+/// suffix. No `Range`, a malformed one or several ranges (which a log viewer doesn't need)
+/// get the whole log, as RFC 9110 allows.
+fn log_range(headers: &HeaderMap, len: u64) -> LogRange {
+    let Some(spec) = headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().strip_prefix("bytes="))
+    else {
+        return LogRange::Whole;
+    };
+    let Some((start, end)) = spec.split_once('-').filter(|_| !spec.contains(',')) else {
+        return LogRange::Whole;
+    };
+    let (start, end) = (start.trim(), end.trim());
+
+    if start.is_empty() {
+        let Ok(suffix) = end.parse::<u64>() else {
+            return LogRange::Whole;
+        };
+        if suffix == 0 || len == 0 {
+            return LogRange::Unsatisfiable;
+        }
+        return LogRange::Part {
+            start: len.saturating_sub(suffix),
+            end: len - 1,
+        };
+    }
+    let Ok(start) = start.parse::<u64>() else {
+        return LogRange::Whole;
+    };
+    let end = match end {
+        "" => None,
+        end => match end.parse::<u64>() {
+            Ok(end) if end >= start => Some(end),
+            _ => return LogRange::Whole,
+        },
+    };
+    if start >= len {
+        return LogRange::Unsatisfiable;
+    }
+    LogRange::Part {
+        start,
+        end: end.map_or(len - 1, |end| end.min(len - 1)),
+    }
+}
+
+/// Generations running now (claimed by an instance) and queued for a worker.
+async fn list_runs(State(state): State<AppState>, headers: HeaderMap) -> ApiResult {
+    authorize(&state, &headers)?;
+    let now = Utc::now();
+    let running: Vec<Value> = store::get_generation_claims(&state.pool)
+        .await?
+        .into_iter()
+        .map(|(slug, claimed_by, claimed_at, expires_at)| {
+            json!({
+                "channel": slug,
+                "instance": claimed_by,
+                "started_at": rfc3339(Some(claimed_at)),
+                "expires_at": rfc3339(Some(expires_at)),
+                // An expired claim belongs to an instance that stopped mid-run
+                "stale": expires_at <= now,
+            })
+        })
+        .collect();
+    let queued: Vec<Value> = store::get_queued_generations(&state.pool)
+        .await?
+        .into_iter()
+        .map(|request| {
+            json!({
+                "id": request.id,
+                "channel": request.slug,
+                "requested_at": rfc3339(Some(request.requested_at)),
+                "window_from": rfc3339(request.window_from),
+                "window_to": rfc3339(request.window_to),
+                "since_secs": request.since_secs,
+                "replaces_draft": request.replaces_draft,
+            })
+        })
+        .collect();
+    Ok(axum::Json(json!({ "running": running, "queued": queued })).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(value: &str, len: u64) -> LogRange {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, value.parse().unwrap());
+        log_range(&headers, len)
+    }
+
+    #[test]
+    fn test_log_range_bounded_and_open_ended() {
+        assert_eq!(range("bytes=0-99", 1000), LogRange::Part { start: 0, end: 99 });
+        assert_eq!(range("bytes=900-", 1000), LogRange::Part { start: 900, end: 999 });
+        // An end past the log is cut to it
+        assert_eq!(range("bytes=900-5000", 1000), LogRange::Part { start: 900, end: 999 });
+        assert_eq!(range(" bytes= 10 - 20 ", 1000), LogRange::Part { start: 10, end: 20 });
+    }
+
+    #[test]
+    fn test_log_range_suffix() {
+        assert_eq!(range("bytes=-100", 1000), LogRange::Part { start: 900, end: 999 });
+        // A suffix longer than the log is the whole log, as a part
+        assert_eq!(range("bytes=-5000", 1000), LogRange::Part { start: 0, end: 999 });
+        assert_eq!(range("bytes=-0", 1000), LogRange::Unsatisfiable);
+        assert_eq!(range("bytes=-10", 0), LogRange::Unsatisfiable);
+    }
+
+    #[test]
+    fn test_log_range_out_of_range() {
+        assert_eq!(range("bytes=1000-", 1000), LogRange::Unsatisfiable);
+        assert_eq!(range("bytes=2000-3000", 1000), LogRange::Unsatisfiable);
+        assert_eq!(range("bytes=0-", 0), LogRange::Unsatisfiable);
+    }
+
+    #[test]
+    fn test_log_range_malformed_is_whole() {
+        assert_eq!(log_range(&HeaderMap::new(), 1000), LogRange::Whole);
+        for value in [
+            "bytes=",
+            "bytes=abc-",
+            "bytes=-abc",
+            "bytes=10",
+            "bytes=20-10",
+            "bytes=0-1,5-9",
+            "items=0-10",
+        ] {
+            assert_eq!(range(value, 1000), LogRange::Whole, "{value}");
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! Large generation logs: an article's inline `generation_log` is cut to
+//! `[pail].generation_log_inline_bytes`, and the full log goes to `<data_dir>/logs/<article id>.log`,
+//! served by `GET /api/v1/articles/<id>/log` (see docs/specs/generation-engine.md "Generation Logs").
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+use tracing::debug;
+
+use crate::config::Config;
+use crate::models::GeneratedArticle;
+use crate::store;
+
+/// Log files younger than this are never swept: the article may not be stored yet.
+const SWEEP_GRACE: Duration = Duration::from_secs(3600);
+
+/// Where the full logs of articles live.
+pub fn log_dir(config: &Config) -> PathBuf {
+    config.pail.data_dir.join("logs")
+}
+
+/// The full log file of an article. It exists only for articles whose log was cut.
+pub fn log_path(config: &Config, article_id: &str) -> PathBuf {
+    log_dir(config).join(format!("{article_id}.log"))
+}
+
+/// Write the article's log to its file and keep only the head and tail inline, if it's over
+/// the inline limit.
+pub async fn spill(config: &Config, article: &mut GeneratedArticle) -> Result<()> {
+    let limit = config.pail.generation_log_inline_bytes as usize;
+    if limit == 0 || article.generation_log.len() <= limit {
+        return Ok(());
+    }
+    let dir = log_dir(config);
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .with_context(|| format!("creating log directory {}", dir.display()))?;
+    let path = log_path(config, &article.id);
+    tokio::fs::write(&path, &article.generation_log)
+        .await
+        .with_context(|| format!("writing generation log {}", path.display()))?;
+    debug!(article_id = %article.id, bytes = article.generation_log.len(), "generation log written to file");
+    article.generation_log = truncate(&article.generation_log, limit, &article.id);
+    Ok(())
+}
+
+/// Head and tail of a log, each half the limit, around a note on where the rest is. The end
+/// of a log (the error, the final output) matters as much as its start.
+fn truncate(log: &str, limit: usize, article_id: &str) -> String {
+    let head = log.floor_char_boundary(limit / 2);
+    let tail = log.ceil_char_boundary(log.len() - limit / 2);
+    format!(
+        "{}\n\n[... {} bytes cut; full log: GET /api/v1/articles/{article_id}/log ...]\n\n{}",
+        &log[..head],
+        tail - head,
+        &log[tail..]
+    )
+}
+
+/// The full log of an article: its file if the inline copy was cut, else the inline copy.
+pub async fn read_full(config: &Config, article_id: &str, inline: &str) -> Result<String> {
+    let path = log_path(config, article_id);
+    match tokio::fs::read(&path).await {
+        Ok(bytes) => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(inline.to_string()),
+        Err(e) => Err(e).with_context(|| format!("reading generation log {}", path.display())),
+    }
+}
+
+/// Delete log files of articles that were deleted or had their log stripped. Returns the
+/// number of files deleted.
+pub async fn sweep(pool: &SqlitePool, config: &Config) -> Result<usize> {
+    let dir = log_dir(config);
+    let mut entries = match tokio::fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).with_context(|| format!("reading log directory {}", dir.display())),
+    };
+    let keep: HashSet<String> = store::get_article_ids_with_log(pool).await?.into_iter().collect();
+
+    let mut deleted = 0;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name();
+        let Some(article_id) = name.to_str().and_then(|n| n.strip_suffix(".log")) else {
+            continue;
+        };
+        if keep.contains(article_id) {
+            continue;
+        }
+        let recent = entry
+            .metadata()
+            .await
+            .and_then(|m| m.modified())
+            .is_ok_and(|modified| SystemTime::now().duration_since(modified).unwrap_or_default() < SWEEP_GRACE);
+        if recent {
+            continue;
+        }
+        tokio::fs::remove_file(entry.path())
+            .await
+            .with_context(|| format!("deleting {}", entry.path().display()))?;
+        deleted += 1;
+    }
+    Ok(deleted)
+}
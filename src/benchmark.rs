@@ -8,8 +8,10 @@ use serde::{Deserialize, Serialize};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
-use crate::config::Config;
+use crate::budget::Budget;
+use crate::config::{Config, OpencodeConfig};
 use crate::strategy::{self, StrategyRegistry};
+use crate::telegram::TgClients;
 use crate::{cli, db, generate, pipeline, store};
 
 /// Arguments parsed from `pail benchmark run`.
@@ -123,23 +125,6 @@ fn parse_share_suffix(text: &str) -> Option<String> {
     })
 }
 
-/// Recursively copy a directory tree.
-fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
-    std::fs::create_dir_all(dst).with_context(|| format!("creating {}", dst.display()))?;
-    for entry in std::fs::read_dir(src).with_context(|| format!("reading {}", src.display()))? {
-        let entry = entry?;
-        let src_path = entry.path();
-        let dst_path = dst.join(entry.file_name());
-        if src_path.is_dir() {
-            copy_dir_recursive(&src_path, &dst_path)?;
-        } else {
-            std::fs::copy(&src_path, &dst_path)
-                .with_context(|| format!("copying {} -> {}", src_path.display(), dst_path.display()))?;
-        }
-    }
-    Ok(())
-}
-
 #[derive(Deserialize)]
 struct SessionListEntry {
     id: String,
@@ -227,7 +212,7 @@ async fn export_session(binary: &str, workspace_dir: &Path, dest: &Path, log_tex
 async fn run_model_samples(
     run_dir: &Path,
     workspace_dir: &Path,
-    binary: &str,
+    opencode: &OpencodeConfig,
     model: &str,
     strategy_name: &str,
     prompt: &str,
@@ -260,7 +245,7 @@ async fn run_model_samples(
             }
         };
 
-        if let Err(e) = copy_dir_recursive(workspace_dir, tmp.path()) {
+        if let Err(e) = generate::copy_dir_recursive(workspace_dir, tmp.path()) {
             warn!(model = %model, sample = sample_num, error = %e, "failed to copy workspace");
             results.push(SampleResult {
                 duration: Duration::ZERO,
@@ -277,11 +262,21 @@ async fn run_model_samples(
         }
 
         let start = Instant::now();
-        let invoke_result = generate::invoke_opencode(binary, tmp.path(), model, prompt, timeout, cancel.clone()).await;
+        let invoke_result = generate::invoke_opencode(
+            opencode,
+            tmp.path(),
+            model,
+            prompt,
+            timeout,
+            Budget::global(opencode),
+            None,
+            cancel.clone(),
+        )
+        .await;
         let duration = start.elapsed();
 
         let (log, exit_code, error) = match invoke_result {
-            Ok((log, code)) => (log, code, None),
+            Ok((log, code, _)) => (log, code, None),
             Err(e) => {
                 let err_str = format!("{e:#}");
                 warn!(model = %model, sample = sample_num, error = %err_str, "opencode invocation failed");
@@ -327,7 +322,7 @@ async fn run_model_samples(
 
             // Export opencode session transcript directly to file
             let session_path = sample_dir.join("session.json");
-            if export_session(binary, tmp.path(), &session_path, &log_for_export).await {
+            if export_session(&opencode.binary, tmp.path(), &session_path, &log_for_export).await {
                 info!(model = %model, sample = sample_num, "session exported");
             } else {
                 debug!(model = %model, sample = sample_num, "no session to export");
@@ -397,10 +392,20 @@ pub(crate) async fn run_benchmark(config: &Config, registry: &StrategyRegistry,
 
     // Prepare pipeline context (fetches RSS, queries items)
     info!("fetching content and preparing workspace...");
-    let ctx = pipeline::prepare_pipeline_context(&pool, channel_config, time_window, true, None, &cancel)
-        .await
-        .context("preparing pipeline context")?
-        .ok_or_else(|| anyhow::anyhow!("no content items found in the specified time window"))?;
+    // No TG clients: benchmarks use the stored TG history
+    let tg_clients = TgClients::new();
+    let ctx = pipeline::prepare_pipeline_context(
+        &pool,
+        &bench_config,
+        channel_config,
+        time_window,
+        true,
+        &tg_clients,
+        &cancel,
+    )
+    .await
+    .context("preparing pipeline context")?
+    .ok_or_else(|| anyhow::anyhow!("no content items found in the specified time window"))?;
 
     // Resolve strategy (--strategy flag overrides channel/default)
     let strategy_name = args
@@ -427,15 +432,23 @@ pub(crate) async fn run_benchmark(config: &Config, registry: &StrategyRegistry,
         &ctx.items,
         &source_ref_map,
         &ctx.folder_channels,
+        &ctx.pages,
         ctx.covers_from,
         ctx.covers_to,
     )
     .await
     .context("preparing workspace")?;
 
-    let prompt = generate::write_prompt(ws.path(), strat, channel_config)
-        .await
-        .context("writing prompt")?;
+    let prompt = generate::write_prompt(
+        ws.path(),
+        strat,
+        config,
+        channel_config,
+        &ctx.feedback,
+        ctx.previous_article.as_ref(),
+    )
+    .await
+    .context("writing prompt")?;
 
     // Write empty output.md to workspace
     tokio::fs::write(ws.path().join("output.md"), "")
@@ -449,7 +462,7 @@ pub(crate) async fn run_benchmark(config: &Config, registry: &StrategyRegistry,
 
     // Copy workspace snapshot
     let workspace_snapshot = run_dir.join("workspace");
-    copy_dir_recursive(ws.path(), &workspace_snapshot).context("copying workspace snapshot")?;
+    generate::copy_dir_recursive(ws.path(), &workspace_snapshot).context("copying workspace snapshot")?;
     info!(path = %workspace_snapshot.display(), "workspace snapshot saved");
 
     // Discover models
@@ -461,7 +474,7 @@ pub(crate) async fn run_benchmark(config: &Config, registry: &StrategyRegistry,
     for model in &models {
         let run_dir = run_dir.clone();
         let workspace_snapshot = workspace_snapshot.clone();
-        let binary = config.opencode.binary.clone();
+        let opencode = config.opencode.clone();
         let model = model.clone();
         let strategy_name = strategy_name.clone();
         let prompt = prompt.clone();
@@ -473,7 +486,7 @@ pub(crate) async fn run_benchmark(config: &Config, registry: &StrategyRegistry,
             let results = run_model_samples(
                 &run_dir,
                 &workspace_snapshot,
-                &binary,
+                &opencode,
                 &model,
                 &strategy_name,
                 &prompt,
@@ -0,0 +1,160 @@
+//! Breaking updates: between scheduled digests, a short update article is generated when a
+//! channel's trigger sources post (see docs/specs/breaking-updates.md).
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+
+use crate::config::{BreakingConfig, OutputChannelConfig};
+use crate::models::OutputChannel;
+use crate::pipeline::TimeWindow;
+use crate::store;
+
+/// Appended to the channel's prompt for update runs.
+const UPDATE_DIRECTIVE: &str = "This run is a breaking update, not the regular digest. The items are new posts \
+     from the channel's priority sources since the last digest or update. Write a short article: a few \
+     paragraphs on what is new and why it matters, most important first. No overview of the rest of the \
+     news; the next digest covers everything.";
+
+/// Settings key holding when the channel's last update was started.
+fn last_update_key(channel_id: &str) -> String {
+    format!("breaking_last_update:{channel_id}")
+}
+
+/// The window of an update if one is due: the cooldown has passed since the last update and
+/// the trigger sources have at least `min_items` items since the last digest or update.
+/// Nothing is recorded; once the update is queued, `record_update` starts the cooldown.
+/// Scheduled channels that have never generated get no updates; their first digest comes first.
+pub async fn due_update(
+    pool: &SqlitePool,
+    channel: &OutputChannel,
+    breaking: &BreakingConfig,
+    now: DateTime<Utc>,
+) -> Result<Option<TimeWindow>> {
+    let key = last_update_key(&channel.id);
+    let last_update = store::get_setting(pool, &key)
+        .await?
+        .and_then(|v| DateTime::parse_from_rfc3339(&v).ok())
+        .map(|d| d.with_timezone(&Utc));
+    let cooldown = humantime::parse_duration(&breaking.cooldown).context("parsing breaking.cooldown")?;
+    let cooldown = chrono::Duration::from_std(cooldown).unwrap_or(chrono::Duration::hours(1));
+    if last_update.is_some_and(|last| now - last < cooldown) {
+        return Ok(None);
+    }
+
+    let last_digest = match (channel.last_generated, &channel.schedule) {
+        (Some(last_generated), _) => last_generated,
+        (None, Some(_)) => return Ok(None),
+        // Update-only channel: the first update looks back one cooldown
+        (None, None) => now - cooldown,
+    };
+    let from = last_update.map_or(last_digest, |last| last.max(last_digest));
+    let source_ids = store::get_channel_source_ids(pool, &channel.id).await?;
+    let trigger_ids: Vec<String> = store::get_sources_by_ids(pool, &source_ids)
+        .await?
+        .into_iter()
+        .filter(|s| s.enabled && breaking.sources.contains(&s.name))
+        .map(|s| s.id)
+        .collect();
+    let new_items = store::get_items_in_window(pool, &trigger_ids, from, now).await?;
+    if new_items.len() < breaking.min_items as usize {
+        return Ok(None);
+    }
+
+    Ok(Some(TimeWindow::Update {
+        from,
+        sources: breaking.sources.clone(),
+    }))
+}
+
+/// Record an update queued at `now`: the cooldown runs from here and the next update's window
+/// starts here. Called only once the run is queued, so a skipped one (a generation already in
+/// flight) is checked again on the next tick; a queued run that fails still waits out the
+/// cooldown.
+pub async fn record_update(pool: &SqlitePool, channel_id: &str, now: DateTime<Utc>) -> Result<()> {
+    store::set_setting(pool, &last_update_key(channel_id), &now.to_rfc3339()).await
+}
+
+/// The channel config an update runs with: the update directive after the prompt, the
+/// update's title prefix and timeout, and none of the digest-only extras (required sections,
+/// output format, changelog, self-evaluation).
+pub fn update_config(channel_config: &OutputChannelConfig, breaking: &BreakingConfig) -> OutputChannelConfig {
+    let mut config = channel_config.clone();
+    config.prompt = format!("{}\n\n{UPDATE_DIRECTIVE}", channel_config.prompt.trim_end());
+    config.postprocess.title_prefix = Some(breaking.title_prefix.clone());
+    config.timeout = Some(breaking.timeout.clone());
+    config.sections = Vec::new();
+    config.format = None;
+    config.changelog = false;
+    config.self_eval = None;
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ContentItem;
+
+    fn breaking_config() -> BreakingConfig {
+        BreakingConfig {
+            sources: vec!["wire".to_string()],
+            min_items: 1,
+            cooldown: "1h".to_string(),
+            timeout: "10m".to_string(),
+            title_prefix: "Update: ".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_only_a_recorded_update_starts_the_cooldown() {
+        let pool = crate::db::memory_pool().await;
+        for query in [
+            "INSERT INTO sources (id, source_type, name) VALUES ('src', 'rss', 'wire')",
+            "INSERT INTO output_channels (id, name, slug, prompt) VALUES ('ch', 'News', 'news', 'p')",
+            "INSERT INTO output_channel_sources (output_channel_id, source_id) VALUES ('ch', 'src')",
+        ] {
+            sqlx::query(query).execute(&pool).await.unwrap();
+        }
+        let now = Utc::now();
+        store::upsert_content_item(
+            &pool,
+            &ContentItem {
+                id: "item".to_string(),
+                source_id: "src".to_string(),
+                ingested_at: now,
+                original_date: now - chrono::Duration::minutes(5),
+                content_type: "article".to_string(),
+                title: Some("Breaking".to_string()),
+                body: "News".to_string(),
+                url: None,
+                author: None,
+                metadata: "{}".to_string(),
+                dedup_key: "item".to_string(),
+                upstream_changed: false,
+            },
+        )
+        .await
+        .unwrap();
+        let channel = OutputChannel {
+            id: "ch".to_string(),
+            name: "News".to_string(),
+            slug: "news".to_string(),
+            schedule: Some("daily:08:00".to_string()),
+            prompt: "p".to_string(),
+            model: None,
+            language: None,
+            enabled: true,
+            last_generated: Some(now - chrono::Duration::hours(2)),
+            skipped_from: None,
+        };
+        let breaking = breaking_config();
+
+        // Checking records nothing: an update skipped for a run in flight is still due
+        assert!(due_update(&pool, &channel, &breaking, now).await.unwrap().is_some());
+        assert!(due_update(&pool, &channel, &breaking, now).await.unwrap().is_some());
+
+        record_update(&pool, "ch", now).await.unwrap();
+        let later = now + chrono::Duration::minutes(30);
+        assert!(due_update(&pool, &channel, &breaking, later).await.unwrap().is_none());
+    }
+}
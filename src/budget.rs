@@ -0,0 +1,246 @@
+//! Per-run cost and token limits for opencode (see docs/specs/generation-engine.md "Budget Limits").
+//! Usage is read from opencode's own session records while the run is in progress.
+
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::config::{OpencodeConfig, OutputChannelConfig};
+
+/// How often a running session's usage is checked against the budget. Each check runs
+/// `opencode session list` and one `opencode export` per session, so it stays coarse.
+const POLL_SECS: u64 = 60;
+
+/// Limits for one opencode run. Both `None` means unlimited (no polling).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Budget {
+    pub max_cost_usd: Option<f64>,
+    pub max_tokens: Option<u64>,
+}
+
+impl Budget {
+    /// Channel limits, falling back to `[opencode]`.
+    pub fn for_channel(opencode: &OpencodeConfig, channel: &OutputChannelConfig) -> Self {
+        Self {
+            max_cost_usd: channel.max_cost_usd.or(opencode.max_cost_usd),
+            max_tokens: channel.max_tokens.or(opencode.max_tokens),
+        }
+    }
+
+    /// `[opencode]` limits only (benchmark runs).
+    pub fn global(opencode: &OpencodeConfig) -> Self {
+        Self {
+            max_cost_usd: opencode.max_cost_usd,
+            max_tokens: opencode.max_tokens,
+        }
+    }
+
+    fn is_limited(&self) -> bool {
+        self.max_cost_usd.is_some() || self.max_tokens.is_some()
+    }
+
+    /// Why `usage` is over budget, or `None` if it isn't.
+    fn exceeded_by(&self, usage: &SessionUsage) -> Option<String> {
+        if let Some(max) = self.max_cost_usd
+            && usage.cost_usd > max
+        {
+            return Some(format!("cost ${:.2} over max_cost_usd ${max:.2}", usage.cost_usd));
+        }
+        if let Some(max) = self.max_tokens
+            && usage.tokens > max
+        {
+            return Some(format!("{} tokens over max_tokens {max}", usage.tokens));
+        }
+        None
+    }
+}
+
+/// Cost and tokens of the opencode sessions run in a workspace.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SessionUsage {
+    pub cost_usd: f64,
+    /// Input (cache reads included), output, reasoning, and cache write tokens.
+    pub tokens: u64,
+}
+
+impl std::fmt::Display for SessionUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "${:.4}, {} tokens", self.cost_usd, self.tokens)
+    }
+}
+
+#[derive(Deserialize)]
+struct SessionListEntry {
+    id: String,
+    #[serde(default)]
+    directory: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SessionExport {
+    #[serde(default)]
+    messages: Vec<ExportedMessage>,
+}
+
+#[derive(Deserialize)]
+struct ExportedMessage {
+    info: MessageInfo,
+}
+
+#[derive(Deserialize)]
+struct MessageInfo {
+    #[serde(default)]
+    role: String,
+    #[serde(default)]
+    cost: f64,
+    #[serde(default)]
+    tokens: Option<MessageTokens>,
+}
+
+#[derive(Deserialize, Default)]
+struct MessageTokens {
+    #[serde(default)]
+    input: u64,
+    #[serde(default)]
+    output: u64,
+    #[serde(default)]
+    reasoning: u64,
+    #[serde(default)]
+    cache: CacheTokens,
+}
+
+#[derive(Deserialize, Default)]
+struct CacheTokens {
+    // `read` is left out: it is already part of `input`
+    #[serde(default)]
+    write: u64,
+}
+
+impl MessageTokens {
+    /// Tokens billed for one message. Cache writes are not part of `input`.
+    fn total(&self) -> u64 {
+        self.input + self.output + self.reasoning + self.cache.write
+    }
+}
+
+impl SessionExport {
+    /// Cost and tokens of the session's assistant messages.
+    fn usage(&self) -> SessionUsage {
+        let mut usage = SessionUsage::default();
+        for message in self.messages.iter().filter(|m| m.info.role == "assistant") {
+            usage.cost_usd += message.info.cost;
+            usage.tokens += message.info.tokens.as_ref().map_or(0, MessageTokens::total);
+        }
+        usage
+    }
+}
+
+/// Resolves once usage exceeds `budget`, with the reason; never resolves for an unlimited
+/// budget. Runs next to the opencode child in a `select!`.
+pub async fn watch(binary: &str, workspace: &Path, budget: Budget) -> String {
+    if !budget.is_limited() {
+        return std::future::pending().await;
+    }
+    loop {
+        tokio::time::sleep(Duration::from_secs(POLL_SECS)).await;
+        if let Some(usage) = session_usage(binary, workspace).await {
+            debug!(usage = %usage, "opencode session usage");
+            if let Some(reason) = budget.exceeded_by(&usage) {
+                return reason;
+            }
+        }
+    }
+}
+
+/// Sum cost and tokens over the opencode sessions whose directory is `workspace`
+/// (the main session and any subagent sessions opencode lists). `None` if opencode
+/// can't be queried or no session exists yet.
+pub async fn session_usage(binary: &str, workspace: &Path) -> Option<SessionUsage> {
+    let output = tokio::process::Command::new(binary)
+        .args(["session", "list", "-n", "50", "--format", "json"])
+        .current_dir(workspace)
+        .stderr(std::process::Stdio::null())
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let sessions: Vec<SessionListEntry> = serde_json::from_slice(&output.stdout).ok()?;
+    let workspace = workspace.canonicalize().unwrap_or_else(|_| workspace.to_path_buf());
+    let ids: Vec<String> = sessions
+        .into_iter()
+        .filter(|s| {
+            s.directory
+                .as_deref()
+                .is_some_and(|d| Path::new(d).canonicalize().is_ok_and(|d| d == workspace))
+        })
+        .map(|s| s.id)
+        .collect();
+    if ids.is_empty() {
+        return None;
+    }
+
+    let mut usage = SessionUsage::default();
+    for id in ids {
+        let output = tokio::process::Command::new(binary)
+            .args(["export", &id])
+            .current_dir(&workspace)
+            .stderr(std::process::Stdio::null())
+            .output()
+            .await
+            .ok()?;
+        let Ok(export) = serde_json::from_slice::<SessionExport>(&output.stdout) else {
+            debug!(session_id = %id, "unreadable opencode session export");
+            continue;
+        };
+        let session = export.usage();
+        usage.cost_usd += session.cost_usd;
+        usage.tokens += session.tokens;
+    }
+    Some(usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_usage_counts_cache_reads_once() {
+        let export: SessionExport = serde_json::from_value(serde_json::json!({
+            "messages": [
+                {"info": {"role": "user"}},
+                {"info": {"role": "assistant", "cost": 0.25, "tokens": {
+                    "input": 1000, "output": 200, "reasoning": 50, "cache": {"read": 800, "write": 100}
+                }}},
+                {"info": {"role": "assistant", "cost": 0.5, "tokens": {"input": 10, "output": 5}}},
+                {"info": {"role": "assistant", "cost": 0.125}}
+            ]
+        }))
+        .unwrap();
+        let usage = export.usage();
+        assert_eq!(usage.tokens, 1000 + 200 + 50 + 100 + 10 + 5);
+        assert_eq!(usage.cost_usd, 0.875);
+    }
+
+    #[test]
+    fn test_exceeded_by() {
+        let budget = Budget {
+            max_cost_usd: Some(1.0),
+            max_tokens: Some(500),
+        };
+        let under = SessionUsage {
+            cost_usd: 1.0,
+            tokens: 500,
+        };
+        assert!(budget.exceeded_by(&under).is_none());
+        let over = SessionUsage {
+            cost_usd: 0.5,
+            tokens: 501,
+        };
+        assert_eq!(budget.exceeded_by(&over).unwrap(), "501 tokens over max_tokens 500");
+        assert!(Budget::default().exceeded_by(&over).is_none());
+    }
+}
@@ -0,0 +1,318 @@
+//! Generation bundles: one article's workspace, output, and log packed into a `.tar.gz`
+//! (`pail bundle`), and replayed with `pail generate --from-bundle` (see
+//! docs/specs/generation-engine.md "Bundles").
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::article_log;
+use crate::budget::Budget;
+use crate::config::Config;
+use crate::models::{GeneratedArticleRow, Source};
+use crate::strategy::{self, StrategyRegistry};
+use crate::{generate, store};
+
+/// Bumped when the archive layout changes incompatibly.
+const BUNDLE_FORMAT: u32 = 1;
+
+/// Workspace entries packed into a bundle. `opencode.json` and `.opencode/` are left out:
+/// they may carry provider settings, and a replay writes them from the current config.
+const WORKSPACE_ENTRIES: &[&str] = &["manifest.json", "prompt.md", "previous.md", "sources", "pages"];
+
+/// `bundle.json`: what the bundle was made from.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundleInfo {
+    pub format: u32,
+    pub pail_version: String,
+    pub article_id: String,
+    pub channel: String,
+    pub title: String,
+    pub model: String,
+    pub strategy: String,
+    pub generated_at: DateTime<Utc>,
+    pub covers_from: DateTime<Utc>,
+    pub covers_to: DateTime<Utc>,
+    /// `workspace` (packed from the kept workspace, exactly what the model saw) or `rebuilt`
+    /// (recreated from stored items and the current config).
+    pub origin: String,
+}
+
+/// Result of `pail generate --from-bundle`.
+pub struct Replay {
+    pub info: BundleInfo,
+    pub raw_output: String,
+}
+
+/// Pack an article's generation into a `.tar.gz` at `out`. Uses the kept workspace
+/// (`[opencode].keep_workspace`) when there is one, else rebuilds the workspace from the
+/// article's stored items.
+pub async fn create(
+    pool: &SqlitePool,
+    config: &Config,
+    registry: &StrategyRegistry,
+    article_id: &str,
+    out: &Path,
+) -> Result<BundleInfo> {
+    let article = store::get_article_by_id(pool, article_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("no article with ID '{article_id}'"))?;
+    let channel = store::get_channel_by_id(pool, &article.output_channel_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("article '{article_id}' belongs to a channel that no longer exists"))?;
+
+    let kept = config.pail.data_dir.join("workspaces").join(&article.id);
+    let (workspace, origin, _rebuilt_dir) = if kept.join("manifest.json").exists() {
+        info!(workspace = %kept.display(), "bundling kept workspace");
+        (kept, "workspace", None)
+    } else {
+        let dir = rebuild_workspace(pool, config, registry, &article, &channel.slug).await?;
+        (dir.path().to_path_buf(), "rebuilt", Some(dir))
+    };
+
+    // A kept workspace holds the exact output; otherwise reassemble it from the stored article
+    let output = match tokio::fs::read_to_string(workspace.join("output.md")).await {
+        Ok(output) if origin == "workspace" && !output.trim().is_empty() => output,
+        _ => stored_output(&article),
+    };
+
+    let info = BundleInfo {
+        format: BUNDLE_FORMAT,
+        pail_version: env!("CARGO_PKG_VERSION").to_string(),
+        article_id: article.id.clone(),
+        channel: channel.slug.clone(),
+        title: article.title.clone(),
+        model: article.model_used.clone(),
+        strategy: article.strategy_used.clone(),
+        generated_at: article.generated_at,
+        covers_from: article.covers_from,
+        covers_to: article.covers_to,
+        origin: origin.to_string(),
+    };
+    let info_json = serde_json::to_string_pretty(&info)?;
+    let log = article_log::read_full(config, &article.id, &article.generation_log).await?;
+    let out = out.to_path_buf();
+
+    tokio::task::spawn_blocking(move || write_archive(&out, &workspace, &info_json, &output, &log))
+        .await
+        .context("writing bundle")??;
+    Ok(info)
+}
+
+/// Recreate an article's workspace from its stored items: source files, prefetched pages
+/// still in the cache, and the prompt as the current config renders it.
+async fn rebuild_workspace(
+    pool: &SqlitePool,
+    config: &Config,
+    registry: &StrategyRegistry,
+    article: &GeneratedArticleRow,
+    slug: &str,
+) -> Result<tempfile::TempDir> {
+    let channel_config = config.output_channel.iter().find(|c| c.slug == slug).ok_or_else(|| {
+        anyhow::anyhow!("channel '{slug}' is no longer in the config, and the article has no kept workspace")
+    })?;
+    let strategy = registry
+        .get(&article.strategy_used)
+        .ok_or_else(|| anyhow::anyhow!("strategy '{}' not found in registry", article.strategy_used))?;
+    let merged_opencode_config = strategy::resolve_opencode_config(strategy)?;
+
+    let item_ids: Vec<String> = serde_json::from_str(&article.content_item_ids).unwrap_or_default();
+    let items = store::get_items_by_ids(pool, &item_ids).await?;
+    if items.is_empty() {
+        anyhow::bail!("the article's items are no longer stored (removed by content retention)");
+    }
+    if items.len() < item_ids.len() {
+        warn!(
+            stored = items.len(),
+            generated_with = item_ids.len(),
+            "some of the article's items were removed by content retention, bundling the rest"
+        );
+    }
+
+    let mut source_ids: Vec<String> = items.iter().map(|i| i.source_id.clone()).collect();
+    source_ids.sort();
+    source_ids.dedup();
+    let sources = store::get_sources_by_ids(pool, &source_ids).await?;
+    let source_map: HashMap<String, &Source> = sources.iter().map(|s| (s.id.clone(), s)).collect();
+    let mut folder_channels = HashMap::new();
+    for source in sources.iter().filter(|s| s.source_type == "telegram_folder") {
+        folder_channels.insert(
+            source.id.clone(),
+            store::get_folder_channel_map(pool, &source.id).await?,
+        );
+    }
+    let urls: Vec<String> = items.iter().filter_map(|i| i.url.clone()).collect();
+    let mut pages = store::get_fetched_pages(pool, &urls).await?;
+    pages.retain(|_, page| page.status == "ok" && page.text.is_some());
+
+    let ws = generate::prepare_workspace(
+        config,
+        channel_config,
+        strategy,
+        &merged_opencode_config,
+        &items,
+        &source_map,
+        &folder_channels,
+        &pages,
+        article.covers_from,
+        article.covers_to,
+    )
+    .await?;
+    // Feedback notes and the changelog block aren't reproduced: they depend on what was
+    // stored at generation time
+    generate::write_prompt(ws.path(), strategy, config, channel_config, &[], None).await?;
+    Ok(ws.dir)
+}
+
+/// `output.md` reassembled from the stored title, topics, summary, and body.
+fn stored_output(article: &GeneratedArticleRow) -> String {
+    let title = serde_json::to_string(&article.title).unwrap_or_default();
+    let summary = article
+        .summary
+        .as_ref()
+        .map(|s| format!("summary: {}\n", serde_json::to_string(s).unwrap_or_default()))
+        .unwrap_or_default();
+    format!(
+        "---\ntitle: {title}\ntopics: {}\n{summary}---\n\n{}\n",
+        article.topics,
+        article.body_markdown.trim_end()
+    )
+}
+
+/// Layout: `bundle.json`, `output.md`, `generation.log` at the top, the workspace under
+/// `workspace/`, so a replay can run in `workspace/` without the model seeing the old result.
+fn write_archive(out: &Path, workspace: &Path, info_json: &str, output: &str, log: &str) -> Result<()> {
+    let file = std::fs::File::create(out).with_context(|| format!("creating {}", out.display()))?;
+    let mut tar = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let mut append_text = |name: &str, text: &str| -> Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(text.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(Utc::now().timestamp() as u64);
+        header.set_cksum();
+        tar.append_data(&mut header, name, text.as_bytes())
+            .with_context(|| format!("adding {name}"))
+    };
+    append_text("bundle.json", info_json)?;
+    append_text("output.md", output)?;
+    append_text("generation.log", log)?;
+
+    for entry in WORKSPACE_ENTRIES {
+        let path = workspace.join(entry);
+        let name = Path::new("workspace").join(entry);
+        let added = if path.is_dir() {
+            tar.append_dir_all(&name, &path)
+        } else if path.is_file() {
+            tar.append_path_with_name(&path, &name)
+        } else {
+            continue;
+        };
+        added.with_context(|| format!("adding {}", path.display()))?;
+    }
+
+    tar.into_inner()?.finish().context("finishing bundle")?;
+    Ok(())
+}
+
+/// Run a bundle's workspace through opencode again with the bundle's prompt and model.
+/// Nothing is read from or written to the database. `strategy_override` picks the tools
+/// written to the workspace (default: the bundle's strategy).
+pub async fn replay(
+    config: &Config,
+    registry: &StrategyRegistry,
+    bundle: &Path,
+    strategy_override: Option<&str>,
+    cancel: CancellationToken,
+) -> Result<Replay> {
+    let dir = tempfile::Builder::new()
+        .prefix("pail-bundle-")
+        .tempdir()
+        .context("creating replay directory")?;
+    let archive = bundle.to_path_buf();
+    let dest: PathBuf = dir.path().to_path_buf();
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let file = std::fs::File::open(&archive).with_context(|| format!("opening {}", archive.display()))?;
+        // `unpack` refuses entries that would land outside `dest`
+        tar::Archive::new(GzDecoder::new(file))
+            .unpack(&dest)
+            .with_context(|| format!("unpacking {}", archive.display()))
+    })
+    .await
+    .context("unpacking bundle")??;
+
+    let info: BundleInfo = serde_json::from_str(
+        &tokio::fs::read_to_string(dir.path().join("bundle.json"))
+            .await
+            .context("reading bundle.json (not a pail bundle?)")?,
+    )
+    .context("parsing bundle.json")?;
+    if info.format > BUNDLE_FORMAT {
+        anyhow::bail!(
+            "bundle format {} is newer than this pail supports ({BUNDLE_FORMAT}); made by pail {}",
+            info.format,
+            info.pail_version
+        );
+    }
+
+    let ws_path = dir.path().join("workspace");
+    let prompt = tokio::fs::read_to_string(ws_path.join("prompt.md"))
+        .await
+        .context("reading workspace/prompt.md")?;
+
+    let strategy_name = strategy_override.unwrap_or(&info.strategy);
+    let strategy = registry
+        .get(strategy_name)
+        .ok_or_else(|| anyhow::anyhow!("strategy '{strategy_name}' not found in registry"))?;
+    generate::write_opencode_config(&ws_path, &strategy::resolve_opencode_config(strategy)?).await?;
+    generate::write_strategy_tools(&ws_path, strategy).await?;
+    tokio::fs::write(ws_path.join("output.md"), "").await?;
+
+    // The channel's budget and timeout apply if it still exists; the global ones otherwise
+    let channel_config = config.output_channel.iter().find(|c| c.slug == info.channel);
+    let budget = match channel_config {
+        Some(channel_config) => Budget::for_channel(&config.opencode, channel_config),
+        None => Budget::global(&config.opencode),
+    };
+    let timeout = channel_config
+        .and_then(|c| c.timeout.as_deref())
+        .unwrap_or(&strategy.meta.timeout);
+
+    info!(
+        article = %info.article_id,
+        channel = %info.channel,
+        model = %info.model,
+        strategy = %strategy_name,
+        "replaying bundle"
+    );
+    let (log, exit_code, _) = generate::invoke_opencode(
+        &config.opencode,
+        &ws_path,
+        &info.model,
+        &prompt,
+        timeout,
+        budget,
+        None,
+        cancel,
+    )
+    .await
+    .context("invoking opencode")?;
+    if exit_code != Some(0) {
+        warn!(exit_code = ?exit_code, "opencode exited with non-zero code, checking output anyway");
+    }
+
+    let raw_output = tokio::fs::read_to_string(ws_path.join("output.md")).await?;
+    if raw_output.trim().is_empty() {
+        anyhow::bail!("replay produced an empty output.md. opencode log:\n{log}");
+    }
+    Ok(Replay { info, raw_output })
+}
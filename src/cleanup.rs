@@ -1,15 +1,28 @@
 use std::sync::Arc;
 
-use chrono::Utc;
+use chrono::{Timelike, Utc};
 use sqlx::SqlitePool;
+use tokio::sync::watch;
 use tokio_util::sync::CancellationToken;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::config::Config;
-use crate::store;
+use crate::{article_log, db, store};
+
+/// Feed access log entries are kept this long, independent of content retention.
+const FEED_ACCESS_LOG_RETENTION_DAYS: i64 = 90;
+
+/// Generation run history (`/runs`) is kept this long.
+const GENERATION_RUN_RETENTION_DAYS: i64 = 90;
+
+/// A live run not updated for this long belongs to a process that died mid-generation.
+const STALE_LIVE_RUN_HOURS: i64 = 1;
+
+/// Settings key holding the local date of the last database maintenance.
+const LAST_MAINTENANCE_KEY: &str = "db_last_maintenance";
 
 /// Content retention cleanup loop. Wakes every hour.
-pub async fn cleanup_loop(pool: SqlitePool, config: Arc<Config>, cancel: CancellationToken) {
+pub async fn cleanup_loop(pool: SqlitePool, config_rx: watch::Receiver<Arc<Config>>, cancel: CancellationToken) {
     info!("cleanup job started");
 
     loop {
@@ -21,6 +34,7 @@ pub async fn cleanup_loop(pool: SqlitePool, config: Arc<Config>, cancel: Cancell
             _ = tokio::time::sleep(std::time::Duration::from_secs(3600)) => {}
         }
 
+        let config = config_rx.borrow().clone();
         let retention = match humantime::parse_duration(&config.pail.retention) {
             Ok(d) => chrono::Duration::from_std(d).unwrap_or(chrono::Duration::days(7)),
             Err(e) => {
@@ -31,15 +45,209 @@ pub async fn cleanup_loop(pool: SqlitePool, config: Arc<Config>, cancel: Cancell
 
         let cutoff = Utc::now() - retention;
 
-        match store::delete_old_content_items(&pool, cutoff).await {
-            Ok(deleted) => {
-                if deleted > 0 {
-                    info!(deleted, cutoff = %cutoff.to_rfc3339(), "cleaned up old content items");
+        if config.pail.archive {
+            match store::archive_old_content_items(&pool, cutoff).await {
+                Ok(archived) if archived > 0 => {
+                    info!(archived, cutoff = %cutoff.to_rfc3339(), "archived old content items");
                 }
+                Ok(_) => {}
+                Err(e) => error!(error = %e, "content archiving failed"),
             }
-            Err(e) => {
-                error!(error = %e, "content cleanup failed");
+        } else {
+            match store::delete_old_content_items(&pool, cutoff).await {
+                Ok(deleted) => {
+                    if deleted > 0 {
+                        info!(deleted, cutoff = %cutoff.to_rfc3339(), "cleaned up old content items");
+                    }
+                }
+                Err(e) => {
+                    error!(error = %e, "content cleanup failed");
+                }
+            }
+        }
+
+        // Cached page fetches share the content retention: older pages belong to items that are gone
+        match store::delete_old_fetched_pages(&pool, cutoff).await {
+            Ok(deleted) if deleted > 0 => {
+                info!(deleted, cutoff = %cutoff.to_rfc3339(), "cleaned up old fetched pages");
+            }
+            Ok(_) => {}
+            Err(e) => error!(error = %e, "fetched page cleanup failed"),
+        }
+
+        // Pre-processor drops too: a feed still returning such an old entry has it run again
+        match store::delete_old_preprocess_dropped(&pool, cutoff).await {
+            Ok(deleted) if deleted > 0 => {
+                info!(deleted, cutoff = %cutoff.to_rfc3339(), "cleaned up old pre-processor drops");
+            }
+            Ok(_) => {}
+            Err(e) => error!(error = %e, "pre-processor drop cleanup failed"),
+        }
+
+        // Downloaded TG photos too, unless an article still embeds them
+        match store::delete_old_tg_media(&pool, cutoff).await {
+            Ok(deleted) if deleted > 0 => {
+                info!(deleted, cutoff = %cutoff.to_rfc3339(), "cleaned up old TG photos");
+            }
+            Ok(_) => {}
+            Err(e) => error!(error = %e, "TG photo cleanup failed"),
+        }
+
+        let log_cutoff = Utc::now() - chrono::Duration::days(FEED_ACCESS_LOG_RETENTION_DAYS);
+        match store::delete_old_feed_access_log(&pool, log_cutoff).await {
+            Ok(deleted) if deleted > 0 => {
+                info!(deleted, cutoff = %log_cutoff.to_rfc3339(), "cleaned up old feed access log entries");
+            }
+            Ok(_) => {}
+            Err(e) => error!(error = %e, "feed access log cleanup failed"),
+        }
+
+        let runs_cutoff = Utc::now() - chrono::Duration::days(GENERATION_RUN_RETENTION_DAYS);
+        match store::delete_old_generation_runs(&pool, runs_cutoff).await {
+            Ok(deleted) if deleted > 0 => {
+                info!(deleted, cutoff = %runs_cutoff.to_rfc3339(), "cleaned up old generation runs");
+            }
+            Ok(_) => {}
+            Err(e) => error!(error = %e, "generation run cleanup failed"),
+        }
+
+        let live_cutoff = Utc::now() - chrono::Duration::hours(STALE_LIVE_RUN_HOURS);
+        match store::delete_stale_live_runs(&pool, live_cutoff).await {
+            Ok(deleted) if deleted > 0 => {
+                info!(deleted, cutoff = %live_cutoff.to_rfc3339(), "cleaned up stale live runs");
+            }
+            Ok(_) => {}
+            Err(e) => error!(error = %e, "live run cleanup failed"),
+        }
+
+        // Pruned even with recording off, so turning it off doesn't leave the window behind
+        if let Ok(keep) = humantime::parse_duration(&config.telegram.debug_updates.keep) {
+            let debug_cutoff = Utc::now() - chrono::Duration::from_std(keep).unwrap_or(chrono::Duration::days(3));
+            match store::delete_old_tg_debug_updates(&pool, debug_cutoff).await {
+                Ok(deleted) if deleted > 0 => {
+                    info!(deleted, cutoff = %debug_cutoff.to_rfc3339(), "cleaned up old TG debug updates");
+                }
+                Ok(_) => {}
+                Err(e) => error!(error = %e, "TG debug update cleanup failed"),
+            }
+        }
+
+        cleanup_articles(&pool, &config).await;
+
+        if config.database.maintenance {
+            maintain_database(&pool, &config).await;
+        }
+    }
+}
+
+/// Run `db::maintain` once a day, in the first hourly pass that falls in
+/// `[database].maintenance_hour` with no generation running. It runs after retention cleanup,
+/// so the pages that cleanup freed are reclaimed.
+async fn maintain_database(pool: &SqlitePool, config: &Config) {
+    let tz: chrono_tz::Tz = config.pail.timezone.parse().unwrap_or(chrono_tz::UTC);
+    let now = Utc::now();
+    let local = now.with_timezone(&tz);
+    if local.hour() != config.database.maintenance_hour {
+        return;
+    }
+    let today = local.date_naive().to_string();
+    match store::get_setting(pool, LAST_MAINTENANCE_KEY).await {
+        Ok(Some(last)) if last == today => return,
+        Ok(_) => {}
+        Err(e) => {
+            error!(error = %e, "reading last database maintenance failed");
+            return;
+        }
+    }
+    match store::any_generation_running(pool, now).await {
+        Ok(false) => {}
+        Ok(true) => {
+            info!("generation running, skipping database maintenance until tomorrow");
+            return;
+        }
+        Err(e) => {
+            error!(error = %e, "checking for running generations failed");
+            return;
+        }
+    }
+    // Recorded first: a failing maintenance isn't retried before the next day
+    if let Err(e) = store::set_setting(pool, LAST_MAINTENANCE_KEY, &today).await {
+        error!(error = %e, "recording database maintenance failed");
+        return;
+    }
+
+    // Never the conversion VACUUM: rewriting the whole file is left to `pail db maintain`
+    match db::maintain(pool, &config.db_path(), false).await {
+        Ok(report) => {
+            info!(
+                size_before = report.size_before,
+                size_after = report.size_after,
+                free_pages_before = report.free_pages_before,
+                free_pages_after = report.free_pages_after,
+                checkpoint_busy = report.checkpoint_busy,
+                "database maintenance done"
+            );
+            if report.needs_conversion {
+                warn!(
+                    free_pages = report.free_pages_after,
+                    "database predates incremental auto-vacuum, so free pages aren't reclaimed; run `pail db maintain` once to convert it"
+                );
+            }
+        }
+        Err(e) => error!(error = %e, "database maintenance failed"),
+    }
+}
+
+/// Apply the generated article retention policy. All parts are opt-in; with no
+/// article settings configured, articles are kept forever.
+async fn cleanup_articles(pool: &SqlitePool, config: &Config) {
+    if let Some(cutoff) = age_cutoff(config.pail.article_retention.as_deref()) {
+        match store::delete_old_articles(pool, cutoff).await {
+            Ok(deleted) if deleted > 0 => {
+                info!(deleted, cutoff = %cutoff.to_rfc3339(), "cleaned up old generated articles");
+            }
+            Ok(_) => {}
+            Err(e) => error!(error = %e, "article age cleanup failed"),
+        }
+    }
+
+    if let Some(keep) = config.pail.article_keep_per_channel {
+        match store::delete_excess_articles(pool, keep).await {
+            Ok(deleted) if deleted > 0 => {
+                info!(deleted, keep, "cleaned up generated articles beyond per-channel limit");
+            }
+            Ok(_) => {}
+            Err(e) => error!(error = %e, "article count cleanup failed"),
+        }
+    }
+
+    if let Some(cutoff) = age_cutoff(config.pail.strip_generation_log_after.as_deref()) {
+        match store::strip_old_generation_logs(pool, cutoff).await {
+            Ok(stripped) if stripped > 0 => {
+                info!(stripped, cutoff = %cutoff.to_rfc3339(), "stripped old generation logs");
             }
+            Ok(_) => {}
+            Err(e) => error!(error = %e, "generation log cleanup failed"),
+        }
+    }
+
+    // Full logs of articles that were deleted or stripped above, by whichever policy
+    match article_log::sweep(pool, config).await {
+        Ok(deleted) if deleted > 0 => info!(deleted, "deleted generation log files of removed articles"),
+        Ok(_) => {}
+        Err(e) => error!(error = %e, "generation log file cleanup failed"),
+    }
+}
+
+/// Turn an optional humantime duration into a cutoff timestamp. Invalid values are
+/// logged and skipped (config validation rejects them at startup).
+fn age_cutoff(age: Option<&str>) -> Option<chrono::DateTime<Utc>> {
+    let age = age?;
+    match humantime::parse_duration(age) {
+        Ok(d) => chrono::Duration::from_std(d).ok().map(|d| Utc::now() - d),
+        Err(e) => {
+            error!(error = %e, age = %age, "invalid article retention duration");
+            None
         }
     }
 }
@@ -12,12 +12,46 @@ pub struct Cli {
     #[arg(long, short, global = true, default_value = "config.toml")]
     pub config: PathBuf,
 
+    /// Don't apply pending database migrations at daemon startup; refuse to start instead
+    #[arg(long)]
+    pub no_auto_migrate: bool,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
+    /// Create a config file by answering a few questions (first-run setup)
+    Init {
+        /// Overwrite the config file if it exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Forward a loopback port to the allowlist proxy inside an opencode sandbox, then run
+    /// the command (internal: started by the sandbox wrapper)
+    #[command(hide = true)]
+    SandboxBridge {
+        /// The proxy's Unix socket
+        #[arg(long)]
+        socket: PathBuf,
+        /// Loopback port to listen on
+        #[arg(long)]
+        port: u16,
+        /// The command to run
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+
+    /// Run the daemon (same as no subcommand), optionally only its HTTP server or only its
+    /// background work
+    Daemon {
+        /// Which parts to run: `server` (HTTP), `worker` (polling and generation), or `all`
+        #[arg(long, value_enum, default_value = "all")]
+        role: crate::daemon::Role,
+    },
+
     /// Config file management (validate, edit sources)
     Config {
         #[command(subcommand)]
@@ -26,8 +60,14 @@ pub enum Commands {
 
     /// Generate a digest article for an output channel
     Generate {
-        /// Output channel slug
-        slug: String,
+        /// Output channel slug (not needed with --from-bundle)
+        #[arg(required_unless_present = "from_bundle")]
+        slug: Option<String>,
+
+        /// Replay a `pail bundle` archive instead: rerun its prompt and workspace through
+        /// opencode without touching the database
+        #[arg(long, conflicts_with_all = ["slug", "since", "from", "to"])]
+        from_bundle: Option<PathBuf>,
 
         /// Write raw markdown output to this file
         #[arg(long)]
@@ -48,6 +88,24 @@ pub enum Commands {
         /// Exact end of time window (RFC 3339, e.g., "2026-02-16T08:00:00Z"). Requires --from.
         #[arg(long, requires = "from")]
         to: Option<String>,
+
+        /// Generate even if a stored article already covers the same window
+        #[arg(long)]
+        force: bool,
+
+        /// With mark_tg_read, log the chats that would be marked as read instead of marking them
+        #[arg(long)]
+        mark_read_dry_run: bool,
+    },
+
+    /// Pack an article's generation (manifest, prompt, sources, output, log) into a .tar.gz
+    Bundle {
+        /// Article ID (as in /article/<id>)
+        article_id: String,
+
+        /// Archive to write
+        #[arg(long, default_value = "bundle.tar.gz")]
+        out: PathBuf,
     },
 
     /// Launch an interactive opencode TUI session with collected source data
@@ -72,6 +130,12 @@ pub enum Commands {
         to: Option<String>,
     },
 
+    /// Iterate on a channel's editorial prompt against one frozen set of items
+    Prompt {
+        #[command(subcommand)]
+        command: PromptCommands,
+    },
+
     /// Run benchmarks for article generation
     Benchmark {
         #[command(subcommand)]
@@ -84,11 +148,176 @@ pub enum Commands {
         command: StrategyCommands,
     },
 
+    /// Leave a note on a generated article; recent notes steer future digests of its channel
+    Feedback {
+        /// Article ID (the UUID in /article/<id> URLs and Atom entry IDs)
+        article_id: String,
+
+        /// What to change, e.g. "too much crypto, more depth on EU policy"
+        #[arg(long)]
+        note: String,
+    },
+
+    /// Show a word-level diff of two generated articles
+    Diff {
+        /// The older article's ID
+        old: String,
+
+        /// The newer article's ID
+        new: String,
+
+        /// Mark changes with [-removed-] / {+added+} even on a terminal
+        #[arg(long)]
+        no_color: bool,
+    },
+
+    /// Review drafts of channels with `require_approval`
+    Drafts {
+        #[command(subcommand)]
+        command: DraftsCommands,
+    },
+
+    /// Track which published articles you've read
+    Articles {
+        #[command(subcommand)]
+        command: ArticlesCommands,
+    },
+
+    /// Inspect the content items a generation would get
+    Items {
+        #[command(subcommand)]
+        command: ItemsCommands,
+    },
+
+    /// History of generation runs: outcome, duration, items, attempts
+    Runs {
+        #[command(subcommand)]
+        command: RunsCommands,
+    },
+
+    /// Named feed tokens limited to some channels, and the feed access log
+    Tokens {
+        #[command(subcommand)]
+        command: TokensCommands,
+    },
+
+    /// Copy output channels in the config file
+    Channels {
+        #[command(subcommand)]
+        command: ChannelsCommands,
+    },
+
+    /// Add, disable, or remove sources in the config file
+    Sources {
+        #[command(subcommand)]
+        command: SourcesCommands,
+    },
+
     /// Telegram session management
     Tg {
         #[command(subcommand)]
         command: TgCommands,
     },
+
+    /// Control a running daemon over its control socket
+    Ctl {
+        #[command(subcommand)]
+        command: CtlCommands,
+    },
+
+    /// Database schema migrations
+    Db {
+        #[command(subcommand)]
+        command: DbCommands,
+    },
+
+    /// Run the daemon as a systemd service
+    Service {
+        #[command(subcommand)]
+        command: ServiceCommands,
+    },
+
+    /// Generation workspaces kept with `[opencode].keep_workspace`
+    Workspaces {
+        #[command(subcommand)]
+        command: WorkspacesCommands,
+    },
+
+    /// Export pail's own feeds for other tools
+    Export {
+        #[command(subcommand)]
+        command: ExportCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ServiceCommands {
+    /// Write a systemd unit for this binary and config, then enable and start it
+    Install {
+        /// Install a user unit (~/.config/systemd/user) instead of a system unit
+        #[arg(long)]
+        user: bool,
+
+        /// Unit name
+        #[arg(long, default_value = "pail")]
+        name: String,
+
+        /// Print the unit instead of installing it
+        #[arg(long)]
+        print: bool,
+
+        /// Enable the unit without starting it
+        #[arg(long)]
+        no_start: bool,
+    },
+    /// Stop, disable, and remove the unit
+    Uninstall {
+        /// Remove the user unit
+        #[arg(long)]
+        user: bool,
+
+        /// Unit name
+        #[arg(long, default_value = "pail")]
+        name: String,
+    },
+    /// Show the unit's state (`systemctl status`)
+    Status {
+        /// Check the user unit
+        #[arg(long)]
+        user: bool,
+
+        /// Unit name
+        #[arg(long, default_value = "pail")]
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum WorkspacesCommands {
+    /// Delete kept workspaces
+    Prune {
+        /// Only delete workspaces kept longer ago than this (e.g., "7d"); default: all
+        #[arg(long)]
+        older_than: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ExportCommands {
+    /// Every enabled channel's Atom feed as OPML, with a feed token in each URL
+    Opml {
+        /// Server URL the feed URLs start with (default: `[pail].public_url`, else from `[pail].listen`)
+        #[arg(long)]
+        base_url: Option<String>,
+
+        /// Named feed token to put in the URLs; lists only its channels (default: the main feed token)
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -99,6 +328,219 @@ pub enum ConfigCommands {
     Edit,
 }
 
+#[derive(Subcommand)]
+pub enum DraftsCommands {
+    /// List drafts awaiting review
+    List,
+    /// Publish a draft to its channel's feeds (and Matrix room, if configured)
+    Approve {
+        /// Article ID
+        article_id: String,
+    },
+    /// Reject a draft; it is never published
+    Reject {
+        /// Article ID
+        article_id: String,
+    },
+    /// Reject a draft and generate a new one for the same time window
+    Regenerate {
+        /// Article ID
+        article_id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ArticlesCommands {
+    /// Mark articles read: the given IDs, one channel's, or every unread article
+    MarkRead {
+        /// Article IDs (default: all unread articles)
+        article_ids: Vec<String>,
+
+        /// Only this channel's unread articles
+        #[arg(long, conflicts_with = "article_ids")]
+        channel: Option<String>,
+
+        /// Mark the given articles unread instead
+        #[arg(long, requires = "article_ids")]
+        unread: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ItemsCommands {
+    /// List the items the channel's next generation would include, after its filters
+    List {
+        /// Output channel slug
+        #[arg(long)]
+        slug: String,
+
+        /// Window ending now instead of the next generation's (e.g. "24h")
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only this source's items
+        #[arg(long)]
+        source: Option<String>,
+
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PromptCommands {
+    /// Build the channel's workspace once, then edit the editorial directive and regenerate
+    /// from the same inputs, with a diff of each output against the previous one
+    Tune {
+        /// Output channel slug
+        slug: String,
+
+        /// Override generation strategy (default: channel's configured strategy)
+        #[arg(long)]
+        strategy: Option<String>,
+
+        /// Override time window with relative duration (e.g., "7d", "12h"). Mutually exclusive with --from/--to.
+        #[arg(long, conflicts_with_all = ["from", "to"])]
+        since: Option<String>,
+
+        /// Exact start of time window (RFC 3339, e.g., "2026-02-14T20:00:00Z"). Requires --to.
+        #[arg(long, requires = "to")]
+        from: Option<String>,
+
+        /// Exact end of time window (RFC 3339, e.g., "2026-02-16T08:00:00Z"). Requires --from.
+        #[arg(long, requires = "from")]
+        to: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RunsCommands {
+    /// List recent generation runs, newest first
+    List {
+        /// Only this output channel's runs (slug)
+        #[arg(long)]
+        channel: Option<String>,
+
+        /// How many runs to show
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TokensCommands {
+    /// Create a token and print it (it's only shown once)
+    Create {
+        /// Token name, e.g. who it's for
+        name: String,
+
+        /// Channel slug the token may read; repeat for several (default: every channel)
+        #[arg(long = "channel")]
+        channels: Vec<String>,
+    },
+    /// List tokens with their channels and last use
+    List,
+    /// Revoke a token; its feeds answer 401 from then on
+    Revoke {
+        /// Token name
+        name: String,
+    },
+    /// Show recent feed requests, newest first
+    Log {
+        /// Only this token's requests (`(feed_token)` for the main feed token)
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Number of entries
+        #[arg(long, default_value = "50")]
+        limit: i64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ChannelsCommands {
+    /// Append a copy of a channel to the config file under a new slug
+    Clone {
+        /// Slug of the channel to copy
+        slug: String,
+
+        /// Slug of the copy
+        #[arg(long)]
+        new_slug: String,
+
+        /// Name of the copy (default: the original's name with the new slug)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Use this file's contents as the copy's `prompt`
+        #[arg(long)]
+        prompt_file: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SourcesCommands {
+    /// List enabled sources with their fetch health
+    List {
+        /// Also show item counts, a 14-day activity sparkline, cache validators and Telegram resolution
+        #[arg(long)]
+        verbose: bool,
+    },
+    /// Add a new source to the config file
+    Add {
+        #[command(subcommand)]
+        source: AddSourceCommands,
+    },
+    /// Set `enabled = false` on a source (keeps it and its channel references)
+    Disable {
+        /// Source name
+        name: String,
+    },
+    /// Set `enabled = true` on a previously disabled source
+    Enable {
+        /// Source name
+        name: String,
+    },
+    /// Remove a source and drop it from every output channel's `sources` list
+    Remove {
+        /// Source name
+        name: String,
+    },
+    /// Delete a source's items and fetch state (cache validators, health, Telegram resolution)
+    Reset {
+        /// Source name
+        name: String,
+        /// Fetch the source again right away (Telegram: history back to `[pail].retention`)
+        #[arg(long)]
+        refetch: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AddSourceCommands {
+    /// Add an RSS/Atom feed source
+    Rss {
+        /// Source name (must be unique)
+        name: String,
+
+        /// Feed URL
+        url: String,
+
+        /// Also add the source to this output channel (by slug). Repeatable.
+        #[arg(long = "channel")]
+        channels: Vec<String>,
+
+        /// Human-readable description shown to the AI in the workspace
+        #[arg(long)]
+        description: Option<String>,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum BenchmarkCommands {
     /// Run all models and collect article outputs
@@ -157,12 +599,134 @@ pub enum StrategyCommands {
     },
 }
 
+#[derive(Subcommand)]
+pub enum CtlCommands {
+    /// Queue a generation for a channel now, outside its schedule
+    Generate {
+        /// Output channel slug
+        slug: String,
+
+        /// Relative time window (e.g., "12h"). Default: since the channel's last generation.
+        #[arg(long)]
+        since: Option<String>,
+    },
+    /// Re-read the config file and apply it without restarting
+    Reload,
+    /// Stop scheduled generations for a channel (on-demand generations still run)
+    Pause {
+        /// Output channel slug
+        slug: String,
+    },
+    /// Resume scheduled generations for a paused channel
+    Resume {
+        /// Output channel slug
+        slug: String,
+    },
+    /// Show daemon health and channel schedules
+    Status {
+        /// Print the raw JSON response
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DbCommands {
+    /// Apply pending migrations
+    Migrate,
+    /// List applied and pending migrations with their checksums
+    Status,
+    /// Revert applied migrations newer than VERSION (VERSION itself stays applied)
+    Rollback {
+        /// Target schema version; 0 reverts everything reversible
+        version: i64,
+
+        /// Revert without asking (default: only print the plan)
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Compress stored item bodies and generation logs written before compression, then vacuum
+    Compress {
+        /// Store everything uncompressed instead (before rolling back past the compression migration)
+        #[arg(long)]
+        undo: bool,
+    },
+    /// Reclaim free pages, refresh query planner statistics and truncate the WAL
+    Maintain,
+}
+
 #[derive(Subcommand)]
 pub enum TgCommands {
     /// Interactive MTProto login wizard
-    Login,
+    Login {
+        /// Log in by scanning a QR code from the Telegram app instead of entering a phone/code
+        #[arg(long)]
+        qr: bool,
+        /// Account to log in (a `[telegram.accounts]` name, or `default` for `[telegram]`)
+        #[arg(long, default_value = "default")]
+        account: String,
+    },
     /// Show Telegram session status
-    Status,
+    Status {
+        /// Account to check (a `[telegram.accounts]` name, or `default` for `[telegram]`)
+        #[arg(long, default_value = "default")]
+        account: String,
+    },
+    /// Fetch a source's message history further back than regular fetches; resumes if interrupted
+    Backfill {
+        /// Source name (a telegram_channel, telegram_group or telegram_folder source)
+        #[arg(long)]
+        source: String,
+        /// How far back, e.g. "90d"
+        #[arg(long)]
+        since: String,
+    },
+    /// Inspect updates recorded with `[telegram.debug_updates]`
+    Debug {
+        #[command(subcommand)]
+        command: TgDebugCommands,
+    },
+    /// Include or exclude single chats of a telegram_folder source
+    Folder {
+        #[command(subcommand)]
+        command: TgFolderCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TgFolderCommands {
+    /// List the folder's chats and whether each is included
+    Channels {
+        /// Folder source name
+        source: String,
+    },
+    /// Stop fetching a chat of the folder; kept across folder re-syncs
+    Disable {
+        /// Folder source name
+        source: String,
+        /// The chat: @username, numeric ID, or name
+        channel: String,
+    },
+    /// Fetch a disabled chat of the folder again
+    Enable {
+        /// Folder source name
+        source: String,
+        /// The chat: @username, numeric ID, or name
+        channel: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TgDebugCommands {
+    /// Find update sequence gaps and message ranges that never arrived live
+    Gaps {
+        /// Account whose updates to analyze
+        #[arg(long, default_value = "default")]
+        account: String,
+        /// How far back to look, e.g. "24h"
+        #[arg(long, default_value = "24h")]
+        since: String,
+    },
 }
 
 /// Parse --since/--from/--to into a TimeWindow.
@@ -0,0 +1,99 @@
+//! Transparent zstd compression of large stored text: content item bodies and generation logs
+//! (see docs/core.md "Compression").
+//!
+//! A compressed value is stored as a BLOB in the same column. Reads tell the two apart by
+//! content: a zstd frame starts with `28 B5 2F FD`, and `0xB5` can't follow an ASCII byte in
+//! UTF-8, so no stored text is mistaken for a frame. The `body_compressed` / `log_compressed`
+//! flag columns are bookkeeping for `pail db compress` and change detection.
+
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::sqlite::{Sqlite, SqliteArgumentValue, SqliteTypeInfo, SqliteValueRef};
+use sqlx::{Decode, Encode, Type};
+
+/// Values shorter than this stay plain: the frame overhead eats most of the saving.
+const MIN_COMPRESS_BYTES: usize = 512;
+
+/// zstd's default level: most of the ratio of higher levels at a fraction of the CPU time.
+const LEVEL: i32 = 3;
+
+/// zstd frame magic number, little-endian.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// A text value as written to the database.
+pub enum Encoded<'a> {
+    Plain(&'a str),
+    Zstd(Vec<u8>),
+}
+
+impl Encoded<'_> {
+    /// Value of the row's flag column.
+    pub fn is_compressed(&self) -> bool {
+        matches!(self, Encoded::Zstd(_))
+    }
+
+    /// Bytes the value takes in the database.
+    pub fn stored_len(&self) -> usize {
+        match self {
+            Encoded::Plain(text) => text.len(),
+            Encoded::Zstd(bytes) => bytes.len(),
+        }
+    }
+}
+
+/// Compressed when long enough and it actually saves space, plain otherwise.
+pub fn encode(text: &str) -> Encoded<'_> {
+    if text.len() < MIN_COMPRESS_BYTES {
+        return Encoded::Plain(text);
+    }
+    match zstd::encode_all(text.as_bytes(), LEVEL) {
+        Ok(bytes) if bytes.len() < text.len() => Encoded::Zstd(bytes),
+        _ => Encoded::Plain(text),
+    }
+}
+
+impl Type<Sqlite> for Encoded<'_> {
+    fn type_info() -> SqliteTypeInfo {
+        <str as Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for Encoded<'q> {
+    fn encode_by_ref(&self, buf: &mut Vec<SqliteArgumentValue<'q>>) -> Result<IsNull, BoxDynError> {
+        match self {
+            Encoded::Plain(text) => <&str as Encode<'q, Sqlite>>::encode_by_ref(text, buf),
+            Encoded::Zstd(bytes) => <Vec<u8> as Encode<'q, Sqlite>>::encode_by_ref(bytes, buf),
+        }
+    }
+}
+
+/// A text column that may hold a compressed value; decodes to the original text. Used as
+/// `#[sqlx(try_from = "StoredText")]` on `String` fields.
+pub struct StoredText(pub String);
+
+impl From<StoredText> for String {
+    fn from(text: StoredText) -> Self {
+        text.0
+    }
+}
+
+impl Type<Sqlite> for StoredText {
+    fn type_info() -> SqliteTypeInfo {
+        <Vec<u8> as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <Vec<u8> as Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for StoredText {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        let bytes = <&[u8] as Decode<'r, Sqlite>>::decode(value)?;
+        if bytes.starts_with(&ZSTD_MAGIC) {
+            let raw = zstd::decode_all(bytes)?;
+            return Ok(StoredText(String::from_utf8(raw)?));
+        }
+        Ok(StoredText(std::str::from_utf8(bytes)?.to_string()))
+    }
+}
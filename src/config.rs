@@ -1,10 +1,11 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::error::ConfigError;
+use crate::{language, notify};
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
@@ -14,8 +15,36 @@ pub struct Config {
     #[serde(default)]
     pub opencode: OpencodeConfig,
     #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
     pub telegram: TelegramConfig,
     #[serde(default)]
+    pub matrix: MatrixConfig,
+    #[serde(default)]
+    pub embeddings: EmbeddingsConfig,
+    #[serde(default)]
+    pub transcription: TranscriptionConfig,
+    #[serde(default)]
+    pub prefetch: PrefetchConfig,
+    #[serde(default)]
+    pub tagging: TaggingConfig,
+    #[serde(default)]
+    pub self_eval: SelfEvalConfig,
+    #[serde(default)]
+    pub translation: TranslationConfig,
+    #[serde(default)]
+    pub source_health: SourceHealthConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub reporting: ReportingConfig,
+    /// Reusable voice and style blocks, referenced by channel `persona`.
+    #[serde(default)]
+    pub persona: BTreeMap<String, PersonaConfig>,
+    /// Defaults shared by channels, referenced by channel `template`.
+    #[serde(default)]
+    pub channel_template: BTreeMap<String, ChannelTemplateConfig>,
+    #[serde(default)]
     pub source: Vec<SourceConfig>,
     #[serde(default)]
     pub output_channel: Vec<OutputChannelConfig>,
@@ -29,6 +58,24 @@ pub struct PailConfig {
     pub data_dir: PathBuf,
     #[serde(default = "default_retention")]
     pub retention: String,
+    /// Move content items past `retention` to `content_items_archive` instead of deleting them.
+    #[serde(default)]
+    pub archive: bool,
+    /// Delete generated articles older than this (default: keep forever).
+    pub article_retention: Option<String>,
+    /// Keep at most this many generated articles per output channel (default: unlimited).
+    pub article_keep_per_channel: Option<u32>,
+    /// Clear `generation_log` on articles older than this, keeping the article itself.
+    pub strip_generation_log_after: Option<String>,
+    /// Generation logs over this many bytes are stored in full under `data_dir/logs/`, and
+    /// only their head and tail inline in the article. 0 keeps every log inline.
+    #[serde(default = "default_generation_log_inline_bytes")]
+    pub generation_log_inline_bytes: u64,
+    /// A generation whose window shares at least this fraction of the longer of the two
+    /// windows with a stored article's is refused as a duplicate (`pail generate --force`
+    /// overrides). Default 0.9.
+    #[serde(default = "default_duplicate_overlap")]
+    pub duplicate_overlap: f64,
     #[serde(default = "default_timezone")]
     pub timezone: String,
     #[serde(default = "default_log_level")]
@@ -37,10 +84,20 @@ pub struct PailConfig {
     pub max_concurrent_generations: u32,
     #[serde(default = "default_listen")]
     pub listen: String,
+    /// Public base URL of the server (`https://digest.example.org`), for links that leave
+    /// pail: Webmention sources and `pail export opml`.
+    pub public_url: Option<String>,
+    /// Daemon control socket for `pail ctl`: a Unix socket path (relative to data_dir), or
+    /// `tcp:127.0.0.1:<port>`. Empty disables it. Default: `<data_dir>/pail.sock` on Unix.
+    pub control_socket: Option<String>,
     pub feed_token: Option<String>,
+    /// Bearer token for the admin API (`/api/v1`); the API is off without one.
+    pub admin_token: Option<String>,
     #[serde(default = "default_strategy")]
     pub default_strategy: String,
     pub strategies_dir: Option<PathBuf>,
+    /// Default editorial directive template file for channels without their own.
+    pub prompt_template: Option<PathBuf>,
 }
 
 fn default_version() -> u32 {
@@ -49,9 +106,15 @@ fn default_version() -> u32 {
 fn default_data_dir() -> PathBuf {
     PathBuf::from("./data")
 }
+fn default_generation_log_inline_bytes() -> u64 {
+    256 * 1024
+}
 fn default_retention() -> String {
     "7d".to_string()
 }
+fn default_duplicate_overlap() -> f64 {
+    0.9
+}
 fn default_timezone() -> String {
     "UTC".to_string()
 }
@@ -74,12 +137,25 @@ fn default_strategy() -> String {
 pub struct DatabaseConfig {
     #[serde(default = "default_db_path")]
     pub path: String,
+    /// Run `db::maintain` once a day in the daemon (checkpoint, incremental vacuum, ANALYZE).
+    #[serde(default = "default_maintenance")]
+    pub maintenance: bool,
+    /// Local hour (`[pail].timezone`, 0–23) in which the daily maintenance runs.
+    #[serde(default = "default_maintenance_hour")]
+    pub maintenance_hour: u32,
+    /// Log SQL statements that take longer than this (ms) at WARN, within the span of the store
+    /// function that ran them. 0 disables it.
+    #[serde(default = "default_log_slow_queries_ms")]
+    pub log_slow_queries_ms: u64,
 }
 
 impl Default for DatabaseConfig {
     fn default() -> Self {
         Self {
             path: default_db_path(),
+            maintenance: true,
+            maintenance_hour: default_maintenance_hour(),
+            log_slow_queries_ms: default_log_slow_queries_ms(),
         }
     }
 }
@@ -87,6 +163,15 @@ impl Default for DatabaseConfig {
 fn default_db_path() -> String {
     "pail.db".to_string()
 }
+fn default_maintenance() -> bool {
+    true
+}
+fn default_maintenance_hour() -> u32 {
+    4
+}
+fn default_log_slow_queries_ms() -> u64 {
+    1000
+}
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct OpencodeConfig {
@@ -94,6 +179,17 @@ pub struct OpencodeConfig {
     pub binary: String,
     #[serde(default)]
     pub default_model: Option<String>,
+    /// Keep generation workspaces in `data_dir/workspaces/`: "never", "on_failure", or "always".
+    #[serde(default = "default_keep_workspace")]
+    pub keep_workspace: String,
+    /// Run `opencode run` inside a sandbox wrapper (see docs/specs/generation-engine.md "Sandboxing").
+    pub sandbox: Option<SandboxConfig>,
+    /// Abort a run whose opencode sessions cost more than this (USD). Channels can override.
+    pub max_cost_usd: Option<f64>,
+    /// Abort a run whose opencode sessions used more tokens than this. Channels can override.
+    pub max_tokens: Option<u64>,
+    /// Kill `opencode run` after it has written nothing for this long, e.g. "10m". Off by default.
+    pub idle_timeout: Option<String>,
 }
 
 impl Default for OpencodeConfig {
@@ -101,16 +197,500 @@ impl Default for OpencodeConfig {
         Self {
             binary: default_opencode_binary(),
             default_model: None,
+            keep_workspace: default_keep_workspace(),
+            sandbox: None,
+            max_cost_usd: None,
+            max_tokens: None,
+            idle_timeout: None,
         }
     }
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+fn default_keep_workspace() -> String {
+    "never".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SandboxConfig {
+    /// "bubblewrap", "firejail", "docker", or "custom".
+    pub wrapper: String,
+    /// firejail: profile file (optional); docker: image with opencode installed (required).
+    pub profile: Option<String>,
+    /// Wrapper command template for `wrapper = "custom"`, run with `sh -c`. Placeholders:
+    /// `{command}`, `{workspace}`, `{rw_binds}`, `{ro_binds}`, `{profile}`.
+    pub command: Option<String>,
+    /// Hosts opencode may connect to, through a filtering proxy. Empty: no restriction.
+    #[serde(default)]
+    pub allow_hosts: Vec<String>,
+    /// Extra host paths mounted read-only (bubblewrap, docker, custom). firejail already sees
+    /// the whole filesystem read-only.
+    #[serde(default)]
+    pub ro_binds: Vec<String>,
+}
+
+/// Outgoing connections (see docs/specs/network.md).
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkConfig {
+    /// `socks5://`, `socks5h://`, `http://` or `https://` proxy URL for every HTTP client and
+    /// the Telegram connection. Without it, HTTP clients use the `HTTPS_PROXY` / `ALL_PROXY`
+    /// environment variables, if set.
+    pub proxy: Option<String>,
+    /// Where site operators can reach whoever runs this instance; sent in the User-Agent of
+    /// page prefetch and scrape requests.
+    #[serde(default = "default_contact_url")]
+    pub contact_url: String,
+    /// Minimum time between two prefetch / scrape requests to the same host. A longer
+    /// robots.txt `Crawl-delay` wins.
+    #[serde(default = "default_crawl_delay")]
+    pub crawl_delay: String,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            contact_url: default_contact_url(),
+            crawl_delay: default_crawl_delay(),
+        }
+    }
+}
+
+fn default_contact_url() -> String {
+    "https://github.com/kittyandrew/pail".to_string()
+}
+
+fn default_crawl_delay() -> String {
+    "1s".to_string()
+}
+
+/// Per-source `proxy` value that bypasses `[network].proxy` (and the environment's).
+pub const DIRECT_PROXY: &str = "direct";
+
+/// Proxy URL schemes accepted by `[network].proxy` and per-source `proxy`.
+pub const PROXY_SCHEMES: &[&str] = &["socks5", "socks5h", "http", "https"];
+
+/// Name of the account configured by the top-level `[telegram]` api_id / api_hash.
+pub const DEFAULT_TG_ACCOUNT: &str = "default";
+
+/// Whether a source type is read through a Telegram account (MTProto). `telegram_bot`
+/// sources receive messages through the Bot API instead and have no account or session.
+pub fn is_mtproto_source(source_type: &str) -> bool {
+    source_type.starts_with("telegram_") && source_type != "telegram_bot"
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct TelegramConfig {
     #[serde(default)]
     pub enabled: bool,
     pub api_id: Option<i32>,
     pub api_hash: Option<String>,
+    /// Additional accounts (`[telegram.accounts.<name>]`). Sources pick one with `tg_account`.
+    #[serde(default)]
+    pub accounts: BTreeMap<String, TelegramAccountConfig>,
+    #[serde(default)]
+    pub write_queue: TgWriteQueueConfig,
+    #[serde(default)]
+    pub debug_updates: TgDebugUpdatesConfig,
+    #[serde(default)]
+    pub media: TgMediaConfig,
+    /// Bot API token from @BotFather, for `telegram_bot` sources. Independent of `enabled`.
+    pub bot_token: Option<String>,
+    /// What happens to stored items of messages deleted in Telegram: one of `DELETED_MESSAGE_ACTIONS`.
+    #[serde(default = "default_tg_deleted_messages")]
+    pub deleted_messages: String,
+}
+
+impl Default for TelegramConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_id: None,
+            api_hash: None,
+            accounts: BTreeMap::new(),
+            write_queue: TgWriteQueueConfig::default(),
+            debug_updates: TgDebugUpdatesConfig::default(),
+            media: TgMediaConfig::default(),
+            bot_token: None,
+            deleted_messages: default_tg_deleted_messages(),
+        }
+    }
+}
+
+/// `[telegram].deleted_messages` values: leave the item, tombstone it (kept but left out of
+/// generations), or delete it.
+pub const DELETED_MESSAGE_ACTIONS: &[&str] = &["keep", "mark", "remove"];
+
+fn default_tg_deleted_messages() -> String {
+    "mark".to_string()
+}
+
+/// Buffer between the live update stream and the database (see docs/specs/telegram.md
+/// "Write Queue"). One queue per account.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TgWriteQueueConfig {
+    /// Messages held before the overflow policy kicks in.
+    #[serde(default = "default_tg_queue_capacity")]
+    pub capacity: usize,
+    /// Most messages written in one transaction.
+    #[serde(default = "default_tg_write_batch_size")]
+    pub batch_size: usize,
+    /// "drop_oldest" or "drop_newest".
+    #[serde(default = "default_tg_queue_overflow")]
+    pub overflow: String,
+}
+
+impl Default for TgWriteQueueConfig {
+    fn default() -> Self {
+        Self {
+            capacity: default_tg_queue_capacity(),
+            batch_size: default_tg_write_batch_size(),
+            overflow: default_tg_queue_overflow(),
+        }
+    }
+}
+
+/// Recording of received updates for `pail tg debug gaps` (see docs/specs/telegram.md
+/// "Debugging Gaps"). Off by default.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TgDebugUpdatesConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// How long recorded updates are kept (humantime).
+    #[serde(default = "default_tg_debug_keep")]
+    pub keep: String,
+}
+
+impl Default for TgDebugUpdatesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            keep: default_tg_debug_keep(),
+        }
+    }
+}
+
+fn default_tg_debug_keep() -> String {
+    "3d".to_string()
+}
+
+/// Download of photos from live Telegram messages, served at `/media/{id}` for articles to
+/// embed (see docs/specs/telegram.md "Media"). Off by default.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct TgMediaConfig {
+    #[serde(default)]
+    pub download: bool,
+}
+
+fn default_tg_queue_capacity() -> usize {
+    10_000
+}
+
+fn default_tg_write_batch_size() -> usize {
+    200
+}
+
+fn default_tg_queue_overflow() -> String {
+    "drop_oldest".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TelegramAccountConfig {
+    pub api_id: i32,
+    pub api_hash: String,
+}
+
+impl TelegramConfig {
+    /// API credentials of an account; `default` is the top-level `[telegram]` pair.
+    pub fn account(&self, name: &str) -> Option<TelegramAccountConfig> {
+        if name == DEFAULT_TG_ACCOUNT {
+            match (self.api_id, self.api_hash.as_deref()) {
+                (Some(api_id), Some(api_hash)) if api_id != 0 && !api_hash.is_empty() => Some(TelegramAccountConfig {
+                    api_id,
+                    api_hash: api_hash.to_string(),
+                }),
+                _ => None,
+            }
+        } else {
+            self.accounts.get(name).cloned()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MatrixConfig {
+    pub homeserver: Option<String>,
+    pub access_token: Option<String>,
+}
+
+/// OpenAI-compatible embeddings endpoint used for relevance filtering
+/// (OpenAI, or a local server such as Ollama or llama.cpp).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EmbeddingsConfig {
+    /// Base URL; requests go to `{api_url}/embeddings`.
+    pub api_url: Option<String>,
+    pub api_key: Option<String>,
+    pub model: Option<String>,
+}
+
+/// External speech-to-text command for podcast sources.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TranscriptionConfig {
+    /// Shell command; `{audio}` is replaced with the quoted path of the downloaded episode.
+    /// Must print the transcript to stdout.
+    pub command: Option<String>,
+    #[serde(default = "default_transcription_timeout")]
+    pub timeout: String,
+    /// New episodes transcribed per source per poll (transcription is slow).
+    #[serde(default = "default_max_episodes_per_fetch")]
+    pub max_episodes_per_fetch: u32,
+}
+
+impl Default for TranscriptionConfig {
+    fn default() -> Self {
+        Self {
+            command: None,
+            timeout: default_transcription_timeout(),
+            max_episodes_per_fetch: default_max_episodes_per_fetch(),
+        }
+    }
+}
+
+fn default_transcription_timeout() -> String {
+    "2h".to_string()
+}
+
+fn default_max_episodes_per_fetch() -> u32 {
+    2
+}
+
+/// Full-text fetch of linked articles before generation, so the model doesn't download
+/// them itself (see docs/specs/prefetch.md).
+#[derive(Debug, Clone, Deserialize)]
+pub struct PrefetchConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Pages fetched per generation, newest items first. Items beyond the cap are left to the model.
+    #[serde(default = "default_prefetch_max_pages")]
+    pub max_pages: u32,
+    /// Per-page request timeout.
+    #[serde(default = "default_prefetch_timeout")]
+    pub timeout: String,
+}
+
+impl Default for PrefetchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_pages: default_prefetch_max_pages(),
+            timeout: default_prefetch_timeout(),
+        }
+    }
+}
+
+fn default_prefetch_max_pages() -> u32 {
+    50
+}
+
+fn default_prefetch_timeout() -> String {
+    "20s".to_string()
+}
+
+/// OpenAI-compatible chat completions endpoint that tags newly ingested items, for channel
+/// `include_tags` / `exclude_tags` (see docs/specs/tagging.md). Meant for a small, cheap model.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TaggingConfig {
+    /// Base URL; requests go to `{api_url}/chat/completions`. Tagging is off unless set.
+    pub api_url: Option<String>,
+    pub api_key: Option<String>,
+    pub model: Option<String>,
+    /// Tags the model may assign. Empty lets it choose its own.
+    #[serde(default)]
+    pub vocabulary: Vec<String>,
+    #[serde(default = "default_max_tags_per_item")]
+    pub max_tags_per_item: u32,
+}
+
+impl Default for TaggingConfig {
+    fn default() -> Self {
+        Self {
+            api_url: None,
+            api_key: None,
+            model: None,
+            vocabulary: Vec::new(),
+            max_tags_per_item: default_max_tags_per_item(),
+        }
+    }
+}
+
+impl TaggingConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.api_url.is_some()
+    }
+}
+
+fn default_max_tags_per_item() -> u32 {
+    3
+}
+
+/// OpenAI-compatible chat completions endpoint that scores generated articles against a
+/// rubric, for channels with `self_eval` (see docs/specs/self-evaluation.md). Meant for a
+/// cheaper model than the one generating.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SelfEvalConfig {
+    /// Base URL; requests go to `{api_url}/chat/completions`. Self-evaluation is off unless set.
+    pub api_url: Option<String>,
+    pub api_key: Option<String>,
+    pub model: Option<String>,
+}
+
+impl SelfEvalConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.api_url.is_some()
+    }
+}
+
+/// OpenAI-compatible chat completions endpoint that translates generated articles into a
+/// channel's `translate` languages after they're stored (see docs/specs/translation.md).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TranslationConfig {
+    /// Base URL; requests go to `{api_url}/chat/completions`. Translation is off unless set.
+    pub api_url: Option<String>,
+    pub api_key: Option<String>,
+    pub model: Option<String>,
+}
+
+impl TranslationConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.api_url.is_some()
+    }
+}
+
+/// What a channel's `self_eval` does with an article: `score` only stores the scores;
+/// `annotate` also adds a note to articles below `self_eval_min_score`; `retry` first
+/// regenerates once and keeps the better article.
+pub const SELF_EVAL_ACTIONS: &[&str] = &["score", "annotate", "retry"];
+
+/// What a channel's `verify_links` does with dead links: `flag` only records them in the
+/// article metadata; `rewrite` also replaces each with its text and a footnote.
+pub const LINK_CHECK_ACTIONS: &[&str] = &["flag", "rewrite"];
+
+/// Canonical form of a tag: trimmed, lowercase, inner whitespace collapsed to `-`.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.split_whitespace().collect::<Vec<_>>().join("-").to_lowercase()
+}
+
+/// Dead-source detection for polled sources (see docs/specs/source-health.md).
+#[derive(Debug, Clone, Deserialize)]
+pub struct SourceHealthConfig {
+    /// Consecutive failed fetches before a source is reported as failing.
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// A source whose newest item is older than this is reported as stale. Per-source
+    /// `stale_after` overrides it; `"off"` disables the check.
+    #[serde(default = "default_stale_after")]
+    pub stale_after: String,
+    /// Matrix room to post status changes to (requires `[matrix]`).
+    pub alert_matrix_room: Option<String>,
+    /// Rewrite a source's `url` in the config file when its feed moved permanently.
+    #[serde(default)]
+    pub rewrite_moved_urls: bool,
+}
+
+impl Default for SourceHealthConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: default_failure_threshold(),
+            stale_after: default_stale_after(),
+            alert_matrix_room: None,
+            rewrite_moved_urls: false,
+        }
+    }
+}
+
+fn default_failure_threshold() -> u32 {
+    5
+}
+
+fn default_stale_after() -> String {
+    "14d".to_string()
+}
+
+/// Alerts on failures that need attention (see docs/specs/notifications.md).
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationsConfig {
+    /// Telegram account that messages its own Saved Messages.
+    pub telegram_account: Option<String>,
+    /// Shoutrrr-style service URLs (`ntfy://`, `smtp://`, ...).
+    #[serde(default)]
+    pub urls: Vec<String>,
+    /// Events to notify about (default: all).
+    #[serde(default = "default_notification_events")]
+    pub events: Vec<String>,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            telegram_account: None,
+            urls: Vec::new(),
+            events: default_notification_events(),
+        }
+    }
+}
+
+fn default_notification_events() -> Vec<String> {
+    notify::Event::ALL.iter().map(|e| e.as_str().to_string()).collect()
+}
+
+/// Error reporting to Sentry (see docs/observability.md). Read once at startup.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ReportingConfig {
+    /// Sentry DSN. Overrides the `SENTRY_DSN` env var; `""` disables reporting.
+    pub sentry_dsn: Option<String>,
+    /// Sentry environment. Overrides `SENTRY_ENVIRONMENT` (default: "development").
+    pub environment: Option<String>,
+    /// Share of HTTP requests sent as performance transactions, 0.0 to 1.0.
+    #[serde(default = "default_traces_sample_rate")]
+    pub traces_sample_rate: f32,
+}
+
+impl Default for ReportingConfig {
+    fn default() -> Self {
+        Self {
+            sentry_dsn: None,
+            environment: None,
+            traces_sample_rate: default_traces_sample_rate(),
+        }
+    }
+}
+
+fn default_traces_sample_rate() -> f32 {
+    1.0
+}
+
+/// Parse a `stale_after` value: a humantime duration, or `"off"` (`None`).
+pub fn parse_stale_after(value: &str) -> Result<Option<std::time::Duration>, humantime::DurationError> {
+    if value == "off" {
+        Ok(None)
+    } else {
+        humantime::parse_duration(value).map(Some)
+    }
+}
+
+/// Check a proxy URL: one of `PROXY_SCHEMES` with a host, e.g. `socks5://127.0.0.1:1080`.
+fn validate_proxy_url(value: &str) -> Result<(), String> {
+    let url = reqwest::Url::parse(value).map_err(|e| format!("invalid URL '{value}': {e}"))?;
+    if !PROXY_SCHEMES.contains(&url.scheme()) {
+        return Err(format!(
+            "unsupported scheme '{}' (expected one of: {})",
+            url.scheme(),
+            PROXY_SCHEMES.join(", ")
+        ));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(format!("'{value}' has no host"));
+    }
+    Ok(())
 }
 
 fn default_opencode_binary() -> String {
@@ -132,55 +712,730 @@ pub struct SourceConfig {
     pub tg_id: Option<i64>,
     pub tg_username: Option<String>,
     pub tg_folder_name: Option<String>,
+    /// `[telegram.accounts]` entry to read this source with (default: the top-level account).
+    pub tg_account: Option<String>,
     #[serde(default = "default_enabled")]
     pub enabled: Option<bool>,
     pub description: Option<String>,
+    // Scrape-specific fields
+    pub selectors: Option<ScrapeSelectorsConfig>,
+    // IMAP-specific fields
+    pub imap: Option<ImapSourceConfig>,
+    // Read-later (wallabag, omnivore) fields
+    pub read_later: Option<ReadLaterSourceConfig>,
+    // Link aggregator (lemmy, lobsters) fields
+    pub aggregator: Option<AggregatorSourceConfig>,
+    // Calendar (ics) fields
+    pub ics: Option<IcsSourceConfig>,
+    // X/Twitter fields
+    pub twitter: Option<TwitterSourceConfig>,
+    // Slack fields
+    pub slack: Option<SlackSourceConfig>,
+    // arXiv fields
+    pub arxiv: Option<ArxivSourceConfig>,
+    // Webhook (push) fields
+    pub webhook: Option<WebhookSourceConfig>,
+    /// Overrides `[source_health].stale_after` for this source (e.g. `"60d"` for a monthly blog).
+    pub stale_after: Option<String>,
+    /// Only use this source's items detected in one of these languages (ISO 639-1 codes).
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// Telegram: only use posts with at least this many views (channels and folders).
+    pub min_views: Option<u32>,
+    /// Telegram: only use messages with at least this many reactions.
+    pub min_reactions: Option<u32>,
+    /// Proxy for this source's requests, overriding `[network].proxy`; `"direct"` for none.
+    pub proxy: Option<String>,
+    /// Feeds: what identifies an entry, one of `DEDUP_STRATEGIES` (default `"guid"`).
+    pub dedup: Option<String>,
+    /// HTTP sources: extra request headers (a browser `User-Agent`, a `Cookie`), sent on top
+    /// of pail's own and of `auth`.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// HTTP sources: request timeout, overriding the default 30s.
+    pub timeout: Option<String>,
+    /// Polled sources: command (program and arguments, no shell) each new item body is piped
+    /// through before storage; its stdout is the stored body.
+    #[serde(default)]
+    pub preprocess: Vec<String>,
+    /// Time limit of `preprocess` per item.
+    #[serde(default = "default_preprocess_timeout")]
+    pub preprocess_timeout: String,
+    /// What a failed `preprocess` does: one of `PREPROCESS_FAILURE_ACTIONS`.
+    #[serde(default = "default_preprocess_on_error")]
+    pub preprocess_on_error: String,
 }
 
-fn default_poll_interval() -> String {
-    "30m".to_string()
+/// Per-source `preprocess_on_error` values (see docs/specs/preprocessing.md).
+pub const PREPROCESS_FAILURE_ACTIONS: &[&str] = &["keep", "skip", "fail"];
+
+fn default_preprocess_timeout() -> String {
+    "30s".to_string()
 }
-fn default_max_items() -> u32 {
-    200
+
+fn default_preprocess_on_error() -> String {
+    "keep".to_string()
 }
-fn default_enabled() -> Option<bool> {
-    Some(true)
+
+/// Source types fetched with the shared feed client, where `headers` and `timeout` apply.
+pub const HTTP_FETCH_SOURCE_TYPES: &[&str] = &["rss", "podcast", "scrape", "microformats", "ics", "arxiv"];
+
+/// Per-source `dedup` strategies of feed sources (see docs/specs/rss-sources.md "Deduplication").
+pub const DEDUP_STRATEGIES: &[&str] = &["guid", "url", "url+title", "content_hash"];
+
+impl SourceConfig {
+    /// The proxy this source's requests go through: its own `proxy`, else `[network].proxy`.
+    /// `Some("direct")` means explicitly none.
+    pub fn effective_proxy<'a>(&'a self, network: &'a NetworkConfig) -> Option<&'a str> {
+        self.proxy.as_deref().or(network.proxy.as_deref())
+    }
 }
 
-#[derive(Debug, Clone, Deserialize)]
-pub struct SourceAuthConfig {
-    #[serde(rename = "type")]
-    pub auth_type: String,
-    pub username: Option<String>,
-    pub password: Option<String>,
-    pub token: Option<String>,
-    pub header_name: Option<String>,
-    pub header_value: Option<String>,
+/// CSS selectors for a `scrape` source. `item` selects each entry on the page; the
+/// other selectors are evaluated inside each matched item. Stored as JSON in the DB.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ScrapeSelectorsConfig {
+    pub item: String,
+    pub title: Option<String>,
+    pub link: Option<String>,
+    pub date: Option<String>,
+    pub body: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
-pub struct OutputChannelConfig {
-    pub name: String,
-    pub slug: String,
-    pub schedule: Option<String>,
-    pub sources: Vec<String>,
-    pub prompt: String,
-    pub model: Option<String>,
-    pub language: Option<String>,
+/// Mailbox settings for an `imap` source. Stored as JSON in the DB.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ImapSourceConfig {
+    pub host: String,
+    #[serde(default = "default_imap_port")]
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    #[serde(default = "default_imap_folder")]
+    pub folder: String,
+    /// Only ingest messages whose From header contains one of these (case-insensitive).
     #[serde(default)]
-    pub mark_tg_read: Option<bool>,
-    #[serde(default = "default_channel_enabled")]
-    pub enabled: Option<bool>,
-    pub strategy: Option<String>,
+    pub from: Vec<String>,
+    /// Only ingest messages whose Subject contains one of these (case-insensitive).
+    #[serde(default)]
+    pub subject: Vec<String>,
 }
 
-fn default_channel_enabled() -> Option<bool> {
-    Some(true)
+/// Settings for a read-later source (`wallabag`, `omnivore`). Credentials are in
+/// `[source.auth]`; this holds the rest. Stored as JSON in the DB.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ReadLaterSourceConfig {
+    /// wallabag API client (created under "API clients management").
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    /// Also ingest archived (read) articles; default is unread only.
+    #[serde(default)]
+    pub include_archived: bool,
+    /// Only ingest articles carrying all of these tags (Omnivore: labels).
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
-impl Config {
-    /// Resolve the database path (relative to data_dir if not absolute).
-    pub fn db_path(&self) -> PathBuf {
+/// Settings for a link aggregator source (`lemmy`, `lobsters`). Stored as JSON in the DB.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AggregatorSourceConfig {
+    /// Lemmy community, `name` (local to the instance) or `name@instance`.
+    pub community: Option<String>,
+    /// Lemmy listing sort (default `Hot`).
+    pub sort: Option<String>,
+    /// Lobsters tags; empty means the front page (`hottest`).
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Skip posts scoring below this.
+    pub min_score: Option<i64>,
+    /// Append the top comments of each new post to its body.
+    #[serde(default)]
+    pub include_comments: bool,
+    #[serde(default = "default_max_comments")]
+    pub max_comments: u32,
+}
+
+/// Lemmy `SortType` values accepted for `aggregator.sort`.
+pub const LEMMY_SORTS: &[&str] = &[
+    "Active",
+    "Hot",
+    "New",
+    "Old",
+    "Scaled",
+    "Controversial",
+    "MostComments",
+    "NewComments",
+    "TopHour",
+    "TopSixHour",
+    "TopTwelveHour",
+    "TopDay",
+    "TopWeek",
+    "TopMonth",
+    "TopYear",
+    "TopAll",
+];
+
+fn default_max_comments() -> u32 {
+    5
+}
+
+/// Settings for an `ics` calendar source. Stored as JSON in the DB.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IcsSourceConfig {
+    /// Pick up events starting within this long from now.
+    #[serde(default = "default_ics_lookahead")]
+    pub lookahead: String,
+    /// Zone for floating event times and for display (default: `[pail].timezone`).
+    pub timezone: Option<String>,
+}
+
+impl Default for IcsSourceConfig {
+    fn default() -> Self {
+        Self {
+            lookahead: default_ics_lookahead(),
+            timezone: None,
+        }
+    }
+}
+
+fn default_ics_lookahead() -> String {
+    "7d".to_string()
+}
+
+/// Settings for a `twitter` source. The account is read through a Nitter instance's RSS
+/// (`backend = "nitter"`, `url` is the instance) or the X API v2 (`backend = "api"`, bearer
+/// token in `[source.auth]`). Stored as JSON in the DB.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TwitterSourceConfig {
+    /// Account handle, without the `@`.
+    pub username: String,
+    /// `nitter` (default) or `api`.
+    #[serde(default = "default_twitter_backend")]
+    pub backend: String,
+    /// Also ingest the account's replies to other accounts (self-replies are always kept as threads).
+    #[serde(default)]
+    pub include_replies: bool,
+    #[serde(default)]
+    pub include_retweets: bool,
+}
+
+fn default_twitter_backend() -> String {
+    "nitter".to_string()
+}
+
+/// Accepted values for `twitter.backend`.
+pub const TWITTER_BACKENDS: &[&str] = &["nitter", "api"];
+
+/// Settings for a `slack` source: channels of the workspace the `[source.auth]` bearer token
+/// (bot or user) belongs to. Stored as JSON in the DB.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SlackSourceConfig {
+    /// Channel names (with or without `#`) or IDs (`C0123ABCD`).
+    pub channels: Vec<String>,
+    /// Append thread replies to the item of the message that started the thread.
+    #[serde(default = "default_slack_include_threads")]
+    pub include_threads: bool,
+}
+
+fn default_slack_include_threads() -> bool {
+    true
+}
+
+/// Settings for an `arxiv` source: new papers in some categories, optionally narrowed by
+/// keywords. Stored as JSON in the DB.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ArxivSourceConfig {
+    /// arXiv categories (`cs.CL`, `cs.CR`, `math.PR`); a paper in any of them matches.
+    pub categories: Vec<String>,
+    /// Keep only papers whose title or abstract contains one of these (case-insensitive).
+    /// Empty keeps every paper in the categories.
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Fetch the HTML full text of new papers and append it to the abstract.
+    #[serde(default)]
+    pub full_text: bool,
+    /// With `full_text`, only fetch it for papers whose title or abstract contains one of
+    /// these. Empty fetches it for every new paper.
+    #[serde(default)]
+    pub full_text_keywords: Vec<String>,
+}
+
+/// Settings for a `webhook` source: items are pushed to `POST /ingest/<slug>` instead of being
+/// fetched. Only read from the config, never stored in the DB.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct WebhookSourceConfig {
+    /// URL path segment: lowercase letters, digits and `-`, unique among webhook sources.
+    pub slug: String,
+    /// Bearer token the pushing side must send.
+    pub token: String,
+}
+
+/// Shortest accepted secret token (`webhook.token`, `[pail].admin_token`).
+pub const MIN_TOKEN_LEN: usize = 16;
+
+fn default_imap_port() -> u16 {
+    993
+}
+fn default_imap_folder() -> String {
+    "INBOX".to_string()
+}
+
+fn default_poll_interval() -> String {
+    "30m".to_string()
+}
+fn default_max_items() -> u32 {
+    200
+}
+fn default_enabled() -> Option<bool> {
+    Some(true)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SourceAuthConfig {
+    #[serde(rename = "type")]
+    pub auth_type: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub token: Option<String>,
+    pub header_name: Option<String>,
+    pub header_value: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OutputChannelConfig {
+    pub name: String,
+    pub slug: String,
+    /// Name of a `[channel_template.<name>]` whose values fill in the unset fields below.
+    pub template: Option<String>,
+    pub schedule: Option<String>,
+    pub sources: Vec<String>,
+    #[serde(default)]
+    pub prompt: String,
+    /// Editorial directive template file (minijinja). Overrides `[pail].prompt_template`.
+    pub prompt_template: Option<PathBuf>,
+    /// Name of a `[persona.<name>]` whose style is added to the prompt.
+    pub persona: Option<String>,
+    pub model: Option<String>,
+    /// Fallback chain instead of `model`: each generation attempt moves on to the next model.
+    #[serde(default)]
+    pub models: Vec<String>,
+    pub language: Option<String>,
+    #[serde(default)]
+    pub mark_tg_read: Option<bool>,
+    /// Only mark the chats of these sources (names from the channel's `sources`) as read.
+    /// Empty: every Telegram source of the channel.
+    #[serde(default)]
+    pub mark_tg_read_sources: Vec<String>,
+    /// Log what mark-as-read would mark instead of marking it.
+    #[serde(default)]
+    pub mark_tg_read_dry_run: bool,
+    #[serde(default = "default_channel_enabled")]
+    pub enabled: Option<bool>,
+    pub strategy: Option<String>,
+    /// Matrix room ID (`!abc:example.org`) to post each generated article to.
+    pub matrix_room: Option<String>,
+    /// Send Webmentions to the pages each published article links to, and mark its page up
+    /// with `u-url` / `u-syndication` links. Requires `[pail].public_url`.
+    #[serde(default)]
+    pub webmention: bool,
+    /// Text describing what this channel cares about. Enables embedding relevance filtering.
+    pub interest_profile: Option<String>,
+    #[serde(default = "default_relevance_threshold")]
+    pub relevance_threshold: f32,
+    /// What to do with items below the threshold: "exclude" or "demote".
+    #[serde(default = "default_relevance_action")]
+    pub relevance_action: String,
+    /// Hold generated articles as drafts until approved (`/drafts`, `pail drafts`).
+    #[serde(default)]
+    pub require_approval: bool,
+    /// Required `##` sections of every article, in order.
+    #[serde(default)]
+    pub sections: Vec<String>,
+    /// Reading mode of the articles (`OUTPUT_FORMATS`): replaces the strategy's body layout in
+    /// the prompt and is checked once the output is parsed. Unset: the strategy's own layout.
+    pub format: Option<String>,
+    /// Keep only items tagged with at least one of these (requires `[tagging]`).
+    #[serde(default)]
+    pub include_tags: Vec<String>,
+    /// Drop items tagged with any of these (requires `[tagging]`).
+    #[serde(default)]
+    pub exclude_tags: Vec<String>,
+    /// Give each generation the channel's previous article and ask for a
+    /// "What changed since the last digest" section.
+    #[serde(default)]
+    pub changelog: bool,
+    /// Per-run budget; overrides `[opencode].max_cost_usd` / `max_tokens`.
+    pub max_cost_usd: Option<f64>,
+    pub max_tokens: Option<u64>,
+    /// Estimated tokens the workspace's source files may take; the longest item bodies are
+    /// truncated to fit.
+    pub input_token_budget: Option<u64>,
+    /// Write one workspace file per item (`sources/<slug>/NNN.md`, with YAML frontmatter)
+    /// instead of one file per source.
+    #[serde(default)]
+    pub item_files: bool,
+    /// Fewest items in the window worth an article; below it `min_items_action` applies.
+    pub min_items: Option<u32>,
+    /// What a run with fewer than `min_items` items does (`MIN_ITEMS_ACTIONS`).
+    #[serde(default = "default_min_items_action")]
+    pub min_items_action: String,
+    /// Only use items detected in one of these languages (ISO 639-1 codes).
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// Window a scheduled run covers: `since_last` (since `last_generated`), `previous_week`
+    /// or `previous_month` (the last full calendar week / month in `[pail].timezone`).
+    #[serde(default = "default_channel_window")]
+    pub window: String,
+    /// Title and topic rules applied to every generated article.
+    #[serde(default)]
+    pub postprocess: PostprocessConfig,
+    /// Identifiers replaced in every article before it's stored or served.
+    #[serde(default)]
+    pub redact: RedactConfig,
+    /// Order among generations waiting for a slot (`[pail].max_concurrent_generations`):
+    /// higher runs first, ties in the order they queued. Default 0.
+    #[serde(default)]
+    pub priority: i32,
+    /// opencode timeout for this channel's runs; overrides the strategy's `timeout`.
+    pub timeout: Option<String>,
+    /// Score each article with `[self_eval]` and act on low scores (`SELF_EVAL_ACTIONS`).
+    pub self_eval: Option<String>,
+    /// Overall score (0.0–1.0) below which `annotate` / `retry` apply. Default 0.7.
+    #[serde(default = "default_self_eval_min_score")]
+    pub self_eval_min_score: f64,
+    /// Check the article's links before storing it (`LINK_CHECK_ACTIONS`).
+    pub verify_links: Option<String>,
+    /// Translate each article into these languages (ISO 639-1 codes) with `[translation]`,
+    /// served with `?lang=` next to the original.
+    #[serde(default)]
+    pub translate: Vec<String>,
+    /// Short update articles between scheduled digests when a trigger source posts.
+    pub breaking: Option<BreakingConfig>,
+}
+
+/// Model used when neither the channel nor `[opencode].default_model` names one.
+pub const BUILTIN_MODEL: &str = "opencode/big-pickle";
+
+impl OutputChannelConfig {
+    /// Models to generate with, in fallback order: `models`, else `model`, else
+    /// `[opencode].default_model`, else `BUILTIN_MODEL`. Never empty.
+    pub fn model_chain(&self, opencode: &OpencodeConfig) -> Vec<String> {
+        if !self.models.is_empty() {
+            return self.models.clone();
+        }
+        let model = self
+            .model
+            .as_deref()
+            .or(opencode.default_model.as_deref())
+            .unwrap_or(BUILTIN_MODEL);
+        vec![model.to_string()]
+    }
+}
+
+/// `[channel_template.<name>]`: defaults for channels with `template = "<name>"`, applied when
+/// the config is loaded. A value set on the channel wins.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ChannelTemplateConfig {
+    pub schedule: Option<String>,
+    pub model: Option<String>,
+    #[serde(default)]
+    pub models: Vec<String>,
+    pub persona: Option<String>,
+    pub strategy: Option<String>,
+    pub language: Option<String>,
+    pub timeout: Option<String>,
+    pub prompt_template: Option<PathBuf>,
+    pub format: Option<String>,
+}
+
+/// `[persona.<name>]`: voice and style instructions shared by channels. They're composed into
+/// the prompt after the editorial directive, under their own heading.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PersonaConfig {
+    /// What the persona is for; not sent to the model.
+    #[allow(dead_code)]
+    pub description: Option<String>,
+    pub style: String,
+}
+
+/// `[output_channel.breaking]`: an update article is generated when trigger sources post
+/// between scheduled digests (see docs/specs/breaking-updates.md).
+#[derive(Debug, Clone, Deserialize)]
+pub struct BreakingConfig {
+    /// Sources (names from the channel's `sources`) whose new items trigger an update.
+    pub sources: Vec<String>,
+    /// New trigger items needed to fire an update. Default 1.
+    #[serde(default = "default_breaking_min_items")]
+    pub min_items: u32,
+    /// Minimum time between two updates of the channel. Default 1h.
+    #[serde(default = "default_breaking_cooldown")]
+    pub cooldown: String,
+    /// opencode timeout of an update run. Default 10m.
+    #[serde(default = "default_breaking_timeout")]
+    pub timeout: String,
+    /// Title prefix of update articles, replacing `postprocess.title_prefix`. Supports
+    /// `{date}` and `{channel}`. Default "Update: ".
+    #[serde(default = "default_breaking_title_prefix")]
+    pub title_prefix: String,
+}
+
+fn default_breaking_min_items() -> u32 {
+    1
+}
+
+fn default_breaking_cooldown() -> String {
+    "1h".to_string()
+}
+
+fn default_breaking_timeout() -> String {
+    "10m".to_string()
+}
+
+fn default_breaking_title_prefix() -> String {
+    "Update: ".to_string()
+}
+
+/// `[output_channel.postprocess]`: rules applied to the model's title and topics before the
+/// article is stored (see docs/specs/generation-engine.md "Post-processing").
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PostprocessConfig {
+    /// Prepended to the title unless it already starts with it. Supports `{date}`, `{channel}`.
+    pub title_prefix: Option<String>,
+    /// Appended to the title unless it already ends with it. Same placeholders.
+    pub title_suffix: Option<String>,
+    /// Topic spellings mapped to the one to use, matched case-insensitively.
+    #[serde(default)]
+    pub topic_aliases: BTreeMap<String, String>,
+    /// Keep at most this many topics (the model's first ones).
+    pub max_topics: Option<usize>,
+}
+
+/// `[output_channel.redact]`: text replaced in the channel's articles (title, topics, summary,
+/// body) before they're stored or served (see docs/specs/redaction.md).
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedactConfig {
+    /// Text replaced wherever it appears, case-insensitively.
+    #[serde(default)]
+    pub literals: Vec<String>,
+    /// Regular expressions (`regex` crate syntax) replaced wherever they match.
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// What each match is replaced with. Default "[redacted]".
+    #[serde(default = "default_redact_replacement")]
+    pub replacement: String,
+}
+
+impl Default for RedactConfig {
+    fn default() -> Self {
+        Self {
+            literals: Vec::new(),
+            patterns: Vec::new(),
+            replacement: default_redact_replacement(),
+        }
+    }
+}
+
+impl RedactConfig {
+    pub fn is_empty(&self) -> bool {
+        self.literals.is_empty() && self.patterns.is_empty()
+    }
+}
+
+fn default_redact_replacement() -> String {
+    "[redacted]".to_string()
+}
+
+/// Placeholders accepted in `title_prefix` / `title_suffix`.
+pub const TITLE_PLACEHOLDERS: &[&str] = &["date", "channel"];
+
+/// The first `{placeholder}` of a title template that isn't one of `TITLE_PLACEHOLDERS`.
+fn unknown_title_placeholder(value: &str) -> Option<&str> {
+    value
+        .split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}'))
+        .map(|(p, _)| p)
+        .find(|p| !TITLE_PLACEHOLDERS.contains(p))
+}
+
+fn validate_postprocess(channel: &str, rules: &PostprocessConfig) -> Result<()> {
+    for (key, value) in [
+        ("title_prefix", &rules.title_prefix),
+        ("title_suffix", &rules.title_suffix),
+    ] {
+        let Some(value) = value else { continue };
+        if let Some(placeholder) = unknown_title_placeholder(value) {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{channel}': unknown placeholder {{{placeholder}}} in postprocess.{key} (expected one of: {})",
+                TITLE_PLACEHOLDERS.iter().map(|p| format!("{{{p}}}")).collect::<Vec<_>>().join(", ")
+            ))
+            .into());
+        }
+    }
+    if let Some((from, _)) = rules
+        .topic_aliases
+        .iter()
+        .find(|(from, to)| from.trim().is_empty() || to.trim().is_empty())
+    {
+        return Err(ConfigError::Validation(format!(
+            "output channel '{channel}': postprocess.topic_aliases entry '{from}' has an empty topic"
+        ))
+        .into());
+    }
+    if rules.max_topics == Some(0) {
+        return Err(ConfigError::Validation(format!(
+            "output channel '{channel}': postprocess.max_topics must be at least 1"
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+fn validate_redact(channel: &str, rules: &RedactConfig) -> Result<()> {
+    if rules.literals.iter().any(|literal| literal.trim().is_empty()) {
+        return Err(ConfigError::Validation(format!(
+            "output channel '{channel}': redact.literals must not contain empty strings"
+        ))
+        .into());
+    }
+    for pattern in &rules.patterns {
+        match regex::Regex::new(pattern) {
+            Ok(re) if re.is_match("") => {
+                return Err(ConfigError::Validation(format!(
+                    "output channel '{channel}': redact pattern '{pattern}' matches empty text"
+                ))
+                .into());
+            }
+            Ok(_) => {}
+            Err(e) => {
+                return Err(ConfigError::Validation(format!(
+                    "output channel '{channel}': invalid redact pattern '{pattern}': {e}"
+                ))
+                .into());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_mark_tg_read(config: &Config, channel: &OutputChannelConfig) -> Result<()> {
+    let name = &channel.name;
+    if channel.mark_tg_read != Some(true) {
+        if !channel.mark_tg_read_sources.is_empty() || channel.mark_tg_read_dry_run {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{name}': mark_tg_read_sources and mark_tg_read_dry_run require mark_tg_read = true"
+            ))
+            .into());
+        }
+        return Ok(());
+    }
+    for source_name in &channel.mark_tg_read_sources {
+        if !channel.sources.contains(source_name) {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{name}': mark_tg_read source '{source_name}' is not one of the channel's sources"
+            ))
+            .into());
+        }
+        let is_mtproto = config
+            .source
+            .iter()
+            .find(|s| &s.name == source_name)
+            .is_some_and(|s| is_mtproto_source(&s.source_type));
+        if !is_mtproto {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{name}': mark_tg_read source '{source_name}' must be a telegram_channel, telegram_group or telegram_folder source"
+            ))
+            .into());
+        }
+    }
+    Ok(())
+}
+
+fn validate_breaking(channel: &OutputChannelConfig, breaking: &BreakingConfig) -> Result<()> {
+    let name = &channel.name;
+    if breaking.sources.is_empty() {
+        return Err(ConfigError::Validation(format!(
+            "output channel '{name}': breaking.sources must list at least one source"
+        ))
+        .into());
+    }
+    if let Some(source) = breaking.sources.iter().find(|s| !channel.sources.contains(s)) {
+        return Err(ConfigError::Validation(format!(
+            "output channel '{name}': breaking source '{source}' is not one of the channel's sources"
+        ))
+        .into());
+    }
+    if breaking.min_items == 0 {
+        return Err(ConfigError::Validation(format!(
+            "output channel '{name}': breaking.min_items must be at least 1"
+        ))
+        .into());
+    }
+    for (key, value) in [("cooldown", &breaking.cooldown), ("timeout", &breaking.timeout)] {
+        match humantime::parse_duration(value) {
+            Ok(d) if !d.is_zero() => {}
+            Ok(_) => {
+                return Err(ConfigError::Validation(format!(
+                    "output channel '{name}': breaking.{key} must be greater than 0"
+                ))
+                .into());
+            }
+            Err(e) => {
+                return Err(
+                    ConfigError::Validation(format!("output channel '{name}': breaking.{key} '{value}': {e}")).into(),
+                );
+            }
+        }
+    }
+    if let Some(placeholder) = unknown_title_placeholder(&breaking.title_prefix) {
+        return Err(ConfigError::Validation(format!(
+            "output channel '{name}': unknown placeholder {{{placeholder}}} in breaking.title_prefix (expected one of: {})",
+            TITLE_PLACEHOLDERS.iter().map(|p| format!("{{{p}}}")).collect::<Vec<_>>().join(", ")
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Accepted values for an output channel's `window`.
+pub const CHANNEL_WINDOWS: &[&str] = &["since_last", "previous_week", "previous_month"];
+
+fn default_channel_window() -> String {
+    "since_last".to_string()
+}
+
+/// What a run below a channel's `min_items` does: `skip` generates nothing (a `since_last`
+/// window carries its items over to the next run), `stub` stores a short "quiet period"
+/// article listing the items, without the model.
+pub const MIN_ITEMS_ACTIONS: &[&str] = &["skip", "stub"];
+
+/// Channel `format` values: `narrative` prose sections, `bulleted` one bullet per story,
+/// `briefing` a TL;DR of at most 5 bullets, then short sections (see
+/// docs/specs/generation-engine.md "Output Formats").
+pub const OUTPUT_FORMATS: &[&str] = &["narrative", "bulleted", "briefing"];
+
+fn default_min_items_action() -> String {
+    "skip".to_string()
+}
+
+fn default_relevance_threshold() -> f32 {
+    0.3
+}
+
+fn default_self_eval_min_score() -> f64 {
+    0.7
+}
+
+fn default_relevance_action() -> String {
+    "exclude".to_string()
+}
+
+fn default_channel_enabled() -> Option<bool> {
+    Some(true)
+}
+
+impl Config {
+    /// Resolve the database path (relative to data_dir if not absolute).
+    pub fn db_path(&self) -> PathBuf {
         let db_path = Path::new(&self.database.path);
         if db_path.is_absolute() {
             db_path.to_path_buf()
@@ -188,6 +1443,46 @@ impl Config {
             self.pail.data_dir.join(db_path)
         }
     }
+
+    /// Telegram accounts read by at least one enabled TG source. The daemon runs one
+    /// listener per account in this set.
+    pub fn tg_accounts_in_use(&self) -> BTreeSet<String> {
+        self.source
+            .iter()
+            .filter(|s| is_mtproto_source(&s.source_type) && s.enabled.unwrap_or(true))
+            .map(|s| s.tg_account.clone().unwrap_or_else(|| DEFAULT_TG_ACCOUNT.to_string()))
+            .collect()
+    }
+
+    /// Resolve the control socket endpoint, or `None` if it's disabled.
+    pub fn control_endpoint(&self) -> Option<ControlEndpoint> {
+        match self.pail.control_socket.as_deref() {
+            Some("") => None,
+            Some(value) => match value.strip_prefix("tcp:") {
+                Some(addr) => addr.parse().ok().map(ControlEndpoint::Tcp),
+                None if Path::new(value).is_absolute() => Some(ControlEndpoint::Unix(PathBuf::from(value))),
+                None => Some(ControlEndpoint::Unix(self.pail.data_dir.join(value))),
+            },
+            None if cfg!(unix) => Some(ControlEndpoint::Unix(self.pail.data_dir.join("pail.sock"))),
+            None => None,
+        }
+    }
+}
+
+/// Where the daemon listens for `pail ctl` commands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlEndpoint {
+    Unix(PathBuf),
+    Tcp(std::net::SocketAddr),
+}
+
+impl std::fmt::Display for ControlEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ControlEndpoint::Unix(path) => write!(f, "{}", path.display()),
+            ControlEndpoint::Tcp(addr) => write!(f, "tcp:{addr}"),
+        }
+    }
 }
 
 pub fn load_config(path: &Path) -> Result<Config> {
@@ -200,10 +1495,40 @@ pub fn load_config(path: &Path) -> Result<Config> {
     if let Ok(dir) = std::env::var("PAIL_DATA_DIR") {
         config.pail.data_dir = PathBuf::from(dir);
     }
+    apply_channel_templates(&mut config);
 
     Ok(config)
 }
 
+/// Fill each channel's unset fields from its `[channel_template]`. Unknown templates are left
+/// for `validate_config` to report.
+fn apply_channel_templates(config: &mut Config) {
+    for channel in &mut config.output_channel {
+        let Some(template) = channel
+            .template
+            .as_ref()
+            .and_then(|name| config.channel_template.get(name))
+        else {
+            continue;
+        };
+        channel.schedule = channel.schedule.take().or_else(|| template.schedule.clone());
+        // `model` and `models` are one setting: a channel with either keeps its own
+        if channel.model.is_none() && channel.models.is_empty() {
+            channel.model = template.model.clone();
+            channel.models = template.models.clone();
+        }
+        channel.persona = channel.persona.take().or_else(|| template.persona.clone());
+        channel.strategy = channel.strategy.take().or_else(|| template.strategy.clone());
+        channel.language = channel.language.take().or_else(|| template.language.clone());
+        channel.timeout = channel.timeout.take().or_else(|| template.timeout.clone());
+        channel.format = channel.format.take().or_else(|| template.format.clone());
+        channel.prompt_template = channel
+            .prompt_template
+            .take()
+            .or_else(|| template.prompt_template.clone());
+    }
+}
+
 pub fn validate_config(config: &Config) -> Result<()> {
     // Validate config version
     if config.pail.version != 1 {
@@ -235,202 +1560,1466 @@ pub fn validate_config(config: &Config) -> Result<()> {
             ))
             .into());
         }
-        if let Some(ref desc) = source.description
-            && let Some(bad) = desc.chars().find(|c| c.is_control() || *c == '"' || *c == '\\')
-        {
+        if let Some(ref desc) = source.description
+            && let Some(bad) = desc.chars().find(|c| c.is_control() || *c == '"' || *c == '\\')
+        {
+            return Err(ConfigError::Validation(format!(
+                "source '{}': description contains disallowed character {:?} \
+                 (control characters, double quotes, and backslashes are not allowed)",
+                source.name, bad
+            ))
+            .into());
+        }
+    }
+
+    // Validate source types
+    for source in &config.source {
+        match source.source_type.as_str() {
+            "rss" => {
+                if source.url.is_none() {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': RSS source must have a 'url'",
+                        source.name
+                    ))
+                    .into());
+                }
+            }
+            "microformats" => {
+                if source.url.is_none() {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': microformats source must have a 'url'",
+                        source.name
+                    ))
+                    .into());
+                }
+            }
+            "scrape" => {
+                if source.url.is_none() {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': scrape source must have a 'url'",
+                        source.name
+                    ))
+                    .into());
+                }
+                let Some(ref selectors) = source.selectors else {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': scrape source must have a [source.selectors] table with at least 'item'",
+                        source.name
+                    ))
+                    .into());
+                };
+                let all = [
+                    Some(&selectors.item),
+                    selectors.title.as_ref(),
+                    selectors.link.as_ref(),
+                    selectors.date.as_ref(),
+                    selectors.body.as_ref(),
+                ];
+                for selector in all.into_iter().flatten() {
+                    scraper::Selector::parse(selector).map_err(|e| {
+                        ConfigError::Validation(format!(
+                            "source '{}': invalid CSS selector '{}': {}",
+                            source.name, selector, e
+                        ))
+                    })?;
+                }
+            }
+            "imap" => {
+                let Some(ref imap) = source.imap else {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': imap source must have a [source.imap] table",
+                        source.name
+                    ))
+                    .into());
+                };
+                for (field, value) in [
+                    ("host", &imap.host),
+                    ("username", &imap.username),
+                    ("password", &imap.password),
+                    ("folder", &imap.folder),
+                ] {
+                    if value.trim().is_empty() {
+                        return Err(ConfigError::Validation(format!(
+                            "source '{}': imap.{field} must not be empty",
+                            source.name
+                        ))
+                        .into());
+                    }
+                }
+                if let Some(bad) = imap
+                    .from
+                    .iter()
+                    .chain(&imap.subject)
+                    .find(|f| f.trim().is_empty() || f.chars().any(|c| c.is_control()))
+                {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': invalid imap filter {:?} (must be non-empty, no control characters)",
+                        source.name, bad
+                    ))
+                    .into());
+                }
+            }
+            "wallabag" | "omnivore" => {
+                if source.url.is_none() {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': {} source must have a 'url' (the instance base URL)",
+                        source.name, source.source_type
+                    ))
+                    .into());
+                }
+                let auth_type = source.auth.as_ref().map(|a| a.auth_type.as_str());
+                let settings = source.read_later.clone().unwrap_or_default();
+                if source.source_type == "wallabag" {
+                    if auth_type != Some("basic") {
+                        return Err(ConfigError::Validation(format!(
+                            "source '{}': wallabag source requires [source.auth] type = \"basic\" with the account's username and password",
+                            source.name
+                        ))
+                        .into());
+                    }
+                    if settings.client_id.as_deref().is_none_or(|s| s.trim().is_empty())
+                        || settings.client_secret.as_deref().is_none_or(|s| s.trim().is_empty())
+                    {
+                        return Err(ConfigError::Validation(format!(
+                            "source '{}': wallabag source requires read_later.client_id and read_later.client_secret",
+                            source.name
+                        ))
+                        .into());
+                    }
+                } else if auth_type != Some("bearer") {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': omnivore source requires [source.auth] type = \"bearer\" with the API key as 'token'",
+                        source.name
+                    ))
+                    .into());
+                }
+                if let Some(bad) = settings.tags.iter().find(|t| {
+                    t.trim().is_empty() || t.contains(',') || t.contains('"') || t.chars().any(|c| c.is_control())
+                }) {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': invalid read_later tag {:?} (must be non-empty, no commas, quotes, or control characters)",
+                        source.name, bad
+                    ))
+                    .into());
+                }
+            }
+            "lemmy" | "lobsters" => {
+                let settings = source.aggregator.clone().unwrap_or_default();
+                if source.source_type == "lemmy" {
+                    if source.url.is_none() {
+                        return Err(ConfigError::Validation(format!(
+                            "source '{}': lemmy source must have a 'url' (the instance base URL)",
+                            source.name
+                        ))
+                        .into());
+                    }
+                    if settings.community.as_deref().is_none_or(|c| c.trim().is_empty()) {
+                        return Err(ConfigError::Validation(format!(
+                            "source '{}': lemmy source requires aggregator.community",
+                            source.name
+                        ))
+                        .into());
+                    }
+                    if let Some(ref sort) = settings.sort
+                        && !LEMMY_SORTS.contains(&sort.as_str())
+                    {
+                        return Err(ConfigError::Validation(format!(
+                            "source '{}': invalid aggregator.sort '{}' (expected one of: {})",
+                            source.name,
+                            sort,
+                            LEMMY_SORTS.join(", ")
+                        ))
+                        .into());
+                    }
+                } else if let Some(bad) = settings
+                    .tags
+                    .iter()
+                    .find(|t| t.is_empty() || !t.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+                {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': invalid lobsters tag {:?} (letters, digits, '-' and '_' only)",
+                        source.name, bad
+                    ))
+                    .into());
+                }
+                if !(1..=50).contains(&settings.max_comments) {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': aggregator.max_comments must be between 1 and 50",
+                        source.name
+                    ))
+                    .into());
+                }
+            }
+            "ics" => {
+                if source.url.is_none() {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': ics source must have a 'url'",
+                        source.name
+                    ))
+                    .into());
+                }
+                let settings = source.ics.clone().unwrap_or_default();
+                match humantime::parse_duration(&settings.lookahead) {
+                    Ok(d) if d > std::time::Duration::ZERO && d <= std::time::Duration::from_secs(366 * 86400) => {}
+                    _ => {
+                        return Err(ConfigError::Validation(format!(
+                            "source '{}': ics.lookahead must be a duration between 1s and 366d, got '{}'",
+                            source.name, settings.lookahead
+                        ))
+                        .into());
+                    }
+                }
+                if let Some(ref tz) = settings.timezone
+                    && tz.parse::<chrono_tz::Tz>().is_err()
+                {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': invalid ics.timezone '{tz}'",
+                        source.name
+                    ))
+                    .into());
+                }
+            }
+            "twitter" => {
+                let Some(ref settings) = source.twitter else {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': twitter source must have a [source.twitter] table with 'username'",
+                        source.name
+                    ))
+                    .into());
+                };
+                let username = settings.username.trim_start_matches('@');
+                if username.is_empty()
+                    || username.len() > 15
+                    || !username.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': invalid twitter.username '{}' (up to 15 letters, digits or '_')",
+                        source.name, settings.username
+                    ))
+                    .into());
+                }
+                match settings.backend.as_str() {
+                    "nitter" => {
+                        if source.url.is_none() {
+                            return Err(ConfigError::Validation(format!(
+                                "source '{}': twitter source with backend \"nitter\" must have a 'url' (the Nitter instance)",
+                                source.name
+                            ))
+                            .into());
+                        }
+                    }
+                    "api" => {
+                        if source.auth.as_ref().map(|a| a.auth_type.as_str()) != Some("bearer") {
+                            return Err(ConfigError::Validation(format!(
+                                "source '{}': twitter source with backend \"api\" requires [source.auth] type = \"bearer\" with the API bearer token",
+                                source.name
+                            ))
+                            .into());
+                        }
+                    }
+                    other => {
+                        return Err(ConfigError::Validation(format!(
+                            "source '{}': invalid twitter.backend '{other}' (expected one of: {})",
+                            source.name,
+                            TWITTER_BACKENDS.join(", ")
+                        ))
+                        .into());
+                    }
+                }
+            }
+            "slack" => {
+                let Some(ref settings) = source.slack else {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': slack source must have a [source.slack] table with 'channels'",
+                        source.name
+                    ))
+                    .into());
+                };
+                if settings.channels.is_empty()
+                    || settings
+                        .channels
+                        .iter()
+                        .any(|c| c.trim_start_matches('#').trim().is_empty())
+                {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': slack.channels must list at least one channel name or ID",
+                        source.name
+                    ))
+                    .into());
+                }
+                if source.auth.as_ref().map(|a| a.auth_type.as_str()) != Some("bearer") {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': slack source requires [source.auth] type = \"bearer\" with a bot or user token",
+                        source.name
+                    ))
+                    .into());
+                }
+            }
+            "arxiv" => {
+                let Some(ref settings) = source.arxiv else {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': arxiv source must have a [source.arxiv] table with 'categories'",
+                        source.name
+                    ))
+                    .into());
+                };
+                if settings.categories.is_empty() {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': arxiv.categories must list at least one category (e.g. \"cs.CL\")",
+                        source.name
+                    ))
+                    .into());
+                }
+                if let Some(bad) = settings.categories.iter().find(|c| !is_arxiv_category(c)) {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': invalid arxiv category '{bad}' (expected an archive and subject class, e.g. \"cs.CL\")",
+                        source.name
+                    ))
+                    .into());
+                }
+                if let Some(bad) = settings
+                    .keywords
+                    .iter()
+                    .chain(&settings.full_text_keywords)
+                    .find(|k| k.trim().is_empty() || k.contains('"'))
+                {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': invalid arxiv keyword '{bad}' (must be non-empty and without '\"')",
+                        source.name
+                    ))
+                    .into());
+                }
+                if !settings.full_text_keywords.is_empty() && !settings.full_text {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': arxiv.full_text_keywords requires arxiv.full_text = true",
+                        source.name
+                    ))
+                    .into());
+                }
+            }
+            "pail_stats" => {
+                if source.url.is_some() || source.auth.is_some() {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': pail_stats source reads pail's own database and takes no 'url' or 'auth'",
+                        source.name
+                    ))
+                    .into());
+                }
+            }
+            "podcast" => {
+                if source.url.is_none() {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': podcast source must have a 'url'",
+                        source.name
+                    ))
+                    .into());
+                }
+                if config.transcription.command.as_deref().unwrap_or("").trim().is_empty() {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': podcast sources require [transcription].command",
+                        source.name
+                    ))
+                    .into());
+                }
+            }
+            "webhook" => {
+                let Some(ref settings) = source.webhook else {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': webhook source must have a [source.webhook] table with 'slug' and 'token'",
+                        source.name
+                    ))
+                    .into());
+                };
+                if settings.slug.is_empty()
+                    || !settings
+                        .slug
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+                {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': invalid webhook.slug '{}' (lowercase letters, digits and '-')",
+                        source.name, settings.slug
+                    ))
+                    .into());
+                }
+                if settings.token.len() < MIN_TOKEN_LEN {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': webhook.token must be at least {MIN_TOKEN_LEN} characters",
+                        source.name
+                    ))
+                    .into());
+                }
+                let duplicate = config.source.iter().any(|other| {
+                    other.name != source.name
+                        && other.webhook.as_ref().is_some_and(|w| w.slug == settings.slug)
+                        && other.source_type == "webhook"
+                });
+                if duplicate {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': webhook.slug '{}' is used by another webhook source",
+                        source.name, settings.slug
+                    ))
+                    .into());
+                }
+            }
+            "telegram_channel" | "telegram_group" => {
+                if source.tg_username.is_none() && source.tg_id.is_none() {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': {} source must have 'tg_username' or 'tg_id'",
+                        source.name, source.source_type
+                    ))
+                    .into());
+                }
+            }
+            "telegram_bot" => {
+                if source.tg_username.is_none() && source.tg_id.is_none() {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': telegram_bot source must have 'tg_username' or 'tg_id' (the chat the bot is in)",
+                        source.name
+                    ))
+                    .into());
+                }
+                if config.telegram.bot_token.as_deref().is_none_or(|t| !t.contains(':')) {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': telegram_bot sources require [telegram].bot_token (from @BotFather)",
+                        source.name
+                    ))
+                    .into());
+                }
+            }
+            "telegram_folder" => {
+                if source.tg_folder_name.is_none() {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': telegram_folder source must have 'tg_folder_name'",
+                        source.name
+                    ))
+                    .into());
+                }
+            }
+            other => {
+                return Err(
+                    ConfigError::Validation(format!("source '{}': unknown type '{}'", source.name, other)).into(),
+                );
+            }
+        }
+
+        // Validate auth config
+        if let Some(auth) = &source.auth {
+            match auth.auth_type.as_str() {
+                "basic" => {
+                    if auth.username.is_none() || auth.password.is_none() {
+                        return Err(ConfigError::Validation(format!(
+                            "source '{}': basic auth requires 'username' and 'password'",
+                            source.name
+                        ))
+                        .into());
+                    }
+                }
+                "bearer" => {
+                    if auth.token.is_none() {
+                        return Err(ConfigError::Validation(format!(
+                            "source '{}': bearer auth requires 'token'",
+                            source.name
+                        ))
+                        .into());
+                    }
+                }
+                "header" => {
+                    if auth.header_name.is_none() || auth.header_value.is_none() {
+                        return Err(ConfigError::Validation(format!(
+                            "source '{}': header auth requires 'header_name' and 'header_value'",
+                            source.name
+                        ))
+                        .into());
+                    }
+                }
+                other => {
+                    return Err(ConfigError::Validation(format!(
+                        "source '{}': unknown auth type '{}'",
+                        source.name, other
+                    ))
+                    .into());
+                }
+            }
+        }
+
+        // Validate max_items fits in i32 (SQLite INTEGER)
+        if source.max_items > i32::MAX as u32 {
+            return Err(ConfigError::Validation(format!(
+                "source '{}': max_items {} exceeds maximum ({})",
+                source.name,
+                source.max_items,
+                i32::MAX
+            ))
+            .into());
+        }
+
+        // Validate the stale_after override (polled sources only; TG sources aren't polled)
+        if let Some(ref stale_after) = source.stale_after {
+            if source.source_type.starts_with("telegram_") {
+                return Err(ConfigError::Validation(format!(
+                    "source '{}': stale_after only applies to polled sources",
+                    source.name
+                ))
+                .into());
+            }
+            parse_stale_after(stale_after).map_err(|e| {
+                ConfigError::Validation(format!(
+                    "source '{}': invalid stale_after '{stale_after}': {e}",
+                    source.name
+                ))
+            })?;
+        }
+
+        if let Some(bad) = source.languages.iter().find(|l| !language::is_known(l)) {
+            return Err(ConfigError::Validation(format!(
+                "source '{}': unknown language code '{bad}' in languages (use ISO 639-1 codes like \"en\")",
+                source.name
+            ))
+            .into());
+        }
+
+        // Groups have no view counts
+        if source.min_views.is_some() && !matches!(source.source_type.as_str(), "telegram_channel" | "telegram_folder") {
+            return Err(ConfigError::Validation(format!(
+                "source '{}': min_views only applies to telegram_channel and telegram_folder sources",
+                source.name
+            ))
+            .into());
+        }
+        if source.min_reactions.is_some() && !is_mtproto_source(&source.source_type) {
+            return Err(ConfigError::Validation(format!(
+                "source '{}': min_reactions only applies to Telegram sources read through an account",
+                source.name
+            ))
+            .into());
+        }
+
+        // Validate poll_interval is parseable
+        humantime::parse_duration(&source.poll_interval).map_err(|e| {
+            ConfigError::Validation(format!(
+                "source '{}': invalid poll_interval '{}': {}",
+                source.name, source.poll_interval, e
+            ))
+        })?;
+    }
+
+    // Validate source names are unique
+    let mut source_names = HashSet::new();
+    for source in &config.source {
+        if !source_names.insert(&source.name) {
+            return Err(ConfigError::Validation(format!("duplicate source name: '{}'", source.name)).into());
+        }
+    }
+
+    // Validate Telegram accounts: names are used in session table names
+    for (name, account) in &config.telegram.accounts {
+        if name == DEFAULT_TG_ACCOUNT
+            || name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            return Err(ConfigError::Validation(format!(
+                "[telegram.accounts.{name}]: account names must be lowercase letters, digits, and '_' (and not '{DEFAULT_TG_ACCOUNT}')"
+            ))
+            .into());
+        }
+        if account.api_id == 0 || account.api_hash.is_empty() {
+            return Err(ConfigError::Validation(format!(
+                "[telegram.accounts.{name}]: api_id and api_hash are required (get them at https://my.telegram.org)"
+            ))
+            .into());
+        }
+    }
+
+    // Validate Telegram config if any TG sources are present
+    let has_tg_sources = config.source.iter().any(|s| is_mtproto_source(&s.source_type));
+    if has_tg_sources && !config.telegram.enabled {
+        return Err(ConfigError::Validation(
+            "telegram sources are configured but [telegram].enabled is false".to_string(),
+        )
+        .into());
+    }
+    let write_queue = &config.telegram.write_queue;
+    if write_queue.capacity == 0 || write_queue.batch_size == 0 {
+        return Err(ConfigError::Validation(
+            "[telegram.write_queue] capacity and batch_size must be at least 1".to_string(),
+        )
+        .into());
+    }
+    if !matches!(write_queue.overflow.as_str(), "drop_oldest" | "drop_newest") {
+        return Err(ConfigError::Validation(format!(
+            "[telegram.write_queue].overflow must be \"drop_oldest\" or \"drop_newest\", got '{}'",
+            write_queue.overflow
+        ))
+        .into());
+    }
+    if !DELETED_MESSAGE_ACTIONS.contains(&config.telegram.deleted_messages.as_str()) {
+        return Err(ConfigError::Validation(format!(
+            "[telegram].deleted_messages must be one of: {}, got '{}'",
+            DELETED_MESSAGE_ACTIONS.join(", "),
+            config.telegram.deleted_messages
+        ))
+        .into());
+    }
+    humantime::parse_duration(&config.telegram.debug_updates.keep).map_err(|e| {
+        ConfigError::Validation(format!(
+            "[telegram.debug_updates].keep: invalid duration '{}': {e}",
+            config.telegram.debug_updates.keep
+        ))
+    })?;
+    for source in config.source.iter().filter(|s| is_mtproto_source(&s.source_type)) {
+        match source.tg_account.as_deref() {
+            None | Some(DEFAULT_TG_ACCOUNT) => {
+                match config.telegram.api_id {
+                    None | Some(0) => {
+                        return Err(ConfigError::Validation(
+                            "telegram sources require a valid [telegram].api_id (get one at https://my.telegram.org)"
+                                .to_string(),
+                        )
+                        .into());
+                    }
+                    _ => {}
+                }
+                match config.telegram.api_hash.as_deref() {
+                    None | Some("") => {
+                        return Err(ConfigError::Validation(
+                            "telegram sources require a valid [telegram].api_hash (get one at https://my.telegram.org)"
+                                .to_string(),
+                        )
+                        .into());
+                    }
+                    _ => {}
+                }
+            }
+            Some(account) if !config.telegram.accounts.contains_key(account) => {
+                return Err(ConfigError::Validation(format!(
+                    "source '{}': tg_account '{account}' is not defined in [telegram.accounts]",
+                    source.name
+                ))
+                .into());
+            }
+            Some(_) => {}
+        }
+    }
+    if let Some(source) = config
+        .source
+        .iter()
+        .find(|s| s.tg_account.is_some() && !is_mtproto_source(&s.source_type))
+    {
+        return Err(ConfigError::Validation(format!(
+            "source '{}': tg_account is only valid for telegram sources read through an account",
+            source.name
+        ))
+        .into());
+    }
+
+    // Validate personas
+    for (name, persona) in &config.persona {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+        {
+            return Err(ConfigError::Validation(format!(
+                "persona '{name}': name must contain only lowercase letters, digits, hyphens and underscores"
+            ))
+            .into());
+        }
+        if persona.style.trim().is_empty() {
+            return Err(ConfigError::Validation(format!("persona '{name}': style must not be empty")).into());
+        }
+    }
+
+    // Validate output channels
+    let mut channel_slugs = HashSet::new();
+    for channel in &config.output_channel {
+        if !channel_slugs.insert(&channel.slug) {
+            return Err(ConfigError::Validation(format!("duplicate output channel slug: '{}'", channel.slug)).into());
+        }
+
+        // Validate slug is URL-safe (used in feed paths: /feed/<username>/<slug>.atom)
+        if !channel
+            .slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            || channel.slug.is_empty()
+            || channel.slug.starts_with('-')
+            || channel.slug.ends_with('-')
+        {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': slug '{}' must be non-empty, contain only lowercase letters, digits, and hyphens, \
+                 and not start or end with a hyphen",
+                channel.name, channel.slug
+            ))
+            .into());
+        }
+
+        if channel.model.is_some() && !channel.models.is_empty() {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': set either model or models, not both",
+                channel.name
+            ))
+            .into());
+        }
+        if channel.models.iter().any(|m| m.trim().is_empty()) {
+            return Err(
+                ConfigError::Validation(format!("output channel '{}': models must not be empty", channel.name)).into(),
+            );
+        }
+
+        if let Some(ref template) = channel.template
+            && !config.channel_template.contains_key(template)
+        {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': unknown template '{template}' (no [channel_template.{template}] section)",
+                channel.name
+            ))
+            .into());
+        }
+
+        // Validate editorial directive: inline prompt or a template (channel or global)
+        let template = channel
+            .prompt_template
+            .as_deref()
+            .or(config.pail.prompt_template.as_deref());
+        if channel.prompt.trim().is_empty() && template.is_none() {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': must have a 'prompt' or a 'prompt_template'",
+                channel.name
+            ))
+            .into());
+        }
+        if let Some(path) = template {
+            validate_prompt_template(path)
+                .map_err(|e| ConfigError::Validation(format!("output channel '{}': {}", channel.name, e)))?;
+        }
+        if let Some(ref persona) = channel.persona
+            && !config.persona.contains_key(persona)
+        {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': unknown persona '{persona}' (no [persona.{persona}] section)",
+                channel.name
+            ))
+            .into());
+        }
+
+        if channel.sources.is_empty() {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': must have at least one source",
+                channel.name
+            ))
+            .into());
+        }
+
+        // Validate source references exist
+        for source_name in &channel.sources {
+            if !source_names.contains(source_name) {
+                return Err(ConfigError::Validation(format!(
+                    "output channel '{}': references unknown source '{}'",
+                    channel.name, source_name
+                ))
+                .into());
+            }
+        }
+
+        // Validate schedule expression (if present)
+        if let Some(ref schedule) = channel.schedule {
+            validate_schedule(schedule)
+                .map_err(|e| ConfigError::Validation(format!("output channel '{}': {}", channel.name, e)))?;
+        }
+
+        // Validate Matrix delivery target (if present)
+        if let Some(ref room) = channel.matrix_room {
+            if !room.starts_with('!') || !room.contains(':') {
+                return Err(ConfigError::Validation(format!(
+                    "output channel '{}': matrix_room '{}' must be a room ID like '!abc123:example.org'",
+                    channel.name, room
+                ))
+                .into());
+            }
+            if config.matrix.homeserver.as_deref().unwrap_or("").is_empty()
+                || config.matrix.access_token.as_deref().unwrap_or("").is_empty()
+            {
+                return Err(ConfigError::Validation(format!(
+                    "output channel '{}': matrix_room requires [matrix].homeserver and [matrix].access_token",
+                    channel.name
+                ))
+                .into());
+            }
+        }
+
+        if channel.webmention && config.pail.public_url.is_none() {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': webmention requires [pail].public_url",
+                channel.name
+            ))
+            .into());
+        }
+
+        // Validate required sections: one-line headings, each listed once
+        let mut seen_sections = HashSet::new();
+        for section in &channel.sections {
+            if section.trim().is_empty() || section.contains('#') || section.chars().any(|c| c.is_control()) {
+                return Err(ConfigError::Validation(format!(
+                    "output channel '{}': invalid section {:?} (must be non-empty, single-line, without '#')",
+                    channel.name, section
+                ))
+                .into());
+            }
+            if !seen_sections.insert(section.trim().to_lowercase()) {
+                return Err(ConfigError::Validation(format!(
+                    "output channel '{}': section '{}' is listed twice",
+                    channel.name, section
+                ))
+                .into());
+            }
+        }
+
+        // Validate tag filters: tagging configured, tags known to the vocabulary, no overlap
+        if !channel.include_tags.is_empty() || !channel.exclude_tags.is_empty() {
+            if !config.tagging.is_enabled() {
+                return Err(ConfigError::Validation(format!(
+                    "output channel '{}': include_tags / exclude_tags require [tagging].api_url and [tagging].model",
+                    channel.name
+                ))
+                .into());
+            }
+            let vocabulary: HashSet<String> = config.tagging.vocabulary.iter().map(|t| normalize_tag(t)).collect();
+            for tag in channel.include_tags.iter().chain(&channel.exclude_tags) {
+                let normalized = normalize_tag(tag);
+                if normalized.is_empty() || normalized.chars().any(|c| c.is_control()) {
+                    return Err(ConfigError::Validation(format!(
+                        "output channel '{}': invalid tag {:?}",
+                        channel.name, tag
+                    ))
+                    .into());
+                }
+                if !vocabulary.is_empty() && !vocabulary.contains(&normalized) {
+                    return Err(ConfigError::Validation(format!(
+                        "output channel '{}': tag '{}' is not in [tagging].vocabulary",
+                        channel.name, tag
+                    ))
+                    .into());
+                }
+            }
+            let include: HashSet<String> = channel.include_tags.iter().map(|t| normalize_tag(t)).collect();
+            if let Some(tag) = channel
+                .exclude_tags
+                .iter()
+                .find(|t| include.contains(&normalize_tag(t)))
+            {
+                return Err(ConfigError::Validation(format!(
+                    "output channel '{}': tag '{}' is in both include_tags and exclude_tags",
+                    channel.name, tag
+                ))
+                .into());
+            }
+        }
+
+        // Validate relevance filtering (if enabled)
+        if let Some(ref profile) = channel.interest_profile {
+            if profile.trim().is_empty() {
+                return Err(ConfigError::Validation(format!(
+                    "output channel '{}': interest_profile must not be empty",
+                    channel.name
+                ))
+                .into());
+            }
+            if config.embeddings.api_url.as_deref().unwrap_or("").is_empty()
+                || config.embeddings.model.as_deref().unwrap_or("").is_empty()
+            {
+                return Err(ConfigError::Validation(format!(
+                    "output channel '{}': interest_profile requires [embeddings].api_url and [embeddings].model",
+                    channel.name
+                ))
+                .into());
+            }
+        }
+        if !(-1.0..=1.0).contains(&channel.relevance_threshold) {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': relevance_threshold {} must be between -1.0 and 1.0",
+                channel.name, channel.relevance_threshold
+            ))
+            .into());
+        }
+        if !matches!(channel.relevance_action.as_str(), "exclude" | "demote") {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': relevance_action '{}' must be \"exclude\" or \"demote\"",
+                channel.name, channel.relevance_action
+            ))
+            .into());
+        }
+        if let Some(ref action) = channel.self_eval {
+            if !SELF_EVAL_ACTIONS.contains(&action.as_str()) {
+                return Err(ConfigError::Validation(format!(
+                    "output channel '{}': self_eval '{action}' must be one of: {}",
+                    channel.name,
+                    SELF_EVAL_ACTIONS.join(", ")
+                ))
+                .into());
+            }
+            if !config.self_eval.is_enabled() {
+                return Err(ConfigError::Validation(format!(
+                    "output channel '{}': self_eval requires [self_eval].api_url and [self_eval].model",
+                    channel.name
+                ))
+                .into());
+            }
+        }
+        if let Some(ref action) = channel.verify_links
+            && !LINK_CHECK_ACTIONS.contains(&action.as_str())
+        {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': verify_links '{action}' must be one of: {}",
+                channel.name,
+                LINK_CHECK_ACTIONS.join(", ")
+            ))
+            .into());
+        }
+        if !(0.0..=1.0).contains(&channel.self_eval_min_score) {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': self_eval_min_score {} must be between 0.0 and 1.0",
+                channel.name, channel.self_eval_min_score
+            ))
+            .into());
+        }
+        if channel.max_cost_usd.is_some_and(|c| !c.is_finite() || c <= 0.0) {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': max_cost_usd must be greater than 0",
+                channel.name
+            ))
+            .into());
+        }
+        if channel.max_tokens == Some(0) {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': max_tokens must be at least 1",
+                channel.name
+            ))
+            .into());
+        }
+        if channel.input_token_budget == Some(0) {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': input_token_budget must be at least 1",
+                channel.name
+            ))
+            .into());
+        }
+        if channel.min_items == Some(0) {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': min_items must be at least 1",
+                channel.name
+            ))
+            .into());
+        }
+        if !MIN_ITEMS_ACTIONS.contains(&channel.min_items_action.as_str()) {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': invalid min_items_action '{}' (expected one of: {})",
+                channel.name,
+                channel.min_items_action,
+                MIN_ITEMS_ACTIONS.join(", ")
+            ))
+            .into());
+        }
+        if let Some(ref format) = channel.format
+            && !OUTPUT_FORMATS.contains(&format.as_str())
+        {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': invalid format '{format}' (expected one of: {})",
+                channel.name,
+                OUTPUT_FORMATS.join(", ")
+            ))
+            .into());
+        }
+        if !CHANNEL_WINDOWS.contains(&channel.window.as_str()) {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': invalid window '{}' (expected one of: {})",
+                channel.name,
+                channel.window,
+                CHANNEL_WINDOWS.join(", ")
+            ))
+            .into());
+        }
+        if let Some(bad) = channel.languages.iter().find(|l| !language::is_known(l)) {
+            return Err(ConfigError::Validation(format!(
+                "output channel '{}': unknown language code '{bad}' in languages (use ISO 639-1 codes like \"en\")",
+                channel.name
+            ))
+            .into());
+        }
+        if !channel.translate.is_empty() {
+            if let Some(bad) = channel.translate.iter().find(|l| !language::is_known(l)) {
+                return Err(ConfigError::Validation(format!(
+                    "output channel '{}': unknown language code '{bad}' in translate (use ISO 639-1 codes like \"uk\")",
+                    channel.name
+                ))
+                .into());
+            }
+            if !config.translation.is_enabled() {
+                return Err(ConfigError::Validation(format!(
+                    "output channel '{}': translate requires [translation].api_url and [translation].model",
+                    channel.name
+                ))
+                .into());
+            }
+        }
+        validate_mark_tg_read(config, channel)?;
+        validate_postprocess(&channel.name, &channel.postprocess)?;
+        validate_redact(&channel.name, &channel.redact)?;
+        if let Some(ref breaking) = channel.breaking {
+            validate_breaking(channel, breaking)?;
+        }
+        if let Some(ref timeout) = channel.timeout {
+            match humantime::parse_duration(timeout) {
+                Ok(d) if !d.is_zero() => {}
+                _ => {
+                    return Err(ConfigError::Validation(format!(
+                        "output channel '{}': invalid timeout '{timeout}' (expected a duration like \"45m\")",
+                        channel.name
+                    ))
+                    .into());
+                }
+            }
+        }
+    }
+
+    // Validate transcription settings
+    humantime::parse_duration(&config.transcription.timeout).map_err(|e| {
+        ConfigError::Validation(format!(
+            "[transcription].timeout '{}': {}",
+            config.transcription.timeout, e
+        ))
+    })?;
+    if let Some(ref command) = config.transcription.command
+        && !command.contains("{audio}")
+    {
+        return Err(
+            ConfigError::Validation("[transcription].command must contain the {audio} placeholder".to_string()).into(),
+        );
+    }
+    if config.transcription.max_episodes_per_fetch == 0 {
+        return Err(
+            ConfigError::Validation("[transcription].max_episodes_per_fetch must be at least 1".to_string()).into(),
+        );
+    }
+
+    // Validate admin API token
+    if let Some(ref token) = config.pail.admin_token {
+        if token.len() < MIN_TOKEN_LEN {
+            return Err(ConfigError::Validation(format!(
+                "[pail].admin_token must be at least {MIN_TOKEN_LEN} characters"
+            ))
+            .into());
+        }
+        if config.pail.feed_token.as_deref() == Some(token.as_str()) {
+            return Err(
+                ConfigError::Validation("[pail].admin_token must differ from [pail].feed_token".to_string()).into(),
+            );
+        }
+    }
+
+    // Validate control socket
+    if let Some(ref value) = config.pail.control_socket
+        && !value.is_empty()
+    {
+        match value.strip_prefix("tcp:") {
+            Some(addr) => {
+                let addr: std::net::SocketAddr = addr.parse().map_err(|_| {
+                    ConfigError::Validation(format!(
+                        "[pail].control_socket '{value}': expected tcp:<ip>:<port>, e.g. tcp:127.0.0.1:8081"
+                    ))
+                })?;
+                if !addr.ip().is_loopback() {
+                    return Err(ConfigError::Validation(format!(
+                        "[pail].control_socket '{value}' must listen on a loopback address"
+                    ))
+                    .into());
+                }
+                if config.pail.feed_token.is_none() {
+                    return Err(ConfigError::Validation(
+                        "[pail].control_socket over TCP requires [pail].feed_token (used to authenticate pail ctl)"
+                            .to_string(),
+                    )
+                    .into());
+                }
+            }
+            None if !cfg!(unix) => {
+                return Err(ConfigError::Validation(format!(
+                    "[pail].control_socket '{value}': Unix sockets are not supported on this platform, \
+                     use tcp:127.0.0.1:<port>"
+                ))
+                .into());
+            }
+            None => {}
+        }
+    }
+
+    // Validate prefetch settings
+    humantime::parse_duration(&config.prefetch.timeout)
+        .map_err(|e| ConfigError::Validation(format!("[prefetch].timeout '{}': {}", config.prefetch.timeout, e)))?;
+    if config.prefetch.max_pages == 0 {
+        return Err(ConfigError::Validation("[prefetch].max_pages must be at least 1".to_string()).into());
+    }
+
+    // Validate embeddings API URL
+    if let Some(ref api_url) = config.embeddings.api_url
+        && !(api_url.starts_with("https://") || api_url.starts_with("http://"))
+    {
+        return Err(ConfigError::Validation(format!(
+            "[embeddings].api_url '{api_url}' must start with http:// or https://"
+        ))
+        .into());
+    }
+
+    // Validate [tagging]: an http(s) endpoint plus a model, and a usable vocabulary
+    if let Some(ref api_url) = config.tagging.api_url {
+        if !(api_url.starts_with("https://") || api_url.starts_with("http://")) {
+            return Err(ConfigError::Validation(format!(
+                "[tagging].api_url '{api_url}' must start with http:// or https://"
+            ))
+            .into());
+        }
+        if config.tagging.model.as_deref().unwrap_or("").is_empty() {
+            return Err(ConfigError::Validation("[tagging].model is required when api_url is set".to_string()).into());
+        }
+    }
+    if config.tagging.max_tags_per_item == 0 {
+        return Err(ConfigError::Validation("[tagging].max_tags_per_item must be at least 1".to_string()).into());
+    }
+    let mut seen_tags = HashSet::new();
+    for tag in &config.tagging.vocabulary {
+        let normalized = normalize_tag(tag);
+        if normalized.is_empty() || normalized.chars().any(|c| c.is_control()) {
+            return Err(ConfigError::Validation(format!("[tagging].vocabulary: invalid tag {tag:?}")).into());
+        }
+        if !seen_tags.insert(normalized) {
+            return Err(ConfigError::Validation(format!("[tagging].vocabulary: tag '{tag}' is listed twice")).into());
+        }
+    }
+
+    // Validate [network].proxy and per-source overrides
+    if let Some(ref proxy) = config.network.proxy {
+        validate_proxy_url(proxy).map_err(|e| ConfigError::Validation(format!("[network].proxy: {e}")))?;
+        // MTProto isn't HTTP: the Telegram connection only goes through SOCKS5
+        if config.telegram.enabled && !proxy.starts_with("socks5") {
+            return Err(ConfigError::Validation(format!(
+                "[network].proxy '{proxy}': Telegram connections need a socks5:// proxy"
+            ))
+            .into());
+        }
+    }
+    for source in &config.source {
+        let Some(ref proxy) = source.proxy else {
+            continue;
+        };
+        // Telegram sources share a connection per account (or bot), webhook sources are pushed
+        // to, and IMAP connects over plain TCP
+        if source.source_type.starts_with("telegram_") || matches!(source.source_type.as_str(), "webhook" | "imap") {
+            return Err(ConfigError::Validation(format!(
+                "source '{}': proxy doesn't apply to {} sources (set [network].proxy instead)",
+                source.name, source.source_type
+            ))
+            .into());
+        }
+        if proxy != DIRECT_PROXY {
+            validate_proxy_url(proxy)
+                .map_err(|e| ConfigError::Validation(format!("source '{}': proxy: {e}", source.name)))?;
+        }
+    }
+
+    // Validate per-source dedup strategies (feed sources only)
+    for source in &config.source {
+        let Some(ref dedup) = source.dedup else {
+            continue;
+        };
+        if !matches!(source.source_type.as_str(), "rss" | "podcast") {
+            return Err(ConfigError::Validation(format!(
+                "source '{}': dedup only applies to rss and podcast sources",
+                source.name
+            ))
+            .into());
+        }
+        if !DEDUP_STRATEGIES.contains(&dedup.as_str()) {
             return Err(ConfigError::Validation(format!(
-                "source '{}': description contains disallowed character {:?} \
-                 (control characters, double quotes, and backslashes are not allowed)",
-                source.name, bad
+                "source '{}': dedup must be one of: {}, got '{dedup}'",
+                source.name,
+                DEDUP_STRATEGIES.join(", ")
             ))
             .into());
         }
     }
 
-    // Validate source types
+    // Validate per-source request headers and timeouts (HTTP sources only)
     for source in &config.source {
-        match source.source_type.as_str() {
-            "rss" => {
-                if source.url.is_none() {
-                    return Err(ConfigError::Validation(format!(
-                        "source '{}': RSS source must have a 'url'",
-                        source.name
-                    ))
-                    .into());
-                }
+        if source.headers.is_empty() && source.timeout.is_none() {
+            continue;
+        }
+        if !HTTP_FETCH_SOURCE_TYPES.contains(&source.source_type.as_str()) {
+            return Err(ConfigError::Validation(format!(
+                "source '{}': headers and timeout only apply to {} sources",
+                source.name,
+                HTTP_FETCH_SOURCE_TYPES.join(", ")
+            ))
+            .into());
+        }
+        for (name, value) in &source.headers {
+            if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                return Err(
+                    ConfigError::Validation(format!("source '{}': invalid header name '{name}'", source.name)).into(),
+                );
             }
-            "telegram_channel" | "telegram_group" => {
-                if source.tg_username.is_none() && source.tg_id.is_none() {
-                    return Err(ConfigError::Validation(format!(
-                        "source '{}': {} source must have 'tg_username' or 'tg_id'",
-                        source.name, source.source_type
-                    ))
-                    .into());
-                }
+            if reqwest::header::HeaderValue::from_str(value).is_err() {
+                return Err(ConfigError::Validation(format!(
+                    "source '{}': invalid value for header '{name}'",
+                    source.name
+                ))
+                .into());
             }
-            "telegram_folder" => {
-                if source.tg_folder_name.is_none() {
+            // Crawled pages identify as pail's crawler, the name robots.txt rules are matched against
+            if matches!(source.source_type.as_str(), "scrape" | "microformats")
+                && name.eq_ignore_ascii_case("user-agent")
+            {
+                return Err(ConfigError::Validation(format!(
+                    "source '{}': {} sources always send the crawler User-Agent ([network].contact_url)",
+                    source.name, source.source_type
+                ))
+                .into());
+            }
+            if source.auth.is_some() && name.eq_ignore_ascii_case("authorization") {
+                return Err(ConfigError::Validation(format!(
+                    "source '{}': an Authorization header conflicts with [source.auth]; use one or the other",
+                    source.name
+                ))
+                .into());
+            }
+        }
+        if let Some(ref timeout) = source.timeout {
+            match humantime::parse_duration(timeout) {
+                Ok(d) if d.is_zero() => {
                     return Err(ConfigError::Validation(format!(
-                        "source '{}': telegram_folder source must have 'tg_folder_name'",
+                        "source '{}': timeout must be above zero",
                         source.name
                     ))
                     .into());
                 }
-            }
-            other => {
-                return Err(
-                    ConfigError::Validation(format!("source '{}': unknown type '{}'", source.name, other)).into(),
-                );
-            }
-        }
-
-        // Validate auth config
-        if let Some(auth) = &source.auth {
-            match auth.auth_type.as_str() {
-                "basic" => {
-                    if auth.username.is_none() || auth.password.is_none() {
-                        return Err(ConfigError::Validation(format!(
-                            "source '{}': basic auth requires 'username' and 'password'",
-                            source.name
-                        ))
-                        .into());
-                    }
-                }
-                "bearer" => {
-                    if auth.token.is_none() {
-                        return Err(ConfigError::Validation(format!(
-                            "source '{}': bearer auth requires 'token'",
-                            source.name
-                        ))
-                        .into());
-                    }
-                }
-                "header" => {
-                    if auth.header_name.is_none() || auth.header_value.is_none() {
-                        return Err(ConfigError::Validation(format!(
-                            "source '{}': header auth requires 'header_name' and 'header_value'",
-                            source.name
-                        ))
-                        .into());
-                    }
-                }
-                other => {
+                Ok(_) => {}
+                Err(e) => {
                     return Err(ConfigError::Validation(format!(
-                        "source '{}': unknown auth type '{}'",
-                        source.name, other
+                        "source '{}': invalid timeout '{timeout}': {e}",
+                        source.name
                     ))
                     .into());
                 }
             }
         }
+    }
 
-        // Validate max_items fits in i32 (SQLite INTEGER)
-        if source.max_items > i32::MAX as u32 {
+    // Validate per-source pre-processors (polled sources only)
+    for source in &config.source {
+        if source.preprocess.is_empty() {
+            continue;
+        }
+        if !crate::fetch::POLLED_SOURCE_TYPES.contains(&source.source_type.as_str()) {
             return Err(ConfigError::Validation(format!(
-                "source '{}': max_items {} exceeds maximum ({})",
-                source.name,
-                source.max_items,
-                i32::MAX
+                "source '{}': preprocess doesn't apply to {} sources",
+                source.name, source.source_type
             ))
             .into());
         }
-
-        // Validate poll_interval is parseable
-        humantime::parse_duration(&source.poll_interval).map_err(|e| {
+        if source.preprocess[0].trim().is_empty() {
+            return Err(ConfigError::Validation(format!("source '{}': preprocess needs a command", source.name)).into());
+        }
+        humantime::parse_duration(&source.preprocess_timeout).map_err(|e| {
             ConfigError::Validation(format!(
-                "source '{}': invalid poll_interval '{}': {}",
-                source.name, source.poll_interval, e
+                "source '{}': invalid preprocess_timeout '{}': {e}",
+                source.name, source.preprocess_timeout
             ))
         })?;
+        if !PREPROCESS_FAILURE_ACTIONS.contains(&source.preprocess_on_error.as_str()) {
+            return Err(ConfigError::Validation(format!(
+                "source '{}': preprocess_on_error must be one of: {}, got '{}'",
+                source.name,
+                PREPROCESS_FAILURE_ACTIONS.join(", "),
+                source.preprocess_on_error
+            ))
+            .into());
+        }
     }
 
-    // Validate source names are unique
-    let mut source_names = HashSet::new();
-    for source in &config.source {
-        if !source_names.insert(&source.name) {
-            return Err(ConfigError::Validation(format!("duplicate source name: '{}'", source.name)).into());
-        }
+    // Validate [network] crawler settings
+    let contact = &config.network.contact_url;
+    if !(contact.starts_with("https://") || contact.starts_with("http://") || contact.starts_with("mailto:")) {
+        return Err(ConfigError::Validation(format!(
+            "[network].contact_url '{contact}' must be an http(s):// or mailto: URL"
+        ))
+        .into());
+    }
+    if contact.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(
+            ConfigError::Validation(format!("[network].contact_url '{contact}' must not contain whitespace")).into(),
+        );
     }
+    humantime::parse_duration(&config.network.crawl_delay)
+        .map_err(|e| ConfigError::Validation(format!("[network].crawl_delay '{}': {e}", config.network.crawl_delay)))?;
 
-    // Validate Telegram config if any TG sources are present
-    let has_tg_sources = config.source.iter().any(|s| s.source_type.starts_with("telegram_"));
-    if has_tg_sources {
-        if !config.telegram.enabled {
-            return Err(ConfigError::Validation(
-                "telegram sources are configured but [telegram].enabled is false".to_string(),
-            )
+    // Validate [self_eval]: an http(s) endpoint plus a model
+    if let Some(ref api_url) = config.self_eval.api_url {
+        if !(api_url.starts_with("https://") || api_url.starts_with("http://")) {
+            return Err(ConfigError::Validation(format!(
+                "[self_eval].api_url '{api_url}' must start with http:// or https://"
+            ))
             .into());
         }
-        match config.telegram.api_id {
-            None | Some(0) => {
-                return Err(ConfigError::Validation(
-                    "telegram sources require a valid [telegram].api_id (get one at https://my.telegram.org)"
-                        .to_string(),
-                )
-                .into());
-            }
-            _ => {}
-        }
-        match config.telegram.api_hash.as_deref() {
-            None | Some("") => {
-                return Err(ConfigError::Validation(
-                    "telegram sources require a valid [telegram].api_hash (get one at https://my.telegram.org)"
-                        .to_string(),
-                )
-                .into());
-            }
-            _ => {}
+        if config.self_eval.model.as_deref().unwrap_or("").is_empty() {
+            return Err(ConfigError::Validation("[self_eval].model is required when api_url is set".to_string()).into());
         }
     }
 
-    // Validate output channels
-    let mut channel_slugs = HashSet::new();
-    for channel in &config.output_channel {
-        if !channel_slugs.insert(&channel.slug) {
-            return Err(ConfigError::Validation(format!("duplicate output channel slug: '{}'", channel.slug)).into());
+    if let Some(ref public_url) = config.pail.public_url
+        && !(public_url.starts_with("https://") || public_url.starts_with("http://"))
+    {
+        return Err(ConfigError::Validation(format!(
+            "[pail].public_url '{public_url}' must start with http:// or https://"
+        ))
+        .into());
+    }
+
+    // Validate [translation]: an http(s) endpoint plus a model
+    if let Some(ref api_url) = config.translation.api_url {
+        if !(api_url.starts_with("https://") || api_url.starts_with("http://")) {
+            return Err(ConfigError::Validation(format!(
+                "[translation].api_url '{api_url}' must start with http:// or https://"
+            ))
+            .into());
         }
+        if config.translation.model.as_deref().unwrap_or("").is_empty() {
+            return Err(
+                ConfigError::Validation("[translation].model is required when api_url is set".to_string()).into(),
+            );
+        }
+    }
 
-        // Validate slug is URL-safe (used in feed paths: /feed/<username>/<slug>.atom)
-        if !channel
-            .slug
-            .chars()
-            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
-            || channel.slug.is_empty()
-            || channel.slug.starts_with('-')
-            || channel.slug.ends_with('-')
-        {
+    // Validate [source_health]
+    if config.source_health.failure_threshold == 0 {
+        return Err(ConfigError::Validation("[source_health].failure_threshold must be at least 1".to_string()).into());
+    }
+    parse_stale_after(&config.source_health.stale_after).map_err(|e| {
+        ConfigError::Validation(format!(
+            "[source_health].stale_after '{}': {e}",
+            config.source_health.stale_after
+        ))
+    })?;
+    if let Some(ref room) = config.source_health.alert_matrix_room {
+        if !room.starts_with('!') || !room.contains(':') {
             return Err(ConfigError::Validation(format!(
-                "output channel '{}': slug '{}' must be non-empty, contain only lowercase letters, digits, and hyphens, \
-                 and not start or end with a hyphen",
-                channel.name, channel.slug
+                "[source_health].alert_matrix_room '{room}' must be a room ID like '!abc123:example.org'"
             ))
             .into());
         }
+        if config.matrix.homeserver.as_deref().unwrap_or("").is_empty()
+            || config.matrix.access_token.as_deref().unwrap_or("").is_empty()
+        {
+            return Err(ConfigError::Validation(
+                "[source_health].alert_matrix_room requires [matrix].homeserver and [matrix].access_token".to_string(),
+            )
+            .into());
+        }
+    }
 
-        if channel.sources.is_empty() {
+    // Validate [notifications]
+    for event in &config.notifications.events {
+        if !notify::Event::ALL.iter().any(|e| e.as_str() == event) {
             return Err(ConfigError::Validation(format!(
-                "output channel '{}': must have at least one source",
-                channel.name
+                "[notifications].events: unknown event '{event}' (expected generation_failed, source_failing or startup_degraded)"
+            ))
+            .into());
+        }
+    }
+    for url in &config.notifications.urls {
+        // The URL may hold credentials; only its scheme goes in the error
+        let scheme = url.split("://").next().unwrap_or("");
+        notify::parse_service_url(url)
+            .map_err(|e| ConfigError::Validation(format!("[notifications].urls '{scheme}://...': {e}")))?;
+    }
+    if let Some(ref account) = config.notifications.telegram_account {
+        if !config.telegram.enabled {
+            return Err(ConfigError::Validation(
+                "[notifications].telegram_account requires [telegram].enabled".to_string(),
+            )
+            .into());
+        }
+        if config.telegram.account(account).is_none() {
+            return Err(ConfigError::Validation(format!(
+                "[notifications].telegram_account '{account}' is not a configured Telegram account"
             ))
             .into());
         }
+    }
 
-        // Validate source references exist
-        for source_name in &channel.sources {
-            if !source_names.contains(source_name) {
+    // Validate [reporting]
+    if let Some(ref dsn) = config.reporting.sentry_dsn
+        && !dsn.is_empty()
+        && !(dsn.starts_with("https://") || dsn.starts_with("http://"))
+    {
+        return Err(ConfigError::Validation(
+            "[reporting].sentry_dsn must be an http:// or https:// DSN, or \"\" to disable reporting".to_string(),
+        )
+        .into());
+    }
+    if !(0.0..=1.0).contains(&config.reporting.traces_sample_rate) {
+        return Err(
+            ConfigError::Validation("[reporting].traces_sample_rate must be between 0.0 and 1.0".to_string()).into(),
+        );
+    }
+
+    // Validate workspace retention
+    if !matches!(
+        config.opencode.keep_workspace.as_str(),
+        "never" | "on_failure" | "always"
+    ) {
+        return Err(ConfigError::Validation(format!(
+            "[opencode].keep_workspace '{}' must be \"never\", \"on_failure\", or \"always\"",
+            config.opencode.keep_workspace
+        ))
+        .into());
+    }
+
+    if config.opencode.max_cost_usd.is_some_and(|c| !c.is_finite() || c <= 0.0) {
+        return Err(ConfigError::Validation("[opencode].max_cost_usd must be greater than 0".to_string()).into());
+    }
+    if config.opencode.max_tokens == Some(0) {
+        return Err(ConfigError::Validation("[opencode].max_tokens must be at least 1".to_string()).into());
+    }
+    if let Some(ref idle_timeout) = config.opencode.idle_timeout {
+        match humantime::parse_duration(idle_timeout) {
+            Ok(d) if d.is_zero() => {
+                return Err(
+                    ConfigError::Validation("[opencode].idle_timeout must be greater than 0".to_string()).into(),
+                );
+            }
+            Ok(_) => {}
+            Err(e) => {
                 return Err(ConfigError::Validation(format!(
-                    "output channel '{}': references unknown source '{}'",
-                    channel.name, source_name
+                    "[opencode].idle_timeout '{idle_timeout}' is not a valid duration: {e}"
                 ))
                 .into());
             }
         }
+    }
 
-        // Validate schedule expression (if present)
-        if let Some(ref schedule) = channel.schedule {
-            validate_schedule(schedule)
-                .map_err(|e| ConfigError::Validation(format!("output channel '{}': {}", channel.name, e)))?;
-        }
+    if let Some(ref sandbox) = config.opencode.sandbox {
+        validate_sandbox(sandbox).map_err(|e| ConfigError::Validation(format!("[opencode.sandbox]: {e}")))?;
+    }
+
+    // Validate Matrix homeserver URL
+    if let Some(ref homeserver) = config.matrix.homeserver
+        && !(homeserver.starts_with("https://") || homeserver.starts_with("http://"))
+    {
+        return Err(ConfigError::Validation(format!(
+            "[matrix].homeserver '{homeserver}' must start with http:// or https://"
+        ))
+        .into());
     }
 
     // Validate timezone
@@ -444,25 +3033,117 @@ pub fn validate_config(config: &Config) -> Result<()> {
     humantime::parse_duration(&config.pail.retention)
         .map_err(|e| ConfigError::Validation(format!("retention '{}': {}", config.pail.retention, e)))?;
 
+    // Validate article retention policy
+    if let Some(ref age) = config.pail.article_retention {
+        humantime::parse_duration(age)
+            .map_err(|e| ConfigError::Validation(format!("article_retention '{age}': {e}")))?;
+    }
+    if let Some(ref age) = config.pail.strip_generation_log_after {
+        humantime::parse_duration(age)
+            .map_err(|e| ConfigError::Validation(format!("strip_generation_log_after '{age}': {e}")))?;
+    }
+    if !(config.pail.duplicate_overlap > 0.0 && config.pail.duplicate_overlap <= 1.0) {
+        return Err(ConfigError::Validation(format!(
+            "duplicate_overlap {} must be greater than 0 and at most 1",
+            config.pail.duplicate_overlap
+        ))
+        .into());
+    }
+    if config.database.maintenance_hour > 23 {
+        return Err(ConfigError::Validation(format!(
+            "[database].maintenance_hour {} must be between 0 and 23",
+            config.database.maintenance_hour
+        ))
+        .into());
+    }
+    if config.pail.article_keep_per_channel == Some(0) {
+        return Err(ConfigError::Validation(
+            "article_keep_per_channel must be at least 1 (omit it to keep all articles)".to_string(),
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Validate `[opencode.sandbox]`: a known wrapper with the settings it needs.
+fn validate_sandbox(sandbox: &SandboxConfig) -> Result<(), String> {
+    match sandbox.wrapper.as_str() {
+        "bubblewrap" | "firejail" | "docker" | "custom" => {}
+        other => {
+            return Err(format!(
+                "wrapper '{other}' must be \"bubblewrap\", \"firejail\", \"docker\", or \"custom\""
+            ));
+        }
+    }
+    match (sandbox.wrapper.as_str(), &sandbox.command) {
+        ("custom", Some(command)) if !command.contains("{command}") => {
+            return Err("command must contain the {command} placeholder".to_string());
+        }
+        ("custom", None) => return Err("wrapper \"custom\" requires a command template".to_string()),
+        ("custom", Some(_)) => {}
+        (_, Some(_)) => return Err("command is only used with wrapper = \"custom\"".to_string()),
+        (_, None) => {}
+    }
+    match (sandbox.wrapper.as_str(), &sandbox.profile) {
+        ("docker", None) => return Err("wrapper \"docker\" requires profile (the image to run)".to_string()),
+        ("bubblewrap", Some(_)) => return Err("bubblewrap has no profiles; remove profile".to_string()),
+        _ => {}
+    }
+    for host in &sandbox.allow_hosts {
+        if host.is_empty() || host.contains(['/', ':', '*']) || host.chars().any(char::is_whitespace) {
+            return Err(format!(
+                "allow_hosts entry '{host}' must be a bare host name (subdomains are included)"
+            ));
+        }
+    }
+    for path in &sandbox.ro_binds {
+        if !Path::new(path).is_absolute() {
+            return Err(format!("ro_binds entry '{path}' must be an absolute path"));
+        }
+        if path.contains(':') {
+            return Err(format!("ro_binds entry '{path}' must not contain ':'"));
+        }
+    }
+    Ok(())
+}
+
+/// arXiv category: an archive (`cs`, `astro-ph`, `hep-th`), optionally with a subject class
+/// (`cs.CL`, `cond-mat.mes-hall`).
+fn is_arxiv_category(category: &str) -> bool {
+    let valid = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphabetic() || c == '-');
+    match category.split_once('.') {
+        Some((archive, class)) => valid(archive) && valid(class),
+        None => valid(category),
+    }
+}
+
+/// Validate that a prompt template file exists and is syntactically valid.
+/// Rendering errors (e.g. undefined variables) can only surface at generation time.
+fn validate_prompt_template(path: &Path) -> Result<(), String> {
+    let source =
+        std::fs::read_to_string(path).map_err(|e| format!("cannot read prompt_template '{}': {}", path.display(), e))?;
+    minijinja::Environment::new()
+        .template_from_str(&source)
+        .map_err(|e| format!("invalid prompt_template '{}': {}", path.display(), e))?;
     Ok(())
 }
 
 /// Validate a schedule expression.
-/// Supported formats: "at:HH:MM[,HH:MM...]", "weekly:DAY,HH:MM", "cron:EXPR"
-fn validate_schedule(schedule: &str) -> Result<(), String> {
+/// Supported formats: "at:HH:MM[,HH:MM...]", "weekly:DAY[,DAY...],HH:MM", "monthly:D[,D...],HH:MM",
+/// "cron:EXPR"
+pub(crate) fn validate_schedule(schedule: &str) -> Result<(), String> {
     if let Some(times) = schedule.strip_prefix("at:") {
         for time_str in times.split(',') {
             validate_time(time_str.trim())?;
         }
         Ok(())
     } else if let Some(rest) = schedule.strip_prefix("weekly:") {
-        let parts: Vec<&str> = rest.splitn(2, ',').collect();
-        if parts.len() != 2 {
+        let Some((days, time)) = rest.rsplit_once(',') else {
             return Err(format!(
-                "invalid weekly schedule '{schedule}': expected 'weekly:DAY,HH:MM'"
+                "invalid weekly schedule '{schedule}': expected 'weekly:DAY[,DAY...],HH:MM'"
             ));
-        }
-        let day = parts[0].trim().to_lowercase();
+        };
         let valid_days = [
             "monday",
             "tuesday",
@@ -472,10 +3153,29 @@ fn validate_schedule(schedule: &str) -> Result<(), String> {
             "saturday",
             "sunday",
         ];
-        if !valid_days.contains(&day.as_str()) {
-            return Err(format!("invalid day '{day}' in schedule '{schedule}'"));
+        for day in days.split(',') {
+            let day = day.trim().to_lowercase();
+            if !valid_days.contains(&day.as_str()) {
+                return Err(format!("invalid day '{day}' in schedule '{schedule}'"));
+            }
+        }
+        validate_time(time.trim())?;
+        Ok(())
+    } else if let Some(rest) = schedule.strip_prefix("monthly:") {
+        let Some((days, time)) = rest.rsplit_once(',') else {
+            return Err(format!(
+                "invalid monthly schedule '{schedule}': expected 'monthly:D[,D...],HH:MM'"
+            ));
+        };
+        for day in days.split(',') {
+            if !matches!(day.trim().parse::<u32>(), Ok(1..=31)) {
+                return Err(format!(
+                    "invalid day of month '{}' in schedule '{schedule}': expected 1-31",
+                    day.trim()
+                ));
+            }
         }
-        validate_time(parts[1].trim())?;
+        validate_time(time.trim())?;
         Ok(())
     } else if let Some(expr) = schedule.strip_prefix("cron:") {
         // Validate by parsing with the cron crate (7-field: prepend seconds, append year)
@@ -486,7 +3186,7 @@ fn validate_schedule(schedule: &str) -> Result<(), String> {
         Ok(())
     } else {
         Err(format!(
-            "invalid schedule '{schedule}': must start with 'at:', 'weekly:', or 'cron:'"
+            "invalid schedule '{schedule}': must start with 'at:', 'weekly:', 'monthly:', or 'cron:'"
         ))
     }
 }
@@ -1,12 +1,16 @@
 use std::collections::HashSet;
+use std::path::Path;
 
 use anyhow::{Context, Result};
-use toml_edit::{Array, DocumentMut, Formatted, Item, Table, Value};
+use toml_edit::{Array, ArrayOfTables, DocumentMut, Formatted, Item, Table, Value};
+
+use crate::config::{is_mtproto_source, load_config, validate_config};
 
 /// A new source to add to the config file.
 pub struct NewSource {
     pub name: String,
     pub source_type: String,
+    pub url: Option<String>,
     pub tg_username: Option<String>,
     pub tg_id: Option<i64>,
     pub tg_folder_name: Option<String>,
@@ -32,6 +36,10 @@ pub fn add_source(doc: &mut DocumentMut, source: &NewSource) {
     table.insert("name", toml_edit::value(&source.name));
     table.insert("type", toml_edit::value(&source.source_type));
 
+    if let Some(ref url) = source.url {
+        table.insert("url", toml_edit::value(url));
+    }
+
     if let Some(ref username) = source.tg_username {
         table.insert("tg_username", toml_edit::value(username));
     }
@@ -80,11 +88,256 @@ pub fn remove_source(doc: &mut DocumentMut, source_name: &str) -> bool {
     }
 }
 
+/// Set a source's `enabled` flag. Returns true if the source was found.
+pub fn set_source_enabled(doc: &mut DocumentMut, source_name: &str, enabled: bool) -> bool {
+    let Some(sources) = doc.get_mut("source").and_then(|v| v.as_array_of_tables_mut()) else {
+        return false;
+    };
+
+    for source in sources.iter_mut() {
+        if source.get("name").and_then(|v| v.as_str()) == Some(source_name) {
+            source.insert("enabled", toml_edit::value(enabled));
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Set a source's `url`, keeping the value's surrounding formatting. Returns true if the
+/// source was found.
+pub fn set_source_url(doc: &mut DocumentMut, source_name: &str, url: &str) -> bool {
+    let Some(sources) = doc.get_mut("source").and_then(|v| v.as_array_of_tables_mut()) else {
+        return false;
+    };
+
+    for source in sources.iter_mut() {
+        if source.get("name").and_then(|v| v.as_str()) == Some(source_name) {
+            match source.get_mut("url").and_then(|v| v.as_value_mut()) {
+                Some(value) => {
+                    let decor = value.decor().clone();
+                    *value = url.into();
+                    *value.decor_mut() = decor;
+                }
+                None => {
+                    source.insert("url", toml_edit::value(url));
+                }
+            }
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Remove a source name from every output channel's `sources` array.
+/// Returns the names of the channels that referenced it.
+pub fn remove_source_from_channels(doc: &mut DocumentMut, source_name: &str) -> Vec<String> {
+    let mut affected = Vec::new();
+
+    let Some(channels) = doc.get_mut("output_channel").and_then(|v| v.as_array_of_tables_mut()) else {
+        return affected;
+    };
+
+    for channel in channels.iter_mut() {
+        let channel_name = channel
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let Some(sources) = channel.get_mut("sources").and_then(|v| v.as_array_mut()) else {
+            continue;
+        };
+
+        let before = sources.len();
+        sources.retain(|v| v.as_str() != Some(source_name));
+        if sources.len() != before {
+            affected.push(channel_name);
+        }
+    }
+
+    affected
+}
+
+/// Append a source name to the `sources` array of the channel with the given slug.
+/// Returns true if the channel was found.
+pub fn add_source_to_channel(doc: &mut DocumentMut, channel_slug: &str, source_name: &str) -> bool {
+    let Some(channels) = doc.get_mut("output_channel").and_then(|v| v.as_array_of_tables_mut()) else {
+        return false;
+    };
+
+    for channel in channels.iter_mut() {
+        if channel.get("slug").and_then(|v| v.as_str()) == Some(channel_slug) {
+            match channel.get_mut("sources").and_then(|v| v.as_array_mut()) {
+                Some(sources) => {
+                    if !sources.iter().any(|v| v.as_str() == Some(source_name)) {
+                        sources.push(source_name);
+                    }
+                }
+                None => {
+                    let mut arr = Array::new();
+                    arr.push(source_name);
+                    channel.insert("sources", toml_edit::value(arr));
+                }
+            }
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Append a `[[<array>]]` table built from JSON fields (e.g. `source`, `output_channel`).
+/// `lead` keys come first in the given order, the rest follow alphabetically; nested objects
+/// become inline tables. Null fields are left out.
+pub fn add_table_from_json(
+    doc: &mut DocumentMut,
+    array: &str,
+    lead: &[&str],
+    fields: &serde_json::Map<String, serde_json::Value>,
+) -> Result<()> {
+    let mut table = Table::new();
+    table.set_implicit(true);
+    let rest = fields
+        .keys()
+        .filter(|k| !lead.contains(&k.as_str()))
+        .map(String::as_str);
+    for key in lead.iter().copied().chain(rest) {
+        if let Some(value) = fields.get(key).and_then(|v| json_to_toml(v).transpose()) {
+            table.insert(key, Item::Value(value.with_context(|| format!("field '{key}'"))?));
+        }
+    }
+
+    let tables = doc
+        .entry(array)
+        .or_insert_with(|| Item::ArrayOfTables(toml_edit::ArrayOfTables::new()))
+        .as_array_of_tables_mut()
+        .with_context(|| format!("'{array}' is not an array of tables"))?;
+    tables.push(table);
+    Ok(())
+}
+
+/// Set fields of the `[[<array>]]` table whose `key_field` equals `key`, from JSON. Null
+/// removes the field; existing values keep their surrounding formatting. Returns false if no
+/// such table exists.
+pub fn update_table_from_json(
+    doc: &mut DocumentMut,
+    array: &str,
+    key_field: &str,
+    key: &str,
+    fields: &serde_json::Map<String, serde_json::Value>,
+) -> Result<bool> {
+    let Some(tables) = doc.get_mut(array).and_then(|v| v.as_array_of_tables_mut()) else {
+        return Ok(false);
+    };
+    let Some(table) = tables
+        .iter_mut()
+        .find(|t| t.get(key_field).and_then(|v| v.as_str()) == Some(key))
+    else {
+        return Ok(false);
+    };
+
+    for (field, json) in fields {
+        let Some(value) = json_to_toml(json).with_context(|| format!("field '{field}'"))? else {
+            table.remove(field);
+            continue;
+        };
+        match table.get_mut(field).and_then(|v| v.as_value_mut()) {
+            Some(existing) => {
+                let decor = existing.decor().clone();
+                *existing = value;
+                *existing.decor_mut() = decor;
+            }
+            None => {
+                table.insert(field, Item::Value(value));
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// A JSON value as TOML (`None` for null). TOML has no null, so arrays and objects can't
+/// contain one.
+fn json_to_toml(json: &serde_json::Value) -> Result<Option<Value>> {
+    let value = match json {
+        serde_json::Value::Null => return Ok(None),
+        serde_json::Value::Bool(b) => Value::Boolean(Formatted::new(*b)),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(Formatted::new(i)),
+            None => Value::Float(Formatted::new(n.as_f64().context("number out of range")?)),
+        },
+        serde_json::Value::String(s) => Value::String(Formatted::new(s.clone())),
+        serde_json::Value::Array(items) => {
+            let mut array = Array::new();
+            for item in items {
+                array.push(json_to_toml(item)?.context("null inside an array")?);
+            }
+            Value::Array(array)
+        }
+        serde_json::Value::Object(fields) => {
+            let mut table = toml_edit::InlineTable::new();
+            for (key, item) in fields {
+                let value = json_to_toml(item)?.with_context(|| format!("null in '{key}'"))?;
+                table.insert(key, value);
+            }
+            Value::InlineTable(table)
+        }
+    };
+    Ok(Some(value))
+}
+
 /// Render the document back to a TOML string.
 pub fn render(doc: &DocumentMut) -> String {
     doc.to_string()
 }
 
+/// Write new content to config, validate, rollback on failure, and show diff.
+pub fn write_with_validation(config_path: &Path, original: &str, new_content: &str) -> Result<()> {
+    write_validated(config_path, original, new_content)?;
+    show_diff(original, new_content);
+    Ok(())
+}
+
+/// Write new content to config and validate it, restoring the original on failure.
+pub fn write_validated(config_path: &Path, original: &str, new_content: &str) -> Result<()> {
+    std::fs::write(config_path, new_content).context("writing config file")?;
+
+    match load_config(config_path).and_then(|cfg| validate_config(&cfg).map(|()| cfg)) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            std::fs::write(config_path, original).context("restoring config backup")?;
+            Err(e).context("config validation failed after write — restored original")
+        }
+    }
+}
+
+/// Show a simple diff between old and new content.
+fn show_diff(old: &str, new: &str) {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    let mut has_changes = false;
+
+    for line in &new_lines {
+        if !old_lines.contains(line) {
+            if !has_changes {
+                println!("\nChanges:");
+                has_changes = true;
+            }
+            println!("  + {line}");
+        }
+    }
+    for line in &old_lines {
+        if !new_lines.contains(line) {
+            if !has_changes {
+                println!("\nChanges:");
+                has_changes = true;
+            }
+            println!("  - {line}");
+        }
+    }
+}
+
 /// Get source names matching a predicate on source type.
 fn get_sources_matching(doc: &DocumentMut, predicate: impl Fn(&str) -> bool) -> Vec<String> {
     let mut names = Vec::new();
@@ -168,6 +421,36 @@ pub fn set_channel_sources(doc: &mut DocumentMut, channel_name: &str, sources: &
     false
 }
 
+/// A copy of the channel with slug `slug` (sub-tables like `postprocess` included) under a new
+/// slug and name, with `prompt` replaced if given, rendered as a `[[output_channel]]` block to
+/// append to the file. Returns `None` if there's no such channel.
+pub fn clone_channel(
+    doc: &DocumentMut,
+    slug: &str,
+    new_slug: &str,
+    new_name: &str,
+    prompt: Option<&str>,
+) -> Option<String> {
+    let channels = doc.get("output_channel").and_then(|v| v.as_array_of_tables())?;
+    let mut table = channels
+        .iter()
+        .find(|ch| ch.get("slug").and_then(|v| v.as_str()) == Some(slug))?
+        .clone();
+    // Comments above the original describe the original
+    table.decor_mut().clear();
+    table.insert("name", toml_edit::value(new_name));
+    table.insert("slug", toml_edit::value(new_slug));
+    if let Some(prompt) = prompt {
+        table.insert("prompt", toml_edit::value(prompt));
+    }
+
+    let mut array = ArrayOfTables::new();
+    array.push(table);
+    let mut fragment = DocumentMut::new();
+    fragment.insert("output_channel", Item::ArrayOfTables(array));
+    Some(fragment.to_string())
+}
+
 /// Get detailed metadata for all Telegram sources (channels, groups, and folders).
 pub fn get_tg_sources_detailed(doc: &DocumentMut) -> Vec<TgSourceInfo> {
     let Some(sources) = doc.get("source").and_then(|v| v.as_array_of_tables()) else {
@@ -179,7 +462,7 @@ pub fn get_tg_sources_detailed(doc: &DocumentMut) -> Vec<TgSourceInfo> {
         .filter_map(|source| {
             let name = source.get("name").and_then(|v| v.as_str())?.to_string();
             let source_type = source.get("type").and_then(|v| v.as_str())?;
-            if !source_type.starts_with("telegram_") {
+            if !is_mtproto_source(source_type) {
                 return None;
             }
 
@@ -279,6 +562,7 @@ prompt = "Write a digest"
         let source = NewSource {
             name: "New Channel".to_string(),
             source_type: "telegram_channel".to_string(),
+            url: None,
             tg_username: Some("new_channel".to_string()),
             tg_id: Some(12345),
             tg_folder_name: None,
@@ -297,6 +581,55 @@ prompt = "Write a digest"
         assert!(rendered.contains("# My sources"));
     }
 
+    #[test]
+    fn test_add_table_from_json() {
+        let mut doc = parse_document(SAMPLE_CONFIG).unwrap();
+        let fields = serde_json::json!({
+            "type": "imap",
+            "name": "Newsletters",
+            "description": null,
+            "imap": { "host": "imap.example.com", "port": 993 },
+            "languages": ["en"],
+        });
+
+        add_table_from_json(&mut doc, "source", &["name", "type"], fields.as_object().unwrap()).unwrap();
+
+        let names = get_all_source_names(&doc);
+        assert!(names.contains(&"Newsletters".to_string()));
+        let rendered = render(&doc);
+        assert!(rendered.contains("name = \"Newsletters\"\ntype = \"imap\"\nimap = {"));
+        assert!(rendered.contains("port = 993"));
+        assert!(!rendered.contains("description = \"\""));
+        // Parses back as a config table
+        assert!(parse_document(&rendered).is_ok());
+    }
+
+    #[test]
+    fn test_update_table_from_json() {
+        let mut doc = parse_document(SAMPLE_CONFIG).unwrap();
+        let fields = serde_json::json!({ "url": "https://hnrss.org/best", "enabled": false, "description": null });
+
+        assert!(update_table_from_json(&mut doc, "source", "name", "Hacker News", fields.as_object().unwrap()).unwrap());
+        assert!(!update_table_from_json(&mut doc, "source", "name", "Missing", fields.as_object().unwrap()).unwrap());
+
+        let rendered = render(&doc);
+        assert!(rendered.contains("url = \"https://hnrss.org/best\""));
+        assert!(rendered.contains("enabled = false"));
+        // Other sources untouched
+        assert!(rendered.contains("description = \"Ukrainian tech news\""));
+
+        let removed = serde_json::json!({ "description": null });
+        update_table_from_json(&mut doc, "source", "name", "Tech Ukraine", removed.as_object().unwrap()).unwrap();
+        assert!(!render(&doc).contains("Ukrainian tech news"));
+    }
+
+    #[test]
+    fn test_json_null_in_array_rejected() {
+        let mut doc = parse_document(SAMPLE_CONFIG).unwrap();
+        let fields = serde_json::json!({ "name": "Bad", "type": "rss", "languages": [null] });
+        assert!(add_table_from_json(&mut doc, "source", &["name", "type"], fields.as_object().unwrap()).is_err());
+    }
+
     #[test]
     fn test_remove_source() {
         let mut doc = parse_document(SAMPLE_CONFIG).unwrap();
@@ -330,6 +663,7 @@ prompt = "Write a digest"
         let source = NewSource {
             name: "Added".to_string(),
             source_type: "telegram_channel".to_string(),
+            url: None,
             tg_username: Some("added".to_string()),
             tg_id: None,
             tg_folder_name: None,
@@ -348,6 +682,7 @@ prompt = "Write a digest"
         let source = NewSource {
             name: "First".to_string(),
             source_type: "telegram_channel".to_string(),
+            url: None,
             tg_username: Some("first".to_string()),
             tg_id: None,
             tg_folder_name: None,
@@ -361,6 +696,76 @@ prompt = "Write a digest"
         assert_eq!(names, vec!["First"]);
     }
 
+    #[test]
+    fn test_add_rss_source_with_url() {
+        let mut doc = parse_document(SAMPLE_CONFIG).unwrap();
+        let source = NewSource {
+            name: "Lobsters".to_string(),
+            source_type: "rss".to_string(),
+            url: Some("https://lobste.rs/rss".to_string()),
+            tg_username: None,
+            tg_id: None,
+            tg_folder_name: None,
+            description: None,
+        };
+
+        add_source(&mut doc, &source);
+
+        let rendered = render(&doc);
+        assert!(rendered.contains("url = \"https://lobste.rs/rss\""));
+    }
+
+    #[test]
+    fn test_set_source_enabled() {
+        let mut doc = parse_document(SAMPLE_CONFIG).unwrap();
+        assert!(set_source_enabled(&mut doc, "Hacker News", false));
+        assert!(!set_source_enabled(&mut doc, "Nonexistent", false));
+
+        let sources = doc["source"].as_array_of_tables().unwrap();
+        let hn = sources
+            .iter()
+            .find(|s| s["name"].as_str() == Some("Hacker News"))
+            .unwrap();
+        assert_eq!(hn["enabled"].as_bool(), Some(false));
+        assert!(render(&doc).contains("# My sources"));
+    }
+
+    #[test]
+    fn test_set_source_url() {
+        let mut doc = parse_document(SAMPLE_CONFIG).unwrap();
+        assert!(set_source_url(&mut doc, "Hacker News", "https://hnrss.org/newest"));
+        assert!(!set_source_url(&mut doc, "Nonexistent", "https://example.com/feed"));
+
+        let rendered = render(&doc);
+        assert!(rendered.contains("url = \"https://hnrss.org/newest\"\n"));
+        assert!(!rendered.contains("frontpage"));
+        assert!(rendered.contains("# My sources"));
+    }
+
+    #[test]
+    fn test_remove_source_from_channels() {
+        let mut doc = parse_document(SAMPLE_CONFIG).unwrap();
+        let affected = remove_source_from_channels(&mut doc, "Hacker News");
+        assert_eq!(affected, vec!["Tech Digest"]);
+
+        let sources = get_channel_sources(&doc, "Tech Digest");
+        assert_eq!(sources, vec!["Tech Ukraine", "My Folder"]);
+
+        assert!(remove_source_from_channels(&mut doc, "Hacker News").is_empty());
+    }
+
+    #[test]
+    fn test_add_source_to_channel() {
+        let mut doc = parse_document(SAMPLE_CONFIG).unwrap();
+        assert!(add_source_to_channel(&mut doc, "tech-digest", "Lobsters"));
+        // Adding twice doesn't duplicate
+        assert!(add_source_to_channel(&mut doc, "tech-digest", "Lobsters"));
+        assert!(!add_source_to_channel(&mut doc, "nonexistent", "Lobsters"));
+
+        let sources = get_channel_sources(&doc, "Tech Digest");
+        assert_eq!(sources, vec!["Tech Ukraine", "Hacker News", "My Folder", "Lobsters"]);
+    }
+
     #[test]
     fn test_get_output_channel_names() {
         let doc = parse_document(SAMPLE_CONFIG).unwrap();
@@ -476,4 +881,41 @@ prompt = "test"
         assert!(names.contains("B"));
         assert!(!names.contains("Orphan"));
     }
+
+    #[test]
+    fn test_clone_channel() {
+        let config = r#"
+# The main digest
+[[output_channel]]
+name = "Tech Digest"
+slug = "tech-digest"
+sources = ["Hacker News"]
+prompt = "Write a digest"
+
+[output_channel.postprocess]
+max_topics = 5
+"#;
+        let doc = parse_document(config).unwrap();
+        let fragment = clone_channel(
+            &doc,
+            "tech-digest",
+            "tech-weekly",
+            "Tech Weekly",
+            Some("Weekly\nroundup"),
+        )
+        .unwrap();
+        assert!(!fragment.contains("# The main digest"));
+
+        let cloned = parse_document(&format!("{config}\n{fragment}")).unwrap();
+        let channels = cloned["output_channel"].as_array_of_tables().unwrap();
+        assert_eq!(channels.len(), 2);
+        let copy = channels.get(1).unwrap();
+        assert_eq!(copy["slug"].as_str(), Some("tech-weekly"));
+        assert_eq!(copy["name"].as_str(), Some("Tech Weekly"));
+        assert_eq!(copy["prompt"].as_str(), Some("Weekly\nroundup"));
+        assert_eq!(copy["postprocess"]["max_topics"].as_integer(), Some(5));
+        assert_eq!(get_channel_sources(&cloned, "Tech Weekly"), vec!["Hacker News"]);
+
+        assert!(clone_channel(&doc, "missing", "x", "X", None).is_none());
+    }
 }
@@ -0,0 +1,477 @@
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::SqlitePool;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, watch};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::config::{Config, ControlEndpoint, load_config, validate_config};
+use crate::daemon::Role;
+use crate::health::{self, Health};
+use crate::scheduler::{GenerationRequest, Schedule};
+use crate::strategy::{self, StrategyRegistry};
+use crate::{pipeline, poller, scheduler, server, store};
+
+/// A request line is one small JSON object; anything longer is a misbehaving client.
+const MAX_REQUEST_BYTES: u64 = 64 * 1024;
+
+/// `status` lists sources skipped in at least this many articles within `SKIP_REPORT_DAYS`.
+const SKIP_REPORT_MIN_ARTICLES: i64 = 3;
+const SKIP_REPORT_DAYS: i64 = 30;
+
+/// One command sent over the control socket (see docs/specs/control-socket.md).
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum ControlRequest {
+    /// Queue an on-demand generation. `since` is a duration ("12h"); without it the
+    /// scheduled window is used (last_generated → now).
+    Generate { slug: String, since: Option<String> },
+    /// Re-read the config file and apply it without restarting.
+    Reload,
+    /// Stop scheduled generations for a channel.
+    Pause { slug: String },
+    /// Resume scheduled generations for a paused channel.
+    Resume { slug: String },
+    /// Daemon health and per-channel schedule state.
+    Status,
+}
+
+/// Wire format of a request: the command plus the feed token (required over TCP).
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+    #[serde(flatten)]
+    request: ControlRequest,
+}
+
+/// Everything the control server needs from the daemon.
+#[derive(Clone)]
+pub struct ControlState {
+    pub pool: SqlitePool,
+    pub config_path: PathBuf,
+    pub config_tx: Arc<watch::Sender<Arc<Config>>>,
+    pub registry: Arc<StrategyRegistry>,
+    pub generation_tx: mpsc::Sender<GenerationRequest>,
+    pub health: Arc<Health>,
+    pub feed_token: String,
+    pub started_at: DateTime<Utc>,
+    pub role: Role,
+}
+
+pub enum ControlListener {
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener, PathBuf),
+    Tcp(tokio::net::TcpListener),
+}
+
+/// Bind the control endpoint. A leftover Unix socket file is removed, unless another daemon
+/// is still answering on it.
+pub async fn bind(endpoint: &ControlEndpoint) -> Result<ControlListener> {
+    match endpoint {
+        #[cfg(unix)]
+        ControlEndpoint::Unix(path) => {
+            if path.exists() {
+                if tokio::net::UnixStream::connect(path).await.is_ok() {
+                    anyhow::bail!(
+                        "control socket {} is in use (is another daemon running?)",
+                        path.display()
+                    );
+                }
+                std::fs::remove_file(path).with_context(|| format!("removing stale socket {}", path.display()))?;
+            }
+            // Owner-only: file permissions are the socket's authentication. The socket is bound
+            // inside a fresh 0700 directory and narrowed there, then renamed into place, so it
+            // is never reachable with the umask's default mode.
+            let parent = path
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or(Path::new("."));
+            let staging = tempfile::Builder::new()
+                .prefix(".pail-ctl-")
+                .tempdir_in(parent)
+                .with_context(|| format!("creating a staging directory in {}", parent.display()))?;
+            let staged = staging.path().join("control.sock");
+            let listener = tokio::net::UnixListener::bind(&staged)
+                .with_context(|| format!("binding control socket {}", path.display()))?;
+            std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))
+                .with_context(|| format!("setting permissions on {}", staged.display()))?;
+            std::fs::rename(&staged, path).with_context(|| format!("moving control socket to {}", path.display()))?;
+            Ok(ControlListener::Unix(listener, path.clone()))
+        }
+        #[cfg(not(unix))]
+        ControlEndpoint::Unix(path) => {
+            anyhow::bail!(
+                "Unix control sockets are not supported on this platform: {}",
+                path.display()
+            )
+        }
+        ControlEndpoint::Tcp(addr) => {
+            let listener = tokio::net::TcpListener::bind(addr)
+                .await
+                .with_context(|| format!("binding control socket tcp:{addr}"))?;
+            Ok(ControlListener::Tcp(listener))
+        }
+    }
+}
+
+/// Accept control connections until shutdown. Each connection may send several requests,
+/// one JSON object per line; each gets one JSON response line.
+pub async fn serve(listener: ControlListener, state: ControlState, cancel: CancellationToken) {
+    info!("control socket started");
+    loop {
+        match &listener {
+            #[cfg(unix)]
+            ControlListener::Unix(unix, _) => {
+                let accepted = tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    accepted = unix.accept() => accepted,
+                };
+                match accepted {
+                    Ok((stream, _)) => {
+                        tokio::spawn(handle_connection(stream, state.clone(), false));
+                    }
+                    Err(e) => warn!(error = %e, "control socket accept failed"),
+                }
+            }
+            ControlListener::Tcp(tcp) => {
+                let accepted = tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    accepted = tcp.accept() => accepted,
+                };
+                match accepted {
+                    Ok((stream, peer)) => {
+                        debug!(peer = %peer, "control connection");
+                        tokio::spawn(handle_connection(stream, state.clone(), true));
+                    }
+                    Err(e) => warn!(error = %e, "control socket accept failed"),
+                }
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    if let ControlListener::Unix(_, path) = &listener {
+        let _ = std::fs::remove_file(path);
+    }
+    info!("control socket shutting down");
+}
+
+async fn handle_connection<S>(stream: S, state: ControlState, require_token: bool)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (read, mut write) = tokio::io::split(stream);
+    let mut reader = BufReader::new(read.take(MAX_REQUEST_BYTES));
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        match reader.read_line(&mut line).await {
+            Ok(0) => return,
+            Ok(_) => {}
+            Err(e) => {
+                debug!(error = %e, "control connection read failed");
+                return;
+            }
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Envelope>(&line) {
+            Err(e) => json!({ "ok": false, "error": format!("invalid request: {e}") }),
+            Ok(envelope)
+                if require_token
+                    && !envelope
+                        .token
+                        .as_deref()
+                        .is_some_and(|t| server::constant_time_eq(t, &state.feed_token)) =>
+            {
+                json!({ "ok": false, "error": "unauthorized" })
+            }
+            Ok(envelope) => match dispatch(&state, envelope.request).await {
+                Ok(result) => json!({ "ok": true, "result": result }),
+                Err(e) => json!({ "ok": false, "error": format!("{e:#}") }),
+            },
+        };
+
+        let mut out = response.to_string();
+        out.push('\n');
+        if write.write_all(out.as_bytes()).await.is_err() {
+            return;
+        }
+        // The request budget is per line, not per connection
+        reader.get_mut().set_limit(MAX_REQUEST_BYTES);
+    }
+}
+
+async fn dispatch(state: &ControlState, request: ControlRequest) -> Result<Value> {
+    debug!(?request, "control request");
+    match request {
+        ControlRequest::Generate { slug, since } => {
+            let config = state.config_tx.borrow().clone();
+            if !config.output_channel.iter().any(|c| c.slug == slug) {
+                anyhow::bail!("unknown channel '{slug}'");
+            }
+            let window = since
+                .map(|s| {
+                    humantime::parse_duration(&s)
+                        .map(pipeline::TimeWindow::Since)
+                        .with_context(|| format!("invalid since duration: '{s}'"))
+                })
+                .transpose()?;
+            state
+                .generation_tx
+                .try_send(GenerationRequest {
+                    slug: slug.clone(),
+                    window,
+                    replaces_draft: None,
+                })
+                .map_err(|_| anyhow::anyhow!("generation queue is full, try again later"))?;
+            info!(slug = %slug, "generation requested via control socket");
+            Ok(json!({ "queued": slug }))
+        }
+        ControlRequest::Reload => reload(state).await,
+        ControlRequest::Pause { slug } => set_paused(state, &slug, true).await,
+        ControlRequest::Resume { slug } => set_paused(state, &slug, false).await,
+        ControlRequest::Status => status(state).await,
+    }
+}
+
+async fn set_paused(state: &ControlState, slug: &str, paused: bool) -> Result<Value> {
+    if !store::set_channel_paused(&state.pool, slug, paused).await? {
+        anyhow::bail!("unknown channel '{slug}'");
+    }
+    info!(slug = %slug, paused, "channel pause state changed via control socket");
+    Ok(json!({ "slug": slug, "paused": paused }))
+}
+
+async fn reload(state: &ControlState) -> Result<Value> {
+    let restart_required = apply_config(&state.pool, &state.config_path, &state.registry, &state.config_tx).await?;
+    Ok(json!({ "reloaded": true, "restart_required": restart_required }))
+}
+
+/// Load, validate, and apply the config file. The running config is only replaced once
+/// every check (and the DB sync) has passed, so a broken edit leaves the daemon as it was.
+/// Returns the changed settings that only take effect after a restart. Also used by the admin
+/// API after it edits the file.
+pub(crate) async fn apply_config(
+    pool: &SqlitePool,
+    config_path: &Path,
+    registry: &StrategyRegistry,
+    config_tx: &watch::Sender<Arc<Config>>,
+) -> Result<Vec<&'static str>> {
+    let config = load_config(config_path).context("loading config")?;
+    validate_config(&config).context("config validation failed")?;
+    strategy::validate_strategy_config(&config, registry).context("strategy validation failed")?;
+
+    store::sync_config_to_db(pool, &config)
+        .await
+        .context("syncing config to database")?;
+
+    let previous = config_tx.borrow().clone();
+    let restart_required = restart_only_changes(&previous, &config);
+    config_tx.send_replace(Arc::new(config));
+
+    if restart_required.is_empty() {
+        info!(path = %config_path.display(), "config reloaded");
+    } else {
+        warn!(
+            path = %config_path.display(),
+            fields = ?restart_required,
+            "config reloaded; some changes only take effect after a restart"
+        );
+    }
+    Ok(restart_required)
+}
+
+/// Settings read once at startup: a reload accepts new values but the daemon keeps the old ones.
+fn restart_only_changes(old: &Config, new: &Config) -> Vec<&'static str> {
+    let mut changed = Vec::new();
+    if old.pail.listen != new.pail.listen {
+        changed.push("pail.listen");
+    }
+    if old.pail.control_socket != new.pail.control_socket {
+        changed.push("pail.control_socket");
+    }
+    if old.pail.data_dir != new.pail.data_dir || old.database.path != new.database.path {
+        changed.push("database.path");
+    }
+    if old.pail.feed_token != new.pail.feed_token {
+        changed.push("pail.feed_token");
+    }
+    if old.pail.max_concurrent_generations != new.pail.max_concurrent_generations {
+        changed.push("pail.max_concurrent_generations");
+    }
+    if old.pail.strategies_dir != new.pail.strategies_dir {
+        changed.push("pail.strategies_dir");
+    }
+    if old.pail.timezone != new.pail.timezone {
+        changed.push("pail.timezone");
+    }
+    if old.telegram.enabled != new.telegram.enabled
+        || old.telegram.api_id != new.telegram.api_id
+        || old.telegram.api_hash != new.telegram.api_hash
+        || old.telegram.accounts != new.telegram.accounts
+        || old.telegram.write_queue != new.telegram.write_queue
+        || old.tg_accounts_in_use() != new.tg_accounts_in_use()
+    {
+        changed.push("telegram");
+    }
+    if old.reporting != new.reporting {
+        changed.push("reporting");
+    }
+    changed
+}
+
+async fn status(state: &ControlState) -> Result<Value> {
+    let now = Utc::now();
+    let config = state.config_tx.borrow().clone();
+    let tz: Tz = config.pail.timezone.parse().unwrap_or(chrono_tz::UTC);
+    let snapshot = state.health.snapshot();
+    // `null` for loops this instance doesn't run (see docs/specs/daemon.md "Roles")
+    let runs_jobs = state.role.runs_jobs();
+    let scheduler_alive = runs_jobs.then(|| {
+        health::is_alive(
+            snapshot.scheduler_tick,
+            scheduler::TICK_SECS as i64,
+            state.started_at,
+            now,
+        )
+    });
+    let poller_alive =
+        runs_jobs.then(|| health::is_alive(snapshot.poller_tick, poller::TICK_SECS as i64, state.started_at, now));
+
+    let channels = store::get_all_enabled_channels(&state.pool).await?;
+    let paused = store::get_paused_channel_ids(&state.pool).await?;
+    let fmt = |dt: Option<DateTime<Utc>>| dt.map(|d| d.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+
+    let mut channel_status = Vec::with_capacity(channels.len());
+    for c in &channels {
+        let is_paused = paused.contains(&c.id);
+        let next_run = c
+            .schedule
+            .as_deref()
+            .filter(|_| !is_paused)
+            .and_then(|s| Schedule::parse(s).ok())
+            .and_then(|s| s.next_tick(tz, now));
+        let coverage = store::get_latest_article_coverage(&state.pool, &c.id)
+            .await?
+            .map(|(cited, provided)| json!({ "cited": cited, "provided": provided }));
+        channel_status.push(json!({
+            "slug": c.slug,
+            "name": c.name,
+            "schedule": c.schedule,
+            "paused": is_paused,
+            "last_generated": fmt(c.last_generated),
+            "next_run": fmt(next_run),
+            "coverage": coverage,
+        }));
+    }
+
+    let skipped: Vec<Value> = store::get_repeatedly_skipped_sources(
+        &state.pool,
+        now - chrono::Duration::days(SKIP_REPORT_DAYS),
+        SKIP_REPORT_MIN_ARTICLES,
+    )
+    .await?
+    .into_iter()
+    .map(|(source, articles, items, reason)| {
+        json!({ "source": source, "articles": articles, "items": items, "last_reason": reason })
+    })
+    .collect();
+
+    Ok(json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "started_at": fmt(Some(state.started_at)),
+        "uptime_secs": (now - state.started_at).num_seconds(),
+        "role": state.role.as_str(),
+        "scheduler": {
+            "alive": scheduler_alive,
+            "last_tick": fmt(snapshot.scheduler_tick),
+        },
+        "poller": {
+            "alive": poller_alive,
+            "last_tick": fmt(snapshot.poller_tick),
+        },
+        "telegram": {
+            "enabled": snapshot.telegram.enabled,
+            "connected": snapshot.telegram.connected(),
+            "authorized": snapshot.telegram.authorized(),
+            "error": snapshot.telegram.error(),
+            "accounts": snapshot.telegram.accounts.keys().collect::<Vec<_>>(),
+        },
+        "channels": channel_status,
+        "skipped_sources": skipped,
+    }))
+}
+
+/// Send one request to the running daemon and return its `result`. Used by `pail ctl`.
+pub async fn send(config: &Config, request: ControlRequest) -> Result<Value> {
+    let endpoint = config
+        .control_endpoint()
+        .ok_or_else(|| anyhow::anyhow!("the control socket is disabled (pail.control_socket = \"\")"))?;
+
+    // The socket's file permissions authenticate Unix connections; TCP needs the feed token
+    let token = match endpoint {
+        ControlEndpoint::Tcp(_) => config.pail.feed_token.clone(),
+        ControlEndpoint::Unix(_) => None,
+    };
+    let mut line = serde_json::to_string(&Envelope { token, request })?;
+    line.push('\n');
+
+    let response = match &endpoint {
+        #[cfg(unix)]
+        ControlEndpoint::Unix(path) => {
+            let stream = tokio::net::UnixStream::connect(path)
+                .await
+                .with_context(|| format!("connecting to {endpoint} (is the daemon running?)"))?;
+            roundtrip(stream, &line).await?
+        }
+        #[cfg(not(unix))]
+        ControlEndpoint::Unix(_) => anyhow::bail!("Unix control sockets are not supported on this platform"),
+        ControlEndpoint::Tcp(addr) => {
+            let stream = tokio::net::TcpStream::connect(addr)
+                .await
+                .with_context(|| format!("connecting to {endpoint} (is the daemon running?)"))?;
+            roundtrip(stream, &line).await?
+        }
+    };
+
+    if response["ok"].as_bool() == Some(true) {
+        Ok(response["result"].clone())
+    } else {
+        let error = response["error"].as_str().unwrap_or("unknown error");
+        anyhow::bail!("daemon: {error}")
+    }
+}
+
+async fn roundtrip<S>(stream: S, line: &str) -> Result<Value>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (read, mut write) = tokio::io::split(stream);
+    write
+        .write_all(line.as_bytes())
+        .await
+        .context("sending control request")?;
+    let mut response = String::new();
+    BufReader::new(read)
+        .read_line(&mut response)
+        .await
+        .context("reading control response")?;
+    if response.is_empty() {
+        anyhow::bail!("daemon closed the connection without responding");
+    }
+    serde_json::from_str(&response).context("parsing control response")
+}
@@ -1,26 +1,87 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use rand::Rng;
+use sentry::SentryFutureExt;
 use sqlx::SqlitePool;
-use tokio::sync::{RwLock, Semaphore};
+use tokio::sync::{RwLock, Semaphore, mpsc, watch};
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 
 use rand::distr::Alphanumeric;
 
-use crate::config::Config;
+use crate::config::{Config, DEFAULT_TG_ACCOUNT};
+use crate::health::Health;
 use crate::strategy::StrategyRegistry;
-use crate::{cleanup, db, generate, poller, scheduler, server, store, telegram, tg_listener};
+use crate::telegram::TgClients;
+use crate::{
+    cleanup, control, db, generate, instance_lock, notify, poller, reporting, scheduler, server, store, telegram,
+    tg_bot, tg_debug, tg_listener, webmention,
+};
+
+/// Which parts of the daemon an instance runs (see docs/specs/daemon.md "Roles").
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Role {
+    /// HTTP server only; on-demand generations are queued in the database for a worker.
+    Server,
+    /// Polling, Telegram, scheduling, generation, and cleanup; no HTTP server.
+    Worker,
+    /// Everything in one process.
+    All,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Server => "server",
+            Role::Worker => "worker",
+            Role::All => "all",
+        }
+    }
+
+    /// Whether this instance serves the HTTP feeds and pages.
+    pub fn serves_http(self) -> bool {
+        self != Role::Worker
+    }
+
+    /// Whether this instance runs the background loops and generations.
+    pub fn runs_jobs(self) -> bool {
+        self != Role::Server
+    }
+}
+
+pub async fn run(
+    config: Config,
+    config_path: PathBuf,
+    registry: StrategyRegistry,
+    auto_migrate: bool,
+    role: Role,
+) -> Result<()> {
+    info!(role = role.as_str(), "starting daemon");
+
+    // One instance runs jobs per database; the lock is held until `run` returns
+    let _instance_lock = if role.runs_jobs() {
+        Some(instance_lock::acquire(&config, &instance_id(), role.as_str())?)
+    } else {
+        None
+    };
 
-pub async fn run(config: Config, registry: StrategyRegistry) -> Result<()> {
     // Validate models early so provider auth issues surface at boot, not at first
     // scheduled generation (which would silently fail and produce Sentry noise).
-    generate::validate_models(&config)
-        .await
-        .context("model validation failed")?;
+    if role.runs_jobs() {
+        generate::validate_models(&config)
+            .await
+            .context("model validation failed")?;
+    }
 
-    let pool = db::create_pool(&config).await.context("creating database")?;
+    let pool = if auto_migrate {
+        db::create_pool(&config).await.context("creating database")?
+    } else {
+        let pool = db::connect(&config).await.context("opening database")?;
+        db::ensure_up_to_date(&pool).await?;
+        pool
+    };
     info!(db_path = %config.db_path().display(), "database ready");
 
     // Sync config to DB
@@ -33,61 +94,180 @@ pub async fn run(config: Config, registry: StrategyRegistry) -> Result<()> {
     let feed_token = bootstrap_feed_token(&pool, &config).await?;
 
     let config = Arc::new(config);
+    // Background tasks read the current config from here; `pail ctl reload` replaces it
+    let (config_tx, config_rx) = watch::channel(config.clone());
+    let config_tx = Arc::new(config_tx);
     let registry = Arc::new(registry);
     let cancel = CancellationToken::new();
     let semaphore = Arc::new(Semaphore::new(config.pail.max_concurrent_generations as usize));
-
-    // Start Telegram before the scheduler so the client is available for mark-as-read
-    let (tg_handle, tg_client) = if config.telegram.enabled {
-        match start_telegram(&config, &pool, cancel.clone()).await {
-            Ok((handle, client)) => (Some(handle), Some(client)),
-            Err(e) => {
-                error!(error = %e, "failed to start Telegram listener, continuing without TG");
-                (None, None)
+    let health = Arc::new(Health::default());
+    let started_at = chrono::Utc::now();
+
+    // Start Telegram before the scheduler so the clients are available for mark-as-read.
+    // One listener per account that enabled TG sources read with, plus the default account.
+    let mut tg_handles = Vec::new();
+    let mut tg_clients = TgClients::new();
+    let mut tg_failures = Vec::new();
+    if config.telegram.enabled && role.runs_jobs() {
+        health.update_telegram(|tg| tg.enabled = true);
+        let mut accounts = config.tg_accounts_in_use();
+        if config.telegram.account(DEFAULT_TG_ACCOUNT).is_some() {
+            accounts.insert(DEFAULT_TG_ACCOUNT.to_string());
+        }
+        // The account notifications are sent with needs a client even without sources
+        if let Some(ref account) = config.notifications.telegram_account {
+            accounts.insert(account.clone());
+        }
+        for account in accounts {
+            health.update_tg_account(&account, |_| {});
+            match start_telegram(&config, &pool, &account, health.clone(), cancel.clone()).await {
+                Ok((handle, client)) => {
+                    tg_handles.push(handle);
+                    tg_clients.insert(account, client);
+                }
+                Err(e) => {
+                    error!(account = %account, error = %e, "failed to start Telegram listener, continuing without it");
+                    health.update_tg_account(&account, |tg| tg.error = Some(format!("{e:#}")));
+                    tg_failures.push(format!("Telegram account '{account}': {e:#}"));
+                }
             }
         }
-    } else {
-        (None, None)
-    };
+    }
+    let tg_clients = Arc::new(tg_clients);
+    if !tg_failures.is_empty() {
+        notify::send(
+            &config,
+            &tg_clients,
+            notify::Event::StartupDegraded,
+            "pail: daemon started without Telegram",
+            &tg_failures.join("\n"),
+        )
+        .await;
+    }
 
-    // Spawn background tasks
-    let scheduler_handle = tokio::spawn(scheduler::scheduler_loop(
-        pool.clone(),
-        config.clone(),
-        registry,
-        semaphore.clone(),
-        tg_client,
-        cancel.clone(),
-    ));
+    // On-demand generations (e.g. draft regeneration) are sent to the scheduler
+    let (generation_tx, generation_rx) = mpsc::channel(scheduler::REQUEST_QUEUE_SIZE);
 
-    let poller_handle = tokio::spawn(poller::polling_loop(pool.clone(), cancel.clone()));
+    // Bind the control socket early: a socket still in use means another daemon is running
+    let control_listener = match config.control_endpoint() {
+        Some(endpoint) => {
+            let listener = control::bind(&endpoint).await?;
+            info!(endpoint = %endpoint, "control socket listening");
+            Some(listener)
+        }
+        None => None,
+    };
 
-    let cleanup_handle = tokio::spawn(cleanup::cleanup_loop(pool.clone(), config.clone(), cancel.clone()));
+    // Spawn background tasks
+    // Each task reports errors and panics to Sentry tagged with its component
+    let mut task_handles = Vec::new();
+    if role.runs_jobs() {
+        task_handles.push(tokio::spawn(
+            scheduler::scheduler_loop(
+                pool.clone(),
+                config_rx.clone(),
+                registry.clone(),
+                semaphore.clone(),
+                tg_clients.clone(),
+                generation_rx,
+                instance_id(),
+                health.clone(),
+                cancel.clone(),
+            )
+            .bind_hub(reporting::task_hub("scheduler", &[])),
+        ));
+
+        task_handles.push(tokio::spawn(
+            poller::polling_loop(
+                pool.clone(),
+                config_rx.clone(),
+                config_path.clone(),
+                tg_clients.clone(),
+                health.clone(),
+                cancel.clone(),
+            )
+            .bind_hub(reporting::task_hub("poller", &[])),
+        ));
+
+        task_handles.push(tokio::spawn(
+            cleanup::cleanup_loop(pool.clone(), config_rx.clone(), cancel.clone())
+                .bind_hub(reporting::task_hub("cleanup", &[])),
+        ));
+
+        task_handles.push(tokio::spawn(
+            webmention::send_loop(pool.clone(), config_rx.clone(), cancel.clone())
+                .bind_hub(reporting::task_hub("webmention", &[])),
+        ));
+
+        // Bot API sources; idles until a telegram_bot source and [telegram].bot_token exist
+        task_handles.push(tokio::spawn(
+            tg_bot::polling_loop(pool.clone(), config_rx.clone(), cancel.clone())
+                .bind_hub(reporting::task_hub("tg_bot", &[])),
+        ));
+    } else {
+        // No local scheduler: a worker instance picks requests up from the database
+        task_handles.push(tokio::spawn(
+            scheduler::forward_requests(pool.clone(), generation_rx, cancel.clone())
+                .bind_hub(reporting::task_hub("scheduler", &[])),
+        ));
+    }
 
     // Build and start HTTP server
     let timezone: chrono_tz::Tz = config.pail.timezone.parse().expect("timezone already validated");
-    let app_state = server::AppState {
-        pool: pool.clone(),
-        feed_token,
-        timezone,
-    };
-
-    let router = server::build_router(app_state);
-    let listener = tokio::net::TcpListener::bind(&config.pail.listen)
-        .await
-        .with_context(|| format!("binding to {}", config.pail.listen))?;
-
-    info!(listen = %config.pail.listen, "HTTP server listening");
+    let control_handle = control_listener.map(|listener| {
+        let state = control::ControlState {
+            pool: pool.clone(),
+            config_path: config_path.clone(),
+            config_tx: config_tx.clone(),
+            registry: registry.clone(),
+            generation_tx: generation_tx.clone(),
+            health: health.clone(),
+            feed_token: feed_token.clone(),
+            started_at,
+            role,
+        };
+        tokio::spawn(control::serve(listener, state, cancel.clone()))
+    });
 
-    // Run the server with graceful shutdown
-    let server_cancel = cancel.clone();
-    let server_handle = tokio::spawn(async move {
-        axum::serve(listener, router)
-            .with_graceful_shutdown(async move {
-                server_cancel.cancelled().await;
-            })
+    if role.serves_http() {
+        let app_state = server::AppState {
+            pool: pool.clone(),
+            config: config_rx,
+            feed_token,
+            csrf_key: rand::random(),
+            timezone,
+            health,
+            started_at,
+            generation_tx,
+            registry,
+            semaphore,
+            previews: Default::default(),
+            role,
+            config_path,
+            config_tx,
+            config_edit_lock: Default::default(),
+        };
+
+        let router = server::build_router(app_state);
+        let listener = tokio::net::TcpListener::bind(&config.pail.listen)
             .await
-    });
+            .with_context(|| format!("binding to {}", config.pail.listen))?;
+
+        info!(listen = %config.pail.listen, "HTTP server listening");
+
+        // Run the server with graceful shutdown
+        let server_cancel = cancel.clone();
+        task_handles.push(tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router)
+                .with_graceful_shutdown(async move {
+                    server_cancel.cancelled().await;
+                })
+                .await
+            {
+                error!(error = %e, "HTTP server failed");
+            }
+        }));
+    }
 
     // Wait for shutdown signal
     wait_for_shutdown().await;
@@ -99,11 +279,13 @@ pub async fn run(config: Config, registry: StrategyRegistry) -> Result<()> {
     // Wait for tasks with timeout
     let shutdown_timeout = std::time::Duration::from_secs(10);
     let _ = tokio::time::timeout(shutdown_timeout, async {
-        let _ = scheduler_handle.await;
-        let _ = poller_handle.await;
-        let _ = cleanup_handle.await;
-        let _ = server_handle.await;
-        if let Some(h) = tg_handle {
+        for h in task_handles {
+            let _ = h.await;
+        }
+        if let Some(h) = control_handle {
+            let _ = h.await;
+        }
+        for h in tg_handles {
             let _ = h.await;
         }
     })
@@ -116,93 +298,100 @@ pub async fn run(config: Config, registry: StrategyRegistry) -> Result<()> {
     Ok(())
 }
 
-/// Start the Telegram listener. Returns a JoinHandle for the listener task and a cloned Client
-/// for use by the scheduler (mark-as-read).
+/// Start the Telegram listener for one account. Returns a JoinHandle for the listener task
+/// and a cloned Client for use by the scheduler (mark-as-read).
 async fn start_telegram(
     config: &Config,
     pool: &SqlitePool,
+    account: &str,
+    health: Arc<Health>,
     cancel: CancellationToken,
 ) -> Result<(tokio::task::JoinHandle<()>, grammers_client::Client)> {
     // Connect (session data is stored in the database, loaded by SqlxSession)
-    let conn = telegram::connect(config, pool)
+    let conn = telegram::connect(config, pool, account)
         .await
         .context("connecting to Telegram")?;
+    health.update_tg_account(account, |tg| tg.connected = true);
 
     // Check authorization
     match conn.client.is_authorized().await {
         Ok(true) => {
+            health.update_tg_account(account, |tg| tg.authorized = true);
             let me = conn.client.get_me().await.context("getting TG user info")?;
             info!(
+                account,
                 user = %me.full_name(),
                 username = ?me.username(),
                 "Telegram session authorized"
             );
         }
         Ok(false) => {
-            error!("Telegram session not authorized. Run 'pail tg login' first.");
+            error!(
+                account,
+                "Telegram session not authorized. Run 'pail tg login --account {account}' first."
+            );
+            health.update_tg_account(account, |tg| tg.connected = false);
             conn.client.disconnect();
             conn.runner_handle.abort();
             anyhow::bail!("Telegram not authorized");
         }
         Err(e) => {
-            error!(error = %e, "failed to check Telegram authorization");
+            error!(account, error = %e, "failed to check Telegram authorization");
+            health.update_tg_account(account, |tg| tg.connected = false);
             conn.client.disconnect();
             conn.runner_handle.abort();
             anyhow::bail!("Telegram auth check failed: {e}");
         }
     }
 
-    // Resolve source usernames -> tg_ids
-    let tg_sources = store::get_tg_sources(pool).await.context("loading TG sources")?;
-
-    telegram::resolve_source_ids(&conn.client, pool, &tg_sources)
-        .await
-        .context("resolving TG source IDs")?;
+    // Resolve source usernames -> tg_ids, folders -> channel lists, uncached peers
+    telegram::prepare_account_sources(&conn.client, pool, account).await?;
 
-    // Resolve folder sources
-    let folder_sources: Vec<_> = tg_sources
-        .iter()
-        .filter(|s| s.source_type == "telegram_folder")
-        .cloned()
-        .collect();
-
-    telegram::resolve_folders(&conn.client, pool, &folder_sources)
-        .await
-        .context("resolving TG folders")?;
-
-    telegram::ensure_peer_cache(&conn.client, pool, &tg_sources)
-        .await
-        .context("warming TG peer cache")?;
-
-    // Build subscription map
-    // Re-fetch sources after resolution to get updated tg_ids
-    let tg_sources = store::get_tg_sources(pool).await.context("reloading TG sources")?;
-    let direct_sources: Vec<_> = tg_sources
-        .iter()
-        .filter(|s| s.source_type != "telegram_folder")
-        .cloned()
-        .collect();
-
-    let folder_channels = store::get_all_folder_channel_ids(pool)
-        .await
-        .context("loading folder channel IDs")?;
-
-    let subscription_map = telegram::build_subscription_map(&direct_sources, &folder_channels);
+    // Build subscription map from the sources' updated tg_ids
+    let subscription_map = telegram::account_subscription_map(pool, account).await?;
     let subscribed_count = subscription_map.len();
     let subscriptions = Arc::new(RwLock::new(subscription_map));
 
-    info!(subscribed_chats = subscribed_count, "Telegram listener started");
+    info!(
+        account,
+        subscribed_chats = subscribed_count,
+        "Telegram listener started"
+    );
 
     // Clone client for the scheduler (mark-as-read) before moving it into the listener
     let scheduler_client = conn.client.clone();
 
     // Spawn listener task
     let pool = pool.clone();
-    let handle = tokio::spawn(async move {
-        tg_listener::listener_loop(conn.client, pool, subscriptions, conn.updates_rx, cancel).await;
-        // Clean shutdown: disconnect and stop runner
-        conn.runner_handle.abort();
-    });
+    let hub = reporting::task_hub("tg_listener", &[("tg_account", account)]);
+    let telegram_config = config.telegram.clone();
+    let account = account.to_string();
+    let updates_rx = if config.telegram.debug_updates.enabled {
+        info!(account, "recording received updates ([telegram.debug_updates])");
+        tg_debug::tap(conn.updates_rx, account.clone(), pool.clone())
+    } else {
+        conn.updates_rx
+    };
+    let (client, runner_handle) = (conn.client, conn.runner_handle);
+    let handle = tokio::spawn(
+        async move {
+            tg_listener::listener_loop(
+                client,
+                account.clone(),
+                pool,
+                subscriptions,
+                updates_rx,
+                telegram_config,
+                health.clone(),
+                cancel,
+            )
+            .await;
+            // Clean shutdown: disconnect and stop runner
+            runner_handle.abort();
+            health.update_tg_account(&account, |tg| tg.connected = false);
+        }
+        .bind_hub(hub),
+    );
 
     Ok((handle, scheduler_client))
 }
@@ -231,7 +420,18 @@ async fn bootstrap_feed_token(pool: &SqlitePool, config: &Config) -> Result<Stri
     Ok(token)
 }
 
-fn generate_token() -> String {
+/// Name of this process in generation claims and the instance lock: host and process ID.
+pub(crate) fn instance_id() -> String {
+    let host = std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    format!("{host}:{}", std::process::id())
+}
+
+pub(crate) fn generate_token() -> String {
     rand::rng()
         .sample_iter(&Alphanumeric)
         .take(32)
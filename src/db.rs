@@ -1,42 +1,392 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
 use anyhow::{Context, Result};
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
-use sqlx::{Executor, Row, SqlitePool};
+use sha2::{Digest, Sha256};
+use sqlx::sqlite::{SqliteAutoVacuum, SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{ConnectOptions, Executor, Row, SqlitePool};
 use tracing::info;
 
 use crate::config::Config;
 
-/// Ordered list of migrations. Each entry is (version, name, sql).
-/// Versions must be monotonically increasing.
-const MIGRATIONS: &[(i64, &str, &str)] = &[
-    (
-        1,
-        "initial_schema",
-        include_str!("../migrations/20260211_000001_initial_schema.sql"),
-    ),
-    (2, "phase1b", include_str!("../migrations/20260211_000002_phase1b.sql")),
-    (
-        3,
-        "phase2_telegram",
-        include_str!("../migrations/20260212_000003_phase2_telegram.sql"),
-    ),
-    (
-        4,
-        "workspace_improvements",
-        include_str!("../migrations/20260213_000004_workspace_improvements.sql"),
-    ),
-    (
-        5,
-        "nullable_schedule",
-        include_str!("../migrations/20260218_000005_nullable_schedule.sql"),
-    ),
-    (
-        6,
-        "strategy_used",
-        include_str!("../migrations/20260302_000006_strategy_used.sql"),
-    ),
+/// A schema migration. `down` reverts `up`; `None` marks a migration that can't be rolled back.
+struct Migration {
+    version: i64,
+    name: &'static str,
+    up: &'static str,
+    down: Option<&'static str>,
+}
+
+/// Ordered list of migrations. Versions must be monotonically increasing.
+/// Down-migrations live next to their up-migration as `<same name>.down.sql`.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        up: include_str!("../migrations/20260211_000001_initial_schema.sql"),
+        down: None,
+    },
+    Migration {
+        version: 2,
+        name: "phase1b",
+        up: include_str!("../migrations/20260211_000002_phase1b.sql"),
+        down: Some(include_str!("../migrations/20260211_000002_phase1b.down.sql")),
+    },
+    Migration {
+        version: 3,
+        name: "phase2_telegram",
+        up: include_str!("../migrations/20260212_000003_phase2_telegram.sql"),
+        down: Some(include_str!("../migrations/20260212_000003_phase2_telegram.down.sql")),
+    },
+    Migration {
+        version: 4,
+        name: "workspace_improvements",
+        up: include_str!("../migrations/20260213_000004_workspace_improvements.sql"),
+        down: Some(include_str!(
+            "../migrations/20260213_000004_workspace_improvements.down.sql"
+        )),
+    },
+    Migration {
+        version: 5,
+        name: "nullable_schedule",
+        up: include_str!("../migrations/20260218_000005_nullable_schedule.sql"),
+        down: None,
+    },
+    Migration {
+        version: 6,
+        name: "strategy_used",
+        up: include_str!("../migrations/20260302_000006_strategy_used.sql"),
+        down: Some(include_str!("../migrations/20260302_000006_strategy_used.down.sql")),
+    },
+    Migration {
+        version: 7,
+        name: "scrape_sources",
+        up: include_str!("../migrations/20261015_000007_scrape_sources.sql"),
+        down: Some(include_str!("../migrations/20261015_000007_scrape_sources.down.sql")),
+    },
+    Migration {
+        version: 8,
+        name: "article_feedback",
+        up: include_str!("../migrations/20261015_000008_article_feedback.sql"),
+        down: Some(include_str!("../migrations/20261015_000008_article_feedback.down.sql")),
+    },
+    Migration {
+        version: 9,
+        name: "item_embeddings",
+        up: include_str!("../migrations/20261015_000009_item_embeddings.sql"),
+        down: Some(include_str!("../migrations/20261015_000009_item_embeddings.down.sql")),
+    },
+    Migration {
+        version: 10,
+        name: "imap_sources",
+        up: include_str!("../migrations/20261015_000010_imap_sources.sql"),
+        down: Some(include_str!("../migrations/20261015_000010_imap_sources.down.sql")),
+    },
+    Migration {
+        version: 11,
+        name: "article_status",
+        up: include_str!("../migrations/20261015_000011_article_status.sql"),
+        down: Some(include_str!("../migrations/20261015_000011_article_status.down.sql")),
+    },
+    Migration {
+        version: 12,
+        name: "fetched_pages",
+        up: include_str!("../migrations/20261015_000012_fetched_pages.sql"),
+        down: Some(include_str!("../migrations/20261015_000012_fetched_pages.down.sql")),
+    },
+    Migration {
+        version: 13,
+        name: "channel_paused",
+        up: include_str!("../migrations/20261015_000013_channel_paused.sql"),
+        down: Some(include_str!("../migrations/20261015_000013_channel_paused.down.sql")),
+    },
+    Migration {
+        version: 14,
+        name: "read_later_sources",
+        up: include_str!("../migrations/20261015_000014_read_later_sources.sql"),
+        down: Some(include_str!(
+            "../migrations/20261015_000014_read_later_sources.down.sql"
+        )),
+    },
+    Migration {
+        version: 15,
+        name: "item_tags",
+        up: include_str!("../migrations/20261015_000015_item_tags.sql"),
+        down: Some(include_str!("../migrations/20261015_000015_item_tags.down.sql")),
+    },
+    Migration {
+        version: 16,
+        name: "tg_accounts",
+        up: include_str!("../migrations/20261015_000016_tg_accounts.sql"),
+        down: Some(include_str!("../migrations/20261015_000016_tg_accounts.down.sql")),
+    },
+    Migration {
+        version: 17,
+        name: "source_health",
+        up: include_str!("../migrations/20261015_000017_source_health.sql"),
+        down: Some(include_str!("../migrations/20261015_000017_source_health.down.sql")),
+    },
+    Migration {
+        version: 18,
+        name: "source_moves",
+        up: include_str!("../migrations/20261015_000018_source_moves.sql"),
+        down: Some(include_str!("../migrations/20261015_000018_source_moves.down.sql")),
+    },
+    Migration {
+        version: 19,
+        name: "aggregator_sources",
+        up: include_str!("../migrations/20261015_000019_aggregator_sources.sql"),
+        down: Some(include_str!(
+            "../migrations/20261015_000019_aggregator_sources.down.sql"
+        )),
+    },
+    Migration {
+        version: 20,
+        name: "ics_sources",
+        up: include_str!("../migrations/20261015_000020_ics_sources.sql"),
+        down: Some(include_str!("../migrations/20261015_000020_ics_sources.down.sql")),
+    },
+    Migration {
+        version: 21,
+        name: "skipped_items",
+        up: include_str!("../migrations/20261015_000021_skipped_items.sql"),
+        down: Some(include_str!("../migrations/20261015_000021_skipped_items.down.sql")),
+    },
+    Migration {
+        version: 22,
+        name: "twitter_sources",
+        up: include_str!("../migrations/20261015_000022_twitter_sources.sql"),
+        down: Some(include_str!("../migrations/20261015_000022_twitter_sources.down.sql")),
+    },
+    Migration {
+        version: 23,
+        name: "article_item_citations",
+        up: include_str!("../migrations/20261015_000023_article_item_citations.sql"),
+        down: Some(include_str!(
+            "../migrations/20261015_000023_article_item_citations.down.sql"
+        )),
+    },
+    Migration {
+        version: 24,
+        name: "article_read_state",
+        up: include_str!("../migrations/20261015_000024_article_read_state.sql"),
+        down: Some(include_str!(
+            "../migrations/20261015_000024_article_read_state.down.sql"
+        )),
+    },
+    Migration {
+        version: 25,
+        name: "slack_sources",
+        up: include_str!("../migrations/20261015_000025_slack_sources.sql"),
+        down: Some(include_str!("../migrations/20261015_000025_slack_sources.down.sql")),
+    },
+    Migration {
+        version: 26,
+        name: "generation_claims",
+        up: include_str!("../migrations/20261015_000026_generation_claims.sql"),
+        down: Some(include_str!("../migrations/20261015_000026_generation_claims.down.sql")),
+    },
+    Migration {
+        version: 27,
+        name: "arxiv_sources",
+        up: include_str!("../migrations/20261015_000027_arxiv_sources.sql"),
+        down: Some(include_str!("../migrations/20261015_000027_arxiv_sources.down.sql")),
+    },
+    Migration {
+        version: 28,
+        name: "compressed_text",
+        up: include_str!("../migrations/20261015_000028_compressed_text.sql"),
+        down: Some(include_str!("../migrations/20261015_000028_compressed_text.down.sql")),
+    },
+    Migration {
+        version: 29,
+        name: "self_eval",
+        up: include_str!("../migrations/20261015_000029_self_eval.sql"),
+        down: Some(include_str!("../migrations/20261015_000029_self_eval.down.sql")),
+    },
+    Migration {
+        version: 30,
+        name: "article_metadata",
+        up: include_str!("../migrations/20261015_000030_article_metadata.sql"),
+        down: Some(include_str!("../migrations/20261015_000030_article_metadata.down.sql")),
+    },
+    Migration {
+        version: 31,
+        name: "source_proxy",
+        up: include_str!("../migrations/20261015_000031_source_proxy.sql"),
+        down: Some(include_str!("../migrations/20261015_000031_source_proxy.down.sql")),
+    },
+    Migration {
+        version: 32,
+        name: "article_summary",
+        up: include_str!("../migrations/20261015_000032_article_summary.sql"),
+        down: Some(include_str!("../migrations/20261015_000032_article_summary.down.sql")),
+    },
+    Migration {
+        version: 33,
+        name: "feed_tokens",
+        up: include_str!("../migrations/20261015_000033_feed_tokens.sql"),
+        down: Some(include_str!("../migrations/20261015_000033_feed_tokens.down.sql")),
+    },
+    Migration {
+        version: 34,
+        name: "tg_backfill_progress",
+        up: include_str!("../migrations/20261015_000034_tg_backfill_progress.sql"),
+        down: Some(include_str!(
+            "../migrations/20261015_000034_tg_backfill_progress.down.sql"
+        )),
+    },
+    Migration {
+        version: 35,
+        name: "generation_runs",
+        up: include_str!("../migrations/20261015_000035_generation_runs.sql"),
+        down: Some(include_str!("../migrations/20261015_000035_generation_runs.down.sql")),
+    },
+    Migration {
+        version: 36,
+        name: "tg_debug_updates",
+        up: include_str!("../migrations/20261015_000036_tg_debug_updates.sql"),
+        down: Some(include_str!("../migrations/20261015_000036_tg_debug_updates.down.sql")),
+    },
+    Migration {
+        version: 37,
+        name: "item_upstream_deleted",
+        up: include_str!("../migrations/20261015_000037_item_upstream_deleted.sql"),
+        down: Some(include_str!(
+            "../migrations/20261015_000037_item_upstream_deleted.down.sql"
+        )),
+    },
+    Migration {
+        version: 38,
+        name: "generation_run_details",
+        up: include_str!("../migrations/20261015_000038_generation_run_details.sql"),
+        down: Some(include_str!(
+            "../migrations/20261015_000038_generation_run_details.down.sql"
+        )),
+    },
+    Migration {
+        version: 39,
+        name: "source_dedup",
+        up: include_str!("../migrations/20261015_000039_source_dedup.sql"),
+        down: Some(include_str!("../migrations/20261015_000039_source_dedup.down.sql")),
+    },
+    Migration {
+        version: 40,
+        name: "generation_run_model",
+        up: include_str!("../migrations/20261015_000040_generation_run_model.sql"),
+        down: Some(include_str!(
+            "../migrations/20261015_000040_generation_run_model.down.sql"
+        )),
+    },
+    Migration {
+        version: 41,
+        name: "content_items_archive",
+        up: include_str!("../migrations/20261015_000041_content_items_archive.sql"),
+        down: Some(include_str!(
+            "../migrations/20261015_000041_content_items_archive.down.sql"
+        )),
+    },
+    Migration {
+        version: 42,
+        name: "article_translations",
+        up: include_str!("../migrations/20261015_000042_article_translations.sql"),
+        down: Some(include_str!(
+            "../migrations/20261015_000042_article_translations.down.sql"
+        )),
+    },
+    Migration {
+        version: 43,
+        name: "source_http_overrides",
+        up: include_str!("../migrations/20261015_000043_source_http_overrides.sql"),
+        down: Some(include_str!(
+            "../migrations/20261015_000043_source_http_overrides.down.sql"
+        )),
+    },
+    Migration {
+        version: 44,
+        name: "webmentions",
+        up: include_str!("../migrations/20261015_000044_webmentions.sql"),
+        down: Some(include_str!("../migrations/20261015_000044_webmentions.down.sql")),
+    },
+    Migration {
+        version: 45,
+        name: "live_runs",
+        up: include_str!("../migrations/20261015_000045_live_runs.sql"),
+        down: Some(include_str!("../migrations/20261015_000045_live_runs.down.sql")),
+    },
+    Migration {
+        version: 46,
+        name: "query_indexes",
+        up: include_str!("../migrations/20261015_000046_query_indexes.sql"),
+        down: Some(include_str!("../migrations/20261015_000046_query_indexes.down.sql")),
+    },
+    Migration {
+        version: 47,
+        name: "generation_run_usage",
+        up: include_str!("../migrations/20261015_000047_generation_run_usage.sql"),
+        down: Some(include_str!(
+            "../migrations/20261015_000047_generation_run_usage.down.sql"
+        )),
+    },
+    Migration {
+        version: 48,
+        name: "tg_media",
+        up: include_str!("../migrations/20261015_000048_tg_media.sql"),
+        down: Some(include_str!("../migrations/20261015_000048_tg_media.down.sql")),
+    },
+    Migration {
+        version: 49,
+        name: "tagging_failures",
+        up: include_str!("../migrations/20261015_000049_tagging_failures.sql"),
+        down: Some(include_str!("../migrations/20261015_000049_tagging_failures.down.sql")),
+    },
+    Migration {
+        version: 50,
+        name: "preprocess_dropped",
+        up: include_str!("../migrations/20261015_000050_preprocess_dropped.sql"),
+        down: Some(include_str!(
+            "../migrations/20261015_000050_preprocess_dropped.down.sql"
+        )),
+    },
 ];
 
+/// One row of `pail db status`: a known migration, or a version recorded in the database
+/// that this binary doesn't know (written by a newer pail).
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: String,
+    /// SHA-256 of the embedded up-migration; `None` for unknown versions.
+    pub checksum: Option<String>,
+    /// `applied_at` from `schema_version`; `None` if pending.
+    pub applied_at: Option<String>,
+    /// Checksum recorded when the migration was applied. `None` for migrations applied before
+    /// checksums were recorded.
+    pub recorded_checksum: Option<String>,
+    pub reversible: bool,
+    pub known: bool,
+}
+
+impl MigrationStatus {
+    pub fn is_applied(&self) -> bool {
+        self.applied_at.is_some()
+    }
+
+    /// The embedded SQL differs from what was applied.
+    pub fn checksum_mismatch(&self) -> bool {
+        matches!((&self.checksum, &self.recorded_checksum), (Some(a), Some(b)) if a != b)
+    }
+}
+
+/// Open the database, applying pending migrations.
 pub async fn create_pool(config: &Config) -> Result<SqlitePool> {
+    let pool = connect(config).await?;
+    run_migrations(&pool).await?;
+    Ok(pool)
+}
+
+/// Open the database without touching the schema.
+pub async fn connect(config: &Config) -> Result<SqlitePool> {
     let db_path = config.db_path();
 
     // Ensure the parent directory exists
@@ -44,11 +394,19 @@ pub async fn create_pool(config: &Config) -> Result<SqlitePool> {
         std::fs::create_dir_all(parent).with_context(|| format!("creating data directory: {}", parent.display()))?;
     }
 
-    let options = SqliteConnectOptions::new()
+    let mut options = SqliteConnectOptions::new()
         .filename(&db_path)
         .create_if_missing(true)
+        // Takes effect for new databases; `maintain` converts existing ones
+        .auto_vacuum(SqliteAutoVacuum::Incremental)
         .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal)
         .foreign_keys(true);
+    // sqlx times every statement; slow ones are logged with the SQL, rows and elapsed time,
+    // inside the store function's span (its source or channel)
+    options = match config.database.log_slow_queries_ms {
+        0 => options.log_slow_statements(log::LevelFilter::Off, Duration::ZERO),
+        ms => options.log_slow_statements(log::LevelFilter::Warn, Duration::from_millis(ms)),
+    };
 
     let pool = SqlitePoolOptions::new()
         .max_connections(5)
@@ -58,23 +416,124 @@ pub async fn create_pool(config: &Config) -> Result<SqlitePool> {
 
     info!(path = %db_path.display(), "database connected (WAL mode, foreign keys enabled)");
 
-    run_migrations(&pool).await?;
-
     Ok(pool)
 }
 
-async fn run_migrations(pool: &SqlitePool) -> Result<()> {
-    // Create schema_version table if it doesn't exist
+/// What one `maintain` run did.
+pub struct MaintenanceReport {
+    /// Database file plus WAL, in bytes.
+    pub size_before: u64,
+    pub size_after: u64,
+    pub free_pages_before: i64,
+    pub free_pages_after: i64,
+    /// The database had no incremental auto-vacuum and was rebuilt with a full VACUUM.
+    pub converted: bool,
+    /// The database has no incremental auto-vacuum and `convert` wasn't given, so no free
+    /// pages were reclaimed.
+    pub needs_conversion: bool,
+    /// The WAL checkpoint couldn't finish because a reader or writer was busy.
+    pub checkpoint_busy: bool,
+}
+
+/// Reclaim space and refresh planner statistics: incremental vacuum, ANALYZE, then a WAL
+/// checkpoint(TRUNCATE). Runs on one connection; safe while the daemon is up. An older
+/// database without incremental auto-vacuum is switched over with a full VACUUM only if
+/// `convert`, since that rewrites the file and blocks writers until it's done.
+pub async fn maintain(pool: &SqlitePool, db_path: &Path, convert: bool) -> Result<MaintenanceReport> {
+    let size_before = file_size(db_path);
+    let mut conn = pool.acquire().await.context("acquiring connection")?;
+    let free_pages_before: i64 = sqlx::query_scalar("PRAGMA freelist_count")
+        .fetch_one(&mut *conn)
+        .await
+        .context("reading freelist_count")?;
+
+    // 0 = none, 1 = full, 2 = incremental
+    let auto_vacuum: i64 = sqlx::query_scalar("PRAGMA auto_vacuum")
+        .fetch_one(&mut *conn)
+        .await
+        .context("reading auto_vacuum")?;
+    let incremental = auto_vacuum == 2;
+    let converted = !incremental && convert;
+    if converted {
+        conn.execute("PRAGMA auto_vacuum = INCREMENTAL")
+            .await
+            .context("setting auto_vacuum")?;
+        conn.execute("VACUUM").await.context("vacuuming database")?;
+    } else if incremental {
+        conn.execute("PRAGMA incremental_vacuum")
+            .await
+            .context("running incremental vacuum")?;
+    }
+
+    conn.execute("ANALYZE").await.context("analyzing database")?;
+
+    let (busy, _, _): (i64, i64, i64) = sqlx::query_as("PRAGMA wal_checkpoint(TRUNCATE)")
+        .fetch_one(&mut *conn)
+        .await
+        .context("checkpointing WAL")?;
+
+    let free_pages_after: i64 = sqlx::query_scalar("PRAGMA freelist_count")
+        .fetch_one(&mut *conn)
+        .await
+        .context("reading freelist_count")?;
+    drop(conn);
+
+    Ok(MaintenanceReport {
+        size_before,
+        size_after: file_size(db_path),
+        free_pages_before,
+        free_pages_after,
+        converted,
+        needs_conversion: !incremental && !convert,
+        checkpoint_busy: busy != 0,
+    })
+}
+
+/// Size of the database file and its WAL.
+fn file_size(db_path: &Path) -> u64 {
+    let mut wal = db_path.as_os_str().to_owned();
+    wal.push("-wal");
+    [db_path.as_os_str(), wal.as_os_str()]
+        .iter()
+        .filter_map(|p| std::fs::metadata(p).ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+fn checksum(sql: &str) -> String {
+    format!("{:x}", Sha256::digest(sql.as_bytes()))
+}
+
+async fn ensure_schema_version_table(pool: &SqlitePool) -> Result<()> {
     pool.execute(
         "CREATE TABLE IF NOT EXISTS schema_version (
             version INTEGER PRIMARY KEY NOT NULL,
             name TEXT NOT NULL,
-            applied_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+            applied_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+            checksum TEXT
         )",
     )
     .await
     .context("creating schema_version table")?;
 
+    // Databases created before checksums were recorded lack the column
+    let has_checksum: bool =
+        sqlx::query_scalar("SELECT COUNT(*) > 0 FROM pragma_table_info('schema_version') WHERE name = 'checksum'")
+            .fetch_one(pool)
+            .await
+            .context("inspecting schema_version table")?;
+    if !has_checksum {
+        pool.execute("ALTER TABLE schema_version ADD COLUMN checksum TEXT")
+            .await
+            .context("adding schema_version.checksum")?;
+    }
+    Ok(())
+}
+
+/// Apply all pending migrations. Returns how many were applied.
+pub async fn run_migrations(pool: &SqlitePool) -> Result<usize> {
+    ensure_schema_version_table(pool).await?;
+
     // Get the current max version
     let row = sqlx::query("SELECT COALESCE(MAX(version), 0) as v FROM schema_version")
         .fetch_one(pool)
@@ -83,16 +542,20 @@ async fn run_migrations(pool: &SqlitePool) -> Result<()> {
     let current_version: i64 = row.get("v");
 
     let mut applied = 0;
-    for &(version, name, sql) in MIGRATIONS {
-        if version <= current_version {
+    for migration in MIGRATIONS {
+        if migration.version <= current_version {
             continue;
         }
-        pool.execute(sql)
+        let (version, name) = (migration.version, migration.name);
+        // Not wrapped in a transaction: migration 5 toggles `PRAGMA foreign_keys`, which is a
+        // no-op inside one.
+        pool.execute(migration.up)
             .await
             .with_context(|| format!("applying migration v{version} ({name})"))?;
-        sqlx::query("INSERT INTO schema_version (version, name) VALUES (?, ?)")
+        sqlx::query("INSERT INTO schema_version (version, name, checksum) VALUES (?, ?, ?)")
             .bind(version)
             .bind(name)
+            .bind(checksum(migration.up))
             .execute(pool)
             .await
             .with_context(|| format!("recording migration v{version}"))?;
@@ -106,5 +569,140 @@ async fn run_migrations(pool: &SqlitePool) -> Result<()> {
         info!(applied, "database migrations applied");
     }
 
+    Ok(applied)
+}
+
+/// Every known migration plus any unknown applied version, ordered by version.
+pub async fn migration_status(pool: &SqlitePool) -> Result<Vec<MigrationStatus>> {
+    ensure_schema_version_table(pool).await?;
+
+    let rows = sqlx::query("SELECT version, name, applied_at, checksum FROM schema_version ORDER BY version")
+        .fetch_all(pool)
+        .await
+        .context("querying schema_version")?;
+    let mut applied: BTreeMap<i64, (String, String, Option<String>)> = rows
+        .into_iter()
+        .map(|r| {
+            (
+                r.get("version"),
+                (r.get("name"), r.get("applied_at"), r.get("checksum")),
+            )
+        })
+        .collect();
+
+    let mut statuses: Vec<MigrationStatus> = MIGRATIONS
+        .iter()
+        .map(|m| {
+            let recorded = applied.remove(&m.version);
+            MigrationStatus {
+                version: m.version,
+                name: m.name.to_string(),
+                checksum: Some(checksum(m.up)),
+                applied_at: recorded.as_ref().map(|(_, at, _)| at.clone()),
+                recorded_checksum: recorded.and_then(|(_, _, sum)| sum),
+                reversible: m.down.is_some(),
+                known: true,
+            }
+        })
+        .collect();
+    statuses.extend(applied.into_iter().map(|(version, (name, at, sum))| MigrationStatus {
+        version,
+        name,
+        checksum: None,
+        applied_at: Some(at),
+        recorded_checksum: sum,
+        reversible: false,
+        known: false,
+    }));
+    statuses.sort_by_key(|s| s.version);
+    Ok(statuses)
+}
+
+/// Fail unless the schema is current. Used by the daemon under `--no-auto-migrate`.
+pub async fn ensure_up_to_date(pool: &SqlitePool) -> Result<()> {
+    let current: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM schema_version")
+        .fetch_one(pool)
+        .await
+        .unwrap_or(0);
+    let pending: Vec<String> = MIGRATIONS
+        .iter()
+        .filter(|m| m.version > current)
+        .map(|m| format!("v{} ({})", m.version, m.name))
+        .collect();
+    if !pending.is_empty() {
+        anyhow::bail!(
+            "database schema is at v{current} and {} migration(s) are pending: {}. Run `pail db migrate` first",
+            pending.len(),
+            pending.join(", ")
+        );
+    }
     Ok(())
 }
+
+/// The applied migrations `rollback(pool, target)` would revert, newest first.
+/// Errors if any of them is unknown to this binary or irreversible.
+pub async fn rollback_plan(pool: &SqlitePool, target: i64) -> Result<Vec<(i64, String)>> {
+    if target < 0 {
+        anyhow::bail!("invalid target version {target}");
+    }
+    let statuses = migration_status(pool).await?;
+    if target > 0 && !statuses.iter().any(|s| s.version == target && s.is_applied()) {
+        anyhow::bail!("migration v{target} is not applied");
+    }
+
+    let mut plan = Vec::new();
+    for status in statuses.iter().rev().filter(|s| s.is_applied() && s.version > target) {
+        if !status.known {
+            anyhow::bail!(
+                "migration v{} ({}) was applied by a newer pail; roll it back with that version",
+                status.version,
+                status.name
+            );
+        }
+        if !status.reversible {
+            anyhow::bail!("migration v{} ({}) can't be rolled back", status.version, status.name);
+        }
+        plan.push((status.version, status.name.clone()));
+    }
+    Ok(plan)
+}
+
+/// Revert every applied migration newer than `target`, newest first; `target` itself stays
+/// applied. Each step runs in its own transaction. Returns the reverted versions.
+pub async fn rollback(pool: &SqlitePool, target: i64) -> Result<Vec<(i64, String)>> {
+    let plan = rollback_plan(pool, target).await?;
+    for (version, name) in &plan {
+        let down = MIGRATIONS
+            .iter()
+            .find(|m| m.version == *version)
+            .and_then(|m| m.down)
+            .expect("rollback_plan only returns reversible migrations");
+        let mut tx = pool.begin().await.context("starting rollback transaction")?;
+        (&mut *tx)
+            .execute(down)
+            .await
+            .with_context(|| format!("reverting migration v{version} ({name})"))?;
+        sqlx::query("DELETE FROM schema_version WHERE version = ?")
+            .bind(version)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("unrecording migration v{version}"))?;
+        tx.commit()
+            .await
+            .with_context(|| format!("committing rollback of v{version}"))?;
+        info!(version, name = %name, "reverted migration");
+    }
+    Ok(plan)
+}
+
+/// An in-memory database with every migration applied, for tests.
+#[cfg(test)]
+pub(crate) async fn memory_pool() -> SqlitePool {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    run_migrations(&pool).await.unwrap();
+    pool
+}
@@ -0,0 +1,203 @@
+//! Word-level diff of two articles (`pail diff`). Lines are matched first, then the words of
+//! each changed block, so an edited paragraph shows as a few changed words rather than a
+//! removed and an added paragraph.
+
+/// Hunks whose word table would exceed this many cells are shown as a whole removed block
+/// followed by a whole added block (the table is `u32` per cell).
+const MAX_WORD_DIFF_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Same,
+    Removed,
+    Added,
+}
+
+/// Diff `old` against `new`. Concatenating the `Same` and `Removed` parts gives `old`;
+/// `Same` and `Added` give `new`. Adjacent parts of the same kind are merged.
+pub fn word_diff(old: &str, new: &str) -> Vec<(Op, String)> {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+
+    let mut out = Vec::new();
+    let mut removed: Vec<&str> = Vec::new();
+    let mut added: Vec<&str> = Vec::new();
+    for (op, line) in lcs(&old_lines, &new_lines) {
+        match op {
+            Op::Removed => removed.push(line),
+            Op::Added => added.push(line),
+            Op::Same => {
+                diff_hunk(&removed.concat(), &added.concat(), &mut out);
+                removed.clear();
+                added.clear();
+                push(&mut out, Op::Same, line);
+            }
+        }
+    }
+    diff_hunk(&removed.concat(), &added.concat(), &mut out);
+    out
+}
+
+/// Render a diff for the terminal: red/green with `color`, else wdiff-style
+/// `[-removed-]` / `{+added+}` markers.
+pub fn render(diff: &[(Op, String)], color: bool) -> String {
+    let mut out = String::new();
+    for (op, text) in diff {
+        match (op, color) {
+            (Op::Same, _) => out.push_str(text),
+            (Op::Removed, true) => out.push_str(&format!("\x1b[31m{text}\x1b[0m")),
+            (Op::Added, true) => out.push_str(&format!("\x1b[32m{text}\x1b[0m")),
+            (Op::Removed, false) => out.push_str(&format!("[-{text}-]")),
+            (Op::Added, false) => out.push_str(&format!("{{+{text}+}}")),
+        }
+    }
+    out
+}
+
+/// Word-diff one block of changed lines.
+fn diff_hunk(removed: &str, added: &str, out: &mut Vec<(Op, String)>) {
+    let old_words = words(removed);
+    let new_words = words(added);
+    if (old_words.len() + 1) * (new_words.len() + 1) > MAX_WORD_DIFF_CELLS {
+        push(out, Op::Removed, removed);
+        push(out, Op::Added, added);
+        return;
+    }
+    for (op, word) in lcs(&old_words, &new_words) {
+        push(out, op, word);
+    }
+}
+
+/// Split text into alternating runs of whitespace and non-whitespace, keeping both.
+fn words(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut in_space = None;
+    for (i, c) in text.char_indices() {
+        let space = c.is_whitespace();
+        if in_space.is_some_and(|s| s != space) {
+            tokens.push(&text[start..i]);
+            start = i;
+        }
+        in_space = Some(space);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+/// Longest-common-subsequence alignment of two token lists.
+fn lcs<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<(Op, &'a str)> {
+    // table[i][j] = LCS length of a[i..] and b[j..]
+    let width = b.len() + 1;
+    let mut table = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            table[i * width + j] = if a[i] == b[j] {
+                table[(i + 1) * width + j + 1] + 1
+            } else {
+                table[(i + 1) * width + j].max(table[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::with_capacity(a.len().max(b.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            ops.push((Op::Same, a[i]));
+            i += 1;
+            j += 1;
+        } else if table[(i + 1) * width + j] >= table[i * width + j + 1] {
+            ops.push((Op::Removed, a[i]));
+            i += 1;
+        } else {
+            ops.push((Op::Added, b[j]));
+            j += 1;
+        }
+    }
+    ops.extend(a[i..].iter().map(|t| (Op::Removed, *t)));
+    ops.extend(b[j..].iter().map(|t| (Op::Added, *t)));
+    ops
+}
+
+fn push(out: &mut Vec<(Op, String)>, op: Op, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match out.last_mut() {
+        Some((last, s)) if *last == op => s.push_str(text),
+        _ => out.push((op, text.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `old` and `new` texts a diff was computed from.
+    fn sides(diff: &[(Op, String)]) -> (String, String) {
+        let side = |skip: Op| {
+            diff.iter()
+                .filter(|(op, _)| *op != skip)
+                .map(|(_, t)| t.as_str())
+                .collect()
+        };
+        (side(Op::Added), side(Op::Removed))
+    }
+
+    #[test]
+    fn test_lcs_aligns_common_tokens() {
+        let ops = lcs(&["a", "b", "c", "d"], &["a", "c", "d", "e"]);
+        assert_eq!(
+            ops,
+            [
+                (Op::Same, "a"),
+                (Op::Removed, "b"),
+                (Op::Same, "c"),
+                (Op::Same, "d"),
+                (Op::Added, "e"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lcs_empty_sides() {
+        assert_eq!(lcs(&[], &["x"]), [(Op::Added, "x")]);
+        assert_eq!(lcs(&["x"], &[]), [(Op::Removed, "x")]);
+        assert!(lcs(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn test_word_diff_changed_word() {
+        let diff = word_diff("# Title\n\nThe quick fox.\n", "# Title\n\nThe slow fox.\n");
+        assert_eq!(
+            diff,
+            [
+                (Op::Same, "# Title\n\nThe ".to_string()),
+                (Op::Removed, "quick".to_string()),
+                (Op::Added, "slow".to_string()),
+                (Op::Same, " fox.\n".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_word_diff_reconstructs_both_sides() {
+        let old = "one two\nthree\n\nfour five six\n";
+        let new = "one 2\n\nfour five six\nseven\n";
+        assert_eq!(sides(&word_diff(old, new)), (old.to_string(), new.to_string()));
+    }
+
+    #[test]
+    fn test_words_keeps_whitespace_runs() {
+        assert_eq!(words("a  b\nc"), ["a", "  ", "b", "\n", "c"]);
+    }
+
+    #[test]
+    fn test_render_without_color() {
+        let diff = word_diff("a b", "a c");
+        assert_eq!(render(&diff, false), "a [-b-]{+c+}");
+    }
+}
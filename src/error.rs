@@ -16,6 +16,51 @@ pub enum FetchError {
     Http { url: String, source: reqwest::Error },
     #[error("failed to parse feed from {url}: {message}")]
     Parse { url: String, message: String },
+    #[error("feed at {url} is gone (410)")]
+    Gone { url: String },
+    #[error("response from {url} is larger than {limit} bytes")]
+    TooLarge { url: String, limit: usize },
+}
+
+/// Why a crawler request (page prefetch, scrape source) wasn't sent or failed.
+#[derive(Debug, Error)]
+pub enum CrawlError {
+    #[error("disallowed by robots.txt")]
+    Disallowed,
+    #[error("robots.txt unreachable")]
+    RobotsUnreachable,
+    #[error("too many requests queued for this host")]
+    Throttled,
+    /// Refused by a public-only crawler (see `Crawler::public_only`).
+    #[error("{0}")]
+    Refused(PublicUrlError),
+    #[error("{0}")]
+    Http(#[from] reqwest::Error),
+}
+
+impl From<PublicUrlError> for CrawlError {
+    fn from(e: PublicUrlError) -> Self {
+        match e {
+            PublicUrlError::Http(e) => CrawlError::Http(e),
+            e => CrawlError::Refused(e),
+        }
+    }
+}
+
+/// Why a request to a URL taken from generated or third-party content (link check,
+/// webmentions, page prefetch) wasn't sent or failed.
+#[derive(Debug, Error)]
+pub enum PublicUrlError {
+    #[error("invalid URL: {0}")]
+    InvalidUrl(String),
+    #[error("cannot resolve {host}: {message}")]
+    Unresolvable { host: String, message: String },
+    #[error("{host} resolves to {ip}, which is not a public address")]
+    NotPublic { host: String, ip: std::net::IpAddr },
+    #[error("more than {0} redirects")]
+    TooManyRedirects(usize),
+    #[error("{0}")]
+    Http(#[from] reqwest::Error),
 }
 
 #[derive(Debug, Error)]
@@ -26,10 +71,16 @@ pub enum GenerationError {
     OpencodeExecution { exit_code: Option<i32>, stderr: String },
     #[error("opencode timed out after {0}")]
     Timeout(String),
+    #[error("opencode stalled: {0}")]
+    Stalled(String),
+    #[error("generation budget exceeded: {0}")]
+    BudgetExceeded(String),
     #[error("failed to parse output: {0}")]
     OutputParse(String),
     #[error("workspace preparation failed: {0}")]
     Workspace(#[from] std::io::Error),
+    #[error("article {article_id} ('{title}') already covers this window")]
+    DuplicateWindow { article_id: String, title: String },
 }
 
 #[derive(Debug, Error)]
@@ -1,32 +1,424 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
 use anyhow::Result;
 use base64::Engine;
 use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
 use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH, USER_AGENT};
+use reqwest::redirect::Policy;
 use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
+use crate::config::Config;
 use crate::error::FetchError;
 use crate::models::{ContentItem, Source};
+use crate::{
+    fetch_aggregator, fetch_arxiv, fetch_ics, fetch_imap, fetch_microformats, fetch_podcast, fetch_read_later,
+    fetch_scrape, fetch_slack, fetch_stats, fetch_twitter, http_client, preprocess,
+};
+
+/// Source types fetched by the poller (daemon) and one-shot before CLI generation.
+pub const POLLED_SOURCE_TYPES: &[&str] = &[
+    "rss",
+    "scrape",
+    "microformats",
+    "podcast",
+    "imap",
+    "wallabag",
+    "omnivore",
+    "lemmy",
+    "lobsters",
+    "ics",
+    "twitter",
+    "slack",
+    "arxiv",
+    "pail_stats",
+];
+
+/// Request timeout of sources without their own `timeout`.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Redirects followed per feed request (reqwest's default limit).
+const MAX_REDIRECTS: usize = 10;
+
+/// Largest feed response read; a bigger one fails the fetch.
+const MAX_FEED_BYTES: usize = 20 * 1024 * 1024;
+
+/// Largest entry content (HTML) converted to text; the rest is dropped before conversion.
+const MAX_ENTRY_HTML_BYTES: usize = 2 * 1024 * 1024;
+
+/// Largest stored item body. Longer ones are cut and flagged `truncated` in metadata.
+const MAX_BODY_BYTES: usize = 256 * 1024;
+
+/// Longest stored entry title.
+const MAX_TITLE_CHARS: usize = 500;
+
+/// Element nesting past which HTML has its tags removed instead of being rendered, so a
+/// deeply nested "HTML bomb" can't exhaust the converter.
+const MAX_HTML_DEPTH: usize = 200;
+
+/// Elements that never have a closing tag, for `html_too_deep`.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
 
 /// Result of an RSS fetch, including items and HTTP cache headers.
 pub struct FetchResult {
     pub items: Vec<ContentItem>,
     pub etag: Option<String>,
     pub last_modified: Option<String>,
+    /// New feed URL when every redirect on the way was permanent (301/308).
+    pub moved_to: Option<String>,
+}
+
+/// Fetch one polled source, dispatching on its type (see `POLLED_SOURCE_TYPES`).
+/// New items go through the source's `preprocess` command, if it has one.
+pub async fn fetch_polled_source(
+    pool: &SqlitePool,
+    config: &Config,
+    source: &Source,
+    transcriber: Option<&fetch_podcast::Transcriber>,
+) -> Result<FetchResult> {
+    let mut result = match source.source_type.as_str() {
+        "scrape" => fetch_scrape::fetch_scrape_source(config, source).await,
+        "microformats" => fetch_microformats::fetch_microformats_source(config, source).await,
+        "podcast" => fetch_podcast::fetch_podcast_source(pool, config, source, transcriber).await,
+        "imap" => fetch_imap::fetch_imap_source(pool, source).await,
+        "wallabag" | "omnivore" => fetch_read_later::fetch_read_later_source(source).await,
+        "lemmy" | "lobsters" => fetch_aggregator::fetch_aggregator_source(pool, source).await,
+        "ics" => fetch_ics::fetch_ics_source(config, source).await,
+        "twitter" => fetch_twitter::fetch_twitter_source(source).await,
+        "slack" => fetch_slack::fetch_slack_source(pool, source).await,
+        "arxiv" => fetch_arxiv::fetch_arxiv_source(pool, config, source).await,
+        "pail_stats" => fetch_stats::fetch_stats_source(pool, config, source).await,
+        _ => fetch_rss_source(source).await,
+    }?;
+    if let Some(configured) = config.source.iter().find(|s| s.name == source.name)
+        && !configured.preprocess.is_empty()
+    {
+        preprocess::preprocess_items(pool, configured, &mut result).await?;
+    }
+    Ok(result)
 }
 
 /// Fetch RSS items from a source. Returns ContentItems and HTTP cache headers.
 /// On 304 Not Modified, returns an empty items list with the existing cache headers.
 pub async fn fetch_rss_source(source: &Source) -> Result<FetchResult> {
-    let url = source.url.as_deref().ok_or_else(|| FetchError::Parse {
+    let url = source.fetch_url().ok_or_else(|| FetchError::Parse {
         url: source.name.clone(),
         message: "RSS source has no URL".to_string(),
     })?;
 
     let max_items = source.max_items as usize;
 
-    // Build HTTP client with auth if needed
+    let response = fetch_feed(source, url).await?;
+    let Some(feed) = response.feed else {
+        return Ok(FetchResult {
+            items: Vec::new(),
+            etag: response.etag,
+            last_modified: response.last_modified,
+            moved_to: response.moved_to,
+        });
+    };
+
+    let now = Utc::now();
+
+    let items: Vec<ContentItem> = feed
+        .entries
+        .into_iter()
+        .take(max_items)
+        .filter_map(|entry| {
+            // Get the best content: prefer content over summary
+            let raw_body = entry
+                .content
+                .and_then(|c| c.body)
+                .or_else(|| entry.summary.map(|s| s.content))
+                .unwrap_or_default();
+
+            let (body, truncated) = entry_body(&raw_body);
+            let metadata = match truncated {
+                Some(metadata) => {
+                    warn!(
+                        source = %source.name,
+                        entry_id = %entry.id,
+                        bytes = raw_body.len(),
+                        "feed entry too large, truncating its body"
+                    );
+                    metadata
+                }
+                None => "{}".to_string(),
+            };
+
+            if body.is_empty() && entry.title.is_none() {
+                debug!(entry_id = ?entry.id, "skipping empty entry");
+                return None;
+            }
+
+            let title: Option<String> = entry
+                .title
+                .map(|t| sanitize_text(&t.content).chars().take(MAX_TITLE_CHARS).collect());
+            let url = entry.links.first().map(|l| l.href.clone());
+            let author = entry.authors.first().map(|a| sanitize_text(&a.name));
+
+            let original_date: DateTime<Utc> = entry.published.or(entry.updated).unwrap_or(now);
+
+            let dedup_key = entry_dedup_key(
+                source.dedup.as_deref(),
+                &entry.id,
+                url.as_deref(),
+                title.as_deref(),
+                &body,
+                true,
+            );
+
+            let content_type = if url.is_some() { "link" } else { "text" };
+
+            Some(ContentItem {
+                id: Uuid::new_v4().to_string(),
+                source_id: source.id.clone(),
+                ingested_at: now,
+                original_date,
+                content_type: content_type.to_string(),
+                title,
+                body,
+                url,
+                author,
+                metadata,
+                dedup_key,
+                upstream_changed: false,
+            })
+        })
+        .collect();
+
+    if items.is_empty() {
+        warn!(source = %source.name, url = %url, "feed returned no usable items");
+    }
+
+    Ok(FetchResult {
+        items,
+        etag: response.etag,
+        last_modified: response.last_modified,
+        moved_to: response.moved_to,
+    })
+}
+
+/// An entry's content as plain text (RSS bodies are often HTML), within the size caps. A cut
+/// body comes with the item metadata that flags it.
+fn entry_body(raw_body: &str) -> (String, Option<String>) {
+    let html = cut_at(raw_body, MAX_ENTRY_HTML_BYTES);
+    let body = sanitize_text(&strip_html(html));
+    let truncated = html.len() < raw_body.len() || body.len() > MAX_BODY_BYTES;
+    let body = cut_at(&body, MAX_BODY_BYTES).to_string();
+    let metadata =
+        truncated.then(|| serde_json::json!({ "truncated": true, "original_bytes": raw_body.len() }).to_string());
+    (body, metadata)
+}
+
+/// Dedup key for a feed entry per the source's `dedup` strategy (see docs/specs/rss-sources.md
+/// "Deduplication"). `guid` (the default): GUID if available, else SHA-256 of URL + title;
+/// `url`: the URL, else as `guid`; `url+title`: SHA-256 of URL + title; `content_hash`:
+/// SHA-256 of title + body. With `normalize`, the `url` and `url+title` keys use the URL
+/// without tracking parameters; the `guid` fallback always hashes it as written, so keys
+/// stored before normalization existed stay the same.
+pub(crate) fn entry_dedup_key(
+    strategy: Option<&str>,
+    entry_id: &str,
+    url: Option<&str>,
+    title: Option<&str>,
+    body: &str,
+    normalize: bool,
+) -> String {
+    let normalized = url.map(|u| if normalize { normalize_url(u) } else { u.to_string() });
+    let hash = |a: &str, b: &str| {
+        let mut hasher = Sha256::new();
+        hasher.update(a);
+        hasher.update("|");
+        hasher.update(b);
+        format!("sha256:{:x}", hasher.finalize())
+    };
+    match strategy.unwrap_or("guid") {
+        "url" if normalized.is_some() => normalized.unwrap_or_default(),
+        "url+title" => hash(normalized.as_deref().unwrap_or(""), title.unwrap_or("")),
+        "content_hash" => hash(title.unwrap_or(""), body),
+        _ if !entry_id.is_empty() => entry_id.to_string(),
+        _ => hash(url.unwrap_or(""), title.unwrap_or("")),
+    }
+}
+
+/// Drop `utm_*` tracking parameters from a URL's query, so links that differ only in campaign
+/// tags dedup as one. Other parameters are kept as written; unparseable URLs are returned as is.
+pub(crate) fn normalize_url(url: &str) -> String {
+    let Ok(mut parsed) = reqwest::Url::parse(url) else {
+        return url.to_string();
+    };
+    let Some(query) = parsed.query() else {
+        return url.to_string();
+    };
+    let is_tracking = |pair: &str| {
+        let key = pair.split('=').next().unwrap_or("");
+        key.len() > 4 && key.get(..4).is_some_and(|prefix| prefix.eq_ignore_ascii_case("utm_"))
+    };
+    if !query.split('&').any(is_tracking) {
+        return url.to_string();
+    }
+    let kept: Vec<&str> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty() && !is_tracking(pair))
+        .collect();
+    let kept = kept.join("&");
+    parsed.set_query((!kept.is_empty()).then_some(kept.as_str()));
+    parsed.to_string()
+}
+
+/// A fetched and parsed feed plus the HTTP cache headers to store for the next conditional GET.
+pub(crate) struct FeedResponse {
+    /// `None` on 304 Not Modified.
+    pub feed: Option<feed_rs::model::Feed>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Final URL when the request was redirected only permanently (301/308).
+    pub moved_to: Option<String>,
+}
+
+/// Conditional GET + parse of a source's feed (shared by RSS and podcast sources).
+/// On 304 Not Modified, returns no feed and the existing cache headers.
+///
+/// Redirects are followed as usual; when all of them were permanent the final URL is returned
+/// in `moved_to` (see docs/specs/rss-sources.md "Moved and Gone Feeds"). `410 Gone` is
+/// reported as `FetchError::Gone`.
+pub(crate) async fn fetch_feed(source: &Source, url: &str) -> Result<FeedResponse> {
+    // Cleared by the redirect policy on the first temporary redirect of the chain
+    let permanent = Arc::new(AtomicBool::new(true));
+    let policy = {
+        let permanent = permanent.clone();
+        Policy::custom(move |attempt| {
+            if !matches!(
+                attempt.status(),
+                StatusCode::MOVED_PERMANENTLY | StatusCode::PERMANENT_REDIRECT
+            ) {
+                permanent.store(false, Ordering::Relaxed);
+            }
+            if attempt.previous().len() > MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else {
+                attempt.follow()
+            }
+        })
+    };
+    let client = client_builder(source, url)?
+        .redirect(policy)
+        .build()
+        .map_err(|e| FetchError::Http {
+            url: url.to_string(),
+            source: e,
+        })?;
+
+    debug!(url = %url, source = %source.name, "fetching feed");
+
+    let mut response = client.get(url).send().await.map_err(|e| FetchError::Http {
+        url: url.to_string(),
+        source: e,
+    })?;
+
+    let moved_to =
+        (response.url().as_str() != url && permanent.load(Ordering::Relaxed)).then(|| response.url().to_string());
+    if response.status() == StatusCode::GONE {
+        return Err(FetchError::Gone { url: url.to_string() }.into());
+    }
+
+    // Extract cache headers from response before consuming the body
+    let resp_etag = response
+        .headers()
+        .get("etag")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+    let resp_last_modified = response
+        .headers()
+        .get("last-modified")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+
+    // Handle 304 Not Modified — feed hasn't changed
+    if response.status() == StatusCode::NOT_MODIFIED {
+        info!(source = %source.name, url = %url, "feed not modified (304)");
+        return Ok(FeedResponse {
+            feed: None,
+            etag: resp_etag.or_else(|| source.last_etag.clone()),
+            last_modified: resp_last_modified.or_else(|| source.last_modified_header.clone()),
+            moved_to,
+        });
+    }
+
+    // Check for HTTP errors before trying to parse the body as RSS
+    if !response.status().is_success() {
+        return Err(FetchError::Http {
+            url: url.to_string(),
+            source: response.error_for_status().unwrap_err(),
+        }
+        .into());
+    }
+
+    let too_large = || FetchError::TooLarge {
+        url: url.to_string(),
+        limit: MAX_FEED_BYTES,
+    };
+    if response.content_length().is_some_and(|len| len > MAX_FEED_BYTES as u64) {
+        return Err(too_large().into());
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| FetchError::Http {
+        url: url.to_string(),
+        source: e,
+    })? {
+        body.extend_from_slice(&chunk);
+        if body.len() > MAX_FEED_BYTES {
+            return Err(too_large().into());
+        }
+    }
+
+    let feed = match feed_rs::parser::parse(&body[..]) {
+        Ok(feed) => feed,
+        // Invalid UTF-8 without another declared encoding: parse with the bad bytes replaced
+        Err(e) if std::str::from_utf8(&body).is_err() => {
+            warn!(source = %source.name, url = %url, "feed isn't valid UTF-8, replacing invalid bytes");
+            feed_rs::parser::parse(String::from_utf8_lossy(&body).as_bytes()).map_err(|_| FetchError::Parse {
+                url: url.to_string(),
+                message: e.to_string(),
+            })?
+        }
+        Err(e) => {
+            return Err(FetchError::Parse {
+                url: url.to_string(),
+                message: e.to_string(),
+            }
+            .into());
+        }
+    };
+
+    Ok(FeedResponse {
+        feed: Some(feed),
+        etag: resp_etag,
+        last_modified: resp_last_modified,
+        moved_to,
+    })
+}
+
+/// Build an HTTP client for fetching a source: auth headers, User-Agent, and
+/// conditional GET headers from the last successful fetch.
+pub(crate) fn build_client(source: &Source, url: &str) -> Result<reqwest::Client, FetchError> {
+    client_builder(source, url)?.build().map_err(|e| FetchError::Http {
+        url: url.to_string(),
+        source: e,
+    })
+}
+
+/// Client builder behind `build_client`, for callers that need extra settings.
+fn client_builder(source: &Source, url: &str) -> Result<reqwest::ClientBuilder, FetchError> {
     let mut headers = HeaderMap::new();
 
     // Use auth from DB model fields (synced from config)
@@ -77,6 +469,25 @@ pub async fn fetch_rss_source(source: &Source) -> Result<FetchResult> {
         HeaderValue::from_static(concat!("pail/", env!("CARGO_PKG_VERSION"))),
     );
 
+    // The source's own headers go on top, so its User-Agent replaces pail's
+    if let Some(ref extra) = source.headers {
+        let extra: BTreeMap<String, String> = serde_json::from_str(extra).map_err(|e| FetchError::Parse {
+            url: url.to_string(),
+            message: format!("invalid stored headers: {e}"),
+        })?;
+        for (name, value) in extra {
+            let header_value = HeaderValue::from_str(&value).map_err(|_| FetchError::Parse {
+                url: url.to_string(),
+                message: format!("invalid header value for {name}"),
+            })?;
+            let header_name: HeaderName = name.parse().map_err(|_| FetchError::Parse {
+                url: url.to_string(),
+                message: format!("invalid header name: {name}"),
+            })?;
+            headers.insert(header_name, header_value);
+        }
+    }
+
     // Add conditional GET headers if we have cached values
     if let Some(ref etag) = source.last_etag
         && let Ok(val) = HeaderValue::from_str(etag)
@@ -89,137 +500,250 @@ pub async fn fetch_rss_source(source: &Source) -> Result<FetchResult> {
         headers.insert(IF_MODIFIED_SINCE, val);
     }
 
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .default_headers(headers)
-        .build()
-        .map_err(|e| FetchError::Http {
-            url: url.to_string(),
-            source: e,
-        })?;
-
-    debug!(url = %url, source = %source.name, "fetching RSS feed");
-
-    let response = client.get(url).send().await.map_err(|e| FetchError::Http {
+    let timeout = source
+        .fetch_timeout
+        .as_deref()
+        .and_then(|t| humantime::parse_duration(t).ok())
+        .unwrap_or(DEFAULT_TIMEOUT);
+    let builder = reqwest::Client::builder().timeout(timeout).default_headers(headers);
+    http_client::with_proxy(builder, source.proxy.as_deref()).map_err(|e| FetchError::Http {
         url: url.to_string(),
         source: e,
-    })?;
-
-    // Extract cache headers from response before consuming the body
-    let resp_etag = response
-        .headers()
-        .get("etag")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string());
-    let resp_last_modified = response
-        .headers()
-        .get("last-modified")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string());
+    })
+}
 
-    // Handle 304 Not Modified — feed hasn't changed
-    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
-        info!(source = %source.name, url = %url, "feed not modified (304)");
-        return Ok(FetchResult {
-            items: Vec::new(),
-            etag: resp_etag.or_else(|| source.last_etag.clone()),
-            last_modified: resp_last_modified.or_else(|| source.last_modified_header.clone()),
-        });
+/// Convert HTML to plain text. If the input doesn't look like HTML, return it as-is.
+/// HTML nested deeper than `MAX_HTML_DEPTH` only has its tags removed.
+pub(crate) fn strip_html(text: &str) -> String {
+    if !text.contains('<') {
+        return text.to_string();
+    }
+    if html_too_deep(text) {
+        debug!(bytes = text.len(), "HTML nested too deeply to render, removing tags");
+        return remove_tags(text);
     }
+    html2text::from_read(text.as_bytes(), 200).unwrap_or_else(|_| text.to_string())
+}
 
-    // Check for HTTP errors before trying to parse the body as RSS
-    if !response.status().is_success() {
-        return Err(FetchError::Http {
-            url: url.to_string(),
-            source: response.error_for_status().unwrap_err(),
+/// Whether elements in `html` nest deeper than `MAX_HTML_DEPTH`. A rough stack of open tags,
+/// not a parse: void elements and self-closing tags don't nest, a closing tag closes what was
+/// opened after its element, and an unclosed `<p>` or `<li>` ends at the next one, as in HTML.
+fn html_too_deep(html: &str) -> bool {
+    let mut open: Vec<String> = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let end = rest.find('>').unwrap_or(rest.len());
+        let tag = &rest[..end];
+        rest = &rest[end..];
+        let closing = tag.strip_prefix('/');
+        let name = closing
+            .unwrap_or(tag)
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        if name.is_empty() {
+            continue;
+        }
+        if closing.is_some() {
+            if let Some(at) = open.iter().rposition(|n| *n == name) {
+                open.truncate(at);
+            }
+            continue;
+        }
+        if tag.ends_with('/') || VOID_ELEMENTS.contains(&name.as_str()) {
+            continue;
+        }
+        if name == "li" {
+            while open.last().is_some_and(|n| n == "p") {
+                open.pop();
+            }
+        }
+        if (name == "p" || name == "li") && open.last() == Some(&name) {
+            open.pop();
+        }
+        open.push(name);
+        if open.len() > MAX_HTML_DEPTH {
+            return true;
         }
-        .into());
     }
+    false
+}
 
-    let body = response.bytes().await.map_err(|e| FetchError::Http {
-        url: url.to_string(),
-        source: e,
-    })?;
+/// `html` without its tags. Entities are left as written.
+fn remove_tags(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                out.push(' ');
+            }
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
 
-    let feed = feed_rs::parser::parse(&body[..]).map_err(|e| FetchError::Parse {
-        url: url.to_string(),
-        message: e.to_string(),
-    })?;
+/// Drop control characters other than newlines and tabs: NUL bytes, terminal escape
+/// sequences and C1 codes that break workspace files. `\r\n` and a lone `\r` become `\n`.
+fn sanitize_text(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .chars()
+        .map(|c| if c == '\r' { '\n' } else { c })
+        .filter(|&c| c == '\n' || c == '\t' || !c.is_control())
+        .collect()
+}
 
-    let now = Utc::now();
+/// The start of `text`, at most `max` bytes, cut at a char boundary.
+fn cut_at(text: &str, max: usize) -> &str {
+    if text.len() <= max {
+        return text;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
 
-    let items: Vec<ContentItem> = feed
-        .entries
-        .into_iter()
-        .take(max_items)
-        .filter_map(|entry| {
-            // Get the best content: prefer content over summary
-            let raw_body = entry
-                .content
-                .and_then(|c| c.body)
-                .or_else(|| entry.summary.map(|s| s.content))
-                .unwrap_or_default();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            // Convert HTML to plain text (RSS bodies are often HTML)
-            let body = strip_html(&raw_body);
+    #[test]
+    fn test_normalize_url_drops_tracking_parameters() {
+        assert_eq!(
+            normalize_url("https://example.com/a?utm_source=rss&id=7&UTM_Medium=feed"),
+            "https://example.com/a?id=7"
+        );
+        assert_eq!(
+            normalize_url("https://example.com/a?utm_source=rss"),
+            "https://example.com/a"
+        );
+        // Untouched unless there is something to drop, including parameters merely starting with utm
+        assert_eq!(
+            normalize_url("https://example.com/a?b=1&&c"),
+            "https://example.com/a?b=1&&c"
+        );
+        assert_eq!(
+            normalize_url("https://example.com/a?utm=1"),
+            "https://example.com/a?utm=1"
+        );
+        assert_eq!(normalize_url("not a url?utm_source=x"), "not a url?utm_source=x");
+    }
 
-            if body.is_empty() && entry.title.is_none() {
-                debug!(entry_id = ?entry.id, "skipping empty entry");
-                return None;
-            }
+    #[test]
+    fn test_entry_dedup_key_strategies() {
+        let tagged = Some("https://example.com/post?utm_source=rss");
+        let clean = Some("https://example.com/post");
+        let key = |strategy, id, url, normalize| entry_dedup_key(strategy, id, url, Some("Title"), "Body", normalize);
 
-            let title = entry.title.map(|t| t.content);
-            let url = entry.links.first().map(|l| l.href.clone());
-            let author = entry.authors.first().map(|a| a.name.clone());
+        assert_eq!(key(None, "guid-1", tagged, true), "guid-1");
+        assert_eq!(key(Some("url"), "guid-1", tagged, true), "https://example.com/post");
+        assert_eq!(
+            key(Some("url"), "guid-1", tagged, false),
+            "https://example.com/post?utm_source=rss"
+        );
+        // Without a URL, `url` falls back to the GUID
+        assert_eq!(key(Some("url"), "guid-1", None, true), "guid-1");
+        assert_eq!(
+            key(Some("url+title"), "", tagged, true),
+            key(Some("url+title"), "", clean, true)
+        );
+        assert_ne!(
+            key(Some("url+title"), "", tagged, false),
+            key(Some("url+title"), "", clean, false)
+        );
+        assert_eq!(
+            key(Some("content_hash"), "a", tagged, true),
+            key(Some("content_hash"), "b", None, true)
+        );
+        assert!(key(Some("content_hash"), "a", None, true).starts_with("sha256:"));
+    }
 
-            let original_date: DateTime<Utc> = entry.published.or(entry.updated).unwrap_or(now);
+    #[test]
+    fn test_entry_dedup_key_guid_fallback_keeps_legacy_hash() {
+        // Keys stored before URL normalization hashed the URL as written
+        let tagged = Some("https://example.com/post?utm_source=rss");
+        let legacy = format!(
+            "sha256:{:x}",
+            Sha256::digest("https://example.com/post?utm_source=rss|Title")
+        );
+        assert_eq!(entry_dedup_key(None, "", tagged, Some("Title"), "", true), legacy);
+        assert_eq!(
+            entry_dedup_key(Some("guid"), "", tagged, Some("Title"), "", true),
+            legacy
+        );
+    }
 
-            // Dedup key: GUID if available, else SHA-256 of URL + title
-            // (see docs/specs/rss-sources.md "Deduplication")
-            let dedup_key = if !entry.id.is_empty() {
-                entry.id.clone()
-            } else {
-                let mut hasher = Sha256::new();
-                hasher.update(url.as_deref().unwrap_or(""));
-                hasher.update("|");
-                hasher.update(title.as_deref().unwrap_or(""));
-                format!("sha256:{:x}", hasher.finalize())
-            };
+    #[test]
+    fn test_sanitize_text_line_endings_and_controls() {
+        assert_eq!(sanitize_text("a\r\nb\rc\nd"), "a\nb\nc\nd");
+        assert_eq!(sanitize_text("tab\there\0\x1b[31mred\u{85}"), "tab\there[31mred");
+    }
 
-            let content_type = if url.is_some() { "link" } else { "text" };
+    #[test]
+    fn test_html_too_deep() {
+        let nested =
+            |tag: &str, n: usize| format!("{}text{}", format!("<{tag}>").repeat(n), format!("</{tag}>").repeat(n));
+        assert!(!html_too_deep(&nested("div", MAX_HTML_DEPTH)));
+        assert!(html_too_deep(&nested("div", MAX_HTML_DEPTH + 1)));
+        // Siblings, void elements and self-closing tags don't nest
+        assert!(!html_too_deep(&"<div>x</div>".repeat(1000)));
+        assert!(!html_too_deep(&format!(
+            "<div>{}</div>",
+            "<br><img src=x><span/>".repeat(1000)
+        )));
+    }
 
-            Some(ContentItem {
-                id: Uuid::new_v4().to_string(),
-                source_id: source.id.clone(),
-                ingested_at: now,
-                original_date,
-                content_type: content_type.to_string(),
-                title,
-                body,
-                url,
-                author,
-                metadata: "{}".to_string(),
-                dedup_key,
-                upstream_changed: false,
-            })
-        })
-        .collect();
+    #[test]
+    fn test_html_too_deep_unclosed_paragraphs_and_items() {
+        assert!(!html_too_deep(&"<p>paragraph".repeat(1000)));
+        assert!(!html_too_deep(&format!("<ul>{}</ul>", "<li>item".repeat(1000))));
+        assert!(!html_too_deep(&format!("<ul>{}</ul>", "<li><p>item".repeat(1000))));
+        // A closing tag closes what was left open inside its element
+        assert!(!html_too_deep(&"<div><span><b>x</div>".repeat(1000)));
+        // Nested lists still nest
+        assert!(html_too_deep(&"<ul><li>".repeat(MAX_HTML_DEPTH)));
+    }
 
-    if items.is_empty() {
-        warn!(source = %source.name, url = %url, "feed returned no usable items");
+    #[test]
+    fn test_cut_at() {
+        assert_eq!(cut_at("short", 10), "short");
+        assert_eq!(cut_at("exact", 5), "exact");
+        assert_eq!(cut_at("abcdef", 3), "abc");
+        // "é" is two bytes: a cut inside it goes back to its start
+        assert_eq!(cut_at("aé", 2), "a");
+        assert_eq!(cut_at("aé", 3), "aé");
+        assert_eq!(cut_at("é", 0), "");
     }
 
-    Ok(FetchResult {
-        items,
-        etag: resp_etag,
-        last_modified: resp_last_modified,
-    })
-}
+    #[test]
+    fn test_entry_body_truncation_metadata() {
+        let (body, metadata) = entry_body("<p>Hello <b>world</b></p>");
+        assert_eq!(body.trim(), "Hello world");
+        assert_eq!(metadata, None);
 
-/// Convert HTML to plain text. If the input doesn't look like HTML, return it as-is.
-fn strip_html(text: &str) -> String {
-    if !text.contains('<') {
-        return text.to_string();
+        // Text over the body cap
+        let long = "word ".repeat(MAX_BODY_BYTES / 5 + 100);
+        let (body, metadata) = entry_body(&long);
+        assert_eq!(body.len(), MAX_BODY_BYTES);
+        let metadata: serde_json::Value = serde_json::from_str(&metadata.unwrap()).unwrap();
+        assert_eq!(
+            metadata,
+            serde_json::json!({ "truncated": true, "original_bytes": long.len() })
+        );
+
+        // HTML over the conversion cap is flagged even when its text is short
+        let html = format!("<p>short</p><!--{}-->", "x".repeat(MAX_ENTRY_HTML_BYTES));
+        let (body, metadata) = entry_body(&html);
+        assert!(body.len() < MAX_BODY_BYTES);
+        let metadata: serde_json::Value = serde_json::from_str(&metadata.unwrap()).unwrap();
+        assert_eq!(metadata["original_bytes"], html.len());
     }
-    html2text::from_read(text.as_bytes(), 200).unwrap_or_else(|_| text.to_string())
 }
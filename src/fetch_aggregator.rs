@@ -0,0 +1,444 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use reqwest::header::USER_AGENT;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use sqlx::SqlitePool;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::config::AggregatorSourceConfig;
+use crate::error::FetchError;
+use crate::fetch::{self, FetchResult};
+use crate::http_client;
+use crate::models::{ContentItem, Source};
+use crate::store;
+
+const REQUEST_TIMEOUT_SECS: u64 = 30;
+
+/// Lobsters instance used when the source has no `url`.
+const LOBSTERS_DEFAULT_URL: &str = "https://lobste.rs";
+
+/// Lemmy caps `limit` on listings at 50.
+const LEMMY_MAX_LIMIT: i32 = 50;
+
+/// A post, normalized across aggregators.
+struct Post {
+    /// Stable ID for the dedup key (Lemmy: ActivityPub ID, Lobsters: short ID).
+    id: String,
+    title: String,
+    /// Linked article; `None` for text posts.
+    link: Option<String>,
+    discussion_url: String,
+    author: Option<String>,
+    published: DateTime<Utc>,
+    body: String,
+    score: i64,
+    comment_count: i64,
+    /// Community (Lemmy) or tags (Lobsters).
+    topics: Vec<String>,
+    /// Lemmy post ID on the queried instance, for the comment listing.
+    lemmy_post_id: Option<i64>,
+}
+
+struct Comment {
+    author: Option<String>,
+    score: i64,
+    text: String,
+}
+
+/// Fetch posts from a link aggregator (`lemmy`, `lobsters`). Posts below `min_score` are
+/// skipped until a later poll sees them above it; comments are only fetched for posts not
+/// stored yet (stored items are immutable).
+pub async fn fetch_aggregator_source(pool: &SqlitePool, source: &Source) -> Result<FetchResult> {
+    let settings: AggregatorSourceConfig = match source.aggregator_config.as_deref() {
+        Some(json) => serde_json::from_str(json).map_err(|e| FetchError::Parse {
+            url: source.name.clone(),
+            message: format!("invalid stored aggregator settings: {e}"),
+        })?,
+        None => AggregatorSourceConfig::default(),
+    };
+    let base_url = match source.source_type.as_str() {
+        "lobsters" => source.url.as_deref().unwrap_or(LOBSTERS_DEFAULT_URL),
+        _ => source.url.as_deref().ok_or_else(|| FetchError::Parse {
+            url: source.name.clone(),
+            message: format!("{} source has no URL", source.source_type),
+        })?,
+    }
+    .trim_end_matches('/');
+
+    let builder = reqwest::Client::builder().timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS));
+    let client = http_client::with_proxy(builder, source.proxy.as_deref())
+        .and_then(|builder| builder.build())
+        .context("building HTTP client")?;
+
+    let posts = match source.source_type.as_str() {
+        "lemmy" => fetch_lemmy_posts(&client, source, base_url, &settings).await?,
+        "lobsters" => fetch_lobsters_posts(&client, base_url, &settings).await?,
+        other => anyhow::bail!("unsupported aggregator '{other}'"),
+    };
+    let listed = posts.len();
+
+    let now = Utc::now();
+    let mut items = Vec::new();
+    for post in posts.into_iter().take(source.max_items as usize) {
+        if settings.min_score.is_some_and(|min| post.score < min) {
+            continue;
+        }
+        let dedup_key = format!("{}:{}", source.source_type, post.id);
+        let mut comments = Vec::new();
+        if settings.include_comments
+            && post.comment_count > 0
+            && !store::content_item_exists(pool, &source.id, &dedup_key).await?
+        {
+            let fetched = match source.source_type.as_str() {
+                "lemmy" => fetch_lemmy_comments(&client, base_url, &post, settings.max_comments).await,
+                _ => fetch_lobsters_comments(&client, base_url, &post, settings.max_comments).await,
+            };
+            match fetched {
+                Ok(fetched) => comments = fetched,
+                // The post is still worth ingesting without its discussion
+                Err(e) => warn!(source = %source.name, post = %post.discussion_url, "failed to fetch comments: {e:#}"),
+            }
+        }
+        items.push(build_item(source, post, comments, dedup_key, now));
+    }
+    info!(
+        source = %source.name,
+        service = %source.source_type,
+        listed,
+        kept = items.len(),
+        "fetched aggregator posts"
+    );
+
+    Ok(FetchResult {
+        items,
+        etag: None,
+        last_modified: None,
+        moved_to: None,
+    })
+}
+
+async fn get_json<T: DeserializeOwned>(client: &reqwest::Client, url: &str, query: &[(&str, String)]) -> Result<T> {
+    let response = client
+        .get(url)
+        .header(USER_AGENT, concat!("pail/", env!("CARGO_PKG_VERSION")))
+        .query(query)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| FetchError::Http {
+            url: url.to_string(),
+            source: e,
+        })?;
+    response
+        .json()
+        .await
+        .with_context(|| format!("parsing response from {url}"))
+}
+
+#[derive(Deserialize)]
+struct LemmyPostList {
+    posts: Vec<LemmyPostView>,
+}
+
+#[derive(Deserialize)]
+struct LemmyPostView {
+    post: LemmyPost,
+    creator: LemmyPerson,
+    community: LemmyCommunity,
+    counts: LemmyPostCounts,
+}
+
+#[derive(Deserialize)]
+struct LemmyPost {
+    id: i64,
+    name: String,
+    url: Option<String>,
+    body: Option<String>,
+    published: String,
+    ap_id: String,
+}
+
+#[derive(Deserialize)]
+struct LemmyPerson {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct LemmyCommunity {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct LemmyPostCounts {
+    score: i64,
+    comments: i64,
+}
+
+#[derive(Deserialize)]
+struct LemmyCommentList {
+    comments: Vec<LemmyCommentView>,
+}
+
+#[derive(Deserialize)]
+struct LemmyCommentView {
+    comment: LemmyComment,
+    creator: LemmyPerson,
+    counts: LemmyCommentCounts,
+}
+
+#[derive(Deserialize)]
+struct LemmyComment {
+    content: String,
+    #[serde(default)]
+    deleted: bool,
+    #[serde(default)]
+    removed: bool,
+}
+
+#[derive(Deserialize)]
+struct LemmyCommentCounts {
+    score: i64,
+}
+
+/// Lemmy: `GET /api/v3/post/list` for the community, newest `max_items` in `sort` order.
+async fn fetch_lemmy_posts(
+    client: &reqwest::Client,
+    source: &Source,
+    base_url: &str,
+    settings: &AggregatorSourceConfig,
+) -> Result<Vec<Post>> {
+    let url = format!("{base_url}/api/v3/post/list");
+    let community = settings.community.as_deref().unwrap_or_default();
+    let list: LemmyPostList = get_json(
+        client,
+        &url,
+        &[
+            ("community_name", community.to_string()),
+            ("sort", settings.sort.clone().unwrap_or_else(|| "Hot".to_string())),
+            ("limit", source.max_items.min(LEMMY_MAX_LIMIT).to_string()),
+            ("type_", "All".to_string()),
+        ],
+    )
+    .await?;
+
+    debug!(source = %source.name, posts = list.posts.len(), "lemmy posts");
+    Ok(list
+        .posts
+        .into_iter()
+        .map(|view| Post {
+            id: view.post.ap_id,
+            title: view.post.name,
+            link: view.post.url.filter(|u| !u.is_empty()),
+            discussion_url: format!("{base_url}/post/{}", view.post.id),
+            author: Some(view.creator.name),
+            published: parse_lemmy_date(&view.post.published).unwrap_or_else(Utc::now),
+            body: view.post.body.unwrap_or_default(),
+            score: view.counts.score,
+            comment_count: view.counts.comments,
+            topics: vec![view.community.name],
+            lemmy_post_id: Some(view.post.id),
+        })
+        .collect())
+}
+
+/// Lemmy: top-level comments of a post by score, `GET /api/v3/comment/list`.
+async fn fetch_lemmy_comments(
+    client: &reqwest::Client,
+    base_url: &str,
+    post: &Post,
+    max_comments: u32,
+) -> Result<Vec<Comment>> {
+    let Some(post_id) = post.lemmy_post_id else {
+        return Ok(Vec::new());
+    };
+    let url = format!("{base_url}/api/v3/comment/list");
+    let list: LemmyCommentList = get_json(
+        client,
+        &url,
+        &[
+            ("post_id", post_id.to_string()),
+            ("sort", "Top".to_string()),
+            ("max_depth", "1".to_string()),
+            ("limit", max_comments.to_string()),
+            ("type_", "All".to_string()),
+        ],
+    )
+    .await?;
+
+    Ok(list
+        .comments
+        .into_iter()
+        .filter(|view| !view.comment.deleted && !view.comment.removed)
+        .take(max_comments as usize)
+        .map(|view| Comment {
+            author: Some(view.creator.name),
+            score: view.counts.score,
+            text: view.comment.content,
+        })
+        .collect())
+}
+
+/// Lemmy timestamps are RFC 3339 since 0.19; older instances omit the offset (UTC).
+fn parse_lemmy_date(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|d| d.to_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f").map(|d| d.and_utc()))
+        .ok()
+}
+
+#[derive(Deserialize)]
+struct LobstersStory {
+    short_id: String,
+    title: String,
+    #[serde(default)]
+    url: String,
+    comments_url: String,
+    created_at: DateTime<chrono::FixedOffset>,
+    score: i64,
+    comment_count: i64,
+    #[serde(default)]
+    description_plain: String,
+    /// A username, or `{"username": ...}` on older versions.
+    #[serde(default)]
+    submitter_user: serde_json::Value,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct LobstersStoryDetail {
+    #[serde(default)]
+    comments: Vec<LobstersComment>,
+}
+
+#[derive(Deserialize)]
+struct LobstersComment {
+    #[serde(default)]
+    comment_plain: String,
+    score: i64,
+    depth: u32,
+    #[serde(default)]
+    commenting_user: serde_json::Value,
+    #[serde(default)]
+    is_deleted: bool,
+    #[serde(default)]
+    is_moderated: bool,
+}
+
+/// Lobsters: `/t/<tags>.json` for the configured tags, else the front page `/hottest.json`.
+async fn fetch_lobsters_posts(
+    client: &reqwest::Client,
+    base_url: &str,
+    settings: &AggregatorSourceConfig,
+) -> Result<Vec<Post>> {
+    let url = if settings.tags.is_empty() {
+        format!("{base_url}/hottest.json")
+    } else {
+        format!("{base_url}/t/{}.json", settings.tags.join(","))
+    };
+    let stories: Vec<LobstersStory> = get_json(client, &url, &[]).await?;
+
+    Ok(stories
+        .into_iter()
+        .map(|story| Post {
+            id: story.short_id,
+            title: story.title,
+            link: Some(story.url).filter(|u| !u.is_empty()),
+            discussion_url: story.comments_url,
+            author: lobsters_username(&story.submitter_user),
+            published: story.created_at.to_utc(),
+            body: story.description_plain,
+            score: story.score,
+            comment_count: story.comment_count,
+            topics: story.tags,
+            lemmy_post_id: None,
+        })
+        .collect())
+}
+
+/// Lobsters: top-level comments of a story by score, from `/s/<short_id>.json`.
+async fn fetch_lobsters_comments(
+    client: &reqwest::Client,
+    base_url: &str,
+    post: &Post,
+    max_comments: u32,
+) -> Result<Vec<Comment>> {
+    let url = format!("{base_url}/s/{}.json", post.id);
+    let detail: LobstersStoryDetail = get_json(client, &url, &[]).await?;
+
+    let mut comments: Vec<LobstersComment> = detail
+        .comments
+        .into_iter()
+        .filter(|c| c.depth == 0 && !c.is_deleted && !c.is_moderated)
+        .collect();
+    comments.sort_by_key(|c| std::cmp::Reverse(c.score));
+    Ok(comments
+        .into_iter()
+        .take(max_comments as usize)
+        .map(|c| Comment {
+            author: lobsters_username(&c.commenting_user),
+            score: c.score,
+            text: c.comment_plain,
+        })
+        .collect())
+}
+
+fn lobsters_username(user: &serde_json::Value) -> Option<String> {
+    user.as_str()
+        .or_else(|| user.get("username").and_then(|u| u.as_str()))
+        .map(str::to_string)
+}
+
+/// `url` is the linked article (the discussion page for text posts); the discussion page,
+/// score and comment count go into metadata.
+fn build_item(
+    source: &Source,
+    post: Post,
+    comments: Vec<Comment>,
+    dedup_key: String,
+    now: DateTime<Utc>,
+) -> ContentItem {
+    let mut body = fetch::strip_html(&post.body).trim().to_string();
+    if !comments.is_empty() {
+        if !body.is_empty() {
+            body.push_str("\n\n");
+        }
+        body.push_str("Top comments:");
+        for comment in &comments {
+            let text = comment.text.split_whitespace().collect::<Vec<_>>().join(" ");
+            let author = comment.author.as_deref().unwrap_or("[deleted]");
+            body.push_str(&format!("\n- {author} ({}): {text}", comment.score));
+        }
+    }
+
+    let mut metadata = json!({
+        "service": source.source_type,
+        "score": post.score,
+        "comment_count": post.comment_count,
+        "discussion_url": post.discussion_url,
+    });
+    if !post.topics.is_empty() {
+        metadata["topics"] = json!(post.topics);
+    }
+
+    let content_type = if post.link.is_some() { "link" } else { "text" };
+    ContentItem {
+        id: Uuid::new_v4().to_string(),
+        source_id: source.id.clone(),
+        ingested_at: now,
+        original_date: post.published,
+        content_type: content_type.to_string(),
+        title: Some(post.title.trim().to_string()).filter(|t| !t.is_empty()),
+        body,
+        url: Some(post.link.unwrap_or(post.discussion_url)),
+        author: post.author,
+        metadata: metadata.to_string(),
+        dedup_key,
+        upstream_changed: false,
+    }
+}
@@ -0,0 +1,259 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::Utc;
+use futures_util::StreamExt;
+use reqwest::Url;
+use serde_json::json;
+use sqlx::SqlitePool;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::config::{ArxivSourceConfig, Config};
+use crate::error::FetchError;
+use crate::fetch::{self, FetchResult};
+use crate::http_client::Crawler;
+use crate::models::{ContentItem, Source};
+use crate::{prefetch, store};
+
+/// Query API used when the source has no `url`.
+const API_DEFAULT_URL: &str = "https://export.arxiv.org/api/query";
+
+/// Canonical host for abstract, PDF and HTML links.
+const ARXIV_URL: &str = "https://arxiv.org";
+
+/// Largest `max_results` the API serves in one request.
+const API_MAX_RESULTS: i32 = 2000;
+
+/// arXiv asks automated clients to wait this long between requests.
+const REQUEST_DELAY: Duration = Duration::from_secs(3);
+
+/// Full-text requests in flight; the crawler still spaces them by `REQUEST_DELAY`, so this
+/// only bounds how far ahead they queue.
+const CONCURRENT_FULL_TEXTS: usize = 4;
+
+/// Authors listed before "et al.".
+const MAX_AUTHORS: usize = 3;
+
+/// Fetch the newest papers in the configured categories matching the keywords, newest
+/// `max_items` first. With `full_text`, new papers also get their HTML full text, one request
+/// each through the crawler, at least `REQUEST_DELAY` apart.
+pub async fn fetch_arxiv_source(pool: &SqlitePool, config: &Config, source: &Source) -> Result<FetchResult> {
+    let settings: ArxivSourceConfig = match source.arxiv_config.as_deref() {
+        Some(json) => serde_json::from_str(json).map_err(|e| FetchError::Parse {
+            url: source.name.clone(),
+            message: format!("invalid stored arxiv settings: {e}"),
+        })?,
+        None => {
+            return Err(FetchError::Parse {
+                url: source.name.clone(),
+                message: "arxiv source has no [source.arxiv] settings".to_string(),
+            }
+            .into());
+        }
+    };
+    let base = source.url.as_deref().unwrap_or(API_DEFAULT_URL);
+    let url = reqwest::Url::parse_with_params(
+        base,
+        &[
+            ("search_query", search_query(&settings)),
+            ("sortBy", "submittedDate".to_string()),
+            ("sortOrder", "descending".to_string()),
+            ("max_results", source.max_items.clamp(1, API_MAX_RESULTS).to_string()),
+        ],
+    )
+    .map_err(|e| FetchError::Parse {
+        url: base.to_string(),
+        message: format!("invalid arxiv API URL: {e}"),
+    })?;
+
+    // No conditional GET: the API doesn't send cache headers for queries
+    let mut unconditional = source.clone();
+    unconditional.last_etag = None;
+    unconditional.last_modified_header = None;
+    let Some(feed) = fetch::fetch_feed(&unconditional, url.as_str()).await?.feed else {
+        return Ok(FetchResult {
+            items: Vec::new(),
+            etag: None,
+            last_modified: None,
+            moved_to: None,
+        });
+    };
+
+    // The API reports query errors as a feed with a single error entry
+    if let Some(error) = feed.entries.iter().find(|e| e.id.contains("/api/errors")) {
+        let message = error.summary.as_ref().map(|s| s.content.trim().to_string());
+        anyhow::bail!(
+            "arXiv API error: {}",
+            message.unwrap_or_else(|| "unknown error".to_string())
+        );
+    }
+
+    let listed = feed.entries.len();
+    let now = Utc::now();
+    let mut papers: Vec<(ContentItem, serde_json::Value)> = Vec::new();
+    // (index into `papers`, arXiv ID with version) of the papers that get their full text
+    let mut wanted: Vec<(usize, String)> = Vec::new();
+    for entry in feed.entries {
+        let Some(abs_id) = entry.id.split("/abs/").nth(1).map(str::to_string) else {
+            continue;
+        };
+        let title = entry.title.map(|t| collapse(&t.content)).unwrap_or_default();
+        let abstract_text = entry.summary.map(|s| collapse(&s.content)).unwrap_or_default();
+        // The API's field search is stemmed and tokenized; keywords are meant literally
+        let searchable = format!("{title}\n{abstract_text}");
+        if !matches_any(&searchable, &settings.keywords) {
+            continue;
+        }
+
+        // Versions of a paper are one item: the first one seen is kept
+        let paper_id = strip_version(&abs_id).to_string();
+        let dedup_key = format!("arxiv:{paper_id}");
+        if settings.full_text
+            && matches_any(&searchable, &settings.full_text_keywords)
+            && !store::content_item_exists(pool, &source.id, &dedup_key).await?
+        {
+            wanted.push((papers.len(), abs_id.clone()));
+        }
+
+        let names: Vec<&str> = entry.authors.iter().map(|a| a.name.as_str()).collect();
+        let author = match names.len() {
+            0 => None,
+            n if n > MAX_AUTHORS => Some(format!("{} et al.", names[..MAX_AUTHORS].join(", "))),
+            _ => Some(names.join(", ")),
+        };
+        let categories: Vec<&str> = entry.categories.iter().map(|c| c.term.as_str()).collect();
+        let metadata = json!({
+            "arxiv_id": abs_id,
+            "pdf_url": format!("{ARXIV_URL}/pdf/{abs_id}"),
+            "categories": categories,
+            "full_text": false,
+        });
+
+        let item = ContentItem {
+            id: Uuid::new_v4().to_string(),
+            source_id: source.id.clone(),
+            ingested_at: now,
+            original_date: entry.published.or(entry.updated).unwrap_or(now),
+            content_type: "paper".to_string(),
+            title: Some(title),
+            body: abstract_text,
+            url: Some(format!("{ARXIV_URL}/abs/{paper_id}")),
+            author,
+            metadata: String::new(),
+            dedup_key,
+            upstream_changed: false,
+        };
+        papers.push((item, metadata));
+    }
+
+    let mut full_texts = 0;
+    if !wanted.is_empty() {
+        let client = fetch::build_client(&unconditional, ARXIV_URL)?;
+        let crawler = Crawler::new(client, &config.network)?.with_min_delay(REQUEST_DELAY);
+        let texts: Vec<(usize, Option<String>)> = futures_util::stream::iter(wanted)
+            .map(|(index, abs_id)| {
+                let crawler = &crawler;
+                async move { (index, fetch_full_text(crawler, &source.name, &abs_id).await) }
+            })
+            .buffer_unordered(CONCURRENT_FULL_TEXTS)
+            .collect()
+            .await;
+        for (index, text) in texts {
+            if let Some(text) = text {
+                let (item, metadata) = &mut papers[index];
+                item.body.push_str("\n\nFull text:\n\n");
+                item.body.push_str(&text);
+                metadata["full_text"] = json!(true);
+                full_texts += 1;
+            }
+        }
+    }
+    let items: Vec<ContentItem> = papers
+        .into_iter()
+        .map(|(mut item, metadata)| {
+            item.metadata = metadata.to_string();
+            item
+        })
+        .collect();
+
+    info!(
+        source = %source.name,
+        listed,
+        items = items.len(),
+        full_texts,
+        "fetched arXiv papers"
+    );
+    Ok(FetchResult {
+        items,
+        etag: None,
+        last_modified: None,
+        moved_to: None,
+    })
+}
+
+/// `(cat:A OR cat:B) AND (ti:"k" OR abs:"k" ...)`, the keyword clause only when keywords are set.
+fn search_query(settings: &ArxivSourceConfig) -> String {
+    let categories = settings
+        .categories
+        .iter()
+        .map(|c| format!("cat:{c}"))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    if settings.keywords.is_empty() {
+        return format!("({categories})");
+    }
+    let keywords = settings
+        .keywords
+        .iter()
+        .map(|k| format!("ti:\"{k}\" OR abs:\"{k}\""))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    format!("({categories}) AND ({keywords})")
+}
+
+/// Case-insensitive substring match against any keyword; an empty list matches everything.
+fn matches_any(text: &str, keywords: &[String]) -> bool {
+    if keywords.is_empty() {
+        return true;
+    }
+    let text = text.to_lowercase();
+    keywords.iter().any(|k| text.contains(&k.trim().to_lowercase()))
+}
+
+/// `2401.01234v2` → `2401.01234`, `hep-th/9901001v1` → `hep-th/9901001`.
+fn strip_version(id: &str) -> &str {
+    match id.rsplit_once('v') {
+        Some((base, version)) if !version.is_empty() && version.chars().all(|c| c.is_ascii_digit()) => base,
+        _ => id,
+    }
+}
+
+/// Titles and abstracts come hard-wrapped; join them into single lines.
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Main text of the paper's HTML rendering (`arxiv.org/html/<id>`). Not every paper has one
+/// (older papers, LaTeX the converter can't handle); those keep just the abstract.
+async fn fetch_full_text(crawler: &Crawler, source_name: &str, abs_id: &str) -> Option<String> {
+    let url = Url::parse(&format!("{ARXIV_URL}/html/{abs_id}")).ok()?;
+    let response = match crawler.get(&url).await {
+        Ok(response) if response.status() == reqwest::StatusCode::NOT_FOUND => {
+            debug!(source = %source_name, paper = %abs_id, "no HTML full text for paper");
+            return None;
+        }
+        Ok(response) if !response.status().is_success() => {
+            let status = response.status();
+            warn!(source = %source_name, paper = %abs_id, "failed to fetch arXiv full text: HTTP {status}");
+            return None;
+        }
+        Ok(response) => response,
+        Err(e) => {
+            warn!(source = %source_name, paper = %abs_id, "failed to fetch arXiv full text: {e}");
+            return None;
+        }
+    };
+    let html = response.text().await.ok()?;
+    prefetch::extract_text(&html).map(|(_, text)| text)
+}
@@ -0,0 +1,866 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::config::{Config, IcsSourceConfig};
+use crate::error::FetchError;
+use crate::fetch::{self, FetchResult};
+use crate::models::{ContentItem, Source};
+
+/// Recurrence periods walked per event before giving up (a daily event from 1970 is ~20k).
+const MAX_RECURRENCE_PERIODS: usize = 100_000;
+
+/// Fetch an iCalendar feed and turn events starting within `ics.lookahead` into items. Each
+/// occurrence is stored once (dedup key: UID + start), dated when it was first seen, so an
+/// event shows up in the first digest after it comes within the lookahead.
+pub async fn fetch_ics_source(config: &Config, source: &Source) -> Result<FetchResult> {
+    let url = source.fetch_url().ok_or_else(|| FetchError::Parse {
+        url: source.name.clone(),
+        message: "ics source has no URL".to_string(),
+    })?;
+    // webcal:// is a hint for calendar apps; the feed itself is served over HTTPS
+    let url = match url.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{rest}"),
+        None => url.to_string(),
+    };
+    let settings: IcsSourceConfig = match source.ics_config.as_deref() {
+        Some(json) => serde_json::from_str(json).map_err(|e| FetchError::Parse {
+            url: url.clone(),
+            message: format!("invalid stored ics settings: {e}"),
+        })?,
+        None => IcsSourceConfig::default(),
+    };
+    let lookahead = humantime::parse_duration(&settings.lookahead)
+        .ok()
+        .and_then(|d| Duration::from_std(d).ok())
+        .unwrap_or(Duration::days(7));
+    let tz: Tz = settings
+        .timezone
+        .as_deref()
+        .unwrap_or(&config.pail.timezone)
+        .parse()
+        .unwrap_or(chrono_tz::UTC);
+
+    // No conditional GET: events come into the lookahead while the file stays the same
+    let mut unconditional = source.clone();
+    unconditional.last_etag = None;
+    unconditional.last_modified_header = None;
+    let client = fetch::build_client(&unconditional, &url)?;
+
+    debug!(url = %url, source = %source.name, "fetching calendar");
+    let response = client.get(&url).send().await.map_err(|e| FetchError::Http {
+        url: url.clone(),
+        source: e,
+    })?;
+    if response.status() == reqwest::StatusCode::GONE {
+        return Err(FetchError::Gone { url }.into());
+    }
+    if !response.status().is_success() {
+        return Err(FetchError::Http {
+            url: url.clone(),
+            source: response.error_for_status().unwrap_err(),
+        }
+        .into());
+    }
+    let text = response.text().await.map_err(|e| FetchError::Http {
+        url: url.clone(),
+        source: e,
+    })?;
+    if !text.trim_start().starts_with("BEGIN:VCALENDAR") {
+        return Err(FetchError::Parse {
+            url,
+            message: "not an iCalendar file (no BEGIN:VCALENDAR)".to_string(),
+        }
+        .into());
+    }
+
+    let now = Utc::now();
+    let events = parse_calendar(&text, tz);
+    let mut occurrences = upcoming(&events, now, now + lookahead);
+    occurrences.sort_by_key(|(_, start)| start.utc());
+    occurrences.truncate(source.max_items as usize);
+
+    let items: Vec<ContentItem> = occurrences
+        .into_iter()
+        .map(|(event, start)| to_content_item(source, event, start, tz, now))
+        .collect();
+    debug!(source = %source.name, events = events.len(), upcoming = items.len(), "parsed calendar");
+
+    Ok(FetchResult {
+        items,
+        etag: None,
+        last_modified: None,
+        moved_to: None,
+    })
+}
+
+/// Start or end of an event: a date (all-day) or a wall-clock time in a zone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EventTime {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime, Tz),
+}
+
+impl EventTime {
+    fn naive(self) -> NaiveDateTime {
+        match self {
+            EventTime::Date(d) => d.and_time(NaiveTime::MIN),
+            EventTime::DateTime(dt, _) => dt,
+        }
+    }
+
+    fn with_naive(self, naive: NaiveDateTime) -> Self {
+        match self {
+            EventTime::Date(_) => EventTime::Date(naive.date()),
+            EventTime::DateTime(_, tz) => EventTime::DateTime(naive, tz),
+        }
+    }
+
+    /// The instant; all-day dates start at midnight UTC (compared against "today" separately).
+    fn utc(self) -> DateTime<Utc> {
+        match self {
+            EventTime::Date(d) => d.and_time(NaiveTime::MIN).and_utc(),
+            EventTime::DateTime(dt, tz) => to_utc(dt, tz),
+        }
+    }
+}
+
+/// One VEVENT.
+#[derive(Debug, Default)]
+struct Event {
+    uid: Option<String>,
+    summary: Option<String>,
+    description: Option<String>,
+    location: Option<String>,
+    url: Option<String>,
+    organizer: Option<String>,
+    status: Option<String>,
+    start: Option<EventTime>,
+    end: Option<EventTime>,
+    duration: Option<Duration>,
+    rrule: Option<String>,
+    exdates: Vec<EventTime>,
+    /// Set on an edited occurrence of a recurring event; replaces that occurrence.
+    recurrence_id: Option<EventTime>,
+}
+
+impl Event {
+    /// Length of the event, from DTEND or DURATION.
+    fn length(&self) -> Option<Duration> {
+        match (self.start, self.end) {
+            (Some(start), Some(end)) => Some(end.naive() - start.naive()),
+            _ => self.duration,
+        }
+    }
+}
+
+/// A content line: `NAME;PARAM=value:value`.
+struct Property<'a> {
+    name: String,
+    params: Vec<(String, String)>,
+    value: &'a str,
+}
+
+impl Property<'_> {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Parse the VEVENTs of a calendar. Floating times and unknown TZIDs use `default_tz`.
+fn parse_calendar(text: &str, default_tz: Tz) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut current: Option<Event> = None;
+    // Components nested in a VEVENT (VALARM) have properties of their own
+    let mut nested = 0usize;
+
+    for line in unfold(text) {
+        let Some(prop) = parse_line(&line) else {
+            continue;
+        };
+        match (prop.name.as_str(), prop.value.to_ascii_uppercase().as_str()) {
+            ("BEGIN", "VEVENT") => {
+                current = Some(Event::default());
+                nested = 0;
+                continue;
+            }
+            ("END", "VEVENT") => {
+                events.extend(current.take());
+                continue;
+            }
+            ("BEGIN", _) if current.is_some() => {
+                nested += 1;
+                continue;
+            }
+            ("END", _) if current.is_some() => {
+                nested = nested.saturating_sub(1);
+                continue;
+            }
+            _ => {}
+        }
+        let Some(event) = current.as_mut().filter(|_| nested == 0) else {
+            continue;
+        };
+
+        match prop.name.as_str() {
+            "UID" => event.uid = Some(prop.value.trim().to_string()),
+            "SUMMARY" => event.summary = Some(unescape(prop.value)),
+            "DESCRIPTION" => event.description = Some(unescape(prop.value)),
+            "LOCATION" => event.location = Some(unescape(prop.value)),
+            "URL" => event.url = Some(prop.value.trim().to_string()),
+            "STATUS" => event.status = Some(prop.value.trim().to_ascii_uppercase()),
+            "ORGANIZER" => {
+                event.organizer = prop
+                    .param("CN")
+                    .map(str::to_string)
+                    .or_else(|| prop.value.strip_prefix("mailto:").map(str::to_string));
+            }
+            "DTSTART" => event.start = parse_time(&prop, prop.value, default_tz),
+            "DTEND" => event.end = parse_time(&prop, prop.value, default_tz),
+            "DURATION" => event.duration = parse_duration(prop.value),
+            "RRULE" => event.rrule = Some(prop.value.trim().to_string()),
+            "RECURRENCE-ID" => event.recurrence_id = parse_time(&prop, prop.value, default_tz),
+            "EXDATE" => event
+                .exdates
+                .extend(prop.value.split(',').filter_map(|v| parse_time(&prop, v, default_tz))),
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Occurrences (event, start) starting within `[from, to]`. All-day events count from the
+/// start of `from`'s day in the event's own terms, so today's all-day events are included.
+fn upcoming(events: &[Event], from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<(&Event, EventTime)> {
+    // Edited occurrences replace the generated ones with the same UID + RECURRENCE-ID
+    let overridden: HashSet<(&str, DateTime<Utc>)> = events
+        .iter()
+        .filter_map(|e| Some((e.uid.as_deref()?, e.recurrence_id?.utc())))
+        .collect();
+
+    let in_window = |start: EventTime| match start {
+        EventTime::Date(d) => d >= from.date_naive() && d.and_time(NaiveTime::MIN).and_utc() <= to,
+        EventTime::DateTime(..) => (from..=to).contains(&start.utc()),
+    };
+
+    let mut out = Vec::new();
+    for event in events {
+        let Some(start) = event.start else {
+            continue;
+        };
+        let rule = match (&event.rrule, event.recurrence_id) {
+            (Some(rrule), None) => match parse_rrule(rrule, start) {
+                Some(rule) => Some(rule),
+                None => {
+                    debug!(uid = ?event.uid, rrule = %rrule, "unsupported RRULE, using the first occurrence only");
+                    None
+                }
+            },
+            _ => None,
+        };
+        let starts = match rule {
+            Some(rule) => expand(start, &rule, to),
+            None => vec![start],
+        };
+        for occurrence in starts {
+            if !in_window(occurrence) {
+                continue;
+            }
+            if event.exdates.iter().any(|ex| ex.utc() == occurrence.utc()) {
+                continue;
+            }
+            if event.recurrence_id.is_none()
+                && event.rrule.is_some()
+                && let Some(ref uid) = event.uid
+                && overridden.contains(&(uid.as_str(), occurrence.utc()))
+            {
+                continue;
+            }
+            out.push((event, occurrence));
+        }
+    }
+    out
+}
+
+fn to_content_item(source: &Source, event: &Event, start: EventTime, tz: Tz, now: DateTime<Utc>) -> ContentItem {
+    let end = event.length().map(|len| start.with_naive(start.naive() + len));
+    let cancelled = event.status.as_deref() == Some("CANCELLED");
+    let summary = event.summary.clone().unwrap_or_else(|| "(untitled event)".to_string());
+    let title = if cancelled {
+        format!("[Cancelled] {summary}")
+    } else {
+        summary
+    };
+
+    let key = match event.uid {
+        Some(ref uid) => uid.clone(),
+        None => {
+            let mut hasher = Sha256::new();
+            hasher.update(&title);
+            hasher.update("|");
+            hasher.update(event.location.as_deref().unwrap_or(""));
+            format!("sha256:{:x}", hasher.finalize())
+        }
+    };
+    let start_key = match start {
+        EventTime::Date(d) => d.to_string(),
+        EventTime::DateTime(..) => start.utc().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+    };
+
+    let body = event
+        .description
+        .as_deref()
+        .map(|d| fetch::strip_html(d).trim().to_string())
+        .unwrap_or_default();
+
+    let iso = |t: EventTime| match t {
+        EventTime::Date(d) => d.to_string(),
+        EventTime::DateTime(..) => t.utc().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+    };
+    let mut metadata = json!({
+        "event_start": iso(start),
+        "event_time": format_event_time(start, end, tz),
+        "all_day": matches!(start, EventTime::Date(_)),
+    });
+    if let Some(end) = end {
+        metadata["event_end"] = json!(iso(end));
+    }
+    if let Some(ref location) = event.location {
+        metadata["location"] = json!(location);
+    }
+    if cancelled {
+        metadata["status"] = json!("cancelled");
+    }
+
+    ContentItem {
+        id: Uuid::new_v4().to_string(),
+        source_id: source.id.clone(),
+        ingested_at: now,
+        original_date: now,
+        content_type: "event".to_string(),
+        title: Some(title),
+        body,
+        url: event.url.clone(),
+        author: event.organizer.clone(),
+        metadata: metadata.to_string(),
+        dedup_key: format!("ics:{key}:{start_key}"),
+        upstream_changed: false,
+    }
+}
+
+/// Human-readable event time in the display zone, e.g. "Tue 2026-10-20 18:30–21:00 (Europe/Kyiv)".
+fn format_event_time(start: EventTime, end: Option<EventTime>, tz: Tz) -> String {
+    match (start, end) {
+        (EventTime::Date(d), end) => {
+            // DTEND of an all-day event is exclusive
+            let last = match end {
+                Some(EventTime::Date(e)) if e > d + Duration::days(1) => Some(e - Duration::days(1)),
+                _ => None,
+            };
+            match last {
+                Some(last) => format!("{} – {} (all day)", d.format("%a %Y-%m-%d"), last.format("%a %Y-%m-%d")),
+                None => format!("{} (all day)", d.format("%a %Y-%m-%d")),
+            }
+        }
+        (EventTime::DateTime(..), end) => {
+            let start = start.utc().with_timezone(&tz);
+            let mut s = start.format("%a %Y-%m-%d %H:%M").to_string();
+            if let Some(end) = end {
+                let end = end.utc().with_timezone(&tz);
+                if end.date_naive() == start.date_naive() {
+                    s.push_str(&format!("–{}", end.format("%H:%M")));
+                } else {
+                    s.push_str(&format!(" – {}", end.format("%a %Y-%m-%d %H:%M")));
+                }
+            }
+            format!("{s} ({tz})")
+        }
+    }
+}
+
+/// Join folded lines (a line starting with a space or tab continues the previous one).
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in text.split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        match raw.strip_prefix([' ', '\t']) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().push_str(rest),
+            _ if !raw.is_empty() => lines.push(raw.to_string()),
+            _ => {}
+        }
+    }
+    lines
+}
+
+/// Split a content line into name, parameters, and value. Parameter values may be quoted
+/// and contain `:` or `;`.
+fn parse_line(line: &str) -> Option<Property<'_>> {
+    let mut in_quotes = false;
+    let mut colon = None;
+    let mut separators = Vec::new();
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => separators.push(i),
+            ':' if !in_quotes => {
+                colon = Some(i);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let colon = colon?;
+    let mut bounds = separators;
+    bounds.push(colon);
+    let params = bounds
+        .windows(2)
+        .filter_map(|w| {
+            let (key, value) = line[w[0] + 1..w[1]].split_once('=')?;
+            Some((key.to_ascii_uppercase(), value.trim_matches('"').to_string()))
+        })
+        .collect();
+    Some(Property {
+        name: line[..bounds[0]].to_ascii_uppercase(),
+        params,
+        value: &line[colon + 1..],
+    })
+}
+
+/// Undo TEXT escaping (`\n`, `\,`, `\;`, `\\`).
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out.trim().to_string()
+}
+
+/// Parse a DATE or DATE-TIME value: `20261020`, `20261020T183000Z` (UTC),
+/// `20261020T183000` with `TZID` (or floating, in `default_tz`).
+fn parse_time(prop: &Property, value: &str, default_tz: Tz) -> Option<EventTime> {
+    let value = value.trim();
+    if value.len() == 8 || prop.param("VALUE").is_some_and(|v| v.eq_ignore_ascii_case("DATE")) {
+        return NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d")
+            .ok()
+            .map(EventTime::Date);
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let dt = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(EventTime::DateTime(dt, chrono_tz::UTC));
+    }
+    let dt = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let tz = prop.param("TZID").and_then(parse_tzid).unwrap_or(default_tz);
+    Some(EventTime::DateTime(dt, tz))
+}
+
+/// IANA zone of a TZID. Some producers prefix it (`/mozilla.org/20050126_1/Europe/Berlin`);
+/// the trailing `Area/City` is tried too.
+fn parse_tzid(tzid: &str) -> Option<Tz> {
+    if let Ok(tz) = tzid.parse() {
+        return Some(tz);
+    }
+    let parts: Vec<&str> = tzid.trim_matches('/').split('/').collect();
+    (2..=3)
+        .filter(|&n| parts.len() >= n)
+        .find_map(|n| parts[parts.len() - n..].join("/").parse().ok())
+}
+
+/// Parse an ISO 8601 DURATION such as `PT1H30M`, `P1D`, or `P2W`.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let mut rest = value.strip_prefix('P')?;
+    let mut total = Duration::zero();
+    let mut in_time = false;
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix('T') {
+            in_time = true;
+            rest = r;
+            continue;
+        }
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let n: i64 = rest[..digits].parse().ok()?;
+        let unit = rest[digits..].chars().next()?;
+        total += match (unit, in_time) {
+            ('W', false) => Duration::weeks(n),
+            ('D', false) => Duration::days(n),
+            ('H', true) => Duration::hours(n),
+            ('M', true) => Duration::minutes(n),
+            ('S', true) => Duration::seconds(n),
+            _ => return None,
+        };
+        rest = &rest[digits + 1..];
+    }
+    Some(if negative { -total } else { total })
+}
+
+fn to_utc(naive: NaiveDateTime, tz: Tz) -> DateTime<Utc> {
+    match tz.from_local_datetime(&naive).earliest() {
+        Some(dt) => dt.with_timezone(&Utc),
+        // Inside a DST gap: the wall-clock time doesn't exist, use the hour after
+        None => tz
+            .from_local_datetime(&(naive + Duration::hours(1)))
+            .earliest()
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|| naive.and_utc()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Freq {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The supported subset of RRULE: FREQ, INTERVAL, COUNT, UNTIL, WKST, plus BYDAY (weekly:
+/// weekdays; monthly: optionally with an ordinal, e.g. `2TU`, `-1FR`) and BYMONTHDAY (monthly).
+#[derive(Debug)]
+struct RRule {
+    freq: Freq,
+    interval: u32,
+    count: Option<u32>,
+    until: Option<DateTime<Utc>>,
+    by_day: Vec<(i32, Weekday)>,
+    by_month_day: Vec<i32>,
+}
+
+/// Parse an RRULE; `None` if it uses parts outside the supported subset.
+fn parse_rrule(value: &str, start: EventTime) -> Option<RRule> {
+    let mut rule = RRule {
+        freq: Freq::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        by_day: Vec::new(),
+        by_month_day: Vec::new(),
+    };
+    let mut freq = None;
+    for part in value.split(';').filter(|p| !p.is_empty()) {
+        let (key, val) = part.split_once('=')?;
+        match key.to_ascii_uppercase().as_str() {
+            "FREQ" => {
+                freq = Some(match val.to_ascii_uppercase().as_str() {
+                    "DAILY" => Freq::Daily,
+                    "WEEKLY" => Freq::Weekly,
+                    "MONTHLY" => Freq::Monthly,
+                    "YEARLY" => Freq::Yearly,
+                    _ => return None,
+                })
+            }
+            "INTERVAL" => rule.interval = val.parse().ok().filter(|&n| n > 0)?,
+            "COUNT" => rule.count = Some(val.parse().ok()?),
+            "UNTIL" => {
+                let tz = match start {
+                    EventTime::DateTime(_, tz) => tz,
+                    EventTime::Date(_) => chrono_tz::UTC,
+                };
+                let until = match val.len() {
+                    8 => EventTime::Date(NaiveDate::parse_from_str(val, "%Y%m%d").ok()?),
+                    _ => match val.strip_suffix('Z') {
+                        Some(v) => {
+                            EventTime::DateTime(NaiveDateTime::parse_from_str(v, "%Y%m%dT%H%M%S").ok()?, chrono_tz::UTC)
+                        }
+                        None => EventTime::DateTime(NaiveDateTime::parse_from_str(val, "%Y%m%dT%H%M%S").ok()?, tz),
+                    },
+                };
+                // A date UNTIL includes that whole day, up to its last second
+                rule.until = Some(match until {
+                    EventTime::Date(d) => {
+                        to_utc(d.and_time(NaiveTime::MIN) + Duration::days(1), tz) - Duration::seconds(1)
+                    }
+                    t => t.utc(),
+                });
+            }
+            "WKST" => {}
+            "BYDAY" => {
+                for day in val.split(',') {
+                    let day = day.trim();
+                    let split = day.len().checked_sub(2)?;
+                    // `get`: a multi-byte character would make the split point panic
+                    let weekday = parse_weekday(day.get(split..)?)?;
+                    let ordinal = match day.get(..split)? {
+                        "" => 0,
+                        n => n.parse().ok()?,
+                    };
+                    rule.by_day.push((ordinal, weekday));
+                }
+            }
+            "BYMONTHDAY" => {
+                for day in val.split(',') {
+                    rule.by_month_day.push(day.trim().parse().ok()?);
+                }
+            }
+            _ => return None,
+        }
+    }
+    rule.freq = freq?;
+    let ordinals = rule.by_day.iter().any(|(n, _)| *n != 0);
+    let supported = match rule.freq {
+        Freq::Daily | Freq::Yearly => rule.by_day.is_empty() && rule.by_month_day.is_empty(),
+        Freq::Weekly => !ordinals && rule.by_month_day.is_empty(),
+        Freq::Monthly => rule.by_day.is_empty() || rule.by_month_day.is_empty(),
+    };
+    supported.then_some(rule)
+}
+
+fn parse_weekday(code: &str) -> Option<Weekday> {
+    Some(match code.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+/// Occurrence starts of a recurring event, from DTSTART up to `limit`.
+fn expand(start: EventTime, rule: &RRule, limit: DateTime<Utc>) -> Vec<EventTime> {
+    let first = start.naive();
+    let time = first.time();
+    // A day of slack for the event's offset from UTC
+    let last_date = (limit + Duration::days(1)).date_naive();
+    let mut out = Vec::new();
+    let mut emitted = 0u32;
+
+    for period in 0..MAX_RECURRENCE_PERIODS {
+        let step = period as i64 * rule.interval as i64;
+        let period_start = match rule.freq {
+            Freq::Daily => Some(first.date() + Duration::days(step)),
+            Freq::Weekly => Some(
+                first.date() - Duration::days(first.weekday().num_days_from_monday() as i64) + Duration::weeks(step),
+            ),
+            Freq::Monthly => add_months(first.date(), step),
+            Freq::Yearly => i32::try_from(first.year() as i64 + step)
+                .ok()
+                .and_then(|year| NaiveDate::from_ymd_opt(year, 1, 1)),
+        };
+        let Some(period_start) = period_start.filter(|d| *d <= last_date) else {
+            break;
+        };
+
+        let mut candidates: Vec<NaiveDate> = match rule.freq {
+            Freq::Daily => vec![period_start],
+            Freq::Weekly => {
+                let week = period_start;
+                if rule.by_day.is_empty() {
+                    vec![week + Duration::days(first.weekday().num_days_from_monday() as i64)]
+                } else {
+                    rule.by_day
+                        .iter()
+                        .map(|(_, wd)| week + Duration::days(wd.num_days_from_monday() as i64))
+                        .collect()
+                }
+            }
+            Freq::Monthly => monthly_days(period_start.year(), period_start.month(), first.day(), rule),
+            Freq::Yearly => NaiveDate::from_ymd_opt(period_start.year(), first.month(), first.day())
+                .into_iter()
+                .collect(),
+        };
+        candidates.sort();
+        candidates.dedup();
+
+        let mut past_limit = false;
+        for date in candidates {
+            let occurrence = start.with_naive(date.and_time(time));
+            if occurrence.naive() < first {
+                continue;
+            }
+            let at = occurrence.utc();
+            if at > limit || rule.until.is_some_and(|until| at > until) {
+                past_limit = true;
+                break;
+            }
+            out.push(occurrence);
+            emitted += 1;
+            if rule.count.is_some_and(|count| emitted >= count) {
+                return out;
+            }
+        }
+        if past_limit {
+            break;
+        }
+    }
+    out
+}
+
+/// First day of the month `months` after `date`'s month.
+fn add_months(date: NaiveDate, months: i64) -> Option<NaiveDate> {
+    let index = date.year() as i64 * 12 + date.month0() as i64 + months;
+    NaiveDate::from_ymd_opt(
+        i32::try_from(index.div_euclid(12)).ok()?,
+        index.rem_euclid(12) as u32 + 1,
+        1,
+    )
+}
+
+/// Days of one month matched by a MONTHLY rule (default: DTSTART's day, skipped in months
+/// that don't have it).
+fn monthly_days(year: i32, month: u32, start_day: u32, rule: &RRule) -> Vec<NaiveDate> {
+    let days_in_month: Vec<NaiveDate> = (1..=31)
+        .filter_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+        .collect();
+    let len = days_in_month.len() as i32;
+
+    if !rule.by_day.is_empty() {
+        let mut out = Vec::new();
+        for &(ordinal, weekday) in &rule.by_day {
+            let matching: Vec<NaiveDate> = days_in_month
+                .iter()
+                .copied()
+                .filter(|d| d.weekday() == weekday)
+                .collect();
+            let count = matching.len() as i32;
+            match ordinal {
+                0 => out.extend(matching),
+                n if n > 0 && n <= count => out.push(matching[(n - 1) as usize]),
+                n if n < 0 && -n <= count => out.push(matching[(count + n) as usize]),
+                _ => {}
+            }
+        }
+        return out;
+    }
+
+    let days: Vec<i32> = if rule.by_month_day.is_empty() {
+        vec![start_day as i32]
+    } else {
+        rule.by_month_day.clone()
+    };
+    days.into_iter()
+        .filter_map(|d| match d {
+            d if d > 0 && d <= len => Some(days_in_month[(d - 1) as usize]),
+            d if d < 0 && -d <= len => Some(days_in_month[(len + d) as usize]),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        at(value).and_utc()
+    }
+
+    /// Occurrence starts (wall-clock) of `rrule` from `start`, up to the end of 2026.
+    fn occurrences(rrule: &str, start: EventTime) -> Vec<NaiveDateTime> {
+        let rule = parse_rrule(rrule, start).expect("supported rule");
+        expand(start, &rule, utc("2026-12-31 23:59"))
+            .into_iter()
+            .map(EventTime::naive)
+            .collect()
+    }
+
+    #[test]
+    fn test_rrule_interval_and_count() {
+        let start = EventTime::DateTime(at("2026-10-01 09:00"), chrono_tz::UTC);
+        assert_eq!(
+            occurrences("FREQ=DAILY;INTERVAL=2;COUNT=3", start),
+            [at("2026-10-01 09:00"), at("2026-10-03 09:00"), at("2026-10-05 09:00")]
+        );
+    }
+
+    #[test]
+    fn test_rrule_weekly_until() {
+        // 2026-10-05 is a Monday
+        let start = EventTime::DateTime(at("2026-10-05 09:00"), chrono_tz::UTC);
+        assert_eq!(
+            occurrences("FREQ=WEEKLY;BYDAY=MO,WE;UNTIL=20261014T235959Z", start),
+            [
+                at("2026-10-05 09:00"),
+                at("2026-10-07 09:00"),
+                at("2026-10-12 09:00"),
+                at("2026-10-14 09:00"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rrule_date_until_includes_the_day() {
+        let start = EventTime::Date(NaiveDate::from_ymd_opt(2026, 10, 1).unwrap());
+        assert_eq!(occurrences("FREQ=DAILY;UNTIL=20261003", start).len(), 3);
+    }
+
+    #[test]
+    fn test_rrule_monthly_byday_ordinals() {
+        let start = EventTime::DateTime(at("2026-10-01 18:00"), chrono_tz::UTC);
+        assert_eq!(
+            occurrences("FREQ=MONTHLY;BYDAY=2TU,-1FR;COUNT=4", start),
+            [
+                at("2026-10-13 18:00"),
+                at("2026-10-30 18:00"),
+                at("2026-11-10 18:00"),
+                at("2026-11-27 18:00"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rrule_rejects_unsupported_and_malformed() {
+        let start = EventTime::DateTime(at("2026-10-01 18:00"), chrono_tz::UTC);
+        assert!(parse_rrule("FREQ=WEEKLY;BYDAY=1MO", start).is_none());
+        assert!(parse_rrule("FREQ=DAILY;BYHOUR=9", start).is_none());
+        assert!(parse_rrule("INTERVAL=2", start).is_none());
+        assert!(parse_rrule("FREQ=WEEKLY;BYDAY=€", start).is_none());
+        assert!(parse_rrule("FREQ=MONTHLY;BYDAY=x€MO", start).is_none());
+    }
+
+    #[test]
+    fn test_exdate_and_tzid() {
+        let calendar = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:standup\r\n\
+            DTSTART;TZID=Europe/Kyiv:20261005T100000\r\n\
+            RRULE:FREQ=DAILY;COUNT=3\r\n\
+            EXDATE;TZID=Europe/Kyiv:20261006T100000\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let events = parse_calendar(calendar, chrono_tz::UTC);
+        let starts: Vec<DateTime<Utc>> = upcoming(&events, utc("2026-10-01 00:00"), utc("2026-10-31 00:00"))
+            .into_iter()
+            .map(|(_, start)| start.utc())
+            .collect();
+        // Kyiv is UTC+3 until the end of October
+        assert_eq!(starts, [utc("2026-10-05 07:00"), utc("2026-10-07 07:00")]);
+    }
+
+    #[test]
+    fn test_parse_tzid_with_prefix() {
+        assert_eq!(parse_tzid("Europe/Berlin"), Some(chrono_tz::Europe::Berlin));
+        assert_eq!(
+            parse_tzid("/mozilla.org/20050126_1/Europe/Berlin"),
+            Some(chrono_tz::Europe::Berlin)
+        );
+        assert_eq!(parse_tzid("Not/AZone"), None);
+    }
+}
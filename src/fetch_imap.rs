@@ -0,0 +1,255 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures_util::TryStreamExt;
+use mail_parser::MessageParser;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::config::ImapSourceConfig;
+use crate::error::FetchError;
+use crate::fetch::{self, FetchResult};
+use crate::models::{ContentItem, Source};
+use crate::store;
+
+/// Upper bound for one whole mailbox session (connect, search, fetch, flag, logout).
+const SESSION_TIMEOUT_SECS: u64 = 120;
+
+type ImapSession = async_imap::Session<tokio_rustls::client::TlsStream<TcpStream>>;
+
+/// Fetch unread messages matching the source's sender/subject filters and convert them to
+/// content items. A message is marked read (`\Seen`) only once its item is stored, on the
+/// poll after the one that returned it; non-matching messages are left untouched.
+pub async fn fetch_imap_source(pool: &SqlitePool, source: &Source) -> Result<FetchResult> {
+    let imap: ImapSourceConfig = source
+        .imap_config
+        .as_deref()
+        .ok_or_else(|| FetchError::Parse {
+            url: source.name.clone(),
+            message: "imap source has no mailbox settings".to_string(),
+        })
+        .and_then(|json| {
+            serde_json::from_str(json).map_err(|e| FetchError::Parse {
+                url: source.name.clone(),
+                message: format!("invalid stored imap settings: {e}"),
+            })
+        })?;
+
+    let items = tokio::time::timeout(
+        Duration::from_secs(SESSION_TIMEOUT_SECS),
+        fetch_messages(pool, source, &imap),
+    )
+    .await
+    .map_err(|_| {
+        anyhow::anyhow!(
+            "IMAP session with {} timed out after {SESSION_TIMEOUT_SECS}s",
+            imap.host
+        )
+    })??;
+
+    Ok(FetchResult {
+        items,
+        etag: None,
+        last_modified: None,
+        moved_to: None,
+    })
+}
+
+async fn fetch_messages(pool: &SqlitePool, source: &Source, imap: &ImapSourceConfig) -> Result<Vec<ContentItem>> {
+    let mut session = connect(imap).await?;
+
+    session
+        .select(&imap.folder)
+        .await
+        .with_context(|| format!("selecting folder '{}'", imap.folder))?;
+
+    let query = search_query(imap);
+    debug!(source = %source.name, query = %query, "searching mailbox");
+    let mut uids: Vec<u32> = session
+        .uid_search(&query)
+        .await
+        .context("searching mailbox")?
+        .into_iter()
+        .collect();
+
+    if uids.is_empty() {
+        debug!(source = %source.name, "no new matching messages");
+        session.logout().await.ok();
+        return Ok(Vec::new());
+    }
+
+    // UIDs grow with arrival order: keep the newest `max_items`, the rest stay unread for the next poll
+    uids.sort_unstable_by(|a, b| b.cmp(a));
+    uids.truncate(source.max_items as usize);
+    let uid_set = uids.iter().map(u32::to_string).collect::<Vec<_>>().join(",");
+
+    // BODY.PEEK doesn't set \Seen; messages are only flagged once their items are stored
+    let fetches: Vec<_> = session
+        .uid_fetch(&uid_set, "(UID BODY.PEEK[])")
+        .await
+        .context("fetching messages")?
+        .try_collect()
+        .await
+        .context("reading fetched messages")?;
+
+    let now = Utc::now();
+    let mut items = Vec::with_capacity(fetches.len());
+    let mut seen = Vec::with_capacity(fetches.len());
+    for fetch in &fetches {
+        let (Some(uid), Some(raw)) = (fetch.uid, fetch.body()) else {
+            continue;
+        };
+        match build_item(source, imap, raw, now) {
+            // Stored by an earlier poll: safe to mark read now
+            Some(item) if store::content_item_exists(pool, &source.id, &item.dedup_key).await? => {
+                seen.push(uid.to_string());
+            }
+            // Returned for storing; marked read on the next poll, once it's in the database
+            Some(item) => items.push(item),
+            None => {
+                warn!(source = %source.name, uid, "skipping unparseable or empty message");
+                seen.push(uid.to_string());
+            }
+        }
+    }
+
+    if !seen.is_empty() {
+        let _: Vec<_> = session
+            .uid_store(seen.join(","), "+FLAGS.SILENT (\\Seen)")
+            .await
+            .context("marking messages read")?
+            .try_collect()
+            .await
+            .context("marking messages read")?;
+    }
+    session.logout().await.ok();
+
+    info!(source = %source.name, folder = %imap.folder, messages = items.len(), "fetched newsletter messages");
+    Ok(items)
+}
+
+/// Open an implicit-TLS connection and log in.
+async fn connect(imap: &ImapSourceConfig) -> Result<ImapSession> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let tls_config =
+        ClientConfig::builder_with_provider(Arc::new(tokio_rustls::rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .context("configuring TLS")?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+    let server_name =
+        ServerName::try_from(imap.host.clone()).with_context(|| format!("invalid host '{}'", imap.host))?;
+
+    let tcp = TcpStream::connect((imap.host.as_str(), imap.port))
+        .await
+        .with_context(|| format!("connecting to {}:{}", imap.host, imap.port))?;
+    let tls = TlsConnector::from(Arc::new(tls_config))
+        .connect(server_name, tcp)
+        .await
+        .with_context(|| format!("TLS handshake with {}", imap.host))?;
+
+    let mut client = async_imap::Client::new(tls);
+    let _greeting = client.read_response().await.context("reading IMAP greeting")??;
+
+    client
+        .login(&imap.username, &imap.password)
+        .await
+        .map_err(|(e, _)| anyhow::Error::from(e).context(format!("logging in as {}", imap.username)))
+}
+
+/// `UNSEEN` plus OR-chains for the sender and subject filters. IMAP `FROM` / `SUBJECT`
+/// are case-insensitive substring matches on the header, evaluated by the server.
+fn search_query(imap: &ImapSourceConfig) -> String {
+    let mut query = String::new();
+    if imap.from.iter().chain(&imap.subject).any(|f| !f.is_ascii()) {
+        query.push_str("CHARSET UTF-8 ");
+    }
+    query.push_str("UNSEEN");
+    for (key, values) in [("FROM", &imap.from), ("SUBJECT", &imap.subject)] {
+        if let Some(clause) = or_chain(key, values) {
+            query.push(' ');
+            query.push_str(&clause);
+        }
+    }
+    query
+}
+
+/// IMAP `OR` is binary prefix: `OR OR FROM "a" FROM "b" FROM "c"`.
+fn or_chain(key: &str, values: &[String]) -> Option<String> {
+    let terms: Vec<String> = values.iter().map(|v| format!("{key} {}", quote(v))).collect();
+    let (first, rest) = terms.split_first()?;
+    Some(rest.iter().fold(first.clone(), |acc, term| format!("OR {acc} {term}")))
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Convert a raw RFC 822 message into a content item. The HTML part is preferred (newsletters
+/// are HTML-first and their text parts are often a stub) and converted to plain text.
+fn build_item(source: &Source, imap: &ImapSourceConfig, raw: &[u8], now: DateTime<Utc>) -> Option<ContentItem> {
+    let message = MessageParser::default().parse(raw)?;
+
+    let title = message
+        .subject()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    let sender = message.from().and_then(|from| from.first());
+    let author = sender.and_then(|a| a.name().or(a.address())).map(str::to_string);
+    let address = sender.and_then(|a| a.address()).map(str::to_string);
+
+    let body = message
+        .body_html(0)
+        .map(|html| fetch::strip_html(&html))
+        .or_else(|| message.body_text(0).map(|text| text.into_owned()))
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    if body.is_empty() && title.is_none() {
+        return None;
+    }
+
+    let original_date = message
+        .date()
+        .and_then(|d| DateTime::from_timestamp(d.to_timestamp(), 0))
+        .unwrap_or(now);
+
+    // Message-ID is globally unique; fall back to a hash for the rare message without one
+    let dedup_key = match message.message_id() {
+        Some(id) => format!("<{id}>"),
+        None => {
+            let mut hasher = Sha256::new();
+            hasher.update(address.as_deref().unwrap_or(""));
+            hasher.update("|");
+            hasher.update(title.as_deref().unwrap_or(""));
+            hasher.update("|");
+            hasher.update(original_date.to_rfc3339());
+            format!("sha256:{:x}", hasher.finalize())
+        }
+    };
+
+    Some(ContentItem {
+        id: Uuid::new_v4().to_string(),
+        source_id: source.id.clone(),
+        ingested_at: now,
+        original_date,
+        content_type: "email".to_string(),
+        title,
+        body,
+        url: None,
+        author,
+        metadata: json!({ "from": address, "folder": imap.folder }).to_string(),
+        dedup_key,
+        upstream_changed: false,
+    })
+}
@@ -0,0 +1,359 @@
+//! Microformats sources: posts of IndieWeb sites without a feed, read from the `h-entry`
+//! markup of a page per microformats2 (see docs/specs/microformats-sources.md).
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use reqwest::Url;
+use scraper::{ElementRef, Html};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::error::FetchError;
+use crate::fetch::{self, FetchResult};
+use crate::fetch_scrape;
+use crate::models::{ContentItem, Source};
+
+/// Property class prefixes of microformats2: plain text, URL, date-time, embedded markup.
+const PROPERTY_PREFIXES: &[&str] = &["p-", "u-", "dt-", "e-"];
+
+/// Date-time forms microformats2 allows besides RFC 3339: a space instead of `T`, and
+/// offsets without a colon.
+const DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S%z",
+    "%Y-%m-%dT%H:%M:%S%z",
+    "%Y-%m-%d %H:%M%z",
+    "%Y-%m-%dT%H:%M%z",
+];
+
+/// Naive date-times (no offset), taken as UTC.
+const NAIVE_DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M",
+];
+
+/// Fetch the source's page and turn each `h-entry` into an item. The page is requested like a
+/// scrape source's: through the crawler, with auth and conditional GETs.
+pub async fn fetch_microformats_source(config: &Config, source: &Source) -> Result<FetchResult> {
+    let url = source.url.as_deref().ok_or_else(|| FetchError::Parse {
+        url: source.name.clone(),
+        message: "microformats source has no URL".to_string(),
+    })?;
+
+    let response = fetch_scrape::fetch_page(config, source, url).await?;
+    let Some(ref page) = response.body else {
+        return Ok(FetchResult {
+            items: Vec::new(),
+            etag: response.etag,
+            last_modified: response.last_modified,
+            moved_to: None,
+        });
+    };
+
+    let items = extract_entries(source, &response.base_url, page);
+
+    if items.is_empty() {
+        warn!(source = %source.name, url = %url, "page has no usable h-entry posts");
+    }
+
+    Ok(FetchResult {
+        items,
+        etag: response.etag,
+        last_modified: response.last_modified,
+        moved_to: None,
+    })
+}
+
+fn extract_entries(source: &Source, base_url: &Url, page: &str) -> Vec<ContentItem> {
+    let document = Html::parse_document(page);
+    let now = Utc::now();
+
+    let mut roots = Vec::new();
+    collect_entries(document.root_element(), &mut roots);
+    roots
+        .into_iter()
+        .take(source.max_items as usize)
+        .filter_map(|root| entry_item(source, base_url, root, now))
+        .collect()
+}
+
+/// `h-entry` roots in document order. Entries nested in an entry (replies, quoted posts)
+/// belong to it and aren't items of their own.
+fn collect_entries<'a>(el: ElementRef<'a>, entries: &mut Vec<ElementRef<'a>>) {
+    for child in el.children().filter_map(ElementRef::wrap) {
+        if child.value().classes().any(|c| c == "h-entry") {
+            entries.push(child);
+        } else {
+            collect_entries(child, entries);
+        }
+    }
+}
+
+fn entry_item(source: &Source, base_url: &Url, root: ElementRef, now: DateTime<Utc>) -> Option<ContentItem> {
+    let properties = properties(root);
+    let first = |name: &str| properties.iter().find(|(n, _)| n == name).map(|(_, el)| *el);
+
+    let content = first("e-content");
+    let body = match content {
+        Some(el) => fetch::strip_html(&el.inner_html()),
+        None => first("p-summary").map(text_value).unwrap_or_default(),
+    }
+    .trim()
+    .to_string();
+
+    // A note's name is often its content (`class="p-name e-content"`); that's no title
+    let title = first("p-name")
+        .filter(|el| content.is_none_or(|c| c.id() != el.id()))
+        .map(text_value)
+        .filter(|name| !name.is_empty() && normalize(name) != normalize(&body));
+
+    if body.is_empty() && title.is_none() {
+        debug!(source = %source.name, "skipping empty h-entry");
+        return None;
+    }
+
+    let url = first("u-url")
+        .or_else(|| first("u-uid"))
+        .and_then(|el| url_value(el, base_url));
+    let original_date = first("dt-published")
+        .or_else(|| first("dt-updated"))
+        .and_then(datetime_value)
+        .unwrap_or(now);
+    let author = first("p-author").map(text_value).filter(|a| !a.is_empty());
+
+    let mut metadata = json!({});
+    let categories: Vec<String> = properties
+        .iter()
+        .filter(|(n, _)| n == "p-category")
+        .map(|(_, el)| text_value(*el))
+        .filter(|c| !c.is_empty())
+        .collect();
+    if !categories.is_empty() {
+        metadata["categories"] = json!(categories);
+    }
+    if let Some(reply_to) = first("u-in-reply-to").and_then(|el| url_value(el, base_url)) {
+        metadata["in_reply_to"] = json!(reply_to);
+    }
+
+    // The permalink identifies a post (edits are flagged like RSS updates); posts without
+    // one are identified by what they say, like scraped items
+    let dedup_key = match url {
+        Some(ref url) => url.clone(),
+        None => {
+            let mut hasher = Sha256::new();
+            hasher.update(title.as_deref().unwrap_or(""));
+            hasher.update("|");
+            hasher.update(&body);
+            format!("sha256:{:x}", hasher.finalize())
+        }
+    };
+
+    let content_type = if url.is_some() { "link" } else { "text" };
+
+    Some(ContentItem {
+        id: Uuid::new_v4().to_string(),
+        source_id: source.id.clone(),
+        ingested_at: now,
+        original_date,
+        content_type: content_type.to_string(),
+        title,
+        body,
+        url,
+        author,
+        metadata: metadata.to_string(),
+        dedup_key,
+        upstream_changed: false,
+    })
+}
+
+/// Property elements of a microformat root with their property class, in document order. A
+/// nested microformat (`p-author h-card`) is a property value, but its own properties aren't
+/// the root's.
+fn properties(root: ElementRef) -> Vec<(String, ElementRef)> {
+    let mut found = Vec::new();
+    collect_properties(root, &mut found);
+    found
+}
+
+fn collect_properties<'a>(el: ElementRef<'a>, found: &mut Vec<(String, ElementRef<'a>)>) {
+    for child in el.children().filter_map(ElementRef::wrap) {
+        for class in child.value().classes() {
+            if PROPERTY_PREFIXES
+                .iter()
+                .any(|p| class.len() > p.len() && class.starts_with(p))
+            {
+                found.push((class.to_string(), child));
+            }
+        }
+        if !is_microformat(child) {
+            collect_properties(child, found);
+        }
+    }
+}
+
+fn is_microformat(el: ElementRef) -> bool {
+    el.value().classes().any(|c| c.len() > 2 && c.starts_with("h-"))
+}
+
+/// Text of a `p-` property. A nested microformat (an `h-card` author) gives its `p-name`.
+fn text_value(el: ElementRef) -> String {
+    if is_microformat(el)
+        && let Some(name) = properties(el).into_iter().find(|(n, _)| n == "p-name")
+    {
+        return text_value(name.1);
+    }
+    let attr = match el.value().name() {
+        "abbr" | "link" => el.value().attr("title"),
+        "data" | "input" => el.value().attr("value"),
+        "img" | "area" => el.value().attr("alt"),
+        _ => None,
+    };
+    match attr {
+        Some(value) => value.trim().to_string(),
+        None => fetch_scrape::element_text(el),
+    }
+}
+
+/// Absolute URL of a `u-` property: the link or media attribute of the element, else its text.
+fn url_value(el: ElementRef, base_url: &Url) -> Option<String> {
+    let value = match el.value().name() {
+        "a" | "area" | "link" => el.value().attr("href"),
+        "img" | "audio" | "video" | "source" | "iframe" => el.value().attr("src"),
+        "object" => el.value().attr("data"),
+        _ => None,
+    };
+    let value = value
+        .map(str::to_string)
+        .unwrap_or_else(|| fetch_scrape::element_text(el));
+    base_url
+        .join(value.trim())
+        .ok()
+        .filter(|u| matches!(u.scheme(), "http" | "https"))
+        .map(|u| u.to_string())
+}
+
+/// Instant of a `dt-` property: the `datetime` attribute of `<time>`, else the element's
+/// title, value or text.
+fn datetime_value(el: ElementRef) -> Option<DateTime<Utc>> {
+    let attr = match el.value().name() {
+        "time" | "ins" | "del" => el.value().attr("datetime"),
+        "abbr" => el.value().attr("title"),
+        "data" | "input" => el.value().attr("value"),
+        _ => None,
+    };
+    let text = attr
+        .map(str::to_string)
+        .unwrap_or_else(|| fetch_scrape::element_text(el));
+    let text = text.trim();
+    fetch_scrape::parse_date(text)
+        .or_else(|| {
+            DATETIME_FORMATS
+                .iter()
+                .find_map(|fmt| DateTime::parse_from_str(text, fmt).ok())
+                .map(|dt| dt.with_timezone(&Utc))
+        })
+        .or_else(|| {
+            NAIVE_DATETIME_FORMATS
+                .iter()
+                .find_map(|fmt| NaiveDateTime::parse_from_str(text, fmt).ok())
+                .map(|dt| dt.and_utc())
+        })
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(page: &str) -> Vec<ContentItem> {
+        let source = Source {
+            id: "src".to_string(),
+            name: "blog".to_string(),
+            max_items: 50,
+            ..Default::default()
+        };
+        extract_entries(&source, &Url::parse("https://example.com/blog/").unwrap(), page)
+    }
+
+    #[test]
+    fn test_article_entry() {
+        let items = entries(
+            r#"<article class="h-entry">
+                 <h1 class="p-name">Hello world</h1>
+                 <a class="p-author h-card" href="/"><span class="p-name">Jane</span> <img class="u-photo" alt="pic" src="/me.jpg"></a>
+                 <time class="dt-published" datetime="2026-03-01 10:00:00+0200">March 1</time>
+                 <a class="u-url" href="/posts/hello">permalink</a>
+                 <a class="p-category" href="/tags/rust">rust</a>
+                 <div class="e-content"><p>First <b>post</b>.</p></div>
+               </article>"#,
+        );
+        assert_eq!(items.len(), 1);
+        let item = &items[0];
+        assert_eq!(item.title.as_deref(), Some("Hello world"));
+        assert_eq!(item.body, "First post.");
+        assert_eq!(item.author.as_deref(), Some("Jane"));
+        assert_eq!(item.url.as_deref(), Some("https://example.com/posts/hello"));
+        assert_eq!(item.dedup_key, "https://example.com/posts/hello");
+        assert_eq!(item.content_type, "link");
+        assert_eq!(item.original_date.to_rfc3339(), "2026-03-01T08:00:00+00:00");
+        let metadata: serde_json::Value = serde_json::from_str(&item.metadata).unwrap();
+        assert_eq!(metadata["categories"], json!(["rust"]));
+    }
+
+    #[test]
+    fn test_note_name_is_not_a_title() {
+        let items = entries(
+            r#"<div class="h-entry"><p class="p-name e-content">Just a note</p></div>
+               <div class="h-entry"><span class="p-name">Same text</span><p class="e-content">Same  text</p></div>"#,
+        );
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|i| i.title.is_none()));
+        assert_eq!(items[0].body, "Just a note");
+        // Without a permalink the item is identified by its text
+        assert!(items[0].dedup_key.starts_with("sha256:"));
+        assert_eq!(items[0].content_type, "text");
+    }
+
+    #[test]
+    fn test_nested_entries_belong_to_their_parent() {
+        let items = entries(
+            r#"<main>
+                 <div class="h-entry">
+                   <p class="e-content">Outer</p>
+                   <a class="u-in-reply-to" href="https://other.example/post">reply</a>
+                   <div class="h-entry"><p class="e-content">Quoted</p></div>
+                 </div>
+                 <div class="h-entry"><p class="p-summary">Second</p></div>
+                 <div class="h-entry"><span class="p-category">empty</span></div>
+               </main>"#,
+        );
+        let bodies: Vec<&str> = items.iter().map(|i| i.body.as_str()).collect();
+        assert_eq!(bodies.len(), 2);
+        assert!(bodies[0].starts_with("Outer"), "{bodies:?}");
+        assert_eq!(bodies[1], "Second");
+        let metadata: serde_json::Value = serde_json::from_str(&items[0].metadata).unwrap();
+        assert_eq!(metadata["in_reply_to"], "https://other.example/post");
+    }
+
+    #[test]
+    fn test_datetime_forms() {
+        let parse = |html: &str| {
+            let document = Html::parse_fragment(html);
+            let el = document.root_element().children().find_map(ElementRef::wrap).unwrap();
+            datetime_value(el).map(|dt| dt.to_rfc3339())
+        };
+        let utc = Some("2026-03-01T10:00:00+00:00".to_string());
+        assert_eq!(parse(r#"<time datetime="2026-03-01T10:00:00Z">x</time>"#), utc);
+        assert_eq!(parse(r#"<abbr title="2026-03-01 12:00+0200">x</abbr>"#), utc);
+        assert_eq!(parse(r#"<data value="2026-03-01T10:00">x</data>"#), utc);
+        assert_eq!(parse("<span>2026-03-01 10:00:00</span>"), utc);
+        assert_eq!(parse("<span>soon</span>"), None);
+    }
+}
@@ -0,0 +1,512 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::header::USER_AGENT;
+use serde_json::json;
+use sqlx::SqlitePool;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::config::{Config, SourceConfig};
+use crate::error::FetchError;
+use crate::fetch::{self, FetchResult};
+use crate::models::{ContentItem, Source};
+use crate::{http_client, preprocess, store};
+
+/// Audio downloads can be large; the per-request timeout is much longer than for feeds.
+const DOWNLOAD_TIMEOUT_SECS: u64 = 1800;
+
+/// Episodes waiting for the daemon's transcription worker. Episodes that don't fit are left
+/// for a later poll.
+const QUEUE_CAPACITY: usize = 8;
+
+/// A new episode selected for transcription.
+struct Episode {
+    dedup_key: String,
+    title: Option<String>,
+    link: Option<String>,
+    author: Option<String>,
+    published: DateTime<Utc>,
+    show_notes: String,
+    audio_url: String,
+    duration_secs: Option<u64>,
+}
+
+/// An episode handed to the transcription worker, with what it needs to store the item.
+struct Job {
+    source: Source,
+    /// The source's config entry, when it has a pre-processor.
+    preprocess: Option<SourceConfig>,
+    command: String,
+    timeout: Duration,
+    episode: Episode,
+}
+
+/// The daemon's transcription worker, started by the poller: new episodes are downloaded,
+/// transcribed and stored one at a time off the polling loop, so a long episode doesn't hold
+/// up other sources' polls (see docs/specs/podcast-sources.md "Transcription Queue").
+pub struct Transcriber {
+    tx: mpsc::Sender<Job>,
+    /// Dedup keys of the episodes queued or being transcribed, so later polls skip them.
+    pending: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Transcriber {
+    pub fn start(pool: SqlitePool, cancel: CancellationToken) -> Self {
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        let pending = Arc::new(Mutex::new(HashSet::new()));
+        tokio::spawn(transcription_worker(pool, rx, pending.clone(), cancel));
+        Self { tx, pending }
+    }
+
+    fn is_pending(&self, dedup_key: &str) -> bool {
+        self.pending.lock().unwrap().contains(dedup_key)
+    }
+
+    /// Queue an episode. Returns false if the queue is full.
+    fn enqueue(&self, job: Job) -> bool {
+        let key = job.episode.dedup_key.clone();
+        self.pending.lock().unwrap().insert(key.clone());
+        if self.tx.try_send(job).is_ok() {
+            return true;
+        }
+        self.pending.lock().unwrap().remove(&key);
+        false
+    }
+}
+
+/// Transcribe and store queued episodes until cancelled.
+async fn transcription_worker(
+    pool: SqlitePool,
+    mut rx: mpsc::Receiver<Job>,
+    pending: Arc<Mutex<HashSet<String>>>,
+    cancel: CancellationToken,
+) {
+    loop {
+        let job = tokio::select! {
+            _ = cancel.cancelled() => return,
+            job = rx.recv() => match job {
+                Some(job) => job,
+                None => return,
+            },
+        };
+        let key = job.episode.dedup_key.clone();
+        tokio::select! {
+            _ = cancel.cancelled() => return,
+            _ = process_job(&pool, job) => {}
+        }
+        pending.lock().unwrap().remove(&key);
+    }
+}
+
+/// Download, transcribe, pre-process and store one queued episode. A failed download stores
+/// nothing, so the next poll queues the episode again.
+async fn process_job(pool: &SqlitePool, job: Job) {
+    let Job {
+        source,
+        preprocess,
+        command,
+        timeout,
+        episode,
+    } = job;
+    let item = match transcribe_episode(&source, &command, timeout, episode).await {
+        Ok(Some(item)) => item,
+        Ok(None) => return,
+        Err(e) => {
+            warn!(source = %source.name, "podcast episode failed: {e:#}");
+            return;
+        }
+    };
+    let mut result = FetchResult {
+        items: vec![item],
+        etag: None,
+        last_modified: None,
+        moved_to: None,
+    };
+    if let Some(ref configured) = preprocess
+        && let Err(e) = preprocess::preprocess_items(pool, configured, &mut result).await
+    {
+        warn!(source = %source.name, "pre-processing a transcribed episode failed: {e:#}");
+        return;
+    }
+    let newest = result.items.iter().map(|i| i.original_date).max();
+    if let Err(e) = store::upsert_content_items(pool, &result.items).await {
+        warn!(source = %source.name, error = %e, "failed to store transcribed episode");
+        return;
+    }
+    // The poll that queued the episode had nothing to store; the item counts toward freshness now
+    if let Err(e) = store::record_fetch_success(pool, &source.id, Utc::now(), newest).await {
+        warn!(source = %source.name, error = %e, "failed to record fetch success");
+    }
+    info!(source = %source.name, items = result.items.len(), "stored transcribed episode");
+}
+
+/// One timestamped transcript segment. `offset` is the char offset of the segment's
+/// text in the item body.
+struct Segment {
+    start: f64,
+    end: f64,
+    offset: usize,
+}
+
+/// Fetch a podcast feed, download new episodes' audio, and transcribe them with
+/// `[transcription].command`. With a `transcriber` (the daemon's poller) the episodes are
+/// queued and no items are returned; otherwise they're transcribed here, and the result has
+/// one item per new episode, with the transcript as body.
+///
+/// Unlike RSS, existing episodes are checked against the DB *before* downloading, so each
+/// episode is transcribed once. Episodes older than `[pail].retention` are skipped, since
+/// cleanup would delete them anyway (and re-transcribe them on the next poll).
+pub async fn fetch_podcast_source(
+    pool: &SqlitePool,
+    config: &Config,
+    source: &Source,
+    transcriber: Option<&Transcriber>,
+) -> Result<FetchResult> {
+    let url = source.fetch_url().ok_or_else(|| FetchError::Parse {
+        url: source.name.clone(),
+        message: "podcast source has no URL".to_string(),
+    })?;
+    let command = config
+        .transcription
+        .command
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("podcast sources require [transcription].command"))?;
+    let timeout = humantime::parse_duration(&config.transcription.timeout).context("parsing transcription timeout")?;
+    let retention = humantime::parse_duration(&config.pail.retention).context("parsing retention")?;
+    let oldest = Utc::now() - chrono::Duration::from_std(retention).unwrap_or(chrono::Duration::days(7));
+
+    let response = fetch::fetch_feed(source, url).await?;
+    let Some(feed) = response.feed else {
+        return Ok(FetchResult {
+            items: Vec::new(),
+            etag: response.etag,
+            last_modified: response.last_modified,
+            moved_to: response.moved_to,
+        });
+    };
+
+    let now = Utc::now();
+    let mut episodes: Vec<Episode> = feed
+        .entries
+        .into_iter()
+        .take(source.max_items as usize)
+        .filter_map(|entry| {
+            let (audio_url, duration_secs) = audio_enclosure(&entry)?;
+            let title = entry.title.map(|t| t.content);
+            let link = entry.links.first().map(|l| l.href.clone());
+            let show_notes = entry
+                .content
+                .and_then(|c| c.body)
+                .or_else(|| entry.summary.map(|s| s.content))
+                .map(|html| fetch::strip_html(&html).trim().to_string())
+                .unwrap_or_default();
+            let dedup_key = fetch::entry_dedup_key(
+                source.dedup.as_deref(),
+                &entry.id,
+                Some(&audio_url),
+                title.as_deref(),
+                &show_notes,
+                // Enclosure URLs are keyed as written: their parameters are often signatures
+                false,
+            );
+            Some(Episode {
+                dedup_key,
+                title,
+                link,
+                author: entry.authors.first().map(|a| a.name.clone()),
+                published: entry.published.or(entry.updated).unwrap_or(now),
+                show_notes,
+                audio_url,
+                duration_secs,
+            })
+        })
+        .filter(|ep| ep.published >= oldest)
+        .collect();
+
+    // Newest first, so the per-fetch cap picks up the latest episodes
+    episodes.sort_by_key(|e| std::cmp::Reverse(e.published));
+
+    let mut new_episodes = Vec::new();
+    for episode in episodes {
+        if new_episodes.len() >= config.transcription.max_episodes_per_fetch as usize {
+            break;
+        }
+        if transcriber.is_some_and(|t| t.is_pending(&episode.dedup_key)) {
+            continue;
+        }
+        if !store::content_item_exists(pool, &source.id, &episode.dedup_key).await? {
+            new_episodes.push(episode);
+        }
+    }
+
+    if new_episodes.is_empty() {
+        debug!(source = %source.name, "no new podcast episodes");
+    }
+
+    let mut items = Vec::new();
+    if let Some(transcriber) = transcriber {
+        let preprocess = config
+            .source
+            .iter()
+            .find(|s| s.name == source.name && !s.preprocess.is_empty())
+            .cloned();
+        let mut queued = 0;
+        for episode in new_episodes {
+            let job = Job {
+                source: source.clone(),
+                preprocess: preprocess.clone(),
+                command: command.to_string(),
+                timeout,
+                episode,
+            };
+            if !transcriber.enqueue(job) {
+                debug!(source = %source.name, "transcription queue full, leaving episodes for the next poll");
+                break;
+            }
+            queued += 1;
+        }
+        if queued > 0 {
+            info!(source = %source.name, episodes = queued, "queued podcast episodes for transcription");
+        }
+    } else {
+        for episode in new_episodes {
+            if let Some(item) = transcribe_episode(source, command, timeout, episode).await? {
+                items.push(item);
+            }
+        }
+    }
+
+    Ok(FetchResult {
+        items,
+        etag: response.etag,
+        last_modified: response.last_modified,
+        moved_to: response.moved_to,
+    })
+}
+
+/// Download and transcribe one episode. `None` if the download failed: that's usually
+/// transient, so nothing is stored and the next poll retries.
+async fn transcribe_episode(
+    source: &Source,
+    command: &str,
+    timeout: Duration,
+    episode: Episode,
+) -> Result<Option<ContentItem>> {
+    let dir = tempfile::Builder::new()
+        .prefix("pail-podcast-")
+        .tempdir()
+        .context("creating temp dir")?;
+    let audio_path = dir.path().join(audio_file_name(&episode.audio_url));
+
+    if let Err(e) = download(&episode.audio_url, &audio_path, source.proxy.as_deref()).await {
+        warn!(source = %source.name, episode = ?episode.title, "episode download failed: {e:#}");
+        return Ok(None);
+    }
+
+    info!(source = %source.name, episode = ?episode.title, "transcribing podcast episode");
+    let transcript = transcribe(command, timeout, &audio_path).await;
+    Ok(Some(build_item(source, episode, transcript, Utc::now())))
+}
+
+/// First audio enclosure of an entry (RSS `<enclosure>` / Media RSS content), with its duration.
+fn audio_enclosure(entry: &feed_rs::model::Entry) -> Option<(String, Option<u64>)> {
+    entry
+        .media
+        .iter()
+        .flat_map(|m| m.content.iter().map(move |c| (c, m.duration.or(c.duration))))
+        .find(|(c, _)| {
+            c.content_type
+                .as_ref()
+                .is_some_and(|mime| mime.ty().as_ref().eq_ignore_ascii_case("audio"))
+        })
+        .and_then(|(c, duration)| Some((c.url.as_ref()?.to_string(), duration.map(|d| d.as_secs()))))
+}
+
+/// File name for the downloaded audio. Keeps the URL's extension, since ffmpeg-based
+/// tooling often picks the demuxer from it.
+fn audio_file_name(audio_url: &str) -> String {
+    let extension = reqwest::Url::parse(audio_url)
+        .ok()
+        .and_then(|u| u.path_segments().and_then(|mut s| s.next_back().map(str::to_string)))
+        .and_then(|name| name.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()))
+        .filter(|ext| ext.len() <= 5 && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+        .unwrap_or_else(|| "audio".to_string());
+    format!("episode.{extension}")
+}
+
+/// Run the transcription command on a downloaded episode and return its stdout.
+async fn transcribe(command: &str, timeout: Duration, audio_path: &std::path::Path) -> Result<String> {
+    let audio_arg = shell_quote(&audio_path.to_string_lossy());
+    let script = command.replace("{audio}", &audio_arg);
+    debug!(command = %script, "running transcription command");
+
+    let child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(&script)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("spawning transcription command")?;
+
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| anyhow::anyhow!("transcription timed out after {}", humantime::format_duration(timeout)))?
+        .context("waiting for transcription command")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let lines: Vec<&str> = stderr.lines().collect();
+        let tail = lines[lines.len().saturating_sub(5)..].join("\n");
+        anyhow::bail!("transcription command exited with {}: {tail}", output.status);
+    }
+
+    let transcript = String::from_utf8_lossy(&output.stdout).to_string();
+    if transcript.trim().is_empty() {
+        anyhow::bail!("transcription command produced no output");
+    }
+    Ok(transcript)
+}
+
+/// Stream the audio file to disk.
+async fn download(url: &str, path: &std::path::Path, proxy: Option<&str>) -> Result<()> {
+    let builder = reqwest::Client::builder().timeout(Duration::from_secs(DOWNLOAD_TIMEOUT_SECS));
+    let client = http_client::with_proxy(builder, proxy)
+        .and_then(|builder| builder.build())
+        .context("building download client")?;
+    let mut response = client
+        .get(url)
+        .header(USER_AGENT, concat!("pail/", env!("CARGO_PKG_VERSION")))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| FetchError::Http {
+            url: url.to_string(),
+            source: e,
+        })?;
+
+    let mut file = tokio::fs::File::create(path).await.context("creating audio file")?;
+    let mut bytes = 0usize;
+    while let Some(chunk) = response.chunk().await.map_err(|e| FetchError::Http {
+        url: url.to_string(),
+        source: e,
+    })? {
+        bytes += chunk.len();
+        file.write_all(&chunk).await.context("writing audio file")?;
+    }
+    file.flush().await.context("flushing audio file")?;
+    debug!(url = %url, bytes, "downloaded episode audio");
+    Ok(())
+}
+
+/// Build the content item. A failed transcription still stores the episode (show notes as
+/// body, error in metadata), so a file the command can't handle isn't retried on every poll.
+fn build_item(source: &Source, episode: Episode, transcript: Result<String>, now: DateTime<Utc>) -> ContentItem {
+    let mut metadata = json!({
+        "audio_url": episode.audio_url,
+    });
+    if let Some(secs) = episode.duration_secs {
+        metadata["duration_secs"] = json!(secs);
+    }
+
+    let body = match transcript {
+        Ok(raw) => {
+            let (body, segments) = parse_transcript(&raw);
+            if !segments.is_empty() {
+                metadata["segments"] = segments
+                    .iter()
+                    .map(|s| json!({ "start": s.start, "end": s.end, "offset": s.offset }))
+                    .collect();
+            }
+            if !episode.show_notes.is_empty() {
+                metadata["show_notes"] = json!(episode.show_notes);
+            }
+            body
+        }
+        Err(e) => {
+            warn!(source = %source.name, episode = ?episode.title, "transcription failed: {e:#}");
+            metadata["transcription_error"] = json!(format!("{e:#}"));
+            episode.show_notes
+        }
+    };
+
+    ContentItem {
+        id: Uuid::new_v4().to_string(),
+        source_id: source.id.clone(),
+        ingested_at: now,
+        original_date: episode.published,
+        content_type: "podcast".to_string(),
+        title: episode.title,
+        body,
+        url: episode.link.or(Some(episode.audio_url)),
+        author: episode.author,
+        metadata: metadata.to_string(),
+        dedup_key: episode.dedup_key,
+        upstream_changed: false,
+    }
+}
+
+/// Parse transcription output. whisper.cpp-style lines (`[00:01:02.000 --> 00:01:05.500]  text`)
+/// become segments; anything else is taken as plain text without timestamps.
+fn parse_transcript(raw: &str) -> (String, Vec<Segment>) {
+    let mut body = String::new();
+    let mut segments = Vec::new();
+
+    for line in raw.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let parsed = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once(']'))
+            .and_then(|(stamps, text)| {
+                let (start, end) = stamps.split_once("-->")?;
+                Some((parse_timestamp(start)?, parse_timestamp(end)?, text.trim()))
+            });
+
+        let (text, timing) = match parsed {
+            Some((start, end, text)) => (text, Some((start, end))),
+            None => (line, None),
+        };
+        if text.is_empty() {
+            continue;
+        }
+        if !body.is_empty() {
+            body.push(' ');
+        }
+        if let Some((start, end)) = timing {
+            segments.push(Segment {
+                start,
+                end,
+                offset: body.chars().count(),
+            });
+        }
+        body.push_str(text);
+    }
+
+    (body, segments)
+}
+
+/// `HH:MM:SS.mmm`, `MM:SS.mmm`, or `HH:MM:SS,mmm` (SRT) → seconds.
+fn parse_timestamp(s: &str) -> Option<f64> {
+    let s = s.trim().replace(',', ".");
+    let mut secs = 0.0;
+    for part in s.split(':') {
+        secs = secs * 60.0 + part.parse::<f64>().ok()?;
+    }
+    Some(secs)
+}
+
+/// Single-quote a string for `sh -c`.
+pub(crate) fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
@@ -0,0 +1,361 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::header::{AUTHORIZATION, USER_AGENT};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use crate::config::ReadLaterSourceConfig;
+use crate::error::FetchError;
+use crate::fetch::{self, FetchResult};
+use crate::http_client;
+use crate::models::{ContentItem, Source};
+
+const REQUEST_TIMEOUT_SECS: u64 = 60;
+
+/// Omnivore's `search` query; `includeContent` returns the saved full text.
+const OMNIVORE_SEARCH_QUERY: &str = "query Search($first: Int, $query: String) {
+  search(first: $first, query: $query, includeContent: true) {
+    ... on SearchSuccess {
+      edges { node { id title url originalArticleUrl author savedAt publishedAt description content labels { name } } }
+    }
+    ... on SearchError { errorCodes }
+  }
+}";
+
+/// A saved article, normalized across services.
+struct SavedArticle {
+    id: String,
+    title: Option<String>,
+    url: Option<String>,
+    author: Option<String>,
+    saved_at: DateTime<Utc>,
+    published_at: Option<DateTime<Utc>>,
+    html: String,
+    tags: Vec<String>,
+    reading_time: Option<u64>,
+}
+
+/// Fetch the most recently saved articles from a read-later service (`wallabag`, `omnivore`).
+/// Each poll takes the newest `max_items`; already stored articles are deduplicated by the
+/// service's article ID.
+pub async fn fetch_read_later_source(source: &Source) -> Result<FetchResult> {
+    let base_url = source
+        .url
+        .as_deref()
+        .ok_or_else(|| FetchError::Parse {
+            url: source.name.clone(),
+            message: format!("{} source has no URL", source.source_type),
+        })?
+        .trim_end_matches('/');
+    let settings: ReadLaterSourceConfig = match source.read_later_config.as_deref() {
+        Some(json) => serde_json::from_str(json).map_err(|e| FetchError::Parse {
+            url: source.name.clone(),
+            message: format!("invalid stored read-later settings: {e}"),
+        })?,
+        None => ReadLaterSourceConfig::default(),
+    };
+
+    let builder = reqwest::Client::builder().timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS));
+    let client = http_client::with_proxy(builder, source.proxy.as_deref())
+        .and_then(|builder| builder.build())
+        .context("building HTTP client")?;
+
+    let articles = match source.source_type.as_str() {
+        "wallabag" => fetch_wallabag(&client, source, base_url, &settings).await?,
+        "omnivore" => fetch_omnivore(&client, source, base_url, &settings).await?,
+        other => anyhow::bail!("unsupported read-later service '{other}'"),
+    };
+
+    let now = Utc::now();
+    let items: Vec<ContentItem> = articles
+        .into_iter()
+        .map(|article| build_item(source, article, now))
+        .collect();
+    info!(source = %source.name, service = %source.source_type, articles = items.len(), "fetched saved articles");
+
+    Ok(FetchResult {
+        items,
+        etag: None,
+        last_modified: None,
+        moved_to: None,
+    })
+}
+
+#[derive(Deserialize)]
+struct WallabagToken {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+struct WallabagEntries {
+    #[serde(rename = "_embedded")]
+    embedded: WallabagEmbedded,
+}
+
+#[derive(Deserialize)]
+struct WallabagEmbedded {
+    items: Vec<WallabagEntry>,
+}
+
+#[derive(Deserialize)]
+struct WallabagEntry {
+    id: i64,
+    title: Option<String>,
+    url: Option<String>,
+    content: Option<String>,
+    created_at: String,
+    published_at: Option<String>,
+    #[serde(default)]
+    published_by: Option<Vec<String>>,
+    #[serde(default)]
+    tags: Vec<WallabagTag>,
+    reading_time: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct WallabagTag {
+    label: String,
+}
+
+/// wallabag: OAuth password grant for an access token, then `GET /api/entries.json`.
+async fn fetch_wallabag(
+    client: &reqwest::Client,
+    source: &Source,
+    base_url: &str,
+    settings: &ReadLaterSourceConfig,
+) -> Result<Vec<SavedArticle>> {
+    let token_url = format!("{base_url}/oauth/v2/token");
+    let token: WallabagToken = client
+        .post(&token_url)
+        .header(USER_AGENT, concat!("pail/", env!("CARGO_PKG_VERSION")))
+        .form(&[
+            ("grant_type", "password"),
+            ("client_id", settings.client_id.as_deref().unwrap_or_default()),
+            ("client_secret", settings.client_secret.as_deref().unwrap_or_default()),
+            ("username", source.auth_username.as_deref().unwrap_or_default()),
+            ("password", source.auth_password.as_deref().unwrap_or_default()),
+        ])
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| FetchError::Http {
+            url: token_url.clone(),
+            source: e,
+        })?
+        .json()
+        .await
+        .context("parsing wallabag token response")?;
+
+    let entries_url = format!("{base_url}/api/entries.json");
+    let mut query = vec![
+        ("sort", "created".to_string()),
+        ("order", "desc".to_string()),
+        ("perPage", source.max_items.to_string()),
+        ("detail", "full".to_string()),
+    ];
+    if !settings.include_archived {
+        query.push(("archive", "0".to_string()));
+    }
+    if !settings.tags.is_empty() {
+        query.push(("tags", settings.tags.join(",")));
+    }
+
+    let entries: WallabagEntries = client
+        .get(&entries_url)
+        .header(AUTHORIZATION, format!("Bearer {}", token.access_token))
+        .header(USER_AGENT, concat!("pail/", env!("CARGO_PKG_VERSION")))
+        .query(&query)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| FetchError::Http {
+            url: entries_url.clone(),
+            source: e,
+        })?
+        .json()
+        .await
+        .context("parsing wallabag entries")?;
+
+    debug!(source = %source.name, entries = entries.embedded.items.len(), "wallabag entries");
+    Ok(entries
+        .embedded
+        .items
+        .into_iter()
+        .map(|e| SavedArticle {
+            id: e.id.to_string(),
+            title: e.title,
+            url: e.url,
+            author: e.published_by.and_then(|authors| authors.into_iter().next()),
+            saved_at: parse_wallabag_date(&e.created_at).unwrap_or_else(Utc::now),
+            published_at: e.published_at.as_deref().and_then(parse_wallabag_date),
+            html: e.content.unwrap_or_default(),
+            tags: e.tags.into_iter().map(|t| t.label).collect(),
+            reading_time: e.reading_time.filter(|&minutes| minutes > 0),
+        })
+        .collect())
+}
+
+/// wallabag dates are ISO 8601 with a `+0000`-style offset.
+fn parse_wallabag_date(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%z")
+        .or_else(|_| DateTime::parse_from_rfc3339(s))
+        .ok()
+        .map(|d| d.to_utc())
+}
+
+#[derive(Deserialize)]
+struct OmnivoreResponse {
+    data: Option<OmnivoreData>,
+    #[serde(default)]
+    errors: Vec<OmnivoreError>,
+}
+
+#[derive(Deserialize)]
+struct OmnivoreError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct OmnivoreData {
+    search: OmnivoreSearch,
+}
+
+#[derive(Deserialize)]
+struct OmnivoreSearch {
+    edges: Option<Vec<OmnivoreEdge>>,
+    #[serde(rename = "errorCodes")]
+    error_codes: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct OmnivoreEdge {
+    node: OmnivoreNode,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OmnivoreNode {
+    id: String,
+    title: Option<String>,
+    url: Option<String>,
+    original_article_url: Option<String>,
+    author: Option<String>,
+    saved_at: DateTime<Utc>,
+    published_at: Option<DateTime<Utc>>,
+    description: Option<String>,
+    content: Option<String>,
+    #[serde(default)]
+    labels: Option<Vec<OmnivoreLabel>>,
+}
+
+#[derive(Deserialize)]
+struct OmnivoreLabel {
+    name: String,
+}
+
+/// Omnivore (self-hosted): the GraphQL `search` query, authenticated with the API key.
+async fn fetch_omnivore(
+    client: &reqwest::Client,
+    source: &Source,
+    base_url: &str,
+    settings: &ReadLaterSourceConfig,
+) -> Result<Vec<SavedArticle>> {
+    let url = format!("{base_url}/api/graphql");
+    let mut search = String::from(if settings.include_archived {
+        "in:all"
+    } else {
+        "in:inbox"
+    });
+    search.push_str(" sort:saved-desc");
+    for tag in &settings.tags {
+        search.push_str(&format!(" label:\"{}\"", tag.replace('"', "")));
+    }
+
+    let response: OmnivoreResponse = client
+        .post(&url)
+        // Omnivore takes the raw API key, without a "Bearer" prefix
+        .header(AUTHORIZATION, source.auth_token.as_deref().unwrap_or_default())
+        .header(USER_AGENT, concat!("pail/", env!("CARGO_PKG_VERSION")))
+        .json(&json!({
+            "query": OMNIVORE_SEARCH_QUERY,
+            "variables": { "first": source.max_items, "query": search },
+        }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| FetchError::Http {
+            url: url.clone(),
+            source: e,
+        })?
+        .json()
+        .await
+        .context("parsing Omnivore response")?;
+
+    if let Some(error) = response.errors.first() {
+        anyhow::bail!("Omnivore API error: {}", error.message);
+    }
+    let search = response
+        .data
+        .ok_or_else(|| anyhow::anyhow!("Omnivore response has no data"))?
+        .search;
+    if let Some(codes) = search.error_codes {
+        anyhow::bail!("Omnivore search failed: {}", codes.join(", "));
+    }
+
+    Ok(search
+        .edges
+        .unwrap_or_default()
+        .into_iter()
+        .map(|edge| {
+            let node = edge.node;
+            SavedArticle {
+                id: node.id,
+                title: node.title,
+                url: node.original_article_url.or(node.url),
+                author: node.author,
+                saved_at: node.saved_at,
+                published_at: node.published_at,
+                html: node.content.or(node.description).unwrap_or_default(),
+                tags: node.labels.unwrap_or_default().into_iter().map(|l| l.name).collect(),
+                reading_time: None,
+            }
+        })
+        .collect())
+}
+
+/// `original_date` is when the article was saved, so a "saved this week" channel covers
+/// what was saved in its window regardless of publication date.
+fn build_item(source: &Source, article: SavedArticle, now: DateTime<Utc>) -> ContentItem {
+    let mut metadata = json!({
+        "service": source.source_type,
+        "saved_at": article.saved_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    });
+    if let Some(published) = article.published_at {
+        metadata["published_at"] = json!(published.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+    }
+    if !article.tags.is_empty() {
+        metadata["tags"] = json!(article.tags);
+    }
+    if let Some(minutes) = article.reading_time {
+        metadata["reading_time_minutes"] = json!(minutes);
+    }
+
+    ContentItem {
+        id: Uuid::new_v4().to_string(),
+        source_id: source.id.clone(),
+        ingested_at: now,
+        original_date: article.saved_at,
+        content_type: "article".to_string(),
+        title: article.title.map(|t| t.trim().to_string()).filter(|t| !t.is_empty()),
+        body: fetch::strip_html(&article.html).trim().to_string(),
+        url: article.url,
+        author: article.author,
+        metadata: metadata.to_string(),
+        dedup_key: format!("{}:{}", source.source_type, article.id),
+        upstream_changed: false,
+    }
+}
@@ -0,0 +1,268 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use reqwest::Url;
+use scraper::{ElementRef, Html, Selector};
+use sha2::{Digest, Sha256};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::config::{Config, ScrapeSelectorsConfig};
+use crate::error::{CrawlError, FetchError};
+use crate::fetch::{self, FetchResult};
+use crate::http_client::Crawler;
+use crate::models::{ContentItem, Source};
+
+/// Date formats tried (in order) for date selector text that isn't RFC 3339 / RFC 2822.
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%d.%m.%Y", "%B %d, %Y", "%b %d, %Y", "%d %B %Y", "%d %b %Y"];
+
+/// Compiled selectors for one scrape source.
+struct Selectors {
+    item: Selector,
+    title: Option<Selector>,
+    link: Option<Selector>,
+    date: Option<Selector>,
+    body: Option<Selector>,
+}
+
+/// A source's page as fetched for extraction.
+pub(crate) struct PageResponse {
+    /// Final URL (after redirects), for resolving relative links.
+    pub base_url: Url,
+    /// `None` on 304 Not Modified.
+    pub body: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+/// Fetch a web page and extract items with the source's CSS selectors.
+pub async fn fetch_scrape_source(config: &Config, source: &Source) -> Result<FetchResult> {
+    let url = source.url.as_deref().ok_or_else(|| FetchError::Parse {
+        url: source.name.clone(),
+        message: "scrape source has no URL".to_string(),
+    })?;
+    let selectors = parse_selectors(source, url)?;
+
+    let response = fetch_page(config, source, url).await?;
+    let Some(ref page) = response.body else {
+        return Ok(FetchResult {
+            items: Vec::new(),
+            etag: response.etag,
+            last_modified: response.last_modified,
+            moved_to: None,
+        });
+    };
+
+    let items = extract_items(source, &selectors, &response.base_url, page);
+
+    if items.is_empty() {
+        warn!(source = %source.name, url = %url, "item selector matched no usable items");
+    }
+
+    Ok(FetchResult {
+        items,
+        etag: response.etag,
+        last_modified: response.last_modified,
+        moved_to: None,
+    })
+}
+
+/// GET a source's page. Uses the same auth and conditional GET handling as RSS sources; the
+/// request goes through the crawler, so robots.txt, the crawl delay and the crawler
+/// User-Agent apply.
+pub(crate) async fn fetch_page(config: &Config, source: &Source, url: &str) -> Result<PageResponse> {
+    let page_url = Url::parse(url).map_err(|e| FetchError::Parse {
+        url: url.to_string(),
+        message: format!("invalid URL: {e}"),
+    })?;
+    let crawler = Crawler::new(fetch::build_client(source, url)?, &config.network)?;
+
+    debug!(url = %url, source = %source.name, "fetching page");
+
+    let response = crawler.get(&page_url).await.map_err(|e| match e {
+        CrawlError::Http(e) => anyhow::Error::from(FetchError::Http {
+            url: url.to_string(),
+            source: e,
+        }),
+        e => anyhow::anyhow!("not fetching {url}: {e}"),
+    })?;
+
+    let resp_etag = response
+        .headers()
+        .get("etag")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+    let resp_last_modified = response
+        .headers()
+        .get("last-modified")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        info!(source = %source.name, url = %url, "page not modified (304)");
+        return Ok(PageResponse {
+            base_url: response.url().clone(),
+            body: None,
+            etag: resp_etag.or_else(|| source.last_etag.clone()),
+            last_modified: resp_last_modified.or_else(|| source.last_modified_header.clone()),
+        });
+    }
+
+    if !response.status().is_success() {
+        return Err(FetchError::Http {
+            url: url.to_string(),
+            source: response.error_for_status().unwrap_err(),
+        }
+        .into());
+    }
+
+    // Resolve relative links against the final URL (after redirects)
+    let base_url = response.url().clone();
+    let page = response.text().await.map_err(|e| FetchError::Http {
+        url: url.to_string(),
+        source: e,
+    })?;
+
+    Ok(PageResponse {
+        base_url,
+        body: Some(page),
+        etag: resp_etag,
+        last_modified: resp_last_modified,
+    })
+}
+
+fn parse_selectors(source: &Source, url: &str) -> Result<Selectors, FetchError> {
+    let config: ScrapeSelectorsConfig = source
+        .scrape_selectors
+        .as_deref()
+        .ok_or_else(|| FetchError::Parse {
+            url: url.to_string(),
+            message: "scrape source has no selectors".to_string(),
+        })
+        .and_then(|json| {
+            serde_json::from_str(json).map_err(|e| FetchError::Parse {
+                url: url.to_string(),
+                message: format!("invalid stored selectors: {e}"),
+            })
+        })?;
+
+    let compile = |selector: &str| {
+        Selector::parse(selector).map_err(|e| FetchError::Parse {
+            url: url.to_string(),
+            message: format!("invalid CSS selector '{selector}': {e}"),
+        })
+    };
+
+    Ok(Selectors {
+        item: compile(&config.item)?,
+        title: config.title.as_deref().map(compile).transpose()?,
+        link: config.link.as_deref().map(compile).transpose()?,
+        date: config.date.as_deref().map(compile).transpose()?,
+        body: config.body.as_deref().map(compile).transpose()?,
+    })
+}
+
+fn extract_items(source: &Source, selectors: &Selectors, base_url: &Url, page: &str) -> Vec<ContentItem> {
+    let document = Html::parse_document(page);
+    let now = Utc::now();
+
+    document
+        .select(&selectors.item)
+        .take(source.max_items as usize)
+        .filter_map(|item| {
+            let title = selectors
+                .title
+                .as_ref()
+                .and_then(|s| item.select(s).next())
+                .map(element_text)
+                .filter(|t| !t.is_empty());
+
+            // Link: first matching element's href (or the item itself if it's an <a>)
+            let link_el = match selectors.link {
+                Some(ref s) => item.select(s).next(),
+                None => Some(item).filter(|el| el.value().name() == "a"),
+            };
+            let url = link_el
+                .and_then(|el| el.value().attr("href"))
+                .and_then(|href| base_url.join(href).ok())
+                .map(|u| u.to_string());
+
+            let body = match selectors.body {
+                Some(ref s) => item.select(s).next().map(|el| fetch::strip_html(&el.html())),
+                None => Some(fetch::strip_html(&item.html())),
+            }
+            .map(|b| b.trim().to_string())
+            .unwrap_or_default();
+
+            if body.is_empty() && title.is_none() {
+                debug!(source = %source.name, "skipping empty scraped item");
+                return None;
+            }
+
+            let original_date = selectors
+                .date
+                .as_ref()
+                .and_then(|s| item.select(s).next())
+                .and_then(|el| {
+                    // Prefer machine-readable <time datetime="...">
+                    el.value()
+                        .attr("datetime")
+                        .and_then(parse_date)
+                        .or_else(|| parse_date(&element_text(el)))
+                })
+                .unwrap_or(now);
+
+            // Content-hash dedup: pages have no GUIDs, so an item is identified by what it says
+            // (see docs/specs/scrape-sources.md "Deduplication")
+            let mut hasher = Sha256::new();
+            hasher.update(url.as_deref().unwrap_or(""));
+            hasher.update("|");
+            hasher.update(title.as_deref().unwrap_or(""));
+            hasher.update("|");
+            hasher.update(&body);
+            let dedup_key = format!("sha256:{:x}", hasher.finalize());
+
+            let content_type = if url.is_some() { "link" } else { "text" };
+
+            Some(ContentItem {
+                id: Uuid::new_v4().to_string(),
+                source_id: source.id.clone(),
+                ingested_at: now,
+                original_date,
+                content_type: content_type.to_string(),
+                title,
+                body,
+                url,
+                author: None,
+                metadata: "{}".to_string(),
+                dedup_key,
+                upstream_changed: false,
+            })
+        })
+        .collect()
+}
+
+/// Whitespace-normalized text content of an element.
+pub(crate) fn element_text(el: ElementRef) -> String {
+    el.text()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Best-effort date parsing for scraped date strings. Dates without a time are taken as midnight UTC.
+pub(crate) fn parse_date(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(text) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc2822(text) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    DATE_FORMATS
+        .iter()
+        .find_map(|fmt| NaiveDate::parse_from_str(text, fmt).ok())
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc())
+}
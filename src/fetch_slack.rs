@@ -0,0 +1,520 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::header::{AUTHORIZATION, USER_AGENT};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use sqlx::SqlitePool;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::config::SlackSourceConfig;
+use crate::error::FetchError;
+use crate::fetch::FetchResult;
+use crate::http_client;
+use crate::models::{ContentItem, Source};
+use crate::store;
+
+const REQUEST_TIMEOUT_SECS: u64 = 30;
+
+/// Web API base used when the source has no `url`.
+const API_DEFAULT_URL: &str = "https://slack.com/api";
+
+/// A message is stored once its thread (or the message itself, without replies) has been quiet
+/// this long, since stored items are immutable.
+const THREAD_QUIET_MINS: i64 = 60;
+
+/// Messages this old are stored even if their thread is still active; later replies are lost.
+const MAX_HOLD_HOURS: i64 = 24;
+
+/// History read on every poll: the hold period plus a margin, so held messages are seen again.
+const HISTORY_WINDOW_HOURS: i64 = MAX_HOLD_HOURS + 1;
+
+/// Furthest back a poll reads after downtime.
+const MAX_CATCH_UP_DAYS: i64 = 7;
+
+/// Page size for history, replies and channel listings, and the page cap per request kind.
+const PAGE_LIMIT: u32 = 200;
+const MAX_PAGES: usize = 10;
+
+/// Message subtypes that carry content. Joins, topic changes and the like are skipped, and
+/// `thread_broadcast` replies are already part of their thread.
+const CONTENT_SUBTYPES: &[&str] = &["bot_message", "file_share", "me_message"];
+
+#[derive(Deserialize)]
+struct AuthTest {
+    /// Workspace URL, `https://<team>.slack.com/`.
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct ChannelList {
+    #[serde(default)]
+    channels: Vec<Channel>,
+    response_metadata: Option<ResponseMetadata>,
+}
+
+#[derive(Deserialize, Clone)]
+struct Channel {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct ResponseMetadata {
+    #[serde(default)]
+    next_cursor: String,
+}
+
+#[derive(Deserialize)]
+struct MessagePage {
+    #[serde(default)]
+    messages: Vec<Message>,
+    response_metadata: Option<ResponseMetadata>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    ts: String,
+    #[serde(default)]
+    text: String,
+    subtype: Option<String>,
+    user: Option<String>,
+    /// Display name of bot and integration posts.
+    username: Option<String>,
+    thread_ts: Option<String>,
+    #[serde(default)]
+    reply_count: i64,
+    latest_reply: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UserInfo {
+    user: User,
+}
+
+#[derive(Deserialize)]
+struct User {
+    name: String,
+    #[serde(default)]
+    profile: UserProfile,
+}
+
+#[derive(Deserialize, Default)]
+struct UserProfile {
+    #[serde(default)]
+    display_name: String,
+    #[serde(default)]
+    real_name: String,
+}
+
+/// Slack Web API client for one poll: token, workspace URL, and the user names resolved so far.
+struct Slack {
+    client: reqwest::Client,
+    base: String,
+    token: String,
+    users: HashMap<String, String>,
+}
+
+/// Fetch recent messages from the configured channels. Each top-level message becomes one item,
+/// with its thread replies appended; it is held until the thread has been quiet for
+/// `THREAD_QUIET_MINS` (at most `MAX_HOLD_HOURS`). Replies are only fetched for messages not
+/// stored yet.
+pub async fn fetch_slack_source(pool: &SqlitePool, source: &Source) -> Result<FetchResult> {
+    let settings: SlackSourceConfig = match source.slack_config.as_deref() {
+        Some(json) => serde_json::from_str(json).map_err(|e| FetchError::Parse {
+            url: source.name.clone(),
+            message: format!("invalid stored slack settings: {e}"),
+        })?,
+        None => {
+            return Err(FetchError::Parse {
+                url: source.name.clone(),
+                message: "slack source has no [source.slack] settings".to_string(),
+            }
+            .into());
+        }
+    };
+    let token = source.auth_token.clone().ok_or_else(|| FetchError::Parse {
+        url: source.name.clone(),
+        message: "slack source has no bearer token".to_string(),
+    })?;
+    let mut slack = Slack {
+        client: http_client::with_proxy(
+            reqwest::Client::builder().timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS)),
+            source.proxy.as_deref(),
+        )
+        .and_then(|builder| builder.build())
+        .context("building HTTP client")?,
+        base: source
+            .url
+            .as_deref()
+            .unwrap_or(API_DEFAULT_URL)
+            .trim_end_matches('/')
+            .to_string(),
+        token,
+        users: HashMap::new(),
+    };
+
+    let workspace: AuthTest = slack.call("auth.test", &[]).await?;
+    let channels = slack.resolve_channels(&settings.channels).await?;
+
+    let now = Utc::now();
+    let oldest = source
+        .last_success_at
+        .map_or(now, |t| t.min(now))
+        .min(now - chrono::Duration::hours(HISTORY_WINDOW_HOURS))
+        .max(now - chrono::Duration::days(MAX_CATCH_UP_DAYS));
+
+    let mut items = Vec::new();
+    let mut failures = Vec::new();
+    for wanted in &settings.channels {
+        let Some(channel) = channels.get(wanted.trim_start_matches('#')) else {
+            warn!(source = %source.name, channel = %wanted, "Slack channel not found or not visible to the token");
+            failures.push(format!("channel '{wanted}' not found"));
+            continue;
+        };
+        match slack
+            .fetch_channel(pool, source, &settings, channel, &workspace.url, oldest, now)
+            .await
+        {
+            Ok(mut fetched) => items.append(&mut fetched),
+            Err(e) => {
+                warn!(source = %source.name, channel = %channel.name, "failed to fetch Slack channel: {e:#}");
+                failures.push(format!("#{}: {e:#}", channel.name));
+            }
+        }
+    }
+    // One inaccessible channel shouldn't stop the others; all of them failing is a fetch failure
+    if failures.len() == settings.channels.len() {
+        anyhow::bail!("no Slack channel could be read: {}", failures.join("; "));
+    }
+
+    items.sort_by_key(|item: &ContentItem| std::cmp::Reverse(item.original_date));
+    items.truncate(source.max_items as usize);
+    info!(
+        source = %source.name,
+        channels = settings.channels.len(),
+        items = items.len(),
+        "fetched Slack messages"
+    );
+
+    Ok(FetchResult {
+        items,
+        etag: None,
+        last_modified: None,
+        moved_to: None,
+    })
+}
+
+impl Slack {
+    /// Call a Web API method. Slack reports most errors as `200 {"ok": false, "error": ...}`.
+    async fn call<T: DeserializeOwned>(&self, method: &str, query: &[(&str, String)]) -> Result<T> {
+        let url = format!("{}/{method}", self.base);
+        let response = self
+            .client
+            .get(&url)
+            .header(USER_AGENT, concat!("pail/", env!("CARGO_PKG_VERSION")))
+            .header(AUTHORIZATION, format!("Bearer {}", self.token))
+            .query(query)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| FetchError::Http {
+                url: url.clone(),
+                source: e,
+            })?;
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .with_context(|| format!("parsing response from {url}"))?;
+        if body.get("ok").and_then(|v| v.as_bool()) != Some(true) {
+            let error = body.get("error").and_then(|v| v.as_str()).unwrap_or("unknown error");
+            let hint = match error {
+                "not_in_channel" => " (invite the app to the channel)",
+                "missing_scope" => " (the token lacks a scope listed in docs/specs/slack-sources.md)",
+                "invalid_auth" | "not_authed" | "token_revoked" => " (check the [source.auth] token)",
+                _ => "",
+            };
+            anyhow::bail!("Slack API {method} failed: {error}{hint}");
+        }
+        serde_json::from_value(body).with_context(|| format!("parsing response from {url}"))
+    }
+
+    /// Map configured channel names and IDs to channels. IDs are looked up in the listing too,
+    /// for the name shown in items.
+    async fn resolve_channels(&self, wanted: &[String]) -> Result<HashMap<String, Channel>> {
+        let mut listed = Vec::new();
+        let mut cursor = String::new();
+        for _ in 0..MAX_PAGES {
+            let mut query = vec![
+                ("types", "public_channel,private_channel".to_string()),
+                ("exclude_archived", "true".to_string()),
+                ("limit", PAGE_LIMIT.to_string()),
+            ];
+            if !cursor.is_empty() {
+                query.push(("cursor", cursor.clone()));
+            }
+            let page: ChannelList = self.call("conversations.list", &query).await?;
+            listed.extend(page.channels);
+            cursor = page.response_metadata.map(|m| m.next_cursor).unwrap_or_default();
+            if cursor.is_empty() {
+                break;
+            }
+        }
+        Ok(wanted
+            .iter()
+            .map(|w| w.trim_start_matches('#'))
+            .filter_map(|w| {
+                let channel = listed.iter().find(|c| c.id == w || c.name.eq_ignore_ascii_case(w))?;
+                Some((w.to_string(), channel.clone()))
+            })
+            .collect())
+    }
+
+    /// Storable, not yet stored top-level messages of one channel since `oldest`, as items.
+    #[allow(clippy::too_many_arguments)]
+    async fn fetch_channel(
+        &mut self,
+        pool: &SqlitePool,
+        source: &Source,
+        settings: &SlackSourceConfig,
+        channel: &Channel,
+        workspace_url: &str,
+        oldest: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<ContentItem>> {
+        let history = self
+            .paginate(
+                "conversations.history",
+                vec![
+                    ("channel", channel.id.clone()),
+                    ("oldest", format!("{}.000000", oldest.timestamp())),
+                ],
+            )
+            .await?;
+        let quiet_before = now - chrono::Duration::minutes(THREAD_QUIET_MINS);
+        let hold_before = now - chrono::Duration::hours(MAX_HOLD_HOURS);
+
+        let mut items = Vec::new();
+        let mut held = 0;
+        for message in history {
+            let top_level = message.thread_ts.as_deref().is_none_or(|t| t == message.ts);
+            let has_content = message.subtype.as_deref().is_none_or(|s| CONTENT_SUBTYPES.contains(&s));
+            let Some(posted) = parse_ts(&message.ts) else {
+                continue;
+            };
+            if !top_level || !has_content {
+                continue;
+            }
+            let threaded = settings.include_threads && message.reply_count > 0;
+            let last_activity = match message.latest_reply.as_deref().filter(|_| threaded) {
+                Some(latest) => parse_ts(latest).unwrap_or(posted),
+                None => posted,
+            };
+            if last_activity > quiet_before && posted > hold_before {
+                held += 1;
+                continue;
+            }
+            let dedup_key = format!("slack:{}:{}", channel.id, message.ts);
+            if store::content_item_exists(pool, &source.id, &dedup_key).await? {
+                continue;
+            }
+
+            let replies = if threaded {
+                match self
+                    .paginate(
+                        "conversations.replies",
+                        vec![("channel", channel.id.clone()), ("ts", message.ts.clone())],
+                    )
+                    .await
+                {
+                    // The first message of the listing is the thread's own root
+                    Ok(replies) => replies.into_iter().filter(|r| r.ts != message.ts).collect(),
+                    // The message is still worth ingesting without its thread
+                    Err(e) => {
+                        warn!(source = %source.name, channel = %channel.name, ts = %message.ts, "failed to fetch thread replies: {e:#}");
+                        Vec::new()
+                    }
+                }
+            } else {
+                Vec::new()
+            };
+
+            items.push(
+                self.build_item(source, channel, workspace_url, message, replies, posted, dedup_key, now)
+                    .await,
+            );
+        }
+        debug!(source = %source.name, channel = %channel.name, items = items.len(), held, "read Slack channel");
+        Ok(items)
+    }
+
+    /// All messages of a history or replies listing, following cursors up to `MAX_PAGES`.
+    async fn paginate(&self, method: &str, base_query: Vec<(&str, String)>) -> Result<Vec<Message>> {
+        let mut messages = Vec::new();
+        let mut cursor = String::new();
+        for _ in 0..MAX_PAGES {
+            let mut query = base_query.clone();
+            query.push(("limit", PAGE_LIMIT.to_string()));
+            if !cursor.is_empty() {
+                query.push(("cursor", cursor.clone()));
+            }
+            let page: MessagePage = self.call(method, &query).await?;
+            messages.extend(page.messages);
+            cursor = page.response_metadata.map(|m| m.next_cursor).unwrap_or_default();
+            if cursor.is_empty() {
+                break;
+            }
+        }
+        Ok(messages)
+    }
+
+    /// Display name of a user, looked up once per poll. Falls back to the ID when `users:read`
+    /// is missing.
+    async fn user_name(&mut self, id: &str) -> String {
+        if let Some(name) = self.users.get(id) {
+            return name.clone();
+        }
+        let name = match self.call::<UserInfo>("users.info", &[("user", id.to_string())]).await {
+            Ok(info) => [
+                info.user.profile.display_name,
+                info.user.profile.real_name,
+                info.user.name,
+            ]
+            .into_iter()
+            .find(|n| !n.is_empty())
+            .unwrap_or_else(|| id.to_string()),
+            Err(e) => {
+                debug!(user = %id, "failed to look up Slack user: {e:#}");
+                id.to_string()
+            }
+        };
+        self.users.insert(id.to_string(), name.clone());
+        name
+    }
+
+    async fn author(&mut self, message: &Message) -> Option<String> {
+        match (message.user.as_deref(), message.username.as_deref()) {
+            (Some(user), _) => Some(self.user_name(user).await),
+            (None, Some(name)) => Some(name.to_string()),
+            (None, None) => None,
+        }
+    }
+
+    /// Message text as plain text: mentions, channel references and links resolved.
+    async fn render(&mut self, text: &str) -> String {
+        let mut out = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find('<') {
+            out.push_str(&rest[..start]);
+            let Some(end) = rest[start..].find('>') else {
+                out.push_str(&rest[start..]);
+                rest = "";
+                break;
+            };
+            let inner = &rest[start + 1..start + end];
+            let (target, label) = match inner.split_once('|') {
+                Some((t, l)) => (t, Some(l)),
+                None => (inner, None),
+            };
+            if let Some(user) = target.strip_prefix('@') {
+                let name = match label {
+                    Some(label) => label.to_string(),
+                    None => self.user_name(user).await,
+                };
+                out.push('@');
+                out.push_str(&name);
+            } else if let Some(channel) = target.strip_prefix('#') {
+                out.push('#');
+                out.push_str(label.unwrap_or(channel));
+            } else if let Some(special) = target.strip_prefix('!') {
+                // `<!here>`, `<!subteam^ID|@team>`, `<!date^...|fallback>`
+                match label {
+                    Some(label) => out.push_str(label),
+                    None => out.push_str(&format!("@{special}")),
+                }
+            } else {
+                match label {
+                    Some(label) if label != target => out.push_str(&format!("{label} ({target})")),
+                    _ => out.push_str(target),
+                }
+            }
+            rest = &rest[start + end + 1..];
+        }
+        out.push_str(rest);
+        unescape(&out)
+    }
+
+    /// One item per top-level message, with its thread replies (if any) appended.
+    #[allow(clippy::too_many_arguments)]
+    async fn build_item(
+        &mut self,
+        source: &Source,
+        channel: &Channel,
+        workspace_url: &str,
+        message: Message,
+        replies: Vec<Message>,
+        posted: DateTime<Utc>,
+        dedup_key: String,
+        now: DateTime<Utc>,
+    ) -> ContentItem {
+        let author = self.author(&message).await;
+        let mut body = self.render(&message.text).await.trim().to_string();
+        if !replies.is_empty() {
+            if !body.is_empty() {
+                body.push_str("\n\n");
+            }
+            body.push_str("Thread replies:");
+            for reply in &replies {
+                let name = self.author(reply).await.unwrap_or_else(|| "unknown".to_string());
+                let text = self.render(&reply.text).await;
+                let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                body.push_str(&format!("\n- {name}: {text}"));
+            }
+        }
+
+        let metadata = json!({
+            "slack_channel": format!("#{}", channel.name),
+            "reply_count": message.reply_count,
+        });
+
+        ContentItem {
+            id: Uuid::new_v4().to_string(),
+            source_id: source.id.clone(),
+            ingested_at: now,
+            original_date: posted,
+            content_type: "message".to_string(),
+            title: None,
+            body,
+            url: Some(permalink(workspace_url, &channel.id, &message.ts)),
+            author,
+            metadata: metadata.to_string(),
+            dedup_key,
+            upstream_changed: false,
+        }
+    }
+}
+
+/// `https://<team>.slack.com/archives/<channel>/p<ts without the dot>`, the form Slack's own
+/// "Copy link" produces.
+fn permalink(workspace_url: &str, channel_id: &str, ts: &str) -> String {
+    format!(
+        "{}/archives/{channel_id}/p{}",
+        workspace_url.trim_end_matches('/'),
+        ts.replace('.', "")
+    )
+}
+
+/// Message timestamp (`1712345678.123456`, also the message ID) as a date.
+fn parse_ts(ts: &str) -> Option<DateTime<Utc>> {
+    let (secs, micros) = ts.split_once('.').unwrap_or((ts, "0"));
+    DateTime::from_timestamp(secs.parse().ok()?, micros.parse::<u32>().ok()?.checked_mul(1000)?)
+}
+
+/// Slack escapes only these three in message text.
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&amp;", "&")
+}
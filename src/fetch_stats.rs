@@ -0,0 +1,249 @@
+//! `pail_stats` sources: one item per day about pail itself — items ingested per source,
+//! generation runs per channel, their failures and cost (see docs/specs/stats-source.md).
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde_json::json;
+use sqlx::SqlitePool;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::fetch::FetchResult;
+use crate::models::{ChannelRunStats, ContentItem, Source};
+use crate::store;
+
+/// Complete days looked back on for a missing report, so a new source or a daemon that was
+/// down still covers the last week.
+const BACKFILL_DAYS: i64 = 7;
+
+/// Characters of a failed run's error quoted in the report.
+const MAX_ERROR_CHARS: usize = 300;
+
+/// Reports for the complete days (in `[pail].timezone`) of the last `BACKFILL_DAYS` that don't
+/// have one yet. Days with nothing ingested and no runs, like those before pail was set up,
+/// are left out.
+pub async fn fetch_stats_source(pool: &SqlitePool, config: &Config, source: &Source) -> Result<FetchResult> {
+    let tz: chrono_tz::Tz = config.pail.timezone.parse().unwrap_or(chrono_tz::UTC);
+    let now = Utc::now();
+    let today = now.with_timezone(&tz).date_naive();
+
+    let mut items = Vec::new();
+    for days_ago in (1..=BACKFILL_DAYS).rev() {
+        let day = today - chrono::Duration::days(days_ago);
+        let dedup_key = format!("pail_stats:{day}");
+        if store::content_item_exists(pool, &source.id, &dedup_key).await? {
+            continue;
+        }
+        let (from, to) = (day_start(&tz, day), day_start(&tz, day.succ_opt().unwrap_or(day)));
+        let ingested = store::get_ingested_counts(pool, from, to).await?;
+        let runs = store::get_run_stats(pool, from, to).await?;
+        if ingested.is_empty() && runs.is_empty() {
+            continue;
+        }
+        let failures = store::get_failed_runs(pool, from, to).await?;
+
+        let items_total: i64 = ingested.iter().map(|(_, n)| n).sum();
+        let metadata = json!({
+            "stats_date": day.to_string(),
+            "items_ingested": items_total,
+            "runs": runs.iter().map(|r| r.runs).sum::<i64>(),
+            "runs_failed": runs.iter().map(|r| r.failed).sum::<i64>(),
+            "cost_usd": runs.iter().filter_map(|r| r.cost_usd).reduce(|a, b| a + b),
+        });
+        items.push(ContentItem {
+            id: Uuid::new_v4().to_string(),
+            source_id: source.id.clone(),
+            ingested_at: now,
+            // The day's end, so the report sits next to the day it covers
+            original_date: to,
+            content_type: "stats".to_string(),
+            title: Some(format!("pail activity on {day}")),
+            body: report(&ingested, &runs, &failures),
+            url: None,
+            author: Some("pail".to_string()),
+            metadata: metadata.to_string(),
+            dedup_key,
+            upstream_changed: false,
+        });
+    }
+
+    info!(source = %source.name, reports = items.len(), "compiled pail stats");
+    Ok(FetchResult {
+        items,
+        etag: None,
+        last_modified: None,
+        moved_to: None,
+    })
+}
+
+/// Midnight of a local day in UTC; 1:00 where a DST change skips midnight.
+fn day_start(tz: &chrono_tz::Tz, day: NaiveDate) -> DateTime<Utc> {
+    let midnight = day.and_hms_opt(0, 0, 0).unwrap_or_default();
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(midnight + chrono::Duration::hours(1)))
+                .earliest()
+        })
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|| midnight.and_utc())
+}
+
+/// The report body: ingestion per source, runs per channel, then each failure.
+fn report(ingested: &[(String, i64)], runs: &[ChannelRunStats], failures: &[(String, Option<String>)]) -> String {
+    let mut md = String::new();
+
+    let items_total: i64 = ingested.iter().map(|(_, n)| n).sum();
+    md.push_str(&format!(
+        "## Ingestion\n\n{items_total} items from {} sources.\n\n",
+        ingested.len()
+    ));
+    if !ingested.is_empty() {
+        md.push_str("| Source | Items |\n|---|---|\n");
+        for (name, count) in ingested {
+            md.push_str(&format!("| {} | {count} |\n", cell(name)));
+        }
+        md.push('\n');
+    }
+
+    let total = |f: fn(&ChannelRunStats) -> i64| runs.iter().map(f).sum::<i64>();
+    let cost: Option<f64> = runs.iter().filter_map(|r| r.cost_usd).reduce(|a, b| a + b);
+    md.push_str(&format!(
+        "## Generation\n\n{} runs: {} generated, {} skipped, {} failed. Cost: {}.\n\n",
+        total(|r| r.runs),
+        total(|r| r.generated),
+        total(|r| r.skipped),
+        total(|r| r.failed),
+        usage(cost, runs.iter().filter_map(|r| r.tokens).reduce(|a, b| a + b)),
+    ));
+    if !runs.is_empty() {
+        md.push_str("| Channel | Runs | Generated | Skipped | Failed | Cost |\n|---|---|---|---|---|---|\n");
+        for r in runs {
+            md.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                cell(&r.channel),
+                r.runs,
+                r.generated,
+                r.skipped,
+                r.failed,
+                usage(r.cost_usd, r.tokens)
+            ));
+        }
+        md.push('\n');
+    }
+
+    if !failures.is_empty() {
+        md.push_str("## Failures\n\n");
+        for (channel, error) in failures {
+            let error = error.as_deref().unwrap_or("no error recorded").replace('\n', " ");
+            let error = match error.char_indices().nth(MAX_ERROR_CHARS) {
+                Some((i, _)) => format!("{}…", &error[..i]),
+                None => error,
+            };
+            md.push_str(&format!("- **{channel}:** {error}\n"));
+        }
+    }
+    md
+}
+
+/// `$0.42 (123456 tokens)`, or `unknown` when no run reported usage.
+fn usage(cost_usd: Option<f64>, tokens: Option<i64>) -> String {
+    match (cost_usd, tokens) {
+        (Some(cost), Some(tokens)) => format!("${cost:.2} ({tokens} tokens)"),
+        (Some(cost), None) => format!("${cost:.2}"),
+        (None, Some(tokens)) => format!("{tokens} tokens"),
+        (None, None) => "unknown".to_string(),
+    }
+}
+
+/// A name as a table cell.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_stats(channel: &str, cost_usd: Option<f64>, tokens: Option<i64>) -> ChannelRunStats {
+        ChannelRunStats {
+            channel: channel.to_string(),
+            runs: 3,
+            generated: 1,
+            skipped: 1,
+            failed: 1,
+            cost_usd,
+            tokens,
+        }
+    }
+
+    #[test]
+    fn test_usage() {
+        assert_eq!(usage(Some(0.42), Some(1200)), "$0.42 (1200 tokens)");
+        assert_eq!(usage(Some(1.0), None), "$1.00");
+        assert_eq!(usage(None, Some(5)), "5 tokens");
+        assert_eq!(usage(None, None), "unknown");
+    }
+
+    #[test]
+    fn test_day_start() {
+        let day = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        assert_eq!(
+            day_start(&chrono_tz::UTC, day).to_rfc3339(),
+            "2025-03-10T00:00:00+00:00"
+        );
+        assert_eq!(
+            day_start(&chrono_tz::Europe::Kyiv, day).to_rfc3339(),
+            "2025-03-09T22:00:00+00:00"
+        );
+        // Chile moves its clocks from midnight to 1:00
+        let dst = NaiveDate::from_ymd_opt(2024, 9, 8).unwrap();
+        assert_eq!(
+            day_start(&chrono_tz::America::Santiago, dst).to_rfc3339(),
+            "2024-09-08T04:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_report_sections() {
+        let ingested = vec![("Hacker News".to_string(), 40), ("a|b".to_string(), 2)];
+        let runs = vec![
+            run_stats("Tech", Some(0.5), Some(1000)),
+            run_stats("News", Some(0.25), None),
+        ];
+        let failures = vec![("Tech".to_string(), Some("opencode\ncrashed".to_string()))];
+        let md = report(&ingested, &runs, &failures);
+
+        assert!(md.contains("## Ingestion\n\n42 items from 2 sources.\n\n"), "{md}");
+        assert!(md.contains("| Hacker News | 40 |\n| a\\|b | 2 |\n"), "{md}");
+        assert!(
+            md.contains("6 runs: 2 generated, 2 skipped, 2 failed. Cost: $0.75 (1000 tokens)."),
+            "{md}"
+        );
+        assert!(md.contains("| Tech | 3 | 1 | 1 | 1 | $0.50 (1000 tokens) |\n"), "{md}");
+        assert!(md.contains("| News | 3 | 1 | 1 | 1 | $0.25 |\n"), "{md}");
+        assert!(md.ends_with("## Failures\n\n- **Tech:** opencode crashed\n"), "{md}");
+    }
+
+    #[test]
+    fn test_report_without_activity_or_usage() {
+        let md = report(&[], &[run_stats("Tech", None, None)], &[("Tech".to_string(), None)]);
+        assert!(
+            md.starts_with("## Ingestion\n\n0 items from 0 sources.\n\n## Generation"),
+            "{md}"
+        );
+        assert!(md.contains("Cost: unknown."), "{md}");
+        assert!(md.contains("- **Tech:** no error recorded\n"), "{md}");
+    }
+
+    #[test]
+    fn test_report_cuts_long_errors() {
+        let error = "é".repeat(MAX_ERROR_CHARS + 10);
+        let md = report(&[], &[], &[("Tech".to_string(), Some(error))]);
+        let expected = format!("- **Tech:** {}…\n", "é".repeat(MAX_ERROR_CHARS));
+        assert!(md.ends_with(&expected), "{md}");
+        // No runs, no channel table
+        assert!(!md.contains("| Channel |"), "{md}");
+    }
+}
@@ -1,10 +1,12 @@
+use std::collections::HashSet;
 use std::time::Duration;
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use grammers_client::Client;
 use grammers_client::media::Media;
-use grammers_session::types::{PeerAuth, PeerId, PeerRef};
+use grammers_mtsender::InvocationError;
+use grammers_session::types::{PeerAuth, PeerId, PeerKind, PeerRef};
 use sqlx::SqlitePool;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
@@ -12,6 +14,22 @@ use uuid::Uuid;
 
 use crate::models::{ContentItem, Source};
 use crate::store;
+use crate::telegram::TgClients;
+use crate::tg_engagement;
+use crate::tg_session::namespaced;
+
+/// History items buffered per write. Backfills can be thousands of messages; one transaction
+/// per batch instead of one autocommit per message.
+const HISTORY_WRITE_BATCH: usize = 500;
+
+/// Messages per history request; a backfill pauses after each page's worth.
+const BACKFILL_PAGE: usize = 100;
+
+/// Pause between history pages of a backfill, on top of any FLOOD_WAIT.
+const BACKFILL_PAGE_PAUSE: Duration = Duration::from_secs(1);
+
+/// Wait used when a FLOOD_WAIT error doesn't say how long.
+const DEFAULT_FLOOD_WAIT_SECS: u32 = 30;
 
 /// Convert a grammers Message to a pail ContentItem.
 /// Returns None for empty messages (no text, no media).
@@ -51,6 +69,13 @@ pub fn message_to_content_item(
     let mut meta = serde_json::Map::new();
     meta.insert("message_id".to_string(), serde_json::json!(message_id));
     meta.insert("chat_id".to_string(), serde_json::json!(chat_id));
+    // Deletions outside channels don't name the chat (see tg_listener)
+    let chat_type = match msg.peer_id().kind() {
+        PeerKind::Channel => "channel",
+        PeerKind::Chat => "chat",
+        PeerKind::User | PeerKind::UserSelf => "user",
+    };
+    meta.insert("chat_type".to_string(), serde_json::json!(chat_type));
 
     if let Some(reply_to) = msg.reply_to_message_id() {
         meta.insert("reply_to_msg_id".to_string(), serde_json::json!(reply_to));
@@ -100,6 +125,13 @@ pub fn message_to_content_item(
         meta.insert("chat_username".to_string(), serde_json::json!(username));
     }
 
+    if let Some(grouped_id) = msg.grouped_id() {
+        meta.insert("grouped_id".to_string(), serde_json::json!(grouped_id));
+    }
+
+    // Counts as of the fetch; refreshed before generation (see tg_engagement)
+    tg_engagement::insert_counts(&mut meta, &tg_engagement::Counts::of(msg));
+
     let metadata = serde_json::to_string(&meta).unwrap_or_else(|_| "{}".to_string());
     let dedup_key = format!("tg:{chat_id}:{message_id}");
     let now = Utc::now();
@@ -121,10 +153,92 @@ pub fn message_to_content_item(
     })
 }
 
+/// Merge the parts of a media album (messages sharing a `grouped_id`, converted with
+/// `message_to_content_item`) into one item: the captions joined as body, every part's media
+/// type in `media_types` and downloaded photos in `media_ids`, and the first message's date
+/// and link. The dedup key is per album, so parts stored separately by an earlier fetch don't
+/// turn into a second copy. A part may itself be a merged album (the stored one, when more
+/// parts arrive later); parts whose messages are all in an earlier part are dropped.
+pub fn coalesce_album(parts: Vec<ContentItem>) -> Option<ContentItem> {
+    let mut parts: Vec<(serde_json::Map<String, serde_json::Value>, ContentItem)> = parts
+        .into_iter()
+        .map(|item| (serde_json::from_str(&item.metadata).unwrap_or_default(), item))
+        .collect();
+    parts.sort_by_key(|(meta, _)| meta.get("message_id").and_then(|v| v.as_i64()));
+    // A merged album lists its messages and their media in `message_ids` / `media_types`
+    let listed = |meta: &serde_json::Map<String, serde_json::Value>, list: &str, single: &str| match meta.get(list) {
+        Some(serde_json::Value::Array(values)) => values.clone(),
+        _ => meta.get(single).cloned().into_iter().collect(),
+    };
+    let mut seen = HashSet::new();
+    parts.retain(|(meta, _)| {
+        let ids: Vec<String> = listed(meta, "message_ids", "message_id")
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        let new = ids.iter().any(|id| !seen.contains(id));
+        seen.extend(ids);
+        new
+    });
+
+    let message_ids: Vec<serde_json::Value> = parts
+        .iter()
+        .flat_map(|(meta, _)| listed(meta, "message_ids", "message_id"))
+        .collect();
+    let media_types: Vec<serde_json::Value> = parts
+        .iter()
+        .flat_map(|(meta, _)| listed(meta, "media_types", "media_type"))
+        .collect();
+    // Downloaded photos of every part (see tg_media)
+    let media_ids: Vec<serde_json::Value> = parts
+        .iter()
+        .filter_map(|(meta, _)| meta.get("media_ids").and_then(|v| v.as_array()))
+        .flatten()
+        .cloned()
+        .collect();
+    // Telegram puts the caption on one part (usually the first); keep any others too
+    let body = parts
+        .iter()
+        .map(|(_, item)| item.body.trim())
+        .filter(|body| !body.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let is_forward = parts.iter().any(|(_, item)| item.content_type == "forward");
+    let author = parts.iter().find_map(|(_, item)| item.author.clone());
+    // Counts land on whichever part Telegram attributes them to; the album gets the highest
+    let counts = parts
+        .iter()
+        .map(|(meta, _)| tg_engagement::read_counts(meta))
+        .fold(tg_engagement::Counts::default(), tg_engagement::Counts::max);
+
+    let mut parts = parts.into_iter();
+    let (mut meta, first) = parts.next()?;
+    let chat_id = meta.get("chat_id").and_then(|v| v.as_i64())?;
+    let grouped_id = meta.get("grouped_id").and_then(|v| v.as_i64())?;
+
+    meta.insert("media_type".to_string(), serde_json::json!("album"));
+    meta.insert("media_types".to_string(), serde_json::Value::Array(media_types));
+    meta.insert("message_ids".to_string(), serde_json::Value::Array(message_ids));
+    tg_engagement::insert_counts(&mut meta, &counts);
+    if !media_ids.is_empty() {
+        meta.insert("media_ids".to_string(), serde_json::Value::Array(media_ids));
+    }
+
+    Some(ContentItem {
+        content_type: if is_forward { "forward" } else { "media" }.to_string(),
+        body,
+        author,
+        metadata: serde_json::to_string(&meta).unwrap_or_else(|_| "{}".to_string()),
+        dedup_key: format!("tg:{chat_id}:album:{grouped_id}"),
+        ..first
+    })
+}
+
 /// Fetch recent TG message history for all TG sources in a channel (CLI mode).
-/// Analogous to the RSS one-shot fetch block in pipeline.rs.
+/// Analogous to the RSS one-shot fetch block in pipeline.rs. Each source is read with the
+/// client of its `tg_account`; sources whose account isn't connected are skipped.
 pub async fn fetch_tg_sources(
-    client: &Client,
+    clients: &TgClients,
     pool: &SqlitePool,
     sources: &[Source],
     since: DateTime<Utc>,
@@ -139,6 +253,12 @@ pub async fn fetch_tg_sources(
             tokio::time::sleep(Duration::from_millis(500)).await;
         }
 
+        let account = source.tg_account();
+        let Some(client) = clients.get(account) else {
+            warn!(source = %source.name, account, "Telegram account not connected, skipping history fetch");
+            continue;
+        };
+
         match source.source_type.as_str() {
             "telegram_channel" | "telegram_group" => {
                 let tg_id = match source.tg_id {
@@ -149,7 +269,7 @@ pub async fn fetch_tg_sources(
                     }
                 };
                 let peer_username = source.tg_username.as_deref().map(|u| u.trim_start_matches('@'));
-                match fetch_channel_history(client, pool, &source.id, tg_id, peer_username, since).await {
+                match fetch_channel_history(client, pool, account, &source.id, tg_id, peer_username, since).await {
                     Ok(count) => info!(source = %source.name, items = count, "fetched TG history"),
                     Err(e) => warn!(source = %source.name, error = format!("{e:#}"), "failed to fetch TG history"),
                 }
@@ -177,6 +297,7 @@ pub async fn fetch_tg_sources(
                     match fetch_channel_history(
                         client,
                         pool,
+                        account,
                         &source.id,
                         *channel_tg_id,
                         channel_username.as_deref(),
@@ -202,13 +323,14 @@ pub async fn fetch_tg_sources(
     Ok(())
 }
 
-/// Resolve a bare tg_id to a PeerRef by looking up tg_peer_info.
+/// Resolve a bare tg_id to a PeerRef by looking up the account's tg_peer_info.
 /// Tries channel first (most common: channels + supergroups), then basic chat.
 /// Falls back to channel with access_hash 0 if the peer isn't cached.
-pub async fn resolve_peer_ref(pool: &SqlitePool, tg_id: i64) -> Result<PeerRef> {
+pub async fn resolve_peer_ref(pool: &SqlitePool, account: &str, tg_id: i64) -> Result<PeerRef> {
+    let lookup = namespaced(account, "SELECT hash FROM tg_peer_info WHERE peer_id = ?");
     // Try as channel/supergroup first (vast majority of cases)
     let channel_bot_api_id = PeerId::channel(tg_id).bot_api_dialog_id();
-    if let Some(hash) = sqlx::query_scalar::<_, Option<i64>>("SELECT hash FROM tg_peer_info WHERE peer_id = ?")
+    if let Some(hash) = sqlx::query_scalar::<_, Option<i64>>(&lookup)
         .bind(channel_bot_api_id)
         .fetch_optional(pool)
         .await
@@ -223,7 +345,7 @@ pub async fn resolve_peer_ref(pool: &SqlitePool, tg_id: i64) -> Result<PeerRef>
 
     // Try as basic group chat
     let chat_bot_api_id = PeerId::chat(tg_id).bot_api_dialog_id();
-    if let Some(row) = sqlx::query_as::<_, (Option<i64>,)>("SELECT hash FROM tg_peer_info WHERE peer_id = ?")
+    if let Some(row) = sqlx::query_as::<_, (Option<i64>,)>(&lookup)
         .bind(chat_bot_api_id)
         .fetch_optional(pool)
         .await
@@ -247,16 +369,21 @@ pub async fn resolve_peer_ref(pool: &SqlitePool, tg_id: i64) -> Result<PeerRef>
 async fn fetch_channel_history(
     client: &Client,
     pool: &SqlitePool,
+    account: &str,
     source_id: &str,
     tg_id: i64,
     peer_username: Option<&str>,
     since: DateTime<Utc>,
 ) -> Result<usize> {
-    let peer_ref = resolve_peer_ref(pool, tg_id).await?;
+    let peer_ref = resolve_peer_ref(pool, account, tg_id).await?;
 
     // No item limit — the time boundary (`since`) is the stop condition.
     let mut iter = client.iter_messages(peer_ref);
     let mut count = 0;
+    let mut batch = Vec::with_capacity(HISTORY_WRITE_BATCH);
+    // Album parts are consecutive in history; collected until a message of another group
+    let mut album: Vec<ContentItem> = Vec::new();
+    let mut album_group: Option<i64> = None;
 
     while let Some(msg) = iter.next().await.context("iterating TG message history")? {
         // Messages arrive newest-first; stop when we pass the time boundary
@@ -264,13 +391,236 @@ async fn fetch_channel_history(
             break;
         }
 
+        let grouped_id = msg.grouped_id();
+        if album_group != grouped_id && !album.is_empty() {
+            batch.extend(coalesce_album(std::mem::take(&mut album)));
+            count += 1;
+        }
+        album_group = grouped_id;
+
         if let Some(item) = message_to_content_item(&msg, source_id, peer_username) {
-            store::upsert_content_item(pool, &item)
+            if grouped_id.is_some() {
+                album.push(item);
+            } else {
+                batch.push(item);
+                count += 1;
+            }
+        }
+        if batch.len() >= HISTORY_WRITE_BATCH {
+            store::upsert_content_items(pool, &batch)
                 .await
-                .context("storing TG history item")?;
-            count += 1;
+                .context("storing TG history items")?;
+            batch.clear();
+        }
+    }
+    if !album.is_empty() {
+        batch.extend(coalesce_album(album));
+        count += 1;
+    }
+    store::upsert_content_items(pool, &batch)
+        .await
+        .context("storing TG history items")?;
+
+    Ok(count)
+}
+
+/// What `backfill_source` did.
+#[derive(Debug, Default)]
+pub struct BackfillSummary {
+    /// Chats fetched (one for a channel or group, each chat of a folder).
+    pub chats: usize,
+    /// Chats already backfilled to `since` by an earlier run.
+    pub skipped: usize,
+    pub items: usize,
+}
+
+/// Deep-fetch a Telegram source's history back to `since` (`pail tg backfill`). Unlike the
+/// regular history fetch, progress is saved per chat as it goes, so an interrupted backfill
+/// resumes below the oldest stored message and a finished one isn't repeated. Requests are
+/// paced, and FLOOD_WAIT errors are waited out instead of failing the run.
+pub async fn backfill_source(
+    client: &Client,
+    pool: &SqlitePool,
+    source: &Source,
+    since: DateTime<Utc>,
+    cancel: &CancellationToken,
+) -> Result<BackfillSummary> {
+    let chats: Vec<(i64, Option<String>)> = match source.source_type.as_str() {
+        "telegram_channel" | "telegram_group" => {
+            let tg_id = source
+                .tg_id
+                .ok_or_else(|| anyhow::anyhow!("source '{}' has no resolved tg_id", source.name))?;
+            let username = source
+                .tg_username
+                .as_deref()
+                .map(|u| u.trim_start_matches('@').to_string());
+            vec![(tg_id, username)]
+        }
+        "telegram_folder" => store::get_folder_channels_with_info(pool, &source.id)
+            .await
+            .with_context(|| format!("loading folder channels for source '{}'", source.name))?
+            .into_iter()
+            .map(|(tg_id, _name, username)| (tg_id, username))
+            .collect(),
+        other => anyhow::bail!(
+            "source '{}' is a {other} source; only Telegram channels, groups and folders read through an \
+             account can be backfilled",
+            source.name
+        ),
+    };
+
+    let mut summary = BackfillSummary::default();
+    for (tg_id, username) in chats {
+        if cancel.is_cancelled() {
+            break;
+        }
+        let progress = store::get_backfill_progress(pool, &source.id, tg_id).await?;
+        if progress
+            .as_ref()
+            .and_then(|p| p.covered_since)
+            .is_some_and(|covered| covered <= since)
+        {
+            debug!(source = %source.name, tg_id, "chat already backfilled");
+            summary.skipped += 1;
+            continue;
         }
+        let offset_id = progress.and_then(|p| p.oldest_message_id).unwrap_or(0);
+        let items = backfill_chat(
+            client,
+            pool,
+            source.tg_account(),
+            &source.id,
+            tg_id,
+            username.as_deref(),
+            since,
+            offset_id,
+            cancel,
+        )
+        .await
+        .with_context(|| format!("backfilling chat {tg_id} of source '{}'", source.name))?;
+        info!(source = %source.name, tg_id, items, resumed = offset_id != 0, "backfilled TG chat");
+        summary.chats += 1;
+        summary.items += items;
     }
+    Ok(summary)
+}
+
+/// Backfill one chat from below message `offset_id` (0: the newest) back to `since`. Returns
+/// the number of items stored. Progress is saved with every stored batch; the chat is marked
+/// complete once `since` or the start of the history is reached.
+#[allow(clippy::too_many_arguments)]
+async fn backfill_chat(
+    client: &Client,
+    pool: &SqlitePool,
+    account: &str,
+    source_id: &str,
+    tg_id: i64,
+    peer_username: Option<&str>,
+    since: DateTime<Utc>,
+    offset_id: i32,
+    cancel: &CancellationToken,
+) -> Result<usize> {
+    let peer_ref = resolve_peer_ref(pool, account, tg_id).await?;
+    let mut iter = client.iter_messages(peer_ref).offset_id(offset_id);
+    let mut count = 0;
+    let mut fetched = 0;
+    let mut last_seen = offset_id;
+    let mut complete = false;
+    let mut batch = Vec::with_capacity(HISTORY_WRITE_BATCH);
+    // Oldest message in `batch`: where a resumed run continues once the batch is stored
+    let mut batch_oldest: Option<i32> = None;
+    let mut album: Vec<ContentItem> = Vec::new();
+    let mut album_group: Option<i64> = None;
+    let mut album_oldest: Option<i32> = None;
+
+    loop {
+        if cancel.is_cancelled() {
+            break;
+        }
+        let msg = match iter.next().await {
+            Ok(Some(msg)) => msg,
+            // Start of the chat's history
+            Ok(None) => {
+                complete = true;
+                break;
+            }
+            Err(InvocationError::Rpc(ref rpc)) if rpc.name.starts_with("FLOOD") => {
+                let wait = Duration::from_secs(u64::from(rpc.value.unwrap_or(DEFAULT_FLOOD_WAIT_SECS)) + 1);
+                warn!(
+                    tg_id,
+                    wait_secs = wait.as_secs(),
+                    "Telegram flood wait, pausing backfill"
+                );
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = tokio::time::sleep(wait) => {}
+                }
+                // The iterator doesn't retry the failed request; continue below the last message seen
+                iter = client.iter_messages(peer_ref).offset_id(last_seen);
+                continue;
+            }
+            Err(e) => return Err(e).context("iterating TG message history"),
+        };
+        // Messages arrive newest-first; stop when we pass the time boundary
+        if msg.date() < since {
+            complete = true;
+            break;
+        }
+        last_seen = msg.id();
+        fetched += 1;
+        if fetched % BACKFILL_PAGE == 0 {
+            tokio::time::sleep(BACKFILL_PAGE_PAUSE).await;
+        }
+
+        let grouped_id = msg.grouped_id();
+        if album_group != grouped_id && !album.is_empty() {
+            batch.extend(coalesce_album(std::mem::take(&mut album)));
+            batch_oldest = album_oldest.take();
+            count += 1;
+        }
+        album_group = grouped_id;
 
+        if let Some(item) = message_to_content_item(&msg, source_id, peer_username) {
+            if grouped_id.is_some() {
+                album.push(item);
+                album_oldest = Some(msg.id());
+            } else {
+                batch.push(item);
+                batch_oldest = Some(msg.id());
+                count += 1;
+            }
+        }
+        if batch.len() >= HISTORY_WRITE_BATCH {
+            store_backfill_batch(pool, source_id, tg_id, &mut batch, batch_oldest.take()).await?;
+        }
+    }
+    // An album cut off by cancellation isn't stored; the next run fetches it whole
+    if complete && !album.is_empty() {
+        batch.extend(coalesce_album(album));
+        batch_oldest = album_oldest;
+        count += 1;
+    }
+    store_backfill_batch(pool, source_id, tg_id, &mut batch, batch_oldest).await?;
+    if complete {
+        store::complete_backfill(pool, source_id, tg_id, since).await?;
+    }
     Ok(count)
 }
+
+/// Store a backfill batch, then record its oldest message as the chat's progress.
+async fn store_backfill_batch(
+    pool: &SqlitePool,
+    source_id: &str,
+    tg_id: i64,
+    batch: &mut Vec<ContentItem>,
+    oldest: Option<i32>,
+) -> Result<()> {
+    store::upsert_content_items(pool, batch)
+        .await
+        .context("storing TG history items")?;
+    batch.clear();
+    if let Some(message_id) = oldest {
+        store::save_backfill_progress(pool, source_id, tg_id, message_id).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,533 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::header::{AUTHORIZATION, USER_AGENT};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::config::TwitterSourceConfig;
+use crate::error::FetchError;
+use crate::fetch::{self, FetchResult};
+use crate::http_client;
+use crate::models::{ContentItem, Source};
+
+const REQUEST_TIMEOUT_SECS: u64 = 30;
+
+/// X API host used when an `api` source has no `url`.
+const API_DEFAULT_URL: &str = "https://api.x.com";
+
+/// Canonical host for tweet links, whichever backend the tweet was read through.
+const CANONICAL_URL: &str = "https://x.com";
+
+/// A self-reply within this long of the previous tweet of a thread continues it. A thread is
+/// stored once its newest tweet is this old, so a thread still being posted isn't cut short.
+const THREAD_GAP_MINS: i64 = 30;
+
+/// Page size limits of `GET /2/users/:id/tweets`.
+const API_MIN_RESULTS: i32 = 5;
+const API_MAX_RESULTS: i32 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TweetKind {
+    Post,
+    /// Reply to the account's own tweet: part of a thread.
+    SelfReply,
+    /// Reply to another account.
+    Reply,
+    Retweet,
+}
+
+/// A tweet, normalized across backends.
+struct Tweet {
+    id: String,
+    /// Handle of the account that wrote it (the original author for retweets).
+    author: String,
+    text: String,
+    published: DateTime<Utc>,
+    kind: TweetKind,
+    /// Replied-to handle (`Reply` only).
+    in_reply_to: Option<String>,
+    /// ID of the tweet this one replies to (API only; Nitter RSS doesn't carry it).
+    parent_id: Option<String>,
+}
+
+/// Fetch an X account's recent tweets through Nitter RSS or the X API. Self-reply chains are
+/// joined into one item per thread; a thread is only stored once it has been quiet for
+/// `THREAD_GAP_MINS`, since stored items are immutable.
+pub async fn fetch_twitter_source(source: &Source) -> Result<FetchResult> {
+    let settings: TwitterSourceConfig = match source.twitter_config.as_deref() {
+        Some(json) => serde_json::from_str(json).map_err(|e| FetchError::Parse {
+            url: source.name.clone(),
+            message: format!("invalid stored twitter settings: {e}"),
+        })?,
+        None => {
+            return Err(FetchError::Parse {
+                url: source.name.clone(),
+                message: "twitter source has no [source.twitter] settings".to_string(),
+            }
+            .into());
+        }
+    };
+    let username = settings.username.trim_start_matches('@');
+
+    let tweets = match settings.backend.as_str() {
+        "api" => fetch_api_tweets(source, username, &settings).await?,
+        _ => fetch_nitter_tweets(source, username).await?,
+    };
+    let listed = tweets.len();
+    let tweets: Vec<Tweet> = tweets
+        .into_iter()
+        .filter(|t| match t.kind {
+            TweetKind::Reply => settings.include_replies,
+            TweetKind::Retweet => settings.include_retweets,
+            TweetKind::Post | TweetKind::SelfReply => true,
+        })
+        .collect();
+
+    let now = Utc::now();
+    let mut threads = group_threads(tweets);
+    let settled_before = now - chrono::Duration::minutes(THREAD_GAP_MINS);
+    let before = threads.len();
+    threads.retain(|thread| thread.last().is_some_and(|t| t.published <= settled_before));
+    let held = before - threads.len();
+    threads.sort_by_key(|thread| std::cmp::Reverse(thread[0].published));
+    threads.truncate(source.max_items as usize);
+
+    let items: Vec<ContentItem> = threads
+        .into_iter()
+        .map(|thread| build_item(source, username, thread, now))
+        .collect();
+    debug!(
+        source = %source.name,
+        backend = %settings.backend,
+        listed,
+        held,
+        items = items.len(),
+        "fetched tweets"
+    );
+
+    Ok(FetchResult {
+        items,
+        etag: None,
+        last_modified: None,
+        moved_to: None,
+    })
+}
+
+/// Join self-replies to the thread they continue, if its last tweet is at most
+/// `THREAD_GAP_MINS` older: the thread holding the replied-to tweet when its ID is known (a
+/// reply to a tweet outside the fetched page starts a thread), else the newest own thread.
+/// Everything else starts a thread of its own. Returns threads with tweets in posting order.
+fn group_threads(mut tweets: Vec<Tweet>) -> Vec<Vec<Tweet>> {
+    tweets.sort_by_key(|t| t.published);
+    let gap = chrono::Duration::minutes(THREAD_GAP_MINS);
+    let mut threads: Vec<Vec<Tweet>> = Vec::new();
+    for tweet in tweets {
+        if tweet.kind == TweetKind::SelfReply {
+            let open = match tweet.parent_id {
+                Some(ref parent) => threads.iter_mut().find(|thread| thread.iter().any(|t| &t.id == parent)),
+                None => threads
+                    .iter_mut()
+                    .rev()
+                    .find(|thread| matches!(thread[0].kind, TweetKind::Post | TweetKind::SelfReply)),
+            };
+            if let Some(thread) = open
+                && thread
+                    .last()
+                    .is_some_and(|last| tweet.published - last.published <= gap)
+            {
+                thread.push(tweet);
+                continue;
+            }
+        }
+        threads.push(vec![tweet]);
+    }
+    threads
+}
+
+/// One item per thread: tweets concatenated in order, linked to the first tweet on x.com.
+fn build_item(source: &Source, username: &str, thread: Vec<Tweet>, now: DateTime<Utc>) -> ContentItem {
+    let first = &thread[0];
+    let url = status_url(&first.author, &first.id);
+    let body = thread
+        .iter()
+        .map(|t| t.text.trim())
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let mut metadata = json!({ "tweet_count": thread.len() });
+    if thread.len() > 1 {
+        metadata["tweet_urls"] = json!(thread.iter().map(|t| status_url(&t.author, &t.id)).collect::<Vec<_>>());
+    }
+    match first.kind {
+        TweetKind::Retweet => metadata["retweet_of"] = json!(format!("@{}", first.author)),
+        TweetKind::Reply => {
+            if let Some(ref to) = first.in_reply_to {
+                metadata["in_reply_to"] = json!(format!("@{to}"));
+            }
+        }
+        _ => {}
+    }
+
+    ContentItem {
+        id: Uuid::new_v4().to_string(),
+        source_id: source.id.clone(),
+        ingested_at: now,
+        original_date: first.published,
+        content_type: "tweet".to_string(),
+        title: None,
+        body,
+        url: Some(url),
+        author: Some(format!("@{username}")),
+        metadata: metadata.to_string(),
+        dedup_key: format!("twitter:{}", first.id),
+        upstream_changed: false,
+    }
+}
+
+fn status_url(author: &str, id: &str) -> String {
+    format!("{CANONICAL_URL}/{author}/status/{id}")
+}
+
+/// Nitter: `GET <instance>/<username>/with_replies/rss` (the plain timeline leaves out the
+/// self-replies that make up threads). Nitter marks replies as `R to @handle: ...` and
+/// retweets as `RT by @handle: ...` in the item title.
+async fn fetch_nitter_tweets(source: &Source, username: &str) -> Result<Vec<Tweet>> {
+    let base = source
+        .url
+        .as_deref()
+        .ok_or_else(|| FetchError::Parse {
+            url: source.name.clone(),
+            message: "twitter source has no Nitter instance URL".to_string(),
+        })?
+        .trim_end_matches('/');
+    let url = format!("{base}/{username}/with_replies/rss");
+
+    // No conditional GET: held threads settle while the feed stays the same
+    let mut unconditional = source.clone();
+    unconditional.last_etag = None;
+    unconditional.last_modified_header = None;
+    let Some(feed) = fetch::fetch_feed(&unconditional, &url).await?.feed else {
+        return Ok(Vec::new());
+    };
+
+    let now = Utc::now();
+    Ok(feed
+        .entries
+        .into_iter()
+        .filter_map(|entry| {
+            let link = entry.links.first().map(|l| l.href.clone())?;
+            let (author, id) = parse_status_link(&link)?;
+            let title = entry.title.map(|t| t.content).unwrap_or_default();
+            let (kind, in_reply_to) = if title.starts_with("RT by @") {
+                (TweetKind::Retweet, None)
+            } else if let Some(rest) = title.strip_prefix("R to @") {
+                let to = rest.split(':').next().unwrap_or_default().to_string();
+                if to.eq_ignore_ascii_case(username) {
+                    (TweetKind::SelfReply, None)
+                } else {
+                    (TweetKind::Reply, Some(to))
+                }
+            } else {
+                (TweetKind::Post, None)
+            };
+            let html = entry.summary.map(|s| s.content).unwrap_or_default();
+            let text = match fetch::strip_html(&html).trim() {
+                "" if kind == TweetKind::Post => title.clone(),
+                "" => title.split_once(": ").map_or(title.clone(), |(_, t)| t.to_string()),
+                text => text.to_string(),
+            };
+            Some(Tweet {
+                id,
+                author,
+                text: rewrite_nitter_links(&text, base),
+                published: entry.published.or(entry.updated).unwrap_or(now),
+                kind,
+                in_reply_to,
+                parent_id: None,
+            })
+        })
+        .collect())
+}
+
+/// `(author, id)` from a status link, `https://<host>/<author>/status/<id>#m`.
+fn parse_status_link(link: &str) -> Option<(String, String)> {
+    let path = reqwest::Url::parse(link).ok()?;
+    let mut segments = path.path_segments()?;
+    let author = segments.next()?.to_string();
+    if segments.next()? != "status" {
+        return None;
+    }
+    let id = segments.next()?.to_string();
+    (!author.is_empty() && !id.is_empty() && id.chars().all(|c| c.is_ascii_digit())).then_some((author, id))
+}
+
+/// Point profile, hashtag and status links in Nitter-rendered text at x.com. Media links stay on
+/// the instance (`/pic/...`), which proxies them.
+fn rewrite_nitter_links(text: &str, base: &str) -> String {
+    let pic = format!("{base}/pic/");
+    text.replace(&pic, "\u{0}")
+        .replace(&format!("{base}/"), &format!("{CANONICAL_URL}/"))
+        .replace('\u{0}', &pic)
+}
+
+#[derive(Deserialize)]
+struct ApiUserLookup {
+    data: ApiUser,
+}
+
+#[derive(Deserialize)]
+struct ApiUser {
+    id: String,
+    username: String,
+}
+
+#[derive(Deserialize)]
+struct ApiTimeline {
+    #[serde(default)]
+    data: Vec<ApiTweet>,
+    #[serde(default)]
+    includes: ApiIncludes,
+}
+
+#[derive(Deserialize, Default)]
+struct ApiIncludes {
+    #[serde(default)]
+    tweets: Vec<ApiTweet>,
+    #[serde(default)]
+    users: Vec<ApiUser>,
+}
+
+#[derive(Deserialize)]
+struct ApiTweet {
+    id: String,
+    text: String,
+    created_at: Option<DateTime<Utc>>,
+    author_id: Option<String>,
+    in_reply_to_user_id: Option<String>,
+    #[serde(default)]
+    referenced_tweets: Vec<ApiReference>,
+    /// Full text of tweets longer than 280 characters (`text` is truncated).
+    note_tweet: Option<ApiNoteTweet>,
+    entities: Option<ApiEntities>,
+}
+
+#[derive(Deserialize)]
+struct ApiReference {
+    #[serde(rename = "type")]
+    kind: String,
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct ApiNoteTweet {
+    text: String,
+    entities: Option<ApiEntities>,
+}
+
+#[derive(Deserialize)]
+struct ApiEntities {
+    #[serde(default)]
+    urls: Vec<ApiUrl>,
+}
+
+#[derive(Deserialize)]
+struct ApiUrl {
+    url: String,
+    expanded_url: Option<String>,
+}
+
+impl ApiTweet {
+    /// Full text with `t.co` links expanded.
+    fn full_text(&self) -> String {
+        let (mut text, entities) = match self.note_tweet {
+            Some(ref note) => (note.text.clone(), note.entities.as_ref()),
+            None => (self.text.clone(), self.entities.as_ref()),
+        };
+        for link in entities.map(|e| e.urls.as_slice()).unwrap_or_default() {
+            if let Some(ref expanded) = link.expanded_url {
+                text = text.replace(&link.url, expanded);
+            }
+        }
+        text
+    }
+
+    fn references(&self, kind: &str) -> Option<&str> {
+        self.referenced_tweets
+            .iter()
+            .find(|r| r.kind == kind)
+            .map(|r| r.id.as_str())
+    }
+}
+
+/// X API v2: look up the account, then `GET /2/users/:id/tweets` with replies (for threads)
+/// and the referenced tweets expanded (for retweet text).
+async fn fetch_api_tweets(source: &Source, username: &str, settings: &TwitterSourceConfig) -> Result<Vec<Tweet>> {
+    let base = source.url.as_deref().unwrap_or(API_DEFAULT_URL).trim_end_matches('/');
+    let token = source.auth_token.as_deref().ok_or_else(|| FetchError::Parse {
+        url: source.name.clone(),
+        message: "twitter API source has no bearer token".to_string(),
+    })?;
+    let builder = reqwest::Client::builder().timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS));
+    let client = http_client::with_proxy(builder, source.proxy.as_deref())
+        .and_then(|builder| builder.build())
+        .context("building HTTP client")?;
+
+    let user: ApiUserLookup = get_json(&client, token, &format!("{base}/2/users/by/username/{username}"), &[]).await?;
+    let mut query = vec![
+        (
+            "max_results",
+            source.max_items.clamp(API_MIN_RESULTS, API_MAX_RESULTS).to_string(),
+        ),
+        (
+            "tweet.fields",
+            "created_at,author_id,in_reply_to_user_id,referenced_tweets,note_tweet,entities".to_string(),
+        ),
+        (
+            "expansions",
+            "referenced_tweets.id,referenced_tweets.id.author_id".to_string(),
+        ),
+        ("user.fields", "username".to_string()),
+    ];
+    if !settings.include_retweets {
+        query.push(("exclude", "retweets".to_string()));
+    }
+    let timeline: ApiTimeline = get_json(
+        &client,
+        token,
+        &format!("{base}/2/users/{}/tweets", user.data.id),
+        &query,
+    )
+    .await?;
+
+    let handle = |id: Option<&str>| {
+        timeline
+            .includes
+            .users
+            .iter()
+            .find(|u| Some(u.id.as_str()) == id)
+            .map(|u| u.username.clone())
+    };
+    let now = Utc::now();
+    Ok(timeline
+        .data
+        .iter()
+        .map(|tweet| {
+            let retweeted = tweet
+                .references("retweeted")
+                .and_then(|id| timeline.includes.tweets.iter().find(|t| t.id == id));
+            let (kind, in_reply_to) = match (retweeted, tweet.in_reply_to_user_id.as_deref()) {
+                (Some(_), _) => (TweetKind::Retweet, None),
+                (None, Some(to)) if to == user.data.id => (TweetKind::SelfReply, None),
+                (None, Some(to)) => (TweetKind::Reply, handle(Some(to))),
+                (None, None) => (TweetKind::Post, None),
+            };
+            // Retweets are stored with the original tweet's author, text and link
+            let shown = retweeted.unwrap_or(tweet);
+            Tweet {
+                id: shown.id.clone(),
+                author: match retweeted {
+                    Some(original) => handle(original.author_id.as_deref()).unwrap_or_else(|| "i".to_string()),
+                    None => user.data.username.clone(),
+                },
+                text: shown.full_text(),
+                published: tweet.created_at.unwrap_or(now),
+                kind,
+                in_reply_to,
+                parent_id: tweet.references("replied_to").map(str::to_string),
+            }
+        })
+        .collect())
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(
+    client: &reqwest::Client,
+    token: &str,
+    url: &str,
+    query: &[(&str, String)],
+) -> Result<T> {
+    let response = client
+        .get(url)
+        .header(USER_AGENT, concat!("pail/", env!("CARGO_PKG_VERSION")))
+        .header(AUTHORIZATION, format!("Bearer {token}"))
+        .query(query)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| FetchError::Http {
+            url: url.to_string(),
+            source: e,
+        })?;
+    response
+        .json()
+        .await
+        .with_context(|| format!("parsing response from {url}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tweet(id: &str, minute: i64, kind: TweetKind, parent_id: Option<&str>) -> Tweet {
+        Tweet {
+            id: id.to_string(),
+            author: "me".to_string(),
+            text: format!("tweet {id}"),
+            published: DateTime::from_timestamp(1_750_000_000, 0).unwrap() + chrono::Duration::minutes(minute),
+            kind,
+            in_reply_to: None,
+            parent_id: parent_id.map(str::to_string),
+        }
+    }
+
+    fn ids(threads: &[Vec<Tweet>]) -> Vec<Vec<&str>> {
+        threads
+            .iter()
+            .map(|thread| thread.iter().map(|t| t.id.as_str()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_group_threads_by_replied_to_tweet() {
+        let threads = group_threads(vec![
+            tweet("3", 10, TweetKind::SelfReply, Some("2")),
+            tweet("1", 0, TweetKind::Post, None),
+            tweet("2", 5, TweetKind::SelfReply, Some("1")),
+            tweet("4", 12, TweetKind::Post, None),
+            tweet("5", 15, TweetKind::SelfReply, Some("3")),
+        ]);
+        assert_eq!(ids(&threads), vec![vec!["1", "2", "3", "5"], vec!["4"]]);
+    }
+
+    #[test]
+    fn test_group_threads_root_outside_window() {
+        // "0" was posted before the page: its replies don't join the unrelated post "1"
+        let threads = group_threads(vec![
+            tweet("1", 0, TweetKind::Post, None),
+            tweet("2", 5, TweetKind::SelfReply, Some("0")),
+            tweet("3", 8, TweetKind::SelfReply, Some("2")),
+            tweet("4", 10, TweetKind::SelfReply, Some("0")),
+        ]);
+        assert_eq!(ids(&threads), vec![vec!["1"], vec!["2", "3"], vec!["4"]]);
+    }
+
+    #[test]
+    fn test_group_threads_gap_and_unknown_parent() {
+        let threads = group_threads(vec![
+            tweet("1", 0, TweetKind::Post, None),
+            tweet("2", 20, TweetKind::SelfReply, None),
+            tweet("3", 25, TweetKind::Reply, None),
+            tweet("4", 30, TweetKind::SelfReply, None),
+            tweet("5", 100, TweetKind::SelfReply, None),
+            tweet("6", 101, TweetKind::SelfReply, Some("1")),
+        ]);
+        assert_eq!(
+            ids(&threads),
+            vec![vec!["1", "2", "4"], vec!["3"], vec!["5"], vec!["6"]]
+        );
+    }
+}
@@ -1,20 +1,29 @@
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use gray_matter::Matter;
 use gray_matter::engine::YAML;
+use sha2::{Digest, Sha256};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use tokio::io::AsyncReadExt;
-
-use crate::config::{Config, OutputChannelConfig};
+use crate::budget::{self, Budget, SessionUsage};
+use crate::config::{Config, OpencodeConfig, OutputChannelConfig, PersonaConfig, PostprocessConfig};
 use crate::error::GenerationError;
-use crate::models::{ContentItem, GeneratedArticle, OutputChannel, Source};
+use crate::live_run::{self, LiveSink};
+use crate::models::{
+    ArticleFeedback, ContentItem, FetchedPage, GeneratedArticle, GeneratedArticleRow, OutputChannel, SkippedItem, Source,
+};
+use crate::output_format;
+use crate::packing::{self, PackingReport};
+use crate::sandbox;
 use crate::strategy::{self, Strategy};
+use crate::tg_media;
 
 /// Key for grouping content items in the workspace.
 /// Non-folder sources group by source_id; folder sources split into per-channel groups.
@@ -36,7 +45,7 @@ struct SourceFileInfo {
 pub struct PreparedWorkspace {
     /// The temporary directory. Dropped when this struct is dropped (auto-cleanup).
     pub dir: tempfile::TempDir,
-    /// Resolved model string (from channel config, global config, or default).
+    /// Resolved model string (the first of the channel's model chain).
     pub model: String,
 }
 
@@ -46,7 +55,25 @@ impl PreparedWorkspace {
     }
 }
 
-/// Prepare a workspace directory with manifest.json, sources/, opencode.json, and tools.
+/// What a generation's model invocations left behind, for its `generation_runs` row.
+#[derive(Debug, Default)]
+pub struct RunTrace {
+    /// Invocations so far, retries included.
+    pub attempts: u32,
+    /// Of the last attempt: opencode's exit code and log (`None` if opencode didn't finish),
+    /// and where its workspace was kept.
+    pub exit_code: Option<i32>,
+    pub log: Option<String>,
+    pub workspace: Option<String>,
+    /// Model of the last attempt.
+    pub model: Option<String>,
+    /// Usage summed over the attempts opencode reported it for.
+    pub usage: Option<SessionUsage>,
+    /// Where to mirror opencode's output while it runs, for `GET /runs/{id}/live`.
+    pub live: Option<LiveSink>,
+}
+
+/// Prepare a workspace directory with manifest.json, sources/, pages/ (if any), opencode.json, and tools.
 /// Does NOT write prompt.md or output.md — those are mode-specific.
 #[allow(clippy::too_many_arguments)]
 pub async fn prepare_workspace(
@@ -57,6 +84,7 @@ pub async fn prepare_workspace(
     items: &[ContentItem],
     source_map: &HashMap<String, &Source>,
     folder_channels: &HashMap<String, HashMap<i64, (String, Option<String>)>>,
+    pages: &HashMap<String, FetchedPage>,
     covers_from: DateTime<Utc>,
     covers_to: DateTime<Utc>,
 ) -> Result<PreparedWorkspace> {
@@ -68,6 +96,31 @@ pub async fn prepare_workspace(
     let ws_path = workspace.path();
     info!(workspace = %ws_path.display(), strategy = %strategy.meta.name, "preparing workspace");
 
+    let (items, packing) = match channel_config.input_token_budget {
+        Some(budget) => {
+            let fixed_tokens: Vec<u64> = items
+                .iter()
+                .map(|item| {
+                    packing::estimate_tokens(&format_content_item(item, None))
+                        .saturating_sub(packing::estimate_tokens(&item.body))
+                })
+                .collect();
+            let (packed, report) = packing::pack_items(items, &fixed_tokens, budget);
+            if !report.truncated.is_empty() {
+                info!(
+                    budget,
+                    estimated_tokens = report.estimated_tokens,
+                    packed_tokens = report.packed_tokens,
+                    truncated = report.truncated.len(),
+                    "items truncated to fit the input token budget"
+                );
+            }
+            (Cow::Owned(packed), Some(report))
+        }
+        None => (Cow::Borrowed(items), None),
+    };
+    let items: &[ContentItem] = &items;
+
     let keys: Vec<SourceKey> = items
         .iter()
         .map(|item| item_source_key(item, source_map))
@@ -85,13 +138,23 @@ pub async fn prepare_workspace(
         covers_from,
         covers_to,
         &config.pail.timezone,
+        packing.as_ref(),
     )
     .await
     .context("writing manifest")?;
 
-    write_source_content(ws_path, items, source_map, &file_infos)
-        .await
-        .context("writing source content")?;
+    write_source_content(
+        ws_path,
+        items,
+        source_map,
+        &file_infos,
+        pages,
+        channel_config.item_files,
+    )
+    .await
+    .context("writing source content")?;
+
+    write_pages(ws_path, pages).await.context("writing prefetched pages")?;
 
     write_opencode_config(ws_path, merged_opencode_config)
         .await
@@ -101,19 +164,15 @@ pub async fn prepare_workspace(
         .await
         .context("writing strategy tools")?;
 
-    let model = channel_config
-        .model
-        .as_deref()
-        .or(config.opencode.default_model.as_deref())
-        .unwrap_or("opencode/big-pickle")
-        .to_string();
+    // The first of the chain; generate_article sets the attempt's model
+    let model = channel_config.model_chain(&config.opencode).swap_remove(0);
 
     Ok(PreparedWorkspace { dir: workspace, model })
 }
 
 /// Write an `AGENTS.md` file to the workspace with workspace context (for interactive mode).
-pub async fn write_agents_md(ws_path: &Path, strategy: &Strategy) -> Result<()> {
-    let content = strategy::workspace_context(strategy, false);
+pub async fn write_agents_md(ws_path: &Path, strategy: &Strategy, item_files: bool) -> Result<()> {
+    let content = strategy::workspace_context(strategy, false, ws_path.join("pages").exists(), item_files);
     tokio::fs::write(ws_path.join("AGENTS.md"), &content)
         .await
         .map_err(GenerationError::Workspace)?;
@@ -132,7 +191,7 @@ pub async fn write_opencode_config(ws_path: &Path, project_config: &serde_json::
 }
 
 /// Write strategy tools to `.opencode/tools/` and merged `package.json` to `.opencode/`.
-async fn write_strategy_tools(ws_path: &Path, strategy: &Strategy) -> Result<()> {
+pub(crate) async fn write_strategy_tools(ws_path: &Path, strategy: &Strategy) -> Result<()> {
     let resolved = strategy::resolve_tools(strategy)?;
 
     if resolved.tool_files.is_empty() {
@@ -284,11 +343,21 @@ pub async fn generate_article(
     items: &[ContentItem],
     source_map: &HashMap<String, &Source>,
     folder_channels: &HashMap<String, HashMap<i64, (String, Option<String>)>>,
+    pages: &HashMap<String, FetchedPage>,
+    feedback: &[ArticleFeedback],
+    previous: Option<&GeneratedArticleRow>,
     covers_from: DateTime<Utc>,
     covers_to: DateTime<Utc>,
     cancel: CancellationToken,
+    model: &str,
+    trace: &mut RunTrace,
 ) -> Result<(GeneratedArticle, String)> {
-    let ws = prepare_workspace(
+    trace.attempts += 1;
+    trace.exit_code = None;
+    trace.log = None;
+    trace.workspace = None;
+    trace.model = Some(model.to_string());
+    let mut ws = prepare_workspace(
         config,
         channel_config,
         strategy,
@@ -296,97 +365,197 @@ pub async fn generate_article(
         items,
         source_map,
         folder_channels,
+        pages,
         covers_from,
         covers_to,
     )
     .await
     .context("preparing workspace")?;
+    ws.model = model.to_string();
 
-    let ws_path = ws.path();
+    let result: Result<(GeneratedArticle, String)> = async {
+        let ws_path = ws.path();
 
-    let prompt = write_prompt(ws_path, strategy, channel_config)
-        .await
-        .context("writing prompt")?;
+        let prompt = write_prompt(ws_path, strategy, config, channel_config, feedback, previous)
+            .await
+            .context("writing prompt")?;
 
-    // Create empty output.md
-    tokio::fs::write(ws_path.join("output.md"), "")
+        // Create empty output.md
+        tokio::fs::write(ws_path.join("output.md"), "")
+            .await
+            .map_err(GenerationError::Workspace)?;
+
+        // Invoke opencode
+        let (generation_log, exit_code, usage) = invoke_opencode(
+            &config.opencode,
+            ws_path,
+            &ws.model,
+            &prompt,
+            channel_config.timeout.as_deref().unwrap_or(&strategy.meta.timeout),
+            Budget::for_channel(&config.opencode, channel_config),
+            trace.live.as_ref(),
+            cancel,
+        )
         .await
-        .map_err(GenerationError::Workspace)?;
+        .context("invoking opencode")?;
+        trace.exit_code = exit_code;
+        trace.log = Some(generation_log.clone());
+        if let Some(usage) = usage {
+            let total = trace.usage.get_or_insert_default();
+            total.cost_usd += usage.cost_usd;
+            total.tokens += usage.tokens;
+        }
 
-    // Invoke opencode
-    let (generation_log, exit_code) = invoke_opencode(
-        &config.opencode.binary,
-        ws_path,
-        &ws.model,
-        &prompt,
-        &strategy.meta.timeout,
-        cancel,
-    )
-    .await
-    .context("invoking opencode")?;
+        if exit_code != Some(0) {
+            warn!(
+                exit_code = ?exit_code,
+                "opencode exited with non-zero code, checking output anyway"
+            );
+        }
 
-    if exit_code != Some(0) {
-        warn!(
-            exit_code = ?exit_code,
-            "opencode exited with non-zero code, checking output anyway"
-        );
-    }
+        // Parse output
+        let output_path = ws_path.join("output.md");
+        let output_content = tokio::fs::read_to_string(&output_path)
+            .await
+            .map_err(GenerationError::Workspace)?;
 
-    // Parse output
-    let output_path = ws_path.join("output.md");
-    let output_content = tokio::fs::read_to_string(&output_path)
-        .await
-        .map_err(GenerationError::Workspace)?;
+        if output_content.trim().is_empty() {
+            // @NOTE: warn (not error) so Sentry captures this as a breadcrumb, not a
+            // separate event.  The actual error propagates up to the scheduler which
+            // logs the single Sentry event with the full chain.
+            warn!(
+                generation_log = %generation_log,
+                "output.md is empty — opencode log above may indicate the cause"
+            );
+            return Err(GenerationError::OutputParse("output.md is empty".to_string()).into());
+        }
 
-    if output_content.trim().is_empty() {
-        // @NOTE: warn (not error) so Sentry captures this as a breadcrumb, not a
-        // separate event.  The actual error propagates up to the scheduler which
-        // logs the single Sentry event with the full chain.
-        warn!(
-            generation_log = %generation_log,
-            "output.md is empty — opencode log above may indicate the cause"
+        let ParsedOutput {
+            title,
+            topics,
+            summary,
+            body: mut body_markdown,
+            mut skipped,
+        } = parse_output(&output_content, &channel_config.sections).context("parsing output")?;
+        if let Some(ref format) = channel_config.format {
+            output_format::check(format, &body_markdown).map_err(GenerationError::OutputParse)?;
+        }
+        resolve_skipped(&mut skipped, items, source_map);
+        let summary = summary.or_else(|| lede(&body_markdown));
+        let tz: chrono_tz::Tz = config.pail.timezone.parse().unwrap_or(chrono_tz::UTC);
+        let (title, topics) = postprocess(
+            &channel_config.postprocess,
+            &channel_config.name,
+            Utc::now().with_timezone(&tz).date_naive(),
+            title,
+            topics,
         );
-        return Err(GenerationError::OutputParse("output.md is empty".to_string()).into());
-    }
 
-    let (title, topics, mut body_markdown) = parse_output(&output_content).context("parsing output")?;
+        // Append opencode session share link if present in generation log
+        let share_suffix =
+            extract_share_url(&generation_log).map(|url| format!("\n\n---\n\n[opencode session]({url})\n"));
+        if let Some(ref suffix) = share_suffix {
+            body_markdown.push_str(suffix);
+        }
 
-    // Append opencode session share link if present in generation log
-    let share_suffix = extract_share_url(&generation_log).map(|url| format!("\n\n---\n\n[opencode session]({url})\n"));
-    if let Some(ref suffix) = share_suffix {
-        body_markdown.push_str(suffix);
-    }
+        // Convert markdown to HTML
+        let body_html = markdown_to_html(&body_markdown);
 
-    // Convert markdown to HTML
-    let body_html = markdown_to_html(&body_markdown);
+        // Also append to raw output so --output file includes the link
+        let mut output_content = output_content;
+        if let Some(ref suffix) = share_suffix {
+            output_content.push_str(suffix);
+        }
 
-    // Also append to raw output so --output file includes the link
-    let mut output_content = output_content;
-    if let Some(ref suffix) = share_suffix {
-        output_content.push_str(suffix);
-    }
+        let content_item_ids: Vec<String> = items.iter().map(|i| i.id.clone()).collect();
+        let cited_item_ids = cited_items(&body_markdown, items, &skipped);
+
+        let article = GeneratedArticle {
+            id: Uuid::new_v4().to_string(),
+            output_channel_id: channel.id.clone(),
+            generated_at: Utc::now(),
+            covers_from,
+            covers_to,
+            title,
+            topics,
+            body_html,
+            body_markdown,
+            content_item_ids,
+            generation_log,
+            model_used: ws.model.clone(),
+            token_count: usage.map(|u| u.tokens as i64),
+            strategy_used: strategy.meta.name.clone(),
+            status: "published".to_string(),
+            skipped,
+            cited_item_ids,
+            eval_score: None,
+            eval_details: None,
+            metadata: "{}".to_string(),
+            summary,
+        };
 
-    let content_item_ids: Vec<String> = items.iter().map(|i| i.id.clone()).collect();
+        Ok((article, output_content))
+    }
+    .await;
 
-    let article = GeneratedArticle {
-        id: Uuid::new_v4().to_string(),
-        output_channel_id: channel.id.clone(),
-        generated_at: Utc::now(),
-        covers_from,
-        covers_to,
-        title,
-        topics,
-        body_html,
-        body_markdown,
-        content_item_ids,
-        generation_log,
-        model_used: ws.model.clone(),
-        token_count: None,
-        strategy_used: strategy.meta.name.clone(),
+    let keep = match config.opencode.keep_workspace.as_str() {
+        "always" => true,
+        "on_failure" => result.is_err(),
+        _ => false,
     };
+    if keep {
+        let id = match result {
+            Ok((ref article, _)) => article.id.clone(),
+            Err(_) => format!("{}-{}", channel_config.slug, Utc::now().format("%Y%m%dT%H%M%SZ")),
+        };
+        let dest = config.pail.data_dir.join("workspaces").join(id);
+        match keep_workspace(ws, &dest).await {
+            Ok(()) => {
+                info!(workspace = %dest.display(), failed = result.is_err(), "kept generation workspace");
+                trace.workspace = Some(dest.display().to_string());
+            }
+            Err(e) => warn!(workspace = %dest.display(), "failed to keep generation workspace: {e:#}"),
+        }
+    }
+    // Otherwise the workspace is cleaned up when `ws` is dropped
 
-    // Workspace is cleaned up when `ws` is dropped
-    Ok((article, output_content))
+    result
+}
+
+/// Move a workspace out of its tempdir to `dest` (`[opencode].keep_workspace`). Falls back to
+/// copying when the tempdir is on another filesystem.
+async fn keep_workspace(ws: PreparedWorkspace, dest: &Path) -> Result<()> {
+    let src = ws.dir.keep();
+    let dest = dest.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+        }
+        if std::fs::rename(&src, &dest).is_ok() {
+            return Ok(());
+        }
+        copy_dir_recursive(&src, &dest)?;
+        std::fs::remove_dir_all(&src).with_context(|| format!("removing {}", src.display()))
+    })
+    .await
+    .context("keeping workspace")?
+}
+
+/// Recursively copy a directory tree.
+pub(crate) fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
+    std::fs::create_dir_all(dst).with_context(|| format!("creating {}", dst.display()))?;
+    for entry in std::fs::read_dir(src).with_context(|| format!("reading {}", src.display()))? {
+        let entry = entry?;
+        let src_path = entry.path();
+        let dst_path = dst.join(entry.file_name());
+        if src_path.is_dir() {
+            copy_dir_recursive(&src_path, &dst_path)?;
+        } else {
+            std::fs::copy(&src_path, &dst_path)
+                .with_context(|| format!("copying {} -> {}", src_path.display(), dst_path.display()))?;
+        }
+    }
+    Ok(())
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -399,6 +568,7 @@ async fn write_manifest(
     covers_from: DateTime<Utc>,
     covers_to: DateTime<Utc>,
     timezone: &str,
+    packing: Option<&PackingReport>,
 ) -> Result<()> {
     // Count items per source key
     let mut key_item_counts: HashMap<SourceKey, usize> = HashMap::new();
@@ -414,20 +584,25 @@ async fn write_manifest(
     let sources_json: Vec<serde_json::Value> = sorted_infos
         .into_iter()
         .map(|(key, info)| {
-            serde_json::json!({
+            let mut entry = serde_json::json!({
                 "slug": info.slug,
                 "name": info.name,
                 "type": info.source_type,
                 "item_count": key_item_counts.get(key).unwrap_or(&0),
-            })
+            });
+            if !info.description.is_empty() {
+                entry["description"] = serde_json::json!(info.description);
+            }
+            entry
         })
         .collect();
 
-    let manifest = serde_json::json!({
+    let mut manifest = serde_json::json!({
         "channel": {
             "name": channel_config.name,
             "slug": channel_config.slug,
             "language": channel_config.language.as_deref().unwrap_or("en"),
+            "sections": channel_config.sections,
         },
         "window": {
             "from": covers_from.to_rfc3339(),
@@ -436,6 +611,9 @@ async fn write_manifest(
         "timezone": timezone,
         "sources": sources_json,
     });
+    if let Some(report) = packing {
+        manifest["packing"] = serde_json::to_value(report).context("serializing packing report")?;
+    }
 
     let manifest_str = serde_json::to_string_pretty(&manifest).context("serializing manifest")?;
 
@@ -450,14 +628,52 @@ async fn write_manifest(
 pub(crate) async fn write_prompt(
     ws_path: &Path,
     strategy: &Strategy,
+    config: &Config,
     channel_config: &OutputChannelConfig,
+    feedback: &[ArticleFeedback],
+    previous: Option<&GeneratedArticleRow>,
 ) -> Result<String> {
-    let rendered = strategy
-        .prompt_body
-        .replace("{editorial_directive}", channel_config.prompt.trim());
+    let mut directive = render_editorial_directive(ws_path, config, channel_config)
+        .await
+        .context("rendering editorial directive")?
+        .trim()
+        .to_string();
+    if let Some(persona) = channel_config
+        .persona
+        .as_ref()
+        .and_then(|name| config.persona.get(name))
+    {
+        directive.push_str("\n\n");
+        directive.push_str(&persona_block(persona));
+    }
+    if let Some(ref format) = channel_config.format {
+        directive.push_str("\n\n");
+        directive.push_str(&output_format::prompt_block(format));
+    }
+    if !channel_config.sections.is_empty() {
+        directive.push_str("\n\n");
+        directive.push_str(&sections_block(&channel_config.sections, previous.is_some()));
+    }
+    if !feedback.is_empty() {
+        directive.push_str("\n\n");
+        directive.push_str(&feedback_section(feedback, &config.pail.timezone));
+    }
+    if let Some(previous) = previous {
+        tokio::fs::write(ws_path.join("previous.md"), previous_article_file(previous))
+            .await
+            .map_err(GenerationError::Workspace)?;
+        directive.push_str("\n\n");
+        directive.push_str(&changelog_block(previous, &config.pail.timezone));
+    }
+    let rendered = strategy.prompt_body.replace("{editorial_directive}", &directive);
 
     // Prepend the workspace context (with output.md bullet) so it's defined in code once
-    let prompt = format!("{}{}", strategy::workspace_context(strategy, true), rendered);
+    let has_pages = ws_path.join("pages").exists();
+    let prompt = format!(
+        "{}{}",
+        strategy::workspace_context(strategy, true, has_pages, channel_config.item_files),
+        rendered
+    );
 
     // Write to workspace for debugging/inspection only
     tokio::fs::write(ws_path.join("prompt.md"), &prompt)
@@ -468,11 +684,154 @@ pub(crate) async fn write_prompt(
     Ok(prompt)
 }
 
+/// Resolve the editorial directive for a channel. Without a template this is the inline
+/// `prompt`. With a template (channel `prompt_template`, else `[pail].prompt_template`), the
+/// file is re-read and rendered with minijinja on every generation, so edits apply without
+/// a restart. Template variables come from `manifest.json` (see docs/specs/generation-engine.md
+/// "Prompt Templates").
+async fn render_editorial_directive(
+    ws_path: &Path,
+    config: &Config,
+    channel_config: &OutputChannelConfig,
+) -> Result<String> {
+    let Some(template_path) = channel_config
+        .prompt_template
+        .as_deref()
+        .or(config.pail.prompt_template.as_deref())
+    else {
+        return Ok(channel_config.prompt.clone());
+    };
+
+    let template = tokio::fs::read_to_string(template_path)
+        .await
+        .with_context(|| format!("reading prompt template {}", template_path.display()))?;
+
+    let manifest_str = tokio::fs::read_to_string(ws_path.join("manifest.json"))
+        .await
+        .map_err(GenerationError::Workspace)?;
+    let manifest: serde_json::Value = serde_json::from_str(&manifest_str).context("parsing manifest")?;
+
+    // Local-time window strings, so templates don't need date math
+    let tz: chrono_tz::Tz = config.pail.timezone.parse().unwrap_or(chrono_tz::UTC);
+    let local = |key: &str| {
+        manifest["window"][key]
+            .as_str()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|dt| dt.with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string())
+    };
+    let item_count: u64 = manifest["sources"]
+        .as_array()
+        .map(|sources| sources.iter().filter_map(|s| s["item_count"].as_u64()).sum())
+        .unwrap_or(0);
+
+    let ctx = serde_json::json!({
+        "channel": manifest["channel"],
+        "window": {
+            "from": manifest["window"]["from"],
+            "to": manifest["window"]["to"],
+            "from_local": local("from"),
+            "to_local": local("to"),
+        },
+        "timezone": manifest["timezone"],
+        "sources": manifest["sources"],
+        "item_count": item_count,
+        "prompt": channel_config.prompt,
+    });
+
+    let mut env = minijinja::Environment::new();
+    env.set_undefined_behavior(minijinja::UndefinedBehavior::Strict);
+    let rendered = env
+        .render_str(&template, ctx)
+        .map_err(|e| anyhow::anyhow!("rendering prompt template {}: {e:#}", template_path.display()))?;
+
+    debug!(template = %template_path.display(), "rendered editorial directive from template");
+    Ok(rendered)
+}
+
+/// The channel's persona, appended to the editorial directive: the directive says what to
+/// cover, the persona how it reads.
+fn persona_block(persona: &PersonaConfig) -> String {
+    format!(
+        "### Voice and style\n\nWrite in this voice throughout. Where the directive above sets the tone \
+         explicitly, the directive wins.\n\n{}",
+        persona.style.trim()
+    )
+}
+
+/// The channel's required article structure, appended to the editorial directive. With
+/// `changelog`, the changelog section is named as the one allowed before them.
+fn sections_block(sections: &[String], changelog: bool) -> String {
+    let mut block = String::from(
+        "### Required structure\n\n\
+         Organize the article under exactly these `##` sections, in this order, using the names verbatim. \
+         Keep every section even in a quiet period: write one line saying there was nothing notable. \
+         Closing lists the instructions ask for (such as `## Skipped`) go after them.",
+    );
+    if changelog {
+        block.push_str(&format!(
+            " The `## {CHANGELOG_HEADING}` section asked for below comes first, before them."
+        ));
+    }
+    block.push('\n');
+    for section in sections {
+        block.push_str(&format!("\n- ## {}", section.trim()));
+    }
+    block
+}
+
+/// `previous.md`: the channel's previous article, as the reader saw it.
+fn previous_article_file(previous: &GeneratedArticleRow) -> String {
+    format!("# {}\n\n{}\n", previous.title, previous.body_markdown.trim())
+}
+
+/// Heading of the changelog section, in the directive block and in the article.
+const CHANGELOG_HEADING: &str = "What changed since the last digest";
+
+/// Changelog mode (`changelog = true`): compare against the previous article in `previous.md`.
+fn changelog_block(previous: &GeneratedArticleRow, timezone: &str) -> String {
+    let tz: chrono_tz::Tz = timezone.parse().unwrap_or(chrono_tz::UTC);
+    format!(
+        "### {CHANGELOG_HEADING}\n\n\
+         `previous.md` holds the previous digest of this channel (\"{}\", published {}). \
+         Start the article with a short `## {CHANGELOG_HEADING}` section, before any other section: \
+         a few bullets on stories that are new, stories that developed further, and stories from the previous digest \
+         that were resolved or went quiet. Don't repeat the previous digest's coverage of a story unless something \
+         changed; refer back to it briefly instead. If nothing meaningful changed, say so in one line.",
+        previous.title,
+        previous.generated_at.with_timezone(&tz).format("%Y-%m-%d %H:%M"),
+    )
+}
+
+/// Format recent reader feedback as a block appended to the editorial directive.
+/// The directive keeps precedence: notes steer emphasis, they don't override instructions.
+fn feedback_section(feedback: &[ArticleFeedback], timezone: &str) -> String {
+    let tz: chrono_tz::Tz = timezone.parse().unwrap_or(chrono_tz::UTC);
+    let mut section = String::from(
+        "### Reader feedback on recent digests\n\n\
+         The reader left these notes on previous digests of this channel (newest first). \
+         Adjust topic selection, depth, and tone accordingly. \
+         If a note conflicts with the directive above, the directive wins.\n",
+    );
+    for fb in feedback {
+        section.push_str(&format!(
+            "\n- {} (on \"{}\"): {}",
+            fb.created_at.with_timezone(&tz).format("%Y-%m-%d"),
+            fb.article_title,
+            fb.note.trim()
+        ));
+    }
+    section
+}
+
+/// Write `sources/<slug>.md` per source: its frontmatter, then its items. With `item_files`,
+/// each item gets its own `sources/<slug>/NNN.md` and the source file lists them instead.
 async fn write_source_content(
     ws_path: &Path,
     items: &[ContentItem],
     source_map: &HashMap<String, &Source>,
     file_infos: &HashMap<SourceKey, SourceFileInfo>,
+    pages: &HashMap<String, FetchedPage>,
+    item_files: bool,
 ) -> Result<()> {
     // Group items by source key
     let mut items_by_key: HashMap<SourceKey, Vec<&ContentItem>> = HashMap::new();
@@ -505,10 +864,45 @@ async fn write_source_content(
             source_items.len(),
         );
 
-        for (i, item) in source_items.iter().enumerate() {
-            content.push_str(&format_content_item(item));
-            if i < source_items.len() - 1 {
-                content.push_str("\n---\n\n");
+        if item_files {
+            let item_dir = sources_dir.join(&info.slug);
+            tokio::fs::create_dir_all(&item_dir)
+                .await
+                .map_err(GenerationError::Workspace)?;
+            // Zero-padded so the files sort in item order
+            let width = source_items.len().to_string().len().max(3);
+            for (i, item) in source_items.iter().enumerate() {
+                let page_file = item
+                    .url
+                    .as_deref()
+                    .filter(|url| pages.contains_key(*url))
+                    .map(page_file_name);
+                let file_name = format!("{:0width$}.md", i + 1);
+                let item_content = format!(
+                    "{}{}",
+                    item_frontmatter(item, &info.name, page_file.as_deref()),
+                    format_content_item(item, page_file.as_deref())
+                );
+                tokio::fs::write(item_dir.join(&file_name), item_content)
+                    .await
+                    .map_err(GenerationError::Workspace)?;
+                content.push_str(&format!(
+                    "- `sources/{}/{file_name}` — {}\n",
+                    info.slug,
+                    item.title.as_deref().unwrap_or("(untitled)")
+                ));
+            }
+        } else {
+            for (i, item) in source_items.iter().enumerate() {
+                let page_file = item
+                    .url
+                    .as_deref()
+                    .filter(|url| pages.contains_key(*url))
+                    .map(page_file_name);
+                content.push_str(&format_content_item(item, page_file.as_deref()));
+                if i < source_items.len() - 1 {
+                    content.push_str("\n---\n\n");
+                }
             }
         }
 
@@ -523,7 +917,63 @@ async fn write_source_content(
     Ok(())
 }
 
-fn format_content_item(item: &ContentItem) -> String {
+/// YAML frontmatter of a per-item file (`item_files`). Strings are written as JSON strings,
+/// which YAML reads as double-quoted scalars.
+fn item_frontmatter(item: &ContentItem, source_name: &str, page_file: Option<&str>) -> String {
+    let quote = |s: &str| serde_json::to_string(s).unwrap_or_default();
+    let mut fm = format!("---\nid: {}\nsource: {}\n", quote(&item.id), quote(source_name));
+    if let Some(ref title) = item.title {
+        fm.push_str(&format!("title: {}\n", quote(title)));
+    }
+    if let Some(ref url) = item.url {
+        fm.push_str(&format!("url: {}\n", quote(url)));
+    }
+    fm.push_str(&format!("date: {}\n", item.original_date.format("%Y-%m-%dT%H:%M:%SZ")));
+    if let Some(ref author) = item.author {
+        fm.push_str(&format!("author: {}\n", quote(author)));
+    }
+    fm.push_str(&format!("type: {}\n", item.content_type));
+    if let Some(page_file) = page_file {
+        fm.push_str(&format!("full_text: {page_file}\n"));
+    }
+    fm.push_str("---\n\n");
+    fm
+}
+
+/// Write prefetched article text to `pages/<hash>.md`, one file per URL.
+async fn write_pages(ws_path: &Path, pages: &HashMap<String, FetchedPage>) -> Result<()> {
+    if pages.is_empty() {
+        return Ok(());
+    }
+    let pages_dir = ws_path.join("pages");
+    tokio::fs::create_dir_all(&pages_dir)
+        .await
+        .map_err(GenerationError::Workspace)?;
+
+    for (url, page) in pages {
+        let mut content = String::new();
+        if let Some(ref title) = page.title {
+            content.push_str(&format!("# {title}\n\n"));
+        }
+        content.push_str(&format!("**Source:** {url}\n\n"));
+        content.push_str(page.text.as_deref().unwrap_or_default());
+        content.push('\n');
+        tokio::fs::write(ws_path.join(page_file_name(url)), content)
+            .await
+            .map_err(GenerationError::Workspace)?;
+    }
+
+    debug!(pages = pages.len(), "wrote prefetched pages");
+    Ok(())
+}
+
+/// Workspace-relative path of a prefetched page: `pages/` plus a short hash of the URL.
+fn page_file_name(url: &str) -> String {
+    let digest = format!("{:x}", Sha256::digest(url.as_bytes()));
+    format!("pages/{}.md", &digest[..16])
+}
+
+fn format_content_item(item: &ContentItem, page_file: Option<&str>) -> String {
     let mut md = String::new();
 
     // Parse metadata for TG-specific fields (message_id, reply_to, forward, media)
@@ -550,6 +1000,13 @@ fn format_content_item(item: &ContentItem) -> String {
         item.original_date.format("%Y-%m-%d %H:%M UTC")
     ));
 
+    // Set by the relevance filter in "demote" mode (see docs/specs/relevance-filtering.md)
+    if let Some(score) = meta.get("relevance_score").and_then(|v| v.as_f64()) {
+        md.push_str(&format!(
+            "**Relevance:** low ({score:.2}) — likely outside this channel's interests\n"
+        ));
+    }
+
     // For forwards, label the sender as "Forwarded by" to avoid misattribution
     if let Some(ref author) = item.author {
         if is_forward {
@@ -577,13 +1034,113 @@ fn format_content_item(item: &ContentItem) -> String {
         md.push_str(&format!("**Original author:** {post_author}\n"));
     }
 
-    if let Some(media) = media_type {
+    // Coalesced TG albums list every part's media type
+    if let Some(types) = meta.get("media_types").and_then(|v| v.as_array()) {
+        let types: Vec<&str> = types.iter().filter_map(|t| t.as_str()).collect();
+        md.push_str(&format!("**Media:** album of {} ({})\n", types.len(), types.join(", ")));
+    } else if let Some(media) = media_type {
         md.push_str(&format!("**Media:** {media}\n"));
     }
+    // Photos pail downloaded, ready to embed (see docs/specs/telegram.md "Media")
+    if let Some(ids) = meta.get("media_ids").and_then(|v| v.as_array()) {
+        let images: Vec<String> = ids
+            .iter()
+            .filter_map(|id| id.as_str())
+            .map(|id| format!("![photo]({})", tg_media::path(id)))
+            .collect();
+        if !images.is_empty() {
+            md.push_str(&format!("**Images:** {}\n", images.join(" ")));
+        }
+    }
+
+    // TG views, forwards and reactions (see docs/specs/telegram.md "Engagement")
+    if let Some(views) = meta.get("views").and_then(|v| v.as_i64()) {
+        let forwards = meta.get("forwards").and_then(|v| v.as_i64()).unwrap_or(0);
+        let reactions = meta.get("reactions").and_then(|v| v.as_i64()).unwrap_or(0);
+        md.push_str(&format!(
+            "**Engagement:** {views} views, {forwards} forwards, {reactions} reactions\n"
+        ));
+    } else if let Some(reactions) = meta.get("reactions").and_then(|v| v.as_i64()).filter(|r| *r > 0) {
+        md.push_str(&format!("**Reactions:** {reactions}\n"));
+    }
+
+    // Podcast episodes (see docs/specs/podcast-sources.md)
+    if let Some(audio_url) = meta.get("audio_url").and_then(|v| v.as_str()) {
+        md.push_str(&format!("**Audio:** {audio_url}\n"));
+    }
+    if let Some(secs) = meta.get("duration_secs").and_then(|v| v.as_u64()) {
+        md.push_str(&format!("**Duration:** {}\n", format_timestamp(secs as f64)));
+    }
+    if meta.get("transcription_error").is_some() {
+        md.push_str("**Transcript:** unavailable (transcription failed) — body is the show notes\n");
+    }
+
+    // Lemmy / Lobsters posts (see docs/specs/aggregator-sources.md)
+    if let Some(score) = meta.get("score").and_then(|v| v.as_i64()) {
+        let comments = meta.get("comment_count").and_then(|v| v.as_i64()).unwrap_or(0);
+        md.push_str(&format!("**Score:** {score} ({comments} comments)\n"));
+    }
+
+    // Calendar events (see docs/specs/ics-sources.md)
+    if let Some(when) = meta.get("event_time").and_then(|v| v.as_str()) {
+        md.push_str(&format!("**When:** {when}\n"));
+    }
+    if let Some(location) = meta.get("location").and_then(|v| v.as_str()) {
+        md.push_str(&format!("**Location:** {location}\n"));
+    }
+
+    // Detected on ingestion (see docs/specs/language-filtering.md)
+    if let Some(language) = meta.get("language").and_then(|v| v.as_str()) {
+        md.push_str(&format!("**Language:** {language}\n"));
+    }
+
+    // Tweets and threads (see docs/specs/twitter-sources.md)
+    if let Some(count) = meta.get("tweet_count").and_then(|v| v.as_u64()).filter(|c| *c > 1) {
+        md.push_str(&format!("**Thread:** {count} tweets\n"));
+    }
+    if let Some(original) = meta.get("retweet_of").and_then(|v| v.as_str()) {
+        md.push_str(&format!("**Retweet of:** {original}\n"));
+    }
+    if let Some(to) = meta.get("in_reply_to").and_then(|v| v.as_str()) {
+        md.push_str(&format!("**In reply to:** {to}\n"));
+    }
+
+    // Slack messages (see docs/specs/slack-sources.md)
+    if let Some(channel) = meta.get("slack_channel").and_then(|v| v.as_str()) {
+        md.push_str(&format!("**Slack channel:** {channel}\n"));
+    }
+    if let Some(count) = meta
+        .get("reply_count")
+        .and_then(|v| v.as_i64())
+        .filter(|c| *c > 0 && meta.get("slack_channel").is_some())
+    {
+        md.push_str(&format!("**Thread:** {count} replies\n"));
+    }
+
+    // arXiv papers (see docs/specs/arxiv-sources.md)
+    if let Some(pdf) = meta.get("pdf_url").and_then(|v| v.as_str()) {
+        md.push_str(&format!("**PDF:** {pdf}\n"));
+    }
+    // arXiv and microformats sources
+    if let Some(categories) = meta.get("categories").and_then(|v| v.as_array()) {
+        let categories: Vec<&str> = categories.iter().filter_map(|c| c.as_str()).collect();
+        md.push_str(&format!("**Categories:** {}\n", categories.join(", ")));
+    }
 
     if let Some(ref url) = item.url {
         md.push_str(&format!("**Link:** {url}\n"));
     }
+    if let Some(discussion) = meta.get("discussion_url").and_then(|v| v.as_str())
+        && item.url.as_deref() != Some(discussion)
+    {
+        md.push_str(&format!("**Discussion:** {discussion}\n"));
+    }
+    if let Some(path) = page_file {
+        md.push_str(&format!("**Full text:** `{path}`\n"));
+    }
+    if meta.get("truncated").and_then(|v| v.as_bool()) == Some(true) {
+        md.push_str("**Truncated:** the feed entry was too large, only its start is stored\n");
+    }
 
     md.push('\n');
 
@@ -591,6 +1148,9 @@ fn format_content_item(item: &ContentItem) -> String {
         if let Some(media) = media_type {
             md.push_str(&format!("[{media} — no caption, see link]\n"));
         }
+    } else if let Some(segments) = meta.get("segments").and_then(|v| v.as_array()) {
+        md.push_str(&body_with_timestamps(&item.body, segments));
+        md.push('\n');
     } else {
         md.push_str(&item.body);
         md.push('\n');
@@ -599,39 +1159,100 @@ fn format_content_item(item: &ContentItem) -> String {
     md
 }
 
+/// Minimum spacing between `[MM:SS]` markers inserted into podcast transcripts.
+const TIMESTAMP_MARKER_INTERVAL_SECS: f64 = 60.0;
+
+/// Insert `[MM:SS]` markers into a transcript at segment boundaries (at most one per minute),
+/// so the model can reference points in the episode without the full per-segment timing.
+fn body_with_timestamps(body: &str, segments: &[serde_json::Value]) -> String {
+    let chars: Vec<char> = body.chars().collect();
+    let mut out = String::with_capacity(body.len() + segments.len() * 10);
+    let mut copied = 0;
+    let mut last_marker: Option<f64> = None;
+
+    for segment in segments {
+        let (Some(start), Some(offset)) = (
+            segment.get("start").and_then(|v| v.as_f64()),
+            segment.get("offset").and_then(|v| v.as_u64()).map(|o| o as usize),
+        ) else {
+            continue;
+        };
+        if offset < copied || offset > chars.len() {
+            continue;
+        }
+        if last_marker.is_some_and(|last| start - last < TIMESTAMP_MARKER_INTERVAL_SECS) {
+            continue;
+        }
+        out.extend(&chars[copied..offset]);
+        let trimmed_len = out.trim_end().len();
+        out.truncate(trimmed_len);
+        if !out.is_empty() {
+            out.push_str("\n\n");
+        }
+        out.push_str(&format!("[{}] ", format_timestamp(start)));
+        copied = offset;
+        last_marker = Some(start);
+    }
+    out.extend(&chars[copied..]);
+    out
+}
+
+/// Seconds → `MM:SS` or `H:MM:SS`.
+fn format_timestamp(secs: f64) -> String {
+    let total = secs.max(0.0) as u64;
+    let (h, m, s) = (total / 3600, (total % 3600) / 60, total % 60);
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m:02}:{s:02}")
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn invoke_opencode(
-    binary: &str,
+    opencode: &OpencodeConfig,
     workspace: &Path,
     model: &str,
     prompt: &str,
     timeout_str: &str,
+    budget: Budget,
+    live: Option<&LiveSink>,
     cancel: CancellationToken,
-) -> Result<(String, Option<i32>)> {
+) -> Result<(String, Option<i32>, Option<SessionUsage>)> {
     let timeout = humantime::parse_duration(timeout_str).context("parsing opencode timeout")?;
+    let idle_timeout = opencode
+        .idle_timeout
+        .as_deref()
+        .map(humantime::parse_duration)
+        .transpose()
+        .context("parsing opencode idle_timeout")?;
 
+    let binary = opencode.binary.as_str();
     info!(
         binary = %binary,
         model = %model,
         workspace = %workspace.display(),
+        sandbox = opencode.sandbox.as_ref().map(|s| s.wrapper.as_str()),
         "invoking opencode"
     );
 
-    let mut cmd = tokio::process::Command::new(binary);
-    cmd.arg("run")
-        .arg("--model")
-        .arg(model)
-        .arg("--")
-        .arg(prompt)
-        .current_dir(workspace)
-        // Enable opencode's Exa-powered websearch tool so the model can verify
-        // facts and find real URLs instead of hallucinating from training data.
-        .env("OPENCODE_ENABLE_EXA", "1")
+    let args = ["run", "--model", model, "--", prompt].map(str::to_string);
+    // Enable opencode's Exa-powered websearch tool so the model can verify
+    // facts and find real URLs instead of hallucinating from training data.
+    let env = [("OPENCODE_ENABLE_EXA", "1")];
+    let mut sandboxed = sandbox::command(opencode.sandbox.as_ref(), binary, &args, &env, workspace).await?;
+    sandboxed
+        .command
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped());
 
-    let mut child = match cmd.spawn() {
+    let mut child = match sandboxed.command.spawn() {
         Ok(c) => c,
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let program = sandboxed.command.as_std().get_program().to_string_lossy().to_string();
+            if program != binary {
+                anyhow::bail!("sandbox wrapper not found: {program}");
+            }
             return Err(GenerationError::OpencodeBinaryNotFound(binary.to_string()).into());
         }
         Err(e) => {
@@ -643,16 +1264,28 @@ pub(crate) async fn invoke_opencode(
         }
     };
 
-    // Take stdout/stderr handles so we can read them after wait/kill
-    let child_stdout = child.stdout.take();
-    let child_stderr = child.stderr.take();
+    // Read stdout/stderr as they're written (see docs/specs/generation-engine.md "Live Output")
+    let output = live_run::Output::default();
+    let readers = [
+        output.read(child.stdout.take(), "stdout"),
+        output.read(child.stderr.take(), "stderr"),
+    ];
+    let mirror = live.map(|live| output.mirror(live.clone()));
+    // Once the process is gone its pipes close; wait for the readers to drain them
+    let collect = async {
+        for reader in readers {
+            let _ = reader.await;
+        }
+        drop(mirror);
+        output.collected()
+    };
 
-    // Wait for completion, timeout, or cancellation (see docs/specs/daemon.md "Graceful Shutdown")
+    // Wait for completion, timeout, stall, or cancellation (see docs/specs/daemon.md "Graceful Shutdown")
     tokio::select! {
         r = tokio::time::timeout(timeout, child.wait()) => {
             match r {
                 Ok(Ok(status)) => {
-                    let (stdout, stderr) = read_child_pipes(child_stdout, child_stderr).await;
+                    let (stdout, stderr) = collect.await;
                     let log = format!("=== STDOUT ===\n{stdout}\n=== STDERR ===\n{stderr}");
                     let exit_code = status.code();
                     if !status.success() {
@@ -662,7 +1295,11 @@ pub(crate) async fn invoke_opencode(
                             "opencode exited with error"
                         );
                     }
-                    Ok((log, exit_code))
+                    let usage = budget::session_usage(binary, workspace).await;
+                    if let Some(ref usage) = usage {
+                        info!(usage = %usage, "opencode session usage");
+                    }
+                    Ok((log, exit_code, usage))
                 }
                 Ok(Err(e)) => Err(GenerationError::OpencodeExecution {
                     exit_code: None,
@@ -672,7 +1309,8 @@ pub(crate) async fn invoke_opencode(
                     warn!("opencode timed out, killing subprocess");
                     let _ = child.kill().await;
                     let _ = child.wait().await;
-                    let (stdout, stderr) = read_child_pipes(child_stdout, child_stderr).await;
+                    sandboxed.cleanup().await;
+                    let (stdout, stderr) = collect.await;
                     let partial_log = format!("=== STDOUT (partial) ===\n{stdout}\n=== STDERR (partial) ===\n{stderr}");
                     Err(GenerationError::Timeout(
                         format!("{timeout_str}. Partial log:\n{partial_log}")
@@ -680,11 +1318,31 @@ pub(crate) async fn invoke_opencode(
                 }
             }
         }
+        silent = output.stalled(idle_timeout) => {
+            let silent = humantime::format_duration(Duration::from_secs(silent.as_secs()));
+            warn!(silent = %silent, "opencode produced no output, killing subprocess");
+            let _ = child.kill().await;
+            let _ = child.wait().await;
+            sandboxed.cleanup().await;
+            let (stdout, stderr) = collect.await;
+            let partial_log = format!("=== STDOUT (partial) ===\n{stdout}\n=== STDERR (partial) ===\n{stderr}");
+            Err(GenerationError::Stalled(format!("no output for {silent}. Partial log:\n{partial_log}")).into())
+        }
+        reason = budget::watch(binary, workspace, budget) => {
+            warn!(reason = %reason, "generation budget exceeded, killing opencode subprocess");
+            let _ = child.kill().await;
+            let _ = child.wait().await;
+            sandboxed.cleanup().await;
+            let (stdout, stderr) = collect.await;
+            let partial_log = format!("=== STDOUT (partial) ===\n{stdout}\n=== STDERR (partial) ===\n{stderr}");
+            Err(GenerationError::BudgetExceeded(format!("{reason}. Partial log:\n{partial_log}")).into())
+        }
         _ = cancel.cancelled() => {
             warn!("generation cancelled, killing opencode subprocess");
             let _ = child.kill().await;
             let _ = child.wait().await;
-            let (stdout, stderr) = read_child_pipes(child_stdout, child_stderr).await;
+            sandboxed.cleanup().await;
+            let (stdout, stderr) = collect.await;
             let partial_log = format!("=== STDOUT (partial) ===\n{stdout}\n=== STDERR (partial) ===\n{stderr}");
             Err(GenerationError::OpencodeExecution {
                 exit_code: None,
@@ -694,27 +1352,6 @@ pub(crate) async fn invoke_opencode(
     }
 }
 
-async fn read_child_pipes(
-    stdout: Option<tokio::process::ChildStdout>,
-    stderr: Option<tokio::process::ChildStderr>,
-) -> (String, String) {
-    let stdout_str = if let Some(mut out) = stdout {
-        let mut buf = Vec::new();
-        let _ = out.read_to_end(&mut buf).await;
-        String::from_utf8_lossy(&buf).to_string()
-    } else {
-        String::new()
-    };
-    let stderr_str = if let Some(mut err) = stderr {
-        let mut buf = Vec::new();
-        let _ = err.read_to_end(&mut buf).await;
-        String::from_utf8_lossy(&buf).to_string()
-    } else {
-        String::new()
-    };
-    (stdout_str, stderr_str)
-}
-
 fn extract_share_url(generation_log: &str) -> Option<String> {
     const PREFIX: &str = "https://opncd.ai/share/";
     let start = generation_log.find(PREFIX)?;
@@ -726,7 +1363,77 @@ fn extract_share_url(generation_log: &str) -> Option<String> {
     Some(rest[..end].to_string())
 }
 
-fn parse_output(content: &str) -> Result<(String, Vec<String>, String)> {
+/// Apply a channel's `[output_channel.postprocess]` rules: enforce the title prefix and suffix,
+/// map topic aliases (dropping duplicates they create), and cap the topic count.
+pub(crate) fn postprocess(
+    rules: &PostprocessConfig,
+    channel_name: &str,
+    date: chrono::NaiveDate,
+    title: String,
+    topics: Vec<String>,
+) -> (String, Vec<String>) {
+    let render = |template: &str| {
+        template
+            .replace("{date}", &date.format("%Y-%m-%d").to_string())
+            .replace("{channel}", channel_name)
+    };
+    let mut title = title;
+    if let Some(ref prefix) = rules.title_prefix {
+        let prefix = render(prefix);
+        if !title.starts_with(&prefix) {
+            title = format!("{prefix}{title}");
+        }
+    }
+    if let Some(ref suffix) = rules.title_suffix {
+        let suffix = render(suffix);
+        if !title.ends_with(&suffix) {
+            title.push_str(&suffix);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut normalized: Vec<String> = topics
+        .into_iter()
+        .map(|topic| {
+            let key = topic.trim().to_lowercase();
+            rules
+                .topic_aliases
+                .iter()
+                .find(|(from, _)| from.trim().to_lowercase() == key)
+                .map_or_else(|| topic.trim().to_string(), |(_, to)| to.trim().to_string())
+        })
+        .filter(|topic| !topic.is_empty() && seen.insert(topic.to_lowercase()))
+        .collect();
+    if let Some(max) = rules.max_topics {
+        normalized.truncate(max);
+    }
+
+    (sanitize_xml_text(&title), normalized)
+}
+
+/// Fields of a parsed `output.md`.
+struct ParsedOutput {
+    title: String,
+    topics: Vec<String>,
+    /// The `summary:` frontmatter field, if the model wrote one.
+    summary: Option<String>,
+    body: String,
+    skipped: Vec<SkippedItem>,
+}
+
+/// Check an `output.md` the way a generation would (`pail prompt tune`), without storing
+/// anything. Returns its title.
+pub(crate) fn check_output(content: &str, channel_config: &OutputChannelConfig) -> Result<String> {
+    let parsed = parse_output(content, &channel_config.sections)?;
+    if let Some(ref format) = channel_config.format {
+        output_format::check(format, &parsed.body).map_err(GenerationError::OutputParse)?;
+    }
+    Ok(parsed.title)
+}
+
+/// Parse `output.md` into (title, topics, body). Required `sections` missing from the body
+/// are logged as a warning; the article is still published.
+fn parse_output(content: &str, sections: &[String]) -> Result<ParsedOutput> {
     let matter = Matter::<YAML>::new();
     let result = matter.parse(content);
 
@@ -753,16 +1460,234 @@ fn parse_output(content: &str) -> Result<(String, Vec<String>, String)> {
         .map(|vec| vec.into_iter().filter_map(|v| v.as_string().ok()).collect())
         .unwrap_or_default();
 
+    let summary = frontmatter
+        .as_ref()
+        .and_then(|m| m.get("summary"))
+        .and_then(|v| v.as_string().ok())
+        .and_then(|s| clip_summary(&sanitize_xml_text(&s)));
+
     let body = result.content;
 
     if body.trim().is_empty() {
         return Err(GenerationError::OutputParse("article body is empty".to_string()).into());
     }
 
+    let missing = missing_sections(&body, sections);
+    if !missing.is_empty() {
+        warn!(missing = ?missing, "article is missing required sections");
+    }
+
     let title = sanitize_xml_text(&title);
     let body = sanitize_xml_text(&body);
+    let skipped = parse_skipped(&body);
+
+    Ok(ParsedOutput {
+        title,
+        topics,
+        summary,
+        body,
+        skipped,
+    })
+}
+
+/// Longest summary kept; a longer one is cut at a word boundary.
+const MAX_SUMMARY_CHARS: usize = 300;
+
+/// Plain text of the body's first top-level paragraph: headings, lists, quotes, tables and
+/// code blocks are passed over.
+pub(crate) fn lede(markdown: &str) -> Option<String> {
+    let mut depth = 0usize;
+    let mut in_paragraph = false;
+    let mut text = String::new();
+    for event in pulldown_cmark::Parser::new(markdown) {
+        match event {
+            pulldown_cmark::Event::Start(tag) => {
+                in_paragraph |= depth == 0 && matches!(tag, pulldown_cmark::Tag::Paragraph);
+                depth += 1;
+            }
+            pulldown_cmark::Event::End(_) => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 && in_paragraph {
+                    if let Some(lede) = clip_summary(&text) {
+                        return Some(lede);
+                    }
+                    in_paragraph = false;
+                    text.clear();
+                }
+            }
+            pulldown_cmark::Event::Text(t) | pulldown_cmark::Event::Code(t) if in_paragraph => text.push_str(&t),
+            pulldown_cmark::Event::SoftBreak | pulldown_cmark::Event::HardBreak if in_paragraph => text.push(' '),
+            _ => {}
+        }
+    }
+    None
+}
 
-    Ok((title, topics, body))
+/// Collapse whitespace and cut at `MAX_SUMMARY_CHARS` on a word boundary. `None` if empty.
+fn clip_summary(text: &str) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return None;
+    }
+    if text.chars().count() <= MAX_SUMMARY_CHARS {
+        return Some(text);
+    }
+    let cut: String = text.chars().take(MAX_SUMMARY_CHARS).collect();
+    let cut = cut.rsplit_once(' ').map_or(cut.as_str(), |(head, _)| head);
+    Some(format!("{}…", cut.trim_end_matches([',', ';', ':', '.', '-', '—'])))
+}
+
+/// Words that mark the last `##` heading as the skipped-items section, lowercase: English and
+/// the translations the strategy prompts ask for (`## Проігноровано`, `## Пропущено`).
+const SKIPPED_HEADING_WORDS: &[&str] = &["skip", "проігнор", "пропущ"];
+
+/// Entries of the `## Skipped` section: the last `##` section, if its heading is a Skipped
+/// heading. Other closing lists, such as further reading, are never taken for it.
+fn parse_skipped(body: &str) -> Vec<SkippedItem> {
+    let lines: Vec<&str> = body.lines().collect();
+    let Some(heading) = lines.iter().rposition(|l| l.starts_with("## ")) else {
+        return Vec::new();
+    };
+    let heading_text = lines[heading].to_lowercase();
+    if !SKIPPED_HEADING_WORDS.iter().any(|word| heading_text.contains(word)) {
+        return Vec::new();
+    }
+    lines[heading + 1..]
+        .iter()
+        .map(|line| line.trim())
+        .take_while(|line| !line.starts_with('#'))
+        .filter_map(parse_skipped_entry)
+        .collect()
+}
+
+/// One list entry: `- [Title](url) — reason`, `- *Name* — reason`, or the grouped Telegram
+/// form `- *Name* — N messages (reason)`.
+fn parse_skipped_entry(line: &str) -> Option<SkippedItem> {
+    let rest = line.strip_prefix("- ").or_else(|| line.strip_prefix("* "))?.trim();
+
+    let (title, url, after) = if let Some(link) = rest.strip_prefix('[') {
+        let (title, tail) = link.split_once("](")?;
+        let (url, after) = tail.split_once(')')?;
+        (title.to_string(), Some(url.trim().to_string()), after)
+    } else if let Some(marker) = ['*', '_'].into_iter().find(|m| rest.starts_with(*m)) {
+        let (title, after) = rest.trim_start_matches(marker).split_once(marker)?;
+        (title.to_string(), None, after.trim_start_matches(marker))
+    } else {
+        return None;
+    };
+    if title.trim().is_empty() {
+        return None;
+    }
+
+    let after = after.trim_start();
+    let reason = ["—", "–", "-", ":"]
+        .iter()
+        .find_map(|sep| after.strip_prefix(sep))
+        .unwrap_or(after)
+        .trim();
+    // "12 messages (ads)": a count and the reason in parentheses, in any language
+    let grouped = reason.split_once(' ').and_then(|(count, tail)| {
+        let count = count.parse::<i64>().ok().filter(|n| *n > 0)?;
+        let (open, close) = tail
+            .find('(')
+            .zip(tail.rfind(')'))
+            .filter(|(open, close)| open < close)?;
+        Some((count, tail[open + 1..close].trim()))
+    });
+    let (item_count, reason) = grouped.unwrap_or((1, reason));
+
+    Some(SkippedItem {
+        title: title.trim().to_string(),
+        url,
+        reason: reason.to_string(),
+        item_count,
+        source_id: None,
+        content_item_id: None,
+    })
+}
+
+/// Link skipped entries to the window's items (by URL) or sources (by name).
+fn resolve_skipped(skipped: &mut [SkippedItem], items: &[ContentItem], source_map: &HashMap<String, &Source>) {
+    let by_url: HashMap<&str, &ContentItem> = items
+        .iter()
+        .filter_map(|i| Some((i.url.as_deref()?.trim_end_matches('/'), i)))
+        .collect();
+    for entry in skipped {
+        if let Some(item) = entry.url.as_deref().and_then(|u| by_url.get(u.trim_end_matches('/'))) {
+            entry.source_id = Some(item.source_id.clone());
+            entry.content_item_id = Some(item.id.clone());
+        } else if let Some(source) = source_map
+            .values()
+            .find(|s| s.name.eq_ignore_ascii_case(entry.title.trim()))
+        {
+            entry.source_id = Some(source.id.clone());
+        }
+    }
+}
+
+/// IDs of the items the article body links to, matched by the item's link, discussion page or
+/// thread tweet links. Items listed under `## Skipped` don't count even though they're linked.
+fn cited_items(body_markdown: &str, items: &[ContentItem], skipped: &[SkippedItem]) -> Vec<String> {
+    let skipped: HashSet<&str> = skipped.iter().filter_map(|s| s.content_item_id.as_deref()).collect();
+    let linked: HashSet<String> = body_links(body_markdown).into_iter().map(normalize_link).collect();
+    items
+        .iter()
+        .filter(|item| !skipped.contains(item.id.as_str()))
+        .filter(|item| {
+            let meta: serde_json::Value = serde_json::from_str(&item.metadata).unwrap_or_default();
+            let tweet_urls = meta.get("tweet_urls").and_then(|v| v.as_array());
+            item.url
+                .as_deref()
+                .into_iter()
+                .chain(meta.get("discussion_url").and_then(|v| v.as_str()))
+                .chain(tweet_urls.into_iter().flatten().filter_map(|v| v.as_str()))
+                .any(|url| linked.contains(&normalize_link(url)))
+        })
+        .map(|item| item.id.clone())
+        .collect()
+}
+
+/// The http(s) URLs in a Markdown body, in order of appearance.
+pub(crate) fn body_links(body_markdown: &str) -> Vec<&str> {
+    body_markdown
+        .split(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '<' | '>' | '[' | ']' | '"'))
+        .filter(|token| token.starts_with("https://") || token.starts_with("http://"))
+        .collect()
+}
+
+/// Comparable form of a link: no scheme, fragment, trailing slash or sentence punctuation.
+pub(crate) fn normalize_link(url: &str) -> String {
+    let url = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '*', '_']);
+    let url = url.split('#').next().unwrap_or(url);
+    let url = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url);
+    url.trim_start_matches("www.").trim_end_matches('/').to_string()
+}
+
+/// Required sections without a matching heading. A `##`-or-deeper heading matches if its whole
+/// text is the section name (case-insensitive) once leading and trailing decorations are
+/// dropped, so "🇺🇦 Ukraine" or "Ukraine:" count but "Ukraine aid" doesn't.
+pub(crate) fn missing_sections<'a>(body: &str, sections: &'a [String]) -> Vec<&'a str> {
+    let headings: Vec<String> = body
+        .lines()
+        .filter_map(|line| {
+            let text = line.trim_start_matches('#');
+            let level = line.len() - text.len();
+            (level >= 2 && text.starts_with(char::is_whitespace)).then(|| bare_heading(text))
+        })
+        .collect();
+    sections
+        .iter()
+        .map(|s| s.trim())
+        .filter(|section| !headings.contains(&bare_heading(section)))
+        .collect()
+}
+
+/// Heading text without surrounding emoji, punctuation and whitespace, lowercased.
+fn bare_heading(text: &str) -> String {
+    text.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase()
 }
 
 /// Sanitize text for XML 1.0 validity.
@@ -847,15 +1772,16 @@ pub(crate) fn sanitize_xml_text(s: &str) -> String {
     result
 }
 
-fn markdown_to_html(markdown: &str) -> String {
-    let parser = pulldown_cmark::Parser::new(markdown);
+pub(crate) fn markdown_to_html(markdown: &str) -> String {
+    // Footnotes: removed links are replaced with one (see docs/specs/link-verification.md)
+    let parser = pulldown_cmark::Parser::new_ext(markdown, pulldown_cmark::Options::ENABLE_FOOTNOTES);
     let mut html = String::new();
     pulldown_cmark::html::push_html(&mut html, parser);
     html
 }
 
 /// Strip ANSI escape sequences (e.g. `\x1b[94m`) from a string.
-fn strip_ansi(s: &str) -> String {
+pub(crate) fn strip_ansi(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     let mut chars = s.chars();
     while let Some(c) = chars.next() {
@@ -875,7 +1801,8 @@ fn strip_ansi(s: &str) -> String {
     out
 }
 
-fn slug_from_name(name: &str) -> String {
+/// A slug from a name: "Morning Tech Digest" is "morning-tech-digest".
+pub(crate) fn slug_from_name(name: &str) -> String {
     name.to_lowercase()
         .chars()
         .map(|c| if c.is_alphanumeric() { c } else { '-' })
@@ -918,13 +1845,9 @@ pub async fn validate_models(config: &Config) -> Result<()> {
     // Collect all effective models from config (deduplicated)
     let mut models_to_check: HashMap<String, Vec<String>> = HashMap::new(); // model -> channel names
     for channel in &config.output_channel {
-        let model = channel
-            .model
-            .as_deref()
-            .or(config.opencode.default_model.as_deref())
-            .unwrap_or("opencode/big-pickle")
-            .to_string();
-        models_to_check.entry(model).or_default().push(channel.name.clone());
+        for model in channel.model_chain(&config.opencode) {
+            models_to_check.entry(model).or_default().push(channel.name.clone());
+        }
     }
 
     let mut missing = Vec::new();
@@ -949,3 +1872,92 @@ pub async fn validate_models(config: &Config) -> Result<()> {
     info!(models = ?models_to_check.keys().collect::<Vec<_>>(), "all configured models available");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sections(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_missing_sections_matches_decorated_headings() {
+        let body = "# Title\n\n## 🇺🇦 Ukraine\ntext\n### ai & ml:\nmore\n";
+        assert!(missing_sections(body, &sections(&["Ukraine", "AI & ML"])).is_empty());
+    }
+
+    #[test]
+    fn test_missing_sections_requires_whole_heading() {
+        let body = "## Ukraine aid\n## Tech\n";
+        assert_eq!(missing_sections(body, &sections(&["Ukraine", "Tech"])), ["Ukraine"]);
+    }
+
+    #[test]
+    fn test_parse_skipped_entries() {
+        let body = "## News\n\ntext\n\n## Skipped\n\
+            - [Ad post](https://example.com/ad) — advertising\n\
+            - *Weather bot* — 12 messages (routine updates)\n\
+            not an entry\n";
+        let skipped = parse_skipped(body);
+        assert_eq!(skipped.len(), 2);
+        assert_eq!(skipped[0].title, "Ad post");
+        assert_eq!(skipped[0].url.as_deref(), Some("https://example.com/ad"));
+        assert_eq!(skipped[0].reason, "advertising");
+        assert_eq!(skipped[1].title, "Weather bot");
+        assert_eq!(
+            (skipped[1].item_count, skipped[1].reason.as_str()),
+            (12, "routine updates")
+        );
+    }
+
+    #[test]
+    fn test_parse_skipped_translated_heading() {
+        let body = "## Новини\ntext\n## Проігноровано\n- [Реклама](https://example.com) — реклама\n";
+        assert_eq!(parse_skipped(body).len(), 1);
+    }
+
+    #[test]
+    fn test_parse_skipped_requires_heading() {
+        // A closing list of links is not a Skipped section, even though its entries parse
+        let body = "## News\ntext\n## Further reading\n- [Deep dive](https://example.com) — background\n";
+        assert!(parse_skipped(body).is_empty());
+        // Only the last section counts
+        let body = "## Skipped\n- [Ad](https://example.com) — ad\n## Outro\nbye\n";
+        assert!(parse_skipped(body).is_empty());
+    }
+
+    #[test]
+    fn test_missing_sections_ignores_non_headings() {
+        let body = "# Ukraine\nUkraine\n##Ukraine\n";
+        assert_eq!(missing_sections(body, &sections(&["Ukraine"])), ["Ukraine"]);
+    }
+
+    #[test]
+    fn test_lede_takes_first_top_level_paragraph() {
+        let body = "## Top stories\n\n- a list item\n\n> a quote\n\n```\ncode\n```\n\nThe *first* `real`\nparagraph.\n\nSecond.\n";
+        assert_eq!(lede(body).as_deref(), Some("The first real paragraph."));
+        // Paragraphs inside lists and quotes are not the lede
+        assert_eq!(lede("- item\n\n  more\n\n> quoted\n"), None);
+        assert_eq!(lede(""), None);
+    }
+
+    #[test]
+    fn test_clip_summary_collapses_whitespace() {
+        assert_eq!(clip_summary("  one\n two\tthree ").as_deref(), Some("one two three"));
+        assert_eq!(clip_summary(" \n "), None);
+    }
+
+    #[test]
+    fn test_clip_summary_cuts_at_a_word_boundary() {
+        let exact = "x".repeat(MAX_SUMMARY_CHARS);
+        assert_eq!(clip_summary(&exact).as_deref(), Some(exact.as_str()));
+
+        // The cut lands inside "tailing", which is dropped with the comma before it
+        let long = format!("{}last, tailing words", "word ".repeat(58));
+        let clipped = clip_summary(&long).unwrap();
+        assert!(clipped.ends_with("last…"), "{clipped}");
+        assert!(clipped.chars().count() <= MAX_SUMMARY_CHARS + 1);
+        assert!(!clipped.contains("tail"));
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+
+/// A background loop counts as alive if it ticked within this many of its own intervals.
+const MISSED_TICKS_BEFORE_DEAD: i64 = 3;
+
+/// Liveness state shared between the daemon's background tasks and the `/healthz` endpoint.
+/// Each loop records a timestamp per iteration; the endpoint derives "alive" from staleness.
+#[derive(Default)]
+pub struct Health {
+    inner: Mutex<HealthState>,
+}
+
+#[derive(Default, Clone)]
+pub struct HealthState {
+    pub scheduler_tick: Option<DateTime<Utc>>,
+    pub poller_tick: Option<DateTime<Utc>>,
+    pub telegram: TelegramHealth,
+}
+
+#[derive(Default, Clone)]
+pub struct TelegramHealth {
+    pub enabled: bool,
+    /// Listener state per account (see docs/specs/telegram.md "Multiple Accounts").
+    pub accounts: BTreeMap<String, TelegramAccountHealth>,
+}
+
+#[derive(Default, Clone)]
+pub struct TelegramAccountHealth {
+    pub connected: bool,
+    pub authorized: bool,
+    pub last_update: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    pub write_queue: WriteQueueHealth,
+}
+
+/// Counters of a listener's write queue (see docs/specs/telegram.md "Write Queue").
+#[derive(Default, Clone)]
+pub struct WriteQueueHealth {
+    /// Messages waiting to be written, as of the last batch.
+    pub depth: usize,
+    pub written: u64,
+    pub dropped: u64,
+    pub batches: u64,
+}
+
+impl TelegramHealth {
+    /// Every account's listener is connected (false when none started).
+    pub fn connected(&self) -> bool {
+        !self.accounts.is_empty() && self.accounts.values().all(|a| a.connected)
+    }
+
+    /// Every account's session is authorized (false when none started).
+    pub fn authorized(&self) -> bool {
+        !self.accounts.is_empty() && self.accounts.values().all(|a| a.authorized)
+    }
+
+    /// Most recent update received by any account.
+    pub fn last_update(&self) -> Option<DateTime<Utc>> {
+        self.accounts.values().filter_map(|a| a.last_update).max()
+    }
+
+    /// Account errors, prefixed with the account name.
+    pub fn error(&self) -> Option<String> {
+        let errors: Vec<String> = self
+            .accounts
+            .iter()
+            .filter_map(|(name, a)| a.error.as_ref().map(|e| format!("{name}: {e}")))
+            .collect();
+        (!errors.is_empty()).then(|| errors.join("; "))
+    }
+}
+
+impl Health {
+    pub fn scheduler_tick(&self) {
+        self.inner.lock().unwrap().scheduler_tick = Some(Utc::now());
+    }
+
+    pub fn poller_tick(&self) {
+        self.inner.lock().unwrap().poller_tick = Some(Utc::now());
+    }
+
+    pub fn update_telegram(&self, f: impl FnOnce(&mut TelegramHealth)) {
+        f(&mut self.inner.lock().unwrap().telegram);
+    }
+
+    pub fn update_tg_account(&self, account: &str, f: impl FnOnce(&mut TelegramAccountHealth)) {
+        f(self
+            .inner
+            .lock()
+            .unwrap()
+            .telegram
+            .accounts
+            .entry(account.to_string())
+            .or_default());
+    }
+
+    pub fn snapshot(&self) -> HealthState {
+        self.inner.lock().unwrap().clone()
+    }
+}
+
+/// Whether a loop with the given interval is considered alive given its last tick.
+/// A loop that never ticked is alive during its first few intervals after `started_at`.
+pub fn is_alive(
+    last_tick: Option<DateTime<Utc>>,
+    interval_secs: i64,
+    started_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> bool {
+    let reference = last_tick.unwrap_or(started_at);
+    now - reference <= chrono::Duration::seconds(interval_secs * MISSED_TICKS_BEFORE_DEAD)
+}
@@ -0,0 +1,542 @@
+//! Shared HTTP plumbing: proxy routing for every client pail builds, the crawler for pages
+//! pail itself fetches from websites (page prefetch, scrape sources), which honors robots.txt,
+//! spaces out requests per host and identifies itself, and the public-address guard for URLs
+//! taken from content (see docs/specs/network.md).
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::header::{LOCATION, USER_AGENT};
+use reqwest::{Method, RequestBuilder, Url};
+use tokio::sync::OnceCell;
+use tokio::time::Instant;
+
+use crate::config::{DIRECT_PROXY, NetworkConfig};
+use crate::error::{CrawlError, PublicUrlError};
+
+/// Product token matched against robots.txt `User-agent` lines.
+const ROBOTS_AGENT: &str = "pail";
+
+/// A robots.txt `Crawl-delay` longer than this is capped.
+const MAX_ROBOTS_CRAWL_DELAY: Duration = Duration::from_secs(30);
+
+/// A request that would wait longer than this for its host's turn is given up.
+const MAX_QUEUE_WAIT: Duration = Duration::from_secs(120);
+
+/// How long a fetched robots.txt is used (RFC 9309 §2.4 caps caching at 24 hours).
+const ROBOTS_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long an unreachable robots.txt keeps its origin disallowed before it's tried again.
+const ROBOTS_RETRY: Duration = Duration::from_secs(10 * 60);
+
+/// Redirects `send_public` follows.
+const MAX_PUBLIC_REDIRECTS: usize = 10;
+
+/// Route a client through `proxy`: a proxy URL, `"direct"` for no proxy at all, or `None`
+/// for reqwest's default (the `HTTPS_PROXY` / `ALL_PROXY` environment variables). Every HTTP
+/// client pail builds goes through here.
+pub(crate) fn with_proxy(
+    builder: reqwest::ClientBuilder,
+    proxy: Option<&str>,
+) -> reqwest::Result<reqwest::ClientBuilder> {
+    match proxy {
+        None => Ok(builder),
+        Some(DIRECT_PROXY) => Ok(builder.no_proxy()),
+        Some(url) => Ok(builder.proxy(reqwest::Proxy::all(url)?)),
+    }
+}
+
+/// User-Agent of crawler requests: `pail/<version> (+<contact_url>)`.
+pub fn crawler_user_agent(network: &NetworkConfig) -> String {
+    format!("pail/{} (+{})", env!("CARGO_PKG_VERSION"), network.contact_url)
+}
+
+/// Send a request to a URL taken from content (an article link, a webmention target) only if
+/// its host resolves to public addresses, and follow redirects by hand with the same check on
+/// every hop, so such URLs can't reach loopback, LAN or cloud metadata addresses. `client`
+/// must be built with `redirect(Policy::none())`; `build` adds headers and a body to each
+/// request. A redirect keeps the method, except that 303 turns it into a GET.
+pub(crate) async fn send_public(
+    client: &reqwest::Client,
+    method: Method,
+    url: &str,
+    build: impl Fn(RequestBuilder) -> RequestBuilder,
+) -> Result<reqwest::Response, PublicUrlError> {
+    let mut url = Url::parse(url).map_err(|e| PublicUrlError::InvalidUrl(e.to_string()))?;
+    let mut method = method;
+    for _ in 0..=MAX_PUBLIC_REDIRECTS {
+        ensure_public(&url).await?;
+        let response = build(client.request(method.clone(), url.clone())).send().await?;
+        let location = response.headers().get(LOCATION).and_then(|v| v.to_str().ok());
+        let Some(location) = location.filter(|_| response.status().is_redirection()) else {
+            return Ok(response);
+        };
+        url = url
+            .join(location)
+            .map_err(|e| PublicUrlError::InvalidUrl(e.to_string()))?;
+        if response.status() == reqwest::StatusCode::SEE_OTHER {
+            method = Method::GET;
+        }
+    }
+    Err(PublicUrlError::TooManyRedirects(MAX_PUBLIC_REDIRECTS))
+}
+
+/// Resolve `url`'s host and fail unless every address it resolves to is public.
+pub(crate) async fn ensure_public(url: &Url) -> Result<(), PublicUrlError> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(PublicUrlError::InvalidUrl(format!("not an http(s) URL: {url}")));
+    }
+    let Some(host) = url.host_str() else {
+        return Err(PublicUrlError::InvalidUrl(format!("no host in {url}")));
+    };
+    // IPv6 literals come bracketed
+    let addresses: Vec<IpAddr> = match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(ip) => vec![ip],
+        Err(_) => {
+            let port = url.port_or_known_default().unwrap_or(443);
+            tokio::net::lookup_host((host, port))
+                .await
+                .map_err(|e| PublicUrlError::Unresolvable {
+                    host: host.to_string(),
+                    message: e.to_string(),
+                })?
+                .map(|addr| addr.ip())
+                .collect()
+        }
+    };
+    let host = host.to_string();
+    match addresses.into_iter().find(|ip| !is_public_ip(*ip)) {
+        Some(ip) => Err(PublicUrlError::NotPublic { host, ip }),
+        None => Ok(()),
+    }
+}
+
+/// Globally routable unicast: not loopback, private, link-local, CGNAT, multicast,
+/// documentation, benchmarking or reserved ranges. IPv4-mapped IPv6 is judged as IPv4.
+pub(crate) fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_public_ipv4(v4),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        // 100.64.0.0/10, carrier-grade NAT
+        || (a == 100 && (64..128).contains(&b))
+        // 192.0.0.0/24, protocol assignments
+        || (a == 192 && b == 0 && c == 0)
+        // 198.18.0.0/15, benchmarking
+        || (a == 198 && (18..20).contains(&b))
+        // 240.0.0.0/4, reserved
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // fc00::/7, unique local
+        || (first & 0xfe00) == 0xfc00
+        // fe80::/10 link-local, fec0::/10 site-local
+        || (first & 0xffc0) == 0xfe80
+        || (first & 0xffc0) == 0xfec0
+        // 2001:db8::/32, documentation
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8)
+        // 64:ff9b:1::/48 local-use NAT64, ::/96 IPv4-compatible
+        || (first == 0x64 && ip.segments()[1] == 0xff9b && ip.segments()[2] == 1)
+        || ip.segments()[..6] == [0; 6])
+}
+
+/// Crawler state shared by every `Crawler` in the process, so prefetch runs, scrape polls and
+/// arXiv fetches that hit the same host are spaced together and share its robots.txt.
+struct CrawlState {
+    robots: Mutex<HashMap<String, Arc<OnceCell<CachedRobots>>>>,
+    /// Earliest start of the next request, per host.
+    next_request: Mutex<HashMap<String, Instant>>,
+}
+
+static CRAWL_STATE: LazyLock<CrawlState> = LazyLock::new(|| CrawlState {
+    robots: Mutex::new(HashMap::new()),
+    next_request: Mutex::new(HashMap::new()),
+});
+
+struct CachedRobots {
+    robots: Robots,
+    fetched_at: Instant,
+}
+
+impl CachedRobots {
+    fn is_stale(&self) -> bool {
+        let ttl = match self.robots {
+            Robots::Rules(_) => ROBOTS_TTL,
+            Robots::Unreachable => ROBOTS_RETRY,
+        };
+        self.fetched_at.elapsed() > ttl
+    }
+}
+
+/// robots.txt of one origin.
+enum Robots {
+    Rules(RobotsTxt),
+    /// 5xx or network error: RFC 9309 says to assume complete disallow.
+    Unreachable,
+}
+
+/// The group that applies to pail.
+#[derive(Default)]
+struct RobotsTxt {
+    rules: Vec<RobotsRule>,
+    crawl_delay: Option<Duration>,
+}
+
+struct RobotsRule {
+    allow: bool,
+    pattern: String,
+}
+
+/// Polite client for pages pail fetches itself. robots.txt is fetched once per origin and kept
+/// for a day (an unreachable one is retried after 10 minutes); requests to one host are spaced
+/// by `[network].crawl_delay`, or the site's longer `Crawl-delay`. Both are shared by all
+/// crawlers of the process, keyed by origin and host; a crawler only carries its client.
+pub struct Crawler {
+    client: reqwest::Client,
+    user_agent: String,
+    crawl_delay: Duration,
+    public_only: bool,
+}
+
+impl Crawler {
+    /// Wrap `client`, which carries the caller's timeout, proxy and auth. The crawler's
+    /// User-Agent replaces the client's.
+    pub fn new(client: reqwest::Client, network: &NetworkConfig) -> Result<Self> {
+        let crawl_delay = humantime::parse_duration(&network.crawl_delay).context("parsing [network].crawl_delay")?;
+        Ok(Self {
+            client,
+            user_agent: crawler_user_agent(network),
+            crawl_delay,
+            public_only: false,
+        })
+    }
+
+    /// Only request hosts that resolve to public addresses, for URLs taken from content. The
+    /// client must be built with `redirect(Policy::none())`: `send_public` follows redirects
+    /// and checks every hop, robots.txt included.
+    pub fn public_only(mut self) -> Self {
+        self.public_only = true;
+        self
+    }
+
+    /// Space requests to a host at least `delay` apart, for sites that ask more than
+    /// `[network].crawl_delay` of automated clients.
+    pub fn with_min_delay(mut self, delay: Duration) -> Self {
+        self.crawl_delay = self.crawl_delay.max(delay);
+        self
+    }
+
+    /// GET `url` once robots.txt allows it and the host's crawl delay has passed.
+    pub async fn get(&self, url: &Url) -> Result<reqwest::Response, CrawlError> {
+        // Checked before robots.txt, so a refused host gets no request at all
+        if self.public_only {
+            ensure_public(url).await?;
+        }
+        let origin = url.origin().ascii_serialization();
+        let cell = {
+            let mut cache = CRAWL_STATE.robots.lock().unwrap();
+            let cell = cache.entry(origin.clone()).or_default();
+            if cell.get().is_some_and(CachedRobots::is_stale) {
+                *cell = Arc::default();
+            }
+            cell.clone()
+        };
+        let cached = cell
+            .get_or_init(|| async {
+                CachedRobots {
+                    robots: self.fetch_robots(url, &origin).await,
+                    fetched_at: Instant::now(),
+                }
+            })
+            .await;
+        let Robots::Rules(ref robots) = cached.robots else {
+            return Err(CrawlError::RobotsUnreachable);
+        };
+        let path = match url.query() {
+            Some(query) => format!("{}?{query}", url.path()),
+            None => url.path().to_string(),
+        };
+        if !is_allowed(&robots.rules, &path) {
+            return Err(CrawlError::Disallowed);
+        }
+
+        let delay = robots.crawl_delay.map_or(self.crawl_delay, |d| {
+            d.min(MAX_ROBOTS_CRAWL_DELAY).max(self.crawl_delay)
+        });
+        self.wait_turn(url, delay).await?;
+        self.send(url.as_str()).await
+    }
+
+    async fn send(&self, url: &str) -> Result<reqwest::Response, CrawlError> {
+        if self.public_only {
+            let response = send_public(&self.client, Method::GET, url, |request| {
+                request.header(USER_AGENT, &self.user_agent)
+            })
+            .await?;
+            return Ok(response);
+        }
+        Ok(self.client.get(url).header(USER_AGENT, &self.user_agent).send().await?)
+    }
+
+    async fn fetch_robots(&self, url: &Url, origin: &str) -> Robots {
+        if self.wait_turn(url, self.crawl_delay).await.is_err() {
+            return Robots::Unreachable;
+        }
+        let response = self.send(&format!("{origin}/robots.txt")).await;
+        match response {
+            Ok(response) if response.status().is_success() => match response.text().await {
+                Ok(body) => Robots::Rules(parse_robots(&body)),
+                Err(_) => Robots::Unreachable,
+            },
+            // RFC 9309 §2.3.1.3: a missing robots.txt (4xx) means no restrictions
+            Ok(response) if response.status().is_client_error() => Robots::Rules(RobotsTxt::default()),
+            _ => Robots::Unreachable,
+        }
+    }
+
+    /// Reserve the host's next request slot and sleep until it. Slots are `delay` apart.
+    async fn wait_turn(&self, url: &Url, delay: Duration) -> Result<(), CrawlError> {
+        let host = url.host_str().unwrap_or_default().to_string();
+        let now = Instant::now();
+        let start = {
+            let mut next_request = CRAWL_STATE.next_request.lock().unwrap();
+            // Hosts whose turn has passed need no entry
+            next_request.retain(|_, next| *next > now);
+            let start = next_request.get(&host).copied().unwrap_or(now).max(now);
+            if start - now > MAX_QUEUE_WAIT {
+                return Err(CrawlError::Throttled);
+            }
+            next_request.insert(host, start + delay);
+            start
+        };
+        tokio::time::sleep_until(start).await;
+        Ok(())
+    }
+}
+
+/// The group naming `pail`, else the `*` group (RFC 9309 §2.2.1). Consecutive `User-agent`
+/// lines share one group; empty `Disallow` lines allow everything and are dropped.
+fn parse_robots(body: &str) -> RobotsTxt {
+    let (mut ours, mut wildcard) = (RobotsTxt::default(), RobotsTxt::default());
+    let mut found_ours = false;
+    let mut agents: Vec<String> = Vec::new();
+    let mut in_rules = false;
+
+    for line in body.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (key, value) = (key.trim().to_ascii_lowercase(), value.trim());
+        let group = if agents.iter().any(|a| a == ROBOTS_AGENT) {
+            Some(&mut ours)
+        } else if agents.iter().any(|a| a == "*") {
+            Some(&mut wildcard)
+        } else {
+            None
+        };
+        match key.as_str() {
+            "user-agent" => {
+                if in_rules {
+                    agents.clear();
+                    in_rules = false;
+                }
+                let agent = value.to_ascii_lowercase();
+                found_ours |= agent == ROBOTS_AGENT;
+                agents.push(agent);
+            }
+            "allow" | "disallow" => {
+                in_rules = true;
+                if let Some(group) = group
+                    && !value.is_empty()
+                {
+                    group.rules.push(RobotsRule {
+                        allow: key == "allow",
+                        pattern: value.to_string(),
+                    });
+                }
+            }
+            // Not in RFC 9309, but widely used; seconds, possibly fractional
+            "crawl-delay" => {
+                in_rules = true;
+                if let Some(group) = group {
+                    group.crawl_delay = value
+                        .parse::<f64>()
+                        .ok()
+                        .and_then(|secs| Duration::try_from_secs_f64(secs).ok());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if found_ours { ours } else { wildcard }
+}
+
+/// The longest matching pattern decides; `Allow` wins a tie. No match means allowed.
+fn is_allowed(rules: &[RobotsRule], path: &str) -> bool {
+    rules
+        .iter()
+        .filter(|r| pattern_matches(&r.pattern, path))
+        .max_by_key(|r| (r.pattern.len(), r.allow))
+        .is_none_or(|r| r.allow)
+}
+
+/// robots.txt path pattern: prefix match, `*` matches any run of characters, a trailing `$`
+/// anchors the end.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = path.strip_prefix(parts.next().unwrap_or("")) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        if anchored && i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_public_ip() {
+        for ip in ["93.184.216.34", "1.1.1.1", "2606:4700:4700::1111", "100.128.0.1"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "224.0.0.1",
+            "198.18.0.1",
+            "192.0.2.1",
+            "240.0.0.1",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "ff02::1",
+            "2001:db8::1",
+            "::ffff:127.0.0.1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[test]
+    fn test_pattern_matches() {
+        assert!(pattern_matches("/", "/anything"));
+        assert!(pattern_matches("/private", "/private/page"));
+        assert!(pattern_matches("/private", "/private-notes"));
+        assert!(!pattern_matches("/private", "/public/private"));
+        assert!(pattern_matches("/*.pdf", "/docs/file.pdf"));
+        assert!(pattern_matches("/*.pdf", "/docs/file.pdf?download=1"));
+        assert!(pattern_matches("/*.pdf$", "/docs/file.pdf"));
+        assert!(!pattern_matches("/*.pdf$", "/docs/file.pdf?download=1"));
+        assert!(pattern_matches("/a/*/c", "/a/b/c/d"));
+        assert!(!pattern_matches("/a/*/c", "/a/b/d"));
+        assert!(pattern_matches("/exact$", "/exact"));
+        assert!(!pattern_matches("/exact$", "/exact/more"));
+        assert!(pattern_matches("/*/end$", "/x/y/end"));
+    }
+
+    #[test]
+    fn test_parse_robots_prefers_own_group() {
+        let robots = parse_robots(
+            "# comment\n\
+             User-agent: *\n\
+             Disallow: /\n\
+             \n\
+             User-agent: Googlebot\n\
+             User-Agent: PAIL\n\
+             Disallow: /private # inline comment\n\
+             Allow: /private/open\n\
+             Disallow:\n\
+             Crawl-delay: 2.5\n\
+             \n\
+             User-agent: other\n\
+             Disallow: /other\n",
+        );
+        assert_eq!(robots.crawl_delay, Some(Duration::from_millis(2500)));
+        let patterns: Vec<(bool, &str)> = robots.rules.iter().map(|r| (r.allow, r.pattern.as_str())).collect();
+        assert_eq!(patterns, vec![(false, "/private"), (true, "/private/open")]);
+        assert!(is_allowed(&robots.rules, "/"));
+        assert!(!is_allowed(&robots.rules, "/private/secret"));
+        assert!(is_allowed(&robots.rules, "/private/open/page"));
+    }
+
+    #[test]
+    fn test_parse_robots_wildcard_group() {
+        let robots = parse_robots("User-agent: bot-a\nUser-agent: *\nDisallow: /tmp\nAllow: /tmp$\n");
+        assert!(!is_allowed(&robots.rules, "/tmp/x"));
+        // Equal length: `Allow` wins the tie
+        let robots = parse_robots("User-agent: *\nDisallow: /page\nAllow: /page\n");
+        assert!(is_allowed(&robots.rules, "/page"));
+        // No group applies: everything is allowed
+        let robots = parse_robots("User-agent: other\nDisallow: /\n");
+        assert!(robots.rules.is_empty());
+        assert!(robots.crawl_delay.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_ensure_public() {
+        let check = |url: &str| {
+            let url = Url::parse(url).unwrap();
+            async move { ensure_public(&url).await }
+        };
+        assert!(check("https://93.184.216.34/page").await.is_ok());
+        assert!(matches!(
+            check("http://localhost:8080/").await,
+            Err(PublicUrlError::NotPublic { .. })
+        ));
+        assert!(matches!(
+            check("http://[fd12::1]/").await,
+            Err(PublicUrlError::NotPublic { .. })
+        ));
+        assert!(matches!(
+            check("ftp://93.184.216.34/").await,
+            Err(PublicUrlError::InvalidUrl(_))
+        ));
+    }
+}
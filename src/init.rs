@@ -0,0 +1,293 @@
+//! `pail init`: first-run setup. Asks for the basics (data directory, timezone, model, a first
+//! RSS source and a first channel), writes a config file that validates, and optionally logs in
+//! to Telegram (see docs/specs/cli.md "init").
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use inquire::validator::Validation;
+use inquire::{Confirm, CustomType, InquireError, Text};
+use toml_edit::{Array, DocumentMut, Item, Table};
+
+use crate::config::{self, BUILTIN_MODEL, DEFAULT_TG_ACCOUNT};
+use crate::config_edit::{self, NewSource};
+use crate::{db, generate, telegram};
+
+/// Answers to the setup questions.
+struct Answers {
+    data_dir: String,
+    timezone: String,
+    model: String,
+    source_name: String,
+    source_url: String,
+    channel_name: String,
+    channel_slug: String,
+    schedule: String,
+    prompt: String,
+    /// `api_id` and `api_hash` from my.telegram.org.
+    telegram: Option<(i32, String)>,
+}
+
+/// Run the setup and write `config_path`. Refuses to replace an existing file unless `force`.
+pub async fn run(config_path: &Path, force: bool) -> Result<()> {
+    if config_path.exists() && !force {
+        anyhow::bail!(
+            "{} already exists; edit it, or run 'pail init --force' to replace it",
+            config_path.display()
+        );
+    }
+
+    println!(
+        "Setting up {}. Press Esc to abort; nothing is written until the end.\n",
+        config_path.display()
+    );
+    let answers = match ask() {
+        Ok(answers) => answers,
+        Err(e) if is_cancel(&e) => {
+            println!("Aborted; nothing was written.");
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    let content = render(&answers);
+
+    // Checked before writing, so an answer the validator rejects leaves no half-valid file
+    let parsed: config::Config = toml::from_str(&content).context("parsing the generated config")?;
+    config::validate_config(&parsed).context("the generated config is invalid")?;
+    if let Some(parent) = config_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+    }
+    std::fs::write(config_path, &content).with_context(|| format!("writing {}", config_path.display()))?;
+    println!("\nWrote {}.", config_path.display());
+
+    if answers.telegram.is_some()
+        && Confirm::new("Log in to Telegram now?")
+            .with_default(true)
+            .with_help_message("Same as 'pail tg login'; you can run that later instead")
+            .prompt()?
+    {
+        telegram_login(config_path).await?;
+    }
+
+    let config_flag = if config_path == Path::new("config.toml") {
+        String::new()
+    } else {
+        format!(" --config {}", config_path.display())
+    };
+    println!("\nNext steps:");
+    println!(
+        "  pail{config_flag} generate {}  (a digest right away)",
+        answers.channel_slug
+    );
+    println!("  pail{config_flag}  (the daemon: polling, schedules and feeds)");
+    println!("See config.example.toml for every option.");
+    Ok(())
+}
+
+/// Whether an error is the user leaving a prompt (Esc or Ctrl-C).
+fn is_cancel(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<InquireError>()
+        .is_some_and(|ie| matches!(ie, InquireError::OperationCanceled | InquireError::OperationInterrupted))
+}
+
+fn ask() -> Result<Answers> {
+    let data_dir = Text::new("Data directory:")
+        .with_default("./data")
+        .with_help_message("Database and other state; relative to where pail runs")
+        .prompt()?;
+    let timezone = Text::new("Timezone:")
+        .with_default(&local_timezone())
+        .with_help_message("IANA name, for schedules and dates (e.g. Europe/Kyiv)")
+        .with_validator(|tz: &str| {
+            Ok(match tz.parse::<chrono_tz::Tz>() {
+                Ok(_) => Validation::Valid,
+                Err(_) => Validation::Invalid("not an IANA timezone name".into()),
+            })
+        })
+        .prompt()?;
+    let model = Text::new("opencode model:")
+        .with_default(BUILTIN_MODEL)
+        .with_help_message("provider/model as opencode names it ('opencode models' lists them)")
+        .with_validator(|model: &str| {
+            Ok(if model.trim().is_empty() {
+                Validation::Invalid("a model is required".into())
+            } else {
+                Validation::Valid
+            })
+        })
+        .prompt()?;
+
+    println!("\nFirst source: an RSS or Atom feed. Add more later with 'pail sources add'.");
+    let source_name = Text::new("Source name:").with_default("Hacker News").prompt()?;
+    let source_url = Text::new("Feed URL:")
+        .with_default("https://hnrss.org/frontpage")
+        .with_validator(|url: &str| {
+            Ok(if url.starts_with("https://") || url.starts_with("http://") {
+                Validation::Valid
+            } else {
+                Validation::Invalid("must start with http:// or https://".into())
+            })
+        })
+        .prompt()?;
+
+    println!("\nFirst output channel: a digest of the source, served as an Atom feed.");
+    let channel_name = Text::new("Channel name:").with_default("Daily Digest").prompt()?;
+    let channel_slug = Text::new("Channel slug:")
+        .with_default(&generate::slug_from_name(&channel_name))
+        .with_help_message("In the feed URL: lowercase letters, digits and hyphens")
+        .with_validator(|slug: &str| {
+            let valid = !slug.is_empty()
+                && !slug.starts_with('-')
+                && !slug.ends_with('-')
+                && slug
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+            Ok(if valid {
+                Validation::Valid
+            } else {
+                Validation::Invalid("lowercase letters, digits and hyphens, not starting or ending with one".into())
+            })
+        })
+        .prompt()?;
+    let schedule = Text::new("Schedule:")
+        .with_default("at:08:00")
+        .with_help_message("at:HH:MM[,HH:MM], weekly:DAY[,DAY],HH:MM, monthly:D[,D],HH:MM or cron:EXPR")
+        .with_validator(|schedule: &str| {
+            Ok(match config::validate_schedule(schedule) {
+                Ok(()) => Validation::Valid,
+                Err(e) => Validation::Invalid(e.into()),
+            })
+        })
+        .prompt()?;
+    let prompt = Text::new("What should the digest cover?")
+        .with_default("Write a daily digest of the most important stories, with a short take on each.")
+        .with_help_message("The editorial directive; edit `prompt` in the config for more")
+        .prompt()?;
+
+    println!();
+    let telegram = if Confirm::new("Set up Telegram?")
+        .with_default(false)
+        .with_help_message("Needs an API ID and hash from https://my.telegram.org")
+        .prompt()?
+    {
+        let api_id = CustomType::<i32>::new("API ID:")
+            .with_error_message("a number, as shown on my.telegram.org")
+            .prompt()?;
+        let api_hash = Text::new("API hash:")
+            .with_validator(|hash: &str| {
+                Ok(if hash.trim().is_empty() {
+                    Validation::Invalid("the API hash is required".into())
+                } else {
+                    Validation::Valid
+                })
+            })
+            .prompt()?;
+        Some((api_id, api_hash.trim().to_string()))
+    } else {
+        None
+    };
+
+    Ok(Answers {
+        data_dir,
+        timezone,
+        model: model.trim().to_string(),
+        source_name: source_name.trim().to_string(),
+        source_url: source_url.trim().to_string(),
+        channel_name: channel_name.trim().to_string(),
+        channel_slug,
+        schedule,
+        prompt: prompt.trim().to_string(),
+        telegram,
+    })
+}
+
+/// The config file for the answers, commented like config.example.toml.
+fn render(answers: &Answers) -> String {
+    let mut doc = DocumentMut::new();
+    doc.decor_mut().set_prefix(
+        "# pail configuration, written by `pail init`.\n\
+         # Every option is documented in config.example.toml.\n\n",
+    );
+
+    let mut pail = Table::new();
+    pail.insert("version", toml_edit::value(1i64));
+    pail.insert("data_dir", toml_edit::value(&answers.data_dir));
+    pail.insert("timezone", toml_edit::value(&answers.timezone));
+    doc.insert("pail", Item::Table(pail));
+
+    let mut opencode = Table::new();
+    opencode.decor_mut().set_prefix("\n");
+    opencode.insert("default_model", toml_edit::value(&answers.model));
+    doc.insert("opencode", Item::Table(opencode));
+
+    if let Some((api_id, ref api_hash)) = answers.telegram {
+        let mut telegram = Table::new();
+        telegram.decor_mut().set_prefix("\n");
+        telegram.insert("enabled", toml_edit::value(true));
+        telegram.insert("api_id", toml_edit::value(i64::from(api_id)));
+        telegram.insert("api_hash", toml_edit::value(api_hash));
+        doc.insert("telegram", Item::Table(telegram));
+    }
+
+    config_edit::add_source(
+        &mut doc,
+        &NewSource {
+            name: answers.source_name.clone(),
+            source_type: "rss".to_string(),
+            url: Some(answers.source_url.clone()),
+            tg_username: None,
+            tg_id: None,
+            tg_folder_name: None,
+            description: None,
+        },
+    );
+
+    let mut channel = Table::new();
+    channel.decor_mut().set_prefix("\n");
+    channel.insert("name", toml_edit::value(&answers.channel_name));
+    channel.insert("slug", toml_edit::value(&answers.channel_slug));
+    channel.insert("schedule", toml_edit::value(&answers.schedule));
+    let mut sources = Array::new();
+    sources.push(answers.source_name.as_str());
+    channel.insert("sources", toml_edit::value(sources));
+    channel.insert("prompt", toml_edit::value(&answers.prompt));
+    let mut channels = toml_edit::ArrayOfTables::new();
+    channels.push(channel);
+    doc.insert("output_channel", Item::ArrayOfTables(channels));
+
+    config_edit::render(&doc)
+}
+
+/// `pail tg login` for the default account of the config just written.
+async fn telegram_login(config_path: &Path) -> Result<()> {
+    let config = config::load_config(config_path)?;
+    let pool = db::create_pool(&config).await.context("creating database")?;
+    let conn = telegram::connect(&config, &pool, DEFAULT_TG_ACCOUNT)
+        .await
+        .context("connecting to Telegram")?;
+    let result = telegram::login(&conn.client, &config, DEFAULT_TG_ACCOUNT)
+        .await
+        .context("Telegram login");
+    conn.client.disconnect();
+    conn.runner_handle.abort();
+    result?;
+    println!("Session saved. Add Telegram sources with 'pail config edit'.");
+    Ok(())
+}
+
+/// The system timezone from `TZ` or `/etc/localtime`, else UTC.
+fn local_timezone() -> String {
+    let from_env = std::env::var("TZ")
+        .ok()
+        .map(|tz| tz.trim_start_matches(':').to_string());
+    // /etc/localtime links into the zoneinfo tree: .../zoneinfo/Europe/Kyiv
+    let from_link = || {
+        let target = std::fs::read_link("/etc/localtime").ok()?;
+        let target = target.to_str()?;
+        Some(target.split_once("zoneinfo/")?.1.to_string())
+    };
+    from_env
+        .or_else(from_link)
+        .filter(|tz| tz.parse::<chrono_tz::Tz>().is_ok())
+        .unwrap_or_else(|| "UTC".to_string())
+}
@@ -0,0 +1,115 @@
+//! Single-instance lock: an instance that runs jobs (`worker` or `all`) holds an exclusive
+//! lock on `<database>.lock` for as long as it runs, so a second one refuses to start and the
+//! CLI can tell a daemon is running (see docs/specs/daemon.md "Instance Lock").
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+/// Who holds the lock, written into the lock file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockHolder {
+    /// Host and process ID, as in generation claims.
+    pub instance: String,
+    pub role: String,
+    pub started_at: DateTime<Utc>,
+}
+
+impl std::fmt::Display for LockHolder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (role {}, started {})",
+            self.instance,
+            self.role,
+            self.started_at.format("%Y-%m-%d %H:%M:%S UTC")
+        )
+    }
+}
+
+/// The held lock. The operating system releases it when the file is closed, including when
+/// the process dies, so a crash never leaves it behind.
+pub struct InstanceLock {
+    _file: File,
+}
+
+/// The lock file: next to the database, so every instance sharing it finds the same one.
+pub fn lock_path(config: &Config) -> PathBuf {
+    let mut path = config.db_path().into_os_string();
+    path.push(".lock");
+    PathBuf::from(path)
+}
+
+/// Take the lock for this process. Fails if another instance holds it.
+pub fn acquire(config: &Config, instance: &str, role: &str) -> Result<InstanceLock> {
+    let path = lock_path(config);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("creating data directory: {}", parent.display()))?;
+    }
+    // Not truncated on open: until the lock is ours, the contents are the holder's
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .with_context(|| format!("opening lock file {}", path.display()))?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            let holder = read_holder(&mut file)
+                .map(|h| h.to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            anyhow::bail!(
+                "another pail instance is running jobs on this database: {holder} (lock {})",
+                path.display()
+            );
+        }
+        Err(TryLockError::Error(e)) => {
+            return Err(e).with_context(|| format!("locking {}", path.display()));
+        }
+    }
+
+    let holder = LockHolder {
+        instance: instance.to_string(),
+        role: role.to_string(),
+        started_at: Utc::now(),
+    };
+    file.set_len(0).context("truncating lock file")?;
+    file.rewind().context("rewinding lock file")?;
+    file.write_all(serde_json::to_string(&holder)?.as_bytes())
+        .context("writing lock file")?;
+    Ok(InstanceLock { _file: file })
+}
+
+/// The instance holding the lock, if one is running.
+pub fn holder(config: &Config) -> Result<Option<LockHolder>> {
+    let path = lock_path(config);
+    let mut file = match File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("opening lock file {}", path.display())),
+    };
+    match file.try_lock_shared() {
+        // Free: whatever the file says is left from an instance that exited
+        Ok(()) => Ok(None),
+        Err(TryLockError::WouldBlock) => Ok(Some(read_holder(&mut file).unwrap_or_else(|| LockHolder {
+            instance: "unknown".to_string(),
+            role: "unknown".to_string(),
+            started_at: DateTime::UNIX_EPOCH,
+        }))),
+        Err(TryLockError::Error(e)) => Err(e).with_context(|| format!("checking lock {}", path.display())),
+    }
+}
+
+fn read_holder(file: &mut File) -> Option<LockHolder> {
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).ok()?;
+    serde_json::from_str(&contents).ok()
+}
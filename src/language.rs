@@ -0,0 +1,192 @@
+//! Language detection on ingestion and per-source / per-channel language filters
+//! (see docs/specs/language-filtering.md).
+
+use std::collections::HashMap;
+
+use tracing::info;
+
+use crate::config::{Config, OutputChannelConfig};
+use crate::models::{ContentItem, Source};
+
+/// Characters of title + body looked at; more doesn't change the result, only the cost.
+const SAMPLE_CHARS: usize = 1000;
+
+/// ISO 639-3 codes whatlang reports, mapped to the ISO 639-1 code stored and configured.
+const ISO_639_1: &[(&str, &str)] = &[
+    ("afr", "af"),
+    ("aka", "ak"),
+    ("amh", "am"),
+    ("ara", "ar"),
+    ("aze", "az"),
+    ("bel", "be"),
+    ("ben", "bn"),
+    ("bul", "bg"),
+    ("cat", "ca"),
+    ("ces", "cs"),
+    ("cmn", "zh"),
+    ("dan", "da"),
+    ("deu", "de"),
+    ("ell", "el"),
+    ("eng", "en"),
+    ("epo", "eo"),
+    ("est", "et"),
+    ("fin", "fi"),
+    ("fra", "fr"),
+    ("guj", "gu"),
+    ("heb", "he"),
+    ("hin", "hi"),
+    ("hrv", "hr"),
+    ("hun", "hu"),
+    ("hye", "hy"),
+    ("ind", "id"),
+    ("ita", "it"),
+    ("jav", "jv"),
+    ("jpn", "ja"),
+    ("kan", "kn"),
+    ("kat", "ka"),
+    ("khm", "km"),
+    ("kor", "ko"),
+    ("lat", "la"),
+    ("lav", "lv"),
+    ("lit", "lt"),
+    ("mal", "ml"),
+    ("mar", "mr"),
+    ("mkd", "mk"),
+    ("mya", "my"),
+    ("nep", "ne"),
+    ("nld", "nl"),
+    ("nob", "nb"),
+    ("ori", "or"),
+    ("pan", "pa"),
+    ("pes", "fa"),
+    ("pol", "pl"),
+    ("por", "pt"),
+    ("ron", "ro"),
+    ("rus", "ru"),
+    ("sin", "si"),
+    ("slk", "sk"),
+    ("slv", "sl"),
+    ("sna", "sn"),
+    ("spa", "es"),
+    ("srp", "sr"),
+    ("swe", "sv"),
+    ("tam", "ta"),
+    ("tel", "te"),
+    ("tgl", "tl"),
+    ("tha", "th"),
+    ("tuk", "tk"),
+    ("tur", "tr"),
+    ("ukr", "uk"),
+    ("urd", "ur"),
+    ("uzb", "uz"),
+    ("vie", "vi"),
+    ("yid", "yi"),
+    ("zul", "zu"),
+];
+
+/// Code stored for a detected language: ISO 639-1 where one exists, else whatlang's 639-3.
+fn code(lang: whatlang::Lang) -> &'static str {
+    let iso3 = lang.code();
+    ISO_639_1
+        .iter()
+        .find(|(three, _)| *three == iso3)
+        .map_or(iso3, |(_, two)| two)
+}
+
+/// Whether `code` is a language the detector can report (for config validation).
+pub fn is_known(code: &str) -> bool {
+    whatlang::Lang::all().iter().any(|lang| self::code(*lang) == code)
+}
+
+/// English name of a language code `is_known` accepts ("uk" is "Ukrainian").
+pub fn name(code: &str) -> Option<&'static str> {
+    whatlang::Lang::all()
+        .iter()
+        .find(|lang| self::code(**lang) == code)
+        .map(|lang| lang.eng_name())
+}
+
+/// Detected language of an item's title and body, or `None` when the text is too short or
+/// mixed for a reliable guess.
+pub fn detect(item: &ContentItem) -> Option<&'static str> {
+    let mut sample = String::new();
+    if let Some(ref title) = item.title {
+        sample.push_str(title);
+        sample.push('\n');
+    }
+    sample.extend(item.body.chars().take(SAMPLE_CHARS));
+    whatlang::detect(&sample)
+        .filter(|info| info.is_reliable())
+        .map(|info| code(info.lang()))
+}
+
+/// `item.metadata` with the detected `language` added. Items that already carry one, or whose
+/// language can't be detected reliably, keep their metadata as is.
+pub fn with_language(item: &ContentItem) -> String {
+    let Some(language) = detect(item) else {
+        return item.metadata.clone();
+    };
+    match serde_json::from_str::<serde_json::Value>(&item.metadata) {
+        Ok(serde_json::Value::Object(mut meta)) if !meta.contains_key("language") => {
+            meta.insert("language".to_string(), language.into());
+            serde_json::Value::Object(meta).to_string()
+        }
+        _ => item.metadata.clone(),
+    }
+}
+
+/// Stored language of an item, if detected.
+fn item_language(item: &ContentItem) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(&item.metadata)
+        .ok()?
+        .get("language")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Drop items whose detected language isn't in their source's or the channel's `languages`.
+/// Items without a detected language are kept. No-op when neither sets `languages`.
+pub fn filter_items(
+    config: &Config,
+    channel_config: &OutputChannelConfig,
+    sources: &[Source],
+    items: Vec<ContentItem>,
+) -> Vec<ContentItem> {
+    // Source filters by DB source ID (config sources are keyed by name)
+    let source_languages: HashMap<&str, &[String]> = sources
+        .iter()
+        .filter_map(|source| {
+            let configured = config.source.iter().find(|s| s.name == source.name)?;
+            (!configured.languages.is_empty()).then_some((source.id.as_str(), configured.languages.as_slice()))
+        })
+        .collect();
+    if channel_config.languages.is_empty() && source_languages.is_empty() {
+        return items;
+    }
+
+    let total = items.len();
+    let kept: Vec<ContentItem> = items
+        .into_iter()
+        .filter(|item| {
+            let Some(language) = item_language(item) else {
+                return true;
+            };
+            let allowed = |list: &[String]| list.is_empty() || list.contains(&language);
+            allowed(&channel_config.languages)
+                && allowed(
+                    source_languages
+                        .get(item.source_id.as_str())
+                        .copied()
+                        .unwrap_or_default(),
+                )
+        })
+        .collect();
+
+    info!(
+        channel = %channel_config.slug,
+        total,
+        kept = kept.len(),
+        "language filter applied"
+    );
+    kept
+}
@@ -0,0 +1,351 @@
+//! Post-generation link verification: every link of a generated article that isn't one of the
+//! window's items is requested, and dead ones are recorded or replaced with a footnote
+//! (see docs/specs/link-verification.md).
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use pulldown_cmark::{Event, LinkType, Parser, Tag};
+use reqwest::redirect::Policy;
+use reqwest::{Method, StatusCode};
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::config::{NetworkConfig, OutputChannelConfig};
+use crate::error::PublicUrlError;
+use crate::models::{ContentItem, GeneratedArticle};
+use crate::{generate, http_client};
+
+/// Links requested in parallel.
+const CONCURRENT_CHECKS: usize = 8;
+
+/// Per-link timeout; a slow site is unverified, not dead.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Links checked per article; the rest are reported as unchecked.
+const MAX_CHECKED_LINKS: usize = 200;
+
+/// Result of requesting one link.
+enum Verdict {
+    Ok,
+    /// 404 / 410, or not a valid URL.
+    Dead(String),
+    /// DNS failure, refused connection, TLS error: dead, unless nothing was reachable at all.
+    Unreachable(String),
+    /// Timeouts, 5xx, 401 / 403 / 429 and the like: the link may well work for a reader.
+    Unverified(String),
+}
+
+/// A link in the article body.
+struct Link {
+    url: String,
+    /// Byte range of the whole link in the body.
+    range: Range<usize>,
+    link_type: LinkType,
+}
+
+/// Check the article's links per the channel's `verify_links`, record the report under
+/// `link_check` in the article metadata, and with `rewrite` replace dead links with their text
+/// and a footnote. No-op for channels without `verify_links`.
+pub async fn verify_links(
+    channel_config: &OutputChannelConfig,
+    network: &NetworkConfig,
+    items: &[ContentItem],
+    article: &mut GeneratedArticle,
+    cancel: &CancellationToken,
+) {
+    let Some(ref action) = channel_config.verify_links else {
+        return;
+    };
+
+    // The opencode session link appended by pail isn't the model's output
+    let body_len = article
+        .body_markdown
+        .find("\n\n---\n\n[opencode session](")
+        .unwrap_or(article.body_markdown.len());
+    let links = extract_links(&article.body_markdown[..body_len]);
+
+    // Item links came from the sources; the ones worth checking are those the model wrote itself
+    let known: HashSet<String> = items
+        .iter()
+        .flat_map(|item| {
+            let meta: serde_json::Value = serde_json::from_str(&item.metadata).unwrap_or_default();
+            let discussion = meta.get("discussion_url").and_then(|v| v.as_str()).map(str::to_string);
+            item.url.clone().into_iter().chain(discussion)
+        })
+        .map(|url| generate::normalize_link(&url))
+        .collect();
+    let mut seen = HashSet::new();
+    let candidates: Vec<String> = links
+        .iter()
+        .map(|link| link.url.clone())
+        .filter(|url| !known.contains(&generate::normalize_link(url)))
+        .filter(|url| seen.insert(url.clone()))
+        .collect();
+    let unchecked = candidates.len().saturating_sub(MAX_CHECKED_LINKS);
+
+    // Redirects are followed by `send_public`, which checks every hop
+    let builder = reqwest::Client::builder()
+        .timeout(CHECK_TIMEOUT)
+        .redirect(Policy::none())
+        .user_agent(concat!("pail/", env!("CARGO_PKG_VERSION")));
+    let client = match http_client::with_proxy(builder, network.proxy.as_deref()).and_then(|builder| builder.build()) {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("link check skipped, building HTTP client failed: {e:#}");
+            return;
+        }
+    };
+    let verdicts: HashMap<String, Verdict> = futures_util::stream::iter(candidates.into_iter().take(MAX_CHECKED_LINKS))
+        .map(|url| {
+            let client = &client;
+            async move {
+                let verdict = check_link(client, &url).await;
+                (url, verdict)
+            }
+        })
+        .buffer_unordered(CONCURRENT_CHECKS)
+        .take_until(cancel.cancelled())
+        .collect()
+        .await;
+
+    // If no link got any response, the network is the problem, not the links
+    let any_reachable = verdicts.values().any(|v| !matches!(v, Verdict::Unreachable(_)));
+    let mut dead: HashMap<&str, String> = HashMap::new();
+    let mut unverified = Vec::new();
+    for (url, verdict) in &verdicts {
+        match verdict {
+            Verdict::Ok => {}
+            Verdict::Dead(reason) => {
+                dead.insert(url, reason.clone());
+            }
+            Verdict::Unreachable(reason) if any_reachable => {
+                dead.insert(url, reason.clone());
+            }
+            Verdict::Unreachable(reason) | Verdict::Unverified(reason) => {
+                unverified.push(json!({ "url": url, "reason": reason }));
+            }
+        }
+    }
+
+    let rewritten = if action == "rewrite" && !dead.is_empty() {
+        let (body, count) = rewrite(&article.body_markdown, &links, &dead);
+        article.body_markdown = body;
+        article.body_html = generate::markdown_to_html(&article.body_markdown);
+        count
+    } else {
+        0
+    };
+
+    info!(
+        channel = %channel_config.slug,
+        links = links.len(),
+        checked = verdicts.len(),
+        dead = dead.len(),
+        unverified = unverified.len(),
+        rewritten,
+        "article links verified"
+    );
+    let mut dead_report: Vec<serde_json::Value> = dead
+        .iter()
+        .map(|(url, reason)| json!({ "url": url, "reason": reason }))
+        .collect();
+    dead_report.sort_by(|a, b| a["url"].as_str().cmp(&b["url"].as_str()));
+    let report = json!({
+        "checked_at": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        "links": links.len(),
+        "checked": verdicts.len(),
+        "unchecked": unchecked,
+        "dead": dead_report,
+        "unverified": unverified,
+        "rewritten": rewritten,
+    });
+    let mut metadata: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&article.metadata).unwrap_or_default();
+    metadata.insert("link_check".to_string(), report);
+    article.metadata = serde_json::Value::Object(metadata).to_string();
+}
+
+/// The http(s) links of a Markdown body, in order. Images aren't links.
+fn extract_links(markdown: &str) -> Vec<Link> {
+    Parser::new(markdown)
+        .into_offset_iter()
+        .filter_map(|(event, range)| match event {
+            Event::Start(Tag::Link {
+                link_type, dest_url, ..
+            }) if dest_url.starts_with("https://") || dest_url.starts_with("http://") => Some(Link {
+                url: dest_url.to_string(),
+                range,
+                link_type,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// HEAD, falling back to GET for servers that don't answer HEAD properly. Redirects are
+/// followed; a link that resolves to a non-public address is not requested.
+async fn check_link(client: &reqwest::Client, url: &str) -> Verdict {
+    let send = |method| http_client::send_public(client, method, url, |request| request);
+    let response = match send(Method::HEAD).await {
+        Ok(response)
+            if matches!(
+                response.status(),
+                StatusCode::METHOD_NOT_ALLOWED
+                    | StatusCode::NOT_IMPLEMENTED
+                    | StatusCode::FORBIDDEN
+                    | StatusCode::BAD_REQUEST
+                    | StatusCode::NOT_FOUND
+            ) =>
+        {
+            // Some servers 404 or 403 a HEAD they'd serve as GET
+            send(Method::GET).await
+        }
+        other => other,
+    };
+    match response {
+        Ok(response) if response.status().is_success() => Verdict::Ok,
+        Ok(response) if matches!(response.status(), StatusCode::NOT_FOUND | StatusCode::GONE) => {
+            Verdict::Dead(response.status().to_string())
+        }
+        Ok(response) => Verdict::Unverified(response.status().to_string()),
+        Err(PublicUrlError::InvalidUrl(_)) => Verdict::Dead("invalid URL".to_string()),
+        Err(e @ PublicUrlError::Unresolvable { .. }) => Verdict::Unreachable(e.to_string()),
+        Err(e @ (PublicUrlError::NotPublic { .. } | PublicUrlError::TooManyRedirects(_))) => {
+            Verdict::Unverified(e.to_string())
+        }
+        Err(PublicUrlError::Http(e)) if e.is_builder() => Verdict::Dead("invalid URL".to_string()),
+        Err(PublicUrlError::Http(e)) if e.is_connect() => Verdict::Unreachable(error_chain(&e)),
+        Err(PublicUrlError::Http(e)) if e.is_timeout() => Verdict::Unverified("timed out".to_string()),
+        Err(PublicUrlError::Http(e)) => Verdict::Unverified(error_chain(&e)),
+    }
+}
+
+/// reqwest's top-level message is just "error sending request"; the cause says what failed.
+fn error_chain(e: &reqwest::Error) -> String {
+    let mut message = e.to_string();
+    let mut source = std::error::Error::source(e);
+    while let Some(cause) = source {
+        message = format!("{message}: {cause}");
+        source = cause.source();
+    }
+    message
+}
+
+/// Replace each dead inline link or autolink with its text and a footnote naming the removed
+/// URL. Other link forms (reference links) are left alone. Returns the new body and the number
+/// of links replaced.
+fn rewrite(body: &str, links: &[Link], dead: &HashMap<&str, String>) -> (String, usize) {
+    // One footnote per dead URL, numbered in order of first appearance
+    let mut footnotes: Vec<(&str, &str)> = Vec::new();
+    let mut replacements: Vec<(Range<usize>, String)> = Vec::new();
+    for link in links {
+        let Some(reason) = dead.get(link.url.as_str()) else {
+            continue;
+        };
+        let source = &body[link.range.clone()];
+        let text = match link.link_type {
+            LinkType::Inline => match source.rfind("](") {
+                Some(end) if source.starts_with('[') => source[1..end].to_string(),
+                _ => continue,
+            },
+            LinkType::Autolink => format!("`{}`", link.url),
+            _ => continue,
+        };
+        let number = match footnotes.iter().position(|(url, _)| *url == link.url) {
+            Some(index) => index + 1,
+            None => {
+                footnotes.push((link.url.as_str(), reason.as_str()));
+                footnotes.len()
+            }
+        };
+        replacements.push((link.range.clone(), format!("{text}[^dead-link-{number}]")));
+    }
+
+    if replacements.is_empty() {
+        return (body.to_string(), 0);
+    }
+    let mut rewritten = body.to_string();
+    for (range, replacement) in replacements.iter().rev() {
+        rewritten.replace_range(range.clone(), replacement);
+    }
+    rewritten.push_str("\n\n");
+    for (number, (url, reason)) in footnotes.iter().enumerate() {
+        rewritten.push_str(&format!(
+            "[^dead-link-{}]: Link removed, it didn't resolve: `{url}` ({reason}).\n",
+            number + 1
+        ));
+    }
+    (rewritten, replacements.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_links() {
+        let body = "See [the post](https://example.com/a) and <https://example.com/b>.\n\n\
+                    ![chart](https://example.com/c.png) [mail](mailto:x@example.com) \
+                    [rel](/local) [ref][1]\n\n[1]: http://example.com/d\n";
+        let links = extract_links(body);
+        let urls: Vec<&str> = links.iter().map(|l| l.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec!["https://example.com/a", "https://example.com/b", "http://example.com/d"]
+        );
+        assert_eq!(&body[links[0].range.clone()], "[the post](https://example.com/a)");
+        assert_eq!(links[0].link_type, LinkType::Inline);
+        assert_eq!(&body[links[1].range.clone()], "<https://example.com/b>");
+        assert_eq!(links[1].link_type, LinkType::Autolink);
+        assert_eq!(links[2].link_type, LinkType::Reference);
+    }
+
+    #[test]
+    fn test_rewrite_dead_links() {
+        let body = "A [first](https://dead.example/x), <https://dead.example/x>, \
+                    [ok](https://ok.example/) and [second **bold**](https://gone.example/y).\n\n\
+                    [ref][1]\n\n[1]: https://gone.example/y\n";
+        let links = extract_links(body);
+        let dead = HashMap::from([
+            ("https://dead.example/x", "404 Not Found".to_string()),
+            ("https://gone.example/y", "410 Gone".to_string()),
+        ]);
+        let (rewritten, count) = rewrite(body, &links, &dead);
+        // The reference link is left alone
+        assert_eq!(count, 3);
+        assert_eq!(
+            rewritten,
+            "A first[^dead-link-1], `https://dead.example/x`[^dead-link-1], \
+             [ok](https://ok.example/) and second **bold**[^dead-link-2].\n\n\
+             [ref][1]\n\n[1]: https://gone.example/y\n\n\n\
+             [^dead-link-1]: Link removed, it didn't resolve: `https://dead.example/x` (404 Not Found).\n\
+             [^dead-link-2]: Link removed, it didn't resolve: `https://gone.example/y` (410 Gone).\n"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_nothing_dead() {
+        let body = "[ok](https://ok.example/)";
+        let (rewritten, count) = rewrite(body, &extract_links(body), &HashMap::new());
+        assert_eq!((rewritten.as_str(), count), (body, 0));
+    }
+
+    #[tokio::test]
+    async fn test_check_link_refuses_private_addresses() {
+        let client = reqwest::Client::builder().redirect(Policy::none()).build().unwrap();
+        for url in [
+            "http://127.0.0.1:9/",
+            "http://[::1]:9/",
+            "http://169.254.169.254/latest/meta-data/",
+        ] {
+            let Verdict::Unverified(reason) = check_link(&client, url).await else {
+                panic!("{url} was not refused");
+            };
+            assert!(reason.contains("not a public address"), "{reason}");
+        }
+    }
+}
@@ -0,0 +1,262 @@
+//! Live opencode output: `opencode run`'s stdout and stderr are read line by line as they're
+//! written, logged (rate-limited), mirrored to `live_runs` for `GET /runs/{id}/live`, and
+//! watched for silence with `[opencode].idle_timeout` (see docs/specs/generation-engine.md
+//! "Live Output").
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use sqlx::SqlitePool;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::generate::strip_ansi;
+use crate::store;
+
+/// Most recent output kept for the live tail.
+const TAIL_BYTES: usize = 64 * 1024;
+
+/// How often a changed tail is written to `live_runs`.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A silent run's row is still touched this often, so cleanup can tell it from an abandoned one.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Output lines logged per `LOG_WINDOW`; the rest are counted and reported once the window ends.
+const LOG_LINES_PER_WINDOW: u32 = 30;
+const LOG_WINDOW: Duration = Duration::from_secs(60);
+
+/// Output of a running opencode process, collected as it's written.
+#[derive(Clone)]
+pub struct Output {
+    inner: Arc<Mutex<Collected>>,
+}
+
+struct Collected {
+    stdout: String,
+    stderr: String,
+    /// The last `TAIL_BYTES` of both streams, interleaved as written.
+    tail: String,
+    /// Bumped by every line, so the mirror only writes when something changed.
+    version: u64,
+    last_output: Option<Instant>,
+    started: Instant,
+    window_start: Instant,
+    logged: u32,
+    suppressed: u32,
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        let now = Instant::now();
+        Self {
+            inner: Arc::new(Mutex::new(Collected {
+                stdout: String::new(),
+                stderr: String::new(),
+                tail: String::new(),
+                version: 0,
+                last_output: None,
+                started: now,
+                window_start: now,
+                logged: 0,
+                suppressed: 0,
+            })),
+        }
+    }
+}
+
+impl Output {
+    /// Read a pipe line by line into the output until it closes.
+    pub fn read<R: AsyncRead + Unpin + Send + 'static>(&self, pipe: Option<R>, stream: &'static str) -> JoinHandle<()> {
+        let output = self.clone();
+        tokio::spawn(async move {
+            let Some(pipe) = pipe else { return };
+            let mut reader = BufReader::new(pipe);
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                match reader.read_until(b'\n', &mut line).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => output.lock().push(stream, &String::from_utf8_lossy(&line)),
+                }
+            }
+        })
+    }
+
+    /// Resolves once the process has written nothing for `idle` (since it started, if it
+    /// never has), with how long that's been. Never resolves without a limit.
+    pub async fn stalled(&self, idle: Option<Duration>) -> Duration {
+        let Some(idle) = idle else {
+            return std::future::pending().await;
+        };
+        loop {
+            let silent = {
+                let collected = self.lock();
+                collected.last_output.unwrap_or(collected.started).elapsed()
+            };
+            if silent >= idle {
+                return silent;
+            }
+            tokio::time::sleep(idle - silent).await;
+        }
+    }
+
+    /// Everything written to stdout and stderr so far.
+    pub fn collected(&self) -> (String, String) {
+        let collected = self.lock();
+        (collected.stdout.clone(), collected.stderr.clone())
+    }
+
+    /// Write the tail to the run's `live_runs` row until the returned guard is dropped.
+    pub fn mirror(&self, live: LiveSink) -> Mirror {
+        let output = self.clone();
+        Mirror(tokio::spawn(async move {
+            let mut written = 0;
+            let mut last_write = Instant::now();
+            loop {
+                tokio::time::sleep(FLUSH_INTERVAL).await;
+                let (version, tail, last_output) = {
+                    let collected = output.lock();
+                    (collected.version, collected.tail.clone(), collected.last_output)
+                };
+                if version == written && last_write.elapsed() < HEARTBEAT_INTERVAL {
+                    continue;
+                }
+                let last_output_at =
+                    last_output.map(|at| Utc::now() - chrono::Duration::from_std(at.elapsed()).unwrap_or_default());
+                if let Err(e) = store::update_live_run(&live.pool, &live.run_id, &tail, last_output_at).await {
+                    warn!(run_id = %live.run_id, "failed to update live output: {e:#}");
+                }
+                written = version;
+                last_write = Instant::now();
+            }
+        }))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Collected> {
+        self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// The task mirroring output to `live_runs`. Dropping it aborts the task, so no path out of a
+/// run (including a cancelled one) leaves it writing.
+pub struct Mirror(JoinHandle<()>);
+
+impl Drop for Mirror {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+impl Collected {
+    fn push(&mut self, stream: &'static str, line: &str) {
+        match stream {
+            "stderr" => self.stderr.push_str(line),
+            _ => self.stdout.push_str(line),
+        }
+        self.tail.push_str(line);
+        if self.tail.len() > TAIL_BYTES {
+            let cut = self.tail.ceil_char_boundary(self.tail.len() - TAIL_BYTES);
+            self.tail.drain(..cut);
+        }
+        self.version += 1;
+        self.last_output = Some(Instant::now());
+
+        let text = strip_ansi(line);
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+        if self.window_start.elapsed() >= LOG_WINDOW {
+            if self.suppressed > 0 {
+                info!(lines = self.suppressed, "opencode output lines not logged (rate limit)");
+            }
+            self.window_start = Instant::now();
+            self.logged = 0;
+            self.suppressed = 0;
+        }
+        if self.logged < LOG_LINES_PER_WINDOW {
+            self.logged += 1;
+            info!(stream, "opencode: {text}");
+        } else {
+            self.suppressed += 1;
+        }
+    }
+}
+
+/// A generation's row in `live_runs`, from its start until it's recorded in `generation_runs`.
+#[derive(Debug, Clone)]
+pub struct LiveSink {
+    pool: SqlitePool,
+    run_id: String,
+}
+
+impl LiveSink {
+    /// Record the run as running. Best-effort: without the row there's no live view, nothing else.
+    pub async fn start(pool: &SqlitePool, run_id: &str, channel_id: &str) -> Option<Self> {
+        match store::insert_live_run(pool, run_id, channel_id).await {
+            Ok(()) => Some(Self {
+                pool: pool.clone(),
+                run_id: run_id.to_string(),
+            }),
+            Err(e) => {
+                warn!(run_id, "failed to record live run: {e:#}");
+                None
+            }
+        }
+    }
+
+    pub async fn finish(self) {
+        if let Err(e) = store::delete_live_run(&self.pool, &self.run_id).await {
+            warn!(run_id = %self.run_id, "failed to delete live run: {e:#}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_trims_tail_to_a_char_boundary() {
+        let output = Output::default();
+        let mut collected = output.lock();
+        // Two-byte characters, so an odd cut would land inside one
+        let line = format!("{}\n", "é".repeat(1000));
+        for _ in 0..40 {
+            collected.push("stdout", &line);
+        }
+        collected.push("stderr", "done\n");
+
+        assert!(collected.tail.len() <= TAIL_BYTES);
+        assert!(collected.tail.len() > TAIL_BYTES - line.len());
+        assert!(collected.tail.ends_with("é\ndone\n"));
+        assert_eq!(collected.stdout.len(), 40 * line.len());
+        assert_eq!(collected.stderr, "done\n");
+        assert_eq!(collected.version, 41);
+    }
+
+    #[test]
+    fn test_push_rate_limits_logged_lines() {
+        let output = Output::default();
+        let mut collected = output.lock();
+        for i in 0..40 {
+            collected.push("stdout", &format!("line {i}\n"));
+        }
+        assert_eq!(collected.logged, LOG_LINES_PER_WINDOW);
+        assert_eq!(collected.suppressed, 10);
+
+        // Blank and escape-only lines aren't logged or counted
+        collected.push("stdout", "\n");
+        collected.push("stderr", "\x1b[0m  \n");
+        assert_eq!(collected.suppressed, 10);
+
+        // A new window starts over
+        collected.window_start = Instant::now() - LOG_WINDOW;
+        collected.push("stdout", "next\n");
+        assert_eq!(collected.logged, 1);
+        assert_eq!(collected.suppressed, 0);
+    }
+}
@@ -1,25 +1,78 @@
+mod admin;
+mod api;
+mod article_log;
 mod benchmark;
+mod breaking;
+mod budget;
+mod bundle;
 mod cleanup;
 mod cli;
+mod compress;
 mod config;
 mod config_edit;
+mod control;
 mod daemon;
 mod db;
+mod diff;
 mod error;
 mod fetch;
+mod fetch_aggregator;
+mod fetch_arxiv;
+mod fetch_ics;
+mod fetch_imap;
+mod fetch_microformats;
+mod fetch_podcast;
+mod fetch_read_later;
+mod fetch_scrape;
+mod fetch_slack;
+mod fetch_stats;
 mod fetch_tg;
+mod fetch_twitter;
 mod generate;
+mod health;
+mod http_client;
+mod init;
+mod instance_lock;
+mod language;
+mod link_check;
+mod live_run;
+mod matrix;
 mod models;
+mod notify;
+mod opml;
+mod output_format;
+mod packing;
 mod pipeline;
 mod poller;
+mod prefetch;
+mod preprocess;
+mod prompt_tune;
+mod redact;
+mod relevance;
+mod reporting;
+mod sandbox;
 mod scheduler;
+mod self_eval;
 mod server;
+mod service;
+mod source_health;
 mod store;
 mod strategy;
+mod tagging;
 mod telegram;
+mod tg_bot;
+mod tg_debug;
+mod tg_engagement;
 mod tg_listener;
+mod tg_media;
 mod tg_session;
+mod translate;
 mod tui;
+mod webhook;
+mod webmention;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::IsTerminal;
 
 use anyhow::{Context, Result};
 use clap::Parser;
@@ -28,30 +81,33 @@ use tokio_util::sync::CancellationToken;
 use tracing::info;
 use tracing_subscriber::prelude::*;
 
-use crate::cli::{BenchmarkCommands, Cli, Commands, ConfigCommands, StrategyCommands, TgCommands};
-use crate::config::{Config, OutputChannelConfig, load_config, validate_config};
+use crate::cli::{
+    AddSourceCommands, ArticlesCommands, BenchmarkCommands, ChannelsCommands, Cli, Commands, ConfigCommands,
+    CtlCommands, DbCommands, DraftsCommands, ExportCommands, ItemsCommands, PromptCommands, RunsCommands,
+    SourcesCommands, StrategyCommands, TgCommands, TgDebugCommands, TgFolderCommands, TokensCommands,
+    WorkspacesCommands,
+};
+use crate::config::{Config, DEFAULT_TG_ACCOUNT, OutputChannelConfig, is_mtproto_source, load_config, validate_config};
+use crate::config_edit::NewSource;
+use crate::control::ControlRequest;
 use crate::strategy::StrategyRegistry;
-use crate::telegram::TgConnection;
+use crate::telegram::{TgClients, TgConnection};
 
-/// Shared CLI setup for commands that run a pipeline (Generate, Interactive).
+/// Shared CLI setup for commands that run a pipeline (Generate, Interactive, Prompt tune).
 struct CliPipelineSetup<'a> {
     pool: SqlitePool,
     channel_config: &'a OutputChannelConfig,
     time_window: Option<pipeline::TimeWindow>,
     cancel: CancellationToken,
-    tg_conn: Option<TgConnection>,
+    tg_conns: Vec<TgConnection>,
 }
 
 /// Set up DB, config sync, channel lookup, cancellation, and TG connection.
 async fn setup_pipeline<'a>(
     config: &'a Config,
     slug: &str,
-    since: &Option<String>,
-    from: &Option<String>,
-    to: &Option<String>,
+    time_window: Option<pipeline::TimeWindow>,
 ) -> Result<CliPipelineSetup<'a>> {
-    let time_window = cli::parse_time_window(since, from, to)?;
-
     let pool = db::create_pool(config).await.context("creating database")?;
     info!(db_path = %config.db_path().display(), "database ready");
 
@@ -73,74 +129,1408 @@ async fn setup_pipeline<'a>(
         cancel_signal.cancel();
     });
 
-    // Check if this channel has TG sources
-    let has_tg_sources = channel_config.sources.iter().any(|name| {
-        config
-            .source
+    // Connect every account this channel's TG sources read with
+    let tg_accounts: BTreeSet<String> = config
+        .source
+        .iter()
+        .filter(|s| channel_config.sources.contains(&s.name) && is_mtproto_source(&s.source_type))
+        .map(|s| s.tg_account.clone().unwrap_or_else(|| DEFAULT_TG_ACCOUNT.to_string()))
+        .collect();
+
+    let mut tg_conns = Vec::new();
+    if config.telegram.enabled {
+        for account in &tg_accounts {
+            let conn = telegram::connect(config, &pool, account)
+                .await
+                .with_context(|| format!("connecting to Telegram account '{account}'"))?;
+
+            // Check auth
+            match conn.client.is_authorized().await {
+                Ok(true) => {}
+                Ok(false) => anyhow::bail!(
+                    "Telegram account '{account}' not authorized. Run 'pail tg login --account {account}' first."
+                ),
+                Err(e) => anyhow::bail!("Telegram auth check failed: {e}"),
+            }
+
+            // Resolve source IDs and folders (same as daemon::start_telegram)
+            telegram::prepare_account_sources(&conn.client, &pool, account).await?;
+
+            tg_conns.push(conn);
+        }
+    }
+
+    Ok(CliPipelineSetup {
+        pool,
+        channel_config,
+        time_window,
+        cancel,
+        tg_conns,
+    })
+}
+
+/// `pail articles mark-read`: set or clear the read state of generated articles.
+async fn run_articles_command(config: &Config, command: ArticlesCommands) -> Result<()> {
+    let pool = db::create_pool(config).await.context("creating database")?;
+    let ArticlesCommands::MarkRead {
+        article_ids,
+        channel,
+        unread,
+    } = command;
+    let now = chrono::Utc::now();
+
+    if article_ids.is_empty() {
+        let channel_id = match channel {
+            Some(ref slug) => Some(
+                store::get_channel_by_slug(&pool, slug)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("no output channel with slug '{slug}'"))?
+                    .id,
+            ),
+            None => None,
+        };
+        let marked = store::mark_all_articles_read(&pool, channel_id.as_deref(), now).await?;
+        println!("Marked {marked} article(s) read.");
+        return Ok(());
+    }
+
+    let read_at = (!unread).then_some(now);
+    for id in &article_ids {
+        if !store::set_article_read(&pool, id, read_at).await? {
+            anyhow::bail!("no published article with ID '{id}'");
+        }
+    }
+    let state = if unread { "unread" } else { "read" };
+    println!("Marked {} article(s) {state}.", article_ids.len());
+    Ok(())
+}
+
+/// `pail items list`: the items a channel's next generation would get (or those of `--since`),
+/// after the same filters. Nothing is fetched and no API is called: engagement counts, tags and
+/// embeddings are the stored ones.
+async fn run_items_command(config: &Config, command: ItemsCommands) -> Result<()> {
+    let ItemsCommands::List {
+        slug,
+        since,
+        source,
+        json,
+    } = command;
+    let channel_config = config
+        .output_channel
+        .iter()
+        .find(|c| c.slug == slug)
+        .ok_or_else(|| anyhow::anyhow!("no output channel config for slug '{slug}'"))?;
+    let time_window = cli::parse_time_window(&since, &None, &None)?;
+
+    let pool = db::create_pool(config).await.context("creating database")?;
+    store::sync_config_to_db(&pool, config)
+        .await
+        .context("syncing config to database")?;
+    let channel = store::get_channel_by_slug(&pool, &slug)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("no output channel with slug '{slug}'"))?;
+    let source_ids = store::get_channel_source_ids(&pool, &channel.id).await?;
+    let mut sources: Vec<_> = store::get_sources_by_ids(&pool, &source_ids)
+        .await?
+        .into_iter()
+        .filter(|s| s.enabled)
+        .collect();
+    if let Some(ref name) = source {
+        sources.retain(|s| &s.name == name);
+        if sources.is_empty() {
+            anyhow::bail!("'{name}' is not an enabled source of channel '{slug}'");
+        }
+    }
+    let source_ids: Vec<String> = sources.iter().map(|s| s.id.clone()).collect();
+
+    let (from, to) = pipeline::covered_window(
+        config,
+        channel_config,
+        &channel,
+        time_window.as_ref(),
+        chrono::Utc::now(),
+    );
+    let window_items = store::get_items_in_window(&pool, &source_ids, from, to).await?;
+    let in_window = window_items.len();
+    let items = pipeline::filter_stored_items(&pool, config, channel_config, &sources, window_items).await;
+
+    let source_names: HashMap<&str, &str> = sources.iter().map(|s| (s.id.as_str(), s.name.as_str())).collect();
+    let source_of = |item: &models::ContentItem| source_names.get(item.source_id.as_str()).copied().unwrap_or("?");
+    let mut per_source: BTreeMap<&str, usize> = BTreeMap::new();
+    for item in &items {
+        *per_source.entry(source_of(item)).or_default() += 1;
+    }
+    let title_of = |item: &models::ContentItem| {
+        item.title.clone().filter(|t| !t.trim().is_empty()).unwrap_or_else(|| {
+            item.body
+                .lines()
+                .find(|l| !l.trim().is_empty())
+                .unwrap_or_default()
+                .to_string()
+        })
+    };
+
+    if json {
+        let listed: Vec<serde_json::Value> = items
             .iter()
-            .any(|s| s.name == *name && s.source_type.starts_with("telegram_"))
-    });
+            .map(|item| {
+                serde_json::json!({
+                    "id": item.id,
+                    "source": source_of(item),
+                    "date": item.original_date.to_rfc3339(),
+                    "title": title_of(item),
+                    "url": item.url,
+                    "chars": item.body.chars().count(),
+                })
+            })
+            .collect();
+        let output = serde_json::json!({
+            "channel": slug,
+            "from": from.to_rfc3339(),
+            "to": to.to_rfc3339(),
+            "in_window": in_window,
+            "filtered_out": in_window - items.len(),
+            "sources": per_source,
+            "items": listed,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    let tz: chrono_tz::Tz = config.pail.timezone.parse().unwrap_or(chrono_tz::UTC);
+    let fmt = |dt: chrono::DateTime<chrono::Utc>| dt.with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string();
+    println!("{slug}: {} – {} ({tz})", fmt(from), fmt(to));
+    println!("{} item(s), {} filtered out", items.len(), in_window - items.len());
+    println!();
+    for item in &items {
+        let title: String = title_of(item).chars().take(80).collect();
+        println!(
+            "{}  {:<24}  {:>7}  {title}",
+            fmt(item.original_date),
+            source_of(item),
+            item.body.chars().count()
+        );
+    }
+    if !per_source.is_empty() {
+        println!();
+        for (name, count) in &per_source {
+            println!("{name:<24}  {count:>5}");
+        }
+    }
+    Ok(())
+}
+
+/// `pail runs list`: recent generation runs (see docs/specs/generation-engine.md "Run History").
+async fn run_runs_command(config: &Config, command: RunsCommands) -> Result<()> {
+    let RunsCommands::List { channel, limit, json } = command;
+    let pool = db::create_pool(config).await.context("creating database")?;
+    let channels = store::get_all_channels(&pool).await?;
+    let channel_id = match channel {
+        Some(ref slug) => Some(
+            channels
+                .iter()
+                .find(|c| &c.slug == slug)
+                .map(|c| c.id.as_str())
+                .ok_or_else(|| anyhow::anyhow!("no output channel with slug '{slug}'"))?,
+        ),
+        None => None,
+    };
+    let runs = store::get_generation_runs(&pool, channel_id, limit.max(1)).await?;
+    let slug_of = |run: &models::GenerationRun| {
+        channels
+            .iter()
+            .find(|c| c.id == run.channel_id)
+            .map(|c| c.slug.as_str())
+            .unwrap_or("?")
+    };
+
+    if json {
+        let listed: Vec<serde_json::Value> = runs
+            .iter()
+            .map(|run| {
+                serde_json::json!({
+                    "id": run.id,
+                    "channel": slug_of(run),
+                    "started_at": run.started_at.to_rfc3339(),
+                    "finished_at": run.finished_at.to_rfc3339(),
+                    "duration_secs": (run.finished_at - run.started_at).num_seconds(),
+                    "status": run.status,
+                    "skip_reason": run.skip_reason,
+                    "error": run.error,
+                    "items": run.item_count,
+                    "attempts": run.attempts,
+                    "retries": run.retries(),
+                    "exit_code": run.exit_code,
+                    "model": run.model,
+                    "article_id": run.article_id,
+                    "workspace": run.workspace,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&listed)?);
+        return Ok(());
+    }
+
+    if runs.is_empty() {
+        println!("No generation runs recorded.");
+        return Ok(());
+    }
+    let tz: chrono_tz::Tz = config.pail.timezone.parse().unwrap_or(chrono_tz::UTC);
+    println!(
+        "{:<16}  {:<20}  {:<28}  {:>9}  {:>5}  {:>3}  {:>4}  {:<28}  ARTICLE / ERROR",
+        "STARTED", "CHANNEL", "OUTCOME", "DURATION", "ITEMS", "TRY", "EXIT", "MODEL"
+    );
+    for run in &runs {
+        let items = run.item_count.map(|n| n.to_string()).unwrap_or_else(|| "-".to_string());
+        let exit_code = run.exit_code.map(|c| c.to_string()).unwrap_or_else(|| "-".to_string());
+        let detail = match (&run.article_id, &run.error) {
+            (Some(article_id), _) => article_id.clone(),
+            (None, Some(error)) => error.lines().next().unwrap_or_default().chars().take(100).collect(),
+            (None, None) => String::new(),
+        };
+        println!(
+            "{:<16}  {:<20}  {:<28}  {:>9}  {:>5}  {:>3}  {:>4}  {:<28}  {detail}",
+            run.started_at.with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string(),
+            slug_of(run),
+            run.outcome(),
+            run.duration(),
+            items,
+            run.attempts,
+            exit_code,
+            run.model.as_deref().unwrap_or("-"),
+        );
+        if let Some(ref workspace) = run.workspace {
+            println!("{:<16}  workspace: {workspace}", "");
+        }
+    }
+    Ok(())
+}
+
+/// `pail tokens`: named feed tokens scoped to channels, and the feed access log.
+async fn run_tokens_command(config: &Config, command: TokensCommands) -> Result<()> {
+    let pool = db::create_pool(config).await.context("creating database")?;
+    let tz: chrono_tz::Tz = config.pail.timezone.parse().unwrap_or(chrono_tz::UTC);
+    let fmt = |dt: chrono::DateTime<chrono::Utc>| dt.with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string();
+
+    match command {
+        TokensCommands::Create { name, channels } => {
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            {
+                anyhow::bail!("token name must be non-empty and contain only letters, digits, '-', '_' and '.'");
+            }
+            if store::get_feed_tokens(&pool).await?.iter().any(|t| t.name == name) {
+                anyhow::bail!("a token named '{name}' already exists (names stay taken after revocation)");
+            }
+            let mut channel_ids = Vec::new();
+            for slug in &channels {
+                let channel = store::get_channel_by_slug(&pool, slug)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("no output channel with slug '{slug}'"))?;
+                channel_ids.push(channel.id);
+            }
+            let token = daemon::generate_token();
+            let scope = (!channels.is_empty()).then_some(channel_ids.as_slice());
+            store::insert_feed_token(&pool, &name, &server::hash_feed_token(&token), scope).await?;
+
+            let example = channels.first().map(String::as_str).unwrap_or("<slug>");
+            println!("Token '{name}': {token}");
+            println!("It won't be shown again. Feed URL: /feed/default/{example}.atom?token={token}");
+            if channels.is_empty() {
+                println!("Channels: all");
+            } else {
+                println!("Channels: {}", channels.join(", "));
+            }
+        }
+        TokensCommands::List => {
+            let tokens = store::get_feed_tokens(&pool).await?;
+            if tokens.is_empty() {
+                println!("No named feed tokens. Create one with `pail tokens create <name> --channel <slug>`.");
+                return Ok(());
+            }
+            for token in tokens {
+                let channels = if token.all_channels {
+                    "all channels".to_string()
+                } else {
+                    token.channel_slugs.unwrap_or_else(|| "no channels".to_string())
+                };
+                let last_used = token.last_used_at.map_or_else(|| "never".to_string(), fmt);
+                let state = token
+                    .revoked_at
+                    .map_or_else(String::new, |at| format!(" · revoked {}", fmt(at)));
+                println!(
+                    "{} · {channels} · created {} · last used {last_used}{state}",
+                    token.name,
+                    fmt(token.created_at)
+                );
+            }
+        }
+        TokensCommands::Revoke { name } => {
+            if !store::revoke_feed_token(&pool, &name, chrono::Utc::now()).await? {
+                anyhow::bail!("no active token named '{name}'");
+            }
+            println!("Revoked token '{name}'.");
+        }
+        TokensCommands::Log { token, limit } => {
+            let entries = store::get_feed_access_log(&pool, token.as_deref(), limit).await?;
+            if entries.is_empty() {
+                println!("No feed requests recorded.");
+                return Ok(());
+            }
+            for entry in entries {
+                println!(
+                    "{} {} {} {} {}",
+                    fmt(entry.accessed_at),
+                    entry.token_name,
+                    entry.channel_slug,
+                    entry.status,
+                    entry.user_agent.as_deref().unwrap_or("-")
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `pail drafts`: list and review drafts of channels with `require_approval`.
+async fn run_drafts_command(config: &Config, registry: &StrategyRegistry, command: DraftsCommands) -> Result<()> {
+    let pool = db::create_pool(config).await.context("creating database")?;
+
+    let article_id = match command {
+        DraftsCommands::List => {
+            let drafts = store::get_draft_articles(&pool).await?;
+            if drafts.is_empty() {
+                println!("No drafts awaiting review.");
+                return Ok(());
+            }
+            for draft in drafts {
+                let channel = store::get_channel_by_id(&pool, &draft.output_channel_id).await?;
+                let slug = channel.as_ref().map(|c| c.slug.as_str()).unwrap_or("?");
+                println!(
+                    "{}  {:<20}  {}  {}",
+                    draft.id,
+                    slug,
+                    draft.generated_at.format("%Y-%m-%d %H:%M"),
+                    draft.title
+                );
+            }
+            return Ok(());
+        }
+        DraftsCommands::Approve { article_id } => {
+            let article = pipeline::approve_draft(&pool, config, &article_id).await?;
+            println!("Published \"{}\".", article.title);
+            return Ok(());
+        }
+        DraftsCommands::Reject { article_id } => {
+            if !store::review_draft(&pool, &article_id, "rejected").await? {
+                anyhow::bail!("no draft with ID '{article_id}'");
+            }
+            println!("Rejected draft {article_id}.");
+            return Ok(());
+        }
+        DraftsCommands::Regenerate { article_id } => article_id,
+    };
+
+    // Regenerate: run the pipeline over the same window; the old draft is rejected once a
+    // replacement exists, so a failed run leaves it reviewable
+    let draft = store::get_article_by_id(&pool, &article_id)
+        .await?
+        .filter(|a| a.status == "draft")
+        .ok_or_else(|| anyhow::anyhow!("no draft with ID '{article_id}'"))?;
+    let channel = store::get_channel_by_id(&pool, &draft.output_channel_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("the draft's output channel no longer exists"))?;
+    pool.close().await;
+
+    let time_window = pipeline::TimeWindow::Explicit {
+        from: draft.covers_from,
+        to: draft.covers_to,
+    };
+    let setup = setup_pipeline(config, &channel.slug, Some(time_window)).await?;
+    let tg_clients: TgClients = setup
+        .tg_conns
+        .iter()
+        .map(|c| (c.account.clone(), c.client.clone()))
+        .collect();
 
-    let tg_conn = if has_tg_sources && config.telegram.enabled {
-        if config.telegram.api_id.is_none() || config.telegram.api_hash.is_none() {
-            anyhow::bail!("Telegram sources require [telegram].api_id and api_hash");
+    let result = pipeline::run_generation(
+        &setup.pool,
+        config,
+        setup.channel_config,
+        registry,
+        None,
+        setup.time_window,
+        true,
+        &tg_clients,
+        // The replacement covers the draft's window by design
+        true,
+        setup.cancel,
+    )
+    .await;
+
+    for conn in setup.tg_conns {
+        conn.client.disconnect();
+        conn.runner_handle.abort();
+    }
+
+    match result? {
+        Some(r) => {
+            store::review_draft(&setup.pool, &article_id, "rejected").await?;
+            println!(
+                "Rejected draft {article_id}; new article: {} ({})",
+                r.article.title, r.article.id
+            );
+        }
+        None => println!("No article generated (no content items, or cancelled), draft kept."),
+    }
+    Ok(())
+}
+
+/// `pail sources`. `list` and `reset` work on the database; the other commands edit the config
+/// file, then resync the database. The file is edited in place with toml_edit (comments and
+/// formatting preserved) and rolled back if the result fails validation.
+async fn run_sources_command(config: &Config, config_path: &std::path::Path, command: SourcesCommands) -> Result<()> {
+    let content = std::fs::read_to_string(config_path).with_context(|| format!("reading {}", config_path.display()))?;
+    let mut doc = config_edit::parse_document(&content)?;
+    let existing = config_edit::get_all_source_names(&doc);
+
+    match command {
+        SourcesCommands::List { verbose } => return list_sources(config, verbose).await,
+        SourcesCommands::Reset { name, refetch } => return reset_source(config, config_path, &name, refetch).await,
+        SourcesCommands::Add {
+            source:
+                AddSourceCommands::Rss {
+                    name,
+                    url,
+                    channels,
+                    description,
+                },
+        } => {
+            if existing.contains(&name) {
+                anyhow::bail!("source '{name}' already exists");
+            }
+            config_edit::add_source(
+                &mut doc,
+                &NewSource {
+                    name: name.clone(),
+                    source_type: "rss".to_string(),
+                    url: Some(url),
+                    tg_username: None,
+                    tg_id: None,
+                    tg_folder_name: None,
+                    description,
+                },
+            );
+            for slug in &channels {
+                if !config_edit::add_source_to_channel(&mut doc, slug, &name) {
+                    anyhow::bail!("no output channel with slug '{slug}'");
+                }
+            }
+            println!("Added source '{name}'.");
+        }
+        SourcesCommands::Disable { name } | SourcesCommands::Enable { name } if !existing.contains(&name) => {
+            anyhow::bail!("source '{name}' not found");
+        }
+        SourcesCommands::Disable { name } => {
+            config_edit::set_source_enabled(&mut doc, &name, false);
+            println!("Disabled source '{name}'.");
+        }
+        SourcesCommands::Enable { name } => {
+            config_edit::set_source_enabled(&mut doc, &name, true);
+            println!("Enabled source '{name}'.");
+        }
+        SourcesCommands::Remove { name } => {
+            if !config_edit::remove_source(&mut doc, &name) {
+                anyhow::bail!("source '{name}' not found");
+            }
+            let channels = config_edit::remove_source_from_channels(&mut doc, &name);
+            println!("Removed source '{name}'.");
+            if !channels.is_empty() {
+                println!("Also removed from output channels: {}", channels.join(", "));
+            }
         }
-        let conn = telegram::connect(config, &pool)
+    }
+
+    config_edit::write_with_validation(config_path, &content, &config_edit::render(&doc))?;
+
+    // Resync so the running daemon's poller picks up the change on its next tick
+    let config = load_config(config_path).context("reloading config")?;
+    let pool = db::create_pool(&config).await.context("creating database")?;
+    store::sync_config_to_db(&pool, &config)
+        .await
+        .context("syncing config to database")?;
+    println!("Database synced.");
+
+    Ok(())
+}
+
+/// `pail sources reset`: drop a source's items and fetch state so it's ingested from scratch,
+/// optionally refetching right away. The config is left alone.
+async fn reset_source(config: &Config, config_path: &std::path::Path, name: &str, refetch: bool) -> Result<()> {
+    let pool = db::create_pool(config).await.context("creating database")?;
+    store::sync_config_to_db(&pool, config)
+        .await
+        .context("syncing config to database")?;
+    let source = store::get_source_by_name(&pool, name)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("source '{name}' not found"))?;
+
+    let deleted = store::reset_source(&pool, &source.id).await?;
+    println!("Reset '{name}': deleted {deleted} item(s), cleared fetch state.");
+    let daemon = instance_lock::holder(config)?;
+    if !refetch {
+        if source.enabled {
+            println!("A running daemon fetches it again on its next poll.");
+        }
+        return Ok(());
+    }
+
+    if fetch::POLLED_SOURCE_TYPES.contains(&source.source_type.as_str()) {
+        // The daemon's poller finds the source due right away; fetching here too would race it
+        if let Some(holder) = daemon {
+            println!("The daemon {holder} is running: it refetches '{name}' on its next poll.");
+            return Ok(());
+        }
+        // Reload: the reset cleared the validators the fetch would send
+        let source = store::get_source_by_name(&pool, name)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("source '{name}' not found"))?;
+        poller::poll_source(&pool, config, config_path, &source, None, chrono::Utc::now()).await;
+        let source = store::get_source_by_name(&pool, name)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("source '{name}' not found"))?;
+        match source.last_error {
+            Some(ref error) if source.consecutive_failures > 0 => anyhow::bail!("refetch failed: {error}"),
+            _ => println!(
+                "Refetched {} item(s).",
+                store::count_source_items(&pool, &source.id).await?
+            ),
+        }
+    } else if is_mtproto_source(&source.source_type) {
+        let account = source.tg_account().to_string();
+        if config.telegram.account(&account).is_none() {
+            anyhow::bail!("Telegram account '{account}' is not configured");
+        }
+        let retention = humantime::parse_duration(&config.pail.retention).context("invalid [pail].retention")?;
+        let since =
+            chrono::Utc::now() - chrono::Duration::from_std(retention).context("[pail].retention is too long")?;
+
+        let conn = telegram::connect(config, &pool, &account)
             .await
             .context("connecting to Telegram")?;
+        let result = async {
+            if !conn
+                .client
+                .is_authorized()
+                .await
+                .context("checking Telegram authorization")?
+            {
+                anyhow::bail!(
+                    "Telegram account '{account}' not authorized. Run 'pail tg login --account {account}' first."
+                );
+            }
+            telegram::prepare_account_sources(&conn.client, &pool, &account).await?;
+            let source = store::get_source_by_name(&pool, name)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("source '{name}' not found"))?;
+            let cancel = CancellationToken::new();
+            let cancel_signal = cancel.clone();
+            tokio::spawn(async move {
+                tokio::signal::ctrl_c().await.ok();
+                cancel_signal.cancel();
+            });
+            fetch_tg::backfill_source(&conn.client, &pool, &source, since, &cancel).await
+        }
+        .await;
+        conn.client.disconnect();
+        conn.runner_handle.abort();
+        let summary = result?;
+        println!(
+            "Refetched {} item(s) from {} chat(s), back to {}.",
+            summary.items,
+            summary.chats,
+            since.format("%Y-%m-%d")
+        );
+    } else {
+        println!(
+            "{} sources can't be refetched: their items arrive as they're sent.",
+            source.source_type
+        );
+    }
+    Ok(())
+}
+
+/// `pail channels clone`: append a copy of a channel to the config file, then sync it to the
+/// database. The copy continues from the original's last generation instead of starting over.
+async fn run_channels_command(config_path: &std::path::Path, command: ChannelsCommands) -> Result<()> {
+    let ChannelsCommands::Clone {
+        slug,
+        new_slug,
+        name,
+        prompt_file,
+    } = command;
+
+    let content = std::fs::read_to_string(config_path).with_context(|| format!("reading {}", config_path.display()))?;
+    let doc = config_edit::parse_document(&content)?;
+    let config = load_config(config_path).context("loading config")?;
+    let Some(original) = config.output_channel.iter().find(|c| c.slug == slug) else {
+        anyhow::bail!("no output channel with slug '{slug}'");
+    };
+    if config.output_channel.iter().any(|c| c.slug == new_slug) {
+        anyhow::bail!("an output channel with slug '{new_slug}' already exists");
+    }
+    let prompt = match prompt_file {
+        Some(ref path) => Some(std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?),
+        None => None,
+    };
+    let name = name.unwrap_or_else(|| format!("{} ({new_slug})", original.name));
+    let fragment = config_edit::clone_channel(&doc, &slug, &new_slug, &name, prompt.as_deref())
+        .ok_or_else(|| anyhow::anyhow!("no [[output_channel]] with slug '{slug}' in {}", config_path.display()))?;
+
+    let new_content = format!("{}\n\n{fragment}", content.trim_end());
+    config_edit::write_with_validation(config_path, &content, &new_content)?;
+    println!("Cloned '{slug}' as '{new_slug}' ({name}).");
 
-        // Check auth
-        match conn.client.is_authorized().await {
-            Ok(true) => {}
-            Ok(false) => anyhow::bail!("Telegram not authorized. Run 'pail tg login' first."),
-            Err(e) => anyhow::bail!("Telegram auth check failed: {e}"),
+    let config = load_config(config_path).context("reloading config")?;
+    let pool = db::create_pool(&config).await.context("creating database")?;
+    store::sync_config_to_db(&pool, &config)
+        .await
+        .context("syncing config to database")?;
+    let original = store::get_channel_by_slug(&pool, &slug).await?;
+    let copy = store::get_channel_by_slug(&pool, &new_slug).await?;
+    if let (Some(original), Some(copy)) = (original, copy)
+        && let Some(last_generated) = original.last_generated
+    {
+        store::update_last_generated(&pool, &copy.id, last_generated).await?;
+    }
+    println!("Database synced.");
+
+    Ok(())
+}
+
+/// Send a `pail ctl` command to the running daemon over its control socket.
+async fn run_ctl_command(config: &Config, command: CtlCommands) -> Result<()> {
+    let (request, json_output) = match command {
+        CtlCommands::Generate { slug, since } => (ControlRequest::Generate { slug, since }, false),
+        CtlCommands::Reload => (ControlRequest::Reload, false),
+        CtlCommands::Pause { slug } => (ControlRequest::Pause { slug }, false),
+        CtlCommands::Resume { slug } => (ControlRequest::Resume { slug }, false),
+        CtlCommands::Status { json } => (ControlRequest::Status, json),
+    };
+    let is_status = matches!(request, ControlRequest::Status);
+    let result = control::send(config, request).await?;
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+
+    if let Some(slug) = result["queued"].as_str() {
+        println!("Generation queued for '{slug}'.");
+    } else if result["reloaded"].as_bool() == Some(true) {
+        println!("Config reloaded.");
+        if let Some(fields) = result["restart_required"].as_array().filter(|f| !f.is_empty()) {
+            let fields: Vec<&str> = fields.iter().filter_map(|f| f.as_str()).collect();
+            println!("Restart the daemon to apply: {}", fields.join(", "));
+        }
+    } else if let Some(paused) = result["paused"].as_bool() {
+        let slug = result["slug"].as_str().unwrap_or_default();
+        if paused {
+            println!("Channel '{slug}' paused.");
+        } else {
+            println!("Channel '{slug}' resumed.");
         }
+    } else if is_status {
+        print_ctl_status(&result);
+    }
+    Ok(())
+}
 
-        // Resolve source IDs and folders (same as daemon::start_telegram)
-        let tg_sources = store::get_tg_sources(&pool).await?;
-        telegram::resolve_source_ids(&conn.client, &pool, &tg_sources).await?;
-        let folder_sources: Vec<_> = tg_sources
-            .iter()
-            .filter(|s| s.source_type == "telegram_folder")
-            .cloned()
-            .collect();
-        telegram::resolve_folders(&conn.client, &pool, &folder_sources).await?;
-        telegram::ensure_peer_cache(&conn.client, &pool, &tg_sources).await?;
+/// `pail sources list`: enabled sources from the database with their fetch health.
+async fn list_sources(config: &Config, verbose: bool) -> Result<()> {
+    let pool = db::create_pool(config).await.context("creating database")?;
+    let now = chrono::Utc::now();
+    let reports = source_health::report(&pool, config, now).await?;
+    let fmt = |dt: Option<chrono::DateTime<chrono::Utc>>| {
+        dt.map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "-".to_string())
+    };
 
-        Some(conn)
+    if verbose {
+        println!(
+            "{:<32}  {:<18}  {:<8}  {:<16}  {:<16}  {:>5}  {:>5}  {:<14}  {:<18}  {:<20}  NOTES",
+            "NAME", "TYPE", "STATUS", "LAST FETCH", "LAST ITEM", "24H", "7D", "ACTIVITY", "CACHE", "TG"
+        );
     } else {
-        None
+        println!(
+            "{:<32}  {:<18}  {:<8}  {:<16}  {:<16}  NOTES",
+            "NAME", "TYPE", "STATUS", "LAST FETCH", "LAST ITEM"
+        );
+    }
+    let mut warnings = 0;
+    for report in &reports {
+        let source = &report.source;
+        let status = &report.status;
+        if status.is_warning() {
+            warnings += 1;
+        }
+        let mut notes = status.detail(now);
+        if let Some(ref moved_url) = source.moved_url {
+            if !notes.is_empty() {
+                notes.push_str("; ");
+            }
+            notes.push_str(&format!("moved to {moved_url}"));
+        }
+        if verbose {
+            println!(
+                "{:<32}  {:<18}  {:<8}  {:<16}  {:<16}  {:>5}  {:>5}  {:<14}  {:<18}  {:<20}  {notes}",
+                source.name,
+                source.source_type,
+                status.label(),
+                fmt(source.last_fetched_at),
+                fmt(source.last_item_at),
+                report.items_24h,
+                report.items_7d,
+                source_health::sparkline(&report.daily),
+                report.cache_state(),
+                report.tg_state().unwrap_or_else(|| "-".to_string()),
+            );
+        } else {
+            println!(
+                "{:<32}  {:<18}  {:<8}  {:<16}  {:<16}  {notes}",
+                source.name,
+                source.source_type,
+                status.label(),
+                fmt(source.last_fetched_at),
+                fmt(source.last_item_at),
+            );
+        }
+    }
+    if warnings > 0 {
+        println!("\n{warnings} source(s) need attention. See docs/specs/source-health.md.");
+    }
+    Ok(())
+}
+
+/// `pail db`: schema migrations, without auto-migrating on connect.
+async fn run_db_command(config: &Config, command: DbCommands) -> Result<()> {
+    let pool = db::connect(config).await.context("opening database")?;
+
+    match command {
+        DbCommands::Migrate => {
+            let applied = db::run_migrations(&pool).await?;
+            if applied == 0 {
+                println!("Database schema is up to date.");
+            } else {
+                println!("Applied {applied} migration(s).");
+            }
+        }
+        DbCommands::Status => {
+            let statuses = db::migration_status(&pool).await?;
+            println!(
+                "{:>4}  {:<24}  {:<20}  {:<12}  NOTES",
+                "VER", "NAME", "APPLIED", "CHECKSUM"
+            );
+            for status in &statuses {
+                let mut notes = Vec::new();
+                if !status.known {
+                    notes.push("unknown to this binary (applied by a newer pail?)");
+                }
+                if status.checksum_mismatch() {
+                    notes.push("CHECKSUM MISMATCH: migration changed since it was applied");
+                }
+                if status.known && !status.reversible {
+                    notes.push("irreversible");
+                }
+                let checksum = status
+                    .checksum
+                    .as_deref()
+                    .or(status.recorded_checksum.as_deref())
+                    .map(|c| c[..12.min(c.len())].to_string())
+                    .unwrap_or_else(|| "-".to_string());
+                println!(
+                    "{:>4}  {:<24}  {:<20}  {:<12}  {}",
+                    status.version,
+                    status.name,
+                    status.applied_at.as_deref().unwrap_or("pending"),
+                    checksum,
+                    notes.join("; ")
+                );
+            }
+            let pending = statuses.iter().filter(|s| !s.is_applied()).count();
+            if pending > 0 {
+                println!("\n{pending} pending migration(s). Run `pail db migrate` to apply.");
+            }
+        }
+        DbCommands::Rollback { version, yes } => {
+            let plan = db::rollback_plan(&pool, version).await?;
+            if plan.is_empty() {
+                println!("Nothing to roll back: no applied migrations newer than v{version}.");
+                return Ok(());
+            }
+            if !yes {
+                println!("Would revert:");
+                for (v, name) in &plan {
+                    println!("  v{v} {name}");
+                }
+                println!(
+                    "\nData in the dropped tables and columns is lost. Back up the database, then rerun with --yes."
+                );
+                return Ok(());
+            }
+            for (v, name) in db::rollback(&pool, version).await? {
+                println!("Reverted v{v} {name}");
+            }
+            println!(
+                "Database is at v{version}. Start the daemon with --no-auto-migrate (or an older pail) to keep it there."
+            );
+        }
+        DbCommands::Compress { undo } => {
+            let db_path = config.db_path();
+            let size_before = std::fs::metadata(&db_path).map(|m| m.len()).unwrap_or(0);
+            for (label, table, column, flag) in [
+                ("item bodies", "content_items", "body", "body_compressed"),
+                (
+                    "generation logs",
+                    "generated_articles",
+                    "generation_log",
+                    "log_compressed",
+                ),
+            ] {
+                let stats = store::recompress_column(&pool, table, column, flag, undo).await?;
+                println!(
+                    "{label}: {} {} rows, {} → {}",
+                    if undo { "decompressed" } else { "compressed" },
+                    stats.rows,
+                    format_size(stats.bytes_before),
+                    format_size(stats.bytes_after),
+                );
+            }
+            store::vacuum(&pool).await?;
+            let size_after = std::fs::metadata(&db_path).map(|m| m.len()).unwrap_or(0);
+            println!(
+                "Database file: {} → {} (after VACUUM)",
+                format_size(size_before),
+                format_size(size_after)
+            );
+            if undo {
+                println!(
+                    "New rows are still compressed by this pail; roll back and switch versions before starting the daemon again."
+                );
+            }
+        }
+        DbCommands::Maintain => {
+            let report = db::maintain(&pool, &config.db_path(), true).await?;
+            if report.converted {
+                println!("Switched to incremental auto-vacuum (full VACUUM).");
+            }
+            println!("Free pages: {} → {}", report.free_pages_before, report.free_pages_after);
+            println!(
+                "Database file + WAL: {} → {}",
+                format_size(report.size_before),
+                format_size(report.size_after)
+            );
+            if report.checkpoint_busy {
+                println!("The WAL checkpoint didn't finish (database busy); the WAL is truncated on a later run.");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `pail tg`. Login, status and backfill connect with the account's session; `debug` and
+/// `folder` work on the database alone.
+async fn run_tg_command(config: &Config, command: TgCommands) -> Result<()> {
+    match command {
+        TgCommands::Debug { command } => run_tg_debug_command(config, command).await,
+        TgCommands::Folder { command } => run_tg_folder_command(config, command).await,
+        TgCommands::Login { qr, account } => {
+            let (pool, conn) = connect_tg_account(config, &account).await?;
+            if qr {
+                telegram::login_qr(&conn.client, config, &pool, &account)
+                    .await
+                    .context("Telegram QR login")?;
+            } else {
+                telegram::login(&conn.client, config, &account)
+                    .await
+                    .context("Telegram login")?;
+            }
+            println!("Session saved. You can now enable Telegram sources in config.");
+            disconnect_tg(conn);
+            Ok(())
+        }
+        TgCommands::Status { account } => {
+            let (_pool, conn) = connect_tg_account(config, &account).await?;
+            telegram::status(&conn.client).await.context("Telegram status")?;
+            disconnect_tg(conn);
+            Ok(())
+        }
+        TgCommands::Backfill { source, since } => {
+            let source_config = config
+                .source
+                .iter()
+                .find(|s| s.name == source)
+                .ok_or_else(|| anyhow::anyhow!("source '{source}' not found"))?;
+            if !is_mtproto_source(&source_config.source_type) {
+                anyhow::bail!(
+                    "source '{}' is a {} source; only Telegram channels, groups and folders read \
+                     through an account can be backfilled",
+                    source_config.name,
+                    source_config.source_type
+                );
+            }
+            let account = source_config
+                .tg_account
+                .clone()
+                .unwrap_or_else(|| DEFAULT_TG_ACCOUNT.to_string());
+
+            let (pool, conn) = connect_tg_account(config, &account).await?;
+            let duration =
+                humantime::parse_duration(&since).with_context(|| format!("invalid --since duration: '{since}'"))?;
+            let since = chrono::Utc::now() - chrono::Duration::from_std(duration).context("--since is too long")?;
+            if !conn
+                .client
+                .is_authorized()
+                .await
+                .context("checking Telegram authorization")?
+            {
+                anyhow::bail!(
+                    "Telegram account '{account}' not authorized. Run 'pail tg login --account {account}' first."
+                );
+            }
+            store::sync_config_to_db(&pool, config)
+                .await
+                .context("syncing config to database")?;
+            telegram::prepare_account_sources(&conn.client, &pool, &account).await?;
+            let source = store::get_source_by_name(&pool, &source)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("source '{source}' not found in the database"))?;
+
+            // Ctrl-C stops after the current message; progress so far is kept
+            let cancel = CancellationToken::new();
+            let cancel_signal = cancel.clone();
+            tokio::spawn(async move {
+                tokio::signal::ctrl_c().await.ok();
+                cancel_signal.cancel();
+            });
+            let summary = fetch_tg::backfill_source(&conn.client, &pool, &source, since, &cancel).await?;
+            println!(
+                "Backfilled {} item(s) from {} chat(s) of '{}'{}.",
+                summary.items,
+                summary.chats,
+                source.name,
+                if summary.skipped > 0 {
+                    format!(" ({} chat(s) already backfilled)", summary.skipped)
+                } else {
+                    String::new()
+                }
+            );
+            if cancel.is_cancelled() {
+                println!("Interrupted; run the same command again to resume.");
+            }
+            disconnect_tg(conn);
+            Ok(())
+        }
+    }
+}
+
+/// Check the account is configured, then open the database and connect with its session.
+async fn connect_tg_account(config: &Config, account: &str) -> Result<(SqlitePool, TgConnection)> {
+    if config.telegram.account(account).is_none() {
+        if account == DEFAULT_TG_ACCOUNT {
+            anyhow::bail!(
+                "Telegram requires a valid [telegram].api_id and api_hash in config \
+                 (get one at https://my.telegram.org)"
+            );
+        }
+        anyhow::bail!("Telegram account '{account}' is not defined in [telegram.accounts]");
+    }
+
+    let pool = db::create_pool(config).await.context("creating database")?;
+    let conn = telegram::connect(config, &pool, account)
+        .await
+        .context("connecting to Telegram")?;
+    Ok((pool, conn))
+}
+
+/// Disconnect cleanly.
+fn disconnect_tg(conn: TgConnection) {
+    conn.client.disconnect();
+    conn.runner_handle.abort();
+}
+
+/// `pail tg folder`: the chats of a folder source and their include flags. Works on the
+/// database alone; a running daemon picks changes up within a minute.
+async fn run_tg_folder_command(config: &Config, command: TgFolderCommands) -> Result<()> {
+    let pool = db::create_pool(config).await.context("creating database")?;
+    let name = match command {
+        TgFolderCommands::Channels { ref source }
+        | TgFolderCommands::Disable { ref source, .. }
+        | TgFolderCommands::Enable { ref source, .. } => source.clone(),
     };
+    let source = store::get_source_by_name(&pool, &name)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("source '{name}' not found in the database"))?;
+    if source.source_type != "telegram_folder" {
+        anyhow::bail!(
+            "source '{name}' is a {} source, not telegram_folder",
+            source.source_type
+        );
+    }
+    let channels = store::get_folder_channels(&pool, &source.id).await?;
+    if channels.is_empty() {
+        println!("No chats known for folder source '{name}' yet; the daemon lists them when it connects the account.");
+        return Ok(());
+    }
 
-    Ok(CliPipelineSetup {
-        pool,
-        channel_config,
-        time_window,
-        cancel,
-        tg_conn,
-    })
+    match command {
+        TgFolderCommands::Channels { .. } => {
+            println!("{:<8}  {:<14}  {:<32}  NAME", "STATE", "ID", "USERNAME");
+            for channel in &channels {
+                println!(
+                    "{:<8}  {:<14}  {:<32}  {}",
+                    if channel.enabled { "enabled" } else { "disabled" },
+                    channel.channel_tg_id,
+                    channel
+                        .channel_username
+                        .as_deref()
+                        .map_or_else(|| "-".to_string(), |u| format!("@{u}")),
+                    channel.channel_name.as_deref().unwrap_or("-"),
+                );
+            }
+            let disabled = channels.iter().filter(|c| !c.enabled).count();
+            println!("\n{} chat(s), {disabled} disabled.", channels.len());
+        }
+        TgFolderCommands::Disable { ref channel, .. } | TgFolderCommands::Enable { ref channel, .. } => {
+            let enabled = matches!(command, TgFolderCommands::Enable { .. });
+            let found = find_folder_channel(&channels, channel)?;
+            store::set_folder_channel_enabled(&pool, &source.id, found.channel_tg_id, enabled).await?;
+            let label = found
+                .channel_name
+                .clone()
+                .unwrap_or_else(|| found.channel_tg_id.to_string());
+            println!(
+                "{} '{label}' in folder source '{name}'.",
+                if enabled { "Enabled" } else { "Disabled" }
+            );
+        }
+    }
+    Ok(())
+}
+
+/// A folder chat by `@username` (case-insensitive), numeric ID, or exact name.
+fn find_folder_channel<'a>(channels: &'a [models::TgFolderChannel], query: &str) -> Result<&'a models::TgFolderChannel> {
+    let username = query.strip_prefix('@').unwrap_or(query);
+    let id: Option<i64> = query.parse().ok();
+    let matches: Vec<_> = channels
+        .iter()
+        .filter(|c| {
+            id == Some(c.channel_tg_id)
+                || c.channel_username
+                    .as_deref()
+                    .is_some_and(|u| u.eq_ignore_ascii_case(username))
+                || c.channel_name.as_deref() == Some(query)
+        })
+        .collect();
+    match matches.as_slice() {
+        [one] => Ok(one),
+        [] => anyhow::bail!("no chat '{query}' in the folder; see `pail tg folder channels`"),
+        _ => anyhow::bail!("'{query}' matches several chats; use the numeric ID"),
+    }
+}
+
+async fn run_tg_debug_command(config: &Config, command: TgDebugCommands) -> Result<()> {
+    let TgDebugCommands::Gaps { account, since } = command;
+    let duration = humantime::parse_duration(&since).with_context(|| format!("invalid --since duration: '{since}'"))?;
+    let since = chrono::Utc::now() - chrono::Duration::from_std(duration).context("--since is too long")?;
+
+    let pool = db::create_pool(config).await.context("creating database")?;
+    let updates = store::get_tg_debug_updates(&pool, &account, since).await?;
+    if updates.is_empty() {
+        if config.telegram.debug_updates.enabled {
+            println!(
+                "No updates recorded for account '{account}' since {}.",
+                since.format("%Y-%m-%d %H:%M UTC")
+            );
+        } else {
+            println!("No updates recorded. Enable [telegram.debug_updates] and restart the daemon to record them.");
+        }
+        return Ok(());
+    }
+
+    // Chat names: sources read by ID, and the channels of folder sources
+    let mut names: HashMap<i64, String> = HashMap::new();
+    for source in store::get_all_sources(&pool).await? {
+        if let Some(tg_id) = source.tg_id {
+            names.insert(tg_id, source.name.clone());
+        }
+        if source.source_type == "telegram_folder" {
+            for (tg_id, (name, _)) in store::get_folder_channel_map(&pool, &source.id).await? {
+                names
+                    .entry(tg_id)
+                    .or_insert(format!("{name} (folder '{}')", source.name));
+            }
+        }
+    }
+
+    let mut stored = HashMap::new();
+    for channel in updates.iter().filter_map(|u| u.channel_id).collect::<BTreeSet<_>>() {
+        stored.insert(channel, tg_debug::stored_message_ids(&pool, channel).await?);
+    }
+    let reports = tg_debug::analyze(&updates, |channel| stored.get(&channel).cloned().unwrap_or_default());
+
+    println!(
+        "{} update(s) recorded for account '{account}' since {}",
+        updates.len(),
+        since.format("%Y-%m-%d %H:%M UTC")
+    );
+    let mut clean = 0;
+    for report in &reports {
+        if report.pts_gaps.is_empty() && report.message_gaps.is_empty() && report.too_long.is_empty() {
+            clean += 1;
+            continue;
+        }
+        let label = match report.channel_id {
+            Some(id) => match names.get(&id) {
+                Some(name) => format!("{name} ({id})"),
+                None => format!("channel {id}"),
+            },
+            None => "common sequence (private chats, basic groups)".to_string(),
+        };
+        println!();
+        println!("{label}: {} update(s)", report.updates);
+        if let (Some(first), Some(last)) = (report.first_pts, report.last_pts) {
+            println!("  pts {first}..{last}");
+        }
+        for gap in &report.pts_gaps {
+            println!(
+                "  pts gap {}..{} between {} and {}",
+                gap.from,
+                gap.to,
+                gap.after.format("%Y-%m-%d %H:%M:%S"),
+                gap.before.format("%H:%M:%S UTC")
+            );
+        }
+        for gap in &report.message_gaps {
+            let missing = gap.to - gap.from + 1;
+            println!(
+                "  messages {}..{} not received live ({missing} missing, {} stored)",
+                gap.from, gap.to, gap.stored
+            );
+        }
+        for at in &report.too_long {
+            println!("  server reported too long at {}", at.format("%Y-%m-%d %H:%M:%S UTC"));
+        }
+    }
+    if clean > 0 {
+        println!();
+        println!("{clean} sequence(s) without gaps.");
+    }
+    Ok(())
+}
+
+/// Byte count for humans: `512 B`, `3.4 MB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+/// `pail export`: pail's own feeds in formats other tools import.
+async fn run_export_command(config: &Config, command: ExportCommands) -> Result<()> {
+    let pool = db::create_pool(config).await.context("creating database")?;
+    match command {
+        ExportCommands::Opml {
+            base_url,
+            token,
+            output,
+        } => {
+            let (token, scope) = match token {
+                Some(token) => {
+                    let scope = store::get_active_feed_token(&pool, &server::hash_feed_token(&token))
+                        .await?
+                        .ok_or_else(|| anyhow::anyhow!("no active named feed token matches --token"))?;
+                    (token, Some(scope))
+                }
+                None => {
+                    let token = match config.pail.feed_token {
+                        Some(ref token) => token.clone(),
+                        None => store::get_setting(&pool, "feed_token").await?.context(
+                            "no feed token yet: set [pail].feed_token or start the daemon once to generate one",
+                        )?,
+                    };
+                    (token, None)
+                }
+            };
+            let base_url = base_url.unwrap_or_else(|| opml::default_base_url(config));
+            let document = opml::export(&pool, &base_url, &token, scope.as_ref()).await?;
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, &document).with_context(|| format!("writing {}", path.display()))?;
+                    let feeds = document.matches("<outline ").count();
+                    println!("Wrote {feeds} feeds to {}", path.display());
+                }
+                None => print!("{document}"),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `pail workspaces`: generation workspaces kept in `data_dir/workspaces/`.
+fn run_workspaces_command(config: &Config, command: WorkspacesCommands) -> Result<()> {
+    match command {
+        WorkspacesCommands::Prune { older_than } => {
+            let max_age = older_than
+                .as_deref()
+                .map(humantime::parse_duration)
+                .transpose()
+                .context("parsing --older-than")?;
+            let dir = config.pail.data_dir.join("workspaces");
+            if !dir.exists() {
+                println!("No kept workspaces in {}.", dir.display());
+                return Ok(());
+            }
+
+            let now = std::time::SystemTime::now();
+            let (mut removed, mut kept) = (0, 0);
+            for entry in std::fs::read_dir(&dir).with_context(|| format!("reading {}", dir.display()))? {
+                let entry = entry?;
+                if !entry.file_type()?.is_dir() {
+                    continue;
+                }
+                let age = entry
+                    .metadata()?
+                    .modified()
+                    .ok()
+                    .and_then(|modified| now.duration_since(modified).ok())
+                    .unwrap_or_default();
+                if max_age.is_some_and(|max| age < max) {
+                    kept += 1;
+                    continue;
+                }
+                std::fs::remove_dir_all(entry.path()).with_context(|| format!("removing {}", entry.path().display()))?;
+                removed += 1;
+            }
+            println!("Removed {removed} workspace(s), kept {kept}.");
+        }
+    }
+    Ok(())
+}
+
+fn print_ctl_status(status: &serde_json::Value) {
+    let str_or =
+        |v: &serde_json::Value, default: &'static str| v.as_str().map(str::to_string).unwrap_or(default.to_string());
+    let uptime = std::time::Duration::from_secs(status["uptime_secs"].as_u64().unwrap_or(0));
+    println!(
+        "pail {} running since {} (up {})",
+        str_or(&status["version"], "?"),
+        str_or(&status["started_at"], "?"),
+        humantime::format_duration(uptime)
+    );
+    let role = str_or(&status["role"], "all");
+    if role != "all" {
+        println!("  role: {role}");
+    }
+    for task in ["scheduler", "poller"] {
+        let alive = match status[task]["alive"].as_bool() {
+            Some(true) => "alive",
+            Some(false) => "STALLED",
+            None => {
+                println!("  {task}: not run by this instance");
+                continue;
+            }
+        };
+        println!(
+            "  {task}: {alive} (last tick {})",
+            str_or(&status[task]["last_tick"], "never")
+        );
+    }
+    let tg = &status["telegram"];
+    let tg_state = if tg["enabled"].as_bool() != Some(true) {
+        "disabled".to_string()
+    } else if let Some(error) = tg["error"].as_str() {
+        format!("error: {error}")
+    } else if tg["connected"].as_bool() == Some(true) && tg["authorized"].as_bool() == Some(true) {
+        "connected".to_string()
+    } else {
+        "disconnected".to_string()
+    };
+    println!("  telegram: {tg_state}");
+
+    let channels = status["channels"].as_array().cloned().unwrap_or_default();
+    if channels.is_empty() {
+        println!("\nNo enabled channels.");
+        return;
+    }
+    println!();
+    for channel in &channels {
+        let state = if channel["paused"].as_bool() == Some(true) {
+            " [paused]"
+        } else {
+            ""
+        };
+        println!("{}{state}", str_or(&channel["slug"], "?"));
+        println!("  schedule:       {}", str_or(&channel["schedule"], "none (CLI only)"));
+        println!("  last generated: {}", str_or(&channel["last_generated"], "never"));
+        println!("  next run:       {}", str_or(&channel["next_run"], "-"));
+        if let (Some(cited), Some(provided)) = (
+            channel["coverage"]["cited"].as_i64(),
+            channel["coverage"]["provided"].as_i64(),
+        ) {
+            println!("  coverage:       {cited}/{provided} items referenced (latest article)");
+        }
+    }
+
+    let skipped = status["skipped_sources"].as_array().cloned().unwrap_or_default();
+    if !skipped.is_empty() {
+        println!("\nOften skipped (last 30 days):");
+        for entry in &skipped {
+            println!(
+                "  {}: skipped in {} article(s), {} item(s); latest reason: {}",
+                str_or(&entry["source"], "?"),
+                entry["articles"].as_i64().unwrap_or(0),
+                entry["items"].as_i64().unwrap_or(0),
+                str_or(&entry["last_reason"], "-"),
+            );
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    // The one command that runs without a config: it writes it
+    if let Some(Commands::Init { force }) = cli.command {
+        return init::run(&cli.config, force).await;
+    }
+    // Runs inside the opencode sandbox, which has neither the config nor pail's network
+    if let Some(Commands::SandboxBridge {
+        ref socket,
+        port,
+        ref command,
+    }) = cli.command
+    {
+        let code = sandbox::run_bridge(socket, port, command).await?;
+        std::process::exit(code);
+    }
+
     let config = load_config(&cli.config).with_context(|| format!("loading config from {}", cli.config.display()))?;
 
     // Initialize Sentry (must happen before tracing subscriber)
-    let _sentry_guard = sentry::init((
-        std::env::var("SENTRY_DSN").ok(),
-        sentry::ClientOptions {
-            traces_sample_rate: 1.0,
-            environment: Some(
-                std::env::var("SENTRY_ENVIRONMENT")
-                    .unwrap_or_else(|_| "development".to_string())
-                    .into(),
-            ),
-            release: std::env::var("GIT_SHA").ok().map(Into::into),
-            ..Default::default()
-        },
-    ));
+    let _sentry_guard = reporting::init(&config);
 
     // Initialize tracing with sentry layer
     let filter = tracing_subscriber::EnvFilter::try_from_default_env()
@@ -173,7 +1563,7 @@ async fn main() -> Result<()> {
                     && config.telegram.api_hash.as_deref().is_some_and(|h| !h.is_empty())
                 {
                     let pool = db::create_pool(&config).await.context("creating database")?;
-                    match telegram::connect(&config, &pool).await {
+                    match telegram::connect(&config, &pool, DEFAULT_TG_ACCOUNT).await {
                         Ok(conn) => match conn.client.is_authorized().await {
                             Ok(true) => Some(conn),
                             _ => {
@@ -201,6 +1591,33 @@ async fn main() -> Result<()> {
                 result?;
             }
         },
+        Some(Commands::Generate {
+            from_bundle: Some(bundle_path),
+            output,
+            strategy,
+            ..
+        }) => {
+            let cancel = CancellationToken::new();
+            let cancel_signal = cancel.clone();
+            tokio::spawn(async move {
+                tokio::signal::ctrl_c().await.ok();
+                cancel_signal.cancel();
+            });
+
+            let replay = bundle::replay(&config, &registry, &bundle_path, strategy.as_deref(), cancel).await?;
+            match output {
+                Some(output_path) => {
+                    std::fs::write(&output_path, &replay.raw_output)
+                        .with_context(|| format!("writing output to {}", output_path.display()))?;
+                    println!("Replayed output written to: {}", output_path.display());
+                }
+                None => print!("{}", replay.raw_output),
+            }
+            eprintln!(
+                "Replayed article {} ('{}', {}) with {}.",
+                replay.info.article_id, replay.info.title, replay.info.channel, replay.info.model
+            );
+        }
         Some(Commands::Generate {
             slug,
             output,
@@ -208,20 +1625,54 @@ async fn main() -> Result<()> {
             since,
             from,
             to,
+            force,
+            mark_read_dry_run,
+            from_bundle: None,
         }) => {
-            let setup = setup_pipeline(&config, &slug, &since, &from, &to).await?;
-            let tg_client_ref = setup.tg_conn.as_ref().map(|c| &c.client);
+            let slug = slug.context("a channel slug is required")?;
+            let time_window = cli::parse_time_window(&since, &from, &to)?;
+            let setup = setup_pipeline(&config, &slug, time_window).await?;
+            let mut channel_config = setup.channel_config.clone();
+            channel_config.mark_tg_read_dry_run |= mark_read_dry_run;
+            let tg_clients: TgClients = setup
+                .tg_conns
+                .iter()
+                .map(|c| (c.account.clone(), c.client.clone()))
+                .collect();
 
-            let result = pipeline::run_generation(
+            // Claim the channel like the daemon's scheduler does, so a running daemon and this
+            // run never generate it at once (or both advance last_generated)
+            let channel = store::get_channel_by_slug(&setup.pool, &slug)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("channel '{slug}' not found in database"))?;
+            let instance = daemon::instance_id();
+            let expected_last_generated = setup.time_window.is_none().then_some(channel.last_generated);
+            if !scheduler::claim(&setup.pool, &channel.id, &instance, expected_last_generated).await? {
+                let daemon = match instance_lock::holder(&config)? {
+                    Some(holder) => format!(" by the daemon {holder}"),
+                    None => String::new(),
+                };
+                anyhow::bail!(
+                    "channel '{slug}' is being generated{daemon}, or was just generated for this window; \
+                     try again when it's done, or use 'pail ctl generate {slug}'"
+                );
+            }
+            let result = scheduler::while_claimed(
                 &setup.pool,
-                &config,
-                setup.channel_config,
-                &registry,
-                strategy.as_deref(),
-                setup.time_window,
-                true,
-                tg_client_ref,
-                setup.cancel,
+                &channel.id,
+                &instance,
+                pipeline::run_generation(
+                    &setup.pool,
+                    &config,
+                    &channel_config,
+                    &registry,
+                    strategy.as_deref(),
+                    setup.time_window,
+                    true,
+                    &tg_clients,
+                    force,
+                    setup.cancel,
+                ),
             )
             .await?;
 
@@ -237,16 +1688,29 @@ async fn main() -> Result<()> {
                     }
                 }
                 None => {
-                    println!("No content items found — generation skipped.");
+                    println!("No article generated: no content items, or fewer than the channel's min_items.");
                 }
             }
 
             // Cleanup TG connection
-            if let Some(conn) = setup.tg_conn {
+            for conn in setup.tg_conns {
                 conn.client.disconnect();
                 conn.runner_handle.abort();
             }
         }
+        Some(Commands::Bundle { article_id, out }) => {
+            let pool = db::create_pool(&config).await.context("creating database")?;
+            let info = bundle::create(&pool, &config, &registry, &article_id, &out).await?;
+            println!(
+                "Bundle written to: {} ({} workspace, '{}')",
+                out.display(),
+                info.origin,
+                info.title
+            );
+            if info.origin == "rebuilt" {
+                println!("No kept workspace for this article: sources and prompt were rebuilt from the database.");
+            }
+        }
         Some(Commands::Interactive {
             slug,
             strategy,
@@ -254,8 +1718,13 @@ async fn main() -> Result<()> {
             from,
             to,
         }) => {
-            let setup = setup_pipeline(&config, &slug, &since, &from, &to).await?;
-            let tg_client_ref = setup.tg_conn.as_ref().map(|c| &c.client);
+            let time_window = cli::parse_time_window(&since, &from, &to)?;
+            let setup = setup_pipeline(&config, &slug, time_window).await?;
+            let tg_clients: TgClients = setup
+                .tg_conns
+                .iter()
+                .map(|c| (c.account.clone(), c.client.clone()))
+                .collect();
 
             let result = pipeline::run_interactive(
                 &setup.pool,
@@ -264,7 +1733,7 @@ async fn main() -> Result<()> {
                 &registry,
                 strategy.as_deref(),
                 setup.time_window,
-                tg_client_ref,
+                &tg_clients,
                 setup.cancel,
             )
             .await?;
@@ -279,7 +1748,48 @@ async fn main() -> Result<()> {
             }
 
             // Cleanup TG connection
-            if let Some(conn) = setup.tg_conn {
+            for conn in setup.tg_conns {
+                conn.client.disconnect();
+                conn.runner_handle.abort();
+            }
+        }
+        Some(Commands::Prompt {
+            command:
+                PromptCommands::Tune {
+                    slug,
+                    strategy,
+                    since,
+                    from,
+                    to,
+                },
+        }) => {
+            let time_window = cli::parse_time_window(&since, &from, &to)?;
+            let setup = setup_pipeline(&config, &slug, time_window).await?;
+            let tg_clients: TgClients = setup
+                .tg_conns
+                .iter()
+                .map(|c| (c.account.clone(), c.client.clone()))
+                .collect();
+
+            let result = prompt_tune::run(
+                &setup.pool,
+                &config,
+                setup.channel_config,
+                &registry,
+                strategy.as_deref(),
+                setup.time_window,
+                &tg_clients,
+                setup.cancel,
+            )
+            .await?;
+
+            match result {
+                Some(runs) => println!("Tuning session ended after {runs} runs."),
+                None => println!("No content items found — nothing to generate from."),
+            }
+
+            // Cleanup TG connection
+            for conn in setup.tg_conns {
                 conn.client.disconnect();
                 conn.runner_handle.abort();
             }
@@ -381,45 +1891,84 @@ async fn main() -> Result<()> {
                 }
             },
         },
-        Some(Commands::Tg { command }) => {
-            // Validate telegram config
-            match config.telegram.api_id {
-                None | Some(0) => {
-                    anyhow::bail!(
-                        "Telegram requires a valid [telegram].api_id in config \
-                         (get one at https://my.telegram.org)"
-                    );
-                }
-                _ => {}
-            }
-            if config.telegram.api_hash.as_deref().is_none_or(|h| h.is_empty()) {
-                anyhow::bail!(
-                    "Telegram requires a valid [telegram].api_hash in config \
-                     (get one at https://my.telegram.org)"
-                );
+        Some(Commands::Feedback { article_id, note }) => {
+            let note = note.trim();
+            if note.is_empty() {
+                anyhow::bail!("feedback note must not be empty");
             }
-
             let pool = db::create_pool(&config).await.context("creating database")?;
-            let conn = telegram::connect(&config, &pool)
-                .await
-                .context("connecting to Telegram")?;
-
-            match command {
-                TgCommands::Login => {
-                    telegram::login(&conn.client, &config).await.context("Telegram login")?;
-                    println!("Session saved. You can now enable Telegram sources in config.");
-                }
-                TgCommands::Status => {
-                    telegram::status(&conn.client).await.context("Telegram status")?;
-                }
+            let article = store::get_article_by_id(&pool, &article_id)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("no article with ID '{article_id}'"))?;
+            store::insert_article_feedback(&pool, &article, note).await?;
+            println!("Feedback saved for \"{}\".", article.title);
+        }
+        Some(Commands::Diff { old, new, no_color }) => {
+            let pool = db::create_pool(&config).await.context("creating database")?;
+            let mut articles = Vec::new();
+            for id in [&old, &new] {
+                let article = store::get_article_by_id(&pool, id)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("no article with ID '{id}'"))?;
+                articles.push(article);
             }
-
-            // Disconnect cleanly
-            conn.client.disconnect();
-            conn.runner_handle.abort();
+            let (old, new) = (&articles[0], &articles[1]);
+            let tz: chrono_tz::Tz = config.pail.timezone.parse().unwrap_or(chrono_tz::UTC);
+            let fmt = |dt: chrono::DateTime<chrono::Utc>| dt.with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string();
+            println!("--- {} ({})", old.title, fmt(old.generated_at));
+            println!("+++ {} ({})", new.title, fmt(new.generated_at));
+            println!();
+            let changes = diff::word_diff(&old.body_markdown, &new.body_markdown);
+            let color = !no_color && std::io::stdout().is_terminal();
+            print!("{}", diff::render(&changes, color));
+        }
+        Some(Commands::Drafts { command }) => {
+            run_drafts_command(&config, &registry, command).await?;
+        }
+        Some(Commands::Articles { command }) => {
+            run_articles_command(&config, command).await?;
+        }
+        Some(Commands::Items { command }) => {
+            run_items_command(&config, command).await?;
+        }
+        Some(Commands::Runs { command }) => {
+            run_runs_command(&config, command).await?;
+        }
+        Some(Commands::Tokens { command }) => {
+            run_tokens_command(&config, command).await?;
+        }
+        Some(Commands::Channels { command }) => {
+            run_channels_command(&cli.config, command).await?;
+        }
+        Some(Commands::Sources { command }) => {
+            run_sources_command(&config, &cli.config, command).await?;
+        }
+        Some(Commands::Tg { command }) => {
+            run_tg_command(&config, command).await?;
+        }
+        Some(Commands::Ctl { command }) => {
+            run_ctl_command(&config, command).await?;
+        }
+        Some(Commands::Db { command }) => {
+            run_db_command(&config, command).await?;
+        }
+        Some(Commands::Service { command }) => {
+            service::run(&config, &cli.config, command)?;
+        }
+        Some(Commands::Workspaces { command }) => {
+            run_workspaces_command(&config, command)?;
+        }
+        Some(Commands::Export { command }) => {
+            run_export_command(&config, command).await?;
         }
         None => {
-            daemon::run(config, registry).await?;
+            daemon::run(config, cli.config, registry, !cli.no_auto_migrate, daemon::Role::All).await?;
+        }
+        Some(Commands::Init { .. } | Commands::SandboxBridge { .. }) => {
+            unreachable!("handled before loading the config")
+        }
+        Some(Commands::Daemon { role }) => {
+            daemon::run(config, cli.config, registry, !cli.no_auto_migrate, role).await?;
         }
     }
 
@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::Url;
+use reqwest::header::USER_AGENT;
+use serde_json::json;
+use tracing::info;
+
+use crate::config::{MatrixConfig, NetworkConfig};
+use crate::http_client;
+
+/// A homeserver that doesn't answer within this is given up on; delivery isn't retried.
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Post a generated article to a Matrix room as an `m.text` message.
+///
+/// The markdown body is sent as the plain-text fallback and the rendered HTML as
+/// `org.matrix.custom.html`. The article ID is used as the transaction ID, so a retried
+/// delivery of the same article is deduplicated by the homeserver.
+pub async fn deliver_article(
+    config: &MatrixConfig,
+    network: &NetworkConfig,
+    room_id: &str,
+    article_id: &str,
+    body_markdown: &str,
+    body_html: &str,
+) -> Result<()> {
+    let content = json!({
+        "msgtype": "m.text",
+        "body": body_markdown,
+        "format": "org.matrix.custom.html",
+        "formatted_body": body_html,
+    });
+    send_message(config, network, room_id, article_id, &content).await?;
+
+    info!(room = %room_id, article_id = %article_id, "delivered article to Matrix");
+    Ok(())
+}
+
+/// Post a plain-text `m.notice` (operational alerts, not digests) to a Matrix room.
+pub async fn send_notice(config: &MatrixConfig, network: &NetworkConfig, room_id: &str, text: &str) -> Result<()> {
+    let content = json!({
+        "msgtype": "m.notice",
+        "body": text,
+    });
+    send_message(config, network, room_id, &uuid::Uuid::new_v4().to_string(), &content).await?;
+    info!(room = %room_id, "posted notice to Matrix");
+    Ok(())
+}
+
+/// PUT an `m.room.message` event with the given transaction ID.
+async fn send_message(
+    config: &MatrixConfig,
+    network: &NetworkConfig,
+    room_id: &str,
+    txn_id: &str,
+    content: &serde_json::Value,
+) -> Result<()> {
+    let homeserver = config
+        .homeserver
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("[matrix].homeserver is not configured"))?;
+    let access_token = config
+        .access_token
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("[matrix].access_token is not configured"))?;
+
+    let url = send_message_url(homeserver, room_id, txn_id)?;
+
+    let client = http_client::with_proxy(reqwest::Client::builder(), network.proxy.as_deref())
+        .and_then(|builder| builder.timeout(SEND_TIMEOUT).build())
+        .context("building HTTP client")?;
+    let response = client
+        .put(url)
+        .bearer_auth(access_token)
+        .header(USER_AGENT, concat!("pail/", env!("CARGO_PKG_VERSION")))
+        .json(content)
+        .send()
+        .await
+        .context("sending Matrix message")?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("Matrix homeserver returned {status}: {body}");
+    }
+    Ok(())
+}
+
+/// Build `/_matrix/client/v3/rooms/{roomId}/send/m.room.message/{txnId}` with each
+/// segment percent-encoded (room IDs contain `!` and `:`).
+fn send_message_url(homeserver: &str, room_id: &str, txn_id: &str) -> Result<Url> {
+    let mut url = Url::parse(homeserver).with_context(|| format!("parsing Matrix homeserver URL '{homeserver}'"))?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow::anyhow!("Matrix homeserver URL '{homeserver}' cannot be a base"))?
+        .pop_if_empty()
+        .extend([
+            "_matrix",
+            "client",
+            "v3",
+            "rooms",
+            room_id,
+            "send",
+            "m.room.message",
+            txn_id,
+        ]);
+    Ok(url)
+}
@@ -6,7 +6,9 @@
 use chrono::{DateTime, Utc};
 use sqlx::FromRow;
 
-#[derive(Debug, Clone, FromRow)]
+use crate::compress::StoredText;
+
+#[derive(Debug, Clone, Default, FromRow)]
 pub struct Source {
     pub id: String,
     pub source_type: String,
@@ -30,6 +32,54 @@ pub struct Source {
     pub tg_folder_id: Option<i32>,
     pub tg_folder_name: Option<String>,
     pub description: Option<String>,
+    /// JSON-encoded `ScrapeSelectorsConfig` (scrape sources only).
+    pub scrape_selectors: Option<String>,
+    /// JSON-encoded `ImapSourceConfig` (imap sources only).
+    pub imap_config: Option<String>,
+    /// JSON-encoded `ReadLaterSourceConfig` (wallabag / omnivore sources only).
+    pub read_later_config: Option<String>,
+    /// Telegram account the source is read with; `None` is the default account.
+    pub tg_account: Option<String>,
+    // Fetch health (polled sources only, see docs/specs/source-health.md)
+    pub consecutive_failures: i64,
+    pub last_error: Option<String>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub last_item_at: Option<DateTime<Utc>>,
+    pub health_alerted: Option<String>,
+    /// Target of a permanent redirect from `url`, fetched instead of it.
+    pub moved_url: Option<String>,
+    /// When the feed answered `410 Gone`; the poller skips the source from then on.
+    pub gone_at: Option<DateTime<Utc>>,
+    /// JSON-encoded `AggregatorSourceConfig` (lemmy / lobsters sources only).
+    pub aggregator_config: Option<String>,
+    /// JSON-encoded `IcsSourceConfig` (ics sources only).
+    pub ics_config: Option<String>,
+    /// JSON-encoded `TwitterSourceConfig` (twitter sources only).
+    pub twitter_config: Option<String>,
+    /// JSON-encoded `SlackSourceConfig` (slack sources only).
+    pub slack_config: Option<String>,
+    /// JSON-encoded `ArxivSourceConfig` (arxiv sources only).
+    pub arxiv_config: Option<String>,
+    /// Proxy for the source's requests (its `proxy`, else `[network].proxy`); `"direct"` for none.
+    pub proxy: Option<String>,
+    /// Feeds: dedup strategy (`DEDUP_STRATEGIES`); `None` for the default, `"guid"`.
+    pub dedup: Option<String>,
+    /// JSON object of extra request headers (HTTP sources only); `None` for none.
+    pub headers: Option<String>,
+    /// Request timeout (humantime) of HTTP sources; `None` for the default 30s.
+    pub fetch_timeout: Option<String>,
+}
+
+impl Source {
+    /// URL to fetch: the recorded permanent redirect target, else the configured `url`.
+    pub fn fetch_url(&self) -> Option<&str> {
+        self.moved_url.as_deref().or(self.url.as_deref())
+    }
+
+    /// Telegram account name for this source (`default` unless `tg_account` is set).
+    pub fn tg_account(&self) -> &str {
+        self.tg_account.as_deref().unwrap_or(crate::config::DEFAULT_TG_ACCOUNT)
+    }
 }
 
 #[derive(Debug, Clone, FromRow)]
@@ -43,6 +93,9 @@ pub struct OutputChannel {
     pub language: Option<String>,
     pub enabled: bool,
     pub last_generated: Option<DateTime<Utc>>,
+    /// Start of the runs skipped for `min_items` since the last article; the next `since_last`
+    /// window starts here instead of at `last_generated`.
+    pub skipped_from: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, FromRow)]
@@ -53,6 +106,7 @@ pub struct ContentItem {
     pub original_date: DateTime<Utc>,
     pub content_type: String,
     pub title: Option<String>,
+    #[sqlx(try_from = "StoredText")]
     pub body: String,
     pub url: Option<String>,
     pub author: Option<String>,
@@ -78,6 +132,34 @@ pub struct GeneratedArticle {
     pub model_used: String,
     pub token_count: Option<i64>,
     pub strategy_used: String,
+    /// `published`, or `draft` / `rejected` for channels with `require_approval`.
+    pub status: String,
+    /// Entries of the article's `## Skipped` section.
+    pub skipped: Vec<SkippedItem>,
+    /// Subset of `content_item_ids` the body links to. Stored in `article_item_citations`.
+    pub cited_item_ids: Vec<String>,
+    /// Overall self-evaluation score (0.0–1.0) and the rubric result as JSON; `None` unless the
+    /// channel has `self_eval`.
+    pub eval_score: Option<f64>,
+    pub eval_details: Option<String>,
+    /// JSON object of post-generation reports (`link_check`).
+    pub metadata: String,
+    /// Lede for feed list views: the model's `summary:` frontmatter field, else the first paragraph.
+    pub summary: Option<String>,
+}
+
+/// One entry of an article's `## Skipped` section, e.g. `- [Title](url) — off-topic` or
+/// `- *Channel* — 12 messages (ads)`. Stored in `skipped_items` with the article.
+#[derive(Debug, Clone)]
+pub struct SkippedItem {
+    pub title: String,
+    pub url: Option<String>,
+    pub reason: String,
+    /// Number of items the entry stands for (`N messages` for grouped Telegram entries).
+    pub item_count: i64,
+    /// Resolved from the URL or source name; `None` when nothing in the window matched.
+    pub source_id: Option<String>,
+    pub content_item_id: Option<String>,
 }
 
 /// Read model for articles from DB (used by Atom feed builder).
@@ -93,8 +175,239 @@ pub struct GeneratedArticleRow {
     pub body_html: String,
     pub body_markdown: String,
     pub content_item_ids: String,
+    #[sqlx(try_from = "StoredText")]
     pub generation_log: String,
     pub model_used: String,
     pub token_count: Option<i64>,
     pub strategy_used: String,
+    pub status: String,
+    /// When the reader marked the article read; `None` while unread.
+    pub read_at: Option<DateTime<Utc>>,
+    pub eval_score: Option<f64>,
+    pub eval_details: Option<String>,
+    pub metadata: String,
+    pub summary: Option<String>,
+}
+
+impl From<GeneratedArticle> for GeneratedArticleRow {
+    /// Row form of an article that isn't stored (ad-hoc previews), for the feed builders.
+    fn from(article: GeneratedArticle) -> Self {
+        Self {
+            id: article.id,
+            output_channel_id: article.output_channel_id,
+            generated_at: article.generated_at,
+            covers_from: article.covers_from,
+            covers_to: article.covers_to,
+            title: article.title,
+            topics: serde_json::to_string(&article.topics).unwrap_or_else(|_| "[]".to_string()),
+            body_html: article.body_html,
+            body_markdown: article.body_markdown,
+            content_item_ids: serde_json::to_string(&article.content_item_ids).unwrap_or_else(|_| "[]".to_string()),
+            generation_log: article.generation_log,
+            model_used: article.model_used,
+            token_count: article.token_count,
+            strategy_used: article.strategy_used,
+            status: article.status,
+            // Read state isn't tracked for unstored articles, so they never show as unread
+            read_at: Some(article.generated_at),
+            eval_score: article.eval_score,
+            eval_details: article.eval_details,
+            metadata: article.metadata,
+            summary: article.summary,
+        }
+    }
+}
+
+/// A reader's note on a generated article (`pail feedback`), fed into later prompts for the channel.
+#[derive(Debug, Clone, FromRow)]
+pub struct ArticleFeedback {
+    pub id: String,
+    pub output_channel_id: String,
+    pub article_id: Option<String>,
+    pub article_title: String,
+    pub note: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A cached full-text fetch of a linked article (see docs/specs/prefetch.md).
+#[derive(Debug, Clone, FromRow)]
+pub struct FetchedPage {
+    pub url: String,
+    pub fetched_at: DateTime<Utc>,
+    /// `ok`, `disallowed` (robots.txt), or `failed` (permanent HTTP error, nothing extractable).
+    pub status: String,
+    pub title: Option<String>,
+    pub text: Option<String>,
+    pub error: Option<String>,
+}
+
+/// An on-demand generation queued by a `server` instance for a worker (see docs/specs/daemon.md "Roles").
+#[derive(Debug, Clone, FromRow)]
+pub struct QueuedGeneration {
+    pub id: String,
+    pub slug: String,
+    pub window_from: Option<DateTime<Utc>>,
+    pub window_to: Option<DateTime<Utc>>,
+    pub since_secs: Option<i64>,
+    pub replaces_draft: Option<String>,
+    pub requested_at: DateTime<Utc>,
+}
+
+/// A named feed token (`pail tokens`), scoped to some channels or all of them.
+#[derive(Debug, Clone, FromRow)]
+pub struct FeedToken {
+    pub id: String,
+    pub name: String,
+    pub all_channels: bool,
+    /// Comma-separated slugs of the token's channels (unused with `all_channels`).
+    pub channel_slugs: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// One authenticated feed request in the access log.
+#[derive(Debug, Clone, FromRow)]
+pub struct FeedAccess {
+    pub token_name: String,
+    pub channel_slug: String,
+    pub status: i64,
+    pub user_agent: Option<String>,
+    pub accessed_at: DateTime<Utc>,
+}
+
+/// Where `pail tg backfill` got to in one chat of a source.
+#[derive(Debug, Clone, FromRow)]
+pub struct TgBackfillProgress {
+    pub oldest_message_id: Option<i32>,
+    pub covered_since: Option<DateTime<Utc>>,
+}
+
+/// One generation attempt of a channel, whatever its outcome.
+#[derive(Debug, Clone, FromRow)]
+pub struct GenerationRun {
+    pub id: String,
+    pub channel_id: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// `generated`, `skipped` or `failed`.
+    pub status: String,
+    /// `no_items`, `min_items`, `duplicate_window` or `cancelled` for skipped runs.
+    pub skip_reason: Option<String>,
+    pub error: Option<String>,
+    /// Items in the window after the channel's filters; `None` if the run failed before.
+    pub item_count: Option<i64>,
+    pub article_id: Option<String>,
+    /// Model invocations, retries included; 0 for runs that never reached the model.
+    pub attempts: i64,
+    /// opencode's exit code on the last attempt.
+    pub exit_code: Option<i64>,
+    /// Kept workspace of a failed run.
+    pub workspace: Option<String>,
+    /// Model of the last attempt: the one that wrote the article, for generated runs.
+    pub model: Option<String>,
+    /// opencode's reported cost and tokens, summed over the attempts that reported them.
+    pub cost_usd: Option<f64>,
+    pub tokens: Option<i64>,
+}
+
+impl GenerationRun {
+    /// Wall time of the run, in whole seconds (`3m 12s`).
+    pub fn duration(&self) -> String {
+        let secs = (self.finished_at - self.started_at).num_seconds().max(0) as u64;
+        humantime::format_duration(std::time::Duration::from_secs(secs)).to_string()
+    }
+
+    /// Status with the skip reason, e.g. `skipped (min_items)`.
+    pub fn outcome(&self) -> String {
+        match self.skip_reason {
+            Some(ref reason) => format!("{} ({reason})", self.status),
+            None => self.status.clone(),
+        }
+    }
+
+    /// Retries on top of the first attempt.
+    pub fn retries(&self) -> i64 {
+        (self.attempts - 1).max(0)
+    }
+}
+
+/// One channel's generation runs over a period, for `pail_stats` sources.
+#[derive(Debug, Clone, FromRow)]
+pub struct ChannelRunStats {
+    pub channel: String,
+    pub runs: i64,
+    pub generated: i64,
+    pub skipped: i64,
+    pub failed: i64,
+    /// `None` if no run in the period reported usage.
+    pub cost_usd: Option<f64>,
+    pub tokens: Option<i64>,
+}
+
+/// A chat in a `telegram_folder` source's folder. Disabled ones aren't fetched or listened to.
+#[derive(Debug, Clone, FromRow)]
+pub struct TgFolderChannel {
+    pub channel_tg_id: i64,
+    pub channel_name: Option<String>,
+    pub channel_username: Option<String>,
+    pub enabled: bool,
+}
+
+/// A translated variant of a generated article, made with `[translation]` for a channel's
+/// `translate` languages.
+#[derive(Debug, Clone, FromRow)]
+pub struct ArticleTranslation {
+    pub article_id: String,
+    /// ISO 639-1 code.
+    pub lang: String,
+    pub title: String,
+    pub body_markdown: String,
+    pub body_html: String,
+    pub model: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A generation in progress and the recent output of its opencode process.
+#[derive(Debug, Clone, FromRow)]
+pub struct LiveRun {
+    pub run_id: String,
+    pub channel_id: String,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// When opencode last wrote anything; `None` before it has.
+    pub last_output_at: Option<DateTime<Utc>>,
+    /// The last 64 KiB of the current attempt's stdout and stderr, interleaved.
+    pub tail: String,
+}
+
+/// An outgoing Webmention for one link of a published article (see docs/specs/webmention.md).
+#[derive(Debug, Clone, FromRow)]
+pub struct Webmention {
+    pub id: i64,
+    pub article_id: String,
+    pub target: String,
+    /// `pending`, `sent`, `no_endpoint` or `failed`.
+    pub status: String,
+    /// The target's Webmention endpoint, once discovered.
+    pub endpoint: Option<String>,
+    pub attempts: i64,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+/// An update a Telegram account received, recorded with `[telegram].debug_updates`.
+#[derive(Debug, Clone, FromRow)]
+pub struct TgDebugUpdate {
+    pub account: String,
+    pub received_at: DateTime<Utc>,
+    pub container: String,
+    pub update_type: String,
+    /// Channel whose pts sequence the update belongs to; `None` for the account's common one.
+    pub channel_id: Option<i64>,
+    pub pts: Option<i64>,
+    pub pts_count: Option<i64>,
+    pub message_id: Option<i64>,
 }
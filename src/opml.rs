@@ -0,0 +1,68 @@
+//! OPML export of pail's own feeds: `GET /feeds.opml` and `pail export opml` list every
+//! enabled channel's Atom feed with a token in its URL, so a feed reader subscribes to all
+//! of them in one import (see docs/specs/atom-feed.md "OPML Export").
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use reqwest::Url;
+use sqlx::SqlitePool;
+
+use crate::config::Config;
+use crate::models::FeedToken;
+use crate::server::html_escape;
+use crate::store;
+
+/// The OPML document for the enabled channels, sorted by name. With a named token's `scope`,
+/// only the channels it may read are listed.
+pub async fn export(pool: &SqlitePool, base_url: &str, token: &str, scope: Option<&FeedToken>) -> Result<String> {
+    let mut channels = store::get_all_enabled_channels(pool).await?;
+    channels.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut outlines = String::new();
+    for channel in channels {
+        if let Some(scope) = scope
+            && !store::feed_token_allows(pool, scope, &channel.id).await?
+        {
+            continue;
+        }
+        let mut url = Url::parse(&format!(
+            "{}/feed/default/{}.atom",
+            base_url.trim_end_matches('/'),
+            channel.slug
+        ))
+        .with_context(|| format!("invalid base URL '{base_url}'"))?;
+        url.query_pairs_mut().append_pair("token", token);
+        let name = html_escape(&channel.name);
+        outlines.push_str(&format!(
+            "    <outline type=\"rss\" text=\"{name}\" title=\"{name}\" xmlUrl=\"{}\"/>\n",
+            html_escape(url.as_str())
+        ));
+    }
+
+    Ok(format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <opml version=\"2.0\">\n  \
+           <head>\n    \
+             <title>pail digests</title>\n    \
+             <dateCreated>{}</dateCreated>\n  \
+           </head>\n  \
+           <body>\n{outlines}  </body>\n\
+         </opml>\n",
+        Utc::now().to_rfc2822()
+    ))
+}
+
+/// Base URL for feeds when there's no request to derive it from: `[pail].public_url`, else
+/// `[pail].listen` with a wildcard address read as localhost.
+pub fn default_base_url(config: &Config) -> String {
+    if let Some(ref public_url) = config.pail.public_url {
+        return public_url.trim_end_matches('/').to_string();
+    }
+    let listen = &config.pail.listen;
+    let (host, port) = listen.rsplit_once(':').unwrap_or((listen.as_str(), "80"));
+    let host = match host {
+        "" | "0.0.0.0" | "[::]" => "localhost",
+        host => host,
+    };
+    format!("http://{host}:{port}")
+}
@@ -0,0 +1,242 @@
+//! Output formats: a channel's `format` swaps the strategy's article layout for one reading
+//! mode (`narrative`, `bulleted`, `briefing`) and checks the parsed article follows it (see
+//! docs/specs/generation-engine.md "Output Formats").
+
+use pulldown_cmark::{Event, HeadingLevel, Parser, Tag, TagEnd};
+
+/// Most bullets a briefing's TL;DR may have.
+const MAX_TLDR_BULLETS: usize = 5;
+
+/// The layout instructions for a format, appended to the editorial directive.
+pub fn prompt_block(format: &str) -> String {
+    let layout = match format {
+        "narrative" => {
+            "Write the article as connected prose. Each `##` section is one or more paragraphs that tell \
+             the story of its topic, weaving related items together, with links inline in the sentences. \
+             Use a list only for a genuine enumeration inside a paragraph's flow, never as a section on its \
+             own: every topic section must have at least one paragraph."
+        }
+        "bulleted" => {
+            "Write the article as bullets. Group stories under `##` topic sections; each story is one \
+             bullet: `- **[Headline](url):** what happened and why it matters, in 1-3 sentences`. Related \
+             items go into the same bullet or nested bullets. No paragraphs inside the sections; an \
+             editor's note may follow a bullet as a blockquote."
+        }
+        _ => {
+            "Write the article as a briefing for a reader with two minutes. Right after the `# Title`, \
+             start with a `## TL;DR` section of 3-5 bullets (never more than 5), one sentence each, most \
+             important first, and nothing else in it. Then cover the stories under `##` topic sections, \
+             one short paragraph (2-3 sentences) per story, opening with the linked headline in bold."
+        }
+    };
+    format!(
+        "### Article format: {format}\n\n{layout} This format replaces any body layout described in the \
+         instructions; everything else there still applies, including links, `## Skipped`, and the \
+         frontmatter."
+    )
+}
+
+/// A `##` section of the body: its heading and what its top-level blocks are.
+#[derive(Default)]
+struct Section {
+    heading: String,
+    paragraphs: usize,
+    lists: usize,
+    items: usize,
+}
+
+impl Section {
+    /// Sections whose layout the instructions fix regardless of format: the skipped-items
+    /// list and the changelog bullets.
+    fn is_exempt(&self) -> bool {
+        let heading = self.heading.to_lowercase();
+        heading.contains("skip") || heading.contains("what changed")
+    }
+}
+
+/// The body's `##` sections in order. Text before the first one is left out.
+fn sections(body: &str) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut depth = 0usize;
+    let mut in_heading = false;
+    for event in Parser::new(body) {
+        match event {
+            Event::Start(Tag::Heading {
+                level: HeadingLevel::H2,
+                ..
+            }) if depth == 0 => {
+                sections.push(Section::default());
+                in_heading = true;
+                depth += 1;
+            }
+            Event::End(TagEnd::Heading(_)) if in_heading => {
+                in_heading = false;
+                depth -= 1;
+            }
+            Event::Start(tag) => {
+                if let Some(section) = sections.last_mut() {
+                    match tag {
+                        Tag::Paragraph if depth == 0 => section.paragraphs += 1,
+                        Tag::List(_) if depth == 0 => section.lists += 1,
+                        // Top-level lists are the only blocks with items one level down
+                        Tag::Item if depth == 1 => section.items += 1,
+                        _ => {}
+                    }
+                }
+                depth += 1;
+            }
+            Event::End(_) => depth = depth.saturating_sub(1),
+            Event::Text(text) | Event::Code(text) if in_heading => {
+                if let Some(section) = sections.last_mut() {
+                    section.heading.push_str(&text);
+                }
+            }
+            _ => {}
+        }
+    }
+    sections
+}
+
+/// Check the article body against its channel's format. The error says what's wrong, for the
+/// attempt's failure.
+pub fn check(format: &str, body: &str) -> Result<(), String> {
+    let sections = sections(body);
+    match format {
+        "narrative" => {
+            if let Some(section) = sections
+                .iter()
+                .find(|s| !s.is_exempt() && s.paragraphs == 0 && s.lists > 0)
+            {
+                return Err(format!(
+                    "narrative section '{}' is a list without any paragraph",
+                    section.heading.trim()
+                ));
+            }
+        }
+        "bulleted" => {
+            if let Some(section) = sections.iter().find(|s| !s.is_exempt() && s.paragraphs > 0) {
+                return Err(format!(
+                    "bulleted section '{}' has {} paragraph(s) outside a list",
+                    section.heading.trim(),
+                    section.paragraphs
+                ));
+            }
+        }
+        "briefing" => {
+            let Some(tldr) = sections.iter().find(|s| is_tldr(&s.heading)) else {
+                return Err("briefing has no `## TL;DR` section".to_string());
+            };
+            if tldr.items == 0 || tldr.paragraphs > 0 {
+                return Err("briefing TL;DR must be a bullet list".to_string());
+            }
+            if tldr.items > MAX_TLDR_BULLETS {
+                return Err(format!(
+                    "briefing TL;DR has {} bullets (at most {MAX_TLDR_BULLETS})",
+                    tldr.items
+                ));
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// A TL;DR heading, however it's spelled ("TL;DR", "TLDR", "tl;dr: today").
+fn is_tldr(heading: &str) -> bool {
+    let letters: String = heading
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect();
+    letters.starts_with("tldr")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sections_counts_top_level_blocks() {
+        let body = "Intro before any section.\n\n\
+                    ## First `code` topic\n\nOne.\n\nTwo.\n\n- a\n- b\n  - nested\n\n\
+                    ## Second\n\n1. x\n2. y\n3. z\n\n> quoted paragraph\n";
+        let sections = sections(body);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].heading, "First code topic");
+        assert_eq!(
+            (sections[0].paragraphs, sections[0].lists, sections[0].items),
+            (2, 1, 2)
+        );
+        assert_eq!(sections[1].heading, "Second");
+        // The blockquote's paragraph isn't top-level
+        assert_eq!(
+            (sections[1].paragraphs, sections[1].lists, sections[1].items),
+            (0, 1, 3)
+        );
+    }
+
+    #[test]
+    fn test_sections_ignores_other_heading_levels() {
+        let sections = sections("# Title\n\nText.\n\n## Topic\n\n### Sub\n\nMore.\n");
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].heading, "Topic");
+        assert_eq!(sections[0].paragraphs, 1);
+    }
+
+    #[test]
+    fn test_check_narrative() {
+        assert!(
+            check(
+                "narrative",
+                "## AI\n\nA story with [a link](https://example.org).\n\n- a list too\n"
+            )
+            .is_ok()
+        );
+        let err = check("narrative", "## AI\n\n- only\n- bullets\n").unwrap_err();
+        assert!(err.contains("'AI'"), "{err}");
+        // The skipped list keeps its own layout
+        assert!(check("narrative", "## AI\n\nProse.\n\n## Skipped\n\n- item\n").is_ok());
+    }
+
+    #[test]
+    fn test_check_bulleted() {
+        assert!(check("bulleted", "## AI\n\n- **[A](https://a.example):** one.\n\n> note\n").is_ok());
+        let err = check("bulleted", "## AI\n\nA paragraph.\n\n- a bullet\n").unwrap_err();
+        assert!(err.contains("1 paragraph(s)"), "{err}");
+        assert!(check("bulleted", "## AI\n\n- a\n\n## What changed\n\nProse here.\n").is_ok());
+    }
+
+    #[test]
+    fn test_check_briefing() {
+        let ok = "## TL;DR\n\n- one\n- two\n- three\n\n## AI\n\nA story.\n";
+        assert!(check("briefing", ok).is_ok());
+        assert_eq!(
+            check("briefing", "## AI\n\nA story.\n").unwrap_err(),
+            "briefing has no `## TL;DR` section"
+        );
+        assert_eq!(
+            check("briefing", "## TL;DR\n\nJust a sentence.\n").unwrap_err(),
+            "briefing TL;DR must be a bullet list"
+        );
+        let long = "## TLDR\n\n- 1\n- 2\n- 3\n- 4\n- 5\n- 6\n";
+        assert_eq!(
+            check("briefing", long).unwrap_err(),
+            "briefing TL;DR has 6 bullets (at most 5)"
+        );
+    }
+
+    #[test]
+    fn test_check_unknown_format_passes() {
+        assert!(check("other", "no sections at all").is_ok());
+    }
+
+    #[test]
+    fn test_is_tldr() {
+        assert!(is_tldr("TL;DR"));
+        assert!(is_tldr("TLDR"));
+        assert!(is_tldr(" tl;dr: today "));
+        assert!(is_tldr("TL/DR"));
+        assert!(!is_tldr("Summary"));
+        assert!(!is_tldr("The TL;DR"));
+    }
+}
@@ -0,0 +1,226 @@
+//! Token-budget packing: with a channel `input_token_budget`, the longest item bodies are
+//! truncated so the workspace's source files fit the budget (see
+//! docs/specs/generation-engine.md "Input Token Budget").
+
+use serde::Serialize;
+
+use crate::models::ContentItem;
+
+/// A truncated body keeps at least this many tokens, even if the budget is then exceeded.
+const MIN_KEPT_TOKENS: u64 = 100;
+
+/// What packing did, written to the workspace manifest as `packing`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PackingReport {
+    pub budget: u64,
+    /// Estimated tokens of the source files before and after packing.
+    pub estimated_tokens: u64,
+    pub packed_tokens: u64,
+    pub truncated: Vec<TruncatedItem>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TruncatedItem {
+    pub item_id: String,
+    pub title: Option<String>,
+    pub url: Option<String>,
+    /// Estimated tokens of the body, and of the part that was kept.
+    pub tokens: u64,
+    pub kept_tokens: u64,
+}
+
+/// Cost of a character in quarter tokens. Approximates BPE tokenizers like cl100k: about
+/// four characters of ASCII text per token, two of other alphabets (Cyrillic, Greek, Arabic),
+/// and one token per CJK character or emoji.
+fn char_cost(c: char) -> u64 {
+    match c as u32 {
+        0..=0x7F => 1,
+        0x2E80..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF | 0x1F000.. => 4,
+        _ => 2,
+    }
+}
+
+/// Estimated token count of `text`.
+pub fn estimate_tokens(text: &str) -> u64 {
+    text.chars().map(char_cost).sum::<u64>().div_ceil(4)
+}
+
+/// Fit the items into `budget` tokens. `fixed_tokens[i]` is what item `i` costs besides its
+/// body (headings, metadata lines); it's kept as is. Bodies are cut to a common cap, chosen so
+/// the shortest bodies stay whole and the longest lose the most. Items keep their order.
+pub fn pack_items(items: &[ContentItem], fixed_tokens: &[u64], budget: u64) -> (Vec<ContentItem>, PackingReport) {
+    let body_tokens: Vec<u64> = items.iter().map(|item| estimate_tokens(&item.body)).collect();
+    let fixed: u64 = fixed_tokens.iter().sum();
+    let estimated_tokens = fixed + body_tokens.iter().sum::<u64>();
+
+    let cap = body_cap(&body_tokens, budget.saturating_sub(fixed));
+    let mut packed = Vec::with_capacity(items.len());
+    let mut truncated = Vec::new();
+    let mut packed_tokens = fixed;
+    for (item, &tokens) in items.iter().zip(&body_tokens) {
+        match cap {
+            Some(cap) if tokens > cap => {
+                let mut item = item.clone();
+                item.body = truncate_body(&item.body, cap, tokens);
+                let kept_tokens = estimate_tokens(&item.body);
+                packed_tokens += kept_tokens;
+                truncated.push(TruncatedItem {
+                    item_id: item.id.clone(),
+                    title: item.title.clone(),
+                    url: item.url.clone(),
+                    tokens,
+                    kept_tokens,
+                });
+                packed.push(item);
+            }
+            _ => {
+                packed_tokens += tokens;
+                packed.push(item.clone());
+            }
+        }
+    }
+
+    let report = PackingReport {
+        budget,
+        estimated_tokens,
+        packed_tokens,
+        truncated,
+    };
+    (packed, report)
+}
+
+/// The largest per-body cap under which the bodies fit `available`, or `None` if they fit
+/// whole. Never below `MIN_KEPT_TOKENS`.
+fn body_cap(body_tokens: &[u64], available: u64) -> Option<u64> {
+    if body_tokens.iter().sum::<u64>() <= available {
+        return None;
+    }
+    let mut sorted = body_tokens.to_vec();
+    sorted.sort_unstable();
+    let mut remaining = available;
+    for (i, &tokens) in sorted.iter().enumerate() {
+        let left = (sorted.len() - i) as u64;
+        if tokens * left > remaining {
+            return Some((remaining / left).max(MIN_KEPT_TOKENS));
+        }
+        remaining -= tokens;
+    }
+    None
+}
+
+/// The start of `body` within `cap` tokens, cut at a paragraph or word break when one is near,
+/// plus a note saying how much was dropped.
+fn truncate_body(body: &str, cap: u64, tokens: u64) -> String {
+    let limit = cap * 4;
+    let mut cost = 0;
+    let mut end = body.len();
+    for (i, c) in body.char_indices() {
+        cost += char_cost(c);
+        if cost > limit {
+            end = i;
+            break;
+        }
+    }
+    let head = &body[..end];
+    // Prefer a break in the last fifth of the kept text
+    let min_break = end * 4 / 5;
+    let cut = head
+        .rfind("\n\n")
+        .filter(|&i| i >= min_break)
+        .or_else(|| head.rfind(char::is_whitespace).filter(|&i| i >= min_break))
+        .unwrap_or(end);
+    format!(
+        "{}\n\n[… truncated to fit the channel's input budget: about {cap} of {tokens} tokens shown]",
+        head[..cut].trim_end()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str, body: String) -> ContentItem {
+        ContentItem {
+            id: id.to_string(),
+            source_id: "src".to_string(),
+            ingested_at: chrono::Utc::now(),
+            original_date: chrono::Utc::now(),
+            content_type: "article".to_string(),
+            title: Some(id.to_string()),
+            body,
+            url: None,
+            author: None,
+            metadata: "{}".to_string(),
+            dedup_key: id.to_string(),
+            upstream_changed: false,
+        }
+    }
+
+    #[test]
+    fn test_estimate_tokens_by_script() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        assert_eq!(estimate_tokens("привіт"), 3);
+        assert_eq!(estimate_tokens("東京"), 2);
+    }
+
+    #[test]
+    fn test_body_cap_spares_short_bodies() {
+        assert_eq!(body_cap(&[100, 500], 600), None);
+        // 100 stays whole, leaving 800 for the two longer bodies
+        assert_eq!(body_cap(&[1000, 100, 500], 900), Some(400));
+        // Never below the floor, even if the budget is then exceeded
+        assert_eq!(body_cap(&[1000, 1000], 50), Some(MIN_KEPT_TOKENS));
+    }
+
+    #[test]
+    fn test_pack_items_truncates_longest_in_order() {
+        let items = vec![
+            item("long", "word ".repeat(800)),
+            item("short", "a".repeat(400)),
+            item("medium", "word ".repeat(400)),
+        ];
+        let (packed, report) = pack_items(&items, &[10, 10, 10], 930);
+
+        let ids: Vec<&str> = packed.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, ["long", "short", "medium"]);
+        assert_eq!(packed[1].body, items[1].body);
+        assert_eq!(report.estimated_tokens, 30 + 1000 + 100 + 500);
+        let truncated: Vec<(&str, u64)> = report
+            .truncated
+            .iter()
+            .map(|t| (t.item_id.as_str(), t.tokens))
+            .collect();
+        assert_eq!(truncated, [("long", 1000), ("medium", 500)]);
+        for t in &report.truncated {
+            assert!(t.kept_tokens <= 400 + 30, "{} kept {}", t.item_id, t.kept_tokens);
+        }
+        assert!(report.packed_tokens < report.estimated_tokens);
+    }
+
+    #[test]
+    fn test_pack_items_within_budget_is_unchanged() {
+        let items = vec![item("a", "text ".repeat(10))];
+        let (packed, report) = pack_items(&items, &[5], 1000);
+        assert_eq!(packed[0].body, items[0].body);
+        assert!(report.truncated.is_empty());
+        assert_eq!(report.packed_tokens, report.estimated_tokens);
+    }
+
+    #[test]
+    fn test_truncate_body_prefers_paragraph_break() {
+        let body = format!("{}\n\n{}", "x".repeat(350), "y".repeat(200));
+        let cut = truncate_body(&body, 100, 138);
+        assert_eq!(
+            cut,
+            format!(
+                "{}\n\n[… truncated to fit the channel's input budget: about 100 of 138 tokens shown]",
+                "x".repeat(350)
+            )
+        );
+        // Without a break near the end, the cut is at the limit, on a char boundary
+        let cut = truncate_body(&"é".repeat(1000), 100, 500);
+        assert!(cut.starts_with(&"é".repeat(200)) && !cut.starts_with(&"é".repeat(201)));
+    }
+}
@@ -1,17 +1,24 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::Duration;
 
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, TimeZone, Utc};
 use sqlx::SqlitePool;
 use tokio_util::sync::CancellationToken;
-use tracing::{info, warn};
+use tracing::{error, info, warn};
 
-use grammers_client::Client;
-
-use crate::config::{Config, OutputChannelConfig};
+use crate::config::{Config, OutputChannelConfig, is_mtproto_source};
+use crate::error::{FetchError, GenerationError};
+use crate::live_run::LiveSink;
 use crate::strategy::{self, StrategyRegistry};
-use crate::{fetch, fetch_tg, generate, models, store, telegram};
+use crate::telegram::TgClients;
+use crate::{
+    article_log, fetch, fetch_tg, generate, language, link_check, matrix, models, prefetch, redact, relevance,
+    self_eval, source_health, store, tagging, telegram, tg_engagement, tg_media, translate, webmention,
+};
+
+/// Number of most recent `pail feedback` notes included in a channel's generation prompt.
+const FEEDBACK_NOTES_IN_PROMPT: i64 = 10;
 
 /// How to determine the generation time window.
 pub enum TimeWindow {
@@ -19,6 +26,8 @@ pub enum TimeWindow {
     Since(Duration),
     /// Exact timestamps (e.g., --from ... --to ...).
     Explicit { from: DateTime<Utc>, to: DateTime<Utc> },
+    /// Breaking update: `from` → now, items of the named trigger sources only.
+    Update { from: DateTime<Utc>, sources: Vec<String> },
 }
 
 /// Result of a successful pipeline run.
@@ -33,19 +42,128 @@ pub(crate) struct PipelineContext {
     pub(crate) items: Vec<models::ContentItem>,
     pub(crate) source_map: HashMap<String, models::Source>,
     pub(crate) folder_channels: HashMap<String, HashMap<i64, (String, Option<String>)>>,
+    pub(crate) feedback: Vec<models::ArticleFeedback>,
+    /// The channel's latest published article (only with `changelog = true`).
+    pub(crate) previous_article: Option<models::GeneratedArticleRow>,
+    /// Prefetched full text of linked articles, keyed by URL (empty unless `[prefetch].enabled`).
+    pub(crate) pages: HashMap<String, models::FetchedPage>,
     pub(crate) covers_from: DateTime<Utc>,
     pub(crate) covers_to: DateTime<Utc>,
     pub(crate) is_override: bool,
+    /// What `last_generated` is set to after a scheduled-window run.
+    pub(crate) advance_to: DateTime<Utc>,
+}
+
+/// Fixed calendar window of a channel's `window` setting in `[pail].timezone`: the previous
+/// Monday–Sunday week or the previous month, up to local midnight. `None` for `since_last`.
+fn calendar_window(window: &str, config: &Config, now: DateTime<Utc>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let tz: chrono_tz::Tz = config.pail.timezone.parse().unwrap_or(chrono_tz::UTC);
+    let today = now.with_timezone(&tz).date_naive();
+    let (start, end) = match window {
+        "previous_week" => {
+            let this_monday = today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+            (this_monday - chrono::Duration::days(7), this_monday)
+        }
+        "previous_month" => {
+            let first_of_month = today.with_day(1)?;
+            (
+                (first_of_month - chrono::Duration::days(1)).with_day(1)?,
+                first_of_month,
+            )
+        }
+        _ => return None,
+    };
+    // Midnight can fall into a DST gap in a few zones; start at the first valid instant
+    let midnight = |date: NaiveDate| {
+        let naive = date.and_time(NaiveTime::MIN);
+        tz.from_local_datetime(&naive)
+            .earliest()
+            .or_else(|| tz.from_local_datetime(&(naive + chrono::Duration::hours(1))).earliest())
+            .map(|d| d.with_timezone(&Utc))
+    };
+    Some((midnight(start)?, midnight(end)?))
+}
+
+/// The window a generation covers: the requested one, else the channel's calendar window,
+/// else since its last generation or the first run skipped after it (7 days for a channel that
+/// never generated).
+pub(crate) fn covered_window(
+    config: &Config,
+    channel_config: &OutputChannelConfig,
+    channel: &models::OutputChannel,
+    time_window: Option<&TimeWindow>,
+    now: DateTime<Utc>,
+) -> (DateTime<Utc>, DateTime<Utc>) {
+    match time_window {
+        Some(TimeWindow::Since(d)) => {
+            let duration = chrono::Duration::from_std(*d).unwrap_or(chrono::Duration::days(7));
+            (now - duration, now)
+        }
+        Some(TimeWindow::Explicit { from, to }) => (*from, *to),
+        Some(TimeWindow::Update { from, .. }) => (*from, now),
+        None => calendar_window(&channel_config.window, config, now).unwrap_or_else(|| {
+            // Items of runs skipped for `min_items` carry over into this window
+            let from = channel
+                .skipped_from
+                .or(channel.last_generated)
+                .unwrap_or(now - chrono::Duration::days(7));
+            (from, now)
+        }),
+    }
+}
+
+/// Apply the channel's item filters in generation order: engagement, language, tags and
+/// embedding relevance. Each is a no-op unless configured.
+pub(crate) async fn filter_items(
+    pool: &SqlitePool,
+    config: &Config,
+    channel_config: &OutputChannelConfig,
+    sources: &[models::Source],
+    tg_clients: &TgClients,
+    mut items: Vec<models::ContentItem>,
+) -> Vec<models::ContentItem> {
+    // TG views and reactions as of now, not as of when the message arrived
+    let refreshed = if tg_clients.is_empty() {
+        HashSet::new()
+    } else {
+        tg_engagement::refresh_counts(tg_clients, pool, sources, &mut items).await
+    };
+    let items = tg_engagement::filter_items(config, sources, &refreshed, items);
+
+    // Language filter (no-op without channel or source `languages`)
+    let items = language::filter_items(config, channel_config, sources, items);
+
+    // Tag filter (no-op without include_tags / exclude_tags)
+    let items = tagging::filter_items(pool, &config.tagging, &config.network, channel_config, items).await;
+
+    // Embedding relevance filter (no-op without interest_profile)
+    relevance::filter_items(pool, &config.embeddings, &config.network, channel_config, items).await
+}
+
+/// `filter_items` without network calls, for previews: stored TG engagement counts, stored
+/// tags and stored embeddings. Untagged items and items without an embedding pass.
+pub(crate) async fn filter_stored_items(
+    pool: &SqlitePool,
+    config: &Config,
+    channel_config: &OutputChannelConfig,
+    sources: &[models::Source],
+    items: Vec<models::ContentItem>,
+) -> Vec<models::ContentItem> {
+    let items = tg_engagement::filter_items(config, sources, &HashSet::new(), items);
+    let items = language::filter_items(config, channel_config, sources, items);
+    let items = tagging::filter_stored_items(pool, channel_config, items).await;
+    relevance::filter_stored_items(pool, &config.embeddings, channel_config, items).await
 }
 
 /// Shared setup: channel/source lookup, time window, content fetching, item querying.
 /// Returns None if no content items were found or if cancelled.
 pub(crate) async fn prepare_pipeline_context(
     pool: &SqlitePool,
+    config: &Config,
     channel_config: &OutputChannelConfig,
     time_window: Option<TimeWindow>,
     fetch_content: bool,
-    tg_client: Option<&Client>,
+    tg_clients: &TgClients,
     cancel: &CancellationToken,
 ) -> Result<Option<PipelineContext>> {
     let channel = store::get_channel_by_slug(pool, &channel_config.slug)
@@ -66,7 +184,13 @@ pub(crate) async fn prepare_pipeline_context(
         .await
         .context("getting sources")?;
 
-    let sources: Vec<_> = all_sources.into_iter().filter(|s| s.enabled).collect();
+    let mut sources: Vec<_> = all_sources.into_iter().filter(|s| s.enabled).collect();
+    if let Some(TimeWindow::Update {
+        sources: ref trigger, ..
+    }) = time_window
+    {
+        sources.retain(|s| trigger.contains(&s.name));
+    }
     let source_ids: Vec<String> = sources.iter().map(|s| s.id.clone()).collect();
 
     if cancel.is_cancelled() {
@@ -76,20 +200,13 @@ pub(crate) async fn prepare_pipeline_context(
     // Determine time window (needed before fetching so TG history knows the boundary)
     let now = Utc::now();
     let is_override = time_window.is_some();
-    let (covers_from, covers_to) = match time_window {
-        Some(TimeWindow::Since(d)) => {
-            let duration = chrono::Duration::from_std(d).unwrap_or(chrono::Duration::days(7));
-            (now - duration, now)
-        }
-        Some(TimeWindow::Explicit { from, to }) => (from, to),
-        None => {
-            let from = if let Some(ref last_gen) = channel.last_generated {
-                *last_gen
-            } else {
-                now - chrono::Duration::days(7)
-            };
-            (from, now)
-        }
+    let (covers_from, covers_to) = covered_window(config, channel_config, &channel, time_window.as_ref(), now);
+    // The scheduler computes the next tick from `last_generated`, so a calendar window (which
+    // ends before the run) advances it to the run time instead of the window end
+    let advance_to = if channel_config.window == "since_last" {
+        covers_to
+    } else {
+        now
     };
 
     info!(
@@ -100,22 +217,32 @@ pub(crate) async fn prepare_pipeline_context(
 
     // One-shot content fetching (CLI mode only)
     if fetch_content {
-        // RSS feeds
-        let rss_sources: Vec<_> = sources.iter().filter(|s| s.source_type == "rss").collect();
-        info!(count = rss_sources.len(), "fetching RSS sources");
+        // RSS feeds, scraped pages, podcasts, and mailboxes
+        let polled_sources: Vec<_> = sources
+            .iter()
+            .filter(|s| fetch::POLLED_SOURCE_TYPES.contains(&s.source_type.as_str()) && s.gone_at.is_none())
+            .collect();
+        info!(count = polled_sources.len(), "fetching polled sources");
 
-        for source in &rss_sources {
+        for source in &polled_sources {
             if cancel.is_cancelled() {
                 return Ok(None);
             }
-            match fetch::fetch_rss_source(source).await {
+            let fetched = fetch::fetch_polled_source(pool, config, source, None).await;
+            match fetched {
                 Ok(result) => {
+                    if let Some(ref url) = result.moved_to {
+                        source_health::record_moved(pool, config, None, source, url).await;
+                    }
                     let count = result.items.len();
-                    for item in result.items {
-                        store::upsert_content_item(pool, &item)
-                            .await
-                            .context("storing content item")?;
+                    let newest = result.items.iter().map(|i| i.original_date).max();
+                    // Bookkeeping for source health; a failed write mustn't fail the run
+                    if let Err(e) = store::record_fetch_success(pool, &source.id, Utc::now(), newest).await {
+                        error!(source = %source.name, error = %e, "failed to record fetch success");
                     }
+                    store::upsert_content_items(pool, &result.items)
+                        .await
+                        .context("storing content items")?;
                     // Save fetch state (ETag, Last-Modified, last_fetched_at) so conditional
                     // GETs work on subsequent runs and the daemon poller knows when we last fetched
                     store::update_source_fetch_state(
@@ -129,22 +256,28 @@ pub(crate) async fn prepare_pipeline_context(
                     .context("updating source fetch state")?;
                     info!(source = %source.name, items = count, "fetched and stored items");
                 }
+                Err(e) if matches!(e.downcast_ref(), Some(FetchError::Gone { .. })) => {
+                    source_health::record_gone(pool, source, Utc::now()).await;
+                }
                 Err(e) => {
                     warn!(source = %source.name, error = %e, "failed to fetch source");
+                    if let Err(e) = store::record_fetch_failure(pool, &source.id, &format!("{e:#}")).await {
+                        error!(source = %source.name, error = %e, "failed to record fetch failure");
+                    }
                 }
             }
         }
 
-        // TG message history
-        if let Some(client) = tg_client {
+        // TG message history, each source through its account's client
+        if !tg_clients.is_empty() {
             let tg_sources: Vec<_> = sources
                 .iter()
-                .filter(|s| s.source_type.starts_with("telegram_"))
+                .filter(|s| is_mtproto_source(&s.source_type))
                 .cloned()
                 .collect();
             if !tg_sources.is_empty() {
                 info!(count = tg_sources.len(), "fetching TG source history");
-                fetch_tg::fetch_tg_sources(client, pool, &tg_sources, covers_from, cancel)
+                fetch_tg::fetch_tg_sources(tg_clients, pool, &tg_sources, covers_from, cancel)
                     .await
                     .context("fetching TG sources")?;
             }
@@ -154,6 +287,7 @@ pub(crate) async fn prepare_pipeline_context(
     let items = store::get_items_in_window(pool, &source_ids, covers_from, covers_to)
         .await
         .context("querying content items")?;
+    let mut items = filter_items(pool, config, channel_config, &sources, tg_clients, items).await;
 
     if items.is_empty() {
         let source_names: Vec<&str> = sources.iter().map(|s| s.name.as_str()).collect();
@@ -167,7 +301,7 @@ pub(crate) async fn prepare_pipeline_context(
         // Update last_generated so the next run doesn't re-check this empty window
         // (see docs/specs/generation-engine.md "Empty Digest Handling")
         if !is_override {
-            store::update_last_generated(pool, &channel.id, covers_to)
+            store::update_last_generated(pool, &channel.id, advance_to)
                 .await
                 .context("updating last_generated")?;
         }
@@ -189,23 +323,63 @@ pub(crate) async fn prepare_pipeline_context(
         }
     }
 
+    let feedback = store::get_recent_feedback(pool, &channel.id, FEEDBACK_NOTES_IN_PROMPT)
+        .await
+        .context("getting recent feedback")?;
+
+    let previous_article = if channel_config.changelog {
+        store::get_recent_articles(pool, &channel.id, 1)
+            .await
+            .context("getting previous article")?
+            .into_iter()
+            .next()
+    } else {
+        None
+    };
+
+    let pages = prefetch::prefetch_pages(pool, &config.prefetch, &config.network, &items, cancel).await;
+
+    // Only photos `/media/{id}` can serve are offered for embedding
+    tg_media::keep_downloaded(pool, &mut items).await;
+
     Ok(Some(PipelineContext {
         channel,
         items,
         source_map,
         folder_channels,
+        feedback,
+        previous_article,
+        pages,
         covers_from,
         covers_to,
         is_override,
+        advance_to,
     }))
 }
 
+/// How a generation ended, short of an error.
+enum RunOutcome {
+    Generated {
+        result: Box<PipelineResult>,
+        items: usize,
+    },
+    /// Nothing stored: `no_items`, `min_items` or `cancelled`. `items` is `None` if the run
+    /// was cancelled before counting them.
+    Skipped {
+        reason: &'static str,
+        items: Option<usize>,
+    },
+}
+
 /// Run the full generation pipeline for a single output channel.
 ///
 /// If `fetch_content` is true, fetches RSS feeds and TG history before generation (CLI mode).
 /// If false, assumes the poller/listener has already fetched content (daemon mode).
 ///
-/// Returns `None` if no content items were found (generation skipped).
+/// Returns `None` if the run was skipped: no content items, fewer than the channel's
+/// `min_items` (with `min_items_action = "skip"`), or cancelled. Unless `force`, a window that a
+/// stored article already covers is refused with `GenerationError::DuplicateWindow`. Every
+/// run is recorded in `generation_runs`.
 #[allow(clippy::too_many_arguments)]
 pub async fn run_generation(
     pool: &SqlitePool,
@@ -215,19 +389,453 @@ pub async fn run_generation(
     strategy_override: Option<&str>,
     time_window: Option<TimeWindow>,
     fetch_content: bool,
-    tg_client: Option<&Client>,
+    tg_clients: &TgClients,
+    force: bool,
     cancel: CancellationToken,
 ) -> Result<Option<PipelineResult>> {
-    let ctx =
-        match prepare_pipeline_context(pool, channel_config, time_window, fetch_content, tg_client, &cancel).await? {
-            Some(ctx) => ctx,
-            None => return Ok(None),
-        };
+    let started_at = Utc::now();
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let mut trace = generate::RunTrace::default();
+    if let Ok(Some(channel)) = store::get_channel_by_slug(pool, &channel_config.slug).await {
+        trace.live = LiveSink::start(pool, &run_id, &channel.id).await;
+    }
+    let outcome = generate_channel(
+        pool,
+        config,
+        channel_config,
+        registry,
+        strategy_override,
+        time_window,
+        fetch_content,
+        tg_clients,
+        force,
+        cancel,
+        &mut trace,
+    )
+    .await;
+    let live = trace.live.take();
+    record_run(pool, channel_config, &run_id, started_at, &outcome, trace).await;
+    if let Some(live) = live {
+        live.finish().await;
+    }
+    outcome.map(|outcome| match outcome {
+        RunOutcome::Generated { result, .. } => Some(*result),
+        RunOutcome::Skipped { .. } => None,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn generate_channel(
+    pool: &SqlitePool,
+    config: &Config,
+    channel_config: &OutputChannelConfig,
+    registry: &StrategyRegistry,
+    strategy_override: Option<&str>,
+    time_window: Option<TimeWindow>,
+    fetch_content: bool,
+    tg_clients: &TgClients,
+    force: bool,
+    cancel: CancellationToken,
+    trace: &mut generate::RunTrace,
+) -> Result<RunOutcome> {
+    let is_update = matches!(time_window, Some(TimeWindow::Update { .. }));
+    let check_duplicates = !force && !is_update;
+    let ctx = match prepare_pipeline_context(
+        pool,
+        config,
+        channel_config,
+        time_window,
+        fetch_content,
+        tg_clients,
+        &cancel,
+    )
+    .await?
+    {
+        Some(ctx) => ctx,
+        None if cancel.is_cancelled() => {
+            return Ok(RunOutcome::Skipped {
+                reason: "cancelled",
+                items: None,
+            });
+        }
+        None => {
+            return Ok(RunOutcome::Skipped {
+                reason: "no_items",
+                items: Some(0),
+            });
+        }
+    };
+    let item_count = ctx.items.len();
+
+    if check_duplicates {
+        ensure_window_free(pool, config, &ctx).await?;
+    }
 
     if cancel.is_cancelled() {
-        return Ok(None);
+        return Ok(RunOutcome::Skipped {
+            reason: "cancelled",
+            items: Some(item_count),
+        });
+    }
+
+    // Too few items for a digest (see docs/specs/generation-engine.md "Minimum Items").
+    // Breaking updates are short by design and exempt.
+    let quiet = !force && !is_update && channel_config.min_items.is_some_and(|min| item_count < min as usize);
+    if quiet && channel_config.min_items_action == "skip" {
+        info!(
+            channel = %ctx.channel.name,
+            items = item_count,
+            min_items = channel_config.min_items,
+            "too few items for a digest, skipping"
+        );
+        if !ctx.is_override {
+            store::skip_window(pool, &ctx.channel.id, ctx.covers_from, ctx.advance_to)
+                .await
+                .context("recording skipped window")?;
+        }
+        return Ok(RunOutcome::Skipped {
+            reason: "min_items",
+            items: Some(item_count),
+        });
+    }
+
+    let (mut article, mut raw_output) = if quiet {
+        info!(channel = %ctx.channel.name, items = item_count, "too few items for a digest, storing a quiet-period article");
+        quiet_period_article(config, channel_config, &ctx)
+    } else {
+        match generate_with_retry(
+            config,
+            channel_config,
+            registry,
+            strategy_override,
+            &ctx,
+            &cancel,
+            trace,
+        )
+        .await?
+        {
+            Some(r) => r,
+            None => {
+                return Ok(RunOutcome::Skipped {
+                    reason: "cancelled",
+                    items: Some(item_count),
+                });
+            }
+        }
+    };
+
+    if channel_config.self_eval.is_some() && !quiet {
+        (article, raw_output) = self_evaluate(
+            config,
+            channel_config,
+            registry,
+            strategy_override,
+            &ctx,
+            &cancel,
+            trace,
+            article,
+            raw_output,
+        )
+        .await;
+    }
+
+    // Last edit of the text: nothing after this may reintroduce what the rules remove
+    redact::redact_article(&channel_config.redact, &mut article, &mut raw_output);
+
+    // Update articles say so in their metadata (see docs/specs/breaking-updates.md)
+    if is_update {
+        let mut metadata: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(&article.metadata).unwrap_or_default();
+        metadata.insert("breaking_update".to_string(), serde_json::Value::Bool(true));
+        article.metadata = serde_json::Value::Object(metadata).to_string();
+    }
+
+    // Channels with require_approval hold the article back until it's reviewed
+    if channel_config.require_approval {
+        article.status = "draft".to_string();
+    }
+
+    // A concurrent run (scheduler double-fire, CLI generate next to the daemon) may have
+    // stored its article while this one was generating
+    if check_duplicates {
+        ensure_window_free(pool, config, &ctx).await?;
+    }
+
+    // Store article; a large log goes to a file first, with only its ends kept inline
+    article_log::spill(config, &mut article)
+        .await
+        .context("storing generation log")?;
+    store::insert_generated_article(pool, &article)
+        .await
+        .context("storing generated article")?;
+
+    // Mark TG channels as read if configured (see docs/specs/telegram.md "Mark-as-Read")
+    if channel_config.mark_tg_read.unwrap_or(false) {
+        // Each account marks the chats it read; items of a source are grouped by its tg_account.
+        // With mark_tg_read_sources, only the listed sources' chats are marked
+        let mut items_by_account: HashMap<&str, Vec<models::ContentItem>> = HashMap::new();
+        for item in &ctx.items {
+            if let Some(source) = ctx.source_map.get(&item.source_id)
+                && is_mtproto_source(&source.source_type)
+                && (channel_config.mark_tg_read_sources.is_empty()
+                    || channel_config.mark_tg_read_sources.contains(&source.name))
+            {
+                items_by_account
+                    .entry(source.tg_account())
+                    .or_default()
+                    .push(item.clone());
+            }
+        }
+        for (account, items) in &items_by_account {
+            match tg_clients.get(*account) {
+                Some(client) => {
+                    telegram::mark_channels_as_read(
+                        client,
+                        pool,
+                        account,
+                        items,
+                        ctx.covers_to,
+                        channel_config.mark_tg_read_dry_run,
+                    )
+                    .await
+                }
+                None => warn!(
+                    channel = %ctx.channel.name,
+                    account,
+                    "mark_tg_read is enabled but no Telegram client available for account"
+                ),
+            }
+        }
+    }
+
+    if article.status == "published" {
+        webmention::enqueue(pool, config, channel_config, &article.id, &article.body_markdown).await;
+    }
+
+    // Update last_generated (skip for --since/--from/--to overrides)
+    if !ctx.is_override {
+        store::update_last_generated(pool, &ctx.channel.id, ctx.advance_to)
+            .await
+            .context("updating last_generated")?;
+    }
+
+    // Deliver to Matrix if configured. Delivery failures don't fail the generation —
+    // the article is already stored, its window closed, and it's served via the Atom feed.
+    // Drafts are delivered when approved instead.
+    if article.status == "published"
+        && let Some(ref room) = channel_config.matrix_room
+        && let Err(e) = matrix::deliver_article(
+            &config.matrix,
+            &config.network,
+            room,
+            &article.id,
+            &article.body_markdown,
+            &article.body_html,
+        )
+        .await
+    {
+        error!(channel = %ctx.channel.name, room = %room, "Matrix delivery failed: {e:#}");
     }
 
+    // Translations come after the original is stored and served (see docs/specs/translation.md)
+    if !channel_config.translate.is_empty() && !quiet && !cancel.is_cancelled() {
+        translate::translate_article(pool, config, channel_config, &article, &cancel).await;
+    }
+
+    info!(title = %article.title, status = %article.status, "article generated successfully");
+
+    Ok(RunOutcome::Generated {
+        result: Box::new(PipelineResult { article, raw_output }),
+        items: item_count,
+    })
+}
+
+/// Store a run's outcome in `generation_runs`. A duplicate window counts as skipped. Failing to
+/// record is logged and doesn't affect the run.
+async fn record_run(
+    pool: &SqlitePool,
+    channel_config: &OutputChannelConfig,
+    run_id: &str,
+    started_at: DateTime<Utc>,
+    outcome: &Result<RunOutcome>,
+    trace: generate::RunTrace,
+) {
+    let channel_id = match store::get_channel_by_slug(pool, &channel_config.slug).await {
+        Ok(Some(channel)) => channel.id,
+        Ok(None) => return,
+        Err(e) => {
+            warn!(channel = %channel_config.name, "failed to record generation run: {e:#}");
+            return;
+        }
+    };
+    let mut run = models::GenerationRun {
+        id: run_id.to_string(),
+        channel_id,
+        started_at,
+        finished_at: Utc::now(),
+        status: "failed".to_string(),
+        skip_reason: None,
+        error: None,
+        item_count: None,
+        article_id: None,
+        attempts: trace.attempts.into(),
+        exit_code: trace.exit_code.map(i64::from),
+        workspace: None,
+        model: trace.model.clone(),
+        cost_usd: trace.usage.map(|u| u.cost_usd),
+        tokens: trace.usage.map(|u| u.tokens as i64),
+    };
+    match outcome {
+        Ok(RunOutcome::Generated { result, items }) => {
+            run.status = "generated".to_string();
+            run.item_count = Some(*items as i64);
+            run.article_id = Some(result.article.id.clone());
+        }
+        Ok(RunOutcome::Skipped { reason, items }) => {
+            run.status = "skipped".to_string();
+            run.skip_reason = Some(reason.to_string());
+            run.item_count = items.map(|n| n as i64);
+        }
+        Err(e) if matches!(e.downcast_ref(), Some(GenerationError::DuplicateWindow { .. })) => {
+            run.status = "skipped".to_string();
+            run.skip_reason = Some("duplicate_window".to_string());
+            run.error = Some(e.to_string());
+        }
+        Err(e) => {
+            run.error = Some(format!("{e:#}"));
+            run.workspace = trace.workspace;
+        }
+    }
+    // A generated run's log is stored with its article
+    let log = trace.log.filter(|_| run.status == "failed");
+    if let Err(e) = store::insert_generation_run(pool, &run, log.as_deref()).await {
+        warn!(channel = %channel_config.name, "failed to record generation run: {e:#}");
+    }
+}
+
+/// The article of a window below the channel's `min_items` with `min_items_action = "stub"`:
+/// a short note listing the items, written without the model.
+fn quiet_period_article(
+    config: &Config,
+    channel_config: &OutputChannelConfig,
+    ctx: &PipelineContext,
+) -> (models::GeneratedArticle, String) {
+    let tz: chrono_tz::Tz = config.pail.timezone.parse().unwrap_or(chrono_tz::UTC);
+    let from = ctx.covers_from.with_timezone(&tz).format("%b %-d, %H:%M");
+    let to = ctx.covers_to.with_timezone(&tz).format("%b %-d, %H:%M");
+    let count = ctx.items.len();
+
+    let mut body = format!(
+        "A quiet period: only {} between {from} and {to}, too few for a digest (this channel \
+         waits for {}). Here they are as they came in.\n",
+        match count {
+            1 => "one new item".to_string(),
+            n => format!("{n} new items"),
+        },
+        channel_config.min_items.unwrap_or_default(),
+    );
+    for item in &ctx.items {
+        let title = item
+            .title
+            .clone()
+            .filter(|t| !t.trim().is_empty())
+            .unwrap_or_else(|| item.body.chars().take(80).collect::<String>().replace('\n', " "));
+        let title = title.trim().replace(['[', ']'], "");
+        let source = ctx.source_map.get(&item.source_id).map(|s| s.name.as_str());
+        body.push_str(&match (&item.url, source) {
+            (Some(url), Some(source)) => format!("\n- [{title}]({url}) — {source}"),
+            (Some(url), None) => format!("\n- [{title}]({url})"),
+            (None, Some(source)) => format!("\n- {title} — {source}"),
+            (None, None) => format!("\n- {title}"),
+        });
+    }
+    body.push('\n');
+
+    let (title, topics) = generate::postprocess(
+        &channel_config.postprocess,
+        &channel_config.name,
+        Utc::now().with_timezone(&tz).date_naive(),
+        "Quiet period".to_string(),
+        Vec::new(),
+    );
+    let raw_output = format!("# {title}\n\n{body}");
+    let article = models::GeneratedArticle {
+        id: uuid::Uuid::new_v4().to_string(),
+        output_channel_id: ctx.channel.id.clone(),
+        generated_at: Utc::now(),
+        covers_from: ctx.covers_from,
+        covers_to: ctx.covers_to,
+        title,
+        topics,
+        body_html: generate::markdown_to_html(&body),
+        body_markdown: body,
+        content_item_ids: ctx.items.iter().map(|i| i.id.clone()).collect(),
+        generation_log: String::new(),
+        model_used: "none".to_string(),
+        token_count: None,
+        strategy_used: "quiet_period".to_string(),
+        status: "published".to_string(),
+        skipped: Vec::new(),
+        cited_item_ids: ctx
+            .items
+            .iter()
+            .filter(|i| i.url.is_some())
+            .map(|i| i.id.clone())
+            .collect(),
+        eval_score: None,
+        eval_details: None,
+        metadata: serde_json::json!({ "quiet_period": true }).to_string(),
+        summary: None,
+    };
+    (article, raw_output)
+}
+
+/// Fail with `GenerationError::DuplicateWindow` if a stored article covers the context's window
+/// (sharing at least `[pail].duplicate_overlap` of it). A scheduled run still advances `last_generated`, so
+/// the scheduler doesn't retry the same window every tick.
+async fn ensure_window_free(pool: &SqlitePool, config: &Config, ctx: &PipelineContext) -> Result<()> {
+    let Some(existing) = store::find_article_for_window(
+        pool,
+        &ctx.channel.id,
+        ctx.covers_from,
+        ctx.covers_to,
+        config.pail.duplicate_overlap,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+    warn!(
+        channel = %ctx.channel.name,
+        article_id = %existing.id,
+        "an article already covers this window, not generating a duplicate"
+    );
+    if !ctx.is_override {
+        store::update_last_generated(pool, &ctx.channel.id, ctx.advance_to)
+            .await
+            .context("updating last_generated")?;
+    }
+    Err(GenerationError::DuplicateWindow {
+        article_id: existing.id,
+        title: existing.title,
+    }
+    .into())
+}
+
+/// Resolve the strategy and generate an article from the prepared context, retrying per the
+/// strategy's `max_retries`. Attempts go down the channel's model chain: the n-th attempt uses
+/// the n-th model, the last model any further ones, and every model gets at least one attempt.
+/// Returns `None` if cancelled. Attempts are counted in `trace`.
+#[allow(clippy::too_many_arguments)]
+async fn generate_with_retry(
+    config: &Config,
+    channel_config: &OutputChannelConfig,
+    registry: &StrategyRegistry,
+    strategy_override: Option<&str>,
+    ctx: &PipelineContext,
+    cancel: &CancellationToken,
+    trace: &mut generate::RunTrace,
+) -> Result<Option<(models::GeneratedArticle, String)>> {
     // Resolve strategy (CLI override takes precedence)
     let strategy_name = strategy_override
         .map(|s| s.to_string())
@@ -243,15 +851,20 @@ pub async fn run_generation(
     let source_ref_map: HashMap<String, &models::Source> = ctx.source_map.iter().map(|(k, v)| (k.clone(), v)).collect();
 
     // Generate with retry
-    let max_retries = strategy.meta.max_retries;
+    let models = channel_config.model_chain(&config.opencode);
+    let attempts = (strategy.meta.max_retries as usize + 1).max(models.len());
     let mut last_err = None;
     let mut result = None;
 
-    for attempt in 0..=max_retries {
+    for attempt in 0..attempts {
         if cancel.is_cancelled() {
             return Ok(None);
         }
-        if attempt > 0 {
+        let model = &models[attempt.min(models.len() - 1)];
+        if attempt > 0 && attempt < models.len() {
+            // Another provider: no reason to wait out whatever failed the previous one
+            warn!(attempt, model = %model, "retrying generation with the next model");
+        } else if attempt > 0 {
             let delay = std::time::Duration::from_secs(30);
             warn!(attempt, delay_secs = 30, "retrying generation");
             tokio::select! {
@@ -269,9 +882,14 @@ pub async fn run_generation(
             &ctx.items,
             &source_ref_map,
             &ctx.folder_channels,
+            &ctx.pages,
+            &ctx.feedback,
+            ctx.previous_article.as_ref(),
             ctx.covers_from,
             ctx.covers_to,
             cancel.clone(),
+            model,
+            trace,
         )
         .await
         {
@@ -282,41 +900,177 @@ pub async fn run_generation(
             Err(e) => {
                 // @NOTE: warn (not error) — per-attempt failures are intermediate.
                 // The final error is reported once by the caller (scheduler/CLI).
-                warn!(attempt, error = %e, "generation attempt failed");
+                warn!(attempt, model = %model, error = %e, "generation attempt failed");
+                // Another attempt would spend the budget again
+                let over_budget = e.chain().any(|c| {
+                    matches!(
+                        c.downcast_ref::<GenerationError>(),
+                        Some(GenerationError::BudgetExceeded(_))
+                    )
+                });
                 last_err = Some(e);
+                if over_budget {
+                    break;
+                }
             }
         }
     }
 
-    let (article, raw_output) = match result {
-        Some(r) => r,
-        None => return Err(last_err.unwrap().context("generation failed after all retries")),
-    };
+    match result {
+        Some((mut article, raw_output)) => {
+            link_check::verify_links(channel_config, &config.network, &ctx.items, &mut article, cancel).await;
+            Ok(Some((article, raw_output)))
+        }
+        None => Err(last_err.unwrap().context("generation failed after all retries")),
+    }
+}
 
-    // Store article
-    store::insert_generated_article(pool, &article)
-        .await
-        .context("storing generated article")?;
+/// Score the article with `[self_eval]` and act on a score below `self_eval_min_score`: with
+/// `retry`, regenerate once and keep the better-scored article; with `annotate` (or a retry
+/// that still scores low), add a quality note. An article that couldn't be scored is kept
+/// as is (see docs/specs/self-evaluation.md).
+#[allow(clippy::too_many_arguments)]
+async fn self_evaluate(
+    config: &Config,
+    channel_config: &OutputChannelConfig,
+    registry: &StrategyRegistry,
+    strategy_override: Option<&str>,
+    ctx: &PipelineContext,
+    cancel: &CancellationToken,
+    trace: &mut generate::RunTrace,
+    mut article: models::GeneratedArticle,
+    raw_output: String,
+) -> (models::GeneratedArticle, String) {
+    let action = channel_config.self_eval.as_deref().unwrap_or("score");
+    let min_score = channel_config.self_eval_min_score;
+    let Some(evaluation) = self_eval::score_article(config, channel_config, &ctx.items, &mut article, cancel).await
+    else {
+        return (article, raw_output);
+    };
+    if evaluation.score >= min_score || action == "score" {
+        return (article, raw_output);
+    }
 
-    // Mark TG channels as read if configured (see docs/specs/telegram.md "Mark-as-Read")
-    if channel_config.mark_tg_read.unwrap_or(false) {
-        if let Some(client) = tg_client {
-            telegram::mark_channels_as_read(client, pool, &ctx.items).await;
-        } else {
-            warn!(channel = %ctx.channel.name, "mark_tg_read is enabled but no Telegram client available");
+    let mut best = (article, raw_output, evaluation);
+    if action == "retry" && !cancel.is_cancelled() {
+        info!(
+            channel = %ctx.channel.name,
+            score = format!("{:.2}", best.2.score),
+            min_score,
+            "article scored below self_eval_min_score, regenerating once"
+        );
+        match generate_with_retry(config, channel_config, registry, strategy_override, ctx, cancel, trace).await {
+            Ok(Some((mut retried, retried_output))) => {
+                // An unscored retry can't be compared; the first article is kept
+                if let Some(evaluation) =
+                    self_eval::score_article(config, channel_config, &ctx.items, &mut retried, cancel).await
+                {
+                    info!(
+                        channel = %ctx.channel.name,
+                        first = format!("{:.2}", best.2.score),
+                        retry = format!("{:.2}", evaluation.score),
+                        "regenerated article scored"
+                    );
+                    if evaluation.score > best.2.score {
+                        best = (retried, retried_output, evaluation);
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!(
+                channel = %ctx.channel.name,
+                "regeneration after a low self-evaluation score failed, keeping the first article: {e:#}"
+            ),
+        }
+        if best.2.score >= min_score {
+            return (best.0, best.1);
         }
     }
 
-    // Update last_generated (skip for --since/--from/--to overrides)
-    if !ctx.is_override {
-        store::update_last_generated(pool, &ctx.channel.id, ctx.covers_to)
-            .await
-            .context("updating last_generated")?;
+    let (mut article, raw_output, evaluation) = best;
+    self_eval::annotate(&mut article, &evaluation, min_score);
+    (article, raw_output)
+}
+
+/// Generate an ad-hoc article over `window` from already-stored items (feed `?window=`,
+/// `/article/preview`). Nothing is fetched, stored, or delivered, and `last_generated` is
+/// untouched. Returns `None` if the window has no items.
+pub async fn run_preview(
+    pool: &SqlitePool,
+    config: &Config,
+    channel_config: &OutputChannelConfig,
+    registry: &StrategyRegistry,
+    window: TimeWindow,
+    cancel: CancellationToken,
+) -> Result<Option<models::GeneratedArticle>> {
+    let ctx = match prepare_pipeline_context(
+        pool,
+        config,
+        channel_config,
+        Some(window),
+        false,
+        &TgClients::new(),
+        &cancel,
+    )
+    .await?
+    {
+        Some(ctx) => ctx,
+        None => return Ok(None),
+    };
+
+    let article = generate_with_retry(
+        config,
+        channel_config,
+        registry,
+        None,
+        &ctx,
+        &cancel,
+        &mut generate::RunTrace::default(),
+    )
+    .await?
+    .map(|(mut article, mut raw_output)| {
+        redact::redact_article(&channel_config.redact, &mut article, &mut raw_output);
+        article
+    });
+    if let Some(ref article) = article {
+        info!(title = %article.title, "preview article generated");
+    }
+    Ok(article)
+}
+
+/// Publish a draft article: it appears in the channel's feeds and is delivered to the
+/// channel's Matrix room (if configured). Errors if the article isn't a draft.
+pub async fn approve_draft(pool: &SqlitePool, config: &Config, article_id: &str) -> Result<models::GeneratedArticleRow> {
+    let mut article = store::get_article_by_id(pool, article_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("no article with ID '{article_id}'"))?;
+    if !store::review_draft(pool, article_id, "published").await? {
+        anyhow::bail!("article '{article_id}' is not a draft (status: {})", article.status);
     }
+    article.status = "published".to_string();
 
-    info!(title = %article.title, "article generated successfully");
+    // The article references its channel by ID; the config knows channels by slug
+    let channel = store::get_channel_by_id(pool, &article.output_channel_id).await?;
+    let channel_config = channel.and_then(|ch| config.output_channel.iter().find(|c| c.slug == ch.slug));
+    if let Some(channel_config) = channel_config {
+        webmention::enqueue(pool, config, channel_config, &article.id, &article.body_markdown).await;
+    }
+    if let Some(room) = channel_config.and_then(|c| c.matrix_room.as_ref())
+        && let Err(e) = matrix::deliver_article(
+            &config.matrix,
+            &config.network,
+            room,
+            &article.id,
+            &article.body_markdown,
+            &article.body_html,
+        )
+        .await
+    {
+        error!(article_id = %article.id, room = %room, "Matrix delivery failed: {e:#}");
+    }
 
-    Ok(Some(PipelineResult { article, raw_output }))
+    info!(article_id = %article.id, title = %article.title, "draft approved");
+    Ok(article)
 }
 
 /// Run an interactive opencode TUI session with collected source data.
@@ -334,13 +1088,14 @@ pub async fn run_interactive(
     registry: &StrategyRegistry,
     strategy_override: Option<&str>,
     time_window: Option<TimeWindow>,
-    tg_client: Option<&Client>,
+    tg_clients: &TgClients,
     cancel: CancellationToken,
 ) -> Result<Option<usize>> {
-    let ctx = match prepare_pipeline_context(pool, channel_config, time_window, true, tg_client, &cancel).await? {
-        Some(ctx) => ctx,
-        None => return Ok(None),
-    };
+    let ctx =
+        match prepare_pipeline_context(pool, config, channel_config, time_window, true, tg_clients, &cancel).await? {
+            Some(ctx) => ctx,
+            None => return Ok(None),
+        };
 
     let item_count = ctx.items.len();
 
@@ -364,13 +1119,14 @@ pub async fn run_interactive(
         &ctx.items,
         &source_ref_map,
         &ctx.folder_channels,
+        &ctx.pages,
         ctx.covers_from,
         ctx.covers_to,
     )
     .await
     .context("preparing interactive workspace")?;
 
-    generate::write_agents_md(ws.path(), strat)
+    generate::write_agents_md(ws.path(), strat, channel_config.item_files)
         .await
         .context("writing AGENTS.md")?;
 
@@ -1,16 +1,38 @@
-use chrono::Utc;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use sentry::SentryFutureExt;
 use sqlx::SqlitePool;
+use tokio::sync::watch;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
-use crate::{fetch, store};
+use crate::config::Config;
+use crate::error::FetchError;
+use crate::fetch_podcast::Transcriber;
+use crate::health::Health;
+use crate::models::Source;
+use crate::telegram::TgClients;
+use crate::{fetch, reporting, source_health, store, tagging};
 
 /// Global minimum poll interval to prevent abuse (see docs/specs/rss-sources.md "Polling").
 const MIN_POLL_INTERVAL_SECS: i64 = 300; // 5 minutes
 
-/// RSS polling loop. Wakes every 60 seconds and fetches due sources.
-pub async fn polling_loop(pool: SqlitePool, cancel: CancellationToken) {
+/// How often the poller wakes to check for due sources.
+pub const TICK_SECS: u64 = 60;
+
+/// RSS polling loop. Wakes every 60 seconds and fetches due sources (RSS, scrape, podcast).
+pub async fn polling_loop(
+    pool: SqlitePool,
+    config_rx: watch::Receiver<Arc<Config>>,
+    config_path: PathBuf,
+    tg_clients: Arc<TgClients>,
+    health: Arc<Health>,
+    cancel: CancellationToken,
+) {
     info!("RSS poller started");
+    let transcriber = Transcriber::start(pool.clone(), cancel.clone());
     // Short initial delay before first poll cycle
     tokio::select! {
         _ = cancel.cancelled() => return,
@@ -23,9 +45,12 @@ pub async fn polling_loop(pool: SqlitePool, cancel: CancellationToken) {
                 info!("RSS poller shutting down");
                 return;
             }
-            _ = tokio::time::sleep(std::time::Duration::from_secs(60)) => {}
+            _ = tokio::time::sleep(std::time::Duration::from_secs(TICK_SECS)) => {}
         }
 
+        health.poller_tick();
+        let config = config_rx.borrow().clone();
+
         let sources = match store::get_all_enabled_sources(&pool).await {
             Ok(s) => s,
             Err(e) => {
@@ -38,7 +63,11 @@ pub async fn polling_loop(pool: SqlitePool, cancel: CancellationToken) {
         let min_interval = chrono::Duration::seconds(MIN_POLL_INTERVAL_SECS);
 
         for source in &sources {
-            if source.source_type != "rss" {
+            if !fetch::POLLED_SOURCE_TYPES.contains(&source.source_type.as_str()) {
+                continue;
+            }
+            if source.gone_at.is_some() {
+                debug!(source = %source.name, "feed is gone, not polling");
                 continue;
             }
 
@@ -62,34 +91,72 @@ pub async fn polling_loop(pool: SqlitePool, cancel: CancellationToken) {
             if cancel.is_cancelled() {
                 return;
             }
+            // A heartbeat per source: a long cycle is still a live loop
+            health.poller_tick();
 
-            info!(source = %source.name, "polling RSS feed");
-
-            let (etag, last_modified) = match fetch::fetch_rss_source(source).await {
-                Ok(result) => {
-                    let count = result.items.len();
-                    for item in result.items {
-                        if let Err(e) = store::upsert_content_item(&pool, &item).await {
-                            warn!(source = %source.name, error = %e, "failed to store content item");
-                        }
-                    }
-                    if count > 0 {
-                        info!(source = %source.name, items = count, "polled and stored items");
-                    }
-                    (result.etag, result.last_modified)
-                }
-                Err(e) => {
-                    warn!(source = %source.name, error = %e, "RSS fetch failed");
-                    (source.last_etag.clone(), source.last_modified_header.clone())
-                }
-            };
+            info!(source = %source.name, source_type = %source.source_type, "polling source");
 
-            // Update last_fetched_at + cache headers regardless of success (avoid hammering broken feeds)
-            if let Err(e) =
-                store::update_source_fetch_state(&pool, &source.id, now, etag.as_deref(), last_modified.as_deref()).await
-            {
-                error!(source = %source.name, error = %e, "failed to update source fetch state");
+            poll_source(&pool, &config, &config_path, source, Some(&transcriber), now)
+                .bind_hub(reporting::task_hub("poller", &[("source", &source.name)]))
+                .await;
+        }
+
+        // Report sources that turned failing / stale or recovered
+        health.poller_tick();
+        source_health::check_sources(&pool, &config, &tg_clients).await;
+
+        // Tag new items, including those from the TG listener (no-op without [tagging])
+        health.poller_tick();
+        tagging::tag_pending_items(&pool, &config.tagging, &config.network).await;
+    }
+}
+
+/// Fetch one due source and record the outcome (items, health, cache headers).
+/// Podcast episodes go to `transcriber` when given, and are transcribed inline otherwise.
+pub async fn poll_source(
+    pool: &SqlitePool,
+    config: &Config,
+    config_path: &Path,
+    source: &Source,
+    transcriber: Option<&Transcriber>,
+    now: DateTime<Utc>,
+) {
+    let fetched = fetch::fetch_polled_source(pool, config, source, transcriber).await;
+
+    let (etag, last_modified) = match fetched {
+        Ok(result) => {
+            if let Some(ref url) = result.moved_to {
+                source_health::record_moved(pool, config, Some(config_path), source, url).await;
+            }
+            let count = result.items.len();
+            let newest = result.items.iter().map(|i| i.original_date).max();
+            if let Err(e) = store::record_fetch_success(pool, &source.id, now, newest).await {
+                error!(source = %source.name, error = %e, "failed to record fetch success");
             }
+            if let Err(e) = store::upsert_content_items(pool, &result.items).await {
+                warn!(source = %source.name, error = %e, "failed to store content items");
+            } else if count > 0 {
+                info!(source = %source.name, items = count, "polled and stored items");
+            }
+            (result.etag, result.last_modified)
+        }
+        Err(e) if matches!(e.downcast_ref(), Some(FetchError::Gone { .. })) => {
+            source_health::record_gone(pool, source, now).await;
+            (source.last_etag.clone(), source.last_modified_header.clone())
         }
+        Err(e) => {
+            warn!(source = %source.name, error = %e, "source fetch failed");
+            if let Err(e) = store::record_fetch_failure(pool, &source.id, &format!("{e:#}")).await {
+                error!(source = %source.name, error = %e, "failed to record fetch failure");
+            }
+            (source.last_etag.clone(), source.last_modified_header.clone())
+        }
+    };
+
+    // Update last_fetched_at + cache headers regardless of success (avoid hammering broken feeds)
+    if let Err(e) =
+        store::update_source_fetch_state(pool, &source.id, now, etag.as_deref(), last_modified.as_deref()).await
+    {
+        error!(source = %source.name, error = %e, "failed to update source fetch state");
     }
 }
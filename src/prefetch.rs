@@ -0,0 +1,313 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use futures_util::StreamExt;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::redirect::Policy;
+use reqwest::{StatusCode, Url};
+use scraper::{Html, Selector};
+use sqlx::SqlitePool;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::config::{NetworkConfig, PrefetchConfig};
+use crate::error::{CrawlError, PublicUrlError};
+use crate::fetch;
+use crate::http_client::{self, Crawler};
+use crate::models::{ContentItem, FetchedPage};
+use crate::store;
+
+/// Pages fetched in parallel; requests to one host are further spaced by the crawler.
+const CONCURRENT_FETCHES: usize = 4;
+
+/// Pages larger than this are not articles worth extracting.
+const MAX_PAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Extracted text is cut here; the model only needs the article, not an entire book.
+const MAX_PAGE_CHARS: usize = 60_000;
+
+/// Less text than this means the extraction found navigation chrome, not an article.
+const MIN_TEXT_CHARS: usize = 200;
+
+/// Content roots tried in order; the first one with enough text wins.
+const CONTENT_SELECTORS: &[&str] = &["article", "main", "[role=main]", "body"];
+
+/// Result of fetching one page. Transient failures are not cached, so the next generation retries.
+enum Outcome {
+    Store(FetchedPage),
+    Transient(String),
+}
+
+/// Fetch the full text of linked articles (RSS and scrape items) before generation, so the
+/// model reads it from the workspace instead of downloading every article itself. Pages are
+/// cached in `fetched_pages` by URL and shared across channels and retries. Returns the
+/// successfully extracted pages, keyed by URL. No-op unless `[prefetch].enabled`.
+///
+/// Failures are logged and generation continues without the missing pages — the model can
+/// still fetch those articles itself.
+pub async fn prefetch_pages(
+    pool: &SqlitePool,
+    config: &PrefetchConfig,
+    network: &NetworkConfig,
+    items: &[ContentItem],
+    cancel: &CancellationToken,
+) -> HashMap<String, FetchedPage> {
+    if !config.enabled {
+        return HashMap::new();
+    }
+    match fetch_pages(pool, config, network, items, cancel).await {
+        Ok(pages) => pages,
+        Err(e) => {
+            warn!("page prefetch failed, the model will fetch articles itself: {e:#}");
+            HashMap::new()
+        }
+    }
+}
+
+async fn fetch_pages(
+    pool: &SqlitePool,
+    config: &PrefetchConfig,
+    network: &NetworkConfig,
+    items: &[ContentItem],
+    cancel: &CancellationToken,
+) -> Result<HashMap<String, FetchedPage>> {
+    // Newest items first, so the cap keeps the articles most likely to make the digest
+    let mut linked: Vec<&ContentItem> = items.iter().filter(|i| i.content_type == "link").collect();
+    linked.sort_by_key(|item| std::cmp::Reverse(item.original_date));
+    let mut seen = HashSet::new();
+    let urls: Vec<String> = linked
+        .iter()
+        .filter_map(|item| item.url.clone())
+        .filter(|url| url.starts_with("https://") || url.starts_with("http://"))
+        .filter(|url| seen.insert(url.clone()))
+        .take(config.max_pages as usize)
+        .collect();
+    if urls.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let mut pages = store::get_fetched_pages(pool, &urls).await?;
+    let cached = pages.len();
+    // Keep the item's URL string alongside the parsed one: `Url` normalizes, and the
+    // workspace looks pages up by the exact string on the item
+    let missing: Vec<(String, Url)> = urls
+        .iter()
+        .filter(|url| !pages.contains_key(*url))
+        .filter_map(|url| Some((url.clone(), Url::parse(url).ok()?)))
+        .collect();
+
+    let timeout = humantime::parse_duration(&config.timeout).context("parsing prefetch timeout")?;
+    // Item links come from third-party content: the crawler follows redirects itself and only
+    // to public addresses (see docs/specs/network.md "Public Addresses")
+    let client = http_client::with_proxy(
+        reqwest::Client::builder().timeout(timeout).redirect(Policy::none()),
+        network.proxy.as_deref(),
+    )
+    .and_then(|builder| builder.build())
+    .context("building prefetch client")?;
+    let crawler = Crawler::new(client, network)?.public_only();
+
+    let outcomes: Vec<(String, Outcome)> = futures_util::stream::iter(missing)
+        .map(|(key, url)| {
+            let crawler = &crawler;
+            async move {
+                let outcome = fetch_page(crawler, &url).await;
+                (key, outcome)
+            }
+        })
+        .buffer_unordered(CONCURRENT_FETCHES)
+        .take_until(cancel.cancelled())
+        .collect()
+        .await;
+
+    let (mut fetched, mut skipped) = (0, 0);
+    for (url, outcome) in outcomes {
+        match outcome {
+            Outcome::Store(mut page) => {
+                page.url = url;
+                if page.status == "ok" {
+                    fetched += 1;
+                } else {
+                    skipped += 1;
+                    debug!(url = %page.url, status = %page.status, error = ?page.error, "page not extracted");
+                }
+                store::upsert_fetched_page(pool, &page).await?;
+                pages.insert(page.url.clone(), page);
+            }
+            Outcome::Transient(e) => {
+                skipped += 1;
+                debug!(url = %url, "page fetch failed, will retry next generation: {e}");
+            }
+        }
+    }
+
+    pages.retain(|_, page| page.status == "ok");
+    info!(
+        urls = urls.len(),
+        cached,
+        fetched,
+        skipped,
+        provided = pages.len(),
+        "prefetched article pages"
+    );
+    Ok(pages)
+}
+
+async fn fetch_page(crawler: &Crawler, url: &Url) -> Outcome {
+    let mut response = match crawler.get(url).await {
+        Ok(r) => r,
+        Err(e @ CrawlError::Disallowed) => {
+            return Outcome::Store(page(url, "disallowed", None, None, Some(&e.to_string())));
+        }
+        // A host that doesn't resolve may be back next time; a private one won't become public
+        Err(CrawlError::Refused(e)) if !matches!(e, PublicUrlError::Unresolvable { .. }) => {
+            return Outcome::Store(page(url, "failed", None, None, Some(&e.to_string())));
+        }
+        Err(e) => return Outcome::Transient(e.to_string()),
+    };
+    let status = response.status();
+    if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+        return Outcome::Transient(format!("HTTP {status}"));
+    }
+    if !status.is_success() {
+        return Outcome::Store(page(url, "failed", None, None, Some(&format!("HTTP {status}"))));
+    }
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("text/html")
+        .to_string();
+    if !content_type.contains("html") {
+        return Outcome::Store(page(
+            url,
+            "failed",
+            None,
+            None,
+            Some(&format!("not an HTML page ({content_type})")),
+        ));
+    }
+
+    let mut body = Vec::new();
+    loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                body.extend_from_slice(&chunk);
+                if body.len() > MAX_PAGE_BYTES {
+                    return Outcome::Store(page(url, "failed", None, None, Some("page larger than 5 MiB")));
+                }
+            }
+            Ok(None) => break,
+            Err(e) => return Outcome::Transient(e.to_string()),
+        }
+    }
+
+    match extract_text(&String::from_utf8_lossy(&body)) {
+        Some((title, text)) => Outcome::Store(page(url, "ok", title, Some(text), None)),
+        None => Outcome::Store(page(url, "failed", None, None, Some("no article text found"))),
+    }
+}
+
+fn page(url: &Url, status: &str, title: Option<String>, text: Option<String>, error: Option<&str>) -> FetchedPage {
+    FetchedPage {
+        url: url.to_string(),
+        fetched_at: Utc::now(),
+        status: status.to_string(),
+        title,
+        text,
+        error: error.map(str::to_string),
+    }
+}
+
+/// Page title and main text. The content root is the largest element matching the first
+/// `CONTENT_SELECTORS` entry that yields enough text — pages with several `<article>`
+/// elements (teasers, comments) keep the main one.
+pub(crate) fn extract_text(html: &str) -> Option<(Option<String>, String)> {
+    let document = Html::parse_document(html);
+
+    let title = Selector::parse("title")
+        .ok()
+        .and_then(|s| document.select(&s).next())
+        .map(|el| el.text().collect::<Vec<_>>().join(" "))
+        .map(|t| t.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|t| !t.is_empty());
+
+    for css in CONTENT_SELECTORS {
+        let Ok(selector) = Selector::parse(css) else {
+            continue;
+        };
+        let Some(root) = document
+            .select(&selector)
+            .max_by_key(|el| el.text().map(str::len).sum::<usize>())
+        else {
+            continue;
+        };
+        let text = fetch::strip_html(&root.html());
+        let text = text.trim();
+        if text.chars().count() < MIN_TEXT_CHARS {
+            continue;
+        }
+        let text = if text.chars().count() > MAX_PAGE_CHARS {
+            let mut cut: String = text.chars().take(MAX_PAGE_CHARS).collect();
+            cut.push_str("\n\n[truncated]");
+            cut
+        } else {
+            text.to_string()
+        };
+        return Some((title, text));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_loopback_link_is_stored_as_failed_without_a_request() {
+        let pool = crate::db::memory_pool().await;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let url = format!("http://{}/secret", listener.local_addr().unwrap());
+        let item = ContentItem {
+            id: "item".to_string(),
+            source_id: "source".to_string(),
+            ingested_at: Utc::now(),
+            original_date: Utc::now(),
+            content_type: "link".to_string(),
+            title: None,
+            body: String::new(),
+            url: Some(url.clone()),
+            author: None,
+            metadata: "{}".to_string(),
+            dedup_key: "item".to_string(),
+            upstream_changed: false,
+        };
+        let config = PrefetchConfig {
+            enabled: true,
+            ..Default::default()
+        };
+
+        let pages = fetch_pages(
+            &pool,
+            &config,
+            &NetworkConfig::default(),
+            &[item],
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+        assert!(pages.is_empty());
+
+        // Neither the page nor robots.txt was requested
+        assert_eq!(listener.accept().unwrap_err().kind(), std::io::ErrorKind::WouldBlock);
+        // Cached as failed, so later generations don't retry it
+        let stored = store::get_fetched_pages(&pool, std::slice::from_ref(&url))
+            .await
+            .unwrap();
+        let page = &stored[&url];
+        assert_eq!(page.status, "failed");
+        assert!(page.error.as_deref().unwrap().contains("not a public address"));
+    }
+}
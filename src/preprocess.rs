@@ -0,0 +1,306 @@
+//! Per-source pre-processors: with `preprocess`, each new item body of a polled source is piped
+//! through an external command before storage (see docs/specs/preprocessing.md).
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures_util::StreamExt;
+use serde_json::json;
+use sqlx::SqlitePool;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Semaphore;
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+use crate::config::SourceConfig;
+use crate::fetch::FetchResult;
+use crate::models::ContentItem;
+use crate::store;
+
+/// Pre-processor commands running at once, across all sources.
+const MAX_CONCURRENT: usize = 4;
+
+/// Pre-processing of one fetch stops after this; the items not done by then are left for the
+/// next poll, like `skip`.
+const TOTAL_TIMEOUT: Duration = Duration::from_secs(600);
+
+static SLOTS: Semaphore = Semaphore::const_new(MAX_CONCURRENT);
+
+/// Run the source's pre-processor over the fetched items. Items already stored or dropped are
+/// left out (their bodies went through the command before); an empty output drops the item,
+/// and its dedup key is recorded. Failures follow the source's `preprocess_on_error`: `keep`
+/// stores the original body, `skip` leaves the item for the next poll, `fail` fails the whole
+/// fetch.
+pub async fn preprocess_items(pool: &SqlitePool, configured: &SourceConfig, result: &mut FetchResult) -> Result<()> {
+    let timeout = humantime::parse_duration(&configured.preprocess_timeout).context("invalid preprocess_timeout")?;
+    let on_error = configured.preprocess_on_error.as_str();
+
+    let mut new_items = Vec::new();
+    for item in std::mem::take(&mut result.items) {
+        if !store::content_item_exists(pool, &item.source_id, &item.dedup_key).await? {
+            new_items.push(item);
+        }
+    }
+    let total = new_items.len();
+    let source_id = new_items.first().map(|i| i.source_id.clone());
+
+    // Outputs come back in item order; dropping the stream at the deadline kills the commands
+    // still running
+    let mut runs = futures_util::stream::iter(new_items)
+        .map(|item| async move {
+            let _slot = SLOTS.acquire().await.expect("pre-processor semaphore is never closed");
+            let output = run(&configured.preprocess, &configured.name, &item, timeout).await;
+            (item, output)
+        })
+        .buffered(MAX_CONCURRENT);
+    let deadline = Instant::now() + TOTAL_TIMEOUT;
+
+    let mut kept = Vec::with_capacity(total);
+    let mut dropped_keys = Vec::new();
+    let (mut processed, mut failed, mut skipped, mut done) = (0, 0, 0, 0);
+    loop {
+        let (mut item, output) = match tokio::time::timeout_at(deadline, runs.next()).await {
+            Ok(Some(run)) => run,
+            Ok(None) => break,
+            Err(_) => {
+                warn!(
+                    source = %configured.name,
+                    left = total - done,
+                    "pre-processing timed out after {}, the rest is left for the next poll",
+                    humantime::format_duration(TOTAL_TIMEOUT)
+                );
+                skipped += total - done;
+                break;
+            }
+        };
+        done += 1;
+        match output {
+            Ok(body) if body.trim().is_empty() => {
+                debug!(source = %configured.name, dedup_key = %item.dedup_key, "pre-processor dropped item");
+                dropped_keys.push(item.dedup_key);
+            }
+            Ok(body) => {
+                item.body = body.trim_end().to_string();
+                processed += 1;
+                kept.push(item);
+            }
+            Err(e) => {
+                failed += 1;
+                match on_error {
+                    "fail" => return Err(e.context(format!("pre-processing item '{}'", item.dedup_key))),
+                    "skip" => {
+                        skipped += 1;
+                        warn!(source = %configured.name, dedup_key = %item.dedup_key, "pre-processor failed, item skipped: {e:#}");
+                    }
+                    _ => {
+                        warn!(source = %configured.name, dedup_key = %item.dedup_key, "pre-processor failed, original body kept: {e:#}");
+                        item.metadata = with_error(&item.metadata, &format!("{e:#}"));
+                        kept.push(item);
+                    }
+                }
+            }
+        }
+    }
+    drop(runs);
+
+    if let Some(ref source_id) = source_id
+        && let Err(e) = store::insert_preprocess_dropped(pool, source_id, &dropped_keys).await
+    {
+        warn!(source = %configured.name, "failed to record items dropped by the pre-processor: {e:#}");
+    }
+    let dropped = dropped_keys.len();
+    if processed + dropped + failed > 0 {
+        info!(source = %configured.name, processed, dropped, failed, "pre-processed new items");
+    }
+    // Without cache headers the next poll gets the full feed back, skipped items included,
+    // instead of a 304
+    if skipped > 0 {
+        result.etag = None;
+        result.last_modified = None;
+    }
+    result.items = kept;
+    Ok(())
+}
+
+/// Pipe one body through the command. Its stdout is the new body.
+async fn run(argv: &[String], source: &str, item: &ContentItem, timeout: Duration) -> Result<String> {
+    let (program, args) = argv.split_first().context("empty preprocess command")?;
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .env("PAIL_SOURCE", source)
+        .env("PAIL_ITEM_TITLE", item.title.as_deref().unwrap_or(""))
+        .env("PAIL_ITEM_URL", item.url.as_deref().unwrap_or(""))
+        .env("PAIL_ITEM_AUTHOR", item.author.as_deref().unwrap_or(""))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("spawning pre-processor '{program}'"))?;
+
+    // Write on a separate task so a command that streams its output can't deadlock on a full pipe
+    let mut stdin = child.stdin.take().context("pre-processor has no stdin")?;
+    let body = item.body.clone();
+    let writer = tokio::spawn(async move {
+        // A command that exits without reading its input closes the pipe; that's its call
+        let _ = stdin.write_all(body.as_bytes()).await;
+    });
+
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| anyhow::anyhow!("pre-processor timed out after {}", humantime::format_duration(timeout)))?
+        .context("waiting for pre-processor")?;
+    if let Err(e) = writer.await {
+        warn!(source, "pre-processor input writer failed: {e}");
+    }
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let lines: Vec<&str> = stderr.lines().collect();
+        let tail = lines[lines.len().saturating_sub(5)..].join("\n");
+        anyhow::bail!("pre-processor exited with {}: {tail}", output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Metadata with `preprocess_error` set, so the kept original body can be told apart.
+fn with_error(metadata: &str, error: &str) -> String {
+    let mut value: serde_json::Value = serde_json::from_str(metadata).unwrap_or_else(|_| json!({}));
+    if !value.is_object() {
+        value = json!({});
+    }
+    value["preprocess_error"] = json!(error);
+    value.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source_config(script: &str, on_error: &str) -> SourceConfig {
+        toml::from_str(&format!(
+            r#"
+            name = "feed"
+            type = "rss"
+            url = "https://example.com/feed.xml"
+            preprocess = ["sh", "-c", {script:?}]
+            preprocess_timeout = "5s"
+            preprocess_on_error = "{on_error}"
+            "#
+        ))
+        .unwrap()
+    }
+
+    fn item(key: &str, body: &str) -> ContentItem {
+        ContentItem {
+            id: key.to_string(),
+            source_id: "src".to_string(),
+            ingested_at: chrono::Utc::now(),
+            original_date: chrono::Utc::now(),
+            content_type: "text".to_string(),
+            title: Some(format!("Title {key}")),
+            body: body.to_string(),
+            url: None,
+            author: None,
+            metadata: "{}".to_string(),
+            dedup_key: key.to_string(),
+            upstream_changed: false,
+        }
+    }
+
+    fn fetched(items: Vec<ContentItem>) -> FetchResult {
+        FetchResult {
+            items,
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+            moved_to: None,
+        }
+    }
+
+    async fn pool() -> SqlitePool {
+        let pool = crate::db::memory_pool().await;
+        sqlx::query("INSERT INTO sources (id, source_type, name) VALUES ('src', 'rss', 'feed')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_run_pipes_body_and_environment() {
+        let argv: Vec<String> = ["sh", "-c", r#"tr a-z A-Z; printf '%s' "$PAIL_SOURCE/$PAIL_ITEM_TITLE""#]
+            .map(String::from)
+            .into();
+        let body = run(&argv, "feed", &item("a", "hello\n"), Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(body, "HELLO\nfeed/Title a");
+
+        let argv: Vec<String> = ["sh", "-c", "echo oops >&2; exit 3"].map(String::from).into();
+        let error = run(&argv, "feed", &item("a", ""), Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert!(format!("{error:#}").contains("oops"), "{error:#}");
+
+        let argv: Vec<String> = ["sleep", "5"].map(String::from).into();
+        let error = run(&argv, "feed", &item("a", ""), Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert!(format!("{error:#}").contains("timed out"), "{error:#}");
+    }
+
+    #[tokio::test]
+    async fn test_dropped_items_are_recorded_and_not_run_again() {
+        let pool = pool().await;
+        // Keeps bodies mentioning "keep", drops the rest
+        let configured = source_config("grep keep || true", "keep");
+        let mut result = fetched(vec![item("a", "keep me"), item("b", "drop me"), item("c", "keep too")]);
+        preprocess_items(&pool, &configured, &mut result).await.unwrap();
+        let keys: Vec<&str> = result.items.iter().map(|i| i.dedup_key.as_str()).collect();
+        assert_eq!(keys, ["a", "c"]);
+        assert!(store::content_item_exists(&pool, "src", "b").await.unwrap());
+
+        // A command that would keep everything never sees the dropped item again
+        let configured = source_config("cat", "keep");
+        let mut result = fetched(vec![item("b", "drop me"), item("d", "new")]);
+        preprocess_items(&pool, &configured, &mut result).await.unwrap();
+        let keys: Vec<&str> = result.items.iter().map(|i| i.dedup_key.as_str()).collect();
+        assert_eq!(keys, ["d"]);
+        assert!(result.etag.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_failure_policies() {
+        let pool = pool().await;
+
+        let mut result = fetched(vec![item("a", "body")]);
+        preprocess_items(&pool, &source_config("exit 1", "keep"), &mut result)
+            .await
+            .unwrap();
+        assert_eq!(result.items[0].body, "body");
+        assert!(result.items[0].metadata.contains("preprocess_error"));
+
+        let mut result = fetched(vec![item("a", "body")]);
+        preprocess_items(&pool, &source_config("exit 1", "skip"), &mut result)
+            .await
+            .unwrap();
+        assert!(result.items.is_empty());
+        assert!(result.etag.is_none());
+        assert!(!store::content_item_exists(&pool, "src", "a").await.unwrap());
+
+        let mut result = fetched(vec![item("a", "body")]);
+        assert!(
+            preprocess_items(&pool, &source_config("exit 1", "fail"), &mut result)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_with_error_replaces_non_object_metadata() {
+        assert_eq!(with_error("[1]", "boom"), r#"{"preprocess_error":"boom"}"#);
+        let metadata: serde_json::Value = serde_json::from_str(&with_error(r#"{"a":1}"#, "boom")).unwrap();
+        assert_eq!(metadata, json!({"a": 1, "preprocess_error": "boom"}));
+    }
+}
@@ -0,0 +1,312 @@
+//! `pail prompt tune`: a REPL for iterating on a channel's editorial directive. Items are
+//! collected and the workspace is built once; each run renders the prompt with the current
+//! directive in a copy of that workspace, so every attempt sees the same inputs (see
+//! docs/specs/interactive-mode.md "Prompt Tuning").
+
+use std::collections::HashMap;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::{Context, Result, bail};
+use sqlx::SqlitePool;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+use crate::budget::{Budget, SessionUsage};
+use crate::config::{Config, OutputChannelConfig};
+use crate::pipeline::{self, PipelineContext, TimeWindow};
+use crate::strategy::{self, Strategy, StrategyRegistry};
+use crate::telegram::TgClients;
+use crate::{diff, generate, models};
+
+const HELP: &str = "\
+Commands:
+  run, r          generate with the current directive
+  edit, e         open the directive in $VISUAL / $EDITOR
+  directive, d    print the current directive
+  show [N]        print run N's output (default: the latest)
+  diff [A B]      diff the outputs of runs A and B (default: the last two)
+  help, h         this list
+  quit, q         leave (also Ctrl-D); Ctrl-C stops a running generation";
+
+/// One generation of the session.
+struct Run {
+    directive: String,
+    output: String,
+}
+
+/// Collect the channel's items for the window, build the workspace, then read commands until
+/// the user quits. Returns the number of runs, or `None` if the window has no items. The config
+/// file is never changed: an edited directive is kept in a file whose path is printed on exit.
+#[allow(clippy::too_many_arguments)]
+pub async fn run(
+    pool: &SqlitePool,
+    config: &Config,
+    channel_config: &OutputChannelConfig,
+    registry: &StrategyRegistry,
+    strategy_override: Option<&str>,
+    time_window: Option<TimeWindow>,
+    tg_clients: &TgClients,
+    cancel: CancellationToken,
+) -> Result<Option<usize>> {
+    let Some(ctx) =
+        pipeline::prepare_pipeline_context(pool, config, channel_config, time_window, true, tg_clients, &cancel).await?
+    else {
+        return Ok(None);
+    };
+
+    let strategy_name = strategy_override
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| strategy::resolve_strategy_name(config, channel_config));
+    let strat = registry
+        .get(&strategy_name)
+        .ok_or_else(|| anyhow::anyhow!("strategy '{strategy_name}' not found in registry"))?;
+    let merged_opencode_config = strategy::resolve_opencode_config(strat)?;
+    let source_ref_map: HashMap<String, &models::Source> = ctx.source_map.iter().map(|(k, v)| (k.clone(), v)).collect();
+    let base = generate::prepare_workspace(
+        config,
+        channel_config,
+        strat,
+        &merged_opencode_config,
+        &ctx.items,
+        &source_ref_map,
+        &ctx.folder_channels,
+        &ctx.pages,
+        ctx.covers_from,
+        ctx.covers_to,
+    )
+    .await
+    .context("preparing workspace")?;
+
+    // With a prompt template the template itself is edited, so it still renders per run
+    let template = channel_config
+        .prompt_template
+        .as_deref()
+        .or(config.pail.prompt_template.as_deref());
+    let initial = match template {
+        Some(path) => tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("reading prompt template {}", path.display()))?,
+        None => channel_config.prompt.clone(),
+    };
+    let session_dir = tempfile::Builder::new()
+        .prefix("pail-tune-")
+        .tempdir()
+        .context("creating session directory")?;
+    let directive_path = session_dir.path().join(match template {
+        Some(_) => "directive.md.j2",
+        None => "directive.md",
+    });
+    tokio::fs::write(&directive_path, &initial)
+        .await
+        .context("writing directive")?;
+
+    let tz: chrono_tz::Tz = config.pail.timezone.parse().unwrap_or(chrono_tz::UTC);
+    let fmt = |dt: chrono::DateTime<chrono::Utc>| dt.with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string();
+    println!(
+        "{} items from {} to {}, {} strategy, {}.",
+        ctx.items.len(),
+        fmt(ctx.covers_from),
+        fmt(ctx.covers_to),
+        strat.meta.name,
+        base.model
+    );
+    println!("Directive: {}\n\n{HELP}\n", directive_path.display());
+
+    let color = std::io::stdout().is_terminal();
+    let mut runs: Vec<Run> = Vec::new();
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        print!("tune> ");
+        std::io::stdout().flush().ok();
+        let line = tokio::select! {
+            line = lines.next_line() => line.context("reading command")?,
+            _ = tokio::signal::ctrl_c() => None,
+        };
+        let Some(line) = line else {
+            println!();
+            break;
+        };
+        let line = line.trim();
+        let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+        let numbers: Vec<usize> = args.split_whitespace().filter_map(|a| a.parse().ok()).collect();
+
+        match command {
+            "" => {}
+            "run" | "r" => {
+                let directive = tokio::fs::read_to_string(&directive_path)
+                    .await
+                    .context("reading directive")?;
+                let tuned = tuned_channel(channel_config, &directive, template.map(|_| directive_path.clone()));
+                let started = Instant::now();
+                match generate_once(config, &tuned, strat, base.path(), &ctx, &base.model).await {
+                    Ok((output, usage)) => {
+                        let n = runs.len() + 1;
+                        let usage = usage.map(|u| format!(", {u}")).unwrap_or_default();
+                        println!("\n=== Run {n} ({}s{usage}) ===", started.elapsed().as_secs());
+                        match generate::check_output(&output, &tuned) {
+                            Ok(title) => println!("Title: {title}\n"),
+                            Err(e) => println!("A generation would reject this output: {e:#}\n"),
+                        }
+                        match runs.last() {
+                            Some(previous) => {
+                                if previous.directive == directive {
+                                    println!("(Same directive as run {}: the changes are the model's own.)\n", n - 1);
+                                }
+                                print_diff((n - 1, &previous.output), (n, &output), color);
+                            }
+                            None => print!("{output}"),
+                        }
+                        runs.push(Run { directive, output });
+                    }
+                    Err(e) => println!("Run failed: {e:#}"),
+                }
+            }
+            "edit" | "e" => {
+                if let Err(e) = edit(&directive_path).await {
+                    println!("{e:#}");
+                }
+            }
+            "directive" | "d" => match tokio::fs::read_to_string(&directive_path).await {
+                Ok(directive) => println!("{}", directive.trim_end()),
+                Err(e) => println!("Reading {}: {e}", directive_path.display()),
+            },
+            "show" => match numbers.first().copied().unwrap_or(runs.len()) {
+                n if (1..=runs.len()).contains(&n) => print!("{}", runs[n - 1].output),
+                _ => println!("No such run ({} so far).", runs.len()),
+            },
+            "diff" => {
+                let (a, b) = match numbers[..] {
+                    [a, b] => (a, b),
+                    [] if runs.len() >= 2 => (runs.len() - 1, runs.len()),
+                    _ => (0, 0),
+                };
+                if a == 0 || b == 0 || a > runs.len() || b > runs.len() {
+                    println!("Give two run numbers (1-{}), or none for the last two.", runs.len());
+                } else {
+                    print_diff((a, &runs[a - 1].output), (b, &runs[b - 1].output), color);
+                }
+            }
+            "help" | "h" | "?" => println!("{HELP}"),
+            "quit" | "q" | "exit" => break,
+            other => println!("Unknown command '{other}'. Type 'help' for the list."),
+        }
+    }
+
+    let directive = tokio::fs::read_to_string(&directive_path).await.unwrap_or_default();
+    if directive != initial {
+        let kept = session_dir.keep();
+        println!(
+            "Edited directive kept at {} (the config is unchanged).",
+            kept.join(directive_path.file_name().unwrap_or_default()).display()
+        );
+    }
+    Ok(Some(runs.len()))
+}
+
+/// The channel with the directive in place of its prompt, or of its template when it has one.
+fn tuned_channel(
+    channel_config: &OutputChannelConfig,
+    directive: &str,
+    template: Option<PathBuf>,
+) -> OutputChannelConfig {
+    let mut tuned = channel_config.clone();
+    match template {
+        Some(path) => tuned.prompt_template = Some(path),
+        None => tuned.prompt = directive.to_string(),
+    }
+    tuned
+}
+
+/// One generation in a fresh copy of the base workspace, with the channel's feedback and
+/// changelog block as a real run would have them. Nothing is stored.
+async fn generate_once(
+    config: &Config,
+    channel_config: &OutputChannelConfig,
+    strategy: &Strategy,
+    base: &Path,
+    ctx: &PipelineContext,
+    model: &str,
+) -> Result<(String, Option<SessionUsage>)> {
+    let ws = tempfile::Builder::new()
+        .prefix("pail-tune-run-")
+        .tempdir()
+        .context("creating run workspace")?;
+    let (src, dst) = (base.to_path_buf(), ws.path().to_path_buf());
+    tokio::task::spawn_blocking(move || generate::copy_dir_recursive(&src, &dst))
+        .await
+        .context("copying workspace")??;
+
+    let prompt = generate::write_prompt(
+        ws.path(),
+        strategy,
+        config,
+        channel_config,
+        &ctx.feedback,
+        ctx.previous_article.as_ref(),
+    )
+    .await
+    .context("writing prompt")?;
+    tokio::fs::write(ws.path().join("output.md"), "").await?;
+
+    // Ctrl-C stops this run, not the session
+    let cancel = CancellationToken::new();
+    let on_ctrl_c = {
+        let cancel = cancel.clone();
+        tokio::spawn(async move {
+            tokio::signal::ctrl_c().await.ok();
+            cancel.cancel();
+        })
+    };
+    let result = generate::invoke_opencode(
+        &config.opencode,
+        ws.path(),
+        model,
+        &prompt,
+        channel_config.timeout.as_deref().unwrap_or(&strategy.meta.timeout),
+        Budget::for_channel(&config.opencode, channel_config),
+        None,
+        cancel,
+    )
+    .await;
+    on_ctrl_c.abort();
+    let (log, exit_code, usage) = result.context("invoking opencode")?;
+    if exit_code != Some(0) {
+        warn!(exit_code = ?exit_code, "opencode exited with non-zero code, checking output anyway");
+    }
+
+    let output = tokio::fs::read_to_string(ws.path().join("output.md")).await?;
+    if output.trim().is_empty() {
+        bail!("output.md is empty. opencode log:\n{log}");
+    }
+    Ok((output, usage))
+}
+
+/// Word diff of two runs' outputs, each given with its run number.
+fn print_diff((a, old): (usize, &str), (b, new): (usize, &str), color: bool) {
+    println!("--- run {a}\n+++ run {b}\n");
+    let changes = diff::word_diff(old, new);
+    print!("{}", diff::render(&changes, color));
+}
+
+/// Open the directive in `$VISUAL` or `$EDITOR` (default `vi`).
+async fn edit(path: &Path) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // Through the shell, so editors with arguments (`code --wait`) work
+    let status = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$1\""))
+        .arg("sh")
+        .arg(path)
+        .status()
+        .await
+        .with_context(|| format!("running {editor}"))?;
+    if !status.success() {
+        bail!("{editor} exited with {status}");
+    }
+    Ok(())
+}
@@ -0,0 +1,202 @@
+//! Redaction: a channel's `[output_channel.redact]` rules replace private identifiers (group
+//! names, people, internal URLs) in its articles before they're stored or served, so a digest
+//! built from private chats can be shared (see docs/specs/redaction.md).
+
+use std::ops::Range;
+
+use pulldown_cmark::{Event, LinkType, Parser, Tag};
+use regex::{NoExpand, Regex};
+use tracing::{info, warn};
+
+use crate::config::RedactConfig;
+use crate::generate;
+use crate::models::GeneratedArticle;
+
+/// The rules compiled into one expression: literals case-insensitively, then the patterns.
+/// One pass means a replacement is never matched again.
+struct Redactor {
+    regex: Regex,
+    replacement: String,
+}
+
+impl Redactor {
+    fn new(rules: &RedactConfig) -> Result<Option<Self>, regex::Error> {
+        if rules.is_empty() {
+            return Ok(None);
+        }
+        let alternatives: Vec<String> = rules
+            .literals
+            .iter()
+            .map(|literal| format!("(?i:{})", regex::escape(literal)))
+            .chain(rules.patterns.iter().map(|pattern| format!("(?:{pattern})")))
+            .collect();
+        Ok(Some(Self {
+            regex: Regex::new(&alternatives.join("|"))?,
+            replacement: rules.replacement.clone(),
+        }))
+    }
+
+    /// The text with every match replaced, and the number of matches.
+    fn text(&self, text: &str) -> (String, usize) {
+        let count = self.regex.find_iter(text).count();
+        if count == 0 {
+            return (text.to_string(), 0);
+        }
+        (
+            self.regex.replace_all(text, NoExpand(&self.replacement)).into_owned(),
+            count,
+        )
+    }
+
+    /// Markdown with every match replaced. A link whose URL matches is first cut down to its
+    /// text, so no half-redacted link is left pointing somewhere.
+    fn markdown(&self, markdown: &str) -> (String, usize) {
+        let mut unlinked = markdown.to_string();
+        let mut count = 0;
+        for (range, text) in self.matching_links(markdown).into_iter().rev() {
+            unlinked.replace_range(range, &text);
+            count += 1;
+        }
+        let (redacted, matches) = self.text(&unlinked);
+        (redacted, count + matches)
+    }
+
+    /// Links whose URL matches a rule: their byte range and what replaces them (the link
+    /// text, or the replacement for autolinks).
+    fn matching_links(&self, markdown: &str) -> Vec<(Range<usize>, String)> {
+        Parser::new(markdown)
+            .into_offset_iter()
+            .filter_map(|(event, range)| match event {
+                Event::Start(Tag::Link {
+                    link_type, dest_url, ..
+                }) if self.regex.is_match(&dest_url) => {
+                    let source = &markdown[range.clone()];
+                    let text = match link_type {
+                        LinkType::Inline => match source.rfind("](") {
+                            Some(end) if source.starts_with('[') => source[1..end].to_string(),
+                            _ => return None,
+                        },
+                        LinkType::Autolink => self.replacement.clone(),
+                        _ => return None,
+                    };
+                    Some((range, text))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Apply the channel's redaction rules to an article and its raw output. The number of
+/// replacements is recorded under `redactions` in the article metadata. No-op without rules.
+pub fn redact_article(rules: &RedactConfig, article: &mut GeneratedArticle, raw_output: &mut String) {
+    let redactor = match Redactor::new(rules) {
+        Ok(Some(redactor)) => redactor,
+        Ok(None) => return,
+        Err(e) => {
+            // Validated when the config is loaded
+            warn!("invalid redaction rules, article not redacted: {e}");
+            return;
+        }
+    };
+
+    let mut total = 0;
+    let mut apply = |text: &mut String, markdown: bool| {
+        let (redacted, count) = if markdown {
+            redactor.markdown(text)
+        } else {
+            redactor.text(text)
+        };
+        *text = redacted;
+        total += count;
+    };
+    apply(&mut article.title, false);
+    for topic in &mut article.topics {
+        apply(topic, false);
+    }
+    if let Some(ref mut summary) = article.summary {
+        apply(summary, false);
+    }
+    apply(&mut article.body_markdown, true);
+    // The raw output (`pail generate --output`) is the same article, before pail's own edits
+    *raw_output = redactor.markdown(raw_output).0;
+    if total == 0 {
+        return;
+    }
+
+    article.title = generate::sanitize_xml_text(&article.title);
+    article.body_html = generate::markdown_to_html(&article.body_markdown);
+    let mut metadata: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&article.metadata).unwrap_or_default();
+    metadata.insert("redactions".to_string(), serde_json::json!(total));
+    article.metadata = serde_json::Value::Object(metadata).to_string();
+    info!(redactions = total, "article redacted");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redactor() -> Redactor {
+        let rules = RedactConfig {
+            literals: vec!["Secret Group".to_string()],
+            patterns: vec![r"intra\.example\.com".to_string()],
+            ..Default::default()
+        };
+        Redactor::new(&rules).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_matching_links_ranges_and_texts() {
+        let markdown = "A [note](https://intra.example.com/n) and <https://intra.example.com/a>, \
+                        not [this](https://example.org/).";
+        let links = redactor().matching_links(markdown);
+        assert_eq!(links.len(), 2);
+        let (ref range, ref text) = links[0];
+        assert_eq!(&markdown[range.clone()], "[note](https://intra.example.com/n)");
+        assert_eq!(text, "note");
+        let (ref range, ref text) = links[1];
+        assert_eq!(&markdown[range.clone()], "<https://intra.example.com/a>");
+        assert_eq!(text, "[redacted]");
+    }
+
+    #[test]
+    fn test_matching_links_leaves_reference_links() {
+        // The definition's URL is plain text to the second pass
+        let markdown = "See [notes][1].\n\n[1]: https://intra.example.com/x\n";
+        assert!(redactor().matching_links(markdown).is_empty());
+        let (redacted, count) = redactor().markdown(markdown);
+        assert_eq!(redacted, "See [notes][1].\n\n[1]: https://[redacted]/x\n");
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_markdown_unlinks_matching_urls() {
+        let (redacted, count) = redactor()
+            .markdown("See [the notes](https://intra.example.com/notes) and [more](https://intra.example.com/more).");
+        assert_eq!(redacted, "See the notes and more.");
+        assert_eq!(count, 2);
+
+        let (redacted, count) = redactor().markdown("Docs at <https://intra.example.com/docs>.");
+        assert_eq!(redacted, "Docs at [redacted].");
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_markdown_redacts_text_and_link_text() {
+        let (redacted, count) = redactor().markdown(
+            "From the secret group: [SECRET GROUP thread](https://example.org/t), see [x](https://intra.example.com).",
+        );
+        assert_eq!(
+            redacted,
+            "From the [redacted]: [[redacted] thread](https://example.org/t), see x."
+        );
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_markdown_without_matches_is_unchanged() {
+        let markdown = "Nothing [private](https://example.org/) here.";
+        assert_eq!(redactor().markdown(markdown), (markdown.to_string(), 0));
+    }
+}
@@ -0,0 +1,281 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::header::USER_AGENT;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::SqlitePool;
+use tracing::{debug, info, warn};
+
+use crate::config::{EmbeddingsConfig, NetworkConfig, OutputChannelConfig};
+use crate::models::ContentItem;
+use crate::{http_client, store};
+
+/// Inputs per embeddings request.
+const BATCH_SIZE: usize = 64;
+
+/// Upper bound for one embeddings request. Scoring runs inside the generation, so a stalled
+/// endpoint would otherwise hold its slot indefinitely.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Characters of title + body embedded per item. Embedding models truncate long input
+/// anyway, and the opening of an item is what decides its topic.
+const MAX_INPUT_CHARS: usize = 2000;
+
+/// The last embedding of a channel's interest profile, kept in `settings` for previews.
+#[derive(Serialize, Deserialize)]
+struct StoredProfile {
+    model: String,
+    profile: String,
+    vector: Vec<f32>,
+}
+
+/// Settings key holding the channel's `StoredProfile`.
+fn profile_key(slug: &str) -> String {
+    format!("relevance_profile:{slug}")
+}
+
+#[derive(Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Score items against the channel's `interest_profile` and exclude or demote the ones
+/// below `relevance_threshold`. No-op for channels without a profile.
+///
+/// Scoring failures are logged and the items pass through unfiltered — a noisy digest
+/// beats a missing one.
+pub async fn filter_items(
+    pool: &SqlitePool,
+    config: &EmbeddingsConfig,
+    network: &NetworkConfig,
+    channel_config: &OutputChannelConfig,
+    items: Vec<ContentItem>,
+) -> Vec<ContentItem> {
+    let Some(ref profile) = channel_config.interest_profile else {
+        return items;
+    };
+
+    match score_items(pool, config, network, &channel_config.slug, profile, &items).await {
+        Ok(scores) => apply_scores(channel_config, items, &scores),
+        Err(e) => {
+            warn!(channel = %channel_config.slug, "relevance scoring failed, using all items: {e:#}");
+            items
+        }
+    }
+}
+
+/// `filter_items` with stored vectors only, for previews: the profile vector of the channel's
+/// last scoring, if its model and profile are still the configured ones, and cached item
+/// embeddings. Items without one pass. Nothing is embedded; without a usable profile vector
+/// the items pass unfiltered.
+pub async fn filter_stored_items(
+    pool: &SqlitePool,
+    config: &EmbeddingsConfig,
+    channel_config: &OutputChannelConfig,
+    items: Vec<ContentItem>,
+) -> Vec<ContentItem> {
+    let (Some(profile), Some(model)) = (&channel_config.interest_profile, &config.model) else {
+        return items;
+    };
+    let stored = match store::get_setting(pool, &profile_key(&channel_config.slug)).await {
+        Ok(stored) => stored.and_then(|v| serde_json::from_str::<StoredProfile>(&v).ok()),
+        Err(e) => {
+            warn!(channel = %channel_config.slug, "loading the stored interest profile failed: {e:#}");
+            None
+        }
+    };
+    let Some(stored) = stored.filter(|s| &s.model == model && &s.profile == profile) else {
+        warn!(channel = %channel_config.slug, "no stored vector for the current interest profile, relevance filter not applied");
+        return items;
+    };
+
+    let ids: Vec<String> = items.iter().map(|i| i.id.clone()).collect();
+    match store::get_item_embeddings(pool, &ids, model).await {
+        Ok(embeddings) => {
+            let scores: Vec<f32> = items
+                .iter()
+                .map(|item| {
+                    embeddings
+                        .get(&item.id)
+                        .map(|v| cosine_similarity(&stored.vector, v))
+                        .unwrap_or(1.0)
+                })
+                .collect();
+            apply_scores(channel_config, items, &scores)
+        }
+        Err(e) => {
+            warn!(channel = %channel_config.slug, "loading item embeddings failed, using all items: {e:#}");
+            items
+        }
+    }
+}
+
+/// Cosine similarity of each item to the profile. Item embeddings are cached per model in
+/// the database; only items without a cached vector hit the API. The profile is embedded
+/// every time and kept for `filter_stored_items`.
+async fn score_items(
+    pool: &SqlitePool,
+    config: &EmbeddingsConfig,
+    network: &NetworkConfig,
+    slug: &str,
+    profile: &str,
+    items: &[ContentItem],
+) -> Result<Vec<f32>> {
+    let model = config
+        .model
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("[embeddings].model is not configured"))?;
+    let client = http_client::with_proxy(reqwest::Client::builder(), network.proxy.as_deref())
+        .and_then(|builder| builder.timeout(REQUEST_TIMEOUT).build())
+        .context("building HTTP client")?;
+
+    let ids: Vec<String> = items.iter().map(|i| i.id.clone()).collect();
+    let mut embeddings = store::get_item_embeddings(pool, &ids, model).await?;
+
+    let missing: Vec<&ContentItem> = items.iter().filter(|i| !embeddings.contains_key(&i.id)).collect();
+    for batch in missing.chunks(BATCH_SIZE) {
+        let inputs: Vec<String> = batch.iter().map(|item| embedding_input(item)).collect();
+        let vectors = embed(&client, config, model, &inputs).await?;
+        for (item, vector) in batch.iter().zip(vectors) {
+            store::insert_item_embedding(pool, &item.id, model, &vector).await?;
+            embeddings.insert(item.id.clone(), vector);
+        }
+    }
+    debug!(
+        computed = missing.len(),
+        cached = items.len() - missing.len(),
+        "item embeddings ready"
+    );
+
+    let profile_vector = embed(&client, config, model, &[profile.to_string()])
+        .await?
+        .pop()
+        .ok_or_else(|| anyhow::anyhow!("embeddings API returned no vector for the interest profile"))?;
+    let stored = StoredProfile {
+        model: model.to_string(),
+        profile: profile.to_string(),
+        vector: profile_vector,
+    };
+    store::set_setting(pool, &profile_key(slug), &serde_json::to_string(&stored)?).await?;
+    let profile_vector = stored.vector;
+
+    Ok(items
+        .iter()
+        .map(|item| {
+            embeddings
+                .get(&item.id)
+                .map(|v| cosine_similarity(&profile_vector, v))
+                .unwrap_or(1.0)
+        })
+        .collect())
+}
+
+/// Drop (`exclude`) or annotate and move to the end (`demote`) items scoring below the threshold.
+/// Demoted items carry `relevance_score` in their in-memory metadata, which the workspace
+/// writer renders as a low-relevance marker. Nothing is written back to the database.
+fn apply_scores(channel_config: &OutputChannelConfig, items: Vec<ContentItem>, scores: &[f32]) -> Vec<ContentItem> {
+    let threshold = channel_config.relevance_threshold;
+    let demote = channel_config.relevance_action == "demote";
+    let total = items.len();
+
+    let mut kept: Vec<(ContentItem, bool)> = Vec::with_capacity(total);
+    let mut below = 0;
+    for (mut item, &score) in items.into_iter().zip(scores) {
+        if score >= threshold {
+            kept.push((item, false));
+            continue;
+        }
+        below += 1;
+        if demote {
+            let mut meta: serde_json::Value = serde_json::from_str(&item.metadata).unwrap_or_else(|_| json!({}));
+            if let Some(obj) = meta.as_object_mut() {
+                obj.insert("relevance_score".to_string(), json!(score));
+            }
+            item.metadata = meta.to_string();
+            kept.push((item, true));
+        }
+    }
+
+    // Stable sort: low-relevance items go last within each source file, original order otherwise
+    kept.sort_by_key(|(_, low)| *low);
+
+    info!(
+        channel = %channel_config.slug,
+        total,
+        below_threshold = below,
+        threshold,
+        action = %channel_config.relevance_action,
+        "relevance filter applied"
+    );
+
+    kept.into_iter().map(|(item, _)| item).collect()
+}
+
+/// Call `{api_url}/embeddings` (OpenAI-compatible) and return vectors in input order.
+async fn embed(
+    client: &reqwest::Client,
+    config: &EmbeddingsConfig,
+    model: &str,
+    inputs: &[String],
+) -> Result<Vec<Vec<f32>>> {
+    let api_url = config
+        .api_url
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("[embeddings].api_url is not configured"))?;
+    let url = format!("{}/embeddings", api_url.trim_end_matches('/'));
+
+    let mut request = client
+        .post(&url)
+        .header(USER_AGENT, concat!("pail/", env!("CARGO_PKG_VERSION")))
+        .json(&json!({ "model": model, "input": inputs }));
+    if let Some(ref key) = config.api_key {
+        request = request.bearer_auth(key);
+    }
+
+    let response = request.send().await.context("sending embeddings request")?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("embeddings API returned {status}: {body}");
+    }
+
+    let mut parsed: EmbeddingsResponse = response.json().await.context("parsing embeddings response")?;
+    if parsed.data.len() != inputs.len() {
+        anyhow::bail!(
+            "embeddings API returned {} vectors for {} inputs",
+            parsed.data.len(),
+            inputs.len()
+        );
+    }
+    parsed.data.sort_by_key(|d| d.index);
+    Ok(parsed.data.into_iter().map(|d| d.embedding).collect())
+}
+
+/// Text embedded for an item: title and body, truncated to `MAX_INPUT_CHARS`.
+fn embedding_input(item: &ContentItem) -> String {
+    let text = match item.title {
+        Some(ref title) => format!("{title}\n\n{}", item.body),
+        None => item.body.clone(),
+    };
+    text.chars().take(MAX_INPUT_CHARS).collect()
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use sentry::{ClientInitGuard, Hub};
+
+use crate::config::Config;
+
+/// Initialize Sentry from `[reporting]`, falling back to the `SENTRY_DSN` /
+/// `SENTRY_ENVIRONMENT` env vars (see docs/observability.md). Must run before the tracing
+/// subscriber is installed. The guard must live for the whole process; dropping it flushes
+/// pending events.
+pub fn init(config: &Config) -> ClientInitGuard {
+    let dsn = match config.reporting.sentry_dsn {
+        Some(ref dsn) => Some(dsn.clone()).filter(|d| !d.is_empty()),
+        None => std::env::var("SENTRY_DSN").ok(),
+    };
+    let environment = config
+        .reporting
+        .environment
+        .clone()
+        .or_else(|| std::env::var("SENTRY_ENVIRONMENT").ok())
+        .unwrap_or_else(|| "development".to_string());
+
+    sentry::init((
+        dsn,
+        sentry::ClientOptions {
+            traces_sample_rate: config.reporting.traces_sample_rate,
+            environment: Some(environment.into()),
+            release: std::env::var("GIT_SHA").ok().map(Into::into),
+            ..Default::default()
+        },
+    ))
+}
+
+/// A hub for one background task, tagged with the daemon component and extra tags (channel,
+/// source, account). Bind it with `SentryFutureExt::bind_hub`: `error!` events and panics
+/// inside the task carry the tags.
+pub fn task_hub(component: &str, tags: &[(&str, &str)]) -> Arc<Hub> {
+    let hub = Arc::new(Hub::new_from_top(Hub::current()));
+    hub.configure_scope(|scope| {
+        scope.set_tag("component", component);
+        for (key, value) in tags {
+            scope.set_tag(key, value);
+        }
+    });
+    hub
+}
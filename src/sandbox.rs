@@ -0,0 +1,748 @@
+//! Optional sandbox around `opencode run` (see docs/specs/generation-engine.md "Sandboxing").
+//! The command is wrapped in bubblewrap, firejail, docker, or a custom template. With
+//! `allow_hosts`, the built-in wrappers give opencode no network of its own: a bridge inside
+//! the sandbox (`pail sandbox-bridge`) forwards a loopback port to a filtering proxy that pail
+//! runs on a Unix socket.
+
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::Command;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::config::SandboxConfig;
+use crate::fetch_podcast::shell_quote;
+
+/// Longest request head the proxy reads before giving up on a connection.
+const MAX_PROXY_HEAD_BYTES: usize = 16 * 1024;
+
+/// Loopback port the bridge listens on inside the sandbox. The sandbox has a network
+/// namespace of its own, so nothing else holds it.
+const BRIDGE_PORT: u16 = 3128;
+
+/// Host paths bubblewrap mounts read-only, where they exist: what opencode and the shell
+/// tools it runs need, and nothing from home directories or pail's configuration.
+const SYSTEM_PATHS: &[&str] = &[
+    "/usr",
+    "/bin",
+    "/sbin",
+    "/lib",
+    "/lib32",
+    "/lib64",
+    "/nix/store",
+    "/run/current-system/sw",
+    "/etc/alternatives",
+    "/etc/ca-certificates",
+    "/etc/ssl",
+    "/etc/pki",
+    "/etc/passwd",
+    "/etc/group",
+    "/etc/nsswitch.conf",
+    "/etc/hosts",
+    "/etc/resolv.conf",
+    "/run/systemd/resolve",
+    "/etc/localtime",
+];
+
+/// A command ready to spawn, plus what lives (the proxy and its socket) or runs (container
+/// removal) alongside it. The proxy stops when this is dropped.
+pub(crate) struct Sandboxed {
+    pub command: Command,
+    proxy: Option<JoinHandle<()>>,
+    /// Holds the proxy socket; removed on drop.
+    _proxy_dir: Option<tempfile::TempDir>,
+    container: Option<String>,
+}
+
+impl Sandboxed {
+    /// Clean up after the process was killed: killing the docker client leaves its container running.
+    pub async fn cleanup(&self) {
+        if let Some(ref name) = self.container {
+            let _ = Command::new("docker").args(["rm", "-f", name]).output().await;
+        }
+    }
+}
+
+impl Drop for Sandboxed {
+    fn drop(&mut self) {
+        if let Some(ref proxy) = self.proxy {
+            proxy.abort();
+        }
+    }
+}
+
+/// Host paths a wrapper mounts, resolved from the environment by `command`.
+struct Layout {
+    workspace: PathBuf,
+    home: Option<PathBuf>,
+    /// Workspace first, then opencode's data, cache, and state directories.
+    rw_binds: Vec<PathBuf>,
+    /// opencode's install and config directories (bubblewrap).
+    tool_paths: Vec<PathBuf>,
+    /// `ro_binds`, plus pail's binary when the bridge runs.
+    ro_binds: Vec<PathBuf>,
+    /// Directory holding the proxy socket, when the network is unshared.
+    proxy_dir: Option<PathBuf>,
+    /// `uid:gid` for docker.
+    owner: Option<String>,
+}
+
+/// Build the command running `binary args...` in `workspace` with `env` set, wrapped as
+/// configured. Without a sandbox this is the plain command.
+pub(crate) async fn command(
+    sandbox: Option<&SandboxConfig>,
+    binary: &str,
+    args: &[String],
+    env: &[(&str, &str)],
+    workspace: &Path,
+) -> Result<Sandboxed> {
+    let Some(sandbox) = sandbox else {
+        let mut command = Command::new(binary);
+        command.args(args).current_dir(workspace).envs(env.iter().copied());
+        return Ok(Sandboxed {
+            command,
+            proxy: None,
+            _proxy_dir: None,
+            container: None,
+        });
+    };
+
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let mut env: Vec<(String, String)> = env.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    let mut inner: Vec<String> = std::iter::once(binary.to_string())
+        .chain(args.iter().cloned())
+        .collect();
+    let mut ro_binds: Vec<PathBuf> = sandbox.ro_binds.iter().map(PathBuf::from).collect();
+    let mut proxy = None;
+    let mut proxy_dir = None;
+
+    if !sandbox.allow_hosts.is_empty() {
+        let url = if sandbox.wrapper == "custom" {
+            // The template decides how the sandbox reaches the host; give it a TCP proxy
+            let listener = TcpListener::bind("127.0.0.1:0")
+                .await
+                .context("binding sandbox proxy")?;
+            let url = format!(
+                "http://{}",
+                listener.local_addr().context("reading sandbox proxy address")?
+            );
+            proxy = Some(start_proxy(ProxyListener::Tcp(listener), sandbox.allow_hosts.clone()));
+            url
+        } else {
+            let (dir, listener) = bind_proxy_socket()?;
+            let exe = std::env::current_exe().context("locating pail's binary for the sandbox bridge")?;
+            inner = bridge_command(&exe, &dir.path().join(PROXY_SOCKET), inner);
+            ro_binds.push(exe);
+            proxy = Some(start_proxy(listener, sandbox.allow_hosts.clone()));
+            proxy_dir = Some(dir);
+            format!("http://127.0.0.1:{BRIDGE_PORT}")
+        };
+        for var in ["HTTP_PROXY", "HTTPS_PROXY", "ALL_PROXY"] {
+            env.push((var.to_string(), url.clone()));
+            env.push((var.to_lowercase(), url.clone()));
+        }
+        // opencode talks to its own server over loopback
+        env.push(("NO_PROXY".to_string(), "localhost,127.0.0.1,::1".to_string()));
+        env.push(("no_proxy".to_string(), "localhost,127.0.0.1,::1".to_string()));
+    }
+
+    let layout = Layout {
+        workspace: workspace.to_path_buf(),
+        rw_binds: writable_paths(workspace, home.as_deref()),
+        tool_paths: tool_paths(binary, home.as_deref()),
+        ro_binds,
+        proxy_dir: proxy_dir.as_ref().map(|dir| dir.path().to_path_buf()),
+        owner: owner(workspace),
+        home,
+    };
+    let container =
+        (sandbox.wrapper == "docker").then(|| format!("pail-opencode-{}", &Uuid::new_v4().simple().to_string()[..12]));
+    let env_keys: Vec<&str> = env.iter().map(|(key, _)| key.as_str()).collect();
+    let (program, argv) = wrapper_argv(sandbox, &layout, &inner, &env_keys, container.as_deref());
+    debug!(wrapper = %sandbox.wrapper, program, isolated = layout.proxy_dir.is_some(), "sandboxing opencode");
+
+    let mut command = Command::new(program);
+    command.args(&argv).current_dir(workspace).envs(env);
+    Ok(Sandboxed {
+        command,
+        proxy,
+        _proxy_dir: proxy_dir,
+        container,
+    })
+}
+
+/// The wrapper program and its arguments for running `inner`.
+fn wrapper_argv(
+    sandbox: &SandboxConfig,
+    layout: &Layout,
+    inner: &[String],
+    env_keys: &[&str],
+    container: Option<&str>,
+) -> (&'static str, Vec<String>) {
+    let mut argv: Vec<String> = Vec::new();
+    let program = match sandbox.wrapper.as_str() {
+        "bubblewrap" => {
+            argv.extend(["--die-with-parent", "--unshare-all"].map(str::to_string));
+            if layout.proxy_dir.is_none() {
+                argv.push("--share-net".to_string());
+            }
+            // An empty root with only the system and tool paths: no home directories
+            for dir in SYSTEM_PATHS
+                .iter()
+                .map(PathBuf::from)
+                .chain(layout.tool_paths.iter().cloned())
+            {
+                argv.extend(["--ro-bind-try".to_string(), path(&dir), path(&dir)]);
+            }
+            for dir in &layout.ro_binds {
+                argv.extend(["--ro-bind".to_string(), path(dir), path(dir)]);
+            }
+            argv.extend(["--dev", "/dev", "--proc", "/proc", "--tmpfs", "/tmp"].map(str::to_string));
+            // After the /tmp tmpfs: the workspace and the socket usually live under it
+            for dir in layout.rw_binds.iter().chain(&layout.proxy_dir) {
+                argv.extend(["--bind".to_string(), path(dir), path(dir)]);
+            }
+            argv.extend(["--chdir".to_string(), path(&layout.workspace), "--".to_string()]);
+            argv.extend(inner.iter().cloned());
+            "bwrap"
+        }
+        "firejail" => {
+            argv.push("--quiet".to_string());
+            if let Some(ref profile) = sandbox.profile {
+                argv.push(format!("--profile={profile}"));
+            }
+            if layout.proxy_dir.is_some() {
+                argv.push("--net=none".to_string());
+            }
+            // System directories are read-only under firejail already; home is made read-only
+            // except for opencode's own directories. The workspace is in the temp dir.
+            if let Some(ref home) = layout.home {
+                argv.push(format!("--read-only={}", home.display()));
+                for dir in layout.rw_binds.iter().filter(|d| d.starts_with(home)) {
+                    argv.push(format!("--read-write={}", dir.display()));
+                }
+            }
+            argv.push("--".to_string());
+            argv.extend(inner.iter().cloned());
+            "firejail"
+        }
+        "docker" => {
+            argv.extend(["run", "--rm"].map(str::to_string));
+            if let Some(name) = container {
+                argv.extend(["--name".to_string(), name.to_string()]);
+            }
+            if layout.proxy_dir.is_some() {
+                argv.extend(["--network", "none"].map(str::to_string));
+            }
+            argv.extend(["--read-only", "--tmpfs", "/tmp", "-w"].map(str::to_string));
+            argv.push(path(&layout.workspace));
+            if let Some(ref owner) = layout.owner {
+                argv.extend(["--user".to_string(), owner.clone()]);
+            }
+            if let Some(ref home) = layout.home {
+                argv.extend(["-e".to_string(), format!("HOME={}", home.display())]);
+            }
+            for dir in layout.rw_binds.iter().chain(&layout.proxy_dir) {
+                argv.extend(["-v".to_string(), format!("{0}:{0}", dir.display())]);
+            }
+            for dir in &layout.ro_binds {
+                argv.extend(["-v".to_string(), format!("{0}:{0}:ro", dir.display())]);
+            }
+            // `-e NAME` copies the variable from the docker client's environment
+            for key in env_keys {
+                argv.extend(["-e".to_string(), key.to_string()]);
+            }
+            argv.push(sandbox.profile.clone().unwrap_or_default());
+            argv.extend(inner.iter().cloned());
+            "docker"
+        }
+        _ => {
+            let script = sandbox
+                .command
+                .as_deref()
+                .unwrap_or("{command}")
+                .replace("{workspace}", &shell_quote(&path(&layout.workspace)))
+                .replace("{rw_binds}", &quote_list(layout.rw_binds.iter().map(|p| path(p))))
+                .replace("{ro_binds}", &quote_list(layout.ro_binds.iter().map(|p| path(p))))
+                .replace("{profile}", &shell_quote(sandbox.profile.as_deref().unwrap_or("")))
+                // Last, so placeholder-like text in the prompt is left alone
+                .replace("{command}", &quote_list(inner.iter().cloned()));
+            // exec: a timeout kills the wrapper itself, not a shell waiting on it
+            argv.extend(["-c".to_string(), format!("exec {script}")]);
+            "sh"
+        }
+    };
+    (program, argv)
+}
+
+fn path(p: &Path) -> String {
+    p.to_string_lossy().to_string()
+}
+
+/// Shell-quoted and space-separated, for a template placeholder.
+fn quote_list(items: impl Iterator<Item = String>) -> String {
+    items.map(|s| shell_quote(&s)).collect::<Vec<_>>().join(" ")
+}
+
+/// `inner`, run through the bridge of pail's binary `exe`.
+fn bridge_command(exe: &Path, socket: &Path, inner: Vec<String>) -> Vec<String> {
+    let mut command = vec![
+        path(exe),
+        "sandbox-bridge".to_string(),
+        "--socket".to_string(),
+        path(socket),
+        "--port".to_string(),
+        BRIDGE_PORT.to_string(),
+        "--".to_string(),
+    ];
+    command.extend(inner);
+    command
+}
+
+/// The workspace plus opencode's data, cache, and state directories (auth, sessions, the
+/// models cache). Created if missing, since bind mounts need an existing source.
+fn writable_paths(workspace: &Path, home: Option<&Path>) -> Vec<PathBuf> {
+    let mut paths = vec![workspace.to_path_buf()];
+    for (var, fallback) in [
+        ("XDG_DATA_HOME", ".local/share"),
+        ("XDG_CACHE_HOME", ".cache"),
+        ("XDG_STATE_HOME", ".local/state"),
+    ] {
+        let Some(dir) = xdg_dir(var, fallback, home) else {
+            continue;
+        };
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            warn!(dir = %dir.display(), "cannot create opencode directory for the sandbox: {e}");
+            continue;
+        }
+        paths.push(dir);
+    }
+    paths
+}
+
+/// `$VAR/opencode`, or `~/<fallback>/opencode`.
+fn xdg_dir(var: &str, fallback: &str, home: Option<&Path>) -> Option<PathBuf> {
+    let base = match std::env::var_os(var) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => home?.join(fallback),
+    };
+    Some(base.join("opencode"))
+}
+
+/// Read-only paths opencode itself needs beyond the system ones: the directory it was found
+/// in on `PATH`, the package directory its binary resolves to (npm and install-script
+/// installs link into one), and its config directory. Never home itself or `/`.
+fn tool_paths(binary: &str, home: Option<&Path>) -> Vec<PathBuf> {
+    let found = if binary.contains('/') {
+        Some(PathBuf::from(binary))
+    } else {
+        std::env::var_os("PATH").and_then(|dirs| {
+            std::env::split_paths(&dirs)
+                .map(|dir| dir.join(binary))
+                .find(|p| p.is_file())
+        })
+    };
+    let mut paths = Vec::new();
+    if let Some(found) = found {
+        paths.extend(found.parent().map(Path::to_path_buf));
+        if let Ok(real) = std::fs::canonicalize(&found) {
+            paths.extend(real.parent().and_then(Path::parent).map(Path::to_path_buf));
+        }
+    }
+    paths.extend(xdg_dir("XDG_CONFIG_HOME", ".config", home).filter(|dir| dir.is_dir()));
+    paths.retain(|p| p.parent().is_some() && !home.is_some_and(|home| home.starts_with(p)));
+    paths.dedup();
+    paths
+}
+
+/// `uid:gid` of the workspace, so files written in a container stay owned by pail's user.
+#[cfg(unix)]
+fn owner(workspace: &Path) -> Option<String> {
+    let meta = std::fs::metadata(workspace).ok()?;
+    Some(format!("{}:{}", meta.uid(), meta.gid()))
+}
+
+#[cfg(not(unix))]
+fn owner(_workspace: &Path) -> Option<String> {
+    None
+}
+
+/// File name of the proxy socket in its private directory.
+const PROXY_SOCKET: &str = "proxy.sock";
+
+/// Where the allowlist proxy accepts connections.
+enum ProxyListener {
+    /// Loopback, for custom templates.
+    Tcp(TcpListener),
+    /// A socket bound into the sandbox, reached through the bridge.
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+}
+
+/// Bind the proxy socket in a new owner-only directory.
+#[cfg(unix)]
+fn bind_proxy_socket() -> Result<(tempfile::TempDir, ProxyListener)> {
+    let dir = tempfile::Builder::new()
+        .prefix("pail-sandbox-")
+        .tempdir()
+        .context("creating the sandbox proxy directory")?;
+    let listener =
+        tokio::net::UnixListener::bind(dir.path().join(PROXY_SOCKET)).context("binding sandbox proxy socket")?;
+    Ok((dir, ProxyListener::Unix(listener)))
+}
+
+#[cfg(not(unix))]
+fn bind_proxy_socket() -> Result<(tempfile::TempDir, ProxyListener)> {
+    anyhow::bail!("allow_hosts needs Unix sockets with this wrapper")
+}
+
+/// Serve the allowlist proxy until the handle is aborted.
+fn start_proxy(listener: ProxyListener, allow_hosts: Vec<String>) -> JoinHandle<()> {
+    let allow_hosts = Arc::new(allow_hosts);
+    tokio::spawn(async move {
+        loop {
+            let accepted = match listener {
+                ProxyListener::Tcp(ref listener) => listener.accept().await.map(|(stream, _)| {
+                    let allow_hosts = allow_hosts.clone();
+                    tokio::spawn(async move { proxy_connection(stream, &allow_hosts).await })
+                }),
+                #[cfg(unix)]
+                ProxyListener::Unix(ref listener) => listener.accept().await.map(|(stream, _)| {
+                    let allow_hosts = allow_hosts.clone();
+                    tokio::spawn(async move { proxy_connection(stream, &allow_hosts).await })
+                }),
+            };
+            match accepted {
+                Ok(connection) => {
+                    tokio::spawn(async move {
+                        match connection.await {
+                            Ok(Err(e)) => debug!("sandbox proxy connection failed: {e:#}"),
+                            Err(e) => warn!("sandbox proxy connection task failed: {e}"),
+                            Ok(Ok(())) => {}
+                        }
+                    });
+                }
+                Err(e) => {
+                    debug!("sandbox proxy accept failed: {e}");
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            }
+        }
+    })
+}
+
+/// `pail sandbox-bridge`, run inside a sandbox without network: forward `127.0.0.1:port` to
+/// the proxy socket, run `command`, and return its exit code.
+pub(crate) async fn run_bridge(socket: &Path, port: u16, command: &[String]) -> Result<i32> {
+    let (program, args) = command.split_first().context("no command to run")?;
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .with_context(|| format!("binding bridge port {port}"))?;
+    let socket = socket.to_path_buf();
+    let forward = tokio::spawn(async move {
+        while let Ok((mut client, _)) = listener.accept().await {
+            let socket = socket.clone();
+            tokio::spawn(async move {
+                match connect_socket(&socket).await {
+                    Ok(mut proxy) => {
+                        let _ = tokio::io::copy_bidirectional(&mut client, &mut proxy).await;
+                    }
+                    Err(e) => eprintln!("sandbox bridge: connecting to {}: {e}", socket.display()),
+                }
+            });
+        }
+    });
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .await
+        .with_context(|| format!("running {program}"))?;
+    forward.abort();
+    Ok(status.code().unwrap_or(1))
+}
+
+#[cfg(unix)]
+async fn connect_socket(socket: &Path) -> std::io::Result<tokio::net::UnixStream> {
+    tokio::net::UnixStream::connect(socket).await
+}
+
+#[cfg(not(unix))]
+async fn connect_socket(_socket: &Path) -> std::io::Result<TcpStream> {
+    Err(std::io::Error::other("Unix sockets are not supported on this platform"))
+}
+
+/// Serve one proxy connection: `CONNECT host:port` tunnels (HTTPS) and absolute-form
+/// `http://` requests, for allowed hosts only.
+async fn proxy_connection<S: AsyncRead + AsyncWrite + Unpin>(mut client: S, allow_hosts: &[String]) -> Result<()> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 4096];
+    let head_end = loop {
+        let n = client.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        head.extend_from_slice(&buf[..n]);
+        if let Some(pos) = head.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if head.len() > MAX_PROXY_HEAD_BYTES {
+            anyhow::bail!("request head too large");
+        }
+    };
+
+    let request_line = String::from_utf8_lossy(head.split(|&b| b == b'\r').next().unwrap_or_default()).to_string();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let tunnel = method.eq_ignore_ascii_case("CONNECT");
+    let authority = if tunnel {
+        Some(target)
+    } else {
+        target
+            .strip_prefix("http://")
+            .map(|rest| rest.split('/').next().unwrap_or(rest))
+    };
+    let Some((host, port)) = authority.and_then(|a| split_host_port(a, if tunnel { 443 } else { 80 })) else {
+        client
+            .write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+            .await?;
+        return Ok(());
+    };
+
+    if !host_allowed(&host, allow_hosts) {
+        warn!(host = %host, "sandbox blocked a connection to a host not in allow_hosts");
+        client
+            .write_all(b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+            .await?;
+        return Ok(());
+    }
+
+    let mut upstream = match TcpStream::connect((host.as_str(), port)).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            client
+                .write_all(b"HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .await?;
+            return Err(e).with_context(|| format!("connecting to {host}:{port}"));
+        }
+    };
+    if tunnel {
+        client.write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n").await?;
+        upstream.write_all(&head[head_end..]).await?;
+    } else {
+        upstream.write_all(&head).await?;
+    }
+    tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+    Ok(())
+}
+
+/// Split `host[:port]` (or `[v6]:port`) into host and port.
+fn split_host_port(authority: &str, default_port: u16) -> Option<(String, u16)> {
+    let authority = authority.rsplit('@').next().unwrap_or(authority);
+    if let Some(rest) = authority.strip_prefix('[') {
+        let (host, tail) = rest.split_once(']')?;
+        let port = match tail.strip_prefix(':') {
+            Some(port) => port.parse().ok()?,
+            None => default_port,
+        };
+        return Some((host.to_string(), port));
+    }
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().ok()?),
+        None => (authority, default_port),
+    };
+    (!host.is_empty()).then(|| (host.to_string(), port))
+}
+
+/// A host is allowed if it is an `allow_hosts` entry or a subdomain of one.
+fn host_allowed(host: &str, allow_hosts: &[String]) -> bool {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    allow_hosts.iter().any(|allowed| {
+        let allowed = allowed.to_ascii_lowercase();
+        host == allowed || host.ends_with(&format!(".{allowed}"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandbox(wrapper: &str, profile: Option<&str>) -> SandboxConfig {
+        SandboxConfig {
+            wrapper: wrapper.to_string(),
+            profile: profile.map(str::to_string),
+            command: None,
+            allow_hosts: vec!["api.example.com".to_string()],
+            ro_binds: Vec::new(),
+        }
+    }
+
+    fn layout(isolated: bool) -> Layout {
+        Layout {
+            workspace: PathBuf::from("/tmp/ws"),
+            home: Some(PathBuf::from("/home/pail")),
+            rw_binds: vec![
+                PathBuf::from("/tmp/ws"),
+                PathBuf::from("/home/pail/.local/share/opencode"),
+            ],
+            tool_paths: vec![PathBuf::from("/home/pail/.opencode")],
+            ro_binds: vec![PathBuf::from("/opt/pail/bin/pail")],
+            proxy_dir: isolated.then(|| PathBuf::from("/tmp/pail-sandbox-x")),
+            owner: Some("1000:1000".to_string()),
+        }
+    }
+
+    /// Whether `argv` has `items` as consecutive arguments.
+    fn has(argv: &[String], items: &[&str]) -> bool {
+        argv.windows(items.len())
+            .any(|w| w.iter().zip(items).all(|(a, b)| a == b))
+    }
+
+    #[test]
+    fn test_host_allowed() {
+        let allow = vec!["example.com".to_string(), "API.other.org".to_string()];
+        assert!(host_allowed("example.com", &allow));
+        assert!(host_allowed("cdn.Example.com.", &allow));
+        assert!(host_allowed("api.other.org", &allow));
+        assert!(!host_allowed("other.org", &allow));
+        assert!(!host_allowed("badexample.com", &allow));
+        assert!(!host_allowed("example.com.evil.net", &allow));
+    }
+
+    #[test]
+    fn test_split_host_port() {
+        assert_eq!(
+            split_host_port("example.com:8443", 443),
+            Some(("example.com".to_string(), 8443))
+        );
+        assert_eq!(
+            split_host_port("example.com", 80),
+            Some(("example.com".to_string(), 80))
+        );
+        assert_eq!(split_host_port("[::1]:8080", 80), Some(("::1".to_string(), 8080)));
+        assert_eq!(split_host_port(":443", 443), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_proxy_socket_refuses_other_hosts() {
+        let (dir, listener) = bind_proxy_socket().unwrap();
+        let proxy = start_proxy(listener, vec!["api.example.com".to_string()]);
+        let mut stream = tokio::net::UnixStream::connect(dir.path().join(PROXY_SOCKET))
+            .await
+            .unwrap();
+        stream
+            .write_all(b"CONNECT evil.example.net:443 HTTP/1.1\r\nHost: evil.example.net\r\n\r\n")
+            .await
+            .unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).await.unwrap();
+        assert!(reply.starts_with("HTTP/1.1 403"), "{reply}");
+        proxy.abort();
+    }
+
+    #[test]
+    fn test_bubblewrap_argv_isolated() {
+        let inner = vec!["opencode".to_string(), "run".to_string()];
+        let (program, argv) = wrapper_argv(&sandbox("bubblewrap", None), &layout(true), &inner, &[], None);
+        assert_eq!(program, "bwrap");
+        assert!(has(&argv, &["--unshare-all"]));
+        assert!(!argv.contains(&"--share-net".to_string()));
+        assert!(!has(&argv, &["/", "/"]), "host root must not be bound: {argv:?}");
+        assert!(
+            !argv.iter().any(|a| a == "/home/pail"),
+            "home must not be bound: {argv:?}"
+        );
+        assert!(has(&argv, &["--ro-bind-try", "/usr", "/usr"]));
+        assert!(has(
+            &argv,
+            &["--ro-bind-try", "/home/pail/.opencode", "/home/pail/.opencode"]
+        ));
+        assert!(has(&argv, &["--ro-bind", "/opt/pail/bin/pail", "/opt/pail/bin/pail"]));
+        assert!(has(&argv, &["--bind", "/tmp/pail-sandbox-x", "/tmp/pail-sandbox-x"]));
+        // Writable binds come after the /tmp tmpfs, or it would hide them
+        let tmpfs = argv.iter().position(|a| a == "--tmpfs").unwrap();
+        let workspace = argv.iter().position(|a| a == "/tmp/ws").unwrap();
+        assert!(tmpfs < workspace);
+        assert!(argv.ends_with(&["--".to_string(), "opencode".to_string(), "run".to_string()]));
+    }
+
+    #[test]
+    fn test_bubblewrap_argv_shares_network_without_allowlist() {
+        let (_, argv) = wrapper_argv(&sandbox("bubblewrap", None), &layout(false), &[], &[], None);
+        assert!(has(&argv, &["--unshare-all", "--share-net"]));
+    }
+
+    #[test]
+    fn test_docker_argv() {
+        let inner = vec!["opencode".to_string()];
+        let (program, argv) = wrapper_argv(
+            &sandbox("docker", Some("example/opencode")),
+            &layout(true),
+            &inner,
+            &["HTTPS_PROXY"],
+            Some("pail-opencode-1"),
+        );
+        assert_eq!(program, "docker");
+        assert!(has(&argv, &["--name", "pail-opencode-1"]));
+        assert!(has(&argv, &["--network", "none"]));
+        assert!(!argv.iter().any(|a| a == "host"));
+        assert!(has(&argv, &["--user", "1000:1000"]));
+        assert!(has(&argv, &["-v", "/tmp/pail-sandbox-x:/tmp/pail-sandbox-x"]));
+        assert!(has(&argv, &["-v", "/opt/pail/bin/pail:/opt/pail/bin/pail:ro"]));
+        assert!(has(&argv, &["-e", "HTTPS_PROXY"]));
+        assert!(argv.ends_with(&["example/opencode".to_string(), "opencode".to_string()]));
+
+        let (_, argv) = wrapper_argv(&sandbox("docker", Some("img")), &layout(false), &inner, &[], None);
+        assert!(!argv.iter().any(|a| a == "--network"));
+    }
+
+    #[test]
+    fn test_firejail_argv() {
+        let (program, argv) = wrapper_argv(&sandbox("firejail", None), &layout(true), &[], &[], None);
+        assert_eq!(program, "firejail");
+        assert!(has(&argv, &["--net=none"]));
+        assert!(has(&argv, &["--read-only=/home/pail"]));
+        assert!(has(&argv, &["--read-write=/home/pail/.local/share/opencode"]));
+    }
+
+    #[test]
+    fn test_custom_argv_quotes_placeholders() {
+        let mut config = sandbox("custom", Some("img"));
+        config.command = Some("runner -w {workspace} {profile} {command}".to_string());
+        let inner = vec!["opencode".to_string(), "it's".to_string()];
+        let (program, argv) = wrapper_argv(&config, &layout(false), &inner, &[], None);
+        assert_eq!(program, "sh");
+        assert_eq!(argv, ["-c", r"exec runner -w '/tmp/ws' 'img' 'opencode' 'it'\''s'"]);
+    }
+
+    #[test]
+    fn test_bridge_command() {
+        let command = bridge_command(
+            Path::new("/usr/bin/pail"),
+            Path::new("/tmp/d/proxy.sock"),
+            vec!["opencode".to_string()],
+        );
+        assert_eq!(
+            command,
+            [
+                "/usr/bin/pail",
+                "sandbox-bridge",
+                "--socket",
+                "/tmp/d/proxy.sock",
+                "--port",
+                "3128",
+                "--",
+                "opencode"
+            ]
+        );
+    }
+}
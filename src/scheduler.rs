@@ -1,19 +1,75 @@
-use std::collections::{HashMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 
 use anyhow::{Context, Result};
-use chrono::{DateTime, Datelike, NaiveTime, TimeZone, Utc, Weekday};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
 use chrono_tz::Tz;
+use sentry::SentryFutureExt;
 use sqlx::SqlitePool;
-use tokio::sync::Semaphore;
+use tokio::sync::{AcquireError, Notify, OwnedSemaphorePermit, Semaphore, mpsc, watch};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
-use crate::config::Config;
-use crate::pipeline;
+use crate::config::{Config, OutputChannelConfig};
+use crate::error::GenerationError;
+use crate::health::Health;
+use crate::models::QueuedGeneration;
 use crate::store;
 use crate::strategy::StrategyRegistry;
+use crate::telegram::TgClients;
+use crate::{breaking, notify, pipeline, reporting};
+
+/// How often the scheduler wakes to check channel schedules.
+pub const TICK_SECS: u64 = 30;
+
+/// A generation claim lasts this long unless renewed; only matters when an instance dies
+/// mid-generation and leaves its claim behind (see docs/specs/daemon.md "Roles").
+const CLAIM_LEASE_SECS: i64 = 600;
+
+/// How often a running generation renews its claim.
+const CLAIM_RENEW_SECS: u64 = 120;
+
+/// Claim a channel's generation for `instance` (see `store::claim_channel`).
+pub async fn claim(
+    pool: &SqlitePool,
+    channel_id: &str,
+    instance: &str,
+    expected_last_generated: Option<Option<DateTime<Utc>>>,
+) -> Result<bool> {
+    let now = Utc::now();
+    let expires_at = now + chrono::Duration::seconds(CLAIM_LEASE_SECS);
+    store::claim_channel(pool, channel_id, instance, now, expires_at, expected_last_generated).await
+}
+
+/// Run a claimed generation, renewing the claim while it runs and releasing it after.
+pub async fn while_claimed<T>(
+    pool: &SqlitePool,
+    channel_id: &str,
+    instance: &str,
+    generation: impl std::future::Future<Output = T>,
+) -> T {
+    let lease = || Utc::now() + chrono::Duration::seconds(CLAIM_LEASE_SECS);
+    tokio::pin!(generation);
+    let mut renew = tokio::time::interval(std::time::Duration::from_secs(CLAIM_RENEW_SECS));
+    renew.tick().await;
+    let result = loop {
+        tokio::select! {
+            result = &mut generation => break result,
+            _ = renew.tick() => {
+                if let Err(e) = store::renew_claim(pool, channel_id, instance, lease()).await {
+                    warn!(channel_id, "failed to renew generation claim: {e:#}");
+                }
+            }
+        }
+    };
+    if let Err(e) = store::release_claim(pool, channel_id, instance).await {
+        warn!(channel_id, "failed to release generation claim: {e:#}");
+    }
+    result
+}
 
 /// RAII guard that removes a channel ID from the in-flight set on drop.
 /// Ensures cleanup even if the generation task panics.
@@ -28,22 +84,92 @@ impl Drop for InFlightGuard {
     }
 }
 
+/// Generations waiting for a slot, served by channel `priority` (highest first), then in the
+/// order they queued. Only the head of the queue waits on the semaphore, so a higher-priority
+/// arrival takes the next free slot ahead of everything queued before it. Running generations
+/// are never interrupted.
+struct GenerationQueue {
+    semaphore: Arc<Semaphore>,
+    waiting: Mutex<BTreeSet<(Reverse<i32>, u64)>>,
+    next_seq: AtomicU64,
+    /// Notified whenever `waiting` changes.
+    changed: Notify,
+}
+
+/// A generation's place in the queue. Dropping it (slot granted, task gone) leaves the queue
+/// and wakes the others, so the next one takes over as head.
+struct QueueTicket<'a> {
+    queue: &'a GenerationQueue,
+    key: (Reverse<i32>, u64),
+}
+
+impl Drop for QueueTicket<'_> {
+    fn drop(&mut self) {
+        self.queue.waiting.lock().unwrap().remove(&self.key);
+        self.queue.changed.notify_waiters();
+    }
+}
+
+impl GenerationQueue {
+    fn new(semaphore: Arc<Semaphore>) -> Self {
+        GenerationQueue {
+            semaphore,
+            waiting: Mutex::new(BTreeSet::new()),
+            next_seq: AtomicU64::new(0),
+            changed: Notify::new(),
+        }
+    }
+
+    /// Generations waiting for a slot.
+    fn len(&self) -> usize {
+        self.waiting.lock().unwrap().len()
+    }
+
+    /// Wait for a generation slot in priority order.
+    async fn acquire(&self, priority: i32) -> Result<OwnedSemaphorePermit, AcquireError> {
+        let key = (Reverse(priority), self.next_seq.fetch_add(1, Ordering::Relaxed));
+        self.waiting.lock().unwrap().insert(key);
+        self.changed.notify_waiters();
+        let _ticket = QueueTicket { queue: self, key };
+        loop {
+            // Registered before looking at the queue, so a change in between isn't missed
+            let changed = self.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+            let is_head = self.waiting.lock().unwrap().first() == Some(&key);
+            if !is_head {
+                changed.await;
+                continue;
+            }
+            // A new arrival may outrank this one: stop waiting and look again
+            tokio::select! {
+                permit = self.semaphore.clone().acquire_owned() => return permit,
+                _ = &mut changed => {}
+            }
+        }
+    }
+}
+
 /// Parsed schedule representation.
 ///
 /// **Note:** `Cron` schedules currently evaluate in UTC, not the user's timezone.
-/// Use `at:` or `weekly:` formats for timezone-aware scheduling.
+/// Use `at:`, `weekly:` or `monthly:` formats for timezone-aware scheduling.
 #[derive(Debug, Clone)]
 pub enum Schedule {
     /// One or more times per day.
     Daily { times: Vec<NaiveTime> },
-    /// Once per week on a specific day and time.
-    Weekly { day: Weekday, time: NaiveTime },
+    /// On one or more days of the week, at one time.
+    Weekly { days: Vec<Weekday>, time: NaiveTime },
+    /// On one or more days of the month (1-31), at one time. A day past the end of a shorter
+    /// month falls on its last day.
+    Monthly { days: Vec<u32>, time: NaiveTime },
     /// Cron expression.
     Cron { schedule: Box<cron::Schedule> },
 }
 
 impl Schedule {
-    /// Parse a schedule string like "at:08:00,20:00", "weekly:monday,08:00", or "cron:0 8 * * *".
+    /// Parse a schedule string like "at:08:00,20:00", "weekly:monday,thursday,08:00",
+    /// "monthly:1,09:00", or "cron:0 8 * * *".
     pub fn parse(s: &str) -> Result<Self> {
         if let Some(times_str) = s.strip_prefix("at:") {
             let mut times = Vec::new();
@@ -55,14 +181,30 @@ impl Schedule {
             times.sort();
             Ok(Schedule::Daily { times })
         } else if let Some(rest) = s.strip_prefix("weekly:") {
-            let parts: Vec<&str> = rest.splitn(2, ',').collect();
-            if parts.len() != 2 {
-                anyhow::bail!("invalid weekly schedule '{s}': expected 'weekly:DAY,HH:MM'");
-            }
-            let day = parse_weekday(parts[0].trim())?;
-            let time = NaiveTime::parse_from_str(parts[1].trim(), "%H:%M")
-                .with_context(|| format!("invalid time '{}'", parts[1].trim()))?;
-            Ok(Schedule::Weekly { day, time })
+            let Some((days, time)) = rest.rsplit_once(',') else {
+                anyhow::bail!("invalid weekly schedule '{s}': expected 'weekly:DAY[,DAY...],HH:MM'");
+            };
+            let days = days
+                .split(',')
+                .map(|d| parse_weekday(d.trim()))
+                .collect::<Result<_>>()?;
+            let time = NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .with_context(|| format!("invalid time '{}'", time.trim()))?;
+            Ok(Schedule::Weekly { days, time })
+        } else if let Some(rest) = s.strip_prefix("monthly:") {
+            let Some((days, time)) = rest.rsplit_once(',') else {
+                anyhow::bail!("invalid monthly schedule '{s}': expected 'monthly:D[,D...],HH:MM'");
+            };
+            let days = days
+                .split(',')
+                .map(|d| match d.trim().parse::<u32>() {
+                    Ok(day @ 1..=31) => Ok(day),
+                    _ => Err(anyhow::anyhow!("invalid day of month '{}'", d.trim())),
+                })
+                .collect::<Result<_>>()?;
+            let time = NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .with_context(|| format!("invalid time '{}'", time.trim()))?;
+            Ok(Schedule::Monthly { days, time })
         } else if let Some(expr) = s.strip_prefix("cron:") {
             // The cron crate expects 7-field (sec min hour dom mon dow year) expressions.
             // Standard 5-field cron: prepend "0" for seconds, append "*" for year.
@@ -73,7 +215,7 @@ impl Schedule {
                 schedule: Box::new(schedule),
             })
         } else {
-            anyhow::bail!("invalid schedule '{s}': must start with 'at:', 'weekly:', or 'cron:'");
+            anyhow::bail!("invalid schedule '{s}': must start with 'at:', 'weekly:', 'monthly:', or 'cron:'");
         }
     }
 
@@ -101,39 +243,15 @@ impl Schedule {
                 }
                 None
             }
-            Schedule::Weekly { day, time } => {
-                let today = after_local.date_naive();
-                let current_weekday = today.weekday();
-                let target_weekday = *day;
-
-                // Days until next occurrence
-                let days_ahead =
-                    (target_weekday.num_days_from_monday() as i64 - current_weekday.num_days_from_monday() as i64 + 7)
-                        % 7;
-
-                // If it's the same day, check if time has passed
-                let candidate_date = if days_ahead == 0 {
-                    if let Some(candidate) = tz.from_local_datetime(&today.and_time(*time)).earliest()
-                        && candidate > after_local
-                    {
-                        return Some(candidate.with_timezone(&Utc));
-                    }
-                    // Time passed today or doesn't exist (DST gap) — next week
-                    today + chrono::Duration::days(7)
-                } else {
-                    today + chrono::Duration::days(days_ahead)
-                };
-
-                // Try candidate_date, then next week if DST gap
-                if let Some(candidate) = tz.from_local_datetime(&candidate_date.and_time(*time)).earliest() {
-                    return Some(candidate.with_timezone(&Utc));
-                }
-                // DST gap on target date — try next week
-                let fallback = candidate_date + chrono::Duration::days(7);
-                tz.from_local_datetime(&fallback.and_time(*time))
-                    .earliest()
-                    .map(|c| c.with_timezone(&Utc))
+            // Two weeks: a day whose time falls in a DST gap is retried the week after
+            Schedule::Weekly { days, time } => {
+                next_matching_day(tz, after_local, *time, 15, |date| days.contains(&date.weekday()))
             }
+            // Three months: a gap on the only day of one month is followed by the next ones
+            Schedule::Monthly { days, time } => next_matching_day(tz, after_local, *time, 93, |date| {
+                let last = last_day_of_month(date);
+                days.iter().any(|&day| day.min(last) == date.day())
+            }),
             Schedule::Cron { schedule } => schedule.after(&after).next(),
         }
     }
@@ -150,6 +268,35 @@ impl Schedule {
     }
 }
 
+/// The first time `time` on a day `matches` after `after_local`, looking at today and the
+/// following `max_days` days. Days where `time` doesn't exist (DST gap) are skipped.
+fn next_matching_day(
+    tz: Tz,
+    after_local: DateTime<Tz>,
+    time: NaiveTime,
+    max_days: i64,
+    matches: impl Fn(NaiveDate) -> bool,
+) -> Option<DateTime<Utc>> {
+    let today = after_local.date_naive();
+    (0..=max_days)
+        .map(|offset| today + chrono::Duration::days(offset))
+        .filter(|date| matches(*date))
+        .filter_map(|date| tz.from_local_datetime(&date.and_time(time)).earliest())
+        .find(|candidate| *candidate > after_local)
+        .map(|candidate| candidate.with_timezone(&Utc))
+}
+
+fn last_day_of_month(date: NaiveDate) -> u32 {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|first| first.pred_opt())
+        .map_or(28, |last| last.day())
+}
+
 fn parse_weekday(s: &str) -> Result<Weekday> {
     match s.to_lowercase().as_str() {
         "monday" | "mon" => Ok(Weekday::Mon),
@@ -163,19 +310,201 @@ fn parse_weekday(s: &str) -> Result<Weekday> {
     }
 }
 
-/// Main scheduler loop. Wakes every 30 seconds and checks all enabled channels.
+/// Capacity of the on-demand generation queue (see `GenerationRequest`).
+pub const REQUEST_QUEUE_SIZE: usize = 16;
+
+/// An on-demand generation outside the channel's schedule (regenerating a draft from
+/// `/drafts`, `pail ctl generate`). Runs with the same concurrency limit and in-flight guard
+/// as scheduled generations, and ignores the channel's pause flag.
+pub struct GenerationRequest {
+    pub slug: String,
+    /// Explicit window (doesn't move `last_generated`), or `None` for the scheduled
+    /// window: `last_generated` → now.
+    pub window: Option<pipeline::TimeWindow>,
+    /// Draft to reject once the new article is stored (regeneration from `/drafts`).
+    pub replaces_draft: Option<String>,
+}
+
+impl From<QueuedGeneration> for GenerationRequest {
+    fn from(queued: QueuedGeneration) -> Self {
+        let window = match (queued.window_from, queued.window_to, queued.since_secs) {
+            (Some(from), Some(to), _) => Some(pipeline::TimeWindow::Explicit { from, to }),
+            (_, _, Some(secs)) => Some(pipeline::TimeWindow::Since(std::time::Duration::from_secs(
+                secs.max(0) as u64
+            ))),
+            _ => None,
+        };
+        GenerationRequest {
+            slug: queued.slug,
+            window,
+            replaces_draft: queued.replaces_draft,
+        }
+    }
+}
+
+/// Everything a spawned generation task needs, cloned per task.
+#[derive(Clone)]
+struct GenerationRunner {
+    pool: SqlitePool,
+    config: watch::Receiver<Arc<Config>>,
+    registry: Arc<StrategyRegistry>,
+    queue: Arc<GenerationQueue>,
+    tg_clients: Arc<TgClients>,
+    in_flight: Arc<Mutex<HashSet<String>>>,
+    /// Identifies this daemon in generation claims shared with other instances.
+    instance: String,
+    cancel: CancellationToken,
+}
+
+impl GenerationRunner {
+    /// Spawn a generation for a channel unless one is already in flight. `window` is `None`
+    /// for the scheduled window (last_generated → now). The task uses the config current at
+    /// spawn time, so a reload mid-generation doesn't change it. It runs only if it can claim
+    /// the channel (see `store::claim_channel`), so instances sharing the database never
+    /// generate the same channel at once. Returns whether the generation was queued.
+    fn spawn(
+        &self,
+        kind: &'static str,
+        channel_id: String,
+        channel_config: OutputChannelConfig,
+        window: Option<pipeline::TimeWindow>,
+        replaces_draft: Option<String>,
+        expected_last_generated: Option<Option<DateTime<Utc>>>,
+    ) -> bool {
+        // Mark channel as in-flight (drop guard ensures removal even on panic)
+        if !self.in_flight.lock().unwrap().insert(channel_id.clone()) {
+            warn!(channel = %channel_config.name, "generation already in progress, skipping");
+            return false;
+        }
+
+        let runner = self.clone();
+        let config = self.config.borrow().clone();
+        let hub = reporting::task_hub("scheduler", &[("channel", &channel_config.slug), ("kind", kind)]);
+
+        tokio::spawn(
+            async move {
+                // Guard ensures channel is removed from in-flight set on drop (including panic)
+                let _guard = InFlightGuard {
+                    set: runner.in_flight.clone(),
+                    channel_id: channel_id.clone(),
+                };
+
+                // Wait for a slot (`max_concurrent_generations`), higher-priority channels first
+                if runner.queue.semaphore.available_permits() == 0 {
+                    info!(
+                        channel = %channel_config.name,
+                        kind,
+                        priority = channel_config.priority,
+                        ahead = runner.queue.len(),
+                        "generation waiting for a slot"
+                    );
+                }
+                let _permit = match runner.queue.acquire(channel_config.priority).await {
+                    Ok(p) => p,
+                    Err(_) => return,
+                };
+
+                if runner.cancel.is_cancelled() {
+                    return;
+                }
+
+                match claim(&runner.pool, &channel_id, &runner.instance, expected_last_generated).await {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        info!(channel = %channel_config.name, kind, "generation claimed by another instance, skipping");
+                        return;
+                    }
+                    Err(e) => {
+                        error!(channel = %channel_config.name, kind, "failed to claim generation: {e:#}");
+                        return;
+                    }
+                }
+
+                info!(channel = %channel_config.name, kind, "generation starting");
+
+                let generation = pipeline::run_generation(
+                    &runner.pool,
+                    &config,
+                    &channel_config,
+                    &runner.registry,
+                    None, // no strategy override in daemon mode
+                    window,
+                    false,
+                    &runner.tg_clients,
+                    // A draft's replacement covers the draft's window by design
+                    replaces_draft.is_some(),
+                    runner.cancel.clone(),
+                );
+                let result = while_claimed(&runner.pool, &channel_id, &runner.instance, generation).await;
+
+                match result {
+                    Ok(Some(r)) => {
+                        info!(channel = %channel_config.name, kind, title = %r.article.title, "generation complete");
+                        if let Some(ref draft_id) = replaces_draft
+                            && let Err(e) = store::review_draft(&runner.pool, draft_id, "rejected").await
+                        {
+                            error!(draft_id = %draft_id, "failed to reject replaced draft: {e:#}");
+                        }
+                    }
+                    Ok(None) => {
+                        debug!(channel = %channel_config.name, kind, "generation skipped (no content, or below min_items)");
+                    }
+                    Err(e) if matches!(e.downcast_ref(), Some(GenerationError::DuplicateWindow { .. })) => {
+                        info!(channel = %channel_config.name, kind, "generation skipped: {e}");
+                    }
+                    Err(e) => {
+                        // Use {:#} to include the full anyhow error chain in the
+                        // Sentry event message (Display only shows the outermost).
+                        error!(channel = %channel_config.name, kind, "generation failed: {e:#}");
+                        // A run killed by shutdown isn't a failure to report
+                        if !runner.cancel.is_cancelled() {
+                            let title = format!("pail: {kind} generation failed for '{}'", channel_config.name);
+                            notify::send(
+                                &config,
+                                &runner.tg_clients,
+                                notify::Event::GenerationFailed,
+                                &title,
+                                &format!("{e:#}"),
+                            )
+                            .await;
+                        }
+                    }
+                }
+            }
+            .bind_hub(hub),
+        );
+        true
+    }
+}
+
+/// Main scheduler loop. Wakes every 30 seconds and checks all enabled channels and the
+/// database queue filled by `server` instances, and runs on-demand generations as they
+/// arrive on `requests`.
+#[allow(clippy::too_many_arguments)]
 pub async fn scheduler_loop(
     pool: SqlitePool,
-    config: Arc<Config>,
+    config_rx: watch::Receiver<Arc<Config>>,
     registry: Arc<StrategyRegistry>,
     semaphore: Arc<Semaphore>,
-    tg_client: Option<grammers_client::Client>,
+    tg_clients: Arc<TgClients>,
+    mut requests: mpsc::Receiver<GenerationRequest>,
+    instance: String,
+    health: Arc<Health>,
     cancel: CancellationToken,
 ) {
     info!("scheduler started");
 
     // Track which channels have in-flight generations to prevent double-firing
-    let in_flight: Arc<Mutex<HashSet<String>>> = Arc::new(Mutex::new(HashSet::new()));
+    let runner = GenerationRunner {
+        pool: pool.clone(),
+        config: config_rx.clone(),
+        registry,
+        queue: Arc::new(GenerationQueue::new(semaphore)),
+        tg_clients,
+        in_flight: Arc::new(Mutex::new(HashSet::new())),
+        instance,
+        cancel: cancel.clone(),
+    };
 
     // Track when we first saw channels that have never generated.
     // For new channels (last_generated = NULL), we wait for their next scheduled tick
@@ -184,13 +513,39 @@ pub async fn scheduler_loop(
     // missed ticks are always skipped (see docs/specs/daemon.md "Missed Ticks").
     let mut first_seen: HashMap<String, DateTime<Utc>> = HashMap::new();
 
+    // An interval rather than a sleep per iteration, so on-demand requests don't push back the next tick
+    let mut tick = tokio::time::interval(std::time::Duration::from_secs(TICK_SECS));
+    tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // The first interval tick completes immediately; schedules are checked from the second one
+    tick.tick().await;
+
     loop {
         tokio::select! {
             _ = cancel.cancelled() => {
                 info!("scheduler shutting down");
                 return;
             }
-            _ = tokio::time::sleep(std::time::Duration::from_secs(30)) => {}
+            Some(request) = requests.recv() => {
+                let config = config_rx.borrow().clone();
+                handle_request(&runner, &config, request).await;
+                continue;
+            }
+            _ = tick.tick() => {}
+        }
+
+        health.scheduler_tick();
+
+        // Snapshot per tick: `pail ctl reload` swaps the config between ticks
+        let config = config_rx.borrow().clone();
+
+        // Generations requested through a `server` instance
+        match store::take_queued_generations(&pool).await {
+            Ok(queued) => {
+                for request in queued {
+                    handle_request(&runner, &config, request.into()).await;
+                }
+            }
+            Err(e) => error!(error = %e, "failed to load queued generation requests"),
         }
 
         let tz: Tz = match config.pail.timezone.parse() {
@@ -209,36 +564,73 @@ pub async fn scheduler_loop(
             }
         };
 
+        // Paused channels (`pail ctl pause`) skip their scheduled runs
+        let paused = match store::get_paused_channel_ids(&pool).await {
+            Ok(p) => p,
+            Err(e) => {
+                error!(error = %e, "failed to load paused channels");
+                continue;
+            }
+        };
+
         let now = Utc::now();
 
         for channel in &channels {
+            if paused.contains(&channel.id) {
+                debug!(channel = %channel.name, "channel paused, skipping");
+                continue;
+            }
+
             // Skip if this channel already has an in-flight generation
-            if in_flight.lock().unwrap().contains(&channel.id) {
+            if runner.in_flight.lock().unwrap().contains(&channel.id) {
                 debug!(channel = %channel.name, "generation already in progress, skipping");
                 continue;
             }
 
-            let schedule_str = match &channel.schedule {
-                Some(s) => s,
-                None => continue, // no schedule — CLI-only channel
-            };
-            let schedule = match Schedule::parse(schedule_str) {
-                Ok(s) => s,
-                Err(e) => {
+            // No schedule: CLI-only channel, or one that only posts breaking updates
+            let due = match channel.schedule.as_deref().map(Schedule::parse) {
+                None => false,
+                Some(Ok(schedule)) => {
+                    // For channels that have never generated, use the time we first saw them
+                    // as the reference point. They wait for their next scheduled tick rather than
+                    // firing immediately. The pipeline still uses the 7-day lookback for content
+                    // collection when last_generated is NULL.
+                    let after = channel
+                        .last_generated
+                        .unwrap_or_else(|| *first_seen.entry(channel.id.clone()).or_insert(now));
+                    schedule.is_due(tz, after, now)
+                }
+                Some(Err(e)) => {
                     warn!(channel = %channel.name, error = %e, "invalid schedule, skipping");
                     continue;
                 }
             };
 
-            // For channels that have never generated, use the time we first saw them
-            // as the reference point. They wait for their next scheduled tick rather than
-            // firing immediately. The pipeline still uses the 7-day lookback for content
-            // collection when last_generated is NULL.
-            let after = channel
-                .last_generated
-                .unwrap_or_else(|| *first_seen.entry(channel.id.clone()).or_insert(now));
-
-            if !schedule.is_due(tz, after, now) {
+            // Between digests, an update when trigger sources posted (see docs/specs/breaking-updates.md)
+            if !due {
+                let Some(channel_config) = config.output_channel.iter().find(|c| c.slug == channel.slug) else {
+                    continue;
+                };
+                let Some(ref breaking_config) = channel_config.breaking else {
+                    continue;
+                };
+                match breaking::due_update(&pool, channel, breaking_config, now).await {
+                    Ok(Some(window)) => {
+                        let queued = runner.spawn(
+                            "breaking",
+                            channel.id.clone(),
+                            breaking::update_config(channel_config, breaking_config),
+                            Some(window),
+                            None,
+                            None,
+                        );
+                        if queued && let Err(e) = breaking::record_update(&pool, &channel.id, now).await {
+                            error!(channel = %channel.name, "failed to record a breaking update: {e:#}");
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => error!(channel = %channel.name, "failed to check for a breaking update: {e:#}"),
+                }
                 continue;
             }
 
@@ -251,63 +643,233 @@ pub async fn scheduler_loop(
                 }
             };
 
-            // Mark channel as in-flight (drop guard ensures removal even on panic)
-            let channel_id = channel.id.clone();
-            in_flight.lock().unwrap().insert(channel_id.clone());
+            runner.spawn(
+                "scheduled",
+                channel.id.clone(),
+                channel_config,
+                None,
+                None,
+                Some(channel.last_generated),
+            );
+        }
+    }
+}
 
-            let pool = pool.clone();
-            let config = config.clone();
-            let registry = registry.clone();
-            let semaphore = semaphore.clone();
-            let tg_client = tg_client.clone();
-            let cancel = cancel.clone();
-            let in_flight = in_flight.clone();
+/// Resolve an on-demand request to a channel and spawn its generation.
+async fn handle_request(runner: &GenerationRunner, config: &Config, request: GenerationRequest) {
+    let Some(channel_config) = config.output_channel.iter().find(|c| c.slug == request.slug) else {
+        warn!(slug = %request.slug, "generation requested for unknown channel");
+        return;
+    };
+    match store::get_channel_by_slug(&runner.pool, &request.slug).await {
+        Ok(Some(channel)) => {
+            runner.spawn(
+                "on-demand",
+                channel.id,
+                channel_config.clone(),
+                request.window,
+                request.replaces_draft,
+                None,
+            );
+        }
+        Ok(None) => warn!(slug = %request.slug, "generation requested for channel missing from database"),
+        Err(e) => error!(slug = %request.slug, "failed to look up channel for requested generation: {e:#}"),
+    }
+}
 
-            tokio::spawn(async move {
-                // Guard ensures channel is removed from in-flight set on drop (including panic)
-                let _guard = InFlightGuard {
-                    set: in_flight,
-                    channel_id,
-                };
+/// Stand-in for `scheduler_loop` on a `server` instance: queues on-demand generations in the
+/// database for a worker instance to run.
+pub async fn forward_requests(
+    pool: SqlitePool,
+    mut requests: mpsc::Receiver<GenerationRequest>,
+    cancel: CancellationToken,
+) {
+    loop {
+        let request = tokio::select! {
+            _ = cancel.cancelled() => return,
+            Some(request) = requests.recv() => request,
+        };
+        let (window, since_secs) = match request.window {
+            Some(pipeline::TimeWindow::Explicit { from, to }) => (Some((from, to)), None),
+            Some(pipeline::TimeWindow::Since(duration)) => (None, Some(duration.as_secs() as i64)),
+            // Updates are started by the scheduler itself, never requested
+            Some(pipeline::TimeWindow::Update { .. }) | None => (None, None),
+        };
+        match store::queue_generation(
+            &pool,
+            &request.slug,
+            window,
+            since_secs,
+            request.replaces_draft.as_deref(),
+        )
+        .await
+        {
+            Ok(()) => info!(slug = %request.slug, "generation queued for a worker instance"),
+            Err(e) => error!(slug = %request.slug, "failed to queue generation: {e:#}"),
+        }
+    }
+}
 
-                // Acquire semaphore permit (limits concurrent generations)
-                let _permit = match semaphore.acquire().await {
-                    Ok(p) => p,
-                    Err(_) => return,
-                };
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Queue one waiter per `(name, priority)`, each fully queued before the next, and
+    /// return the order in which they got the slot once it frees up.
+    async fn grant_order(waiters: &[(&'static str, i32)]) -> Vec<&'static str> {
+        let queue = Arc::new(GenerationQueue::new(Arc::new(Semaphore::new(1))));
+        let held = queue.acquire(0).await.unwrap();
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut tasks = Vec::new();
+        for (i, &(name, priority)) in waiters.iter().enumerate() {
+            let (waiter, order) = (queue.clone(), order.clone());
+            tasks.push(tokio::spawn(async move {
+                let _permit = waiter.acquire(priority).await.unwrap();
+                order.lock().unwrap().push(name);
+            }));
+            while queue.len() < i + 1 {
+                tokio::task::yield_now().await;
+            }
+        }
+        drop(held);
+        for task in tasks {
+            task.await.unwrap();
+        }
+        Arc::try_unwrap(order).unwrap().into_inner().unwrap()
+    }
 
-                if cancel.is_cancelled() {
-                    return;
-                }
+    #[tokio::test]
+    async fn test_queue_serves_priority_then_arrival() {
+        let order = grant_order(&[
+            ("weekly", 0),
+            ("daily-a", 5),
+            ("other", 0),
+            ("daily-b", 5),
+            ("urgent", 9),
+        ])
+        .await;
+        assert_eq!(order, vec!["urgent", "daily-a", "daily-b", "weekly", "other"]);
+    }
 
-                info!(channel = %channel_config.name, "scheduled generation starting");
+    #[tokio::test]
+    async fn test_queue_same_priority_is_fifo() {
+        let order = grant_order(&[("a", 1), ("b", 1), ("c", 1)]).await;
+        assert_eq!(order, vec!["a", "b", "c"]);
+    }
 
-                match pipeline::run_generation(
-                    &pool,
-                    &config,
-                    &channel_config,
-                    &registry,
-                    None, // no strategy override in daemon mode
-                    None,
-                    false,
-                    tg_client.as_ref(),
-                    cancel,
-                )
-                .await
-                {
-                    Ok(Some(r)) => {
-                        info!(channel = %channel_config.name, title = %r.article.title, "scheduled generation complete");
-                    }
-                    Ok(None) => {
-                        debug!(channel = %channel_config.name, "scheduled generation skipped (no content)");
-                    }
-                    Err(e) => {
-                        // Use {:#} to include the full anyhow error chain in the
-                        // Sentry event message (Display only shows the outermost).
-                        error!(channel = %channel_config.name, "scheduled generation failed: {e:#}");
-                    }
-                }
-            });
+    #[tokio::test]
+    async fn test_queue_dropped_waiter_leaves_queue() {
+        let queue = Arc::new(GenerationQueue::new(Arc::new(Semaphore::new(1))));
+        let held = queue.acquire(0).await.unwrap();
+        let head = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.acquire(9).await.map(drop) }
+        });
+        while queue.len() < 1 {
+            tokio::task::yield_now().await;
+        }
+        let next = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.acquire(0).await.map(drop) }
+        });
+        while queue.len() < 2 {
+            tokio::task::yield_now().await;
         }
+
+        // The head's task goes away: the next waiter becomes head and gets the slot
+        head.abort();
+        assert!(head.await.unwrap_err().is_cancelled());
+        assert_eq!(queue.len(), 1);
+        drop(held);
+        next.await.unwrap().unwrap();
+        assert_eq!(queue.len(), 0);
+    }
+
+    #[test]
+    fn test_in_flight_guard_coalesces_and_clears() {
+        let set = Arc::new(Mutex::new(HashSet::new()));
+        assert!(set.lock().unwrap().insert("daily".to_string()));
+        let guard = InFlightGuard {
+            set: set.clone(),
+            channel_id: "daily".to_string(),
+        };
+        // A second generation for the channel is refused while the first is queued or running
+        assert!(!set.lock().unwrap().insert("daily".to_string()));
+        drop(guard);
+        assert!(set.lock().unwrap().is_empty());
+    }
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn next(schedule: &str, tz: Tz, after: &str) -> String {
+        Schedule::parse(schedule)
+            .unwrap()
+            .next_tick(tz, utc(after))
+            .unwrap()
+            .to_rfc3339()
+    }
+
+    #[test]
+    fn test_weekly_with_several_days() {
+        let schedule = "weekly:mon,wed,fri,09:00";
+        // 2025-03-03 is a Monday
+        assert_eq!(
+            next(schedule, Tz::UTC, "2025-03-03T08:00:00Z"),
+            "2025-03-03T09:00:00+00:00"
+        );
+        assert_eq!(
+            next(schedule, Tz::UTC, "2025-03-03T10:00:00Z"),
+            "2025-03-05T09:00:00+00:00"
+        );
+        // Strictly after: a tick at `after` itself is the previous one
+        assert_eq!(
+            next(schedule, Tz::UTC, "2025-03-07T09:00:00Z"),
+            "2025-03-10T09:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_monthly_31_runs_on_the_last_day_of_shorter_months() {
+        let schedule = "monthly:31,09:00";
+        assert_eq!(
+            next(schedule, Tz::UTC, "2025-02-01T00:00:00Z"),
+            "2025-02-28T09:00:00+00:00"
+        );
+        assert_eq!(
+            next(schedule, Tz::UTC, "2024-02-01T00:00:00Z"),
+            "2024-02-29T09:00:00+00:00"
+        );
+        assert_eq!(
+            next(schedule, Tz::UTC, "2025-04-01T00:00:00Z"),
+            "2025-04-30T09:00:00+00:00"
+        );
+        assert_eq!(
+            next(schedule, Tz::UTC, "2025-04-30T10:00:00Z"),
+            "2025-05-31T09:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_dst_gap_days_are_skipped() {
+        // Kyiv skips 03:00-04:00 on 2025-03-30, a Sunday
+        let tz = chrono_tz::Europe::Kyiv;
+        assert_eq!(
+            next("weekly:sun,03:30", tz, "2025-03-29T12:00:00Z"),
+            "2025-04-06T00:30:00+00:00"
+        );
+        assert_eq!(
+            next("monthly:30,03:30", tz, "2025-03-29T12:00:00Z"),
+            "2025-04-30T00:30:00+00:00"
+        );
+
+        let after = utc("2025-03-29T22:00:00Z").with_timezone(&tz);
+        let time = NaiveTime::from_hms_opt(3, 30, 0).unwrap();
+        assert_eq!(next_matching_day(tz, after, time, 0, |_| true), None);
+        assert_eq!(
+            next_matching_day(tz, after, time, 1, |_| true),
+            Some(utc("2025-03-31T00:30:00Z"))
+        );
     }
 }
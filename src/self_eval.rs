@@ -0,0 +1,332 @@
+//! Self-evaluation: a second, cheaper model scores each generated article against a rubric
+//! before it's stored (see docs/specs/self-evaluation.md).
+
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::header::USER_AGENT;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::config::{Config, OutputChannelConfig};
+use crate::models::{ContentItem, GeneratedArticle};
+use crate::tagging::{ChatResponse, json_object};
+use crate::{generate, http_client};
+
+/// Rubric criteria and what the evaluator is told to check for each.
+const CRITERIA: &[(&str, &str)] = &[
+    (
+        "link_integrity",
+        "Links point to the window's items (listed below) or pages they mention; no invented URLs. \
+         Items discussed in the text are linked.",
+    ),
+    (
+        "skipped_section",
+        "The article ends with a Skipped section (the heading may be translated) that accounts for \
+         the items it doesn't cover, each with a reason.",
+    ),
+    (
+        "section_coverage",
+        "The article covers the notable items of the window and has every required section, in order.",
+    ),
+    (
+        "directive_adherence",
+        "The article follows the editorial directive: focus, tone, language, length and format.",
+    ),
+];
+
+/// Items listed to the evaluator; a window beyond this is summarized by its count.
+const MAX_LISTED_ITEMS: usize = 300;
+
+/// Characters of the article sent; longer articles are cut.
+const MAX_ARTICLE_CHARS: usize = 60_000;
+
+/// Links reported by the automated check.
+const MAX_LISTED_LINKS: usize = 20;
+
+/// Upper bound for the evaluation request; a long article takes a while to read.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// One criterion's result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Criterion {
+    /// 0–10.
+    pub score: f64,
+    #[serde(default)]
+    pub note: String,
+}
+
+/// The evaluation of one article, stored as `generated_articles.eval_details`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Evaluation {
+    pub model: String,
+    /// Mean of the criteria scores, scaled to 0.0–1.0. Stored as `eval_score`.
+    pub score: f64,
+    pub criteria: BTreeMap<String, Criterion>,
+    /// Automated checks given to the evaluator.
+    pub unknown_links: Vec<String>,
+    pub missing_sections: Vec<String>,
+}
+
+/// Evaluate `article` and record the result on it. Failures and cancellation are logged and
+/// leave the article unscored: an unchecked digest beats a missing one.
+pub async fn score_article(
+    config: &Config,
+    channel_config: &OutputChannelConfig,
+    items: &[ContentItem],
+    article: &mut GeneratedArticle,
+    cancel: &CancellationToken,
+) -> Option<Evaluation> {
+    let result = tokio::select! {
+        result = evaluate(config, channel_config, items, article) => result,
+        _ = cancel.cancelled() => {
+            info!(channel = %channel_config.slug, "self-evaluation cancelled, keeping the article unscored");
+            return None;
+        }
+    };
+    match result {
+        Ok(evaluation) => {
+            info!(
+                channel = %channel_config.slug,
+                score = format!("{:.2}", evaluation.score),
+                "article self-evaluated"
+            );
+            article.eval_score = Some(evaluation.score);
+            article.eval_details = serde_json::to_string(&evaluation).ok();
+            Some(evaluation)
+        }
+        Err(e) => {
+            warn!(channel = %channel_config.slug, "self-evaluation failed, keeping the article unscored: {e:#}");
+            None
+        }
+    }
+}
+
+async fn evaluate(
+    config: &Config,
+    channel_config: &OutputChannelConfig,
+    items: &[ContentItem],
+    article: &GeneratedArticle,
+) -> Result<Evaluation> {
+    let eval_config = &config.self_eval;
+    let api_url = eval_config
+        .api_url
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("[self_eval].api_url is not configured"))?;
+    let model = eval_config
+        .model
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("[self_eval].model is not configured"))?;
+    let url = format!("{}/chat/completions", api_url.trim_end_matches('/'));
+
+    // The opencode session link appended by pail isn't the model's output
+    let body = article
+        .body_markdown
+        .split("\n\n---\n\n[opencode session](")
+        .next()
+        .unwrap_or_default();
+    let unknown_links = unknown_links(body, items);
+    let missing_sections: Vec<String> = generate::missing_sections(body, &channel_config.sections)
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+
+    let rubric: Vec<String> = CRITERIA
+        .iter()
+        .map(|(name, description)| format!("- {name}: {description}"))
+        .collect();
+    let instructions = format!(
+        "You review generated news digests for quality. Score the article on each criterion from 0 (fails \
+         completely) to 10 (no issues), with a one-sentence note on what's wrong, if anything.\n\n{}\n\n\
+         Reply with JSON only, in the form {{\"link_integrity\": {{\"score\": 8, \"note\": \"...\"}}, ...}} \
+         with every criterion.",
+        rubric.join("\n")
+    );
+
+    let mut input = format!(
+        "## Editorial directive\n\n{}\n\n",
+        editorial_directive(config, channel_config).await
+    );
+    if let Some(persona) = channel_config
+        .persona
+        .as_ref()
+        .and_then(|name| config.persona.get(name))
+    {
+        input.push_str(&format!("## Voice and style\n\n{}\n\n", persona.style.trim()));
+    }
+    if !channel_config.sections.is_empty() {
+        input.push_str(&format!(
+            "## Required sections\n\n{}\n\n",
+            channel_config.sections.join(", ")
+        ));
+    }
+    input.push_str("## Automated checks\n\n");
+    if unknown_links.is_empty() {
+        input.push_str("- Every link matches an item of the window or appears in an item's text\n");
+    } else {
+        input.push_str(&format!(
+            "- Links that match no item and appear in no item's text: {}\n",
+            unknown_links.join(", ")
+        ));
+    }
+    if !missing_sections.is_empty() {
+        input.push_str(&format!(
+            "- Required sections without a heading: {}\n",
+            missing_sections.join(", ")
+        ));
+    }
+    input.push_str(&format!(
+        "- Skipped section entries: {}\n- Items linked from the article: {} of {}\n\n",
+        article.skipped.len(),
+        article.cited_item_ids.len(),
+        items.len()
+    ));
+    input.push_str(&format!("## Items of the window ({})\n\n", items.len()));
+    for item in items.iter().take(MAX_LISTED_ITEMS) {
+        let title = item.title.as_deref().unwrap_or("(untitled)");
+        match item.url {
+            Some(ref url) => input.push_str(&format!("- {title} <{url}>\n")),
+            None => input.push_str(&format!("- {title}\n")),
+        }
+    }
+    if items.len() > MAX_LISTED_ITEMS {
+        input.push_str(&format!("- ... and {} more\n", items.len() - MAX_LISTED_ITEMS));
+    }
+    let article_text: String = body.chars().take(MAX_ARTICLE_CHARS).collect();
+    input.push_str(&format!("\n## Article\n\n# {}\n\n{article_text}\n", article.title));
+
+    let client = http_client::with_proxy(reqwest::Client::builder(), config.network.proxy.as_deref())
+        .and_then(|builder| builder.timeout(REQUEST_TIMEOUT).build())
+        .context("building HTTP client")?;
+    let mut request = client
+        .post(&url)
+        .header(USER_AGENT, concat!("pail/", env!("CARGO_PKG_VERSION")))
+        .json(&json!({
+            "model": model,
+            "temperature": 0,
+            "messages": [
+                { "role": "system", "content": instructions },
+                { "role": "user", "content": input },
+            ],
+        }));
+    if let Some(ref key) = eval_config.api_key {
+        request = request.bearer_auth(key);
+    }
+
+    let response = request.send().await.context("sending self-evaluation request")?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("self-evaluation API returned {status}: {body}");
+    }
+    let parsed: ChatResponse = response.json().await.context("parsing self-evaluation response")?;
+    let content = parsed
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|c| c.message.content)
+        .ok_or_else(|| anyhow::anyhow!("self-evaluation API returned no message"))?;
+
+    let criteria = parse_reply(&content)?;
+    let score = criteria.values().map(|c| c.score).sum::<f64>() / (criteria.len() as f64 * 10.0);
+    Ok(Evaluation {
+        model: model.to_string(),
+        score,
+        criteria,
+        unknown_links,
+        missing_sections,
+    })
+}
+
+/// Parse the model's scores, tolerating text around the JSON object. Every criterion must be
+/// present; scores are clamped to 0–10.
+fn parse_reply(content: &str) -> Result<BTreeMap<String, Criterion>> {
+    let Some(json) = json_object(content) else {
+        anyhow::bail!("self-evaluation reply has no JSON object: {content}");
+    };
+    let mut reply: BTreeMap<String, Criterion> =
+        serde_json::from_str(json).with_context(|| format!("parsing self-evaluation reply: {json}"))?;
+    let mut criteria = BTreeMap::new();
+    for (name, _) in CRITERIA {
+        let mut criterion = reply
+            .remove(*name)
+            .ok_or_else(|| anyhow::anyhow!("self-evaluation reply has no score for '{name}'"))?;
+        if !criterion.score.is_finite() {
+            anyhow::bail!("self-evaluation reply has an invalid score for '{name}'");
+        }
+        criterion.score = criterion.score.clamp(0.0, 10.0);
+        criteria.insert(name.to_string(), criterion);
+    }
+    Ok(criteria)
+}
+
+/// The directive the article was written to: the inline prompt, or the template source for
+/// channels with a prompt template (its rendered form isn't kept).
+async fn editorial_directive(config: &Config, channel_config: &OutputChannelConfig) -> String {
+    let template = channel_config
+        .prompt_template
+        .as_deref()
+        .or(config.pail.prompt_template.as_deref());
+    let Some(path) = template else {
+        return channel_config.prompt.clone();
+    };
+    match tokio::fs::read_to_string(path).await {
+        Ok(source) => format!(
+            "(Jinja template; `{{{{ prompt }}}}` is: {:?})\n\n{source}",
+            channel_config.prompt
+        ),
+        Err(_) => channel_config.prompt.clone(),
+    }
+}
+
+/// Links of the article that match no item (link, discussion page or thread tweets) and
+/// appear in no item's text: likely invented.
+fn unknown_links(body: &str, items: &[ContentItem]) -> Vec<String> {
+    let mut known: HashSet<String> = HashSet::new();
+    for item in items {
+        let meta: serde_json::Value = serde_json::from_str(&item.metadata).unwrap_or_default();
+        let tweet_urls = meta.get("tweet_urls").and_then(|v| v.as_array());
+        let urls = item
+            .url
+            .as_deref()
+            .into_iter()
+            .chain(meta.get("discussion_url").and_then(|v| v.as_str()))
+            .chain(tweet_urls.into_iter().flatten().filter_map(|v| v.as_str()))
+            .chain(generate::body_links(&item.body));
+        known.extend(urls.map(generate::normalize_link));
+    }
+
+    let mut unknown: Vec<String> = Vec::new();
+    for link in generate::body_links(body) {
+        let link = link.trim_end_matches(['.', ',', ';', ':', '!', '?', '*', '_']);
+        if !known.contains(&generate::normalize_link(link)) && !unknown.iter().any(|u| u == link) {
+            unknown.push(link.to_string());
+        }
+    }
+    unknown.truncate(MAX_LISTED_LINKS);
+    unknown
+}
+
+/// Add a quality note listing the weak criteria to the end of an article that scored below
+/// `min_score`.
+pub fn annotate(article: &mut GeneratedArticle, evaluation: &Evaluation, min_score: f64) {
+    let mut note = format!(
+        "\n\n---\n\n> **Quality check:** this digest scored {:.2} in an automated review (threshold {min_score:.2}).\n",
+        evaluation.score
+    );
+    for (name, criterion) in &evaluation.criteria {
+        if criterion.score < min_score * 10.0 {
+            let label = name.replace('_', " ");
+            note.push_str(&format!(
+                "> - {label} ({}/10): {}\n",
+                criterion.score,
+                criterion.note.trim()
+            ));
+        }
+    }
+    article.body_markdown.push_str(&note);
+    article.body_html = generate::markdown_to_html(&article.body_markdown);
+}
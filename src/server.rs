@@ -1,29 +1,103 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
 use atom_syndication::{Category, Content, Entry, Feed, Generator, Link, Person, Text};
 use axum::Router;
-use axum::extract::{Path, Query, State};
-use axum::http::{HeaderMap, StatusCode, header};
-use axum::response::{Html, IntoResponse, Response};
-use axum::routing::get;
+use axum::extract::{Form, Path, Query, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::routing::{get, post};
 use base64::Engine;
-use chrono::FixedOffset;
+use chrono::{DateTime, FixedOffset, Utc};
+use serde_json::json;
+use sha2::{Digest, Sha256};
 use sqlx::SqlitePool;
 use subtle::ConstantTimeEq;
-use tracing::{debug, warn};
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use crate::config::{Config, OutputChannelConfig};
+use crate::daemon::Role;
+use crate::generate::{sanitize_xml_text, strip_ansi};
+use crate::health::{self, Health};
+use crate::models::{FeedToken, GeneratedArticleRow};
+use crate::scheduler::GenerationRequest;
+use crate::strategy::StrategyRegistry;
+use crate::{admin, api, opml, pipeline, poller, scheduler, source_health, store, tg_media, webhook, webmention};
+
+const FEED_PATH_HINT: &str = "Not found. Use /feed/default/<slug>.atom or /feed/default/<slug>.json";
+
+/// Feeds are per-user (token-authenticated), so shared caches must not store them. Readers
+/// may reuse a copy for a few minutes, then revalidate with the ETag / Last-Modified validators.
+const FEED_CACHE_CONTROL: &str = "private, max-age=300";
 
-use crate::generate::sanitize_xml_text;
-use crate::store;
+/// A media ID always serves the same photo.
+const MEDIA_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// Minimum time between two ad-hoc window generations of the same channel. Within it, the
+/// same window is served from the last result and any other window gets 429.
+const PREVIEW_MIN_INTERVAL_SECS: i64 = 300;
+
+/// Longest window an ad-hoc generation accepts.
+const PREVIEW_MAX_WINDOW: Duration = Duration::from_secs(30 * 24 * 3600);
 
 #[derive(Clone)]
 pub struct AppState {
     pub pool: SqlitePool,
+    /// Current config; swapped by `pail ctl reload`.
+    pub config: tokio::sync::watch::Receiver<Arc<Config>>,
     pub feed_token: String,
+    /// Per-process secret the review and admin forms' CSRF nonces are derived from.
+    pub csrf_key: [u8; 32],
     pub timezone: chrono_tz::Tz,
+    pub health: Arc<Health>,
+    pub started_at: DateTime<Utc>,
+    pub generation_tx: tokio::sync::mpsc::Sender<GenerationRequest>,
+    /// Shared with the scheduler; ad-hoc window generations take a permit too.
+    pub registry: Arc<StrategyRegistry>,
+    pub semaphore: Arc<Semaphore>,
+    /// Last ad-hoc window generation per channel slug (rate limit and result cache).
+    pub previews: Arc<Mutex<HashMap<String, PreviewEntry>>>,
+    /// `server` instances don't run the scheduler or poller (see docs/specs/daemon.md "Roles").
+    pub role: Role,
+    /// Admin API config edits: the file to write and where to publish the result.
+    pub config_path: PathBuf,
+    pub config_tx: Arc<tokio::sync::watch::Sender<Arc<Config>>>,
+    /// Serializes admin API config edits (read, modify, write, apply).
+    pub config_edit_lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+/// An ad-hoc window generation: started, or finished with its article (`None` if the window
+/// had no items). Failed generations stay unfinished until the rate limit interval passes.
+pub struct PreviewEntry {
+    window: Duration,
+    started_at: DateTime<Utc>,
+    finished: bool,
+    article: Option<GeneratedArticleRow>,
 }
 
 pub fn build_router(state: AppState) -> Router {
     Router::new()
+        .route("/", get(index_handler))
         .route("/feed/{*path}", get(feed_handler))
+        .route("/feeds.opml", get(opml_handler))
+        .route("/article/preview", get(preview_handler))
         .route("/article/{id}", get(article_handler))
+        .route("/healthz", get(healthz_handler))
+        .route("/drafts", get(drafts_handler))
+        .route("/sources", get(sources_handler))
+        .route("/runs", get(runs_handler))
+        .route("/runs/{id}/log", get(run_log_handler))
+        .route("/runs/{id}/live", get(run_live_handler))
+        .route("/media/{id}", get(media_handler))
+        .route("/drafts/{id}/{action}", post(review_handler))
+        .route("/api/articles/{id}/read", post(read_handler))
+        .route("/ingest/{slug}", post(ingest_handler))
+        .nest("/api/v1", api::router())
+        .nest("/admin", admin::router())
         .layer(sentry_tower::SentryHttpLayer::new().enable_transaction())
         .layer(sentry_tower::NewSentryLayer::<axum::extract::Request>::new_from_top())
         .with_state(state)
@@ -32,6 +106,43 @@ pub fn build_router(state: AppState) -> Router {
 #[derive(serde::Deserialize)]
 pub struct FeedQuery {
     token: Option<String>,
+    /// Ad-hoc digest over the last `window` (humantime, e.g. `24h`) instead of stored articles.
+    window: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ReadQuery {
+    #[serde(flatten)]
+    auth: FeedQuery,
+    /// `false` marks the article unread; anything else (or absent) marks it read.
+    read: Option<String>,
+    /// `article` or `index` to redirect back to that page; JSON response otherwise.
+    from: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ArticleQuery {
+    #[serde(flatten)]
+    auth: FeedQuery,
+    /// Serve the article's translation into this language (ISO 639-1) instead of the original.
+    lang: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct RunsQuery {
+    #[serde(flatten)]
+    auth: FeedQuery,
+    /// Only this channel's runs (slug).
+    channel: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct PreviewQuery {
+    #[serde(flatten)]
+    auth: FeedQuery,
+    slug: String,
+    since: String,
 }
 
 async fn feed_handler(
@@ -40,24 +151,27 @@ async fn feed_handler(
     Query(query): Query<FeedQuery>,
     headers: HeaderMap,
 ) -> Response {
-    // Authenticate
-    if !authenticate(&state.feed_token, &query, &headers) {
+    // Authenticate: the main feed token, or a named token scoped to some channels
+    let Some(auth) = feed_auth(&state, &query, &headers).await else {
         return (
             StatusCode::UNAUTHORIZED,
             [(header::WWW_AUTHENTICATE, "Basic realm=\"pail\"")],
             "Unauthorized",
         )
             .into_response();
-    }
+    };
 
-    // Parse path: expected format is "<username>/<slug>.atom"
-    let path_stripped = match path.strip_suffix(".atom") {
-        Some(p) => p,
-        None => return (StatusCode::NOT_FOUND, "Not found. Use /feed/default/<slug>.atom").into_response(),
+    // Parse path: expected format is "<username>/<slug>.atom" or "<username>/<slug>.json"
+    let (path_stripped, format) = if let Some(p) = path.strip_suffix(".atom") {
+        (p, FeedFormat::Atom)
+    } else if let Some(p) = path.strip_suffix(".json") {
+        (p, FeedFormat::Json)
+    } else {
+        return (StatusCode::NOT_FOUND, FEED_PATH_HINT).into_response();
     };
     let slug = match path_stripped.split_once('/') {
         Some((username, slug)) if username == "default" && !slug.is_empty() && !slug.contains('/') => slug,
-        _ => return (StatusCode::NOT_FOUND, "Not found. Use /feed/default/<slug>.atom").into_response(),
+        _ => return (StatusCode::NOT_FOUND, FEED_PATH_HINT).into_response(),
     };
 
     // Look up channel
@@ -72,6 +186,53 @@ async fn feed_handler(
         }
     };
 
+    let token = match auth {
+        FeedAuth::Main => None,
+        FeedAuth::Token(token) => Some(token),
+    };
+    let in_scope = match token {
+        None => true,
+        Some(ref token) => match store::feed_token_allows(&state.pool, token, &channel.id).await {
+            Ok(allowed) => allowed,
+            Err(e) => {
+                warn!(error = %e, "failed to check feed token scope");
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+            }
+        },
+    };
+    let response = match (&token, &query.window) {
+        (Some(_), _) if !in_scope => (StatusCode::FORBIDDEN, "This token has no access to this feed").into_response(),
+        // Ad-hoc windows run a generation; only the owner's token may spend on that
+        (Some(_), Some(_)) => (StatusCode::FORBIDDEN, "Ad-hoc windows need the main feed token").into_response(),
+        (None, Some(window)) => window_feed(&state, &channel, window, format, &headers).await,
+        (_, None) => channel_feed(&state, &channel, format, &headers).await,
+    };
+
+    let user_agent = headers.get(header::USER_AGENT).and_then(|v| v.to_str().ok());
+    if let Err(e) = store::record_feed_access(
+        &state.pool,
+        token.as_ref(),
+        &channel.slug,
+        response.status().as_u16(),
+        user_agent,
+        Utc::now(),
+    )
+    .await
+    {
+        warn!(error = %e, "failed to record feed access");
+    }
+    response
+}
+
+/// A channel's stored articles as a feed, with conditional GET support.
+async fn channel_feed(
+    state: &AppState,
+    channel: &crate::models::OutputChannel,
+    format: FeedFormat,
+    headers: &HeaderMap,
+) -> Response {
+    let slug = &channel.slug;
+
     // Get recent articles
     let articles = match store::get_recent_articles(&state.pool, &channel.id, 50).await {
         Ok(a) => a,
@@ -81,18 +242,227 @@ async fn feed_handler(
         }
     };
 
-    // Build Atom feed
-    let base_url = derive_base_url(&headers);
-    let feed = build_atom_feed(&channel, &articles, &base_url);
+    let base_url = derive_base_url(headers);
 
-    let xml = feed.to_string();
+    // Conditional GET: readers poll every few minutes, and most polls find nothing new
+    let etag = feed_etag(&format, channel, &articles, &base_url);
+    let newest = articles.first().map(|a| a.generated_at);
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(header::CACHE_CONTROL, HeaderValue::from_static(FEED_CACHE_CONTROL));
+    // Both values are plain ASCII (hex digest, formatted date), so they always form valid headers
+    response_headers.insert(header::ETAG, HeaderValue::from_str(&etag).expect("ETag is ASCII"));
+    if let Some(ref newest) = newest {
+        response_headers.insert(
+            header::LAST_MODIFIED,
+            HeaderValue::from_str(&http_date(newest)).expect("HTTP date is ASCII"),
+        );
+    }
+    if is_not_modified(headers, &etag, newest) {
+        debug!(slug = %slug, "feed not modified");
+        return (StatusCode::NOT_MODIFIED, response_headers).into_response();
+    }
 
-    (
-        StatusCode::OK,
-        [(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
-        xml,
-    )
-        .into_response()
+    let (content_type, body) = match format {
+        FeedFormat::Atom => (
+            "application/atom+xml; charset=utf-8",
+            build_atom_feed(channel, &articles, &base_url).to_string(),
+        ),
+        FeedFormat::Json => (
+            "application/feed+json; charset=utf-8",
+            build_json_feed(channel, &articles, &base_url).to_string(),
+        ),
+    };
+    response_headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    (StatusCode::OK, response_headers, body).into_response()
+}
+
+/// Weak ETag over everything the feed body depends on: format, channel name, base URL, and
+/// the id + timestamp + read state of every served article. Hashing all ids (not just the newest timestamp)
+/// catches an approved draft that sorts below the newest article and retention deletions.
+fn feed_etag(
+    format: &FeedFormat,
+    channel: &crate::models::OutputChannel,
+    articles: &[crate::models::GeneratedArticleRow],
+    base_url: &str,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(match format {
+        FeedFormat::Atom => "atom",
+        FeedFormat::Json => "json",
+    });
+    hasher.update(&channel.name);
+    hasher.update(base_url);
+    for article in articles {
+        hasher.update(&article.id);
+        hasher.update(article.generated_at.timestamp().to_be_bytes());
+        hasher.update([article.read_at.is_some() as u8]);
+    }
+    let digest = format!("{:x}", hasher.finalize());
+    format!("W/\"{}\"", &digest[..32])
+}
+
+/// RFC 9110 §13.2.2: `If-None-Match` takes precedence; `If-Modified-Since` is only
+/// evaluated when the request has no `If-None-Match`.
+fn is_not_modified(headers: &HeaderMap, etag: &str, newest: Option<DateTime<Utc>>) -> bool {
+    if let Some(inm) = headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+        // Weak comparison: `W/` prefixes are ignored on both sides
+        let ours = etag.trim_start_matches("W/");
+        return inm
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == ours);
+    }
+    let (Some(ims), Some(newest)) = (
+        headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| DateTime::parse_from_rfc2822(v).ok()),
+        newest,
+    ) else {
+        return false;
+    };
+    // HTTP dates have second precision
+    newest.timestamp() <= ims.timestamp()
+}
+
+/// IMF-fixdate (`Sun, 06 Nov 1994 08:49:37 GMT`), the format HTTP date headers require.
+fn http_date(dt: &DateTime<Utc>) -> String {
+    dt.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// Component health as JSON. Returns 503 if the database is unreachable or the scheduler or
+/// poller loop stopped ticking; Telegram problems only degrade the status (the daemon keeps
+/// running without TG). Per-channel details are included only for authenticated requests,
+/// so an unauthenticated uptime probe doesn't learn channel names.
+async fn healthz_handler(State(state): State<AppState>, Query(query): Query<FeedQuery>, headers: HeaderMap) -> Response {
+    let now = Utc::now();
+    let snapshot = state.health.snapshot();
+
+    let db_error = store::ping(&state.pool).await.err().map(|e| format!("{e:#}"));
+    // Loops this instance doesn't run are reported as `null` and don't affect the status
+    let runs_jobs = state.role.runs_jobs();
+    let scheduler_alive = runs_jobs.then(|| {
+        health::is_alive(
+            snapshot.scheduler_tick,
+            scheduler::TICK_SECS as i64,
+            state.started_at,
+            now,
+        )
+    });
+    let poller_alive =
+        runs_jobs.then(|| health::is_alive(snapshot.poller_tick, poller::TICK_SECS as i64, state.started_at, now));
+    let tg = &snapshot.telegram;
+    let tg_ok = !tg.enabled || (tg.connected() && tg.authorized());
+
+    // Failing / stale sources (see docs/specs/source-health.md)
+    let config = state.config.borrow().clone();
+    let source_warnings: Vec<(crate::models::Source, source_health::SourceStatus)> =
+        match store::get_all_enabled_sources(&state.pool).await {
+            Ok(sources) => sources
+                .into_iter()
+                .map(|s| {
+                    let status = source_health::evaluate(&config, &s, now);
+                    (s, status)
+                })
+                .filter(|(_, status)| status.is_warning())
+                .collect(),
+            Err(e) => {
+                warn!(error = %e, "failed to load sources for health check");
+                Vec::new()
+            }
+        };
+
+    let status = if db_error.is_some() || scheduler_alive == Some(false) || poller_alive == Some(false) {
+        "error"
+    } else if !tg_ok || !source_warnings.is_empty() {
+        "degraded"
+    } else {
+        "ok"
+    };
+
+    let fmt = |dt: Option<DateTime<Utc>>| dt.map(|d| d.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+
+    let mut body = json!({
+        "status": status,
+        "started_at": fmt(Some(state.started_at)),
+        "role": state.role.as_str(),
+        "database": { "ok": db_error.is_none(), "error": db_error },
+        "scheduler": { "alive": scheduler_alive, "last_tick": fmt(snapshot.scheduler_tick) },
+        "poller": { "alive": poller_alive, "last_tick": fmt(snapshot.poller_tick) },
+        "telegram": {
+            "enabled": tg.enabled,
+            "connected": tg.connected(),
+            "authorized": tg.authorized(),
+            "last_update": fmt(tg.last_update()),
+            "error": tg.error(),
+            "accounts": tg
+                .accounts
+                .iter()
+                .map(|(name, a)| {
+                    (
+                        name.clone(),
+                        json!({
+                            "connected": a.connected,
+                            "authorized": a.authorized,
+                            "last_update": fmt(a.last_update),
+                            "error": a.error,
+                            "write_queue": {
+                                "depth": a.write_queue.depth,
+                                "written": a.write_queue.written,
+                                "dropped": a.write_queue.dropped,
+                                "batches": a.write_queue.batches,
+                            },
+                        }),
+                    )
+                })
+                .collect::<serde_json::Map<_, _>>(),
+        },
+        "sources": { "warnings": source_warnings.len() },
+    });
+
+    if authenticate(&state.feed_token, &query, &headers) {
+        body["sources"]["details"] = source_warnings
+            .iter()
+            .map(|(source, status)| {
+                json!({
+                    "name": source.name,
+                    "status": status.label(),
+                    "detail": status.detail(now),
+                    "last_success_at": fmt(source.last_success_at),
+                    "last_item_at": fmt(source.last_item_at),
+                })
+            })
+            .collect();
+        let channels = store::get_all_enabled_channels(&state.pool).await;
+        let last_articles = store::get_last_article_times(&state.pool).await;
+        if let (Ok(channels), Ok(last_articles)) = (channels, last_articles) {
+            body["channels"] = channels
+                .iter()
+                .map(|c| {
+                    json!({
+                        "slug": c.slug,
+                        "name": c.name,
+                        "schedule": c.schedule,
+                        "last_generated": fmt(c.last_generated),
+                        "last_article_at": fmt(last_articles.get(&c.id).copied()),
+                    })
+                })
+                .collect();
+        }
+    }
+
+    let code = if status == "error" {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    (code, axum::Json(body)).into_response()
+}
+
+/// Output format of a feed request, selected by the path extension.
+enum FeedFormat {
+    Atom,
+    Json,
 }
 
 fn authenticate(feed_token: &str, query: &FeedQuery, headers: &HeaderMap) -> bool {
@@ -105,14 +475,7 @@ fn authenticate(feed_token: &str, query: &FeedQuery, headers: &HeaderMap) -> boo
     }
 
     // Method 2: HTTP Basic Auth
-    if let Some(auth_header) = headers.get(header::AUTHORIZATION)
-        && let Ok(auth_str) = auth_header.to_str()
-        && let Some(encoded) = auth_str.strip_prefix("Basic ")
-        && let Ok(decoded) = base64::engine::general_purpose::STANDARD.decode(encoded.trim())
-        && let Ok(credentials) = String::from_utf8(decoded)
-        && let Some((_user, password)) = credentials.split_once(':')
-        && constant_time_eq(password, feed_token)
-    {
+    if basic_auth_password(headers).is_some_and(|password| constant_time_eq(&password, feed_token)) {
         debug!("authenticated via HTTP Basic Auth");
         return true;
     }
@@ -120,8 +483,93 @@ fn authenticate(feed_token: &str, query: &FeedQuery, headers: &HeaderMap) -> boo
     false
 }
 
+/// The password of an HTTP Basic `Authorization` header.
+fn basic_auth_password(headers: &HeaderMap) -> Option<String> {
+    let encoded = headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Basic ")?;
+    let decoded = base64::engine::general_purpose::STANDARD.decode(encoded.trim()).ok()?;
+    let credentials = String::from_utf8(decoded).ok()?;
+    credentials
+        .split_once(':')
+        .map(|(_user, password)| password.to_string())
+}
+
+/// Who a feed request is authenticated as.
+enum FeedAuth {
+    /// The main feed token: every channel, ad-hoc windows included.
+    Main,
+    /// A named token from `pail tokens`, limited to its channels.
+    Token(FeedToken),
+}
+
+/// Authenticate a feed request with the main feed token or, failing that, a named token
+/// (same methods: `?token=` or the Basic Auth password). Named tokens are stored hashed, so
+/// the lookup is by hash.
+async fn feed_auth(state: &AppState, query: &FeedQuery, headers: &HeaderMap) -> Option<FeedAuth> {
+    if authenticate(&state.feed_token, query, headers) {
+        return Some(FeedAuth::Main);
+    }
+    let token = query.token.clone().or_else(|| basic_auth_password(headers))?;
+    match store::get_active_feed_token(&state.pool, &hash_feed_token(&token)).await {
+        Ok(found) => found.map(FeedAuth::Token),
+        Err(e) => {
+            warn!(error = %e, "failed to look up feed token");
+            None
+        }
+    }
+}
+
+/// Every enabled channel's feed as OPML, for importing them into a reader at once. Each URL
+/// carries the token the request used; a named token lists only its channels.
+async fn opml_handler(State(state): State<AppState>, Query(query): Query<FeedQuery>, headers: HeaderMap) -> Response {
+    let Some(auth) = feed_auth(&state, &query, &headers).await else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Basic realm=\"pail\"")],
+            "Unauthorized",
+        )
+            .into_response();
+    };
+    let (token, scope) = match auth {
+        FeedAuth::Main => (state.feed_token.clone(), None),
+        FeedAuth::Token(scope) => {
+            let token = query
+                .token
+                .clone()
+                .or_else(|| basic_auth_password(&headers))
+                .unwrap_or_default();
+            (token, Some(scope))
+        }
+    };
+
+    match opml::export(&state.pool, &derive_base_url(&headers), &token, scope.as_ref()).await {
+        Ok(document) => ([(header::CONTENT_TYPE, "text/x-opml; charset=utf-8")], document).into_response(),
+        Err(e) => {
+            warn!(error = %e, "failed to build OPML export");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response()
+        }
+    }
+}
+
+/// SHA-256 hex of a named feed token, as stored in `feed_tokens.token_hash`.
+pub(crate) fn hash_feed_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// CSRF nonce for forms acting on behalf of `subject` (a token or session). Derived with the
+/// per-process key, so it reveals nothing about the subject and can't be made up cross-site.
+pub(crate) fn csrf_nonce(key: &[u8; 32], subject: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(key);
+    hasher.update(subject.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
 /// Constant-time string comparison to prevent timing attacks on token validation.
-fn constant_time_eq(a: &str, b: &str) -> bool {
+pub(crate) fn constant_time_eq(a: &str, b: &str) -> bool {
     a.as_bytes().ct_eq(b.as_bytes()).into()
 }
 
@@ -139,30 +587,196 @@ fn derive_base_url(headers: &HeaderMap) -> String {
 }
 
 /// Escape HTML special characters for safe embedding in HTML attributes/content.
-fn html_escape(s: &str) -> String {
+pub(crate) fn html_escape(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
         .replace('"', "&quot;")
 }
 
-async fn article_handler(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+async fn article_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<ArticleQuery>,
+) -> Response {
     // Validate UUID format
     if uuid::Uuid::parse_str(&id).is_err() {
         return (StatusCode::BAD_REQUEST, "Invalid article ID").into_response();
     }
 
-    let article = match store::get_article_by_id(&state.pool, &id).await {
-        Ok(Some(a)) => a,
-        Ok(None) => return (StatusCode::NOT_FOUND, "Article not found").into_response(),
+    // Drafts and rejected articles are only visible on the authenticated /drafts page
+    let mut article = match store::get_article_by_id(&state.pool, &id).await {
+        Ok(Some(a)) if a.status == "published" => a,
+        Ok(_) => return (StatusCode::NOT_FOUND, "Article not found").into_response(),
         Err(e) => {
             warn!(error = %e, "failed to look up article");
             return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
         }
     };
 
+    let coverage = store::get_article_coverage(&state.pool, &article.id)
+        .await
+        .unwrap_or_else(|e| {
+            warn!(error = %e, "failed to look up article coverage");
+            None
+        });
+    // Translations replace the title and body; the rest of the page is the original's
+    if let Some(ref lang) = query.lang {
+        match store::get_article_translation(&state.pool, &article.id, lang).await {
+            Ok(Some(translation)) => {
+                article.title = translation.title;
+                article.body_html = translation.body_html;
+            }
+            Ok(None) => return (StatusCode::NOT_FOUND, "No translation in that language").into_response(),
+            Err(e) => {
+                warn!(error = %e, "failed to look up article translation");
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+            }
+        }
+    }
+    let langs = store::get_article_translation_langs(&state.pool, &article.id)
+        .await
+        .unwrap_or_else(|e| {
+            warn!(error = %e, "failed to look up article translations");
+            Vec::new()
+        });
+
+    let config = state.config.borrow().clone();
+    let channel = store::get_channel_by_id(&state.pool, &article.output_channel_id)
+        .await
+        .unwrap_or_else(|e| {
+            warn!(error = %e, "failed to look up article channel");
+            None
+        });
+    let channel_config = channel.and_then(|ch| config.output_channel.iter().find(|c| c.slug == ch.slug));
+
+    let token = query.auth.token.as_deref();
+    let footer = article_footer(
+        &article,
+        coverage,
+        token,
+        &entry_links(&config, channel_config, &article.id),
+        &language_links(&article.id, &langs, query.lang.as_deref(), token),
+    );
+    render_article_page(&article, &state.timezone, &footer)
+}
+
+/// Footer of a stored article's page: citation coverage (items cited, items provided) when
+/// known, its permalink and copies, links to its translations, and the read/unread toggle.
+/// `token` is carried over from the page's `?token=`.
+fn article_footer(
+    article: &GeneratedArticleRow,
+    coverage: Option<(i64, i64)>,
+    token: Option<&str>,
+    entry_links: &str,
+    languages: &str,
+) -> String {
+    let coverage = coverage
+        .map(|(cited, provided)| format!("{cited}/{provided} items referenced · "))
+        .unwrap_or_default();
+    format!(
+        "<footer class=\"meta\">{coverage}{entry_links}{languages}{toggle}</footer>\n",
+        toggle = read_toggle(article, "article", token)
+    )
+}
+
+/// "Permalink · Matrix" microformats links of an article of a channel with `webmention`: its
+/// public URL (`u-url`) and the Matrix room it's also posted to (`u-syndication`), so pages
+/// receiving its Webmentions can tell where it lives. Empty for other channels.
+fn entry_links(config: &Config, channel_config: Option<&OutputChannelConfig>, article_id: &str) -> String {
+    let (Some(channel_config), Some(public_url)) = (channel_config, config.pail.public_url.as_deref()) else {
+        return String::new();
+    };
+    if !channel_config.webmention {
+        return String::new();
+    }
+    let mut links = vec![format!(
+        "<a class=\"u-url\" href=\"{}\">Permalink</a>",
+        html_escape(&webmention::article_url(public_url, article_id))
+    )];
+    if let Some(ref room) = channel_config.matrix_room {
+        links.push(format!(
+            "<a class=\"u-syndication\" href=\"https://matrix.to/#/{}\">Matrix</a>",
+            html_escape(room)
+        ));
+    }
+    format!("{} · ", links.join(" · "))
+}
+
+/// "Original · uk · de" links between an article and its translations, the page being shown
+/// (`current`, `None` for the original) unlinked. Empty without translations.
+fn language_links(article_id: &str, langs: &[String], current: Option<&str>, token: Option<&str>) -> String {
+    if langs.is_empty() {
+        return String::new();
+    }
+    let base = token_query(token);
+    let link = |lang: Option<&str>, label: &str| {
+        if lang == current {
+            return format!("<strong>{}</strong>", html_escape(label));
+        }
+        let query = match lang {
+            Some(lang) if base.is_empty() => format!("?lang={lang}"),
+            Some(lang) => format!("{base}&lang={lang}"),
+            None => base.clone(),
+        };
+        format!(
+            "<a href=\"/article/{article_id}{}\">{}</a>",
+            html_escape(&query),
+            html_escape(label)
+        )
+    };
+    let mut links = vec![link(None, "Original")];
+    links.extend(langs.iter().map(|lang| link(Some(lang.as_str()), lang)));
+    format!("{} · ", links.join(" · "))
+}
+
+/// Form button that flips an article's read state via `POST /api/articles/{id}/read` and
+/// redirects back to `from` (`article` or `index`).
+fn read_toggle(article: &GeneratedArticleRow, from: &str, token: Option<&str>) -> String {
+    let (read, label) = match article.read_at {
+        Some(_) => ("false", "Mark as unread"),
+        None => ("true", "Mark as read"),
+    };
+    let query = token_query(token);
+    let sep = if query.is_empty() { "?" } else { "&amp;" };
+    format!(
+        r#"<form method="post" action="/api/articles/{id}/read{query}{sep}read={read}&amp;from={from}"><button>{label}</button></form>"#,
+        id = article.id,
+        query = html_escape(&query),
+    )
+}
+
+/// Ad-hoc article over the last `since` of a channel's stored items, rendered like
+/// `/article/{id}`. See docs/specs/atom-feed.md "Ad-hoc Windows".
+async fn preview_handler(
+    State(state): State<AppState>,
+    Query(query): Query<PreviewQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if !authenticate(&state.feed_token, &query.auth, &headers) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Basic realm=\"pail\"")],
+            "Unauthorized",
+        )
+            .into_response();
+    }
+
+    match ad_hoc_article(&state, &query.slug, &query.since).await {
+        Ok(Some(article)) => render_article_page(&article, &state.timezone, ""),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            format!("No content items in the last {}", query.since),
+        )
+            .into_response(),
+        Err(response) => response,
+    }
+}
+
+/// `footer` is raw HTML placed after the body (empty for ad-hoc articles).
+fn render_article_page(article: &GeneratedArticleRow, timezone: &chrono_tz::Tz, footer: &str) -> Response {
     let title = html_escape(&article.title);
-    let local_time = article.generated_at.with_timezone(&state.timezone);
+    let local_time = article.generated_at.with_timezone(timezone);
     let date = local_time.format("%b %-d %Y, %H:%M %Z");
 
     // The body_html starts with <h1>Title</h1> (from markdown "# Title").
@@ -186,12 +800,278 @@ h1 {{ margin-bottom: 0.25rem; }}
 .date {{ color: #666; margin-bottom: 2rem; }}
 a {{ color: #0366d6; }}
 blockquote {{ border-left: 3px solid #ddd; margin-left: 0; padding-left: 1rem; color: #555; }}
+.meta {{ color: #666; font-size: 0.9rem; margin-top: 2rem; border-top: 1px solid #eee; padding-top: 0.5rem; }}
+.meta form {{ display: inline; }}
 </style>
 </head>
 <body>
-<h1>{title}</h1>
-<p class="date">{date}</p>
+<article class="h-entry">
+<h1 class="p-name">{title}</h1>
+<p class="date"><time class="dt-published" datetime="{published}">{date}</time></p>
+<div class="e-content">
 {body}
+</div>
+{footer}</article>
+</body>
+</html>"#,
+        published = article.generated_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    );
+
+    Html(html).into_response()
+}
+
+/// Source health overview: fetch status, cache validators, Telegram resolution and recent
+/// item counts per enabled source. Same data as `pail sources list --verbose`.
+async fn sources_handler(State(state): State<AppState>, Query(query): Query<FeedQuery>, headers: HeaderMap) -> Response {
+    if !authenticate(&state.feed_token, &query, &headers) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Basic realm=\"pail\"")],
+            "Unauthorized",
+        )
+            .into_response();
+    }
+
+    let config = state.config.borrow().clone();
+    let now = Utc::now();
+    let reports = match source_health::report(&state.pool, &config, now).await {
+        Ok(r) => r,
+        Err(e) => {
+            warn!(error = %e, "failed to build source report");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+        }
+    };
+
+    let fmt = |dt: Option<DateTime<Utc>>| {
+        dt.map(|d| d.with_timezone(&state.timezone).format("%b %-d, %H:%M").to_string())
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let mut rows = String::new();
+    for report in &reports {
+        let source = &report.source;
+        let mut detail = report.status.detail(now);
+        if let Some(ref moved_url) = source.moved_url {
+            if !detail.is_empty() {
+                detail.push_str("; ");
+            }
+            detail.push_str(&format!("moved to {moved_url}"));
+        }
+        let class = if report.status.is_warning() { "warn" } else { "" };
+        rows.push_str(&format!(
+            r#"<tr class="{class}">
+<td>{name}<br><span class="muted">{source_type}</span></td>
+<td>{status}<br><span class="muted">{detail}</span></td>
+<td>{fetched}</td>
+<td>{last_item}</td>
+<td class="num">{items_24h}</td>
+<td class="num">{items_7d}</td>
+<td class="spark" title="{daily}">{spark}</td>
+<td>{cache}</td>
+<td>{tg}</td>
+</tr>
+"#,
+            name = html_escape(&source.name),
+            source_type = html_escape(&source.source_type),
+            status = report.status.label(),
+            detail = html_escape(&detail),
+            fetched = fmt(source.last_fetched_at),
+            last_item = fmt(source.last_item_at),
+            items_24h = report.items_24h,
+            items_7d = report.items_7d,
+            daily = report.daily.iter().map(i64::to_string).collect::<Vec<_>>().join(" "),
+            spark = source_health::sparkline(&report.daily),
+            cache = report.cache_state(),
+            tg = html_escape(&report.tg_state().unwrap_or_else(|| "-".to_string())),
+        ));
+    }
+    if reports.is_empty() {
+        rows.push_str(r#"<tr><td colspan="9">No enabled sources.</td></tr>"#);
+    }
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Sources — pail</title>
+<style>
+body {{ max-width: 72rem; margin: 2rem auto; padding: 0 1rem; font-family: system-ui, sans-serif; line-height: 1.4; color: #222; }}
+table {{ border-collapse: collapse; width: 100%; }}
+th, td {{ text-align: left; vertical-align: top; padding: 0.4rem 0.6rem; border-bottom: 1px solid #ddd; }}
+tr.warn {{ background: #fff4e5; }}
+.muted {{ color: #666; font-size: 0.85em; }}
+.num {{ text-align: right; }}
+.spark {{ font-family: monospace; white-space: pre; }}
+</style>
+</head>
+<body>
+<h1>Sources</h1>
+<table>
+<tr><th>Source</th><th>Status</th><th>Last fetch</th><th>Last item</th><th>24h</th><th>7d</th><th>Last {days} days</th><th>Cache</th><th>Telegram</th></tr>
+{rows}</table>
+</body>
+</html>"#,
+        days = source_health::ACTIVITY_DAYS,
+    );
+
+    Html(html).into_response()
+}
+
+/// Runs shown by `/runs` unless `?limit=` says otherwise, and the most it accepts.
+const RUNS_PAGE_DEFAULT: i64 = 100;
+const RUNS_PAGE_MAX: i64 = 1000;
+
+/// Timeline of generation runs across channels, newest first: outcome, duration, items,
+/// attempts, exit code and a link to the opencode log. Same data as `pail runs list`.
+async fn runs_handler(State(state): State<AppState>, Query(query): Query<RunsQuery>, headers: HeaderMap) -> Response {
+    if !authenticate(&state.feed_token, &query.auth, &headers) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Basic realm=\"pail\"")],
+            "Unauthorized",
+        )
+            .into_response();
+    }
+
+    let channels = match store::get_all_channels(&state.pool).await {
+        Ok(c) => c,
+        Err(e) => {
+            warn!(error = %e, "failed to query channels");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+        }
+    };
+    let channel_id = match query.channel {
+        Some(ref slug) => match channels.iter().find(|c| &c.slug == slug) {
+            Some(channel) => Some(channel.id.as_str()),
+            None => return (StatusCode::NOT_FOUND, "Channel not found").into_response(),
+        },
+        None => None,
+    };
+    let limit = query.limit.unwrap_or(RUNS_PAGE_DEFAULT).clamp(1, RUNS_PAGE_MAX);
+    let runs = match store::get_generation_runs(&state.pool, channel_id, limit).await {
+        Ok(r) => r,
+        Err(e) => {
+            warn!(error = %e, "failed to query generation runs");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+        }
+    };
+
+    // Runs still in progress (see docs/specs/generation-engine.md "Live Output")
+    let live_runs = match store::get_live_runs(&state.pool).await {
+        Ok(r) => r,
+        Err(e) => {
+            warn!(error = %e, "failed to query live runs");
+            Vec::new()
+        }
+    };
+
+    let suffix = html_escape(&token_query(query.auth.token.as_deref()));
+    let mut rows = String::new();
+    for run in live_runs
+        .iter()
+        .filter(|r| channel_id.is_none_or(|id| id == r.channel_id))
+    {
+        let channel = channels
+            .iter()
+            .find(|c| c.id == run.channel_id)
+            .map(|c| c.name.as_str())
+            .unwrap_or(run.channel_id.as_str());
+        let running = (Utc::now() - run.started_at).num_seconds().max(0);
+        rows.push_str(&format!(
+            r#"<tr class="running">
+<td>{started}</td>
+<td>{channel}</td>
+<td>running</td>
+<td class="num">{minutes}m {seconds:02}s</td>
+<td class="num">-</td>
+<td class="num">-</td>
+<td class="num">-</td>
+<td>-</td>
+<td><a href="/runs/{id}/live{suffix}">live</a></td>
+</tr>
+"#,
+            started = run.started_at.with_timezone(&state.timezone).format("%b %-d, %H:%M:%S"),
+            channel = html_escape(channel),
+            minutes = running / 60,
+            seconds = running % 60,
+            id = run.run_id,
+        ));
+    }
+    for run in &runs {
+        let channel = channels
+            .iter()
+            .find(|c| c.id == run.channel_id)
+            .map(|c| c.name.as_str())
+            .unwrap_or(run.channel_id.as_str());
+        let mut links = Vec::new();
+        if let Some(ref article_id) = run.article_id {
+            links.push(format!(r#"<a href="/article/{article_id}{suffix}">article</a>"#));
+        }
+        if run.attempts > 0 {
+            links.push(format!(r#"<a href="/runs/{id}/log{suffix}">log</a>"#, id = run.id));
+        }
+        let mut detail = run.error.as_deref().map(html_escape).unwrap_or_default();
+        if let Some(ref workspace) = run.workspace {
+            detail.push_str(&format!("<br>workspace: {}", html_escape(workspace)));
+        }
+        let class = match run.status.as_str() {
+            "failed" => "failed",
+            "skipped" => "skipped",
+            _ => "",
+        };
+        rows.push_str(&format!(
+            r#"<tr class="{class}">
+<td>{started}</td>
+<td>{channel}</td>
+<td>{outcome}<br><span class="muted">{detail}</span></td>
+<td class="num">{duration}</td>
+<td class="num">{items}</td>
+<td class="num">{attempts}</td>
+<td class="num">{exit_code}</td>
+<td>{model}</td>
+<td>{links}</td>
+</tr>
+"#,
+            started = run.started_at.with_timezone(&state.timezone).format("%b %-d, %H:%M:%S"),
+            channel = html_escape(channel),
+            outcome = html_escape(&run.outcome()),
+            duration = run.duration(),
+            items = run.item_count.map(|n| n.to_string()).unwrap_or_else(|| "-".to_string()),
+            attempts = run.attempts,
+            exit_code = run.exit_code.map(|c| c.to_string()).unwrap_or_else(|| "-".to_string()),
+            model = run.model.as_deref().map(html_escape).unwrap_or_else(|| "-".to_string()),
+            links = links.join(" · "),
+        ));
+    }
+    if runs.is_empty() && rows.is_empty() {
+        rows.push_str(r#"<tr><td colspan="9">No generation runs recorded.</td></tr>"#);
+    }
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Runs — pail</title>
+<style>
+body {{ max-width: 72rem; margin: 2rem auto; padding: 0 1rem; font-family: system-ui, sans-serif; line-height: 1.4; color: #222; }}
+table {{ border-collapse: collapse; width: 100%; }}
+th, td {{ text-align: left; vertical-align: top; padding: 0.4rem 0.6rem; border-bottom: 1px solid #ddd; }}
+tr.failed {{ background: #fdecea; }}
+tr.skipped {{ color: #666; }}
+tr.running {{ background: #eef6fd; }}
+.muted {{ color: #666; font-size: 0.85em; }}
+.num {{ text-align: right; }}
+a {{ color: #0366d6; }}
+</style>
+</head>
+<body>
+<h1>Runs</h1>
+<table>
+<tr><th>Started</th><th>Channel</th><th>Outcome</th><th>Duration</th><th>Items</th><th>Attempts</th><th>Exit</th><th>Model</th><th></th></tr>
+{rows}</table>
 </body>
 </html>"#,
     );
@@ -199,6 +1079,661 @@ blockquote {{ border-left: 3px solid #ddd; margin-left: 0; padding-left: 1rem; c
     Html(html).into_response()
 }
 
+/// The opencode log of a run, as plain text.
+async fn run_log_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if !authenticate(&state.feed_token, &query, &headers) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Basic realm=\"pail\"")],
+            "Unauthorized",
+        )
+            .into_response();
+    }
+
+    match store::get_generation_run_log(&state.pool, &id).await {
+        Ok(Some(log)) => ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], log).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "No log stored for this run").into_response(),
+        Err(e) => {
+            warn!(error = %e, "failed to query generation run log");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response()
+        }
+    }
+}
+
+/// A downloaded TG photo. Public like the images of any web page: the ID is a random UUID that
+/// only appears in articles, and feed readers fetch images without credentials.
+async fn media_handler(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    match store::get_tg_media(&state.pool, &id).await {
+        Ok(Some(data)) => (
+            [
+                (header::CONTENT_TYPE, tg_media::MIME_TYPE),
+                (header::CACHE_CONTROL, MEDIA_CACHE_CONTROL),
+            ],
+            data,
+        )
+            .into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "No such media").into_response(),
+        Err(e) => {
+            warn!(error = %e, "failed to query TG media");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response()
+        }
+    }
+}
+
+/// Seconds between reloads of `/runs/{id}/live`.
+const LIVE_REFRESH_SECS: u32 = 5;
+
+/// The latest opencode output of a run in progress, as plain text that reloads itself. A run
+/// that has finished redirects to its log.
+async fn run_live_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if !authenticate(&state.feed_token, &query, &headers) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Basic realm=\"pail\"")],
+            "Unauthorized",
+        )
+            .into_response();
+    }
+
+    let run = match store::get_live_run(&state.pool, &id).await {
+        Ok(Some(run)) => run,
+        Ok(None) => {
+            return match store::get_generation_run_log(&state.pool, &id).await {
+                Ok(Some(_)) => {
+                    Redirect::to(&format!("/runs/{id}/log{}", token_query(query.token.as_deref()))).into_response()
+                }
+                Ok(None) => (StatusCode::NOT_FOUND, "No such run in progress").into_response(),
+                Err(e) => {
+                    warn!(error = %e, "failed to query generation run log");
+                    (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response()
+                }
+            };
+        }
+        Err(e) => {
+            warn!(error = %e, "failed to query live run");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+        }
+    };
+
+    let now = Utc::now();
+    let seconds = |since: DateTime<Utc>| (now - since).num_seconds().max(0);
+    let last_output = match run.last_output_at {
+        Some(at) => format!("last output {}s ago", seconds(at)),
+        None => "no output yet".to_string(),
+    };
+    let body = format!(
+        "=== running for {}s, {last_output} (reloads every {LIVE_REFRESH_SECS}s) ===\n{}",
+        seconds(run.started_at),
+        strip_ansi(&run.tail)
+    );
+    (
+        [
+            (header::CONTENT_TYPE, "text/plain; charset=utf-8".to_string()),
+            (header::REFRESH, LIVE_REFRESH_SECS.to_string()),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+#[derive(serde::Deserialize)]
+pub struct ReviewForm {
+    csrf: String,
+}
+
+/// Review queue for channels with `require_approval`. Each draft is rendered inline with
+/// approve / reject / regenerate buttons.
+async fn drafts_handler(State(state): State<AppState>, Query(query): Query<FeedQuery>, headers: HeaderMap) -> Response {
+    if !authenticate(&state.feed_token, &query, &headers) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Basic realm=\"pail\"")],
+            "Unauthorized",
+        )
+            .into_response();
+    }
+
+    let (drafts, channels) = match (
+        store::get_draft_articles(&state.pool).await,
+        store::get_all_enabled_channels(&state.pool).await,
+    ) {
+        (Ok(d), Ok(c)) => (d, c),
+        (Err(e), _) | (_, Err(e)) => {
+            warn!(error = %e, "failed to query drafts");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+        }
+    };
+
+    // Keep query-param auth across form posts; Basic Auth is resent by the browser
+    let suffix = html_escape(&token_query(query.token.as_deref()));
+    let csrf = csrf_nonce(&state.csrf_key, &state.feed_token);
+
+    let mut entries = String::new();
+    for draft in &drafts {
+        let channel = channels
+            .iter()
+            .find(|c| c.id == draft.output_channel_id)
+            .map(|c| c.name.as_str())
+            .unwrap_or(draft.output_channel_id.as_str());
+        let fmt = |dt: &DateTime<Utc>| dt.with_timezone(&state.timezone).format("%b %-d %Y, %H:%M").to_string();
+        let buttons: String = ["approve", "reject", "regenerate"]
+            .iter()
+            .map(|action| {
+                format!(
+                    r#"<form method="post" action="/drafts/{id}/{action}{suffix}"><input type="hidden" name="csrf" value="{csrf}"><button>{action}</button></form>"#,
+                    id = draft.id,
+                )
+            })
+            .collect();
+        entries.push_str(&format!(
+            r#"<section>
+<h2>{title}</h2>
+<p class="date">{channel} · generated {generated} · covers {from} – {to} · {model}</p>
+<div class="actions">{buttons}</div>
+<details><summary>Read draft</summary>{body}</details>
+</section>
+"#,
+            title = html_escape(&draft.title),
+            channel = html_escape(channel),
+            generated = fmt(&draft.generated_at),
+            from = fmt(&draft.covers_from),
+            to = fmt(&draft.covers_to),
+            model = html_escape(&draft.model_used),
+            body = draft.body_html,
+        ));
+    }
+    if drafts.is_empty() {
+        entries.push_str("<p>No drafts awaiting review.</p>");
+    }
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Drafts — pail</title>
+<style>
+body {{ max-width: 48rem; margin: 2rem auto; padding: 0 1rem; font-family: system-ui, sans-serif; line-height: 1.6; color: #222; }}
+section {{ border-top: 1px solid #ddd; padding-top: 1rem; margin-top: 1.5rem; }}
+h2 {{ margin-bottom: 0.25rem; }}
+.date {{ color: #666; }}
+.actions form {{ display: inline; margin-right: 0.5rem; }}
+a {{ color: #0366d6; }}
+blockquote {{ border-left: 3px solid #ddd; margin-left: 0; padding-left: 1rem; color: #555; }}
+</style>
+</head>
+<body>
+<h1>Drafts</h1>
+{entries}
+</body>
+</html>"#,
+    );
+
+    Html(html).into_response()
+}
+
+/// Approve, reject, or regenerate a draft. Besides the feed token, the form must carry the
+/// page's CSRF nonce, since browsers attach Basic Auth to cross-site posts too.
+async fn review_handler(
+    State(state): State<AppState>,
+    Path((id, action)): Path<(String, String)>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+    Form(form): Form<ReviewForm>,
+) -> Response {
+    if !authenticate(&state.feed_token, &query, &headers)
+        || !constant_time_eq(&form.csrf, &csrf_nonce(&state.csrf_key, &state.feed_token))
+    {
+        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    }
+    if uuid::Uuid::parse_str(&id).is_err() {
+        return (StatusCode::BAD_REQUEST, "Invalid article ID").into_response();
+    }
+
+    let config = state.config.borrow().clone();
+    let result = match action.as_str() {
+        "approve" => pipeline::approve_draft(&state.pool, &config, &id).await.map(|_| ()),
+        "reject" => reject_draft(&state, &id).await,
+        "regenerate" => regenerate_draft(&state, &id).await,
+        _ => return (StatusCode::NOT_FOUND, "Unknown action").into_response(),
+    };
+    if let Err(e) = result {
+        warn!(article_id = %id, action = %action, "draft review failed: {e:#}");
+        return (StatusCode::CONFLICT, format!("{e:#}")).into_response();
+    }
+
+    Redirect::to(&format!("/drafts{}", token_query(query.token.as_deref()))).into_response()
+}
+
+/// Mark an article read or unread (`?read=false`). Form posts from the article and index
+/// pages pass `from` and are redirected back; API clients get the new state as JSON.
+async fn read_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<ReadQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if !authenticate(&state.feed_token, &query.auth, &headers) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Basic realm=\"pail\"")],
+            "Unauthorized",
+        )
+            .into_response();
+    }
+    if uuid::Uuid::parse_str(&id).is_err() {
+        return (StatusCode::BAD_REQUEST, "Invalid article ID").into_response();
+    }
+
+    let read = query.read.as_deref() != Some("false");
+    match store::set_article_read(&state.pool, &id, read.then(Utc::now)).await {
+        Ok(true) => {}
+        Ok(false) => return (StatusCode::NOT_FOUND, "Article not found").into_response(),
+        Err(e) => {
+            warn!(article_id = %id, error = %e, "failed to update read state");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+        }
+    }
+
+    let token = token_query(query.auth.token.as_deref());
+    match query.from.as_deref() {
+        Some("index") => Redirect::to(&format!("/{token}")).into_response(),
+        Some("article") => Redirect::to(&format!("/article/{id}{token}")).into_response(),
+        _ => axum::Json(json!({ "id": id, "read": read })).into_response(),
+    }
+}
+
+/// `POST /ingest/<slug>`: items pushed to a `webhook` source, as JSON or plain text.
+/// Authenticated with the source's own token (`Authorization: Bearer` or `?token=`), not the
+/// feed token, so a leaked script token can't read feeds.
+async fn ingest_handler(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> Response {
+    let config = state.config.borrow().clone();
+    let Some((name, settings)) = config
+        .source
+        .iter()
+        .filter(|s| s.source_type == "webhook")
+        .find_map(|s| s.webhook.as_ref().filter(|w| w.slug == slug).map(|w| (&s.name, w)))
+    else {
+        return (StatusCode::NOT_FOUND, "Unknown webhook").into_response();
+    };
+
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim);
+    if !bearer
+        .or(query.token.as_deref())
+        .is_some_and(|token| constant_time_eq(token, &settings.token))
+    {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer realm=\"pail\"")],
+            "Unauthorized",
+        )
+            .into_response();
+    }
+
+    let source = match store::get_source_by_name(&state.pool, name).await {
+        Ok(Some(source)) if source.enabled => source,
+        Ok(_) => return (StatusCode::NOT_FOUND, "Webhook source is disabled").into_response(),
+        Err(e) => {
+            warn!(source = %name, error = %e, "failed to look up webhook source");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+        }
+    };
+
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .is_some_and(|mime| {
+            let mime = mime.trim();
+            mime == "application/json" || mime.ends_with("+json")
+        });
+    let now = Utc::now();
+    let items = match webhook::parse_payload(&source, is_json, &body, now) {
+        Ok(items) => items,
+        Err(message) => return (StatusCode::BAD_REQUEST, message).into_response(),
+    };
+
+    if let Err(e) = store::upsert_content_items(&state.pool, &items).await {
+        warn!(source = %source.name, error = %e, "failed to store webhook items");
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+    }
+    let newest = items.iter().map(|i| i.original_date).max();
+    if let Err(e) = store::record_fetch_success(&state.pool, &source.id, now, newest).await {
+        warn!(source = %source.name, error = %e, "failed to record webhook delivery");
+    }
+    info!(source = %source.name, items = items.len(), "received webhook items");
+    axum::Json(json!({ "source": source.name, "items": items.len() })).into_response()
+}
+
+/// Articles per channel to list on the index page.
+const INDEX_ARTICLES_PER_CHANNEL: i64 = 10;
+
+/// Reading list: each enabled channel's recent articles with their read state and toggles,
+/// channels with unread articles first.
+async fn index_handler(State(state): State<AppState>, Query(query): Query<FeedQuery>, headers: HeaderMap) -> Response {
+    if !authenticate(&state.feed_token, &query, &headers) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Basic realm=\"pail\"")],
+            "Unauthorized",
+        )
+            .into_response();
+    }
+
+    let (mut channels, unread) = match (
+        store::get_all_enabled_channels(&state.pool).await,
+        store::get_unread_counts(&state.pool).await,
+    ) {
+        (Ok(c), Ok(u)) => (c, u),
+        (Err(e), _) | (_, Err(e)) => {
+            warn!(error = %e, "failed to query channels");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+        }
+    };
+    let unread_of = |id: &str| unread.get(id).copied().unwrap_or(0);
+    channels.sort_by(|a, b| {
+        unread_of(&b.id)
+            .cmp(&unread_of(&a.id))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let token = query.token.as_deref();
+    let suffix = html_escape(&token_query(token));
+    let mut sections = String::new();
+    for channel in &channels {
+        let articles = match store::get_recent_articles(&state.pool, &channel.id, INDEX_ARTICLES_PER_CHANNEL).await {
+            Ok(a) => a,
+            Err(e) => {
+                warn!(error = %e, "failed to query articles");
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response();
+            }
+        };
+        let mut rows = String::new();
+        for article in &articles {
+            rows.push_str(&format!(
+                r#"<li class="{class}"><a href="/article/{id}{suffix}">{title}</a> <span class="date">{date}</span> {toggle}</li>
+"#,
+                class = if article.read_at.is_some() { "read" } else { "unread" },
+                id = article.id,
+                title = html_escape(&article.title),
+                date = article.generated_at.with_timezone(&state.timezone).format("%b %-d, %H:%M"),
+                toggle = read_toggle(article, "index", token),
+            ));
+        }
+        if articles.is_empty() {
+            rows.push_str("<li class=\"read\">No articles yet.</li>\n");
+        }
+        let count = unread_of(&channel.id);
+        let badge = if count > 0 {
+            format!(" <span class=\"badge\">{count} unread</span>")
+        } else {
+            String::new()
+        };
+        sections.push_str(&format!(
+            "<section>\n<h2>{name}{badge}</h2>\n<ul>\n{rows}</ul>\n</section>\n",
+            name = html_escape(&channel.name),
+        ));
+    }
+    if channels.is_empty() {
+        sections.push_str("<p>No enabled channels.</p>");
+    }
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>pail</title>
+<style>
+body {{ max-width: 48rem; margin: 2rem auto; padding: 0 1rem; font-family: system-ui, sans-serif; line-height: 1.6; color: #222; }}
+section {{ border-top: 1px solid #ddd; padding-top: 0.5rem; margin-top: 1.5rem; }}
+h2 {{ margin-bottom: 0.25rem; }}
+ul {{ list-style: none; padding-left: 0; }}
+li.unread a {{ font-weight: bold; }}
+li.read a {{ color: #666; }}
+li form {{ display: inline; }}
+.date {{ color: #666; font-size: 0.9rem; }}
+.badge {{ font-size: 0.9rem; font-weight: normal; color: #0366d6; }}
+a {{ color: #0366d6; }}
+</style>
+</head>
+<body>
+<h1>pail</h1>
+{sections}
+</body>
+</html>"#,
+    );
+
+    Html(html).into_response()
+}
+
+/// `?token=...` (percent-encoded) to carry query-param auth across the HTML pages' forms and links, or empty.
+fn token_query(token: Option<&str>) -> String {
+    let Some(token) = token else {
+        return String::new();
+    };
+    let mut url = reqwest::Url::parse("http://localhost/").expect("static URL");
+    url.query_pairs_mut().append_pair("token", token);
+    format!("?{}", url.query().unwrap_or_default())
+}
+
+/// Reject a draft.
+async fn reject_draft(state: &AppState, id: &str) -> anyhow::Result<()> {
+    if !store::review_draft(&state.pool, id, "rejected").await? {
+        anyhow::bail!("no draft with ID '{id}'");
+    }
+    Ok(())
+}
+
+/// Queue a regeneration of a draft's time window. The scheduler rejects the draft once the
+/// replacement is stored; the replacement goes through review again.
+async fn regenerate_draft(state: &AppState, id: &str) -> anyhow::Result<()> {
+    let article = store::get_article_by_id(&state.pool, id)
+        .await?
+        .filter(|a| a.status == "draft")
+        .ok_or_else(|| anyhow::anyhow!("no draft with ID '{id}'"))?;
+    let channel = store::get_channel_by_id(&state.pool, &article.output_channel_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("the draft's output channel no longer exists"))?;
+    state
+        .generation_tx
+        .try_send(GenerationRequest {
+            slug: channel.slug,
+            window: Some(pipeline::TimeWindow::Explicit {
+                from: article.covers_from,
+                to: article.covers_to,
+            }),
+            replaces_draft: Some(article.id),
+        })
+        .map_err(|e| anyhow::anyhow!("could not queue regeneration: {e}"))
+}
+
+/// Feed with a single ad-hoc article over the last `window` (empty if the window has no
+/// items). Entry links point at `/article/preview`, which serves the same cached result.
+async fn window_feed(
+    state: &AppState,
+    channel: &crate::models::OutputChannel,
+    window: &str,
+    format: FeedFormat,
+    headers: &HeaderMap,
+) -> Response {
+    let articles: Vec<GeneratedArticleRow> = match ad_hoc_article(state, &channel.slug, window).await {
+        Ok(article) => article.into_iter().collect(),
+        Err(response) => return response,
+    };
+
+    let base_url = derive_base_url(headers);
+    let mut preview_url = reqwest::Url::parse(&format!("{base_url}/article/preview")).expect("base URL is valid");
+    preview_url
+        .query_pairs_mut()
+        .append_pair("slug", &channel.slug)
+        .append_pair("since", window);
+    let window_query = |path: &str| {
+        let mut url = reqwest::Url::parse(&format!("{base_url}{path}")).expect("base URL is valid");
+        url.query_pairs_mut().append_pair("window", window);
+        url.to_string()
+    };
+
+    let (content_type, body) = match format {
+        FeedFormat::Atom => {
+            let mut feed = build_atom_feed(channel, &articles, &base_url);
+            feed.id = format!("{}:window:{window}", feed.id);
+            feed.links[0].href = window_query(&format!("/feed/default/{}.atom", channel.slug));
+            for entry in &mut feed.entries {
+                entry.links[0].href = preview_url.to_string();
+            }
+            ("application/atom+xml; charset=utf-8", feed.to_string())
+        }
+        FeedFormat::Json => {
+            let mut feed = build_json_feed(channel, &articles, &base_url);
+            feed["feed_url"] = json!(window_query(&format!("/feed/default/{}.json", channel.slug)));
+            if let Some(items) = feed["items"].as_array_mut() {
+                for item in items {
+                    item["url"] = json!(preview_url.to_string());
+                }
+            }
+            ("application/feed+json; charset=utf-8", feed.to_string())
+        }
+    };
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, FEED_CACHE_CONTROL),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Generate (or serve the cached) ad-hoc article over the last `window` of a channel's stored
+/// items. Doesn't touch `last_generated`. At most one generation per channel per
+/// `PREVIEW_MIN_INTERVAL_SECS` (429 otherwise), and only if a generation slot is free (503).
+async fn ad_hoc_article(state: &AppState, slug: &str, window: &str) -> Result<Option<GeneratedArticleRow>, Response> {
+    // Generation runs on workers; waiting for one would outlast the feed reader's request
+    if !state.role.runs_jobs() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Ad-hoc windows aren't available on a server-only instance",
+        )
+            .into_response());
+    }
+
+    let duration = match humantime::parse_duration(window) {
+        Ok(d) if !d.is_zero() && d <= PREVIEW_MAX_WINDOW => d,
+        Ok(_) => {
+            return Err((StatusCode::BAD_REQUEST, "Window must be between 1s and 30 days").into_response());
+        }
+        Err(e) => return Err((StatusCode::BAD_REQUEST, format!("Invalid window '{window}': {e}")).into_response()),
+    };
+
+    let config = state.config.borrow().clone();
+    let Some(channel_config) = config.output_channel.iter().find(|c| c.slug == slug).cloned() else {
+        return Err((StatusCode::NOT_FOUND, format!("No channel '{slug}'")).into_response());
+    };
+
+    let started_at = Utc::now();
+    {
+        let mut previews = state.previews.lock().unwrap();
+        if let Some(entry) = previews.get(slug) {
+            let age = (started_at - entry.started_at).num_seconds();
+            if age < PREVIEW_MIN_INTERVAL_SECS {
+                if entry.finished && entry.window == duration {
+                    debug!(slug = %slug, window = %window, "serving cached ad-hoc article");
+                    return Ok(entry.article.clone());
+                }
+                let retry_after = (PREVIEW_MIN_INTERVAL_SECS - age).to_string();
+                return Err((
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(header::RETRY_AFTER, retry_after)],
+                    "An ad-hoc digest for this channel was generated recently",
+                )
+                    .into_response());
+            }
+        }
+        previews.insert(
+            slug.to_string(),
+            PreviewEntry {
+                window: duration,
+                started_at,
+                finished: false,
+                article: None,
+            },
+        );
+    }
+
+    let Ok(permit) = state.semaphore.clone().try_acquire_owned() else {
+        state.previews.lock().unwrap().remove(slug);
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, "60")],
+            "All generation slots are busy",
+        )
+            .into_response());
+    };
+
+    info!(slug = %slug, window = %window, "ad-hoc window generation starting");
+
+    // Spawned so a reader disconnecting mid-generation doesn't cancel it; the result is cached
+    let pool = state.pool.clone();
+    let registry = state.registry.clone();
+    let task = tokio::spawn(async move {
+        let _permit = permit;
+        pipeline::run_preview(
+            &pool,
+            &config,
+            &channel_config,
+            &registry,
+            pipeline::TimeWindow::Since(duration),
+            CancellationToken::new(),
+        )
+        .await
+    });
+
+    let article: Option<GeneratedArticleRow> = match task.await {
+        Ok(Ok(article)) => article.map(Into::into),
+        Ok(Err(e)) => {
+            warn!(slug = %slug, window = %window, "ad-hoc generation failed: {e:#}");
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, "Generation failed").into_response());
+        }
+        Err(e) => {
+            warn!(slug = %slug, "ad-hoc generation task failed: {e}");
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, "Generation failed").into_response());
+        }
+    };
+
+    if let Some(entry) = state.previews.lock().unwrap().get_mut(slug)
+        && entry.started_at == started_at
+    {
+        entry.finished = true;
+        entry.article = article.clone();
+    }
+    Ok(article)
+}
+
 fn build_atom_feed(
     channel: &crate::models::OutputChannel,
     articles: &[crate::models::GeneratedArticleRow],
@@ -230,6 +1765,13 @@ fn build_atom_feed(
                 scheme: Some("urn:pail:strategy".to_string()),
                 ..Default::default()
             });
+            if article.read_at.is_none() {
+                categories.push(Category {
+                    term: "unread".to_string(),
+                    scheme: Some("urn:pail:state".to_string()),
+                    ..Default::default()
+                });
+            }
 
             // Derive author from model_used: "anthropic/claude-sonnet-4-5" -> "pail-opencode-claude-sonnet-4-5"
             let model_short = article.model_used.split('/').next_back().unwrap_or(&article.model_used);
@@ -244,7 +1786,10 @@ fn build_atom_feed(
             // but this covers articles generated before that fix was deployed.
             let content = Content {
                 content_type: Some("html".to_string()),
-                value: Some(sanitize_xml_text(&article.body_html)),
+                value: Some(sanitize_xml_text(&tg_media::absolute_urls(
+                    &article.body_html,
+                    base_url,
+                ))),
                 ..Default::default()
             };
 
@@ -254,16 +1799,24 @@ fn build_atom_feed(
                 mime_type: Some("text/html".to_string()),
                 ..Default::default()
             };
+            // Embedded TG photos (see docs/specs/telegram.md "Media")
+            let enclosures = tg_media::embedded_ids(&article.body_html).into_iter().map(|id| Link {
+                href: format!("{base_url}{}", tg_media::path(id)),
+                rel: "enclosure".to_string(),
+                mime_type: Some(tg_media::MIME_TYPE.to_string()),
+                ..Default::default()
+            });
 
             Entry {
                 id: format!("urn:uuid:{}", article.id),
                 title: Text::plain(sanitize_xml_text(&article.title)),
+                summary: article.summary.as_deref().map(|s| Text::plain(sanitize_xml_text(s))),
                 updated: to_fixed(&article.generated_at),
                 authors: vec![author],
                 content: Some(content),
                 categories,
                 published: Some(to_fixed(&article.generated_at)),
-                links: vec![entry_link],
+                links: std::iter::once(entry_link).chain(enclosures).collect(),
                 ..Default::default()
             }
         })
@@ -293,3 +1846,58 @@ fn build_atom_feed(
         ..Default::default()
     }
 }
+
+/// Build a JSON Feed 1.1 document (https://www.jsonfeed.org/version/1.1/) with the same
+/// entries as the Atom feed.
+fn build_json_feed(
+    channel: &crate::models::OutputChannel,
+    articles: &[crate::models::GeneratedArticleRow],
+    base_url: &str,
+) -> serde_json::Value {
+    let items: Vec<serde_json::Value> = articles
+        .iter()
+        .map(|article| {
+            let mut topics: Vec<String> = serde_json::from_str(&article.topics).unwrap_or_default();
+            if article.read_at.is_none() {
+                topics.push("unread".to_string());
+            }
+            let model_short = article.model_used.split('/').next_back().unwrap_or(&article.model_used);
+            let generated_at = article.generated_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+            let attachments: Vec<serde_json::Value> = tg_media::embedded_ids(&article.body_html)
+                .into_iter()
+                .map(
+                    |id| json!({ "url": format!("{base_url}{}", tg_media::path(id)), "mime_type": tg_media::MIME_TYPE }),
+                )
+                .collect();
+
+            let mut item = json!({
+                "id": format!("urn:uuid:{}", article.id),
+                "url": format!("{base_url}/article/{}", article.id),
+                "title": article.title,
+                "summary": article.summary,
+                "content_html": tg_media::absolute_urls(&article.body_html, base_url),
+                "date_published": generated_at,
+                "date_modified": generated_at,
+                "authors": [{ "name": format!("pail-opencode-{model_short}") }],
+                "tags": topics,
+                "_pail": {
+                    "strategy": article.strategy_used,
+                    "covers_from": article.covers_from.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                    "covers_to": article.covers_to.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                },
+            });
+            if !attachments.is_empty() {
+                item["attachments"] = json!(attachments);
+            }
+            item
+        })
+        .collect();
+
+    json!({
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": channel.name,
+        "description": channel.name,
+        "feed_url": format!("{base_url}/feed/default/{}.json", channel.slug),
+        "items": items,
+    })
+}
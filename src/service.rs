@@ -0,0 +1,239 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result};
+
+use crate::cli::ServiceCommands;
+use crate::config::Config;
+
+/// `pail service install|uninstall|status`: manage a systemd unit that runs the daemon with
+/// the current binary and config (see docs/specs/cli.md "service").
+pub fn run(config: &Config, config_path: &Path, command: ServiceCommands) -> Result<()> {
+    if !cfg!(target_os = "linux") {
+        anyhow::bail!(
+            "pail service only manages systemd units (Linux). On other platforms, run `pail --config <file>` \
+             under your service manager of choice"
+        );
+    }
+
+    match command {
+        ServiceCommands::Install {
+            user,
+            name,
+            print,
+            no_start,
+        } => {
+            validate_name(&name)?;
+            let unit = render_unit(config, config_path, user)?;
+            if print {
+                print!("{unit}");
+                return Ok(());
+            }
+
+            let path = unit_path(user, &name)?;
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+            }
+            let existed = path.exists();
+            std::fs::write(&path, unit).with_context(|| format!("writing {}", path.display()))?;
+            println!("{} {}", if existed { "Updated" } else { "Wrote" }, path.display());
+
+            systemctl(user, &["daemon-reload"])?;
+            if no_start {
+                systemctl(user, &["enable", &name])?;
+                println!(
+                    "Enabled {name}; start it with `systemctl{} start {name}`.",
+                    user_flag(user)
+                );
+            } else {
+                systemctl(user, &["enable", "--now", &name])?;
+                if existed {
+                    systemctl(user, &["restart", &name])?;
+                }
+                println!("Enabled and started {name}.");
+            }
+            if user {
+                println!(
+                    "User units stop when you log out unless lingering is enabled: `loginctl enable-linger {}`",
+                    std::env::var("USER").unwrap_or_else(|_| "$USER".to_string())
+                );
+            }
+        }
+        ServiceCommands::Uninstall { user, name } => {
+            validate_name(&name)?;
+            let path = unit_path(user, &name)?;
+            if !path.exists() {
+                println!("No unit at {}.", path.display());
+                return Ok(());
+            }
+            // A unit that was never enabled or started makes `disable --now` fail; removal continues
+            if let Err(e) = systemctl(user, &["disable", "--now", &name]) {
+                eprintln!("warning: {e:#}");
+            }
+            std::fs::remove_file(&path).with_context(|| format!("removing {}", path.display()))?;
+            systemctl(user, &["daemon-reload"])?;
+            println!(
+                "Removed {}. The database and data directory were left in place.",
+                path.display()
+            );
+        }
+        ServiceCommands::Status { user, name } => {
+            validate_name(&name)?;
+            let path = unit_path(user, &name)?;
+            if !path.exists() {
+                println!("Not installed (no unit at {}).", path.display());
+                return Ok(());
+            }
+            println!("Unit file: {}", path.display());
+            // `systemctl status` exits non-zero for stopped units; its output says why
+            let mut cmd = Command::new("systemctl");
+            if user {
+                cmd.arg("--user");
+            }
+            cmd.args(["status", "--no-pager", &name])
+                .status()
+                .context("running systemctl (is systemd installed?)")?;
+        }
+    }
+    Ok(())
+}
+
+/// The unit file. Paths are absolute so the unit doesn't depend on where it was installed from.
+fn render_unit(config: &Config, config_path: &Path, user_unit: bool) -> Result<String> {
+    let binary = std::env::current_exe()
+        .and_then(|p| p.canonicalize())
+        .context("locating the pail binary")?;
+    let config_path = config_path
+        .canonicalize()
+        .with_context(|| format!("resolving {}", config_path.display()))?;
+    // Relative data_dir / database paths are resolved against the working directory
+    let working_dir = std::env::current_dir().context("reading the current directory")?;
+    let data_dir = absolute(&working_dir, &config.pail.data_dir);
+    let config_dir = config_path.parent().unwrap_or(Path::new("/")).to_path_buf();
+
+    let exec = format!("{} --config {}", quote(&binary), quote(&config_path));
+    let mut unit = format!(
+        "# Generated by `pail service install`. Re-run it after moving the binary or config.\n\
+         [Unit]\n\
+         Description=pail digest daemon\n\
+         Documentation=https://github.com/kittyandrew/pail\n\
+         Wants=network-online.target\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         ExecStart={exec}\n\
+         ExecReload={exec} ctl reload\n\
+         WorkingDirectory={}\n",
+        quote(&working_dir)
+    );
+    if !user_unit && let Some(account) = service_user() {
+        unit.push_str(&format!("User={account}\n"));
+    }
+    unit.push_str(&format!(
+        "Restart=on-failure\n\
+         RestartSec=10\n\
+         # Shutdown cancels running generations; give them time to store partial logs\n\
+         TimeoutStopSec=30\n\
+         \n\
+         # Hardening. opencode keeps its auth and caches in the home directory, so home stays writable.\n\
+         NoNewPrivileges=yes\n\
+         ProtectSystem=full\n\
+         ReadWritePaths={} {}\n\
+         PrivateTmp=yes\n\
+         PrivateDevices=yes\n\
+         ProtectKernelTunables=yes\n\
+         ProtectKernelModules=yes\n\
+         ProtectKernelLogs=yes\n\
+         ProtectControlGroups=yes\n\
+         ProtectClock=yes\n\
+         RestrictSUIDSGID=yes\n\
+         RestrictRealtime=yes\n\
+         LockPersonality=yes\n\
+         UMask=0027\n\
+         \n\
+         [Install]\n\
+         WantedBy={}\n",
+        // `-`: don't fail when a path doesn't exist yet
+        quote(format!("-{}", data_dir.display())),
+        quote(format!("-{}", config_dir.display())),
+        if user_unit {
+            "default.target"
+        } else {
+            "multi-user.target"
+        },
+    ));
+    Ok(unit)
+}
+
+/// Where the unit goes: `/etc/systemd/system` or the user's `~/.config/systemd/user`.
+fn unit_path(user_unit: bool, name: &str) -> Result<PathBuf> {
+    let dir = if user_unit {
+        let config_home = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var_os("HOME").context("HOME is not set")?).join(".config"),
+        };
+        config_home.join("systemd").join("user")
+    } else {
+        PathBuf::from("/etc/systemd/system")
+    };
+    Ok(dir.join(format!("{name}.service")))
+}
+
+/// The account a system unit runs as: whoever invoked `sudo pail service install`, else the
+/// current user. `None` for root (no `User=` line).
+fn service_user() -> Option<String> {
+    std::env::var("SUDO_USER")
+        .ok()
+        .or_else(|| std::env::var("USER").ok())
+        .filter(|u| !u.is_empty() && u != "root")
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@'))
+    {
+        anyhow::bail!("invalid service name '{name}': use letters, digits, '-', '_', '.', '@'");
+    }
+    Ok(())
+}
+
+fn systemctl(user_unit: bool, args: &[&str]) -> Result<()> {
+    let mut cmd = Command::new("systemctl");
+    if user_unit {
+        cmd.arg("--user");
+    }
+    let status = cmd
+        .args(args)
+        .status()
+        .context("running systemctl (is systemd installed?)")?;
+    if !status.success() {
+        anyhow::bail!("systemctl{} {} failed ({status})", user_flag(user_unit), args.join(" "));
+    }
+    Ok(())
+}
+
+fn user_flag(user_unit: bool) -> &'static str {
+    if user_unit { " --user" } else { "" }
+}
+
+fn absolute(base: &Path, path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base.join(path)
+    }
+}
+
+/// Quote a path for a unit file: `%` is a specifier prefix, and paths with spaces or quotes
+/// need double quotes.
+fn quote(path: impl AsRef<Path>) -> String {
+    let s = path.as_ref().display().to_string().replace('%', "%%");
+    if s.chars().any(|c| c.is_whitespace() || c == '"' || c == '\\') {
+        format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        s
+    }
+}
@@ -0,0 +1,283 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
+use crate::config::{Config, parse_stale_after};
+use crate::models::Source;
+use crate::telegram::TgClients;
+use crate::{config_edit, fetch, matrix, notify, store};
+
+/// Health of a source, derived from its recorded fetch outcomes (see docs/specs/source-health.md).
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceStatus {
+    Ok,
+    /// At least `failure_threshold` consecutive fetches failed.
+    Failing {
+        failures: i64,
+        error: Option<String>,
+    },
+    /// The newest item seen is older than the source's `stale_after`.
+    Stale {
+        last_item_at: DateTime<Utc>,
+    },
+    /// The feed answered `410 Gone`; no longer polled.
+    Gone {
+        since: DateTime<Utc>,
+    },
+    /// Not polled (Telegram sources); no fetch outcomes to judge.
+    Untracked,
+}
+
+impl SourceStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SourceStatus::Ok => "ok",
+            SourceStatus::Failing { .. } => "failing",
+            SourceStatus::Stale { .. } => "stale",
+            SourceStatus::Gone { .. } => "gone",
+            SourceStatus::Untracked => "-",
+        }
+    }
+
+    pub fn is_warning(&self) -> bool {
+        matches!(
+            self,
+            SourceStatus::Failing { .. } | SourceStatus::Stale { .. } | SourceStatus::Gone { .. }
+        )
+    }
+
+    /// One-line explanation for warnings, empty otherwise.
+    pub fn detail(&self, now: DateTime<Utc>) -> String {
+        match self {
+            SourceStatus::Failing { failures, error } => match error {
+                Some(error) => format!("{failures} failed fetches in a row, last error: {error}"),
+                None => format!("{failures} failed fetches in a row"),
+            },
+            SourceStatus::Stale { last_item_at } => {
+                format!("no new items for {} days", (now - *last_item_at).num_days())
+            }
+            SourceStatus::Gone { since } => format!("feed gone (410) since {}", since.format("%Y-%m-%d")),
+            SourceStatus::Ok | SourceStatus::Untracked => String::new(),
+        }
+    }
+}
+
+/// Evaluate a source against `[source_health]` and its `stale_after` override.
+pub fn evaluate(config: &Config, source: &Source, now: DateTime<Utc>) -> SourceStatus {
+    if !fetch::POLLED_SOURCE_TYPES.contains(&source.source_type.as_str()) {
+        return SourceStatus::Untracked;
+    }
+
+    if let Some(since) = source.gone_at {
+        return SourceStatus::Gone { since };
+    }
+
+    if source.consecutive_failures >= i64::from(config.source_health.failure_threshold) {
+        return SourceStatus::Failing {
+            failures: source.consecutive_failures,
+            error: source.last_error.clone(),
+        };
+    }
+
+    let stale_after = config
+        .source
+        .iter()
+        .find(|s| s.name == source.name)
+        .and_then(|s| s.stale_after.as_deref())
+        .unwrap_or(&config.source_health.stale_after);
+    // Validated at load; "off" disables the check
+    if let Ok(Some(stale_after)) = parse_stale_after(stale_after)
+        && let Ok(stale_after) = chrono::Duration::from_std(stale_after)
+        && let Some(last_item_at) = source.last_item_at
+        && now - last_item_at > stale_after
+    {
+        return SourceStatus::Stale { last_item_at };
+    }
+
+    SourceStatus::Ok
+}
+
+/// Days of per-day item counts in the source report.
+pub const ACTIVITY_DAYS: usize = 14;
+
+/// One enabled source with its status and recent activity. Backs the `/sources` page and
+/// `pail sources list --verbose`.
+pub struct SourceReport {
+    pub source: Source,
+    pub status: SourceStatus,
+    pub items_24h: i64,
+    pub items_7d: i64,
+    /// Items ingested per day over `ACTIVITY_DAYS`, oldest first; the last entry is the last 24 hours.
+    pub daily: Vec<i64>,
+}
+
+impl SourceReport {
+    /// Which HTTP cache validators the next fetch sends.
+    pub fn cache_state(&self) -> &'static str {
+        match (&self.source.last_etag, &self.source.last_modified_header) {
+            (Some(_), Some(_)) => "etag+last-modified",
+            (Some(_), None) => "etag",
+            (None, Some(_)) => "last-modified",
+            (None, None) => "-",
+        }
+    }
+
+    /// Whether a Telegram source's chat or folder has been resolved to an ID; `None` for
+    /// other source types.
+    pub fn tg_state(&self) -> Option<String> {
+        let source = &self.source;
+        match source.source_type.as_str() {
+            "telegram_folder" => Some(match source.tg_folder_id {
+                Some(id) => format!("folder #{id}"),
+                None => "unresolved".to_string(),
+            }),
+            "telegram_bot" => None,
+            t if t.starts_with("telegram_") => Some(match source.tg_id {
+                Some(id) => format!("resolved ({id})"),
+                None => "unresolved".to_string(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Status and activity of every enabled source, in name order.
+pub async fn report(pool: &SqlitePool, config: &Config, now: DateTime<Utc>) -> Result<Vec<SourceReport>> {
+    let mut sources = store::get_all_enabled_sources(pool).await?;
+    sources.sort_by_key(|s| s.name.to_lowercase());
+    let activity = store::get_item_activity(pool, now, ACTIVITY_DAYS as i64).await?;
+
+    Ok(sources
+        .into_iter()
+        .map(|source| {
+            let mut daily = vec![0; ACTIVITY_DAYS];
+            for (_, age, count) in activity.iter().filter(|(id, _, _)| *id == source.id) {
+                if let Some(slot) = usize::try_from(*age).ok().filter(|a| *a < ACTIVITY_DAYS) {
+                    daily[ACTIVITY_DAYS - 1 - slot] += count;
+                }
+            }
+            SourceReport {
+                status: evaluate(config, &source, now),
+                items_24h: daily[ACTIVITY_DAYS - 1],
+                items_7d: daily[ACTIVITY_DAYS - 7..].iter().sum(),
+                daily,
+                source,
+            }
+        })
+        .collect())
+}
+
+/// Unicode block sparkline of `counts`, scaled to the largest value.
+pub fn sparkline(counts: &[i64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = counts.iter().copied().max().unwrap_or(0);
+    counts
+        .iter()
+        .map(|&c| match c {
+            0 => ' ',
+            _ => BARS[((c * 7) / max.max(1)).clamp(0, 7) as usize],
+        })
+        .collect()
+}
+
+/// Report status changes of enabled sources (daemon, once per poller tick): a warning when a
+/// source turns failing or stale, info when it recovers, each posted to
+/// `[source_health].alert_matrix_room` if set. `health_alerted` makes every change report once;
+/// a failed Matrix post is retried on the next tick. A source turning failing is also sent to
+/// `[notifications]`.
+pub async fn check_sources(pool: &SqlitePool, config: &Config, tg_clients: &TgClients) {
+    let sources = match store::get_all_enabled_sources(pool).await {
+        Ok(s) => s,
+        Err(e) => {
+            warn!("failed to load sources for health check: {e:#}");
+            return;
+        }
+    };
+
+    let now = Utc::now();
+    for source in &sources {
+        let status = evaluate(config, source, now);
+        let alerted = status.is_warning().then(|| status.label());
+        if alerted == source.health_alerted.as_deref() {
+            continue;
+        }
+
+        let message = if status.is_warning() {
+            let detail = status.detail(now);
+            warn!(source = %source.name, status = status.label(), "source health warning: {detail}");
+            format!("pail: source '{}' is {}: {detail}", source.name, status.label())
+        } else {
+            info!(source = %source.name, "source recovered");
+            format!("pail: source '{}' recovered", source.name)
+        };
+
+        if let Some(ref room) = config.source_health.alert_matrix_room
+            && let Err(e) = matrix::send_notice(&config.matrix, &config.network, room, &message).await
+        {
+            warn!(source = %source.name, "failed to post source health alert: {e:#}");
+            continue;
+        }
+
+        if let Err(e) = store::set_health_alerted(pool, &source.id, alerted).await {
+            warn!(source = %source.name, "failed to record source health alert: {e:#}");
+            continue;
+        }
+
+        if matches!(status, SourceStatus::Failing { .. }) {
+            let title = format!("pail: source '{}' is failing", source.name);
+            notify::send(
+                config,
+                tg_clients,
+                notify::Event::SourceFailing,
+                &title,
+                &status.detail(now),
+            )
+            .await;
+        }
+    }
+}
+
+/// Handle a permanent redirect reported by a fetch: record the new URL so later fetches go
+/// there directly, and log a config hint. With `[source_health].rewrite_moved_urls` and a
+/// config path (daemon), the source's `url` in the config file is rewritten as well.
+pub async fn record_moved(pool: &SqlitePool, config: &Config, config_path: Option<&Path>, source: &Source, url: &str) {
+    if source.moved_url.as_deref() == Some(url) {
+        return;
+    }
+    if let Err(e) = store::set_source_moved_url(pool, &source.id, url).await {
+        warn!(source = %source.name, "failed to record moved feed URL: {e:#}");
+        return;
+    }
+
+    match config_path {
+        Some(path) if config.source_health.rewrite_moved_urls => match rewrite_source_url(path, &source.name, url) {
+            Ok(()) => info!(source = %source.name, url, "feed moved permanently, updated url in config"),
+            Err(e) => warn!(source = %source.name, url, "feed moved permanently, failed to update config: {e:#}"),
+        },
+        _ => warn!(
+            source = %source.name,
+            url,
+            "feed moved permanently, fetching the new URL; set `url = \"{url}\"` on the source in the config"
+        ),
+    }
+}
+
+/// Handle `410 Gone`: stop polling the source until its URL changes or it is re-enabled.
+pub async fn record_gone(pool: &SqlitePool, source: &Source, now: DateTime<Utc>) {
+    warn!(source = %source.name, "feed is gone (410), no longer polling it");
+    if let Err(e) = store::mark_source_gone(pool, &source.id, now).await {
+        warn!(source = %source.name, "failed to mark source gone: {e:#}");
+    }
+}
+
+fn rewrite_source_url(config_path: &Path, source_name: &str, url: &str) -> Result<()> {
+    let content = std::fs::read_to_string(config_path).context("reading config file")?;
+    let mut doc = config_edit::parse_document(&content)?;
+    if !config_edit::set_source_url(&mut doc, source_name, url) {
+        anyhow::bail!("source not found in config file");
+    }
+    config_edit::write_validated(config_path, &content, &config_edit::render(&doc))
+}
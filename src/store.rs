@@ -1,22 +1,41 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use sqlx::SqlitePool;
-use tracing::debug;
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use tracing::{debug, instrument};
 use uuid::Uuid;
 
+use crate::compress::{self, StoredText};
 use crate::config::Config;
-use crate::models::{ContentItem, GeneratedArticle, GeneratedArticleRow, OutputChannel, Source};
+use crate::language;
+use crate::models::{
+    ArticleFeedback, ArticleTranslation, ChannelRunStats, ContentItem, FeedAccess, FeedToken, FetchedPage,
+    GeneratedArticle, GeneratedArticleRow, GenerationRun, LiveRun, OutputChannel, QueuedGeneration, Source,
+    TgBackfillProgress, TgDebugUpdate, TgFolderChannel, Webmention,
+};
+
+/// All generated article columns in SELECT order (must match GeneratedArticleRow field order).
+const ARTICLE_COLUMNS: &str = "id, output_channel_id, generated_at, covers_from, covers_to,
+    title, topics, body_html, body_markdown, content_item_ids, generation_log, model_used, token_count, strategy_used,
+    status, read_at, eval_score, eval_details, metadata, summary";
 
 /// All source columns in SELECT order (must match Source struct field order).
 const SOURCE_COLUMNS: &str = "id, source_type, name, enabled, url, poll_interval, max_items,
     auth_type, auth_username, auth_password, auth_token, auth_header_name, auth_header_value,
     last_fetched_at, last_etag, last_modified_header,
-    tg_id, tg_username, tg_folder_id, tg_folder_name, description";
+    tg_id, tg_username, tg_folder_id, tg_folder_name, description, scrape_selectors, imap_config, read_later_config,
+    tg_account, consecutive_failures, last_error, last_success_at, last_item_at, health_alerted,
+    moved_url, gone_at, aggregator_config, ics_config, twitter_config, slack_config,
+    arxiv_config, proxy, dedup, headers, fetch_timeout";
 
 /// Upsert a source by name — insert or update if it already exists.
-pub async fn upsert_source(pool: &SqlitePool, source: &crate::config::SourceConfig) -> Result<String> {
+async fn upsert_source(
+    conn: &mut SqliteConnection,
+    source: &crate::config::SourceConfig,
+    network: &crate::config::NetworkConfig,
+) -> Result<String> {
+    let proxy = source.effective_proxy(network);
     let (auth_type, auth_username, auth_password, auth_token, auth_header_name, auth_header_value) =
         if let Some(auth) = &source.auth {
             (
@@ -32,25 +51,85 @@ pub async fn upsert_source(pool: &SqlitePool, source: &crate::config::SourceConf
         };
 
     let enabled = source.enabled.unwrap_or(true);
+    let scrape_selectors = source
+        .selectors
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .context("serializing scrape selectors")?;
+    let imap_config = source
+        .imap
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .context("serializing imap config")?;
+    let read_later_config = source
+        .read_later
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .context("serializing read-later config")?;
+    let aggregator_config = source
+        .aggregator
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .context("serializing aggregator config")?;
+    let ics_config = source
+        .ics
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .context("serializing ics config")?;
+    let twitter_config = source
+        .twitter
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .context("serializing twitter config")?;
+    let slack_config = source
+        .slack
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .context("serializing slack config")?;
+    let arxiv_config = source
+        .arxiv
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .context("serializing arxiv config")?;
+    let headers = (!source.headers.is_empty())
+        .then(|| serde_json::to_string(&source.headers))
+        .transpose()
+        .context("serializing source headers")?;
 
     // Check if source exists by name
     let existing: Option<(String,)> = sqlx::query_as("SELECT id FROM sources WHERE name = ?")
         .bind(&source.name)
-        .fetch_optional(pool)
+        .fetch_optional(&mut *conn)
         .await
         .context("checking for existing source")?;
 
+    // A changed URL drops the recorded redirect and gone state; so does disabling the source
     let id = if let Some((existing_id,)) = existing {
         sqlx::query(
-            "UPDATE sources SET source_type = ?, enabled = ?, url = ?, poll_interval = ?, max_items = ?,
+            "UPDATE sources SET source_type = ?, enabled = ?,
+             moved_url = CASE WHEN url IS ? THEN moved_url END,
+             gone_at = CASE WHEN url IS ? AND ? THEN gone_at END,
+             url = ?, poll_interval = ?, max_items = ?,
              auth_type = ?, auth_username = ?, auth_password = ?, auth_token = ?, auth_header_name = ?, auth_header_value = ?,
-             tg_id = COALESCE(?, tg_id), tg_username = ?, tg_folder_name = ?, description = ?,
-             updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+             tg_id = COALESCE(?, tg_id), tg_username = ?, tg_folder_name = ?, description = ?, scrape_selectors = ?, imap_config = ?,
+             read_later_config = ?, tg_account = ?, aggregator_config = ?, ics_config = ?, twitter_config = ?, slack_config = ?,
+             arxiv_config = ?, proxy = ?, dedup = ?, headers = ?, fetch_timeout = ?, updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
              WHERE id = ?",
         )
         .bind(&source.source_type)
         .bind(enabled)
         .bind(&source.url)
+        .bind(&source.url)
+        .bind(enabled)
+        .bind(&source.url)
         .bind(&source.poll_interval)
         .bind(source.max_items as i32)
         .bind(&auth_type)
@@ -63,8 +142,21 @@ pub async fn upsert_source(pool: &SqlitePool, source: &crate::config::SourceConf
         .bind(&source.tg_username)
         .bind(&source.tg_folder_name)
         .bind(&source.description)
+        .bind(&scrape_selectors)
+        .bind(&imap_config)
+        .bind(&read_later_config)
+        .bind(&source.tg_account)
+        .bind(&aggregator_config)
+        .bind(&ics_config)
+        .bind(&twitter_config)
+        .bind(&slack_config)
+        .bind(&arxiv_config)
+        .bind(proxy)
+        .bind(&source.dedup)
+        .bind(&headers)
+        .bind(&source.timeout)
         .bind(&existing_id)
-        .execute(pool)
+        .execute(&mut *conn)
         .await
         .context("updating source")?;
 
@@ -75,8 +167,10 @@ pub async fn upsert_source(pool: &SqlitePool, source: &crate::config::SourceConf
         sqlx::query(
             "INSERT INTO sources (id, source_type, name, enabled, url, poll_interval, max_items,
              auth_type, auth_username, auth_password, auth_token, auth_header_name, auth_header_value,
-             tg_id, tg_username, tg_folder_name, description)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+             tg_id, tg_username, tg_folder_name, description, scrape_selectors, imap_config, read_later_config,
+             tg_account, aggregator_config, ics_config, twitter_config, slack_config, arxiv_config, proxy, dedup,
+             headers, fetch_timeout)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         )
         .bind(&id)
         .bind(&source.source_type)
@@ -95,7 +189,20 @@ pub async fn upsert_source(pool: &SqlitePool, source: &crate::config::SourceConf
         .bind(&source.tg_username)
         .bind(&source.tg_folder_name)
         .bind(&source.description)
-        .execute(pool)
+        .bind(&scrape_selectors)
+        .bind(&imap_config)
+        .bind(&read_later_config)
+        .bind(&source.tg_account)
+        .bind(&aggregator_config)
+        .bind(&ics_config)
+        .bind(&twitter_config)
+        .bind(&slack_config)
+        .bind(&arxiv_config)
+        .bind(proxy)
+        .bind(&source.dedup)
+        .bind(&headers)
+        .bind(&source.timeout)
+        .execute(&mut *conn)
         .await
         .context("inserting source")?;
 
@@ -107,8 +214,8 @@ pub async fn upsert_source(pool: &SqlitePool, source: &crate::config::SourceConf
 }
 
 /// Upsert an output channel by slug.
-pub async fn upsert_output_channel(
-    pool: &SqlitePool,
+async fn upsert_output_channel(
+    conn: &mut SqliteConnection,
     channel: &crate::config::OutputChannelConfig,
     source_ids: &[String],
 ) -> Result<String> {
@@ -116,7 +223,7 @@ pub async fn upsert_output_channel(
 
     let existing: Option<(String,)> = sqlx::query_as("SELECT id FROM output_channels WHERE slug = ?")
         .bind(&channel.slug)
-        .fetch_optional(pool)
+        .fetch_optional(&mut *conn)
         .await
         .context("checking for existing output channel")?;
 
@@ -129,11 +236,11 @@ pub async fn upsert_output_channel(
         .bind(&channel.name)
         .bind(channel.schedule.as_deref())
         .bind(&channel.prompt)
-        .bind(&channel.model)
+        .bind(channel.model.as_ref().or(channel.models.first()))
         .bind(&channel.language)
         .bind(enabled)
         .bind(&existing_id)
-        .execute(pool)
+        .execute(&mut *conn)
         .await
         .context("updating output channel")?;
 
@@ -150,10 +257,10 @@ pub async fn upsert_output_channel(
         .bind(&channel.slug)
         .bind(channel.schedule.as_deref())
         .bind(&channel.prompt)
-        .bind(&channel.model)
+        .bind(channel.model.as_ref().or(channel.models.first()))
         .bind(&channel.language)
         .bind(enabled)
-        .execute(pool)
+        .execute(&mut *conn)
         .await
         .context("inserting output channel")?;
 
@@ -164,7 +271,7 @@ pub async fn upsert_output_channel(
     // Sync junction table
     sqlx::query("DELETE FROM output_channel_sources WHERE output_channel_id = ?")
         .bind(&id)
-        .execute(pool)
+        .execute(&mut *conn)
         .await
         .context("clearing output channel sources")?;
 
@@ -172,7 +279,7 @@ pub async fn upsert_output_channel(
         sqlx::query("INSERT INTO output_channel_sources (output_channel_id, source_id) VALUES (?, ?)")
             .bind(&id)
             .bind(source_id)
-            .execute(pool)
+            .execute(&mut *conn)
             .await
             .context("linking source to output channel")?;
     }
@@ -182,11 +289,16 @@ pub async fn upsert_output_channel(
 
 /// Sync all sources and output channels from config to DB.
 /// Sources and channels not in config are deleted (cascading to content_items).
+///
+/// Runs in a single transaction: a failure part-way leaves the previous state intact instead
+/// of channels with a cleared source list or junction rows pointing at half-synced sources.
 pub async fn sync_config_to_db(pool: &SqlitePool, config: &Config) -> Result<()> {
+    let mut tx = pool.begin().await.context("starting config sync transaction")?;
+
     // First, upsert all sources and build a name->id map
     let mut source_name_to_id = std::collections::HashMap::new();
     for source in &config.source {
-        let id = upsert_source(pool, source).await?;
+        let id = upsert_source(&mut tx, source, &config.network).await?;
         source_name_to_id.insert(source.name.clone(), id);
     }
 
@@ -199,13 +311,13 @@ pub async fn sync_config_to_db(pool: &SqlitePool, config: &Config) -> Result<()>
             .iter()
             .filter_map(|name| source_name_to_id.get(name).cloned())
             .collect();
-        upsert_output_channel(pool, channel, &source_ids).await?;
+        upsert_output_channel(&mut tx, channel, &source_ids).await?;
     }
 
     // Delete sources not in config
     let config_source_ids: Vec<&str> = source_name_to_id.values().map(|s| s.as_str()).collect();
     let db_sources: Vec<(String, String)> = sqlx::query_as("SELECT id, name FROM sources")
-        .fetch_all(pool)
+        .fetch_all(&mut *tx)
         .await
         .context("listing sources for cleanup")?;
 
@@ -213,7 +325,7 @@ pub async fn sync_config_to_db(pool: &SqlitePool, config: &Config) -> Result<()>
         if !config_source_ids.contains(&id.as_str()) {
             sqlx::query("DELETE FROM sources WHERE id = ?")
                 .bind(id)
-                .execute(pool)
+                .execute(&mut *tx)
                 .await
                 .context("deleting orphaned source")?;
             debug!(name = %name, "deleted orphaned source");
@@ -222,7 +334,7 @@ pub async fn sync_config_to_db(pool: &SqlitePool, config: &Config) -> Result<()>
 
     // Delete output channels not in config
     let db_channels: Vec<(String, String)> = sqlx::query_as("SELECT id, slug FROM output_channels")
-        .fetch_all(pool)
+        .fetch_all(&mut *tx)
         .await
         .context("listing channels for cleanup")?;
 
@@ -230,20 +342,21 @@ pub async fn sync_config_to_db(pool: &SqlitePool, config: &Config) -> Result<()>
         if !config_channel_slugs.contains(slug.as_str()) {
             sqlx::query("DELETE FROM output_channels WHERE id = ?")
                 .bind(id)
-                .execute(pool)
+                .execute(&mut *tx)
                 .await
                 .context("deleting orphaned output channel")?;
             debug!(slug = %slug, "deleted orphaned output channel");
         }
     }
 
+    tx.commit().await.context("committing config sync")?;
     Ok(())
 }
 
 /// Get an output channel by slug.
 pub async fn get_channel_by_slug(pool: &SqlitePool, slug: &str) -> Result<Option<OutputChannel>> {
     let channel = sqlx::query_as::<_, OutputChannel>(
-        "SELECT id, name, slug, schedule, prompt, model, language, enabled, last_generated
+        "SELECT id, name, slug, schedule, prompt, model, language, enabled, last_generated, skipped_from
          FROM output_channels WHERE slug = ?",
     )
     .bind(slug)
@@ -254,7 +367,22 @@ pub async fn get_channel_by_slug(pool: &SqlitePool, slug: &str) -> Result<Option
     Ok(channel)
 }
 
+/// Get an output channel by ID.
+pub async fn get_channel_by_id(pool: &SqlitePool, id: &str) -> Result<Option<OutputChannel>> {
+    let channel = sqlx::query_as::<_, OutputChannel>(
+        "SELECT id, name, slug, schedule, prompt, model, language, enabled, last_generated, skipped_from
+         FROM output_channels WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .context("querying output channel by ID")?;
+
+    Ok(channel)
+}
+
 /// Get source IDs linked to an output channel.
+#[instrument(skip_all, fields(channel_id = %channel_id))]
 pub async fn get_channel_source_ids(pool: &SqlitePool, channel_id: &str) -> Result<Vec<String>> {
     let rows: Vec<(String,)> =
         sqlx::query_as("SELECT source_id FROM output_channel_sources WHERE output_channel_id = ?")
@@ -266,6 +394,16 @@ pub async fn get_channel_source_ids(pool: &SqlitePool, channel_id: &str) -> Resu
     Ok(rows.into_iter().map(|(id,)| id).collect())
 }
 
+/// Get a source by its config name.
+pub async fn get_source_by_name(pool: &SqlitePool, name: &str) -> Result<Option<Source>> {
+    let query = format!("SELECT {SOURCE_COLUMNS} FROM sources WHERE name = ?");
+    sqlx::query_as::<_, Source>(&query)
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .context("querying source by name")
+}
+
 /// Get sources by their IDs.
 pub async fn get_sources_by_ids(pool: &SqlitePool, ids: &[String]) -> Result<Vec<Source>> {
     if ids.is_empty() {
@@ -288,33 +426,193 @@ pub async fn get_sources_by_ids(pool: &SqlitePool, ids: &[String]) -> Result<Vec
     Ok(sources)
 }
 
-/// Upsert a content item (skip if same source_id + dedup_key exists).
+/// Conflict clause of the content item upserts. Bodies are only compared when both are stored
+/// the same way (see `compress`): a body written before compression isn't a changed one.
+const ITEM_UPSERT_CONFLICT: &str = " ON CONFLICT(source_id, dedup_key) DO UPDATE SET
+    upstream_changed = ((excluded.body_compressed = content_items.body_compressed AND excluded.body IS NOT content_items.body)
+                        OR excluded.title IS NOT content_items.title)";
+
+/// Upsert a content item (skip if same source_id + dedup_key exists). The detected language
+/// is added to the stored metadata; large bodies are compressed.
+#[instrument(skip_all, fields(source_id = %item.source_id))]
 pub async fn upsert_content_item(pool: &SqlitePool, item: &ContentItem) -> Result<()> {
-    sqlx::query(
-        "INSERT INTO content_items (id, source_id, ingested_at, original_date, content_type, title, body, url, author, metadata, dedup_key)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-         ON CONFLICT(source_id, dedup_key) DO UPDATE SET
-           upstream_changed = (excluded.body IS NOT content_items.body OR excluded.title IS NOT content_items.title)",
-    )
+    let mut tx = pool.begin().await.context("starting content item transaction")?;
+    let body = compress::encode(&item.body);
+    sqlx::query(&format!(
+        "INSERT INTO content_items (id, source_id, ingested_at, original_date, content_type, title, body, body_compressed, url, author, metadata, dedup_key)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?){ITEM_UPSERT_CONFLICT}"
+    ))
     .bind(&item.id)
     .bind(&item.source_id)
     .bind(item.ingested_at.format("%Y-%m-%dT%H:%M:%SZ").to_string())
     .bind(item.original_date.format("%Y-%m-%dT%H:%M:%SZ").to_string())
     .bind(&item.content_type)
     .bind(&item.title)
-    .bind(&item.body)
+    .bind(&body)
+    .bind(body.is_compressed())
     .bind(&item.url)
     .bind(&item.author)
-    .bind(&item.metadata)
+    .bind(language::with_language(item))
     .bind(&item.dedup_key)
-    .execute(pool)
+    .execute(&mut *tx)
     .await
     .context("upserting content item")?;
+    delete_album_parts(&mut tx, std::slice::from_ref(item)).await?;
+    tx.commit().await.context("committing content item")?;
+
+    Ok(())
+}
+
+/// Rows per multi-row INSERT in `upsert_content_items`. 12 bound parameters per row keeps a
+/// statement under SQLite's historical 999-parameter limit.
+const UPSERT_BATCH_ROWS: usize = 80;
+
+/// Upsert many content items in one transaction, using multi-row INSERTs. Same conflict
+/// semantics as `upsert_content_item`; all-or-nothing.
+#[instrument(skip_all, fields(items = items.len()))]
+pub async fn upsert_content_items(pool: &SqlitePool, items: &[ContentItem]) -> Result<()> {
+    if items.is_empty() {
+        return Ok(());
+    }
+
+    let mut tx = pool.begin().await.context("starting content item transaction")?;
+    for chunk in items.chunks(UPSERT_BATCH_ROWS) {
+        let mut query: QueryBuilder<Sqlite> = QueryBuilder::new(
+            "INSERT INTO content_items (id, source_id, ingested_at, original_date, content_type, title, body, body_compressed, url, author, metadata, dedup_key) ",
+        );
+        query.push_values(chunk, |mut row, item| {
+            let body = compress::encode(&item.body);
+            let compressed = body.is_compressed();
+            row.push_bind(&item.id)
+                .push_bind(&item.source_id)
+                .push_bind(item.ingested_at.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+                .push_bind(item.original_date.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+                .push_bind(&item.content_type)
+                .push_bind(&item.title)
+                .push_bind(body)
+                .push_bind(compressed)
+                .push_bind(&item.url)
+                .push_bind(&item.author)
+                .push_bind(language::with_language(item))
+                .push_bind(&item.dedup_key);
+        });
+        query.push(ITEM_UPSERT_CONFLICT);
+        query
+            .build()
+            .execute(&mut *tx)
+            .await
+            .context("upserting content items")?;
+    }
+    delete_album_parts(&mut tx, items).await?;
+    tx.commit().await.context("committing content items")?;
+
+    debug!(count = items.len(), "upserted content items");
+    Ok(())
+}
+
+/// Delete the per-message items that the Telegram albums among `items` replace: parts stored
+/// one by one before albums were merged, which would otherwise show up next to the album
+/// (see docs/specs/telegram.md "Albums").
+async fn delete_album_parts(conn: &mut SqliteConnection, items: &[ContentItem]) -> Result<()> {
+    for item in items {
+        let keys = album_part_keys(item);
+        if keys.is_empty() {
+            continue;
+        }
+        sqlx::query("DELETE FROM content_items WHERE source_id = ? AND dedup_key IN (SELECT value FROM json_each(?))")
+            .bind(&item.source_id)
+            .bind(serde_json::to_string(&keys)?)
+            .execute(&mut *conn)
+            .await
+            .context("deleting album parts")?;
+    }
+    Ok(())
+}
+
+/// The per-message dedup keys (`tg:<chat_id>:<message_id>`) of an album item's parts; empty
+/// for anything that isn't an album.
+fn album_part_keys(item: &ContentItem) -> Vec<String> {
+    let Some((chat_id, _)) = item.dedup_key.strip_prefix("tg:").and_then(|k| k.split_once(":album:")) else {
+        return Vec::new();
+    };
+    let meta: serde_json::Value = serde_json::from_str(&item.metadata).unwrap_or_default();
+    meta["message_ids"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|id| id.as_i64())
+        .map(|id| format!("tg:{chat_id}:{id}"))
+        .collect()
+}
+
+/// Whether a content item with this dedup key was already stored for the source, or dropped
+/// by its pre-processor.
+#[instrument(skip_all, fields(source_id = %source_id))]
+pub async fn content_item_exists(pool: &SqlitePool, source_id: &str, dedup_key: &str) -> Result<bool> {
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM content_items WHERE source_id = ? AND dedup_key = ?)
+             OR EXISTS(SELECT 1 FROM preprocess_dropped WHERE source_id = ? AND dedup_key = ?)",
+    )
+    .bind(source_id)
+    .bind(dedup_key)
+    .bind(source_id)
+    .bind(dedup_key)
+    .fetch_one(pool)
+    .await
+    .context("checking content item existence")?;
+    Ok(exists)
+}
+
+/// Record items a source's pre-processor dropped, so they aren't pre-processed again.
+pub async fn insert_preprocess_dropped(pool: &SqlitePool, source_id: &str, dedup_keys: &[String]) -> Result<()> {
+    if dedup_keys.is_empty() {
+        return Ok(());
+    }
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut tx = pool.begin().await.context("starting dropped items transaction")?;
+    for key in dedup_keys {
+        sqlx::query("INSERT OR IGNORE INTO preprocess_dropped (source_id, dedup_key, dropped_at) VALUES (?, ?, ?)")
+            .bind(source_id)
+            .bind(key)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await
+            .context("recording dropped item")?;
+    }
+    tx.commit().await.context("committing dropped items")?;
+    Ok(())
+}
+
+/// Forget items dropped by pre-processors before the cutoff. Returns the number of rows deleted.
+pub async fn delete_old_preprocess_dropped(pool: &SqlitePool, cutoff: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM preprocess_dropped WHERE dropped_at < ?")
+        .bind(cutoff.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .execute(pool)
+        .await
+        .context("deleting old dropped items")?;
+    Ok(result.rows_affected())
+}
 
+/// Replace the metadata of stored items, as `(item ID, metadata JSON)` pairs, in one transaction.
+pub async fn update_item_metadata(pool: &SqlitePool, updates: &[(String, String)]) -> Result<()> {
+    if updates.is_empty() {
+        return Ok(());
+    }
+    let mut tx = pool.begin().await.context("starting metadata transaction")?;
+    for (id, metadata) in updates {
+        sqlx::query("UPDATE content_items SET metadata = ? WHERE id = ?")
+            .bind(metadata)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .context("updating item metadata")?;
+    }
+    tx.commit().await.context("committing item metadata")?;
     Ok(())
 }
 
 /// Get content items within a time window for the given source IDs.
+#[instrument(skip_all, fields(sources = source_ids.len(), from = %from, to = %to))]
 pub async fn get_items_in_window(
     pool: &SqlitePool,
     source_ids: &[String],
@@ -325,27 +623,256 @@ pub async fn get_items_in_window(
         return Ok(Vec::new());
     }
 
-    let placeholders: Vec<&str> = source_ids.iter().map(|_| "?").collect();
-    let query = format!(
+    let query = window_items_sql(source_ids.len());
+    let mut q = sqlx::query_as::<_, ContentItem>(&query);
+    for _ in 0..2 {
+        for id in source_ids {
+            q = q.bind(id);
+        }
+        q = q
+            .bind(from.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+            .bind(to.format("%Y-%m-%dT%H:%M:%SZ").to_string());
+    }
+
+    let items = q.fetch_all(pool).await.context("querying content items in window")?;
+
+    Ok(items)
+}
+
+/// `get_items_in_window`'s query for `sources` source IDs: the IDs, `from` and `to`, twice.
+/// Both tables are read through their (source_id, original_date) index, so a window costs one
+/// index seek per source in each (see docs/specs/daemon.md "Query Plans"). An item ingested
+/// again after it was archived is read from content_items only.
+fn window_items_sql(sources: usize) -> String {
+    let placeholders = vec!["?"; sources].join(", ");
+    format!(
         "SELECT id, source_id, ingested_at, original_date, content_type, title, body, url, author, metadata, dedup_key, upstream_changed
          FROM content_items
-         WHERE source_id IN ({})
+         WHERE source_id IN ({placeholders})
            AND original_date >= ?
            AND original_date <= ?
-         ORDER BY original_date ASC",
-        placeholders.join(", ")
-    );
+           AND deleted_upstream_at IS NULL
+         UNION ALL
+         SELECT id, source_id, ingested_at, original_date, content_type, title, body, url, author, metadata, dedup_key, upstream_changed
+         FROM content_items_archive a
+         WHERE source_id IN ({placeholders})
+           AND original_date >= ?
+           AND original_date <= ?
+           AND deleted_upstream_at IS NULL
+           AND NOT EXISTS (SELECT 1 FROM content_items c WHERE c.source_id = a.source_id AND c.dedup_key = a.dedup_key)
+         ORDER BY original_date ASC"
+    )
+}
 
-    let mut q = sqlx::query_as::<_, ContentItem>(&query);
+/// Apply a Telegram message edit to its stored item: the new title and body replace the stored
+/// ones, and the item is flagged `upstream_changed` if either differs. Returns whether an item
+/// was changed.
+pub async fn update_edited_item(pool: &SqlitePool, item: &ContentItem) -> Result<bool> {
+    let body = compress::encode(&item.body);
+    let result = sqlx::query(
+        "UPDATE content_items SET upstream_changed = 1, title = ?, body = ?, body_compressed = ?
+         WHERE source_id = ? AND dedup_key = ?
+           AND (body IS NOT ? OR body_compressed != ? OR title IS NOT ?)",
+    )
+    .bind(&item.title)
+    .bind(&body)
+    .bind(body.is_compressed())
+    .bind(&item.source_id)
+    .bind(&item.dedup_key)
+    .bind(&body)
+    .bind(body.is_compressed())
+    .bind(&item.title)
+    .execute(pool)
+    .await
+    .context("updating edited content item")?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// The stored item with this dedup key for the source, if any.
+pub async fn get_content_item(pool: &SqlitePool, source_id: &str, dedup_key: &str) -> Result<Option<ContentItem>> {
+    sqlx::query_as(
+        "SELECT id, source_id, ingested_at, original_date, content_type, title, body, url, author, metadata, dedup_key, upstream_changed
+         FROM content_items WHERE source_id = ? AND dedup_key = ?",
+    )
+    .bind(source_id)
+    .bind(dedup_key)
+    .fetch_optional(pool)
+    .await
+    .context("querying content item by dedup key")
+}
+
+/// Replace a stored Telegram album with one merged from it and parts that arrived later: the
+/// body, metadata and content type are rewritten, the item is flagged `upstream_changed` if
+/// its body changed, and per-message items of the new parts are deleted. Returns whether the
+/// album was stored.
+pub async fn replace_album(pool: &SqlitePool, item: &ContentItem) -> Result<bool> {
+    let mut tx = pool.begin().await.context("starting album transaction")?;
+    let body = compress::encode(&item.body);
+    let result = sqlx::query(
+        "UPDATE content_items SET
+             upstream_changed = CASE WHEN body_compressed = ? AND body IS NOT ? THEN 1 ELSE upstream_changed END,
+             body = ?, body_compressed = ?, content_type = ?, author = ?, metadata = ?
+         WHERE source_id = ? AND dedup_key = ?",
+    )
+    .bind(body.is_compressed())
+    .bind(&body)
+    .bind(&body)
+    .bind(body.is_compressed())
+    .bind(&item.content_type)
+    .bind(&item.author)
+    .bind(language::with_language(item))
+    .bind(&item.source_id)
+    .bind(&item.dedup_key)
+    .execute(&mut *tx)
+    .await
+    .context("replacing album")?;
+    delete_album_parts(&mut tx, std::slice::from_ref(item)).await?;
+    tx.commit().await.context("committing album")?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Flag an item as changed upstream without touching its content. Returns whether it exists.
+#[instrument(skip_all, fields(source_id = %source_id))]
+pub async fn flag_upstream_changed(pool: &SqlitePool, source_id: &str, dedup_key: &str) -> Result<bool> {
+    let result = sqlx::query("UPDATE content_items SET upstream_changed = 1 WHERE source_id = ? AND dedup_key = ?")
+        .bind(source_id)
+        .bind(dedup_key)
+        .execute(pool)
+        .await
+        .context("flagging content item as changed")?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Keep only `remaining` of a stored Telegram album's `message_ids` after some of its parts
+/// were deleted upstream, and flag it as changed. Returns whether it exists.
+#[instrument(skip_all, fields(source_id = %source_id))]
+pub async fn set_album_message_ids(
+    pool: &SqlitePool,
+    source_id: &str,
+    dedup_key: &str,
+    remaining: &[i64],
+) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE content_items SET upstream_changed = 1, metadata = json_set(metadata, '$.message_ids', json(?))
+         WHERE source_id = ? AND dedup_key = ?",
+    )
+    .bind(serde_json::to_string(remaining)?)
+    .bind(source_id)
+    .bind(dedup_key)
+    .execute(pool)
+    .await
+    .context("updating album parts")?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Stored items of Telegram messages among `message_ids`, as (item ID, source ID, dedup key,
+/// metadata): single
+/// messages by `message_id`, albums if any of their `message_ids` matches. With `chat_id`
+/// `None`, matches messages of any chat that isn't a channel (deletions in private chats and
+/// basic groups don't name the chat).
+pub async fn find_tg_message_items(
+    pool: &SqlitePool,
+    source_ids: &[String],
+    chat_id: Option<i64>,
+    message_ids: &[i32],
+) -> Result<Vec<(String, String, String, String)>> {
+    if source_ids.is_empty() || message_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut query: QueryBuilder<Sqlite> =
+        QueryBuilder::new("SELECT id, source_id, dedup_key, metadata FROM content_items WHERE source_id IN (");
+    let mut sources = query.separated(", ");
     for id in source_ids {
-        q = q.bind(id);
+        sources.push_bind(id);
     }
-    q = q
-        .bind(from.format("%Y-%m-%dT%H:%M:%SZ").to_string())
-        .bind(to.format("%Y-%m-%dT%H:%M:%SZ").to_string());
+    match chat_id {
+        Some(chat_id) => {
+            query.push(") AND json_extract(metadata, '$.chat_id') = ");
+            query.push_bind(chat_id);
+        }
+        None => {
+            query.push(") AND json_extract(metadata, '$.chat_type') IN ('user', 'chat')");
+        }
+    }
+    let ids = message_ids.iter().map(i32::to_string).collect::<Vec<_>>().join(", ");
+    query.push(format!(
+        " AND (json_extract(metadata, '$.message_id') IN ({ids})
+              OR EXISTS (SELECT 1 FROM json_each(metadata, '$.message_ids') WHERE value IN ({ids})))"
+    ));
+    let rows = query
+        .build_query_as::<(String, String, String, String)>()
+        .fetch_all(pool)
+        .await
+        .context("querying items of deleted messages")?;
+    Ok(rows)
+}
 
-    let items = q.fetch_all(pool).await.context("querying content items in window")?;
+/// Tombstone items whose message was deleted upstream: they stay stored (and keep their
+/// dedup keys) but are left out of generations. Returns the number of items marked.
+pub async fn mark_items_deleted_upstream(pool: &SqlitePool, item_ids: &[String]) -> Result<u64> {
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut marked = 0;
+    for chunk in item_ids.chunks(500) {
+        let mut query: QueryBuilder<Sqlite> = QueryBuilder::new("UPDATE content_items SET deleted_upstream_at = ");
+        query.push_bind(&now);
+        query.push(" WHERE deleted_upstream_at IS NULL AND id IN (");
+        let mut ids = query.separated(", ");
+        for id in chunk {
+            ids.push_bind(id);
+        }
+        query.push(")");
+        marked += query
+            .build()
+            .execute(pool)
+            .await
+            .context("marking items deleted upstream")?
+            .rows_affected();
+    }
+    Ok(marked)
+}
+
+/// Delete content items by ID. Returns the number deleted.
+pub async fn delete_items(pool: &SqlitePool, item_ids: &[String]) -> Result<u64> {
+    let mut deleted = 0;
+    for chunk in item_ids.chunks(500) {
+        let mut query: QueryBuilder<Sqlite> = QueryBuilder::new("DELETE FROM content_items WHERE id IN (");
+        let mut ids = query.separated(", ");
+        for id in chunk {
+            ids.push_bind(id);
+        }
+        query.push(")");
+        deleted += query
+            .build()
+            .execute(pool)
+            .await
+            .context("deleting content items")?
+            .rows_affected();
+    }
+    Ok(deleted)
+}
 
+/// Get content items by their IDs, oldest first, archived ones included. IDs no longer
+/// stored are skipped.
+#[instrument(skip_all, fields(items = ids.len()))]
+pub async fn get_items_by_ids(pool: &SqlitePool, ids: &[String]) -> Result<Vec<ContentItem>> {
+    let mut items = Vec::new();
+    // Chunk to stay under SQLite's bound parameter limit (each ID is bound twice)
+    for chunk in ids.chunks(250) {
+        let placeholders = chunk.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+        let query = format!(
+            "SELECT id, source_id, ingested_at, original_date, content_type, title, body, url, author, metadata, dedup_key, upstream_changed
+             FROM content_items WHERE id IN ({placeholders})
+             UNION ALL
+             SELECT id, source_id, ingested_at, original_date, content_type, title, body, url, author, metadata, dedup_key, upstream_changed
+             FROM content_items_archive WHERE id IN ({placeholders})"
+        );
+        let mut q = sqlx::query_as::<_, ContentItem>(&query);
+        for id in chunk.iter().chain(chunk) {
+            q = q.bind(id);
+        }
+        items.extend(q.fetch_all(pool).await.context("querying content items by IDs")?);
+    }
+    items.sort_by_key(|i| i.original_date);
     Ok(items)
 }
 
@@ -354,11 +881,14 @@ pub async fn insert_generated_article(pool: &SqlitePool, article: &GeneratedArti
     let content_item_ids_json =
         serde_json::to_string(&article.content_item_ids).context("serializing content_item_ids")?;
     let topics_json = serde_json::to_string(&article.topics).context("serializing topics")?;
+    let generation_log = compress::encode(&article.generation_log);
+    let mut tx = pool.begin().await.context("starting article transaction")?;
 
     sqlx::query(
         "INSERT INTO generated_articles (id, output_channel_id, generated_at, covers_from, covers_to,
-         title, topics, body_html, body_markdown, content_item_ids, generation_log, model_used, token_count, strategy_used)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+         title, topics, body_html, body_markdown, content_item_ids, generation_log, log_compressed, model_used, token_count,
+         strategy_used, status, eval_score, eval_details, metadata, summary)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(&article.id)
     .bind(&article.output_channel_id)
@@ -370,124 +900,1639 @@ pub async fn insert_generated_article(pool: &SqlitePool, article: &GeneratedArti
     .bind(&article.body_html)
     .bind(&article.body_markdown)
     .bind(&content_item_ids_json)
-    .bind(&article.generation_log)
+    .bind(&generation_log)
+    .bind(generation_log.is_compressed())
     .bind(&article.model_used)
     .bind(article.token_count)
     .bind(&article.strategy_used)
-    .execute(pool)
+    .bind(&article.status)
+    .bind(article.eval_score)
+    .bind(&article.eval_details)
+    .bind(&article.metadata)
+    .bind(&article.summary)
+    .execute(&mut *tx)
     .await
     .context("inserting generated article")?;
 
-    Ok(())
-}
+    let created_at = article.generated_at.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    for skipped in &article.skipped {
+        sqlx::query(
+            "INSERT INTO skipped_items (id, article_id, output_channel_id, source_id, content_item_id, title, url,
+             reason, item_count, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&article.id)
+        .bind(&article.output_channel_id)
+        .bind(&skipped.source_id)
+        .bind(&skipped.content_item_id)
+        .bind(&skipped.title)
+        .bind(&skipped.url)
+        .bind(&skipped.reason)
+        .bind(skipped.item_count)
+        .bind(&created_at)
+        .execute(&mut *tx)
+        .await
+        .context("inserting skipped item")?;
+    }
 
-/// Update the last_generated timestamp on an output channel.
-pub async fn update_last_generated(pool: &SqlitePool, channel_id: &str, timestamp: DateTime<Utc>) -> Result<()> {
-    sqlx::query("UPDATE output_channels SET last_generated = ? WHERE id = ?")
-        .bind(timestamp.format("%Y-%m-%dT%H:%M:%SZ").to_string())
-        .bind(channel_id)
-        .execute(pool)
+    for item_id in &article.content_item_ids {
+        sqlx::query(
+            "INSERT OR IGNORE INTO article_item_citations (article_id, content_item_id, cited) VALUES (?, ?, ?)",
+        )
+        .bind(&article.id)
+        .bind(item_id)
+        .bind(article.cited_item_ids.contains(item_id))
+        .execute(&mut *tx)
         .await
-        .context("updating last_generated")?;
+        .context("inserting article citation")?;
+    }
 
+    tx.commit().await.context("committing generated article")?;
     Ok(())
 }
 
-/// Read a setting from the settings table.
-pub async fn get_setting(pool: &SqlitePool, key: &str) -> Result<Option<String>> {
-    let row: Option<(String,)> = sqlx::query_as("SELECT value FROM settings WHERE key = ?")
-        .bind(key)
-        .fetch_optional(pool)
-        .await
-        .context("reading setting")?;
-    Ok(row.map(|(v,)| v))
+/// Citation coverage of an article: (items cited, items provided). `None` for articles
+/// generated before citations were recorded.
+pub async fn get_article_coverage(pool: &SqlitePool, article_id: &str) -> Result<Option<(i64, i64)>> {
+    let (cited, provided): (i64, i64) =
+        sqlx::query_as("SELECT COALESCE(SUM(cited), 0), COUNT(*) FROM article_item_citations WHERE article_id = ?")
+            .bind(article_id)
+            .fetch_one(pool)
+            .await
+            .context("querying article coverage")?;
+    Ok((provided > 0).then_some((cited, provided)))
 }
 
-/// Upsert a setting in the settings table.
-pub async fn set_setting(pool: &SqlitePool, key: &str, value: &str) -> Result<()> {
+/// Store an article's translation, replacing an earlier one in the same language.
+pub async fn upsert_article_translation(pool: &SqlitePool, translation: &ArticleTranslation) -> Result<()> {
     sqlx::query(
-        "INSERT INTO settings (key, value, updated_at) VALUES (?, ?, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
-         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+        "INSERT INTO article_translations (article_id, lang, title, body_markdown, body_html, model, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT (article_id, lang) DO UPDATE SET
+             title = excluded.title, body_markdown = excluded.body_markdown, body_html = excluded.body_html,
+             model = excluded.model, created_at = excluded.created_at",
     )
-    .bind(key)
-    .bind(value)
+    .bind(&translation.article_id)
+    .bind(&translation.lang)
+    .bind(&translation.title)
+    .bind(&translation.body_markdown)
+    .bind(&translation.body_html)
+    .bind(&translation.model)
+    .bind(translation.created_at.format("%Y-%m-%dT%H:%M:%SZ").to_string())
     .execute(pool)
     .await
-    .context("upserting setting")?;
+    .context("storing article translation")?;
     Ok(())
 }
 
-/// Update fetch state on a source: last_fetched_at, ETag, and Last-Modified.
-pub async fn update_source_fetch_state(
+/// An article's translation into `lang`, if one was made.
+pub async fn get_article_translation(
     pool: &SqlitePool,
-    source_id: &str,
-    timestamp: DateTime<Utc>,
-    etag: Option<&str>,
-    last_modified: Option<&str>,
+    article_id: &str,
+    lang: &str,
+) -> Result<Option<ArticleTranslation>> {
+    sqlx::query_as::<_, ArticleTranslation>(
+        "SELECT article_id, lang, title, body_markdown, body_html, model, created_at
+         FROM article_translations WHERE article_id = ? AND lang = ?",
+    )
+    .bind(article_id)
+    .bind(lang)
+    .fetch_optional(pool)
+    .await
+    .context("querying article translation")
+}
+
+/// Languages an article has translations in, sorted.
+pub async fn get_article_translation_langs(pool: &SqlitePool, article_id: &str) -> Result<Vec<String>> {
+    let langs: Vec<(String,)> =
+        sqlx::query_as("SELECT lang FROM article_translations WHERE article_id = ? ORDER BY lang")
+            .bind(article_id)
+            .fetch_all(pool)
+            .await
+            .context("querying article translation languages")?;
+    Ok(langs.into_iter().map(|(lang,)| lang).collect())
+}
+
+/// Record a generation as running, for `/runs/<id>/live`.
+#[instrument(skip_all, fields(channel_id = %channel_id))]
+pub async fn insert_live_run(pool: &SqlitePool, run_id: &str, channel_id: &str) -> Result<()> {
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    sqlx::query("INSERT INTO live_runs (run_id, channel_id, started_at, updated_at) VALUES (?, ?, ?, ?)")
+        .bind(run_id)
+        .bind(channel_id)
+        .bind(&now)
+        .bind(&now)
+        .execute(pool)
+        .await
+        .context("inserting live run")?;
+    Ok(())
+}
+
+/// Replace a running generation's output tail.
+pub async fn update_live_run(
+    pool: &SqlitePool,
+    run_id: &str,
+    tail: &str,
+    last_output_at: Option<DateTime<Utc>>,
 ) -> Result<()> {
-    sqlx::query("UPDATE sources SET last_fetched_at = ?, last_etag = ?, last_modified_header = ? WHERE id = ?")
-        .bind(timestamp.format("%Y-%m-%dT%H:%M:%SZ").to_string())
-        .bind(etag)
-        .bind(last_modified)
-        .bind(source_id)
+    sqlx::query("UPDATE live_runs SET tail = ?, last_output_at = ?, updated_at = ? WHERE run_id = ?")
+        .bind(tail)
+        .bind(last_output_at.map(|t| t.format("%Y-%m-%dT%H:%M:%SZ").to_string()))
+        .bind(Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .bind(run_id)
         .execute(pool)
         .await
-        .context("updating source fetch state")?;
+        .context("updating live run")?;
     Ok(())
 }
 
-/// Delete content items older than the cutoff. Returns number of deleted rows.
-pub async fn delete_old_content_items(pool: &SqlitePool, cutoff: DateTime<Utc>) -> Result<u64> {
-    let result = sqlx::query("DELETE FROM content_items WHERE ingested_at < ?")
-        .bind(cutoff.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+pub async fn delete_live_run(pool: &SqlitePool, run_id: &str) -> Result<()> {
+    sqlx::query("DELETE FROM live_runs WHERE run_id = ?")
+        .bind(run_id)
         .execute(pool)
         .await
-        .context("deleting old content items")?;
-    Ok(result.rows_affected())
+        .context("deleting live run")?;
+    Ok(())
 }
 
-/// Get recent generated articles for an output channel (for Atom feed).
-pub async fn get_recent_articles(pool: &SqlitePool, channel_id: &str, limit: i64) -> Result<Vec<GeneratedArticleRow>> {
-    let articles = sqlx::query_as::<_, GeneratedArticleRow>(
-        "SELECT id, output_channel_id, generated_at, covers_from, covers_to,
-         title, topics, body_html, body_markdown, content_item_ids, generation_log, model_used, token_count, strategy_used
-         FROM generated_articles
-         WHERE output_channel_id = ?
-         ORDER BY generated_at DESC
-         LIMIT ?",
+pub async fn get_live_run(pool: &SqlitePool, run_id: &str) -> Result<Option<LiveRun>> {
+    sqlx::query_as::<_, LiveRun>(
+        "SELECT run_id, channel_id, started_at, updated_at, last_output_at, tail FROM live_runs WHERE run_id = ?",
     )
-    .bind(channel_id)
-    .bind(limit)
-    .fetch_all(pool)
+    .bind(run_id)
+    .fetch_optional(pool)
     .await
-    .context("querying recent articles")?;
-    Ok(articles)
+    .context("querying live run")
 }
 
-/// Get all enabled output channels.
-pub async fn get_all_enabled_channels(pool: &SqlitePool) -> Result<Vec<OutputChannel>> {
-    let channels = sqlx::query_as::<_, OutputChannel>(
-        "SELECT id, name, slug, schedule, prompt, model, language, enabled, last_generated
-         FROM output_channels WHERE enabled = 1",
+/// Running generations, oldest first.
+pub async fn get_live_runs(pool: &SqlitePool) -> Result<Vec<LiveRun>> {
+    sqlx::query_as::<_, LiveRun>(
+        "SELECT run_id, channel_id, started_at, updated_at, last_output_at, tail FROM live_runs ORDER BY started_at",
     )
     .fetch_all(pool)
     .await
-    .context("querying enabled output channels")?;
-    Ok(channels)
+    .context("querying live runs")
 }
 
-/// Get a single generated article by its UUID.
-pub async fn get_article_by_id(pool: &SqlitePool, article_id: &str) -> Result<Option<GeneratedArticleRow>> {
-    let article = sqlx::query_as::<_, GeneratedArticleRow>(
-        "SELECT id, output_channel_id, generated_at, covers_from, covers_to,
-         title, topics, body_html, body_markdown, content_item_ids, generation_log, model_used, token_count, strategy_used
-         FROM generated_articles WHERE id = ?",
+/// Delete live runs not updated since `cutoff`: left behind by an instance that died mid-run.
+pub async fn delete_stale_live_runs(pool: &SqlitePool, cutoff: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM live_runs WHERE updated_at < ?")
+        .bind(cutoff.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .execute(pool)
+        .await
+        .context("deleting stale live runs")?;
+    Ok(result.rows_affected())
+}
+
+/// Queue Webmentions from an article to `targets`, due now. Targets already queued for the
+/// article are left as they are. Returns the number queued.
+pub async fn enqueue_webmentions(pool: &SqlitePool, article_id: &str, targets: &[String]) -> Result<u64> {
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut tx = pool.begin().await.context("starting transaction")?;
+    let mut queued = 0;
+    for target in targets {
+        queued += sqlx::query(
+            "INSERT OR IGNORE INTO webmentions (article_id, target, next_attempt_at, created_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(article_id)
+        .bind(target)
+        .bind(&now)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await
+        .context("queueing webmention")?
+        .rows_affected();
+    }
+    tx.commit().await.context("committing webmentions")?;
+    Ok(queued)
+}
+
+/// Pending Webmentions due by `now`, oldest first.
+pub async fn get_due_webmentions(pool: &SqlitePool, now: DateTime<Utc>, limit: i64) -> Result<Vec<Webmention>> {
+    sqlx::query_as::<_, Webmention>(
+        "SELECT id, article_id, target, status, endpoint, attempts, next_attempt_at, last_error, created_at, sent_at
+         FROM webmentions WHERE status = 'pending' AND next_attempt_at <= ?
+         ORDER BY next_attempt_at, id LIMIT ?",
     )
-    .bind(article_id)
-    .fetch_optional(pool)
+    .bind(now.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .bind(limit)
+    .fetch_all(pool)
     .await
-    .context("querying article by ID")?;
-    Ok(article)
+    .context("querying due webmentions")
+}
+
+/// Record the outcome of a Webmention attempt: its new status, attempt count, next attempt
+/// (for `pending`), endpoint and error.
+pub async fn update_webmention(pool: &SqlitePool, webmention: &Webmention) -> Result<()> {
+    sqlx::query(
+        "UPDATE webmentions SET status = ?, endpoint = ?, attempts = ?, next_attempt_at = ?, last_error = ?, sent_at = ?
+         WHERE id = ?",
+    )
+    .bind(&webmention.status)
+    .bind(&webmention.endpoint)
+    .bind(webmention.attempts)
+    .bind(webmention.next_attempt_at.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .bind(&webmention.last_error)
+    .bind(webmention.sent_at.map(|t| t.format("%Y-%m-%dT%H:%M:%SZ").to_string()))
+    .bind(webmention.id)
+    .execute(pool)
+    .await
+    .context("updating webmention")?;
+    Ok(())
+}
+
+/// Citation coverage of a channel's latest published article, as for `get_article_coverage`.
+#[instrument(skip_all, fields(channel_id = %channel_id))]
+pub async fn get_latest_article_coverage(pool: &SqlitePool, channel_id: &str) -> Result<Option<(i64, i64)>> {
+    let (cited, provided): (i64, i64) = sqlx::query_as(
+        "SELECT COALESCE(SUM(cited), 0), COUNT(*) FROM article_item_citations
+         WHERE article_id = (SELECT id FROM generated_articles
+                             WHERE output_channel_id = ? AND status = 'published'
+                             ORDER BY generated_at DESC LIMIT 1)",
+    )
+    .bind(channel_id)
+    .fetch_one(pool)
+    .await
+    .context("querying latest article coverage")?;
+    Ok((provided > 0).then_some((cited, provided)))
+}
+
+/// Sources skipped in at least `min_articles` distinct articles since `since`, most-skipped
+/// first: (source name, articles, skipped items, latest reason). Shown by `pail ctl status`.
+pub async fn get_repeatedly_skipped_sources(
+    pool: &SqlitePool,
+    since: DateTime<Utc>,
+    min_articles: i64,
+) -> Result<Vec<(String, i64, i64, String)>> {
+    sqlx::query_as(
+        "SELECT s.name, COUNT(DISTINCT k.article_id), SUM(k.item_count),
+                (SELECT k2.reason FROM skipped_items k2
+                 WHERE k2.source_id = s.id ORDER BY k2.created_at DESC LIMIT 1)
+         FROM skipped_items k JOIN sources s ON s.id = k.source_id
+         WHERE k.created_at >= ?
+         GROUP BY s.id
+         HAVING COUNT(DISTINCT k.article_id) >= ?
+         ORDER BY COUNT(DISTINCT k.article_id) DESC, SUM(k.item_count) DESC",
+    )
+    .bind(since.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .bind(min_articles)
+    .fetch_all(pool)
+    .await
+    .context("querying skipped sources")
+}
+
+/// Update the last_generated timestamp on an output channel.
+#[instrument(skip_all, fields(channel_id = %channel_id))]
+pub async fn update_last_generated(pool: &SqlitePool, channel_id: &str, timestamp: DateTime<Utc>) -> Result<()> {
+    sqlx::query("UPDATE output_channels SET last_generated = ?, skipped_from = NULL WHERE id = ?")
+        .bind(timestamp.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .bind(channel_id)
+        .execute(pool)
+        .await
+        .context("updating last_generated")?;
+
+    Ok(())
+}
+
+/// Record a scheduled run skipped for `min_items`: `last_generated` moves on so the scheduler
+/// waits for the next tick, and `skipped_from` keeps the start of the first skipped window.
+#[instrument(skip_all, fields(channel_id = %channel_id))]
+pub async fn skip_window(
+    pool: &SqlitePool,
+    channel_id: &str,
+    from: DateTime<Utc>,
+    timestamp: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query("UPDATE output_channels SET skipped_from = COALESCE(skipped_from, ?), last_generated = ? WHERE id = ?")
+        .bind(from.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .bind(timestamp.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .bind(channel_id)
+        .execute(pool)
+        .await
+        .context("recording skipped window")?;
+
+    Ok(())
+}
+
+/// Record a generation attempt.
+pub async fn insert_generation_run(pool: &SqlitePool, run: &GenerationRun, log: Option<&str>) -> Result<()> {
+    let log = log.map(compress::encode);
+    sqlx::query(
+        "INSERT INTO generation_runs (id, channel_id, started_at, finished_at, status, skip_reason, error,
+         item_count, article_id, attempts, exit_code, workspace, model, cost_usd, tokens, log, log_compressed)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&run.id)
+    .bind(&run.channel_id)
+    .bind(run.started_at.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .bind(run.finished_at.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .bind(&run.status)
+    .bind(&run.skip_reason)
+    .bind(&run.error)
+    .bind(run.item_count)
+    .bind(&run.article_id)
+    .bind(run.attempts)
+    .bind(run.exit_code)
+    .bind(&run.workspace)
+    .bind(&run.model)
+    .bind(run.cost_usd)
+    .bind(run.tokens)
+    .bind(&log)
+    .bind(log.as_ref().is_some_and(|l| l.is_compressed()))
+    .execute(pool)
+    .await
+    .context("inserting generation run")?;
+
+    Ok(())
+}
+
+/// Most recent generation runs first, of one channel or all of them.
+#[instrument(skip_all, fields(channel_id = ?channel_id))]
+pub async fn get_generation_runs(pool: &SqlitePool, channel_id: Option<&str>, limit: i64) -> Result<Vec<GenerationRun>> {
+    let runs = sqlx::query_as::<_, GenerationRun>(
+        "SELECT id, channel_id, started_at, finished_at, status, skip_reason, error, item_count, article_id,
+         attempts, exit_code, workspace, model, cost_usd, tokens
+         FROM generation_runs WHERE ? IS NULL OR channel_id = ?
+         ORDER BY started_at DESC LIMIT ?",
+    )
+    .bind(channel_id)
+    .bind(channel_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .context("querying generation runs")?;
+    Ok(runs)
+}
+
+/// The opencode log of a run: its own for failed runs, its article's for generated ones.
+/// `None` if the run doesn't exist or has no log.
+pub async fn get_generation_run_log(pool: &SqlitePool, run_id: &str) -> Result<Option<String>> {
+    let row: Option<(Option<StoredText>,)> = sqlx::query_as(
+        "SELECT COALESCE(r.log, a.generation_log) FROM generation_runs r
+         LEFT JOIN generated_articles a ON a.id = r.article_id WHERE r.id = ?",
+    )
+    .bind(run_id)
+    .fetch_optional(pool)
+    .await
+    .context("querying generation run log")?;
+    Ok(row
+        .and_then(|(log,)| log)
+        .map(String::from)
+        .filter(|log| !log.is_empty()))
+}
+
+/// Delete generation runs started before the cutoff. Returns the number deleted.
+pub async fn delete_old_generation_runs(pool: &SqlitePool, cutoff: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM generation_runs WHERE started_at < ?")
+        .bind(cutoff.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .execute(pool)
+        .await
+        .context("deleting old generation runs")?;
+    Ok(result.rows_affected())
+}
+
+/// Read a setting from the settings table.
+pub async fn get_setting(pool: &SqlitePool, key: &str) -> Result<Option<String>> {
+    let row: Option<(String,)> = sqlx::query_as("SELECT value FROM settings WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await
+        .context("reading setting")?;
+    Ok(row.map(|(v,)| v))
+}
+
+/// Upsert a setting in the settings table.
+pub async fn set_setting(pool: &SqlitePool, key: &str, value: &str) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO settings (key, value, updated_at) VALUES (?, ?, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+    )
+    .bind(key)
+    .bind(value)
+    .execute(pool)
+    .await
+    .context("upserting setting")?;
+    Ok(())
+}
+
+/// Update fetch state on a source: last_fetched_at, ETag, and Last-Modified.
+#[instrument(skip_all, fields(source_id = %source_id))]
+pub async fn update_source_fetch_state(
+    pool: &SqlitePool,
+    source_id: &str,
+    timestamp: DateTime<Utc>,
+    etag: Option<&str>,
+    last_modified: Option<&str>,
+) -> Result<()> {
+    sqlx::query("UPDATE sources SET last_fetched_at = ?, last_etag = ?, last_modified_header = ? WHERE id = ?")
+        .bind(timestamp.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .bind(etag)
+        .bind(last_modified)
+        .bind(source_id)
+        .execute(pool)
+        .await
+        .context("updating source fetch state")?;
+    Ok(())
+}
+
+/// Record a successful fetch: resets the failure streak and advances `last_item_at` to the
+/// newest item date of the fetch (never backwards).
+#[instrument(skip_all, fields(source_id = %source_id))]
+pub async fn record_fetch_success(
+    pool: &SqlitePool,
+    source_id: &str,
+    timestamp: DateTime<Utc>,
+    newest_item: Option<DateTime<Utc>>,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE sources SET consecutive_failures = 0, last_error = NULL, last_success_at = ?,
+         last_item_at = CASE WHEN ? > COALESCE(last_item_at, '') THEN ? ELSE last_item_at END
+         WHERE id = ?",
+    )
+    .bind(timestamp.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .bind(newest_item.map(|d| d.format("%Y-%m-%dT%H:%M:%SZ").to_string()))
+    .bind(newest_item.map(|d| d.format("%Y-%m-%dT%H:%M:%SZ").to_string()))
+    .bind(source_id)
+    .execute(pool)
+    .await
+    .context("recording fetch success")?;
+    Ok(())
+}
+
+/// Record a failed fetch: extends the failure streak and keeps the error message.
+#[instrument(skip_all, fields(source_id = %source_id))]
+pub async fn record_fetch_failure(pool: &SqlitePool, source_id: &str, error: &str) -> Result<()> {
+    sqlx::query("UPDATE sources SET consecutive_failures = consecutive_failures + 1, last_error = ? WHERE id = ?")
+        .bind(error)
+        .bind(source_id)
+        .execute(pool)
+        .await
+        .context("recording fetch failure")?;
+    Ok(())
+}
+
+/// Record the target of a permanent redirect; later fetches use it instead of `url`.
+#[instrument(skip_all, fields(source_id = %source_id))]
+pub async fn set_source_moved_url(pool: &SqlitePool, source_id: &str, moved_url: &str) -> Result<()> {
+    sqlx::query("UPDATE sources SET moved_url = ? WHERE id = ?")
+        .bind(moved_url)
+        .bind(source_id)
+        .execute(pool)
+        .await
+        .context("recording moved source URL")?;
+    Ok(())
+}
+
+/// Mark a source's feed as gone (410); the poller skips it until its URL changes or it is
+/// disabled and re-enabled in the config.
+#[instrument(skip_all, fields(source_id = %source_id))]
+pub async fn mark_source_gone(pool: &SqlitePool, source_id: &str, timestamp: DateTime<Utc>) -> Result<()> {
+    sqlx::query("UPDATE sources SET gone_at = ? WHERE id = ?")
+        .bind(timestamp.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .bind(source_id)
+        .execute(pool)
+        .await
+        .context("marking source gone")?;
+    Ok(())
+}
+
+/// Set (or clear, with `None`) the health status last alerted for a source.
+#[instrument(skip_all, fields(source_id = %source_id))]
+pub async fn set_health_alerted(pool: &SqlitePool, source_id: &str, status: Option<&str>) -> Result<()> {
+    sqlx::query("UPDATE sources SET health_alerted = ? WHERE id = ?")
+        .bind(status)
+        .bind(source_id)
+        .execute(pool)
+        .await
+        .context("updating source health alert state")?;
+    Ok(())
+}
+
+/// Delete content items older than the cutoff. Returns number of deleted rows.
+#[instrument(skip_all, fields(cutoff = %cutoff))]
+pub async fn delete_old_content_items(pool: &SqlitePool, cutoff: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM content_items WHERE ingested_at < ?")
+        .bind(cutoff.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .execute(pool)
+        .await
+        .context("deleting old content items")?;
+    Ok(result.rows_affected())
+}
+
+/// Items moved to the archive per transaction by `archive_old_content_items`.
+const ARCHIVE_BATCH_ROWS: i64 = 1000;
+
+/// Move content items ingested before the cutoff to `content_items_archive`, with their tags.
+/// Batched, so writers (the TG listener) aren't held up for the whole move. Returns the
+/// number of items archived.
+#[instrument(skip_all, fields(cutoff = %cutoff))]
+pub async fn archive_old_content_items(pool: &SqlitePool, cutoff: DateTime<Utc>) -> Result<u64> {
+    let cutoff = cutoff.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut archived = 0;
+    loop {
+        let mut tx = pool.begin().await.context("starting archive transaction")?;
+        let ids: Vec<String> = sqlx::query_scalar("SELECT id FROM content_items WHERE ingested_at < ? LIMIT ?")
+            .bind(&cutoff)
+            .bind(ARCHIVE_BATCH_ROWS)
+            .fetch_all(&mut *tx)
+            .await
+            .context("querying items to archive")?;
+        if ids.is_empty() {
+            break;
+        }
+        let ids_json = serde_json::to_string(&ids)?;
+        sqlx::query(
+            "INSERT OR REPLACE INTO content_items_archive
+                 (id, source_id, ingested_at, original_date, content_type, title, body, body_compressed, url, author,
+                  metadata, dedup_key, upstream_changed, deleted_upstream_at, tags, archived_at)
+             SELECT c.id, c.source_id, c.ingested_at, c.original_date, c.content_type, c.title, c.body,
+                    c.body_compressed, c.url, c.author, c.metadata, c.dedup_key, c.upstream_changed,
+                    c.deleted_upstream_at,
+                    (SELECT json_group_array(t.name) FROM content_item_tags it JOIN tags t ON t.id = it.tag_id
+                     WHERE it.content_item_id = c.id),
+                    ?
+             FROM content_items c WHERE c.id IN (SELECT value FROM json_each(?))",
+        )
+        .bind(&now)
+        .bind(&ids_json)
+        .execute(&mut *tx)
+        .await
+        .context("copying items to the archive")?;
+        sqlx::query("DELETE FROM content_items WHERE id IN (SELECT value FROM json_each(?))")
+            .bind(&ids_json)
+            .execute(&mut *tx)
+            .await
+            .context("deleting archived items")?;
+        tx.commit().await.context("committing archive batch")?;
+        archived += ids.len() as u64;
+    }
+    Ok(archived)
+}
+
+/// Delete generated articles generated before the cutoff. Returns the number of rows deleted.
+pub async fn delete_old_articles(pool: &SqlitePool, cutoff: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM generated_articles WHERE generated_at < ?")
+        .bind(cutoff.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .execute(pool)
+        .await
+        .context("deleting old generated articles")?;
+    Ok(result.rows_affected())
+}
+
+/// Delete all but the `keep` most recent articles of each output channel.
+pub async fn delete_excess_articles(pool: &SqlitePool, keep: u32) -> Result<u64> {
+    let result = sqlx::query(
+        "DELETE FROM generated_articles WHERE id IN (
+             SELECT id FROM (
+                 SELECT id, ROW_NUMBER() OVER (PARTITION BY output_channel_id ORDER BY generated_at DESC) AS rn
+                 FROM generated_articles
+             ) WHERE rn > ?
+         )",
+    )
+    .bind(keep as i64)
+    .execute(pool)
+    .await
+    .context("deleting excess generated articles")?;
+    Ok(result.rows_affected())
+}
+
+/// Clear the generation log of articles generated before the cutoff. The article itself is kept.
+pub async fn strip_old_generation_logs(pool: &SqlitePool, cutoff: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query(
+        "UPDATE generated_articles SET generation_log = '', log_compressed = 0
+             WHERE generated_at < ? AND generation_log != ''",
+    )
+    .bind(cutoff.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .execute(pool)
+    .await
+    .context("stripping old generation logs")?;
+    Ok(result.rows_affected())
+}
+
+/// IDs of articles that still have a generation log (log files of the others are swept).
+pub async fn get_article_ids_with_log(pool: &SqlitePool) -> Result<Vec<String>> {
+    let ids: Vec<(String,)> = sqlx::query_as("SELECT id FROM generated_articles WHERE generation_log != ''")
+        .fetch_all(pool)
+        .await
+        .context("querying articles with generation logs")?;
+    Ok(ids.into_iter().map(|(id,)| id).collect())
+}
+
+/// Rows rewritten per transaction by `recompress_column`.
+const RECOMPRESS_BATCH_ROWS: i64 = 500;
+
+/// What `recompress_column` changed.
+#[derive(Debug, Default)]
+pub struct RecompressStats {
+    pub rows: u64,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// Rewrite a compressible column (`content_items.body` / `body_compressed`,
+/// `generated_articles.generation_log` / `log_compressed`) the way new rows are written, or
+/// with `undo`, all plain. Walks the table by rowid, one transaction per batch, so it can run
+/// next to the daemon and be interrupted.
+pub async fn recompress_column(
+    pool: &SqlitePool,
+    table: &str,
+    column: &str,
+    flag: &str,
+    undo: bool,
+) -> Result<RecompressStats> {
+    let mut stats = RecompressStats::default();
+    let mut after_rowid = 0i64;
+    loop {
+        let rows: Vec<(i64, StoredText, bool, i64)> = sqlx::query_as(&format!(
+            "SELECT rowid, {column}, {flag}, length(CAST({column} AS BLOB)) FROM {table}
+             WHERE rowid > ? ORDER BY rowid LIMIT ?"
+        ))
+        .bind(after_rowid)
+        .bind(RECOMPRESS_BATCH_ROWS)
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("reading {table}.{column}"))?;
+        let Some(last) = rows.last() else {
+            break;
+        };
+        after_rowid = last.0;
+
+        let mut tx = pool.begin().await.context("starting recompress transaction")?;
+        for (rowid, StoredText(text), compressed, stored_len) in &rows {
+            let encoded = if undo {
+                compress::Encoded::Plain(text)
+            } else {
+                compress::encode(text)
+            };
+            if encoded.is_compressed() == *compressed {
+                continue;
+            }
+            stats.rows += 1;
+            stats.bytes_before += *stored_len as u64;
+            stats.bytes_after += encoded.stored_len() as u64;
+            sqlx::query(&format!("UPDATE {table} SET {column} = ?, {flag} = ? WHERE rowid = ?"))
+                .bind(&encoded)
+                .bind(encoded.is_compressed())
+                .bind(*rowid)
+                .execute(&mut *tx)
+                .await
+                .with_context(|| format!("rewriting {table}.{column}"))?;
+        }
+        tx.commit().await.context("committing recompressed rows")?;
+    }
+    Ok(stats)
+}
+
+/// Rebuild the database file, returning the space freed by deleted and shrunk rows to the
+/// filesystem.
+pub async fn vacuum(pool: &SqlitePool) -> Result<()> {
+    sqlx::query("VACUUM")
+        .execute(pool)
+        .await
+        .context("vacuuming database")?;
+    Ok(())
+}
+
+/// Check that the database answers a trivial query (health endpoint).
+pub async fn ping(pool: &SqlitePool) -> Result<()> {
+    sqlx::query("SELECT 1")
+        .execute(pool)
+        .await
+        .context("pinging database")?;
+    Ok(())
+}
+
+/// Pause or resume scheduled generation for a channel. Returns false if no channel has the slug.
+pub async fn set_channel_paused(pool: &SqlitePool, slug: &str, paused: bool) -> Result<bool> {
+    let result = sqlx::query("UPDATE output_channels SET paused = ? WHERE slug = ?")
+        .bind(paused)
+        .bind(slug)
+        .execute(pool)
+        .await
+        .context("updating channel pause state")?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// IDs of paused output channels.
+pub async fn get_paused_channel_ids(pool: &SqlitePool) -> Result<HashSet<String>> {
+    let rows: Vec<(String,)> = sqlx::query_as("SELECT id FROM output_channels WHERE paused = 1")
+        .fetch_all(pool)
+        .await
+        .context("querying paused channels")?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
+
+/// Claim a channel's generation for `instance` until `expires_at` (see docs/specs/daemon.md
+/// "Roles"). Fails while another instance holds an unexpired claim. `expected_last_generated`
+/// is what a scheduled run saw when it found the channel due (`Some(None)`: never generated);
+/// the claim also fails once `last_generated` moved on, so a run another instance already
+/// finished isn't repeated. On-demand runs pass `None`. Check and claim are one statement, so
+/// two instances can't both win.
+#[instrument(skip_all, fields(channel_id = %channel_id))]
+pub async fn claim_channel(
+    pool: &SqlitePool,
+    channel_id: &str,
+    instance: &str,
+    now: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    expected_last_generated: Option<Option<DateTime<Utc>>>,
+) -> Result<bool> {
+    let fmt = |t: DateTime<Utc>| t.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let result = sqlx::query(
+        "INSERT INTO generation_claims (channel_id, claimed_by, claimed_at, expires_at)
+         SELECT ?1, ?2, ?3, ?4
+         WHERE ?5 = 0 OR (SELECT last_generated FROM output_channels WHERE id = ?1) IS ?6
+         ON CONFLICT(channel_id) DO UPDATE SET
+             claimed_by = excluded.claimed_by, claimed_at = excluded.claimed_at, expires_at = excluded.expires_at
+         WHERE generation_claims.expires_at <= excluded.claimed_at",
+    )
+    .bind(channel_id)
+    .bind(instance)
+    .bind(fmt(now))
+    .bind(fmt(expires_at))
+    .bind(expected_last_generated.is_some())
+    .bind(expected_last_generated.flatten().map(fmt))
+    .execute(pool)
+    .await
+    .context("claiming channel generation")?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Whether any instance holds an unexpired generation claim (a generation is running).
+pub async fn any_generation_running(pool: &SqlitePool, now: DateTime<Utc>) -> Result<bool> {
+    let running: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM generation_claims WHERE expires_at > ?)")
+        .bind(now.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .fetch_one(pool)
+        .await
+        .context("checking generation claims")?;
+    Ok(running)
+}
+
+/// Extend this instance's claim on a channel while its generation runs.
+#[instrument(skip_all, fields(channel_id = %channel_id))]
+pub async fn renew_claim(pool: &SqlitePool, channel_id: &str, instance: &str, expires_at: DateTime<Utc>) -> Result<()> {
+    sqlx::query("UPDATE generation_claims SET expires_at = ? WHERE channel_id = ? AND claimed_by = ?")
+        .bind(expires_at.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .bind(channel_id)
+        .bind(instance)
+        .execute(pool)
+        .await
+        .context("renewing generation claim")?;
+    Ok(())
+}
+
+/// Drop this instance's claim on a channel once its generation finished.
+#[instrument(skip_all, fields(channel_id = %channel_id))]
+pub async fn release_claim(pool: &SqlitePool, channel_id: &str, instance: &str) -> Result<()> {
+    sqlx::query("DELETE FROM generation_claims WHERE channel_id = ? AND claimed_by = ?")
+        .bind(channel_id)
+        .bind(instance)
+        .execute(pool)
+        .await
+        .context("releasing generation claim")?;
+    Ok(())
+}
+
+/// Queue an on-demand generation for a worker instance. `window` is an explicit
+/// `(from, to)`, `since_secs` a window relative to when the worker picks it up; neither
+/// means the scheduled window.
+pub async fn queue_generation(
+    pool: &SqlitePool,
+    slug: &str,
+    window: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    since_secs: Option<i64>,
+    replaces_draft: Option<&str>,
+) -> Result<()> {
+    let fmt = |t: DateTime<Utc>| t.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    sqlx::query(
+        "INSERT INTO generation_requests (id, slug, window_from, window_to, since_secs, replaces_draft)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(slug)
+    .bind(window.map(|(from, _)| fmt(from)))
+    .bind(window.map(|(_, to)| fmt(to)))
+    .bind(since_secs)
+    .bind(replaces_draft)
+    .execute(pool)
+    .await
+    .context("queueing generation request")?;
+    Ok(())
+}
+
+/// Remove and return all queued generations, oldest first. Deleting and reading in one
+/// statement hands each request to exactly one worker.
+pub async fn take_queued_generations(pool: &SqlitePool) -> Result<Vec<QueuedGeneration>> {
+    let mut rows: Vec<QueuedGeneration> = sqlx::query_as(
+        "DELETE FROM generation_requests
+         RETURNING id, slug, window_from, window_to, since_secs, replaces_draft, requested_at",
+    )
+    .fetch_all(pool)
+    .await
+    .context("taking queued generation requests")?;
+    rows.sort_by_key(|r| r.requested_at);
+    Ok(rows)
+}
+
+/// Queued generations without taking them, oldest first.
+pub async fn get_queued_generations(pool: &SqlitePool) -> Result<Vec<QueuedGeneration>> {
+    let rows = sqlx::query_as(
+        "SELECT id, slug, window_from, window_to, since_secs, replaces_draft, requested_at
+         FROM generation_requests ORDER BY requested_at",
+    )
+    .fetch_all(pool)
+    .await
+    .context("querying queued generation requests")?;
+    Ok(rows)
+}
+
+/// Generations currently claimed (running or, for an expired claim, abandoned by a stopped
+/// instance): (channel slug, claimed_by, claimed_at, expires_at).
+pub async fn get_generation_claims(pool: &SqlitePool) -> Result<Vec<(String, String, DateTime<Utc>, DateTime<Utc>)>> {
+    let rows = sqlx::query_as(
+        "SELECT c.slug, g.claimed_by, g.claimed_at, g.expires_at
+         FROM generation_claims g JOIN output_channels c ON c.id = g.channel_id
+         ORDER BY g.claimed_at",
+    )
+    .fetch_all(pool)
+    .await
+    .context("querying generation claims")?;
+    Ok(rows)
+}
+
+/// Get the timestamp of the most recent generated article per output channel.
+pub async fn get_last_article_times(pool: &SqlitePool) -> Result<HashMap<String, DateTime<Utc>>> {
+    let rows: Vec<(String, DateTime<Utc>)> =
+        sqlx::query_as("SELECT output_channel_id, MAX(generated_at) FROM generated_articles GROUP BY output_channel_id")
+            .fetch_all(pool)
+            .await
+            .context("querying last article times")?;
+    Ok(rows.into_iter().collect())
+}
+
+/// Get recent published articles for an output channel (for Atom feed). Drafts and
+/// rejected articles are excluded.
+#[instrument(skip_all, fields(channel_id = %channel_id))]
+pub async fn get_recent_articles(pool: &SqlitePool, channel_id: &str, limit: i64) -> Result<Vec<GeneratedArticleRow>> {
+    let articles = sqlx::query_as::<_, GeneratedArticleRow>(&format!(
+        "SELECT {ARTICLE_COLUMNS} FROM generated_articles
+         WHERE output_channel_id = ? AND status = 'published'
+         ORDER BY generated_at DESC
+         LIMIT ?"
+    ))
+    .bind(channel_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .context("querying recent articles")?;
+    Ok(articles)
+}
+
+/// The channel's newest article (draft or published, not a breaking update) whose window
+/// shares at least `min_overlap` of the longer of it and `from`–`to`, if any.
+#[instrument(skip_all, fields(channel_id = %channel_id))]
+pub async fn find_article_for_window(
+    pool: &SqlitePool,
+    channel_id: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    min_overlap: f64,
+) -> Result<Option<GeneratedArticleRow>> {
+    let fmt = |t: DateTime<Utc>| t.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let overlapping = sqlx::query_as::<_, GeneratedArticleRow>(&format!(
+        "SELECT {ARTICLE_COLUMNS} FROM generated_articles
+         WHERE output_channel_id = ? AND status IN ('draft', 'published')
+           AND covers_from <= ? AND covers_to >= ?
+           AND json_extract(metadata, '$.breaking_update') IS NOT 1
+         ORDER BY generated_at DESC"
+    ))
+    .bind(channel_id)
+    .bind(fmt(to))
+    .bind(fmt(from))
+    .fetch_all(pool)
+    .await
+    .context("querying articles of the window")?;
+    Ok(overlapping
+        .into_iter()
+        .find(|a| window_overlap((a.covers_from, a.covers_to), (from, to)) >= min_overlap))
+}
+
+/// Length of the intersection of two windows over the length of the longer one: 1.0 for the
+/// same window, 0.0 for disjoint ones. Two empty windows at the same instant count as the same.
+fn window_overlap(a: (DateTime<Utc>, DateTime<Utc>), b: (DateTime<Utc>, DateTime<Utc>)) -> f64 {
+    let shared = (a.1.min(b.1) - a.0.max(b.0)).num_seconds();
+    let longer = (a.1 - a.0).max(b.1 - b.0).num_seconds();
+    match (shared, longer) {
+        (s, _) if s < 0 => 0.0,
+        (_, 0) => 1.0,
+        (s, l) => s as f64 / l as f64,
+    }
+}
+
+/// Get all enabled output channels.
+pub async fn get_all_enabled_channels(pool: &SqlitePool) -> Result<Vec<OutputChannel>> {
+    let channels = sqlx::query_as::<_, OutputChannel>(
+        "SELECT id, name, slug, schedule, prompt, model, language, enabled, last_generated, skipped_from
+         FROM output_channels WHERE enabled = 1",
+    )
+    .fetch_all(pool)
+    .await
+    .context("querying enabled output channels")?;
+    Ok(channels)
+}
+
+/// Get all output channels, enabled or not, by slug.
+pub async fn get_all_channels(pool: &SqlitePool) -> Result<Vec<OutputChannel>> {
+    let channels = sqlx::query_as::<_, OutputChannel>(
+        "SELECT id, name, slug, schedule, prompt, model, language, enabled, last_generated, skipped_from
+         FROM output_channels ORDER BY slug",
+    )
+    .fetch_all(pool)
+    .await
+    .context("querying output channels")?;
+    Ok(channels)
+}
+
+/// Get articles of any status, newest first, optionally only one channel's or one status.
+#[instrument(skip_all, fields(channel_id = ?channel_id))]
+pub async fn list_articles(
+    pool: &SqlitePool,
+    channel_id: Option<&str>,
+    status: Option<&str>,
+    limit: i64,
+) -> Result<Vec<GeneratedArticleRow>> {
+    let articles = sqlx::query_as::<_, GeneratedArticleRow>(&list_articles_sql(channel_id.is_some()))
+        .bind(channel_id)
+        .bind(status)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+        .context("listing articles")?;
+    Ok(articles)
+}
+
+/// `list_articles`' query; parameters are the channel (`?1`, left out without `by_channel`),
+/// the status and the limit. The channel condition is dropped rather than made optional: a
+/// `?1 IS NULL OR` condition can use no index, while this way a channel's listing reads
+/// `idx_generated_articles_channel` and the full one walks `idx_generated_articles_generated`.
+fn list_articles_sql(by_channel: bool) -> String {
+    let channel = if by_channel { "output_channel_id = ?1 AND " } else { "" };
+    format!(
+        "SELECT {ARTICLE_COLUMNS} FROM generated_articles
+         WHERE {channel}(?2 IS NULL OR status = ?2)
+         ORDER BY generated_at DESC
+         LIMIT ?3"
+    )
+}
+
+/// Get a single generated article by its UUID (any status).
+pub async fn get_article_by_id(pool: &SqlitePool, article_id: &str) -> Result<Option<GeneratedArticleRow>> {
+    let article = sqlx::query_as::<_, GeneratedArticleRow>(&format!(
+        "SELECT {ARTICLE_COLUMNS} FROM generated_articles WHERE id = ?"
+    ))
+    .bind(article_id)
+    .fetch_optional(pool)
+    .await
+    .context("querying article by ID")?;
+    Ok(article)
+}
+
+/// Get all articles awaiting approval, newest first.
+pub async fn get_draft_articles(pool: &SqlitePool) -> Result<Vec<GeneratedArticleRow>> {
+    let articles = sqlx::query_as::<_, GeneratedArticleRow>(&format!(
+        "SELECT {ARTICLE_COLUMNS} FROM generated_articles
+         WHERE status = 'draft'
+         ORDER BY generated_at DESC"
+    ))
+    .fetch_all(pool)
+    .await
+    .context("querying draft articles")?;
+    Ok(articles)
+}
+
+/// Move a draft to `published` or `rejected`. Returns false if the article isn't a draft
+/// (already reviewed, or doesn't exist).
+pub async fn review_draft(pool: &SqlitePool, article_id: &str, status: &str) -> Result<bool> {
+    let result = sqlx::query("UPDATE generated_articles SET status = ? WHERE id = ? AND status = 'draft'")
+        .bind(status)
+        .bind(article_id)
+        .execute(pool)
+        .await
+        .context("updating article status")?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Mark a published article read (`read_at` set) or unread (`None`). Returns false if no
+/// published article has that ID.
+pub async fn set_article_read(pool: &SqlitePool, article_id: &str, read_at: Option<DateTime<Utc>>) -> Result<bool> {
+    let result = sqlx::query("UPDATE generated_articles SET read_at = ? WHERE id = ? AND status = 'published'")
+        .bind(read_at.map(|t| t.format("%Y-%m-%dT%H:%M:%SZ").to_string()))
+        .bind(article_id)
+        .execute(pool)
+        .await
+        .context("updating article read state")?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Mark every unread published article read, or only one channel's. Returns the number marked.
+#[instrument(skip_all, fields(channel_id = ?channel_id))]
+pub async fn mark_all_articles_read(pool: &SqlitePool, channel_id: Option<&str>, now: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query(
+        "UPDATE generated_articles SET read_at = ?
+         WHERE read_at IS NULL AND status = 'published' AND (? IS NULL OR output_channel_id = ?)",
+    )
+    .bind(now.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .bind(channel_id)
+    .bind(channel_id)
+    .execute(pool)
+    .await
+    .context("marking articles read")?;
+    Ok(result.rows_affected())
+}
+
+/// Number of unread published articles per output channel (channels with none are absent).
+pub async fn get_unread_counts(pool: &SqlitePool) -> Result<HashMap<String, i64>> {
+    let rows: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT output_channel_id, COUNT(*) FROM generated_articles
+         WHERE read_at IS NULL AND status = 'published'
+         GROUP BY output_channel_id",
+    )
+    .fetch_all(pool)
+    .await
+    .context("querying unread counts")?;
+    Ok(rows.into_iter().collect())
+}
+
+/// Store a feedback note for an article. The article title is copied so the note stays
+/// meaningful after retention cleanup deletes the article.
+pub async fn insert_article_feedback(pool: &SqlitePool, article: &GeneratedArticleRow, note: &str) -> Result<String> {
+    let id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO article_feedback (id, output_channel_id, article_id, article_title, note, created_at)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(&article.output_channel_id)
+    .bind(&article.id)
+    .bind(&article.title)
+    .bind(note)
+    .bind(Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .execute(pool)
+    .await
+    .context("inserting article feedback")?;
+    Ok(id)
+}
+
+/// Get the most recent feedback notes for an output channel, newest first.
+#[instrument(skip_all, fields(channel_id = %channel_id))]
+pub async fn get_recent_feedback(pool: &SqlitePool, channel_id: &str, limit: i64) -> Result<Vec<ArticleFeedback>> {
+    let feedback = sqlx::query_as::<_, ArticleFeedback>(
+        "SELECT id, output_channel_id, article_id, article_title, note, created_at
+         FROM article_feedback
+         WHERE output_channel_id = ?
+         ORDER BY created_at DESC
+         LIMIT ?",
+    )
+    .bind(channel_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .context("querying recent feedback")?;
+    Ok(feedback)
+}
+
+/// `token_name` of requests made with the main feed token in the access log.
+pub const MAIN_FEED_TOKEN_NAME: &str = "(feed_token)";
+
+/// Feed token columns, with the scope's channel slugs aggregated (see `FeedToken`).
+const FEED_TOKEN_SELECT: &str = "SELECT t.id, t.name, t.all_channels,
+        (SELECT group_concat(c.slug, ',') FROM feed_token_channels tc
+         JOIN output_channels c ON c.id = tc.output_channel_id WHERE tc.token_id = t.id) AS channel_slugs,
+        t.created_at, t.last_used_at, t.revoked_at
+     FROM feed_tokens t";
+
+/// Store a named feed token by its hash. `channel_ids` is the scope; `None` allows every channel.
+pub async fn insert_feed_token(
+    pool: &SqlitePool,
+    name: &str,
+    token_hash: &str,
+    channel_ids: Option<&[String]>,
+) -> Result<String> {
+    let id = Uuid::new_v4().to_string();
+    let mut tx = pool.begin().await.context("starting feed token transaction")?;
+    sqlx::query("INSERT INTO feed_tokens (id, name, token_hash, all_channels, created_at) VALUES (?, ?, ?, ?, ?)")
+        .bind(&id)
+        .bind(name)
+        .bind(token_hash)
+        .bind(channel_ids.is_none())
+        .bind(Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .execute(&mut *tx)
+        .await
+        .context("inserting feed token")?;
+    for channel_id in channel_ids.unwrap_or_default() {
+        sqlx::query("INSERT INTO feed_token_channels (token_id, output_channel_id) VALUES (?, ?)")
+            .bind(&id)
+            .bind(channel_id)
+            .execute(&mut *tx)
+            .await
+            .context("inserting feed token channel")?;
+    }
+    tx.commit().await.context("committing feed token")?;
+    Ok(id)
+}
+
+/// All named feed tokens, revoked ones included, oldest first.
+pub async fn get_feed_tokens(pool: &SqlitePool) -> Result<Vec<FeedToken>> {
+    let tokens = sqlx::query_as::<_, FeedToken>(&format!("{FEED_TOKEN_SELECT} ORDER BY t.created_at, t.name"))
+        .fetch_all(pool)
+        .await
+        .context("querying feed tokens")?;
+    Ok(tokens)
+}
+
+/// The unrevoked feed token with this hash.
+pub async fn get_active_feed_token(pool: &SqlitePool, token_hash: &str) -> Result<Option<FeedToken>> {
+    let token = sqlx::query_as::<_, FeedToken>(&format!(
+        "{FEED_TOKEN_SELECT} WHERE t.token_hash = ? AND t.revoked_at IS NULL"
+    ))
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await
+    .context("querying feed token")?;
+    Ok(token)
+}
+
+/// Whether a feed token's scope includes the channel.
+#[instrument(skip_all, fields(channel_id = %channel_id))]
+pub async fn feed_token_allows(pool: &SqlitePool, token: &FeedToken, channel_id: &str) -> Result<bool> {
+    if token.all_channels {
+        return Ok(true);
+    }
+    let row: Option<(String,)> =
+        sqlx::query_as("SELECT token_id FROM feed_token_channels WHERE token_id = ? AND output_channel_id = ?")
+            .bind(&token.id)
+            .bind(channel_id)
+            .fetch_optional(pool)
+            .await
+            .context("querying feed token scope")?;
+    Ok(row.is_some())
+}
+
+/// Revoke a feed token by name. Returns false if there's no unrevoked token with that name.
+pub async fn revoke_feed_token(pool: &SqlitePool, name: &str, now: DateTime<Utc>) -> Result<bool> {
+    let result = sqlx::query("UPDATE feed_tokens SET revoked_at = ? WHERE name = ? AND revoked_at IS NULL")
+        .bind(now.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .bind(name)
+        .execute(pool)
+        .await
+        .context("revoking feed token")?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Record a feed request in the access log. `token` is `None` for the main feed token; a
+/// named token's `last_used_at` is updated too.
+pub async fn record_feed_access(
+    pool: &SqlitePool,
+    token: Option<&FeedToken>,
+    channel_slug: &str,
+    status: u16,
+    user_agent: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let now = now.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    sqlx::query(
+        "INSERT INTO feed_access_log (token_id, token_name, channel_slug, status, user_agent, accessed_at)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(token.map(|t| &t.id))
+    .bind(token.map_or(MAIN_FEED_TOKEN_NAME, |t| t.name.as_str()))
+    .bind(channel_slug)
+    .bind(status)
+    .bind(user_agent)
+    .bind(&now)
+    .execute(pool)
+    .await
+    .context("recording feed access")?;
+    if let Some(token) = token {
+        sqlx::query("UPDATE feed_tokens SET last_used_at = ? WHERE id = ?")
+            .bind(&now)
+            .bind(&token.id)
+            .execute(pool)
+            .await
+            .context("updating feed token last use")?;
+    }
+    Ok(())
+}
+
+/// The most recent feed accesses, newest first, optionally of one token.
+pub async fn get_feed_access_log(pool: &SqlitePool, token_name: Option<&str>, limit: i64) -> Result<Vec<FeedAccess>> {
+    let entries = sqlx::query_as::<_, FeedAccess>(
+        "SELECT token_name, channel_slug, status, user_agent, accessed_at
+         FROM feed_access_log
+         WHERE ? IS NULL OR token_name = ?
+         ORDER BY id DESC
+         LIMIT ?",
+    )
+    .bind(token_name)
+    .bind(token_name)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .context("querying feed access log")?;
+    Ok(entries)
+}
+
+/// Delete feed access log entries older than `cutoff`. Returns the number deleted.
+pub async fn delete_old_feed_access_log(pool: &SqlitePool, cutoff: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM feed_access_log WHERE accessed_at < ?")
+        .bind(cutoff.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .execute(pool)
+        .await
+        .context("deleting old feed access log entries")?;
+    Ok(result.rows_affected())
+}
+
+/// Backfill progress of one chat of a Telegram source.
+#[instrument(skip_all, fields(source_id = %source_id))]
+pub async fn get_backfill_progress(
+    pool: &SqlitePool,
+    source_id: &str,
+    peer_id: i64,
+) -> Result<Option<TgBackfillProgress>> {
+    let progress = sqlx::query_as::<_, TgBackfillProgress>(
+        "SELECT oldest_message_id, covered_since FROM tg_backfill_progress WHERE source_id = ? AND peer_id = ?",
+    )
+    .bind(source_id)
+    .bind(peer_id)
+    .fetch_optional(pool)
+    .await
+    .context("querying backfill progress")?;
+    Ok(progress)
+}
+
+/// Record the oldest message a backfill has stored in a chat.
+#[instrument(skip_all, fields(source_id = %source_id))]
+pub async fn save_backfill_progress(
+    pool: &SqlitePool,
+    source_id: &str,
+    peer_id: i64,
+    oldest_message_id: i32,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO tg_backfill_progress (source_id, peer_id, oldest_message_id, updated_at) VALUES (?, ?, ?, ?)
+         ON CONFLICT(source_id, peer_id) DO UPDATE SET
+             oldest_message_id = excluded.oldest_message_id, updated_at = excluded.updated_at",
+    )
+    .bind(source_id)
+    .bind(peer_id)
+    .bind(oldest_message_id)
+    .bind(Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .execute(pool)
+    .await
+    .context("saving backfill progress")?;
+    Ok(())
+}
+
+/// Record that a chat's history is complete back to `since`. An earlier coverage is kept.
+#[instrument(skip_all, fields(source_id = %source_id))]
+pub async fn complete_backfill(pool: &SqlitePool, source_id: &str, peer_id: i64, since: DateTime<Utc>) -> Result<()> {
+    let since = since.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    sqlx::query(
+        "INSERT INTO tg_backfill_progress (source_id, peer_id, covered_since, updated_at) VALUES (?, ?, ?, ?)
+         ON CONFLICT(source_id, peer_id) DO UPDATE SET
+             covered_since = CASE WHEN covered_since IS NULL OR covered_since > excluded.covered_since
+                 THEN excluded.covered_since ELSE covered_since END,
+             updated_at = excluded.updated_at",
+    )
+    .bind(source_id)
+    .bind(peer_id)
+    .bind(&since)
+    .bind(Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .execute(pool)
+    .await
+    .context("completing backfill")?;
+    Ok(())
+}
+
+/// Get cached embeddings for the given content items under one embedding model.
+pub async fn get_item_embeddings(
+    pool: &SqlitePool,
+    item_ids: &[String],
+    model: &str,
+) -> Result<HashMap<String, Vec<f32>>> {
+    let mut result = HashMap::new();
+    // Chunk to stay under SQLite's bound parameter limit
+    for chunk in item_ids.chunks(500) {
+        let placeholders: Vec<&str> = chunk.iter().map(|_| "?").collect();
+        let query = format!(
+            "SELECT content_item_id, embedding FROM content_item_embeddings
+             WHERE model = ? AND content_item_id IN ({})",
+            placeholders.join(", ")
+        );
+        let mut q = sqlx::query_as::<_, (String, Vec<u8>)>(&query).bind(model);
+        for id in chunk {
+            q = q.bind(id);
+        }
+        let rows = q.fetch_all(pool).await.context("querying item embeddings")?;
+        for (id, blob) in rows {
+            let vector = blob
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect();
+            result.insert(id, vector);
+        }
+    }
+    Ok(result)
+}
+
+/// Cache an embedding for a content item (little-endian f32 blob). Archived items aren't
+/// cached: they're rarely read again.
+pub async fn insert_item_embedding(pool: &SqlitePool, item_id: &str, model: &str, embedding: &[f32]) -> Result<()> {
+    let blob: Vec<u8> = embedding.iter().flat_map(|v| v.to_le_bytes()).collect();
+    sqlx::query(
+        "INSERT OR REPLACE INTO content_item_embeddings (content_item_id, model, embedding)
+         SELECT ?, ?, ? WHERE EXISTS (SELECT 1 FROM content_items WHERE id = ?)",
+    )
+    .bind(item_id)
+    .bind(model)
+    .bind(blob)
+    .bind(item_id)
+    .execute(pool)
+    .await
+    .context("inserting item embedding")?;
+    Ok(())
+}
+
+/// Content items the tagging pass hasn't processed yet, most recently ingested first, except
+/// those that failed `max_failures` times.
+pub async fn get_untagged_items(pool: &SqlitePool, limit: u32, max_failures: i64) -> Result<Vec<ContentItem>> {
+    let items = sqlx::query_as::<_, ContentItem>(
+        "SELECT id, source_id, ingested_at, original_date, content_type, title, body, url, author, metadata, dedup_key, upstream_changed
+         FROM content_items
+         WHERE tagged_at IS NULL AND tagging_failures < ?
+         ORDER BY ingested_at DESC
+         LIMIT ?",
+    )
+    .bind(max_failures)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .context("querying untagged content items")?;
+    Ok(items)
+}
+
+/// Count a failed tagging attempt for each of the items.
+pub async fn record_tagging_failures(pool: &SqlitePool, item_ids: &[String]) -> Result<()> {
+    if item_ids.is_empty() {
+        return Ok(());
+    }
+    sqlx::query(
+        "UPDATE content_items SET tagging_failures = tagging_failures + 1 WHERE id IN (SELECT value FROM json_each(?))",
+    )
+    .bind(serde_json::to_string(item_ids)?)
+    .execute(pool)
+    .await
+    .context("recording tagging failures")?;
+    Ok(())
+}
+
+/// The subset of `item_ids` the tagging pass hasn't processed yet.
+pub async fn get_untagged_item_ids(pool: &SqlitePool, item_ids: &[String]) -> Result<HashSet<String>> {
+    let mut result = HashSet::new();
+    // Chunk to stay under SQLite's bound parameter limit
+    for chunk in item_ids.chunks(500) {
+        let placeholders: Vec<&str> = chunk.iter().map(|_| "?").collect();
+        let query = format!(
+            "SELECT id FROM content_items WHERE tagged_at IS NULL AND id IN ({})",
+            placeholders.join(", ")
+        );
+        let mut q = sqlx::query_scalar::<_, String>(&query);
+        for id in chunk {
+            q = q.bind(id);
+        }
+        result.extend(q.fetch_all(pool).await.context("querying untagged item IDs")?);
+    }
+    Ok(result)
+}
+
+/// Tag names of the given content items, keyed by item ID, archived items included.
+/// Untagged items are absent.
+pub async fn get_item_tags(pool: &SqlitePool, item_ids: &[String]) -> Result<HashMap<String, Vec<String>>> {
+    let mut result: HashMap<String, Vec<String>> = HashMap::new();
+    for chunk in item_ids.chunks(250) {
+        let placeholders = chunk.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+        let query = format!(
+            "SELECT it.content_item_id, t.name AS tag FROM content_item_tags it
+             JOIN tags t ON t.id = it.tag_id
+             WHERE it.content_item_id IN ({placeholders})
+             UNION ALL
+             SELECT a.id, j.value FROM content_items_archive a, json_each(a.tags) j
+             WHERE a.id IN ({placeholders})
+             ORDER BY tag"
+        );
+        let mut q = sqlx::query_as::<_, (String, String)>(&query);
+        for id in chunk.iter().chain(chunk) {
+            q = q.bind(id);
+        }
+        for (item_id, tag) in q.fetch_all(pool).await.context("querying item tags")? {
+            result.entry(item_id).or_default().push(tag);
+        }
+    }
+    Ok(result)
+}
+
+/// Store the tagging pass result for a batch of items in one transaction: create missing
+/// tags, link them, and mark every item as tagged (including items with no tags).
+pub async fn set_item_tags(pool: &SqlitePool, tagged: &[(String, Vec<String>)]) -> Result<()> {
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut tx = pool.begin().await.context("starting item tag transaction")?;
+    for (item_id, tags) in tagged {
+        for tag in tags {
+            sqlx::query("INSERT INTO tags (id, name) VALUES (?, ?) ON CONFLICT(name) DO NOTHING")
+                .bind(Uuid::new_v4().to_string())
+                .bind(tag)
+                .execute(&mut *tx)
+                .await
+                .context("inserting tag")?;
+            sqlx::query(
+                "INSERT OR IGNORE INTO content_item_tags (content_item_id, tag_id)
+                 SELECT ?, id FROM tags WHERE name = ?",
+            )
+            .bind(item_id)
+            .bind(tag)
+            .execute(&mut *tx)
+            .await
+            .context("linking item tag")?;
+        }
+        sqlx::query("UPDATE content_items SET tagged_at = ? WHERE id = ?")
+            .bind(&now)
+            .bind(item_id)
+            .execute(&mut *tx)
+            .await
+            .context("marking item tagged")?;
+    }
+    tx.commit().await.context("committing item tags")?;
+    Ok(())
+}
+
+/// Get cached page fetches for the given URLs, keyed by URL.
+pub async fn get_fetched_pages(pool: &SqlitePool, urls: &[String]) -> Result<HashMap<String, FetchedPage>> {
+    let mut result = HashMap::new();
+    // Chunk to stay under SQLite's bound parameter limit
+    for chunk in urls.chunks(500) {
+        let placeholders: Vec<&str> = chunk.iter().map(|_| "?").collect();
+        let query = format!(
+            "SELECT url, fetched_at, status, title, text, error FROM fetched_pages WHERE url IN ({})",
+            placeholders.join(", ")
+        );
+        let mut q = sqlx::query_as::<_, FetchedPage>(&query);
+        for url in chunk {
+            q = q.bind(url);
+        }
+        let rows = q.fetch_all(pool).await.context("querying fetched pages")?;
+        result.extend(rows.into_iter().map(|page| (page.url.clone(), page)));
+    }
+    Ok(result)
+}
+
+/// Cache the outcome of a page fetch, replacing any earlier entry for the URL.
+pub async fn upsert_fetched_page(pool: &SqlitePool, page: &FetchedPage) -> Result<()> {
+    sqlx::query(
+        "INSERT OR REPLACE INTO fetched_pages (url, fetched_at, status, title, text, error)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&page.url)
+    .bind(page.fetched_at.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .bind(&page.status)
+    .bind(&page.title)
+    .bind(&page.text)
+    .bind(&page.error)
+    .execute(pool)
+    .await
+    .context("storing fetched page")?;
+    Ok(())
+}
+
+/// Delete cached pages fetched before the cutoff. Returns the number of rows deleted.
+pub async fn delete_old_fetched_pages(pool: &SqlitePool, cutoff: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM fetched_pages WHERE fetched_at < ?")
+        .bind(cutoff.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .execute(pool)
+        .await
+        .context("deleting old fetched pages")?;
+    Ok(result.rows_affected())
+}
+
+/// Store a downloaded Telegram photo under its media ID.
+pub async fn insert_tg_media(pool: &SqlitePool, id: &str, chat_id: i64, message_id: i32, data: &[u8]) -> Result<()> {
+    sqlx::query("INSERT OR IGNORE INTO tg_media (id, chat_id, message_id, data, downloaded_at) VALUES (?, ?, ?, ?, ?)")
+        .bind(id)
+        .bind(chat_id)
+        .bind(message_id)
+        .bind(data)
+        .bind(Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .execute(pool)
+        .await
+        .context("storing TG media")?;
+    Ok(())
+}
+
+/// A downloaded Telegram photo, `None` if there is none with this ID.
+pub async fn get_tg_media(pool: &SqlitePool, id: &str) -> Result<Option<Vec<u8>>> {
+    sqlx::query_scalar("SELECT data FROM tg_media WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .context("querying TG media")
+}
+
+/// Which of these media IDs have been downloaded.
+pub async fn get_downloaded_media_ids(pool: &SqlitePool, ids: &[String]) -> Result<HashSet<String>> {
+    let ids_json = serde_json::to_string(ids).context("encoding media IDs")?;
+    let downloaded: Vec<String> =
+        sqlx::query_scalar("SELECT id FROM tg_media WHERE id IN (SELECT value FROM json_each(?))")
+            .bind(ids_json)
+            .fetch_all(pool)
+            .await
+            .context("querying downloaded TG media")?;
+    Ok(downloaded.into_iter().collect())
+}
+
+/// Delete media downloaded before the cutoff that no stored article embeds. Returns the number
+/// of rows deleted.
+pub async fn delete_old_tg_media(pool: &SqlitePool, cutoff: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query(
+        "DELETE FROM tg_media WHERE downloaded_at < ?
+         AND NOT EXISTS (SELECT 1 FROM generated_articles a WHERE instr(a.body_markdown, '/media/' || tg_media.id) > 0)",
+    )
+    .bind(cutoff.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .execute(pool)
+    .await
+    .context("deleting old TG media")?;
+    Ok(result.rows_affected())
+}
+
+/// Record updates received with `[telegram].debug_updates`, in one transaction.
+pub async fn insert_tg_debug_updates(pool: &SqlitePool, updates: &[TgDebugUpdate]) -> Result<()> {
+    let mut tx = pool.begin().await.context("starting debug update transaction")?;
+    for update in updates {
+        sqlx::query(
+            "INSERT INTO tg_debug_updates (account, received_at, container, update_type, channel_id, pts,
+             pts_count, message_id)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&update.account)
+        .bind(update.received_at.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .bind(&update.container)
+        .bind(&update.update_type)
+        .bind(update.channel_id)
+        .bind(update.pts)
+        .bind(update.pts_count)
+        .bind(update.message_id)
+        .execute(&mut *tx)
+        .await
+        .context("inserting debug update")?;
+    }
+    tx.commit().await.context("committing debug updates")?;
+    Ok(())
+}
+
+/// Updates an account received since `since`, in the order they arrived.
+pub async fn get_tg_debug_updates(pool: &SqlitePool, account: &str, since: DateTime<Utc>) -> Result<Vec<TgDebugUpdate>> {
+    let updates = sqlx::query_as::<_, TgDebugUpdate>(
+        "SELECT account, received_at, container, update_type, channel_id, pts, pts_count, message_id
+         FROM tg_debug_updates WHERE account = ? AND received_at >= ? ORDER BY id",
+    )
+    .bind(account)
+    .bind(since.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .fetch_all(pool)
+    .await
+    .context("querying debug updates")?;
+    Ok(updates)
+}
+
+/// Delete recorded debug updates older than the cutoff. Returns the number deleted.
+pub async fn delete_old_tg_debug_updates(pool: &SqlitePool, cutoff: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM tg_debug_updates WHERE received_at < ?")
+        .bind(cutoff.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .execute(pool)
+        .await
+        .context("deleting old debug updates")?;
+    Ok(result.rows_affected())
+}
+
+/// Dedup keys of stored items starting with `prefix`.
+pub async fn get_dedup_keys_with_prefix(pool: &SqlitePool, prefix: &str) -> Result<Vec<String>> {
+    let escaped = prefix.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    let keys = sqlx::query_scalar("SELECT dedup_key FROM content_items WHERE dedup_key LIKE ? ESCAPE '\\'")
+        .bind(format!("{escaped}%"))
+        .fetch_all(pool)
+        .await
+        .context("querying dedup keys")?;
+    Ok(keys)
 }
 
 /// Get all enabled sources.
@@ -500,11 +2545,98 @@ pub async fn get_all_enabled_sources(pool: &SqlitePool) -> Result<Vec<Source>> {
     Ok(sources)
 }
 
+/// Get all sources, enabled or not, by name.
+pub async fn get_all_sources(pool: &SqlitePool) -> Result<Vec<Source>> {
+    let query = format!("SELECT {SOURCE_COLUMNS} FROM sources ORDER BY name");
+    let sources = sqlx::query_as::<_, Source>(&query)
+        .fetch_all(pool)
+        .await
+        .context("querying sources")?;
+    Ok(sources)
+}
+
+/// Items ingested since `now - days` per source and day: (source_id, days ago, count), where
+/// day 0 is the last 24 hours.
+pub async fn get_item_activity(pool: &SqlitePool, now: DateTime<Utc>, days: i64) -> Result<Vec<(String, i64, i64)>> {
+    let now_str = now.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let since = (now - chrono::Duration::days(days))
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string();
+    sqlx::query_as(
+        "SELECT source_id, CAST(julianday(?) - julianday(ingested_at) AS INTEGER) AS age, COUNT(*)
+         FROM content_items WHERE ingested_at >= ?
+         GROUP BY source_id, age",
+    )
+    .bind(&now_str)
+    .bind(&since)
+    .fetch_all(pool)
+    .await
+    .context("querying item activity")
+}
+
+/// Items ingested between `from` and `to` per source: (source name, count), busiest first.
+#[instrument(skip_all, fields(from = %from, to = %to))]
+pub async fn get_ingested_counts(
+    pool: &SqlitePool,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<(String, i64)>> {
+    sqlx::query_as(
+        "SELECT s.name, COUNT(*) AS n FROM content_items c JOIN sources s ON s.id = c.source_id
+         WHERE c.ingested_at >= ? AND c.ingested_at < ?
+         GROUP BY s.id ORDER BY n DESC, s.name",
+    )
+    .bind(from.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .bind(to.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .fetch_all(pool)
+    .await
+    .context("querying ingested item counts")
+}
+
+/// Generation runs started between `from` and `to`, per channel by name.
+#[instrument(skip_all, fields(from = %from, to = %to))]
+pub async fn get_run_stats(pool: &SqlitePool, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<ChannelRunStats>> {
+    sqlx::query_as::<_, ChannelRunStats>(
+        "SELECT o.name AS channel, COUNT(*) AS runs,
+         SUM(r.status = 'generated') AS generated, SUM(r.status = 'skipped') AS skipped,
+         SUM(r.status = 'failed') AS failed, SUM(r.cost_usd) AS cost_usd, SUM(r.tokens) AS tokens
+         FROM generation_runs r JOIN output_channels o ON o.id = r.channel_id
+         WHERE r.started_at >= ? AND r.started_at < ?
+         GROUP BY o.id ORDER BY o.name",
+    )
+    .bind(from.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .bind(to.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .fetch_all(pool)
+    .await
+    .context("querying generation run stats")
+}
+
+/// Errors of the runs that failed between `from` and `to`: (channel name, error), oldest first.
+#[instrument(skip_all, fields(from = %from, to = %to))]
+pub async fn get_failed_runs(
+    pool: &SqlitePool,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<(String, Option<String>)>> {
+    sqlx::query_as(
+        "SELECT o.name, r.error FROM generation_runs r JOIN output_channels o ON o.id = r.channel_id
+         WHERE r.status = 'failed' AND r.started_at >= ? AND r.started_at < ?
+         ORDER BY r.started_at",
+    )
+    .bind(from.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .bind(to.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+    .fetch_all(pool)
+    .await
+    .context("querying failed generation runs")
+}
+
 // ── Telegram-specific queries ──────────────────────────────────────────
 
-/// Get enabled sources where type starts with "telegram_".
+/// Get enabled sources read through a Telegram account (type "telegram_*", except bot sources).
 pub async fn get_tg_sources(pool: &SqlitePool) -> Result<Vec<Source>> {
-    let query = format!("SELECT {SOURCE_COLUMNS} FROM sources WHERE enabled = 1 AND source_type LIKE 'telegram_%'");
+    let query = format!(
+        "SELECT {SOURCE_COLUMNS} FROM sources WHERE enabled = 1 AND source_type LIKE 'telegram_%' AND source_type != 'telegram_bot'"
+    );
     let sources = sqlx::query_as::<_, Source>(&query)
         .fetch_all(pool)
         .await
@@ -512,7 +2644,66 @@ pub async fn get_tg_sources(pool: &SqlitePool) -> Result<Vec<Source>> {
     Ok(sources)
 }
 
+/// Delete a source's items (archived ones too) and forget what fetching it learned: cache validators, health,
+/// Telegram resolution (a `tg_id` resolved from `tg_username`, folder channels) and backfill
+/// progress. A recorded permanent redirect is kept. Returns the number of items deleted.
+#[instrument(skip_all, fields(source_id = %source_id))]
+pub async fn reset_source(pool: &SqlitePool, source_id: &str) -> Result<u64> {
+    let mut tx = pool.begin().await.context("starting source reset transaction")?;
+    let deleted = sqlx::query("DELETE FROM content_items WHERE source_id = ?")
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await
+        .context("deleting source items")?
+        .rows_affected();
+    sqlx::query("DELETE FROM content_items_archive WHERE source_id = ?")
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await
+        .context("deleting archived source items")?;
+    sqlx::query("DELETE FROM preprocess_dropped WHERE source_id = ?")
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await
+        .context("deleting dropped items")?;
+    sqlx::query("DELETE FROM tg_backfill_progress WHERE source_id = ?")
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await
+        .context("deleting backfill progress")?;
+    sqlx::query("DELETE FROM tg_folder_channels WHERE folder_source_id = ?")
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await
+        .context("deleting folder channels")?;
+    sqlx::query(
+        "UPDATE sources SET last_fetched_at = NULL, last_etag = NULL, last_modified_header = NULL,
+             consecutive_failures = 0, last_error = NULL, last_success_at = NULL, last_item_at = NULL,
+             health_alerted = NULL, gone_at = NULL, tg_folder_id = NULL,
+             tg_id = CASE WHEN tg_username IS NOT NULL THEN NULL ELSE tg_id END
+         WHERE id = ?",
+    )
+    .bind(source_id)
+    .execute(&mut *tx)
+    .await
+    .context("clearing source fetch state")?;
+    tx.commit().await.context("committing source reset")?;
+    Ok(deleted)
+}
+
+/// Number of stored items of a source.
+#[instrument(skip_all, fields(source_id = %source_id))]
+pub async fn count_source_items(pool: &SqlitePool, source_id: &str) -> Result<i64> {
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM content_items WHERE source_id = ?")
+        .bind(source_id)
+        .fetch_one(pool)
+        .await
+        .context("counting source items")?;
+    Ok(count)
+}
+
 /// Store resolved numeric tg_id for a source.
+#[instrument(skip_all, fields(source_id = %source_id))]
 pub async fn update_source_tg_id(pool: &SqlitePool, source_id: &str, tg_id: i64) -> Result<()> {
     sqlx::query("UPDATE sources SET tg_id = ? WHERE id = ?")
         .bind(tg_id)
@@ -524,6 +2715,7 @@ pub async fn update_source_tg_id(pool: &SqlitePool, source_id: &str, tg_id: i64)
 }
 
 /// Store resolved folder ID for a folder source.
+#[instrument(skip_all, fields(source_id = %source_id))]
 pub async fn update_source_tg_folder_id(pool: &SqlitePool, source_id: &str, folder_id: i32) -> Result<()> {
     sqlx::query("UPDATE sources SET tg_folder_id = ? WHERE id = ?")
         .bind(folder_id)
@@ -534,48 +2726,88 @@ pub async fn update_source_tg_folder_id(pool: &SqlitePool, source_id: &str, fold
     Ok(())
 }
 
-/// Upsert a channel belonging to a folder source.
-pub async fn upsert_folder_channel(
+/// Replace a folder source's channels with its current members: (channel_tg_id, name,
+/// username). Members already known keep their `enabled` flag; channels that left the folder
+/// are dropped, flag and all.
+#[instrument(skip_all, fields(folder_source_id = %folder_source_id))]
+pub async fn sync_folder_channels(
     pool: &SqlitePool,
     folder_source_id: &str,
-    channel_tg_id: i64,
-    name: Option<&str>,
-    username: Option<&str>,
+    members: &[(i64, Option<String>, Option<String>)],
 ) -> Result<()> {
+    let mut tx = pool.begin().await.context("starting folder channel sync")?;
+    for (channel_tg_id, name, username) in members {
+        sqlx::query(
+            "INSERT INTO tg_folder_channels (folder_source_id, channel_tg_id, channel_name, channel_username)
+             VALUES (?, ?, ?, ?)
+             ON CONFLICT(folder_source_id, channel_tg_id) DO UPDATE SET
+               channel_name = excluded.channel_name,
+               channel_username = excluded.channel_username",
+        )
+        .bind(folder_source_id)
+        .bind(channel_tg_id)
+        .bind(name)
+        .bind(username)
+        .execute(&mut *tx)
+        .await
+        .context("upserting folder channel")?;
+    }
+    let ids = serde_json::to_string(&members.iter().map(|(id, _, _)| *id).collect::<Vec<_>>())?;
     sqlx::query(
-        "INSERT INTO tg_folder_channels (folder_source_id, channel_tg_id, channel_name, channel_username)
-         VALUES (?, ?, ?, ?)
-         ON CONFLICT(folder_source_id, channel_tg_id) DO UPDATE SET
-           channel_name = excluded.channel_name,
-           channel_username = excluded.channel_username",
+        "DELETE FROM tg_folder_channels
+         WHERE folder_source_id = ? AND channel_tg_id NOT IN (SELECT value FROM json_each(?))",
     )
     .bind(folder_source_id)
-    .bind(channel_tg_id)
-    .bind(name)
-    .bind(username)
-    .execute(pool)
+    .bind(ids)
+    .execute(&mut *tx)
     .await
-    .context("upserting folder channel")?;
+    .context("deleting channels that left the folder")?;
+    tx.commit().await.context("committing folder channel sync")?;
     Ok(())
 }
 
-/// Delete all channels for a folder source (used before re-sync).
-pub async fn delete_folder_channels(pool: &SqlitePool, folder_source_id: &str) -> Result<()> {
-    sqlx::query("DELETE FROM tg_folder_channels WHERE folder_source_id = ?")
-        .bind(folder_source_id)
-        .execute(pool)
-        .await
-        .context("deleting folder channels")?;
-    Ok(())
+/// All channels of a folder source, enabled or not, by name.
+#[instrument(skip_all, fields(folder_source_id = %folder_source_id))]
+pub async fn get_folder_channels(pool: &SqlitePool, folder_source_id: &str) -> Result<Vec<TgFolderChannel>> {
+    let channels = sqlx::query_as::<_, TgFolderChannel>(
+        "SELECT channel_tg_id, channel_name, channel_username, enabled FROM tg_folder_channels
+         WHERE folder_source_id = ? ORDER BY COALESCE(channel_name, channel_username, channel_tg_id) COLLATE NOCASE",
+    )
+    .bind(folder_source_id)
+    .fetch_all(pool)
+    .await
+    .context("querying folder channels")?;
+    Ok(channels)
 }
 
-/// Get channels belonging to a folder source with their info.
-/// Returns (channel_tg_id, channel_name, channel_username) for enabled channels.
-pub async fn get_folder_channels_with_info(
+/// Include or exclude one channel of a folder source. Returns false if the folder has no
+/// such channel.
+#[instrument(skip_all, fields(folder_source_id = %folder_source_id))]
+pub async fn set_folder_channel_enabled(
     pool: &SqlitePool,
     folder_source_id: &str,
-) -> Result<Vec<(i64, Option<String>, Option<String>)>> {
-    let rows: Vec<(i64, Option<String>, Option<String>)> = sqlx::query_as(
+    channel_tg_id: i64,
+    enabled: bool,
+) -> Result<bool> {
+    let result =
+        sqlx::query("UPDATE tg_folder_channels SET enabled = ? WHERE folder_source_id = ? AND channel_tg_id = ?")
+            .bind(enabled)
+            .bind(folder_source_id)
+            .bind(channel_tg_id)
+            .execute(pool)
+            .await
+            .context("updating folder channel")?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// A folder channel's (channel_tg_id, channel_name, channel_username).
+pub type FolderChannelInfo = (i64, Option<String>, Option<String>);
+
+/// Get channels belonging to a folder source with their info.
+/// Returns (channel_tg_id, channel_name, channel_username) for enabled channels.
+#[instrument(skip_all, fields(folder_source_id = %folder_source_id))]
+pub async fn get_folder_channels_with_info(pool: &SqlitePool, folder_source_id: &str) -> Result<Vec<FolderChannelInfo>> {
+    let rows: Vec<FolderChannelInfo> = sqlx::query_as(
         "SELECT channel_tg_id, channel_name, channel_username FROM tg_folder_channels WHERE folder_source_id = ? AND enabled = 1",
     )
     .bind(folder_source_id)
@@ -587,6 +2819,7 @@ pub async fn get_folder_channels_with_info(
 
 /// Get a map of channel_tg_id → (channel_name, channel_username) for a folder source.
 /// Used during workspace generation to split folder items into per-channel source files.
+#[instrument(skip_all, fields(folder_source_id = %folder_source_id))]
 pub async fn get_folder_channel_map(
     pool: &SqlitePool,
     folder_source_id: &str,
@@ -621,3 +2854,200 @@ pub async fn get_all_folder_channel_ids(pool: &SqlitePool) -> Result<Vec<(String
     .context("querying all folder channel IDs")?;
     Ok(rows)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An in-memory database with every migration applied. One connection, since each
+    /// in-memory connection is its own database.
+    async fn migrated_pool() -> SqlitePool {
+        crate::db::memory_pool().await
+    }
+
+    /// `EXPLAIN QUERY PLAN` of a statement with `params` parameters, one detail per line.
+    async fn plan(pool: &SqlitePool, sql: &str, params: usize) -> String {
+        let explain = format!("EXPLAIN QUERY PLAN {sql}");
+        let mut query = sqlx::query_as::<_, (i64, i64, i64, String)>(&explain);
+        for _ in 0..params {
+            query = query.bind("x");
+        }
+        let rows = query.fetch_all(pool).await.unwrap();
+        rows.into_iter()
+            .map(|(_, _, _, detail)| detail)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[tokio::test]
+    async fn window_query_seeks_both_tables() {
+        let pool = migrated_pool().await;
+        let plan = plan(&pool, &window_items_sql(2), 8).await;
+        assert!(
+            plan.contains("SEARCH content_items USING INDEX idx_content_items_source_date"),
+            "{plan}"
+        );
+        assert!(
+            plan.contains("USING INDEX idx_content_items_archive_source_date"),
+            "{plan}"
+        );
+        assert!(!plan.contains("SCAN content_items"), "{plan}");
+    }
+
+    #[tokio::test]
+    async fn channel_listing_uses_channel_index() {
+        let pool = migrated_pool().await;
+        let plan = plan(&pool, &list_articles_sql(true), 3).await;
+        assert!(
+            plan.contains("SEARCH generated_articles USING INDEX idx_generated_articles_channel"),
+            "{plan}"
+        );
+        assert!(!plan.contains("TEMP B-TREE"), "{plan}");
+    }
+
+    #[tokio::test]
+    async fn full_listing_walks_generated_at_index() {
+        let pool = migrated_pool().await;
+        let plan = plan(&pool, &list_articles_sql(false), 3).await;
+        assert!(plan.contains("USING INDEX idx_generated_articles_generated"), "{plan}");
+        assert!(!plan.contains("TEMP B-TREE"), "{plan}");
+    }
+
+    #[tokio::test]
+    async fn retention_deletes_seek_by_date() {
+        let pool = migrated_pool().await;
+        let plan_items = plan(&pool, "DELETE FROM content_items WHERE ingested_at < ?", 1).await;
+        assert!(
+            plan_items.contains("idx_content_items_ingested (ingested_at<?)"),
+            "{plan_items}"
+        );
+        let plan_articles = plan(&pool, "DELETE FROM generated_articles WHERE generated_at < ?", 1).await;
+        assert!(
+            plan_articles.contains("idx_generated_articles_generated (generated_at<?)"),
+            "{plan_articles}"
+        );
+    }
+
+    #[test]
+    fn window_overlap_is_relative_to_the_longer_window() {
+        let t = |h: i64| DateTime::<Utc>::UNIX_EPOCH + chrono::Duration::hours(h);
+        assert_eq!(window_overlap((t(0), t(24)), (t(0), t(24))), 1.0);
+        // A double-fired daily run a few minutes later is still the same window
+        let shifted = (
+            t(0) + chrono::Duration::minutes(5),
+            t(24) + chrono::Duration::minutes(5),
+        );
+        assert!(window_overlap((t(0), t(24)), shifted) > 0.99);
+        // A week-long ad-hoc window shares a seventh with each daily digest
+        let week = window_overlap((t(0), t(24)), (t(-144), t(24)));
+        assert!((week - 1.0 / 7.0).abs() < 1e-9, "{week}");
+        assert_eq!(window_overlap((t(0), t(24)), (t(24), t(48))), 0.0);
+        assert_eq!(window_overlap((t(0), t(24)), (t(30), t(48))), 0.0);
+        assert_eq!(window_overlap((t(5), t(5)), (t(5), t(5))), 1.0);
+    }
+
+    fn tg_item(dedup_key: &str, metadata: serde_json::Value) -> ContentItem {
+        ContentItem {
+            id: Uuid::new_v4().to_string(),
+            source_id: "src".to_string(),
+            ingested_at: Utc::now(),
+            original_date: Utc::now(),
+            content_type: "media".to_string(),
+            title: None,
+            body: String::new(),
+            url: None,
+            author: None,
+            metadata: metadata.to_string(),
+            dedup_key: dedup_key.to_string(),
+            upstream_changed: false,
+        }
+    }
+
+    #[test]
+    fn album_part_keys_cover_every_message() {
+        let album = tg_item("tg:-100:album:7", serde_json::json!({"message_ids": [10, 11]}));
+        assert_eq!(album_part_keys(&album), ["tg:-100:10", "tg:-100:11"]);
+        let single = tg_item("tg:-100:10", serde_json::json!({"message_ids": [10]}));
+        assert!(album_part_keys(&single).is_empty());
+    }
+
+    #[tokio::test]
+    async fn storing_an_album_replaces_its_parts() {
+        let pool = migrated_pool().await;
+        sqlx::query("INSERT INTO sources (id, source_type, name) VALUES ('src', 'telegram_channel', 'tg')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let other = tg_item("tg:-100:12", serde_json::json!({"message_id": 12}));
+        upsert_content_items(
+            &pool,
+            &[
+                tg_item("tg:-100:10", serde_json::json!({"message_id": 10})),
+                tg_item("tg:-100:11", serde_json::json!({"message_id": 11})),
+                other.clone(),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let album = tg_item("tg:-100:album:7", serde_json::json!({"message_ids": [10, 11]}));
+        upsert_content_item(&pool, &album).await.unwrap();
+
+        let keys: Vec<String> = sqlx::query_scalar("SELECT dedup_key FROM content_items ORDER BY dedup_key")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(keys, [other.dedup_key, album.dedup_key]);
+    }
+
+    #[tokio::test]
+    async fn an_edit_flags_the_item_as_changed() {
+        let pool = migrated_pool().await;
+        sqlx::query("INSERT INTO sources (id, source_type, name) VALUES ('src', 'telegram_channel', 'tg')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let mut item = tg_item("tg:-100:10", serde_json::json!({"message_id": 10}));
+        item.body = "Typo".to_string();
+        upsert_content_item(&pool, &item).await.unwrap();
+
+        assert!(!update_edited_item(&pool, &item).await.unwrap());
+        item.body = "Fixed".to_string();
+        assert!(update_edited_item(&pool, &item).await.unwrap());
+        let stored = get_content_item(&pool, "src", &item.dedup_key).await.unwrap().unwrap();
+        assert_eq!(stored.body, "Fixed");
+        assert!(stored.upstream_changed);
+    }
+
+    #[tokio::test]
+    async fn replacing_an_album_stores_its_late_parts() {
+        let pool = migrated_pool().await;
+        sqlx::query("INSERT INTO sources (id, source_type, name) VALUES ('src', 'telegram_bot', 'tg')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let mut album = tg_item("tg:-100:album:7", serde_json::json!({"message_ids": [10, 11]}));
+        album.body = "First".to_string();
+        let late_part = tg_item("tg:-100:12", serde_json::json!({"message_id": 12}));
+        upsert_content_items(&pool, &[album.clone(), late_part]).await.unwrap();
+
+        let mut grown = album.clone();
+        grown.id = Uuid::new_v4().to_string();
+        grown.body = "First\n\nLate".to_string();
+        grown.metadata = serde_json::json!({"message_ids": [10, 11, 12]}).to_string();
+        assert!(replace_album(&pool, &grown).await.unwrap());
+
+        let stored = get_content_item(&pool, "src", &album.dedup_key).await.unwrap().unwrap();
+        assert_eq!(stored.id, album.id);
+        assert_eq!(stored.body, "First\n\nLate");
+        assert!(stored.upstream_changed);
+        assert!(stored.metadata.contains("[10,11,12]"));
+        assert!(!content_item_exists(&pool, "src", "tg:-100:12").await.unwrap());
+        assert!(
+            get_content_item(&pool, "src", "tg:-100:album:8")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
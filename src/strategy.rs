@@ -376,14 +376,39 @@ pub fn resolve_tools(strategy: &Strategy) -> Result<ResolvedTools> {
 /// Returns the `## Workspace` section describing the workspace file layout.
 /// Dynamically lists tools based on the strategy's tool list.
 /// When `include_output_md` is true, includes the `output.md` bullet (for generation mode).
-pub fn workspace_context(strategy: &Strategy, include_output_md: bool) -> String {
+/// When `has_pages` is true, describes the prefetched `pages/` directory.
+pub fn workspace_context(strategy: &Strategy, include_output_md: bool, has_pages: bool, item_files: bool) -> String {
     let mut ctx = String::from(
         "\n## Workspace\n\
          All input data is in the current directory:\n\
-         - `manifest.json` — generation metadata (channel config, time window, source list)\n\
-         - `sources/` — one markdown file per source (`<slug>.md`), each with a YAML frontmatter\n\
-         \x20 header (name, type, item_count, description) followed by content items separated by `---`\n",
+         - `manifest.json` — generation metadata (channel config, time window, source list)\n",
     );
+    if item_files {
+        ctx.push_str(
+            "- `sources/` — one markdown file per source (`<slug>.md`) with a YAML frontmatter header\n\
+             \x20 (name, type, item_count, description) and a list of the source's items, and one directory per\n\
+             \x20 source (`<slug>/`) with one file per item (`001.md`, `002.md`, ...). Each item file has a YAML\n\
+             \x20 frontmatter header (id, source, title, url, date, author, type) followed by the item. Read the\n\
+             \x20 lists first and open the items worth covering.\n\
+             \x20 Use source descriptions to judge each source's credibility and context: what it covers, whose\n\
+             \x20 voice it is, how much weight its claims deserve\n",
+        );
+    } else {
+        ctx.push_str(
+            "- `sources/` — one markdown file per source (`<slug>.md`), each with a YAML frontmatter\n\
+             \x20 header (name, type, item_count, description) followed by content items separated by `---`.\n\
+             \x20 Use source descriptions to judge each source's credibility and context: what it covers, whose\n\
+             \x20 voice it is, how much weight its claims deserve\n",
+        );
+    }
+
+    if has_pages {
+        ctx.push_str(
+            "- `pages/` — full text of linked articles, already fetched by pail. Items with a \
+             `**Full text:**` field have their article here: read that file instead of fetching \
+             the URL. Fetch only items without this field\n",
+        );
+    }
 
     // List tools dynamically
     for tool_name in &strategy.meta.tools {
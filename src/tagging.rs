@@ -0,0 +1,348 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::header::USER_AGENT;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::SqlitePool;
+use tracing::{debug, info, warn};
+
+use crate::config::{NetworkConfig, OutputChannelConfig, TaggingConfig, normalize_tag};
+use crate::models::ContentItem;
+use crate::{http_client, store};
+
+/// Items per chat completion request.
+const BATCH_SIZE: usize = 20;
+
+/// Items tagged per daemon pass. The rest wait for the next poller tick.
+const MAX_ITEMS_PER_PASS: u32 = 200;
+
+/// Characters of title + body sent per item; the opening decides the topic.
+const MAX_INPUT_CHARS: usize = 1000;
+
+/// Upper bound for one chat completion request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Failed attempts after which the daemon pass stops sending an item.
+const MAX_FAILURES: i64 = 3;
+
+/// OpenAI-compatible chat completion response (also used by `self_eval`).
+#[derive(Deserialize)]
+pub(crate) struct ChatResponse {
+    pub(crate) choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ChatChoice {
+    pub(crate) message: ChatMessage,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ChatMessage {
+    pub(crate) content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TagReply {
+    items: Vec<TagAssignment>,
+}
+
+#[derive(Deserialize)]
+struct TagAssignment {
+    id: usize,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Tag items ingested since the last pass (daemon, once per poller tick). No-op without
+/// `[tagging]`. Failures are logged; untagged items are retried on the next pass.
+pub async fn tag_pending_items(pool: &SqlitePool, config: &TaggingConfig, network: &NetworkConfig) {
+    if !config.is_enabled() {
+        return;
+    }
+    let items = match store::get_untagged_items(pool, MAX_ITEMS_PER_PASS, MAX_FAILURES).await {
+        Ok(items) => items,
+        Err(e) => {
+            warn!("failed to load untagged items: {e:#}");
+            return;
+        }
+    };
+    if items.is_empty() {
+        return;
+    }
+    match tag_items(pool, config, network, &items).await {
+        Ok(()) => info!(items = items.len(), "tagged content items"),
+        Err(e) => warn!("tagging pass failed: {e:#}"),
+    }
+}
+
+/// Apply the channel's `include_tags` / `exclude_tags`. No-op for channels without either.
+///
+/// Items in the window that haven't been tagged yet are tagged first. Items that still have
+/// no tagging result (API failure) pass through — a noisy digest beats a missing one.
+pub async fn filter_items(
+    pool: &SqlitePool,
+    config: &TaggingConfig,
+    network: &NetworkConfig,
+    channel_config: &OutputChannelConfig,
+    items: Vec<ContentItem>,
+) -> Vec<ContentItem> {
+    if channel_config.include_tags.is_empty() && channel_config.exclude_tags.is_empty() {
+        return items;
+    }
+
+    match load_tags(pool, config, network, &items).await {
+        Ok((tags, untagged)) => apply_filters(channel_config, items, &tags, &untagged),
+        Err(e) => {
+            warn!(channel = %channel_config.slug, "tag filtering failed, using all items: {e:#}");
+            items
+        }
+    }
+}
+
+/// `filter_items` with stored tags only, for previews: nothing is tagged, and untagged items pass.
+pub async fn filter_stored_items(
+    pool: &SqlitePool,
+    channel_config: &OutputChannelConfig,
+    items: Vec<ContentItem>,
+) -> Vec<ContentItem> {
+    if channel_config.include_tags.is_empty() && channel_config.exclude_tags.is_empty() {
+        return items;
+    }
+
+    let ids: Vec<String> = items.iter().map(|i| i.id.clone()).collect();
+    let stored = match store::get_untagged_item_ids(pool, &ids).await {
+        Ok(untagged) => store::get_item_tags(pool, &ids).await.map(|tags| (tags, untagged)),
+        Err(e) => Err(e),
+    };
+    match stored {
+        Ok((tags, untagged)) => apply_filters(channel_config, items, &tags, &untagged),
+        Err(e) => {
+            warn!(channel = %channel_config.slug, "tag filtering failed, using all items: {e:#}");
+            items
+        }
+    }
+}
+
+/// Tag the untagged items among `items`, then return every item's tags and the IDs of items
+/// still without a tagging result.
+async fn load_tags(
+    pool: &SqlitePool,
+    config: &TaggingConfig,
+    network: &NetworkConfig,
+    items: &[ContentItem],
+) -> Result<(HashMap<String, Vec<String>>, HashSet<String>)> {
+    let ids: Vec<String> = items.iter().map(|i| i.id.clone()).collect();
+    let mut untagged = store::get_untagged_item_ids(pool, &ids).await?;
+
+    if !untagged.is_empty() {
+        let pending: Vec<ContentItem> = items.iter().filter(|i| untagged.contains(&i.id)).cloned().collect();
+        match tag_items(pool, config, network, &pending).await {
+            Ok(()) => debug!(items = pending.len(), "tagged items in window"),
+            Err(e) => warn!("tagging items in window failed: {e:#}"),
+        }
+        untagged = store::get_untagged_item_ids(pool, &ids).await?;
+    }
+
+    let tags = store::get_item_tags(pool, &ids).await?;
+    Ok((tags, untagged))
+}
+
+fn apply_filters(
+    channel_config: &OutputChannelConfig,
+    items: Vec<ContentItem>,
+    tags: &HashMap<String, Vec<String>>,
+    untagged: &HashSet<String>,
+) -> Vec<ContentItem> {
+    let include: HashSet<String> = channel_config.include_tags.iter().map(|t| normalize_tag(t)).collect();
+    let exclude: HashSet<String> = channel_config.exclude_tags.iter().map(|t| normalize_tag(t)).collect();
+    let total = items.len();
+
+    let kept: Vec<ContentItem> = items
+        .into_iter()
+        .filter(|item| {
+            if untagged.contains(&item.id) {
+                return true;
+            }
+            let item_tags = tags.get(&item.id).map(Vec::as_slice).unwrap_or_default();
+            if item_tags.iter().any(|t| exclude.contains(t)) {
+                return false;
+            }
+            include.is_empty() || item_tags.iter().any(|t| include.contains(t))
+        })
+        .collect();
+
+    info!(
+        channel = %channel_config.slug,
+        total,
+        kept = kept.len(),
+        untagged = untagged.len(),
+        "tag filter applied"
+    );
+    kept
+}
+
+/// Tag `items` in batches of `BATCH_SIZE` and store the results. A failed batch is retried
+/// item by item, so one bad item doesn't hold back the rest; items that fail while others in
+/// their batch succeed get a failure counted. Stops when every item of a batch fails (the API
+/// is down); earlier batches stay stored.
+async fn tag_items(
+    pool: &SqlitePool,
+    config: &TaggingConfig,
+    network: &NetworkConfig,
+    items: &[ContentItem],
+) -> Result<()> {
+    let client = http_client::with_proxy(reqwest::Client::builder(), network.proxy.as_deref())
+        .and_then(|builder| builder.timeout(REQUEST_TIMEOUT).build())
+        .context("building HTTP client")?;
+    let vocabulary: HashSet<String> = config.vocabulary.iter().map(|t| normalize_tag(t)).collect();
+
+    for batch in items.chunks(BATCH_SIZE) {
+        let error = match request_tags(&client, config, batch).await {
+            Ok(reply) => {
+                store::set_item_tags(pool, &assign_tags(config, &vocabulary, batch, reply)).await?;
+                continue;
+            }
+            Err(e) => e,
+        };
+        if batch.len() == 1 {
+            return Err(error);
+        }
+        warn!("tagging batch failed, retrying its items one by one: {error:#}");
+        let mut failed = Vec::new();
+        for item in batch {
+            let single = std::slice::from_ref(item);
+            match request_tags(&client, config, single).await {
+                Ok(reply) => store::set_item_tags(pool, &assign_tags(config, &vocabulary, single, reply)).await?,
+                Err(e) => {
+                    debug!(item = %item.id, "tagging item failed: {e:#}");
+                    failed.push(item.id.clone());
+                }
+            }
+        }
+        if failed.len() == batch.len() {
+            return Err(error);
+        }
+        warn!(items = failed.len(), "items failed tagging on their own");
+        store::record_tagging_failures(pool, &failed).await?;
+    }
+    Ok(())
+}
+
+/// The tags a reply gives each item of the batch, normalized, limited to the vocabulary (if
+/// any) and capped at `max_tags_per_item`. Items the reply leaves out get none.
+fn assign_tags(
+    config: &TaggingConfig,
+    vocabulary: &HashSet<String>,
+    batch: &[ContentItem],
+    reply: TagReply,
+) -> Vec<(String, Vec<String>)> {
+    let mut assigned: Vec<(String, Vec<String>)> = batch.iter().map(|i| (i.id.clone(), Vec::new())).collect();
+    for assignment in reply.items {
+        let Some((_, tags)) = assigned.get_mut(assignment.id) else {
+            continue;
+        };
+        for tag in assignment.tags.iter().map(|t| normalize_tag(t)) {
+            if tag.is_empty() || tags.contains(&tag) || (!vocabulary.is_empty() && !vocabulary.contains(&tag)) {
+                continue;
+            }
+            if tags.len() < config.max_tags_per_item as usize {
+                tags.push(tag);
+            }
+        }
+    }
+    assigned
+}
+
+/// Call `{api_url}/chat/completions` (OpenAI-compatible) for one batch. Items are numbered
+/// by their index in the batch.
+async fn request_tags(client: &reqwest::Client, config: &TaggingConfig, batch: &[ContentItem]) -> Result<TagReply> {
+    let api_url = config
+        .api_url
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("[tagging].api_url is not configured"))?;
+    let model = config
+        .model
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("[tagging].model is not configured"))?;
+    let url = format!("{}/chat/completions", api_url.trim_end_matches('/'));
+
+    let mut instructions = format!(
+        "You label content items with topic tags so they can be routed into themed digests. \
+         Give each item at most {} tags; an item may get none. \
+         Reply with JSON only, in the form {{\"items\": [{{\"id\": 0, \"tags\": [\"...\"]}}]}}.",
+        config.max_tags_per_item
+    );
+    if config.vocabulary.is_empty() {
+        instructions.push_str(" Use short lowercase tags of one to three words, and the same tag for the same topic.");
+    } else {
+        instructions.push_str(&format!(" Use only these tags: {}.", config.vocabulary.join(", ")));
+    }
+
+    let listing: Vec<String> = batch
+        .iter()
+        .enumerate()
+        .map(|(i, item)| format!("[{i}] {}", tagging_input(item)))
+        .collect();
+
+    let mut request = client
+        .post(&url)
+        .header(USER_AGENT, concat!("pail/", env!("CARGO_PKG_VERSION")))
+        .json(&json!({
+            "model": model,
+            "temperature": 0,
+            "messages": [
+                { "role": "system", "content": instructions },
+                { "role": "user", "content": listing.join("\n\n") },
+            ],
+        }));
+    if let Some(ref key) = config.api_key {
+        request = request.bearer_auth(key);
+    }
+
+    let response = request.send().await.context("sending tagging request")?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("tagging API returned {status}: {body}");
+    }
+
+    let parsed: ChatResponse = response.json().await.context("parsing tagging response")?;
+    let content = parsed
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|c| c.message.content)
+        .ok_or_else(|| anyhow::anyhow!("tagging API returned no message"))?;
+    parse_reply(&content)
+}
+
+/// Parse the model's JSON reply, tolerating code fences or prose around the object.
+fn parse_reply(content: &str) -> Result<TagReply> {
+    let Some(json) = json_object(content) else {
+        anyhow::bail!("tagging reply has no JSON object: {content}");
+    };
+    serde_json::from_str(json).with_context(|| format!("parsing tagging reply: {json}"))
+}
+
+/// The outermost `{...}` of a model reply.
+pub(crate) fn json_object(content: &str) -> Option<&str> {
+    let start = content.find('{')?;
+    let end = content.rfind('}')?;
+    (start < end).then(|| &content[start..=end])
+}
+
+/// Text sent for an item: title and body on one line, truncated to `MAX_INPUT_CHARS`.
+fn tagging_input(item: &ContentItem) -> String {
+    let text = match item.title {
+        Some(ref title) => format!("{title} — {}", item.body),
+        None => item.body.clone(),
+    };
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_INPUT_CHARS)
+        .collect()
+}
@@ -3,41 +3,48 @@ use std::io::Write;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Utc};
 use grammers_client::peer::Peer as ClientPeer;
 use grammers_client::{Client, SenderPool, SignInError};
 use grammers_mtsender::ConnectionParams;
+use grammers_session::Session;
 use grammers_session::types::PeerId;
 use grammers_session::updates::UpdatesLike;
 use grammers_tl_types as tl;
+use qrcode::QrCode;
+use qrcode::render::unicode::Dense1x2;
+use rand::Rng;
 use sqlx::SqlitePool;
 use tokio::sync::mpsc;
 use tracing::{debug, info, warn};
 
-use crate::config::Config;
+use crate::config::{Config, DEFAULT_TG_ACCOUNT, TelegramAccountConfig};
 use crate::error::TelegramError;
 use crate::models::{ContentItem, Source};
 use crate::store;
-use crate::tg_session::SqlxSession;
+use crate::tg_session::{SqlxSession, namespaced};
+
+/// Connected clients by account name (see docs/specs/telegram.md "Multiple Accounts").
+pub type TgClients = HashMap<String, Client>;
 
 /// Holds a connected grammers client and its background runner handle.
 pub struct TgConnection {
+    pub account: String,
     pub client: Client,
     pub updates_rx: mpsc::UnboundedReceiver<UpdatesLike>,
     pub runner_handle: tokio::task::JoinHandle<()>,
 }
 
-/// Create a grammers Client connected to Telegram.
+/// Create a grammers Client connected to Telegram as `account`.
 /// Returns the client and the updates receiver (for the listener loop).
-pub async fn connect(config: &Config, pool: &SqlitePool) -> Result<TgConnection> {
-    let api_id = config
-        .telegram
-        .api_id
-        .ok_or_else(|| TelegramError::Connection("api_id not configured".to_string()))?;
+pub async fn connect(config: &Config, pool: &SqlitePool, account: &str) -> Result<TgConnection> {
+    let api_id = account_credentials(config, account)?.api_id;
 
-    info!("loading Telegram session from database");
+    info!(account, "loading Telegram session from database");
 
     let session = Arc::new(
-        SqlxSession::load(pool.clone())
+        SqlxSession::load(pool.clone(), account)
             .await
             .map_err(|e| TelegramError::Connection(format!("failed to load session: {e}")))?,
     );
@@ -48,6 +55,12 @@ pub async fn connect(config: &Config, pool: &SqlitePool) -> Result<TgConnection>
         ConnectionParams {
             app_version: env!("CARGO_PKG_VERSION").to_string(),
             device_model: "pail".to_string(),
+            // Telegram DCs are IP addresses, so socks5h has nothing to resolve remotely
+            proxy_url: config
+                .network
+                .proxy
+                .as_deref()
+                .map(|url| url.replacen("socks5h://", "socks5://", 1)),
             ..Default::default()
         },
     );
@@ -67,28 +80,33 @@ pub async fn connect(config: &Config, pool: &SqlitePool) -> Result<TgConnection>
     });
 
     Ok(TgConnection {
+        account: account.to_string(),
         client,
         updates_rx: updates,
         runner_handle,
     })
 }
 
+/// API credentials of an account, or a connection error naming what's missing.
+fn account_credentials(config: &Config, account: &str) -> Result<TelegramAccountConfig> {
+    config.telegram.account(account).ok_or_else(|| {
+        if account == DEFAULT_TG_ACCOUNT {
+            TelegramError::Connection("[telegram].api_id / api_hash not configured".to_string()).into()
+        } else {
+            TelegramError::Connection(format!(
+                "Telegram account '{account}' is not defined in [telegram.accounts]"
+            ))
+            .into()
+        }
+    })
+}
+
 /// Interactive login flow (phone -> code -> optional 2FA).
-pub async fn login(client: &Client, config: &Config) -> Result<()> {
-    let api_hash = config
-        .telegram
-        .api_hash
-        .as_deref()
-        .ok_or_else(|| TelegramError::Connection("api_hash not configured".to_string()))?;
-
-    // Check if already authorized
-    if client.is_authorized().await.unwrap_or(false) {
-        let me = client.get_me().await.context("getting current user")?;
-        println!(
-            "Already logged in as {} (@{})",
-            me.full_name(),
-            me.username().unwrap_or("no username")
-        );
+pub async fn login(client: &Client, config: &Config, account: &str) -> Result<()> {
+    let api_hash = account_credentials(config, account)?.api_hash;
+    let api_hash = api_hash.as_str();
+
+    if print_if_authorized(client).await? {
         return Ok(());
     }
 
@@ -163,6 +181,193 @@ pub async fn login(client: &Client, config: &Config) -> Result<()> {
     Ok(())
 }
 
+/// How often the QR login flow re-exports the login token to check whether it was accepted.
+/// Tokens expire after ~30s; re-exporting also yields a fresh token to display.
+const QR_POLL_SECS: u64 = 3;
+
+/// QR-code login flow (`auth.exportLoginToken`): display a `tg://login` QR code, wait for
+/// the user to scan it from an already logged-in Telegram app, then handle DC migration
+/// and the optional 2FA password.
+pub async fn login_qr(client: &Client, config: &Config, pool: &SqlitePool, account: &str) -> Result<()> {
+    let TelegramAccountConfig { api_id, api_hash } = account_credentials(config, account)?;
+    let api_hash = api_hash.as_str();
+
+    if print_if_authorized(client).await? {
+        return Ok(());
+    }
+
+    println!("Scan the QR code with Telegram on your phone:");
+    println!("  Settings → Devices → Link Desktop Device\n");
+
+    let mut displayed_token: Option<Vec<u8>> = None;
+    loop {
+        let request = tl::functions::auth::ExportLoginToken {
+            api_id,
+            api_hash: api_hash.to_string(),
+            except_ids: Vec::new(),
+        };
+
+        let result = match client.invoke(&request).await {
+            Ok(result) => result,
+            Err(e) if e.to_string().contains("SESSION_PASSWORD_NEEDED") => {
+                let authorization = check_password_srp(client, None).await?;
+                return print_authorization(&authorization);
+            }
+            Err(e) => {
+                let msg = e.to_string();
+                if msg.contains("API_ID_INVALID") || msg.contains("CONNECTION_API_ID_INVALID") {
+                    anyhow::bail!(
+                        "invalid Telegram API credentials. Check [telegram].api_id and api_hash in config.toml \
+                         (get valid credentials at https://my.telegram.org)"
+                    );
+                }
+                return Err(anyhow::anyhow!(e).context("exporting login token"));
+            }
+        };
+
+        match result {
+            tl::enums::auth::LoginToken::Token(token) => {
+                if displayed_token.as_ref() != Some(&token.token) {
+                    print_login_qr(&token.token)?;
+                    debug!(expires = token.expires, "displayed new login token");
+                    displayed_token = Some(token.token);
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(QR_POLL_SECS)).await;
+            }
+            tl::enums::auth::LoginToken::MigrateTo(migrate) => {
+                // The account lives on another DC: import the token there and make it home
+                info!(dc_id = migrate.dc_id, "login token accepted on another DC, importing");
+                let request = tl::functions::auth::ImportLoginToken { token: migrate.token };
+                let result = match client.invoke_in_dc(migrate.dc_id, &request).await {
+                    Ok(result) => result,
+                    Err(e) if e.to_string().contains("SESSION_PASSWORD_NEEDED") => {
+                        let authorization = check_password_srp(client, Some(migrate.dc_id)).await?;
+                        SqlxSession::load(pool.clone(), account)
+                            .await?
+                            .set_home_dc_id(migrate.dc_id)
+                            .await;
+                        return print_authorization(&authorization);
+                    }
+                    Err(e) => return Err(anyhow::anyhow!(e).context("importing login token")),
+                };
+                SqlxSession::load(pool.clone(), account)
+                    .await?
+                    .set_home_dc_id(migrate.dc_id)
+                    .await;
+                return match result {
+                    tl::enums::auth::LoginToken::Success(success) => print_authorization(&success.authorization),
+                    _ => anyhow::bail!("unexpected response while importing login token"),
+                };
+            }
+            tl::enums::auth::LoginToken::Success(success) => {
+                return print_authorization(&success.authorization);
+            }
+        }
+    }
+}
+
+/// If the session is already authorized, print the current user and return true.
+async fn print_if_authorized(client: &Client) -> Result<bool> {
+    if !client.is_authorized().await.unwrap_or(false) {
+        return Ok(false);
+    }
+    let me = client.get_me().await.context("getting current user")?;
+    println!(
+        "Already logged in as {} (@{})",
+        me.full_name(),
+        me.username().unwrap_or("no username")
+    );
+    Ok(true)
+}
+
+/// Render a `tg://login?token=...` URL as a terminal QR code (light-on-dark half blocks).
+fn print_login_qr(token: &[u8]) -> Result<()> {
+    let url = format!(
+        "tg://login?token={}",
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(token)
+    );
+    let code = QrCode::new(url.as_bytes()).context("encoding login QR code")?;
+    let image = code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .quiet_zone(true)
+        .build();
+    println!("{image}\n");
+    println!("Waiting for the QR code to be scanned (it refreshes automatically, Ctrl+C to abort)...");
+    Ok(())
+}
+
+/// Complete a QR login that requires the 2FA password, using the SRP check
+/// (`account.getPassword` + `auth.checkPassword`). `dc_id` is set when the login token
+/// was imported on a DC other than the current home DC.
+async fn check_password_srp(client: &Client, dc_id: Option<i32>) -> Result<tl::enums::auth::Authorization> {
+    let request = tl::functions::account::GetPassword {};
+    let tl::enums::account::Password::Password(password) = match dc_id {
+        Some(dc_id) => client.invoke_in_dc(dc_id, &request).await,
+        None => client.invoke(&request).await,
+    }
+    .context("fetching 2FA password parameters")?;
+
+    let Some(tl::enums::PasswordKdfAlgo::Sha256Sha256Pbkdf2Hmacsha512iter100000Sha256ModPow(algo)) =
+        password.current_algo
+    else {
+        anyhow::bail!("unsupported 2FA password algorithm");
+    };
+    let srp_b = password.srp_b.context("2FA parameters missing srp_B")?;
+    let srp_id = password.srp_id.context("2FA parameters missing srp_id")?;
+
+    let hint = password.hint.as_deref().unwrap_or("none");
+    println!("Two-factor authentication required (hint: {hint})");
+    let input = rpassword::prompt_password_stdout("Enter 2FA password: ").context("reading 2FA password")?;
+
+    let mut a = vec![0u8; 256];
+    rand::rng().fill(&mut a[..]);
+    let (m1, g_a) = grammers_crypto::two_factor_auth::calculate_2fa(
+        &algo.salt1,
+        &algo.salt2,
+        &algo.p,
+        &algo.g,
+        srp_b,
+        a,
+        input.as_bytes(),
+    );
+
+    let request = tl::functions::auth::CheckPassword {
+        password: tl::enums::InputCheckPasswordSrp::Srp(tl::types::InputCheckPasswordSrp {
+            srp_id,
+            a: g_a.to_vec(),
+            m1: m1.to_vec(),
+        }),
+    };
+    match dc_id {
+        Some(dc_id) => client.invoke_in_dc(dc_id, &request).await,
+        None => client.invoke(&request).await,
+    }
+    .map_err(|e| anyhow::anyhow!("2FA check failed: {e}"))
+}
+
+/// Print the logged-in user from a successful `auth.Authorization`.
+fn print_authorization(authorization: &tl::enums::auth::Authorization) -> Result<()> {
+    let tl::enums::auth::Authorization::Authorization(auth) = authorization else {
+        anyhow::bail!("Telegram requires sign-up for this account; create it in an official app first");
+    };
+    let tl::enums::User::User(user) = &auth.user else {
+        anyhow::bail!("sign-in returned an empty user");
+    };
+    let full_name = [user.first_name.as_deref(), user.last_name.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+    println!(
+        "Logged in as {} (@{})",
+        full_name,
+        user.username.as_deref().unwrap_or("no username")
+    );
+    Ok(())
+}
+
 /// Print session/connection status.
 pub async fn status(client: &Client) -> Result<()> {
     match client.is_authorized().await {
@@ -245,7 +450,12 @@ pub async fn resolve_source_ids(client: &Client, pool: &SqlitePool, sources: &[S
 /// Resolve folder names to channel lists.
 /// For each folder source, looks up the folder by name via getDialogFilters,
 /// extracts the included peers, and stores them in tg_folder_channels.
-pub async fn resolve_folders(client: &Client, pool: &SqlitePool, folder_sources: &[Source]) -> Result<()> {
+pub async fn resolve_folders(
+    client: &Client,
+    pool: &SqlitePool,
+    account: &str,
+    folder_sources: &[Source],
+) -> Result<()> {
     if folder_sources.is_empty() {
         return Ok(());
     }
@@ -294,18 +504,16 @@ pub async fn resolve_folders(client: &Client, pool: &SqlitePool, folder_sources:
             .await
             .with_context(|| format!("storing folder_id for source '{}'", source.name))?;
 
-        // Clear existing folder channels and re-sync
-        store::delete_folder_channels(pool, &source.id).await?;
-
         // Collect all peers and cache their access hashes
         let all_peers: Vec<&tl::enums::InputPeer> = pinned_peers.iter().chain(included_peers.iter()).collect();
         for peer in &all_peers {
-            cache_input_peer(pool, peer).await;
+            cache_input_peer(pool, account, peer).await;
         }
 
         // Batch-resolve channel peers in a single getChannels call
         let channel_info = batch_resolve_channels(client, &all_peers).await;
 
+        let mut members = Vec::new();
         for peer in &all_peers {
             let tg_id = match peer {
                 tl::enums::InputPeer::Channel(c) => c.channel_id,
@@ -315,10 +523,12 @@ pub async fn resolve_folders(client: &Client, pool: &SqlitePool, folder_sources:
             };
 
             let (name, username) = channel_info.get(&tg_id).cloned().unwrap_or((None, None));
-
-            store::upsert_folder_channel(pool, &source.id, tg_id, name.as_deref(), username.as_deref()).await?;
+            members.push((tg_id, name, username));
         }
 
+        // Re-sync; channels disabled with `pail tg folder disable` stay disabled
+        store::sync_folder_channels(pool, &source.id, &members).await?;
+
         info!(source = %source.name, folder = %folder_name, folder_id, "resolved folder");
     }
 
@@ -334,7 +544,7 @@ pub async fn resolve_folders(client: &Client, pool: &SqlitePool, folder_sources:
 /// This function checks for uncached peers and, if any are found, iterates the user's dialog
 /// list to warm the cache. grammers auto-caches all peers from `getDialogs` responses via
 /// the Session trait.
-pub async fn ensure_peer_cache(client: &Client, pool: &SqlitePool, sources: &[Source]) -> Result<()> {
+pub async fn ensure_peer_cache(client: &Client, pool: &SqlitePool, account: &str, sources: &[Source]) -> Result<()> {
     let mut uncached_ids: Vec<i64> = Vec::new();
 
     for source in sources {
@@ -352,12 +562,15 @@ pub async fn ensure_peer_cache(client: &Client, pool: &SqlitePool, sources: &[So
         let channel_api_id = PeerId::channel(tg_id).bot_api_dialog_id();
         let chat_api_id = PeerId::chat(tg_id).bot_api_dialog_id();
 
-        let found = sqlx::query_scalar::<_, i32>("SELECT 1 FROM tg_peer_info WHERE peer_id IN (?, ?) LIMIT 1")
-            .bind(channel_api_id)
-            .bind(chat_api_id)
-            .fetch_optional(pool)
-            .await
-            .context("checking peer cache")?;
+        let found = sqlx::query_scalar::<_, i32>(&namespaced(
+            account,
+            "SELECT 1 FROM tg_peer_info WHERE peer_id IN (?, ?) LIMIT 1",
+        ))
+        .bind(channel_api_id)
+        .bind(chat_api_id)
+        .fetch_optional(pool)
+        .await
+        .context("checking peer cache")?;
 
         if found.is_none() {
             uncached_ids.push(tg_id);
@@ -380,12 +593,15 @@ pub async fn ensure_peer_cache(client: &Client, pool: &SqlitePool, sources: &[So
         let channel_api_id = PeerId::channel(*tg_id).bot_api_dialog_id();
         let chat_api_id = PeerId::chat(*tg_id).bot_api_dialog_id();
 
-        let found = sqlx::query_scalar::<_, i32>("SELECT 1 FROM tg_peer_info WHERE peer_id IN (?, ?) LIMIT 1")
-            .bind(channel_api_id)
-            .bind(chat_api_id)
-            .fetch_optional(pool)
-            .await
-            .context("verifying peer cache")?;
+        let found = sqlx::query_scalar::<_, i32>(&namespaced(
+            account,
+            "SELECT 1 FROM tg_peer_info WHERE peer_id IN (?, ?) LIMIT 1",
+        ))
+        .bind(channel_api_id)
+        .bind(chat_api_id)
+        .fetch_optional(pool)
+        .await
+        .context("verifying peer cache")?;
 
         if found.is_none() {
             warn!(
@@ -398,6 +614,63 @@ pub async fn ensure_peer_cache(client: &Client, pool: &SqlitePool, sources: &[So
     Ok(())
 }
 
+/// Enabled TG sources read with `account`.
+pub async fn account_sources(pool: &SqlitePool, account: &str) -> Result<Vec<Source>> {
+    Ok(store::get_tg_sources(pool)
+        .await
+        .context("loading TG sources")?
+        .into_iter()
+        .filter(|s| s.tg_account() == account)
+        .collect())
+}
+
+/// Resolve the account's sources: usernames to tg_ids, folder names to channel lists, and
+/// uncached peers. Run after connecting, before fetching history or listening.
+pub async fn prepare_account_sources(client: &Client, pool: &SqlitePool, account: &str) -> Result<()> {
+    let tg_sources = account_sources(pool, account).await?;
+
+    resolve_source_ids(client, pool, &tg_sources)
+        .await
+        .context("resolving TG source IDs")?;
+
+    let folder_sources: Vec<_> = tg_sources
+        .iter()
+        .filter(|s| s.source_type == "telegram_folder")
+        .cloned()
+        .collect();
+    resolve_folders(client, pool, account, &folder_sources)
+        .await
+        .context("resolving TG folders")?;
+
+    ensure_peer_cache(client, pool, account, &tg_sources)
+        .await
+        .context("warming TG peer cache")
+}
+
+/// Subscription map of the account's listener, from the sources' current resolution state.
+pub async fn account_subscription_map(pool: &SqlitePool, account: &str) -> Result<HashMap<i64, Vec<String>>> {
+    let tg_sources = account_sources(pool, account).await?;
+    let direct_sources: Vec<_> = tg_sources
+        .iter()
+        .filter(|s| s.source_type != "telegram_folder")
+        .cloned()
+        .collect();
+    let folder_ids: std::collections::HashSet<&str> = tg_sources
+        .iter()
+        .filter(|s| s.source_type == "telegram_folder")
+        .map(|s| s.id.as_str())
+        .collect();
+
+    let folder_channels: Vec<(String, i64)> = store::get_all_folder_channel_ids(pool)
+        .await
+        .context("loading folder channel IDs")?
+        .into_iter()
+        .filter(|(source_id, _)| folder_ids.contains(source_id.as_str()))
+        .collect();
+
+    Ok(build_subscription_map(&direct_sources, &folder_channels))
+}
+
 /// Build subscription map: chat_id -> Vec<source_id>.
 /// Maps each Telegram chat ID to the list of pail source IDs that want messages from it.
 pub fn build_subscription_map(
@@ -424,11 +697,20 @@ pub fn build_subscription_map(
 /// Mark Telegram channels/groups as read up to the latest message included in a generation.
 /// This is the ONLY write operation pail performs on Telegram
 /// (see docs/specs/telegram.md "Read-Only Contract" and "Mark-as-Read").
-/// Best-effort: failures are logged but never fail the generation pipeline.
-pub async fn mark_channels_as_read(client: &Client, pool: &SqlitePool, items: &[ContentItem]) {
+/// Items past `covers_to` are ignored, and a chat is only marked after its dialog confirms
+/// there's something unread up to that message. With `dry_run`, what would be marked is logged
+/// instead. Best-effort: failures are logged but never fail the generation pipeline.
+pub async fn mark_channels_as_read(
+    client: &Client,
+    pool: &SqlitePool,
+    account: &str,
+    items: &[ContentItem],
+    covers_to: DateTime<Utc>,
+    dry_run: bool,
+) {
     // Group TG content items by chat_id and find the max message_id per chat
     let mut max_msg_per_chat: HashMap<i64, i32> = HashMap::new();
-    for item in items {
+    for item in items.iter().filter(|item| item.original_date <= covers_to) {
         if let Ok(meta) = serde_json::from_str::<serde_json::Value>(&item.metadata)
             && let (Some(chat_id), Some(msg_id)) = (
                 meta.get("chat_id").and_then(|v| v.as_i64()),
@@ -444,11 +726,15 @@ pub async fn mark_channels_as_read(client: &Client, pool: &SqlitePool, items: &[
         return;
     }
 
-    info!(chats = max_msg_per_chat.len(), "marking Telegram channels as read");
+    info!(
+        chats = max_msg_per_chat.len(),
+        dry_run, "marking Telegram channels as read"
+    );
 
+    let mut marked = 0;
     for (&chat_id, &max_id) in &max_msg_per_chat {
         // Resolve peer kind and access hash from the cache
-        let peer_ref = match crate::fetch_tg::resolve_peer_ref(pool, chat_id).await {
+        let peer_ref = match crate::fetch_tg::resolve_peer_ref(pool, account, chat_id).await {
             Ok(pr) => pr,
             Err(e) => {
                 warn!(chat_id, error = %e, "failed to resolve peer for mark-as-read");
@@ -456,6 +742,48 @@ pub async fn mark_channels_as_read(client: &Client, pool: &SqlitePool, items: &[
             }
         };
 
+        // The guard: without the dialog's read state nothing is marked
+        let state = match read_state(client, (&peer_ref).into()).await {
+            Ok(Some(state)) => state,
+            Ok(None) => {
+                warn!(chat_id, "no dialog for chat, not marking it as read");
+                continue;
+            }
+            Err(e) => {
+                warn!(chat_id, error = %e, "failed to get read state, not marking chat as read");
+                continue;
+            }
+        };
+        if max_id > state.top_message {
+            warn!(
+                chat_id,
+                max_id,
+                newest = state.top_message,
+                "covered message is past the chat's newest message, not marking it as read"
+            );
+            continue;
+        }
+        if state.unread_count == 0 || state.read_inbox_max_id >= max_id {
+            debug!(
+                chat_id,
+                max_id,
+                read_up_to = state.read_inbox_max_id,
+                "chat already read"
+            );
+            continue;
+        }
+        if dry_run {
+            info!(
+                chat_id,
+                max_id,
+                read_up_to = state.read_inbox_max_id,
+                unread = state.unread_count,
+                newest = state.top_message,
+                "dry run: would mark chat as read"
+            );
+            continue;
+        }
+
         let is_channel = matches!(peer_ref.id.kind(), grammers_session::types::PeerKind::Channel);
 
         if is_channel {
@@ -468,7 +796,10 @@ pub async fn mark_channels_as_read(client: &Client, pool: &SqlitePool, items: &[
                 max_id,
             };
             match client.invoke(&request).await {
-                Ok(_) => debug!(chat_id, max_id, "marked channel as read"),
+                Ok(_) => {
+                    debug!(chat_id, max_id, "marked channel as read");
+                    marked += 1;
+                }
                 Err(e) => warn!(chat_id, max_id, error = %e, "failed to mark channel as read"),
             }
         } else {
@@ -477,11 +808,45 @@ pub async fn mark_channels_as_read(client: &Client, pool: &SqlitePool, items: &[
                 max_id,
             };
             match client.invoke(&request).await {
-                Ok(_) => debug!(chat_id, max_id, "marked group as read"),
+                Ok(_) => {
+                    debug!(chat_id, max_id, "marked group as read");
+                    marked += 1;
+                }
                 Err(e) => warn!(chat_id, max_id, error = %e, "failed to mark group as read"),
             }
         }
     }
+    if !dry_run {
+        info!(
+            marked,
+            chats = max_msg_per_chat.len(),
+            "marked Telegram channels as read"
+        );
+    }
+}
+
+/// How far a chat is read, from its dialog.
+struct ReadState {
+    read_inbox_max_id: i32,
+    unread_count: i32,
+    /// ID of the chat's newest message.
+    top_message: i32,
+}
+
+async fn read_state(client: &Client, peer: tl::enums::InputPeer) -> Result<Option<ReadState>> {
+    let request = tl::functions::messages::GetPeerDialogs {
+        peers: vec![tl::enums::InputDialogPeer::Peer(tl::types::InputDialogPeer { peer })],
+    };
+    let tl::enums::messages::PeerDialogs::Dialogs(dialogs) =
+        client.invoke(&request).await.context("getting peer dialog")?;
+    Ok(dialogs.dialogs.into_iter().find_map(|dialog| match dialog {
+        tl::enums::Dialog::Dialog(d) => Some(ReadState {
+            read_inbox_max_id: d.read_inbox_max_id,
+            unread_count: d.unread_count,
+            top_message: d.top_message,
+        }),
+        tl::enums::Dialog::Folder(_) => None,
+    }))
 }
 
 // ─── Types and functions for the config editor TUI ───
@@ -853,7 +1218,7 @@ async fn batch_resolve_channels(
 /// Folder definitions contain InputPeers with valid access_hashes, but grammers'
 /// raw `invoke` doesn't auto-cache peers from RPC responses. Without this, subsequent
 /// getHistory calls fail with CHANNEL_INVALID because the access_hash is missing.
-async fn cache_input_peer(pool: &SqlitePool, peer: &tl::enums::InputPeer) {
+async fn cache_input_peer(pool: &SqlitePool, account: &str, peer: &tl::enums::InputPeer) {
     let (peer_id, access_hash) = match peer {
         tl::enums::InputPeer::Channel(c) => (PeerId::channel(c.channel_id), c.access_hash),
         tl::enums::InputPeer::User(u) => (PeerId::user(u.user_id), u.access_hash),
@@ -861,10 +1226,11 @@ async fn cache_input_peer(pool: &SqlitePool, peer: &tl::enums::InputPeer) {
     };
 
     let bot_api_id = peer_id.bot_api_dialog_id();
-    if let Err(e) = sqlx::query(
+    if let Err(e) = sqlx::query(&namespaced(
+        account,
         "INSERT INTO tg_peer_info (peer_id, hash) VALUES (?, ?)
          ON CONFLICT(peer_id) DO UPDATE SET hash = COALESCE(excluded.hash, tg_peer_info.hash)",
-    )
+    ))
     .bind(bot_api_id)
     .bind(access_hash)
     .execute(pool)
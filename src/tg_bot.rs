@@ -0,0 +1,572 @@
+//! Telegram bot mode: `telegram_bot` sources receive the messages of chats a bot was added to
+//! through the Bot API (`getUpdates` long polling) instead of a personal account's MTProto
+//! session. Items have the same shape as the listener's (see docs/specs/telegram.md "Bot Mode").
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::{ContentItem, Source};
+use crate::store;
+use crate::{fetch_tg, http_client};
+
+const API_BASE: &str = "https://api.telegram.org";
+
+/// Seconds a `getUpdates` request waits for new messages before returning empty.
+const LONG_POLL_SECS: u64 = 50;
+
+/// How often to check for bot sources (or a token) when there are none.
+const IDLE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Retry delay after a failed poll, doubled per consecutive failure up to `MAX_BACKOFF`.
+const MIN_BACKOFF: Duration = Duration::from_secs(5);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Bot API chat IDs of supergroups and channels are the MTProto ID offset by -10^12.
+const CHANNEL_ID_OFFSET: i64 = 1_000_000_000_000;
+
+#[derive(Debug, Deserialize)]
+struct ApiResponse<T> {
+    ok: bool,
+    result: Option<T>,
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Update {
+    message: Option<Message>,
+    channel_post: Option<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    message_id: i64,
+    date: i64,
+    chat: Chat,
+    from: Option<User>,
+    sender_chat: Option<Chat>,
+    author_signature: Option<String>,
+    text: Option<String>,
+    caption: Option<String>,
+    reply_to_message: Option<ReplyTo>,
+    forward_origin: Option<ForwardOrigin>,
+    media_group_id: Option<String>,
+    /// Everything else; media is told apart by which field is present.
+    #[serde(flatten)]
+    rest: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chat {
+    id: i64,
+    title: Option<String>,
+    username: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct User {
+    id: i64,
+    first_name: String,
+    last_name: Option<String>,
+}
+
+impl User {
+    fn name(&self) -> String {
+        match self.last_name {
+            Some(ref last) => format!("{} {last}", self.first_name),
+            None => self.first_name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ReplyTo {
+    message_id: i64,
+}
+
+/// `MessageOrigin`: which fields are set depends on `type` ("user", "hidden_user", "chat",
+/// "channel"). A struct rather than an enum so a new origin type doesn't fail the batch.
+#[derive(Debug, Deserialize)]
+struct ForwardOrigin {
+    sender_user: Option<User>,
+    sender_user_name: Option<String>,
+    sender_chat: Option<Chat>,
+    chat: Option<Chat>,
+    author_signature: Option<String>,
+}
+
+/// Media fields of a Bot API message and the `media_type` the listener stores for them.
+/// Voice, video and the like are documents to MTProto, so they are here too.
+const MEDIA_TYPES: &[(&str, &str)] = &[
+    ("photo", "photo"),
+    ("sticker", "sticker"),
+    ("contact", "contact"),
+    ("poll", "poll"),
+    ("dice", "dice"),
+    ("venue", "venue"),
+    ("document", "document"),
+    ("video", "document"),
+    ("animation", "document"),
+    ("audio", "document"),
+    ("voice", "document"),
+    ("video_note", "document"),
+    ("story", "other"),
+    ("game", "other"),
+    ("invoice", "other"),
+];
+
+/// A Bot API chat ID with its kind: the bare IDs of a user, a basic group and a channel can
+/// be equal, so chats are told apart by the whole peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Peer {
+    User(i64),
+    Group(i64),
+    /// Channels and supergroups.
+    Channel(i64),
+}
+
+impl Peer {
+    /// `-100…` is a channel or supergroup, any other negative ID a basic group, a positive
+    /// one a user.
+    fn from_bot_api(id: i64) -> Self {
+        if id <= -CHANNEL_ID_OFFSET {
+            Peer::Channel(-id - CHANNEL_ID_OFFSET)
+        } else if id < 0 {
+            Peer::Group(-id)
+        } else {
+            Peer::User(id)
+        }
+    }
+
+    /// The chat ID as MTProto reports it, so items get the same `chat_id`, links and dedup
+    /// keys as when the chat is read through an account.
+    fn bare_id(self) -> i64 {
+        match self {
+            Peer::User(id) | Peer::Group(id) | Peer::Channel(id) => id,
+        }
+    }
+
+    /// The listener's `chat_type` metadata value.
+    fn chat_type(self) -> &'static str {
+        match self {
+            Peer::User(_) => "user",
+            Peer::Group(_) => "chat",
+            Peer::Channel(_) => "channel",
+        }
+    }
+}
+
+/// Long-poll the Bot API for messages of the chats `telegram_bot` sources read, and store
+/// them. Runs until cancelled; idles while there are no bot sources or no token. The update
+/// offset is kept in the settings table, so a restart resumes where it stopped (Telegram holds
+/// undelivered updates for 24 hours).
+pub async fn polling_loop(pool: SqlitePool, config_rx: watch::Receiver<Arc<Config>>, cancel: CancellationToken) {
+    info!("Telegram bot poller started");
+    let builder = reqwest::Client::builder()
+        .timeout(Duration::from_secs(LONG_POLL_SECS + 15))
+        .user_agent(concat!("pail/", env!("CARGO_PKG_VERSION")));
+    // The proxy is read once; changing it takes a restart
+    let proxy = config_rx.borrow().network.proxy.clone();
+    let client = match http_client::with_proxy(builder, proxy.as_deref()).and_then(|builder| builder.build()) {
+        Ok(client) => client,
+        Err(e) => {
+            error!(error = %e, "failed to build Telegram bot HTTP client");
+            return;
+        }
+    };
+
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        let config = config_rx.borrow().clone();
+        let sources: Vec<Source> = match store::get_all_enabled_sources(&pool).await {
+            Ok(sources) => sources
+                .into_iter()
+                .filter(|s| s.source_type == "telegram_bot")
+                .collect(),
+            Err(e) => {
+                error!(error = %e, "failed to load Telegram bot sources");
+                Vec::new()
+            }
+        };
+        let Some(token) = config.telegram.bot_token.clone().filter(|_| !sources.is_empty()) else {
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = tokio::time::sleep(IDLE_INTERVAL) => continue,
+            }
+        };
+
+        let polled = tokio::select! {
+            _ = cancel.cancelled() => break,
+            polled = poll_once(&client, &pool, &token, &sources) => polled,
+        };
+        match polled {
+            Ok(()) => backoff = MIN_BACKOFF,
+            Err(e) => {
+                warn!(error = format!("{e:#}"), retry_in = ?backoff, "Telegram bot poll failed");
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = tokio::time::sleep(backoff) => {}
+                }
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+    info!("Telegram bot poller shutting down");
+}
+
+/// One `getUpdates` round: fetch, store the items, then advance the offset. If storing fails
+/// the offset stays, and Telegram delivers the same updates again.
+async fn poll_once(client: &reqwest::Client, pool: &SqlitePool, token: &str, sources: &[Source]) -> Result<()> {
+    // Offsets are per bot; a new token starts from whatever Telegram still holds
+    let bot_id = token.split(':').next().unwrap_or_default();
+    let offset_key = format!("tg_bot_offset:{bot_id}");
+    let offset: i64 = store::get_setting(pool, &offset_key)
+        .await?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+
+    let updates = get_updates(client, token, offset).await?;
+    let Some(last_update_id) = updates.iter().filter_map(|u| u.get("update_id")?.as_i64()).max() else {
+        return Ok(());
+    };
+
+    let mut items = Vec::new();
+    // Album parts in this batch, keyed by (source_id, chat, grouped_id)
+    let mut albums: HashMap<(String, Peer, i64), Vec<ContentItem>> = HashMap::new();
+    for update in updates {
+        let update: Update = match serde_json::from_value(update) {
+            Ok(update) => update,
+            Err(e) => {
+                warn!(error = %e, "skipping Telegram bot update that failed to parse");
+                continue;
+            }
+        };
+        let Some(msg) = update.message.or(update.channel_post) else {
+            continue;
+        };
+        let peer = Peer::from_bot_api(msg.chat.id);
+        let matching: Vec<&Source> = sources.iter().filter(|s| reads_chat(s, &msg.chat)).collect();
+        if matching.is_empty() {
+            debug!(
+                chat_id = msg.chat.id,
+                chat = msg.chat.title.as_deref().unwrap_or_default(),
+                "ignoring message from a chat no telegram_bot source reads"
+            );
+            continue;
+        }
+        for source in matching {
+            let Some(item) = message_to_content_item(&msg, &source.id) else {
+                continue;
+            };
+            match msg.media_group_id.as_deref().and_then(|id| id.parse::<i64>().ok()) {
+                Some(grouped_id) => albums
+                    .entry((source.id.clone(), peer, grouped_id))
+                    .or_default()
+                    .push(item),
+                None => items.push(item),
+            }
+        }
+    }
+
+    // Parts can arrive in separate batches: a later one is merged into the stored album
+    let mut grown = Vec::new();
+    for parts in albums.into_values() {
+        let Some(album) = fetch_tg::coalesce_album(parts) else {
+            continue;
+        };
+        match store::get_content_item(pool, &album.source_id, &album.dedup_key).await? {
+            Some(stored) => grown.extend(fetch_tg::coalesce_album(vec![stored, album])),
+            None => items.push(album),
+        }
+    }
+
+    store::upsert_content_items(pool, &items)
+        .await
+        .context("storing Telegram bot items")?;
+    for album in &grown {
+        store::replace_album(pool, album)
+            .await
+            .context("storing Telegram bot album parts")?;
+    }
+    if !grown.is_empty() {
+        debug!(
+            albums = grown.len(),
+            "merged late parts into stored Telegram bot albums"
+        );
+    }
+    let now = Utc::now();
+    let mut newest: HashMap<&str, DateTime<Utc>> = HashMap::new();
+    for item in &items {
+        let date = newest.entry(item.source_id.as_str()).or_insert(item.original_date);
+        *date = (*date).max(item.original_date);
+    }
+    for (source_id, newest) in newest {
+        if let Err(e) = store::record_fetch_success(pool, source_id, now, Some(newest)).await {
+            warn!(source_id, error = %e, "failed to record Telegram bot delivery");
+        }
+    }
+    if !items.is_empty() {
+        info!(items = items.len(), "received Telegram bot messages");
+    }
+
+    store::set_setting(pool, &offset_key, &(last_update_id + 1).to_string()).await
+}
+
+/// `getUpdates` with long polling. Updates are returned raw so one that doesn't parse can be
+/// skipped without losing the batch.
+async fn get_updates(client: &reqwest::Client, token: &str, offset: i64) -> Result<Vec<serde_json::Value>> {
+    let response = client
+        .post(format!("{API_BASE}/bot{token}/getUpdates"))
+        .json(&serde_json::json!({
+            "offset": offset,
+            "timeout": LONG_POLL_SECS,
+            "allowed_updates": ["message", "channel_post"],
+        }))
+        .send()
+        .await
+        // The URL holds the token
+        .map_err(|e| e.without_url())
+        .context("requesting getUpdates")?;
+    let status = response.status();
+    let body: ApiResponse<Vec<serde_json::Value>> = response
+        .json()
+        .await
+        .map_err(|e| e.without_url())
+        .with_context(|| format!("parsing getUpdates response ({status})"))?;
+    match body.result {
+        Some(updates) if body.ok => Ok(updates),
+        // 409 here means a webhook is set for the bot, or another process polls with it
+        _ => anyhow::bail!(
+            "getUpdates returned {status}: {}",
+            body.description.as_deref().unwrap_or("no description")
+        ),
+    }
+}
+
+/// Whether `source` reads `chat`, by `tg_id` or `tg_username`. A `tg_id` in Bot API form names
+/// one peer; a bare (positive) one matches the channel or group with that ID, never a private
+/// chat with a user who happens to share it.
+fn reads_chat(source: &Source, chat: &Chat) -> bool {
+    let peer = Peer::from_bot_api(chat.id);
+    let by_id = source.tg_id.is_some_and(|id| match Peer::from_bot_api(id) {
+        Peer::User(bare) => matches!(peer, Peer::Group(id) | Peer::Channel(id) if id == bare),
+        wanted => wanted == peer,
+    });
+    let by_username = match (source.tg_username.as_deref(), chat.username.as_deref()) {
+        (Some(wanted), Some(username)) => wanted.trim_start_matches('@').eq_ignore_ascii_case(username),
+        _ => false,
+    };
+    by_id || by_username
+}
+
+/// Convert a Bot API message to a ContentItem shaped like `fetch_tg::message_to_content_item`'s,
+/// minus the engagement counts the Bot API doesn't report. Returns None for messages with
+/// neither text nor media (service messages: joins, pins, title changes).
+fn message_to_content_item(msg: &Message, source_id: &str) -> Option<ContentItem> {
+    let peer = Peer::from_bot_api(msg.chat.id);
+    let chat_id = peer.bare_id();
+    let message_id = msg.message_id;
+    let text = msg.text.clone().or_else(|| msg.caption.clone()).unwrap_or_default();
+    let media_type = MEDIA_TYPES
+        .iter()
+        .find(|(field, _)| msg.rest.contains_key(*field))
+        .map(|(_, media_type)| *media_type)
+        .or_else(|| {
+            msg.rest.get("location").map(|location| {
+                if location.get("live_period").is_some() {
+                    "geo_live"
+                } else {
+                    "geo"
+                }
+            })
+        });
+    if text.is_empty() && media_type.is_none() {
+        return None;
+    }
+
+    let content_type = if msg.forward_origin.is_some() {
+        "forward"
+    } else if media_type.is_some() {
+        "media"
+    } else {
+        "text"
+    };
+
+    // Channel posts and anonymous admins are sent as a chat; group members as a user
+    let author = match (&msg.sender_chat, &msg.from) {
+        (Some(chat), _) => msg.author_signature.clone().or_else(|| chat.title.clone()),
+        (None, Some(user)) => Some(user.name()),
+        (None, None) => None,
+    };
+
+    let url = match msg.chat.username {
+        Some(ref username) => format!("https://t.me/{username}/{message_id}"),
+        None => format!("https://t.me/c/{chat_id}/{message_id}"),
+    };
+
+    let mut meta = serde_json::Map::new();
+    meta.insert("message_id".to_string(), serde_json::json!(message_id));
+    meta.insert("chat_id".to_string(), serde_json::json!(chat_id));
+    meta.insert("chat_type".to_string(), serde_json::json!(peer.chat_type()));
+    if let Some(ref reply) = msg.reply_to_message {
+        meta.insert("reply_to_msg_id".to_string(), serde_json::json!(reply.message_id));
+    }
+    if let Some(ref origin) = msg.forward_origin {
+        let from_chat = origin.chat.as_ref().or(origin.sender_chat.as_ref());
+        let from_name = origin
+            .sender_user
+            .as_ref()
+            .map(User::name)
+            .or_else(|| origin.sender_user_name.clone())
+            .or_else(|| from_chat.and_then(|c| c.title.clone()));
+        if let Some(name) = from_name {
+            meta.insert("forward_from".to_string(), serde_json::json!(name));
+        }
+        let from_id = origin
+            .sender_user
+            .as_ref()
+            .map(|u| u.id)
+            .or_else(|| from_chat.map(|c| Peer::from_bot_api(c.id).bare_id()));
+        if let Some(id) = from_id {
+            meta.insert("forward_from_id".to_string(), serde_json::json!(id));
+        }
+        if let Some(ref post_author) = origin.author_signature {
+            meta.insert("forward_post_author".to_string(), serde_json::json!(post_author));
+        }
+    }
+    if let Some(media_type) = media_type {
+        meta.insert("media_type".to_string(), serde_json::json!(media_type));
+    }
+    if let Some(ref username) = msg.chat.username {
+        meta.insert("chat_username".to_string(), serde_json::json!(username));
+    }
+    if let Some(grouped_id) = msg.media_group_id.as_deref().and_then(|id| id.parse::<i64>().ok()) {
+        meta.insert("grouped_id".to_string(), serde_json::json!(grouped_id));
+    }
+
+    Some(ContentItem {
+        id: Uuid::new_v4().to_string(),
+        source_id: source_id.to_string(),
+        ingested_at: Utc::now(),
+        original_date: DateTime::from_timestamp(msg.date, 0).unwrap_or_else(Utc::now),
+        content_type: content_type.to_string(),
+        title: None,
+        body: text,
+        url: Some(url),
+        author,
+        metadata: serde_json::Value::Object(meta).to_string(),
+        // Same key as the listener's, so switching a chat between modes doesn't duplicate it
+        dedup_key: format!("tg:{chat_id}:{message_id}"),
+        upstream_changed: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(chat_id: i64, message_id: i64, extra: serde_json::Value) -> Message {
+        let mut value = serde_json::json!({
+            "message_id": message_id,
+            "date": 1_750_000_000 + message_id,
+            "chat": {"id": chat_id, "title": "Chat"},
+        });
+        value
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn source(tg_id: Option<i64>, tg_username: Option<&str>) -> Source {
+        Source {
+            id: "src".to_string(),
+            source_type: "telegram_bot".to_string(),
+            tg_id,
+            tg_username: tg_username.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_peer_from_bot_api() {
+        assert_eq!(Peer::from_bot_api(-1001234567890), Peer::Channel(1234567890));
+        assert_eq!(Peer::from_bot_api(-4567), Peer::Group(4567));
+        assert_eq!(Peer::from_bot_api(4567), Peer::User(4567));
+        assert_eq!(Peer::from_bot_api(-1001234567890).bare_id(), 1234567890);
+        assert_eq!(Peer::from_bot_api(-4567).chat_type(), "chat");
+    }
+
+    #[test]
+    fn test_reads_chat_by_whole_peer() {
+        let chat = |id: i64, username: Option<&str>| Chat {
+            id,
+            title: None,
+            username: username.map(str::to_string),
+        };
+        let channel = chat(-1001234567890, Some("news"));
+        assert!(reads_chat(&source(Some(-1001234567890), None), &channel));
+        assert!(reads_chat(&source(Some(1234567890), None), &channel));
+        assert!(reads_chat(&source(None, Some("@News")), &channel));
+        assert!(!reads_chat(&source(Some(-1234567890), None), &channel));
+        // A user sharing the channel's bare ID doesn't reach the channel's source
+        let user = chat(1234567890, None);
+        assert!(!reads_chat(&source(Some(1234567890), None), &user));
+        assert!(!reads_chat(&source(Some(-1001234567890), None), &user));
+        assert!(reads_chat(&source(Some(-4567), None), &chat(-4567, None)));
+        assert!(reads_chat(&source(Some(4567), None), &chat(-4567, None)));
+    }
+
+    #[test]
+    fn test_message_to_content_item() {
+        let msg = message(
+            -1001234567890,
+            7,
+            serde_json::json!({"caption": "Look", "photo": [], "media_group_id": "99"}),
+        );
+        let item = message_to_content_item(&msg, "src").unwrap();
+        assert_eq!(item.dedup_key, "tg:1234567890:7");
+        assert_eq!(item.url.as_deref(), Some("https://t.me/c/1234567890/7"));
+        assert_eq!(item.content_type, "media");
+        let meta: serde_json::Value = serde_json::from_str(&item.metadata).unwrap();
+        assert_eq!(meta["chat_type"], "channel");
+        assert_eq!(meta["media_type"], "photo");
+        assert_eq!(meta["grouped_id"], 99);
+
+        // Service messages have neither text nor media
+        let join = message(-4567, 8, serde_json::json!({"new_chat_members": []}));
+        assert!(message_to_content_item(&join, "src").is_none());
+    }
+
+    #[test]
+    fn test_album_parts_merge_across_batches() {
+        let part = |id: i64, caption: Option<&str>| {
+            let mut extra = serde_json::json!({"photo": [], "media_group_id": "99"});
+            if let Some(caption) = caption {
+                extra["caption"] = serde_json::json!(caption);
+            }
+            message_to_content_item(&message(-1001234567890, id, extra), "src").unwrap()
+        };
+        let stored = fetch_tg::coalesce_album(vec![part(11, None), part(10, Some("First"))]).unwrap();
+        let late = fetch_tg::coalesce_album(vec![part(12, Some("Late caption"))]).unwrap();
+        assert_eq!(stored.dedup_key, late.dedup_key);
+
+        let merged = fetch_tg::coalesce_album(vec![stored.clone(), late, part(11, None)]).unwrap();
+        assert_eq!(merged.id, stored.id);
+        assert_eq!(merged.body, "First\n\nLate caption");
+        let meta: serde_json::Value = serde_json::from_str(&merged.metadata).unwrap();
+        assert_eq!(meta["message_ids"], serde_json::json!([10, 11, 12]));
+        assert_eq!(meta["media_types"], serde_json::json!(["photo", "photo", "photo"]));
+        assert_eq!(meta["media_type"], "album");
+    }
+}
@@ -0,0 +1,379 @@
+//! Update recording for diagnosing Telegram gaps: with `[telegram].debug_updates`, the type,
+//! pts and message ID of every update an account receives are kept in `tg_debug_updates` for
+//! `debug_updates_keep`, and `pail tg debug gaps` finds the ranges that never arrived (see
+//! docs/specs/telegram.md "Debugging Gaps").
+
+use std::collections::{BTreeMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use grammers_session::updates::UpdatesLike;
+use grammers_tl_types as tl;
+use sqlx::SqlitePool;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use crate::models::TgDebugUpdate;
+use crate::store;
+
+/// Most recorded updates written in one transaction.
+const WRITE_BATCH: usize = 500;
+
+/// Record the updates passing from the connection to the listener. Returns the receiver the
+/// listener reads instead of `updates_rx`; updates are forwarded as they come, recording
+/// happens on a separate task so the stream never waits for the database.
+pub fn tap(
+    mut updates_rx: mpsc::UnboundedReceiver<UpdatesLike>,
+    account: String,
+    pool: SqlitePool,
+) -> mpsc::UnboundedReceiver<UpdatesLike> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let (record_tx, record_rx) = mpsc::unbounded_channel();
+    tokio::spawn(writer_loop(record_rx, pool));
+    tokio::spawn(async move {
+        while let Some(updates) = updates_rx.recv().await {
+            for record in records(&account, &updates, Utc::now()) {
+                let _ = record_tx.send(record);
+            }
+            if tx.send(updates).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+async fn writer_loop(mut record_rx: mpsc::UnboundedReceiver<TgDebugUpdate>, pool: SqlitePool) {
+    while let Some(first) = record_rx.recv().await {
+        let mut batch = vec![first];
+        while batch.len() < WRITE_BATCH
+            && let Ok(record) = record_rx.try_recv()
+        {
+            batch.push(record);
+        }
+        match store::insert_tg_debug_updates(&pool, &batch).await {
+            Ok(()) => debug!(updates = batch.len(), "recorded TG updates"),
+            Err(e) => warn!(updates = batch.len(), "failed to record TG updates: {e:#}"),
+        }
+    }
+}
+
+/// One record per update in a batch from the server.
+fn records(account: &str, updates: &UpdatesLike, now: DateTime<Utc>) -> Vec<TgDebugUpdate> {
+    let record = |container: &str, update_type: String| TgDebugUpdate {
+        account: account.to_string(),
+        received_at: now,
+        container: container.to_string(),
+        update_type,
+        channel_id: None,
+        pts: None,
+        pts_count: None,
+        message_id: None,
+    };
+    let with_update = |container: &str, update: &tl::enums::Update| {
+        let (channel_id, pts, pts_count, message_id) = sequence(update);
+        TgDebugUpdate {
+            channel_id,
+            pts,
+            pts_count,
+            message_id,
+            ..record(container, variant_name(update))
+        }
+    };
+
+    // Results of the account's own requests (affected messages and the like)
+    let UpdatesLike::Updates(updates) = updates else {
+        return vec![record("other", "Other".to_string())];
+    };
+    match updates {
+        tl::enums::Updates::TooLong => vec![record("updatesTooLong", "TooLong".to_string())],
+        tl::enums::Updates::UpdateShortMessage(u) => vec![TgDebugUpdate {
+            pts: Some(u.pts.into()),
+            pts_count: Some(u.pts_count.into()),
+            message_id: Some(u.id.into()),
+            ..record("updateShortMessage", "ShortMessage".to_string())
+        }],
+        tl::enums::Updates::UpdateShortChatMessage(u) => vec![TgDebugUpdate {
+            pts: Some(u.pts.into()),
+            pts_count: Some(u.pts_count.into()),
+            message_id: Some(u.id.into()),
+            ..record("updateShortChatMessage", "ShortChatMessage".to_string())
+        }],
+        tl::enums::Updates::UpdateShortSentMessage(u) => vec![TgDebugUpdate {
+            pts: Some(u.pts.into()),
+            pts_count: Some(u.pts_count.into()),
+            message_id: Some(u.id.into()),
+            ..record("updateShortSentMessage", "ShortSentMessage".to_string())
+        }],
+        tl::enums::Updates::UpdateShort(u) => vec![with_update("updateShort", &u.update)],
+        tl::enums::Updates::Combined(u) => u.updates.iter().map(|up| with_update("updatesCombined", up)).collect(),
+        tl::enums::Updates::Updates(u) => u.updates.iter().map(|up| with_update("updates", up)).collect(),
+    }
+}
+
+/// Name of an enum variant from its `Debug` form (`NewChannelMessage(UpdateNewChannelMessage {..})`).
+fn variant_name(value: &impl std::fmt::Debug) -> String {
+    let debug = format!("{value:?}");
+    let end = debug.find(['(', ' ', '{']).unwrap_or(debug.len());
+    debug[..end].to_string()
+}
+
+/// Channel (for channel pts), pts, pts_count and message ID of the updates that carry them.
+/// Updates of the account's common sequence have no channel.
+fn sequence(update: &tl::enums::Update) -> (Option<i64>, Option<i64>, Option<i64>, Option<i64>) {
+    let common =
+        |pts: i32, count: i32, message_id: Option<i64>| (None, Some(pts.into()), Some(count.into()), message_id);
+    let channel = |id: Option<i64>, pts: i32, count: i32, message_id: Option<i64>| {
+        (id, Some(pts.into()), Some(count.into()), message_id)
+    };
+    match update {
+        tl::enums::Update::NewMessage(u) => common(u.pts, u.pts_count, Some(message_id(&u.message))),
+        tl::enums::Update::EditMessage(u) => common(u.pts, u.pts_count, Some(message_id(&u.message))),
+        tl::enums::Update::DeleteMessages(u) => common(u.pts, u.pts_count, None),
+        tl::enums::Update::ReadHistoryInbox(u) => common(u.pts, u.pts_count, None),
+        tl::enums::Update::ReadHistoryOutbox(u) => common(u.pts, u.pts_count, None),
+        tl::enums::Update::ReadMessagesContents(u) => common(u.pts, u.pts_count, None),
+        tl::enums::Update::WebPage(u) => common(u.pts, u.pts_count, None),
+        tl::enums::Update::PinnedMessages(u) => common(u.pts, u.pts_count, None),
+        tl::enums::Update::NewChannelMessage(u) => channel(
+            message_channel(&u.message),
+            u.pts,
+            u.pts_count,
+            Some(message_id(&u.message)),
+        ),
+        tl::enums::Update::EditChannelMessage(u) => channel(
+            message_channel(&u.message),
+            u.pts,
+            u.pts_count,
+            Some(message_id(&u.message)),
+        ),
+        tl::enums::Update::DeleteChannelMessages(u) => channel(Some(u.channel_id), u.pts, u.pts_count, None),
+        tl::enums::Update::ChannelWebPage(u) => channel(Some(u.channel_id), u.pts, u.pts_count, None),
+        tl::enums::Update::PinnedChannelMessages(u) => channel(Some(u.channel_id), u.pts, u.pts_count, None),
+        // The server dropped this channel's updates; the client has to fetch the difference
+        tl::enums::Update::ChannelTooLong(u) => (Some(u.channel_id), u.pts.map(i64::from), None, None),
+        _ => (None, None, None, None),
+    }
+}
+
+fn message_id(message: &tl::enums::Message) -> i64 {
+    match message {
+        tl::enums::Message::Empty(m) => m.id.into(),
+        tl::enums::Message::Message(m) => m.id.into(),
+        tl::enums::Message::Service(m) => m.id.into(),
+    }
+}
+
+fn message_channel(message: &tl::enums::Message) -> Option<i64> {
+    let peer = match message {
+        tl::enums::Message::Empty(m) => m.peer_id.as_ref()?,
+        tl::enums::Message::Message(m) => &m.peer_id,
+        tl::enums::Message::Service(m) => &m.peer_id,
+    };
+    match peer {
+        tl::enums::Peer::Channel(c) => Some(c.channel_id),
+        _ => None,
+    }
+}
+
+/// Gaps in one update sequence: the account's common pts, or one channel's.
+pub struct SequenceReport {
+    /// `None` for the common sequence (private chats, basic groups).
+    pub channel_id: Option<i64>,
+    pub updates: usize,
+    pub first_pts: Option<i64>,
+    pub last_pts: Option<i64>,
+    pub pts_gaps: Vec<PtsGap>,
+    /// Message IDs missing between new messages the channel delivered (channels only).
+    pub message_gaps: Vec<MessageGap>,
+    /// When the server reported the sequence too long (`updatesTooLong` / `updateChannelTooLong`).
+    pub too_long: Vec<DateTime<Utc>>,
+}
+
+/// pts values no recorded update accounts for.
+pub struct PtsGap {
+    pub from: i64,
+    pub to: i64,
+    /// Around when: the updates received before and after the gap.
+    pub after: DateTime<Utc>,
+    pub before: DateTime<Utc>,
+}
+
+pub struct MessageGap {
+    pub from: i64,
+    pub to: i64,
+    /// How many of the missing messages are stored anyway (gap recovery or CLI fetch).
+    pub stored: usize,
+}
+
+/// Find the gaps in recorded updates. `stored_ids` returns the message IDs of a channel that
+/// are stored as items.
+pub fn analyze(updates: &[TgDebugUpdate], stored_ids: impl Fn(i64) -> HashSet<i64>) -> Vec<SequenceReport> {
+    let mut sequences: BTreeMap<Option<i64>, Vec<&TgDebugUpdate>> = BTreeMap::new();
+    for update in updates {
+        let channel = update
+            .channel_id
+            .filter(|_| update.pts.is_some() || update.update_type == "ChannelTooLong");
+        sequences.entry(channel).or_default().push(update);
+    }
+
+    let mut reports = Vec::new();
+    for (channel_id, records) in sequences {
+        let mut too_long: Vec<DateTime<Utc>> = records
+            .iter()
+            .filter(|u| matches!(u.update_type.as_str(), "TooLong" | "ChannelTooLong"))
+            .filter(|u| channel_id.is_some() || u.update_type == "TooLong")
+            .map(|u| u.received_at)
+            .collect();
+        too_long.dedup();
+
+        let mut sequenced: Vec<&TgDebugUpdate> = records
+            .iter()
+            .copied()
+            .filter(|u| u.pts.is_some() && u.pts_count.is_some())
+            .collect();
+        sequenced.sort_by_key(|u| (u.pts, u.received_at));
+        sequenced.dedup_by_key(|u| u.pts);
+
+        // Each update moves pts by pts_count: one that starts above the previous end
+        // means updates in between never arrived
+        let mut pts_gaps = Vec::new();
+        for pair in sequenced.windows(2) {
+            let (prev, next) = (pair[0], pair[1]);
+            let (Some(prev_pts), Some(next_pts), Some(count)) = (prev.pts, next.pts, next.pts_count) else {
+                continue;
+            };
+            let start = next_pts - count;
+            if start > prev_pts {
+                pts_gaps.push(PtsGap {
+                    from: prev_pts + 1,
+                    to: start,
+                    after: prev.received_at,
+                    before: next.received_at,
+                });
+            }
+        }
+
+        let mut message_gaps = Vec::new();
+        if let Some(channel) = channel_id {
+            let mut ids: Vec<i64> = records
+                .iter()
+                .filter(|u| u.update_type == "NewChannelMessage")
+                .filter_map(|u| u.message_id)
+                .collect();
+            ids.sort_unstable();
+            ids.dedup();
+            if ids.windows(2).any(|w| w[1] - w[0] > 1) {
+                let stored = stored_ids(channel);
+                for pair in ids.windows(2) {
+                    if pair[1] - pair[0] > 1 {
+                        let (from, to) = (pair[0] + 1, pair[1] - 1);
+                        message_gaps.push(MessageGap {
+                            from,
+                            to,
+                            stored: (from..=to).filter(|id| stored.contains(id)).count(),
+                        });
+                    }
+                }
+            }
+        }
+
+        reports.push(SequenceReport {
+            channel_id,
+            updates: records.len(),
+            first_pts: sequenced.first().and_then(|u| u.pts),
+            last_pts: sequenced.last().and_then(|u| u.pts),
+            pts_gaps,
+            message_gaps,
+            too_long,
+        });
+    }
+    reports
+}
+
+/// Message IDs of a chat stored as items, from their `tg:<chat>:<message>` dedup keys. Album
+/// items are keyed by their group, so album parts don't count.
+pub async fn stored_message_ids(pool: &SqlitePool, chat_id: i64) -> anyhow::Result<HashSet<i64>> {
+    let prefix = format!("tg:{chat_id}:");
+    Ok(store::get_dedup_keys_with_prefix(pool, &prefix)
+        .await?
+        .iter()
+        .filter_map(|key| key.strip_prefix(&prefix)?.parse().ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(
+        minute: i64,
+        update_type: &str,
+        channel_id: Option<i64>,
+        pts: Option<(i64, i64)>,
+        message_id: Option<i64>,
+    ) -> TgDebugUpdate {
+        TgDebugUpdate {
+            account: "main".to_string(),
+            received_at: DateTime::<Utc>::UNIX_EPOCH + chrono::Duration::minutes(minute),
+            container: "updates".to_string(),
+            update_type: update_type.to_string(),
+            channel_id,
+            pts: pts.map(|(pts, _)| pts),
+            pts_count: pts.map(|(_, count)| count),
+            message_id,
+        }
+    }
+
+    #[test]
+    fn test_analyze_finds_pts_gaps_per_sequence() {
+        let updates = [
+            update(0, "NewMessage", None, Some((10, 1)), Some(1)),
+            update(1, "DeleteMessages", None, Some((12, 2)), None),
+            // 13-14 never arrived
+            update(2, "NewMessage", None, Some((15, 1)), Some(2)),
+            // A repeat of an update already recorded is no gap
+            update(3, "NewMessage", None, Some((15, 1)), Some(2)),
+            update(4, "NewChannelMessage", Some(7), Some((100, 1)), Some(50)),
+            update(5, "NewChannelMessage", Some(7), Some((101, 1)), Some(51)),
+            update(6, "TooLong", None, None, None),
+        ];
+        let reports = analyze(&updates, |_| HashSet::new());
+        assert_eq!(reports.len(), 2);
+
+        let common = &reports[0];
+        assert_eq!(common.channel_id, None);
+        assert_eq!(common.updates, 5);
+        assert_eq!((common.first_pts, common.last_pts), (Some(10), Some(15)));
+        let gaps: Vec<(i64, i64)> = common.pts_gaps.iter().map(|g| (g.from, g.to)).collect();
+        assert_eq!(gaps, [(13, 14)]);
+        assert_eq!(common.pts_gaps[0].after, updates[1].received_at);
+        assert_eq!(common.pts_gaps[0].before, updates[2].received_at);
+        assert_eq!(common.too_long, [updates[6].received_at]);
+
+        let channel = &reports[1];
+        assert_eq!(channel.channel_id, Some(7));
+        assert!(channel.pts_gaps.is_empty() && channel.message_gaps.is_empty() && channel.too_long.is_empty());
+    }
+
+    #[test]
+    fn test_analyze_counts_stored_messages_of_gaps() {
+        let updates = [
+            update(0, "NewChannelMessage", Some(7), Some((100, 1)), Some(10)),
+            update(1, "NewChannelMessage", Some(7), Some((101, 1)), Some(14)),
+            update(2, "ChannelTooLong", Some(7), None, None),
+            // Edits don't mark the messages around them as delivered
+            update(3, "EditChannelMessage", Some(7), Some((102, 1)), Some(20)),
+        ];
+        let reports = analyze(&updates, |channel| {
+            assert_eq!(channel, 7);
+            HashSet::from([11, 13, 99])
+        });
+        assert_eq!(reports.len(), 1);
+        let report = &reports[0];
+        assert_eq!(report.channel_id, Some(7));
+        let gaps: Vec<(i64, i64, usize)> = report.message_gaps.iter().map(|g| (g.from, g.to, g.stored)).collect();
+        assert_eq!(gaps, [(11, 13, 2)]);
+        assert_eq!(report.too_long, [updates[2].received_at]);
+        assert!(report.pts_gaps.is_empty());
+    }
+}
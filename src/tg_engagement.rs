@@ -0,0 +1,238 @@
+//! Telegram views, forwards and reactions: captured on ingestion, refreshed before generation,
+//! and the per-source `min_views` / `min_reactions` filter (see docs/specs/telegram.md
+//! "Engagement").
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use sqlx::SqlitePool;
+use tracing::{debug, info, warn};
+
+use crate::config::{self, Config};
+use crate::fetch_tg;
+use crate::models::{ContentItem, Source};
+use crate::store;
+use crate::telegram::TgClients;
+
+/// Messages per `messages.getMessages` / `channels.getMessages` request (the API's limit).
+const REFRESH_BATCH: usize = 100;
+
+/// Engagement counts of a message, as stored in item metadata.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// Only channel posts have view counts.
+    pub views: Option<i64>,
+    pub forwards: Option<i64>,
+    /// Total across all reaction types.
+    pub reactions: i64,
+}
+
+impl Counts {
+    pub fn of(msg: &grammers_client::message::Message) -> Self {
+        Counts {
+            views: msg.view_count().map(i64::from),
+            forwards: msg.forward_count().map(i64::from),
+            reactions: i64::from(msg.reaction_count().unwrap_or(0)),
+        }
+    }
+
+    /// Field-wise maximum, for the parts of an album.
+    pub fn max(self, other: Counts) -> Counts {
+        Counts {
+            views: self.views.max(other.views),
+            forwards: self.forwards.max(other.forwards),
+            reactions: self.reactions.max(other.reactions),
+        }
+    }
+}
+
+pub fn read_counts(meta: &serde_json::Map<String, serde_json::Value>) -> Counts {
+    Counts {
+        views: meta.get("views").and_then(|v| v.as_i64()),
+        forwards: meta.get("forwards").and_then(|v| v.as_i64()),
+        reactions: meta.get("reactions").and_then(|v| v.as_i64()).unwrap_or(0),
+    }
+}
+
+pub fn insert_counts(meta: &mut serde_json::Map<String, serde_json::Value>, counts: &Counts) {
+    if let Some(views) = counts.views {
+        meta.insert("views".to_string(), serde_json::json!(views));
+    }
+    if let Some(forwards) = counts.forwards {
+        meta.insert("forwards".to_string(), serde_json::json!(forwards));
+    }
+    meta.insert("reactions".to_string(), serde_json::json!(counts.reactions));
+}
+
+/// Items of one chat whose counts are re-read: the item's index and its message IDs.
+type ChatItems = Vec<(usize, Vec<i32>)>;
+
+/// Re-read the counts of the window's TG items, which were stored when the message arrived
+/// (a live message has next to no views yet). Updates the items in place and in the database;
+/// returns the IDs of the items that were refreshed. Chats whose account isn't connected, or
+/// whose request fails, keep their stored counts.
+pub async fn refresh_counts(
+    clients: &TgClients,
+    pool: &SqlitePool,
+    sources: &[Source],
+    items: &mut [ContentItem],
+) -> HashSet<String> {
+    let accounts: HashMap<&str, &str> = sources
+        .iter()
+        .filter(|s| config::is_mtproto_source(&s.source_type))
+        .map(|s| (s.id.as_str(), s.tg_account()))
+        .collect();
+
+    // (account, chat_id) -> the chat's items
+    let mut chats: HashMap<(String, i64), ChatItems> = HashMap::new();
+    for (index, item) in items.iter().enumerate() {
+        let Some(account) = accounts.get(item.source_id.as_str()) else {
+            continue;
+        };
+        let meta: serde_json::Value = serde_json::from_str(&item.metadata).unwrap_or_default();
+        let Some(chat_id) = meta.get("chat_id").and_then(|v| v.as_i64()) else {
+            continue;
+        };
+        let message_ids: Vec<i32> = match meta.get("message_ids").and_then(|v| v.as_array()) {
+            Some(ids) => ids.iter().filter_map(|v| v.as_i64()).map(|id| id as i32).collect(),
+            None => meta
+                .get("message_id")
+                .and_then(|v| v.as_i64())
+                .map(|id| vec![id as i32])
+                .unwrap_or_default(),
+        };
+        if !message_ids.is_empty() {
+            chats
+                .entry((account.to_string(), chat_id))
+                .or_default()
+                .push((index, message_ids));
+        }
+    }
+    if chats.is_empty() {
+        return HashSet::new();
+    }
+
+    let mut refreshed = HashSet::new();
+    let mut updates = Vec::new();
+    for (i, ((account, chat_id), chat_items)) in chats.into_iter().enumerate() {
+        let Some(client) = clients.get(&account) else {
+            debug!(account = %account, chat_id, "Telegram account not connected, keeping stored counts");
+            continue;
+        };
+        // Same pacing as history fetches
+        if i > 0 {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        let peer_ref = match fetch_tg::resolve_peer_ref(pool, &account, chat_id).await {
+            Ok(peer_ref) => peer_ref,
+            Err(e) => {
+                warn!(
+                    account = %account,
+                    chat_id,
+                    error = format!("{e:#}"),
+                    "failed to resolve chat for count refresh"
+                );
+                continue;
+            }
+        };
+
+        let ids: Vec<i32> = chat_items.iter().flat_map(|(_, ids)| ids.iter().copied()).collect();
+        let mut counts: HashMap<i32, Counts> = HashMap::new();
+        let mut failed = false;
+        for chunk in ids.chunks(REFRESH_BATCH) {
+            match client.get_messages_by_id(peer_ref, chunk).await {
+                Ok(messages) => {
+                    counts.extend(messages.into_iter().flatten().map(|msg| (msg.id(), Counts::of(&msg))));
+                }
+                Err(e) => {
+                    warn!(account = %account, chat_id, error = %e, "failed to refresh TG message counts");
+                    failed = true;
+                    break;
+                }
+            }
+        }
+        if failed {
+            continue;
+        }
+
+        for (index, message_ids) in chat_items {
+            // Deleted messages come back empty; the item keeps what it had
+            let Some(fresh) = message_ids
+                .iter()
+                .filter_map(|id| counts.get(id).copied())
+                .reduce(Counts::max)
+            else {
+                continue;
+            };
+            let item = &mut items[index];
+            let mut meta: serde_json::Map<String, serde_json::Value> =
+                serde_json::from_str(&item.metadata).unwrap_or_default();
+            insert_counts(&mut meta, &fresh);
+            item.metadata = serde_json::Value::Object(meta).to_string();
+            updates.push((item.id.clone(), item.metadata.clone()));
+            refreshed.insert(item.id.clone());
+        }
+    }
+
+    if let Err(e) = store::update_item_metadata(pool, &updates).await {
+        warn!(error = format!("{e:#}"), "failed to store refreshed TG message counts");
+    }
+    info!(items = refreshed.len(), "refreshed TG message counts");
+    refreshed
+}
+
+/// Drop items of sources with `min_views` / `min_reactions` below the threshold. Only items
+/// whose counts were just refreshed are judged: stored counts of a live message are from the
+/// moment it was posted, so without a refresh the item is kept.
+pub fn filter_items(
+    config: &Config,
+    sources: &[Source],
+    refreshed: &HashSet<String>,
+    items: Vec<ContentItem>,
+) -> Vec<ContentItem> {
+    // Thresholds by DB source ID (config sources are keyed by name)
+    let thresholds: HashMap<&str, (Option<u32>, Option<u32>)> = sources
+        .iter()
+        .filter_map(|source| {
+            let configured = config.source.iter().find(|s| s.name == source.name)?;
+            (configured.min_views.is_some() || configured.min_reactions.is_some())
+                .then_some((source.id.as_str(), (configured.min_views, configured.min_reactions)))
+        })
+        .collect();
+    if thresholds.is_empty() {
+        return items;
+    }
+
+    let total = items.len();
+    let mut unrefreshed = 0;
+    let kept: Vec<ContentItem> = items
+        .into_iter()
+        .filter(|item| {
+            let Some((min_views, min_reactions)) = thresholds.get(item.source_id.as_str()) else {
+                return true;
+            };
+            if !refreshed.contains(&item.id) {
+                unrefreshed += 1;
+                return true;
+            }
+            let meta: serde_json::Map<String, serde_json::Value> =
+                serde_json::from_str(&item.metadata).unwrap_or_default();
+            let counts = read_counts(&meta);
+            let enough_views = match (min_views, counts.views) {
+                (Some(min), Some(views)) => views >= i64::from(*min),
+                _ => true,
+            };
+            let enough_reactions = min_reactions.is_none_or(|min| counts.reactions >= i64::from(min));
+            enough_views && enough_reactions
+        })
+        .collect();
+
+    if unrefreshed > 0 {
+        warn!(
+            items = unrefreshed,
+            "kept TG items without refreshed counts (account not connected?), min_views / min_reactions not applied"
+        );
+    }
+    info!(total, kept = kept.len(), "TG engagement filter applied");
+    kept
+}
@@ -1,51 +1,282 @@
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use grammers_client::Client;
 use grammers_client::client::UpdatesConfiguration;
 use grammers_client::update::Update;
 use grammers_session::updates::UpdatesLike;
 use grammers_tl_types as tl;
+use sentry::{Hub, SentryFutureExt};
 use sqlx::SqlitePool;
-use tokio::sync::{RwLock, mpsc};
+use tokio::sync::{Notify, RwLock, mpsc};
+use tokio::time::Instant;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
+use crate::config::{TelegramConfig, TgWriteQueueConfig};
 use crate::fetch_tg;
+use crate::health::Health;
+use crate::models::ContentItem;
 use crate::store;
 use crate::telegram;
+use crate::tg_media;
+
+/// How long an album waits for more parts after its latest one. Telegram sends an album's
+/// messages back to back, usually in the same update batch.
+const ALBUM_SETTLE: Duration = Duration::from_secs(2);
+
+/// How often the subscription map is rebuilt from the database, so folder channels toggled
+/// with `pail tg folder` from another process take effect without a restart.
+const SUBSCRIPTION_REFRESH: Duration = Duration::from_secs(60);
+
+/// Album parts received from the live stream, held until the album settles.
+#[derive(Default)]
+struct AlbumBuffer {
+    /// Keyed by (source_id, chat_id, grouped_id).
+    pending: HashMap<(String, i64, i64), PendingAlbum>,
+}
+
+struct PendingAlbum {
+    parts: Vec<ContentItem>,
+    last_part: Instant,
+}
+
+impl AlbumBuffer {
+    fn push(&mut self, key: (String, i64, i64), item: ContentItem) {
+        let album = self.pending.entry(key).or_insert_with(|| PendingAlbum {
+            parts: Vec::new(),
+            last_part: Instant::now(),
+        });
+        album.parts.push(item);
+        album.last_part = Instant::now();
+    }
+
+    /// When the oldest-settling album is due, if any.
+    fn next_deadline(&self) -> Option<Instant> {
+        self.pending.values().map(|a| a.last_part + ALBUM_SETTLE).min()
+    }
+
+    /// Queue albums that settled (or all of them, on shutdown) as one item each.
+    fn flush(&mut self, queue: &WriteQueue, all: bool) {
+        let now = Instant::now();
+        let due: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|(_, a)| all || a.last_part + ALBUM_SETTLE <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in due {
+            let Some(album) = self.pending.remove(&key) else {
+                continue;
+            };
+            let (_, chat_id, grouped_id) = key;
+            let parts = album.parts.len();
+            let Some(item) = fetch_tg::coalesce_album(album.parts) else {
+                continue;
+            };
+            queue.push(item);
+            debug!(chat_id, grouped_id, parts, "queued TG album");
+        }
+    }
+}
+
+/// Bounded buffer between the update stream and the database. The listener pushes without
+/// waiting; a writer task drains it in batches, one transaction each. When the database
+/// falls behind, the queue drops messages instead of stalling the stream (a stalled stream
+/// makes Telegram report update gaps, which trigger gap recovery).
+struct WriteQueue {
+    items: Mutex<VecDeque<ContentItem>>,
+    capacity: usize,
+    drop_oldest: bool,
+    notify: Notify,
+    dropped: AtomicU64,
+    closed: AtomicBool,
+}
+
+impl WriteQueue {
+    fn new(config: &TgWriteQueueConfig) -> Self {
+        Self {
+            items: Mutex::new(VecDeque::new()),
+            capacity: config.capacity,
+            drop_oldest: config.overflow == "drop_oldest",
+            notify: Notify::new(),
+            dropped: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    fn push(&self, item: ContentItem) {
+        {
+            let mut items = self.items.lock().unwrap();
+            if items.len() >= self.capacity {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                if !self.drop_oldest {
+                    return;
+                }
+                items.pop_front();
+            }
+            items.push_back(item);
+        }
+        self.notify.notify_one();
+    }
+
+    /// Up to `max` items from the front, and how many are left behind them.
+    fn take_batch(&self, max: usize) -> (Vec<ContentItem>, usize) {
+        let mut items = self.items.lock().unwrap();
+        let n = items.len().min(max);
+        let batch: Vec<_> = items.drain(..n).collect();
+        (batch, items.len())
+    }
+
+    /// Tell the writer to drain what's left and exit.
+    fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.notify.notify_one();
+    }
+}
+
+/// Drain the queue until it is closed and empty. Each batch is one transaction; a batch that
+/// fails as a whole is retried item by item so one bad row doesn't lose the rest.
+async fn writer_loop(queue: Arc<WriteQueue>, pool: SqlitePool, account: String, batch_size: usize, health: Arc<Health>) {
+    let mut reported_dropped = 0;
+    loop {
+        let (batch, depth) = queue.take_batch(batch_size);
+        if batch.is_empty() {
+            if queue.closed.load(Ordering::Acquire) {
+                break;
+            }
+            queue.notify.notified().await;
+            continue;
+        }
+
+        let written = match store::upsert_content_items(&pool, &batch).await {
+            Ok(()) => batch.len(),
+            Err(e) => {
+                warn!(account = %account, items = batch.len(), error = %e, "TG batch write failed, writing items one by one");
+                let mut written = 0;
+                for item in &batch {
+                    match store::upsert_content_item(&pool, item).await {
+                        Ok(()) => written += 1,
+                        Err(e) => warn!(
+                            source_id = %item.source_id,
+                            dedup_key = %item.dedup_key,
+                            error = %e,
+                            "failed to store TG message"
+                        ),
+                    }
+                }
+                written
+            }
+        };
+
+        let dropped = queue.dropped.load(Ordering::Relaxed);
+        if dropped > reported_dropped {
+            warn!(
+                account = %account,
+                dropped = dropped - reported_dropped,
+                depth,
+                "TG write queue full, dropped messages"
+            );
+            reported_dropped = dropped;
+        }
+        debug!(account = %account, written, depth, "wrote TG batch");
+        health.update_tg_account(&account, |tg| {
+            tg.write_queue.depth = depth;
+            tg.write_queue.written += written as u64;
+            tg.write_queue.dropped = dropped;
+            tg.write_queue.batches += 1;
+        });
+    }
+}
 
 /// Run the Telegram event listener loop.
 /// Receives live updates and stores messages from subscribed chats.
+#[allow(clippy::too_many_arguments)]
 pub async fn listener_loop(
     client: Client,
+    account: String,
     pool: SqlitePool,
     subscriptions: Arc<RwLock<HashMap<i64, Vec<String>>>>,
     updates_rx: mpsc::UnboundedReceiver<UpdatesLike>,
+    telegram_config: TelegramConfig,
+    health: Arc<Health>,
     cancel: CancellationToken,
 ) {
-    info!("Telegram listener started");
+    info!(account = %account, "Telegram listener started");
 
     let mut update_stream = client.stream_updates(updates_rx, UpdatesConfiguration::default()).await;
+    let mut albums = AlbumBuffer::default();
+    let write_queue = &telegram_config.write_queue;
+    let queue = Arc::new(WriteQueue::new(write_queue));
+    let writer = tokio::spawn(
+        writer_loop(
+            queue.clone(),
+            pool.clone(),
+            account.clone(),
+            write_queue.batch_size,
+            health.clone(),
+        )
+        .bind_hub(Hub::current()),
+    );
+
+    // Photos are downloaded off the update loop, so a slow download never stalls the stream
+    let (media_tx, downloader) = if telegram_config.media.download {
+        let (tx, handle) = tg_media::spawn(client.clone(), pool.clone(), account.clone());
+        (Some(tx), Some(handle))
+    } else {
+        (None, None)
+    };
+
+    let mut refresh = tokio::time::interval_at(Instant::now() + SUBSCRIPTION_REFRESH, SUBSCRIPTION_REFRESH);
+    refresh.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
     loop {
+        let album_deadline = albums.next_deadline();
         tokio::select! {
             _ = cancel.cancelled() => {
-                info!("Telegram listener shutting down");
+                info!(account = %account, "Telegram listener shutting down");
+                albums.flush(&queue, true);
                 update_stream.sync_update_state().await;
                 break;
             }
+            _ = refresh.tick() => {
+                if let Some(count) = rebuild_subscriptions(&pool, &account, &subscriptions).await {
+                    debug!(account = %account, subscribed_chats = count, "subscription map refreshed");
+                }
+            }
+            _ = tokio::time::sleep_until(album_deadline.unwrap_or_else(Instant::now)), if album_deadline.is_some() => {
+                albums.flush(&queue, false);
+            }
             update = update_stream.next() => {
+                if update.is_ok() {
+                    health.update_tg_account(&account, |tg| tg.last_update = Some(chrono::Utc::now()));
+                }
                 match update {
                     Ok(Update::NewMessage(msg)) if !msg.outgoing() => {
-                        handle_message(&msg, &pool, &subscriptions).await;
+                        handle_message(&msg, &queue, &subscriptions, &mut albums, media_tx.as_ref()).await;
+                    }
+                    Ok(Update::MessageEdited(msg)) => {
+                        handle_edit(&msg, &pool, &subscriptions).await;
+                    }
+                    Ok(Update::MessageDeleted(deletion)) if telegram_config.deleted_messages != "keep" => {
+                        handle_deletion(
+                            deletion.channel_id(),
+                            deletion.messages(),
+                            &telegram_config.deleted_messages,
+                            &pool,
+                            &subscriptions,
+                        )
+                        .await;
                     }
                     Ok(Update::Raw(raw)) => {
                         // Check for folder change events (updateDialogFilter)
-                        handle_raw_update(&raw, &client, &pool, &subscriptions).await;
+                        handle_raw_update(&raw, &client, &account, &pool, &subscriptions).await;
                     }
                     Ok(_) => {
-                        // MessageEdited, MessageDeleted, etc. — ignore for now
+                        // Outgoing messages, callback queries, etc. — ignore
                     }
                     Err(e) => {
                         error!(error = %e, "error receiving Telegram update");
@@ -55,14 +286,25 @@ pub async fn listener_loop(
         }
     }
 
+    // Write whatever is still queued before the pool closes; queued photo downloads are dropped
+    queue.close();
+    if let Err(e) = writer.await {
+        error!(error = %e, "Telegram write queue writer failed, queued messages may be lost");
+    }
+    if let Some(downloader) = downloader {
+        downloader.abort();
+    }
+
     info!("Telegram listener stopped");
 }
 
 /// Handle an incoming new message from a subscribed chat.
 async fn handle_message(
     msg: &grammers_client::update::Message,
-    pool: &SqlitePool,
+    queue: &WriteQueue,
     subscriptions: &Arc<RwLock<HashMap<i64, Vec<String>>>>,
+    albums: &mut AlbumBuffer,
+    media_tx: Option<&mpsc::Sender<tg_media::Download>>,
 ) {
     // Get chat ID
     let chat_id = msg.peer_id().bare_id();
@@ -81,28 +323,159 @@ async fn handle_message(
     // Get chat username for URL construction (computed once before the source_id loop)
     let peer_username: Option<String> = msg.peer().and_then(|p| p.username().map(|u| u.to_string()));
 
-    // Store for each source that subscribes to this chat
+    // Downloaded once, shared by every subscribed source's item
+    let media_id = media_tx.and_then(|tx| tg_media::queue(tx, msg));
+    let to_item = |source_id: &str| {
+        let mut item = fetch_tg::message_to_content_item(msg, source_id, peer_username.as_deref())?;
+        if let Some(ref id) = media_id {
+            tg_media::add_media_id(&mut item, id);
+        }
+        Some(item)
+    };
+
+    // Album parts are held until the whole album arrived, then stored as one item
+    if let Some(grouped_id) = msg.grouped_id() {
+        for source_id in &source_ids {
+            if let Some(item) = to_item(source_id) {
+                albums.push((source_id.clone(), chat_id, grouped_id), item);
+            }
+        }
+        debug!(chat_id, message_id, grouped_id, "buffered TG album part");
+        return;
+    }
+
+    // Queue for each source that subscribes to this chat
     for source_id in &source_ids {
-        if let Some(item) = fetch_tg::message_to_content_item(msg, source_id, peer_username.as_deref())
-            && let Err(e) = store::upsert_content_item(pool, &item).await
-        {
-            warn!(
-                source_id = %source_id,
+        if let Some(item) = to_item(source_id) {
+            queue.push(item);
+        }
+    }
+
+    debug!(chat_id, message_id, sources = source_ids.len(), "queued TG message");
+}
+
+/// Apply an edit of a subscribed chat's message to its stored item (see docs/specs/telegram.md
+/// "Edits and Deletions"). Edits bypass the write queue: they're rare, and an edit of a message
+/// still queued finds no item and is dropped.
+async fn handle_edit(
+    msg: &grammers_client::update::Message,
+    pool: &SqlitePool,
+    subscriptions: &Arc<RwLock<HashMap<i64, Vec<String>>>>,
+) {
+    let chat_id = msg.peer_id().bare_id();
+    let source_ids = match subscriptions.read().await.get(&chat_id) {
+        Some(ids) => ids.clone(),
+        None => return,
+    };
+    let peer_username: Option<String> = msg.peer().and_then(|p| p.username().map(|u| u.to_string()));
+
+    for source_id in &source_ids {
+        // An album's body is merged from all its parts; an edited part only flags it
+        let result = match msg.grouped_id() {
+            Some(grouped_id) => {
+                store::flag_upstream_changed(pool, source_id, &format!("tg:{chat_id}:album:{grouped_id}")).await
+            }
+            None => match fetch_tg::message_to_content_item(msg, source_id, peer_username.as_deref()) {
+                Some(item) => store::update_edited_item(pool, &item).await,
+                None => continue,
+            },
+        };
+        match result {
+            Ok(true) => debug!(chat_id, message_id = msg.id(), source_id, "applied TG message edit"),
+            Ok(false) => {}
+            Err(e) => warn!(
                 chat_id,
-                message_id,
-                error = %e,
-                "failed to store TG message"
+                message_id = msg.id(),
+                error = format!("{e:#}"),
+                "failed to apply TG message edit"
+            ),
+        }
+    }
+}
+
+/// Tombstone or remove the stored items of deleted messages, per `[telegram].deleted_messages`.
+/// Channel deletions name the channel; other deletions only carry message IDs, which are unique
+/// across the account's private chats and basic groups.
+async fn handle_deletion(
+    channel_id: Option<i64>,
+    message_ids: &[i32],
+    action: &str,
+    pool: &SqlitePool,
+    subscriptions: &Arc<RwLock<HashMap<i64, Vec<String>>>>,
+) {
+    let source_ids: Vec<String> = {
+        let subs = subscriptions.read().await;
+        match channel_id {
+            Some(channel_id) => match subs.get(&channel_id) {
+                Some(ids) => ids.clone(),
+                None => return,
+            },
+            None => {
+                let mut ids: Vec<String> = subs.values().flatten().cloned().collect();
+                ids.sort_unstable();
+                ids.dedup();
+                ids
+            }
+        }
+    };
+
+    let rows = match store::find_tg_message_items(pool, &source_ids, channel_id, message_ids).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            warn!(
+                error = format!("{e:#}"),
+                "failed to look up items of deleted TG messages"
+            );
+            return;
+        }
+    };
+
+    // An album goes once all its parts are deleted, in this update or earlier ones; until then
+    // it keeps the remaining parts and is flagged as changed
+    let mut gone = Vec::new();
+    for (item_id, source_id, dedup_key, metadata) in rows {
+        let meta: serde_json::Value = serde_json::from_str(&metadata).unwrap_or_default();
+        let remaining: Vec<i64> = meta
+            .get("message_ids")
+            .and_then(|v| v.as_array())
+            .map(|ids| ids.iter().filter_map(|id| id.as_i64()).collect::<Vec<_>>())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|id| !message_ids.iter().any(|m| i64::from(*m) == *id))
+            .collect();
+        if remaining.is_empty() {
+            gone.push(item_id);
+        } else if let Err(e) = store::set_album_message_ids(pool, &source_id, &dedup_key, &remaining).await {
+            warn!(
+                item_id,
+                error = format!("{e:#}"),
+                "failed to flag album with deleted parts"
             );
         }
     }
+    if gone.is_empty() {
+        return;
+    }
 
-    debug!(chat_id, message_id, sources = source_ids.len(), "stored TG message");
+    let result = match action {
+        "remove" => store::delete_items(pool, &gone).await,
+        _ => store::mark_items_deleted_upstream(pool, &gone).await,
+    };
+    match result {
+        Ok(count) => info!(channel_id, items = count, action, "applied TG message deletion"),
+        Err(e) => warn!(
+            channel_id,
+            error = format!("{e:#}"),
+            "failed to apply TG message deletion"
+        ),
+    }
 }
 
 /// Handle raw TL updates — specifically folder changes (updateDialogFilter).
 async fn handle_raw_update(
     raw: &grammers_client::update::Raw,
     client: &Client,
+    account: &str,
     pool: &SqlitePool,
     subscriptions: &Arc<RwLock<HashMap<i64, Vec<String>>>>,
 ) {
@@ -116,10 +489,10 @@ async fn handle_raw_update(
         return;
     }
 
-    info!("detected folder change, re-resolving folders");
+    info!(account, "detected folder change, re-resolving folders");
 
-    // Re-resolve all folder sources
-    let folder_sources = match store::get_tg_sources(pool).await {
+    // Re-resolve this account's folder sources (folders belong to the account's dialogs)
+    let folder_sources = match telegram::account_sources(pool, account).await {
         Ok(sources) => sources
             .into_iter()
             .filter(|s| s.source_type == "telegram_folder")
@@ -130,41 +503,85 @@ async fn handle_raw_update(
         }
     };
 
-    if let Err(e) = telegram::resolve_folders(client, pool, &folder_sources).await {
+    if let Err(e) = telegram::resolve_folders(client, pool, account, &folder_sources).await {
         error!(error = %e, "failed to re-resolve folders after update");
         return;
     }
 
-    // Rebuild subscription map
-    let tg_sources = match store::get_tg_sources(pool).await {
-        Ok(s) => s,
-        Err(e) => {
-            error!(error = %e, "failed to reload TG sources");
-            return;
-        }
-    };
+    if let Some(count) = rebuild_subscriptions(pool, account, subscriptions).await {
+        info!(subscribed_chats = count, "subscription map rebuilt after folder change");
+    }
+}
 
-    let folder_channels = match store::get_all_folder_channel_ids(pool).await {
-        Ok(fc) => fc,
+/// Replace the subscription map with one built from the current sources and folder channels.
+/// Returns the number of subscribed chats, or `None` (logged) if it couldn't be built.
+async fn rebuild_subscriptions(
+    pool: &SqlitePool,
+    account: &str,
+    subscriptions: &Arc<RwLock<HashMap<i64, Vec<String>>>>,
+) -> Option<usize> {
+    let new_map = match telegram::account_subscription_map(pool, account).await {
+        Ok(map) => map,
         Err(e) => {
-            error!(error = %e, "failed to reload folder channels");
-            return;
+            error!(error = format!("{e:#}"), "failed to rebuild subscription map");
+            return None;
         }
     };
-
-    let direct_sources: Vec<_> = tg_sources
-        .iter()
-        .filter(|s| s.source_type != "telegram_folder")
-        .cloned()
-        .collect();
-
-    let new_map = telegram::build_subscription_map(&direct_sources, &folder_channels);
     let count = new_map.len();
+    *subscriptions.write().await = new_map;
+    Some(count)
+}
 
-    {
-        let mut subs = subscriptions.write().await;
-        *subs = new_map;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn deleted_at(pool: &SqlitePool, dedup_key: &str) -> Option<String> {
+        sqlx::query_scalar("SELECT deleted_upstream_at FROM content_items WHERE dedup_key = ?")
+            .bind(dedup_key)
+            .fetch_one(pool)
+            .await
+            .unwrap()
     }
 
-    info!(subscribed_chats = count, "subscription map rebuilt after folder change");
+    #[tokio::test]
+    async fn test_album_goes_once_every_part_is_deleted() {
+        let pool = crate::db::memory_pool().await;
+        sqlx::query("INSERT INTO sources (id, source_type, name) VALUES ('src', 'telegram_channel', 'tg')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let album = ContentItem {
+            id: "album".to_string(),
+            source_id: "src".to_string(),
+            ingested_at: chrono::Utc::now(),
+            original_date: chrono::Utc::now(),
+            content_type: "media".to_string(),
+            title: None,
+            body: "Photos".to_string(),
+            url: None,
+            author: None,
+            metadata: serde_json::json!({"chat_id": 100, "message_ids": [10, 11, 12]}).to_string(),
+            dedup_key: "tg:100:album:7".to_string(),
+            upstream_changed: false,
+        };
+        store::upsert_content_item(&pool, &album).await.unwrap();
+        let subscriptions = Arc::new(RwLock::new(HashMap::from([(100, vec!["src".to_string()])])));
+
+        // Parts deleted in separate updates: the album keeps the rest until none is left
+        handle_deletion(Some(100), &[10], "mark", &pool, &subscriptions).await;
+        let stored = store::get_content_item(&pool, "src", &album.dedup_key)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(stored.upstream_changed);
+        assert!(stored.metadata.contains("[11,12]"), "{}", stored.metadata);
+        assert_eq!(deleted_at(&pool, &album.dedup_key).await, None);
+
+        handle_deletion(Some(100), &[12, 99], "mark", &pool, &subscriptions).await;
+        assert_eq!(deleted_at(&pool, &album.dedup_key).await, None);
+
+        handle_deletion(Some(100), &[11], "mark", &pool, &subscriptions).await;
+        assert!(deleted_at(&pool, &album.dedup_key).await.is_some());
+    }
 }
@@ -0,0 +1,212 @@
+//! Telegram photos: downloaded from live messages with `[telegram.media] download = true`,
+//! stored in `tg_media`, served at `/media/{id}`, and embedded in articles and their feed
+//! entries (see docs/specs/telegram.md "Media").
+
+use std::collections::HashSet;
+
+use anyhow::{Result, bail};
+use grammers_client::Client;
+use grammers_client::media::Media;
+use sqlx::SqlitePool;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::models::ContentItem;
+use crate::store;
+
+/// Telegram serves photos as JPEG.
+pub const MIME_TYPE: &str = "image/jpeg";
+
+/// Downloads waiting per listener; photos of messages arriving while it's full aren't downloaded.
+const QUEUE_CAPACITY: usize = 256;
+
+/// Largest photo kept. Telegram's biggest photo size is well below it.
+const MAX_BYTES: usize = 10 * 1024 * 1024;
+
+/// Path prefix of media URLs in article bodies, relative to the server.
+const PATH_PREFIX: &str = "/media/";
+
+/// A photo waiting in a listener's download queue.
+pub struct Download {
+    id: String,
+    chat_id: i64,
+    message_id: i32,
+    media: Media,
+}
+
+/// Start a listener's downloader, one photo at a time, until the sender is dropped.
+pub fn spawn(client: Client, pool: SqlitePool, account: String) -> (mpsc::Sender<Download>, JoinHandle<()>) {
+    let (tx, mut rx) = mpsc::channel::<Download>(QUEUE_CAPACITY);
+    let handle = tokio::spawn(async move {
+        while let Some(download) = rx.recv().await {
+            match fetch(&client, &download.media).await {
+                Ok(data) => {
+                    if let Err(e) =
+                        store::insert_tg_media(&pool, &download.id, download.chat_id, download.message_id, &data).await
+                    {
+                        warn!(account = %account, error = format!("{e:#}"), "failed to store TG photo");
+                    } else {
+                        debug!(
+                            chat_id = download.chat_id,
+                            message_id = download.message_id,
+                            bytes = data.len(),
+                            "downloaded TG photo"
+                        );
+                    }
+                }
+                Err(e) => warn!(
+                    account = %account,
+                    chat_id = download.chat_id,
+                    message_id = download.message_id,
+                    error = format!("{e:#}"),
+                    "failed to download TG photo"
+                ),
+            }
+        }
+    });
+    (tx, handle)
+}
+
+/// Queue a message's photo for download. Returns the media ID its item should carry, or
+/// `None` without a photo or with a full queue.
+pub fn queue(tx: &mpsc::Sender<Download>, msg: &grammers_client::message::Message) -> Option<String> {
+    let media @ Media::Photo(_) = msg.media()? else {
+        return None;
+    };
+    let id = Uuid::new_v4().to_string();
+    let download = Download {
+        id: id.clone(),
+        chat_id: msg.peer_id().bare_id(),
+        message_id: msg.id(),
+        media,
+    };
+    match tx.try_send(download) {
+        Ok(()) => Some(id),
+        Err(_) => {
+            warn!(
+                chat_id = msg.peer_id().bare_id(),
+                message_id = msg.id(),
+                "TG photo download queue full, skipping photo"
+            );
+            None
+        }
+    }
+}
+
+async fn fetch(client: &Client, media: &Media) -> Result<Vec<u8>> {
+    let mut download = client.iter_download(media);
+    let mut data = Vec::new();
+    while let Some(chunk) = download.next().await? {
+        data.extend_from_slice(&chunk);
+        if data.len() > MAX_BYTES {
+            bail!("photo larger than {MAX_BYTES} bytes");
+        }
+    }
+    Ok(data)
+}
+
+/// Add a media ID to an item's `media_ids`.
+pub fn add_media_id(item: &mut ContentItem, id: &str) {
+    let mut meta: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&item.metadata).unwrap_or_default();
+    let ids = meta
+        .entry("media_ids")
+        .or_insert_with(|| serde_json::Value::Array(Vec::new()));
+    if let Some(ids) = ids.as_array_mut() {
+        ids.push(serde_json::json!(id));
+    }
+    item.metadata = serde_json::to_string(&meta).unwrap_or_else(|_| "{}".to_string());
+}
+
+/// Leave only downloaded photos in the items' `media_ids`, so the workspace never offers an
+/// image that `/media/{id}` can't serve. Best-effort: on a database error the items lose theirs.
+pub async fn keep_downloaded(pool: &SqlitePool, items: &mut [ContentItem]) {
+    let ids: Vec<String> = items.iter().flat_map(|item| media_ids(&item.metadata)).collect();
+    if ids.is_empty() {
+        return;
+    }
+    let downloaded = store::get_downloaded_media_ids(pool, &ids).await.unwrap_or_else(|e| {
+        warn!(error = format!("{e:#}"), "failed to look up downloaded TG photos");
+        HashSet::new()
+    });
+    for item in items.iter_mut() {
+        let Ok(mut meta) = serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&item.metadata) else {
+            continue;
+        };
+        let Some(ids) = meta.get_mut("media_ids").and_then(|v| v.as_array_mut()) else {
+            continue;
+        };
+        ids.retain(|id| id.as_str().is_some_and(|id| downloaded.contains(id)));
+        if ids.is_empty() {
+            meta.remove("media_ids");
+        }
+        item.metadata = serde_json::to_string(&meta).unwrap_or_else(|_| "{}".to_string());
+    }
+}
+
+fn media_ids(metadata: &str) -> Vec<String> {
+    serde_json::from_str::<serde_json::Value>(metadata)
+        .ok()
+        .and_then(|meta| meta.get("media_ids").cloned())
+        .and_then(|ids| serde_json::from_value(ids).ok())
+        .unwrap_or_default()
+}
+
+/// Server-relative URL of a photo, as the workspace offers it and articles embed it.
+pub fn path(id: &str) -> String {
+    format!("{PATH_PREFIX}{id}")
+}
+
+/// IDs of the photos an article's HTML embeds, in order.
+pub fn embedded_ids(html: &str) -> Vec<&str> {
+    let needle = format!("src=\"{PATH_PREFIX}");
+    html.match_indices(&needle)
+        .filter_map(|(start, _)| {
+            let rest = &html[start + needle.len()..];
+            rest.find('"').map(|end| &rest[..end])
+        })
+        .filter(|id| !id.is_empty())
+        .collect()
+}
+
+/// An article's HTML with embedded photos pointing at `base_url`, for feed readers.
+pub fn absolute_urls(html: &str, base_url: &str) -> String {
+    html.replace(
+        &format!("src=\"{PATH_PREFIX}"),
+        &format!("src=\"{base_url}{PATH_PREFIX}"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_ids() {
+        let html = format!(
+            r#"<p>Two photos</p><img src="{p}a1b2"><img alt="x" src="{p}c3d4"><img src="https://example.com/x.jpg">"#,
+            p = PATH_PREFIX
+        );
+        assert_eq!(embedded_ids(&html), vec!["a1b2", "c3d4"]);
+    }
+
+    #[test]
+    fn test_embedded_ids_skips_empty_and_unterminated() {
+        let html = format!(r#"<img src="{p}"><img src="{p}e5f6"#, p = PATH_PREFIX);
+        assert!(embedded_ids(&html).is_empty());
+        assert!(embedded_ids("<p>no photos</p>").is_empty());
+    }
+
+    #[test]
+    fn test_absolute_urls() {
+        let html = format!(r#"<img src="{p}a1b2"> <a href="{p}a1b2">link</a>"#, p = PATH_PREFIX);
+        assert_eq!(
+            absolute_urls(&html, "https://pail.example.com"),
+            format!(
+                r#"<img src="https://pail.example.com{p}a1b2"> <a href="{p}a1b2">link</a>"#,
+                p = PATH_PREFIX
+            )
+        );
+    }
+}
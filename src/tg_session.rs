@@ -14,9 +14,34 @@ use grammers_session::Session;
 use grammers_session::types::{
     ChannelKind, ChannelState, DcOption, PeerAuth, PeerId, PeerInfo, PeerKind, UpdateState, UpdatesState,
 };
-use sqlx::SqlitePool;
+use sqlx::{Executor, SqlitePool};
 use tracing::warn;
 
+use crate::config::DEFAULT_TG_ACCOUNT;
+
+/// Session tables for additional accounts; the default account's come from the Phase 2
+/// migration. Written with the default `tg_` names and rewritten by `namespaced`.
+const SESSION_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS tg_dc_home (dc_id INTEGER NOT NULL, PRIMARY KEY (dc_id));
+CREATE TABLE IF NOT EXISTS tg_dc_option (
+    dc_id INTEGER NOT NULL, ipv4 TEXT NOT NULL, ipv6 TEXT NOT NULL, auth_key BLOB, PRIMARY KEY (dc_id)
+);
+CREATE TABLE IF NOT EXISTS tg_peer_info (peer_id INTEGER NOT NULL, hash INTEGER, subtype INTEGER, PRIMARY KEY (peer_id));
+CREATE TABLE IF NOT EXISTS tg_update_state (pts INTEGER NOT NULL, qts INTEGER NOT NULL, date INTEGER NOT NULL, seq INTEGER NOT NULL);
+CREATE TABLE IF NOT EXISTS tg_channel_state (peer_id INTEGER NOT NULL, pts INTEGER NOT NULL, PRIMARY KEY (peer_id));
+";
+
+/// Rewrite the `tg_` session table names in `sql` into the account's namespace: the default
+/// account keeps `tg_dc_home` etc., account `work` uses `tg_acct_work_dc_home` etc.
+/// Only for queries on session tables (account names are validated as `[a-z0-9_]+`).
+pub fn namespaced(account: &str, sql: &str) -> String {
+    if account == DEFAULT_TG_ACCOUNT {
+        sql.to_string()
+    } else {
+        sql.replace("tg_", &format!("tg_acct_{account}_"))
+    }
+}
+
 /// Default home DC (DC 2, same as grammers' default).
 const DEFAULT_DC: i32 = 2;
 
@@ -99,23 +124,30 @@ enum PeerSubtype {
 /// Custom grammers Session backed by pail's sqlx SqlitePool.
 pub struct SqlxSession {
     pool: SqlitePool,
+    account: String,
     cache: Mutex<Cache>,
 }
 
 impl SqlxSession {
-    /// Load or initialize a session from the database.
-    /// The tg_* tables must already exist (created by the Phase 2 migration).
-    pub async fn load(pool: SqlitePool) -> anyhow::Result<Self> {
+    /// Load or initialize an account's session from the database.
+    /// The default account's tg_* tables are created by the Phase 2 migration; other
+    /// accounts' tables are created here on first use.
+    pub async fn load(pool: SqlitePool, account: &str) -> anyhow::Result<Self> {
+        if account != DEFAULT_TG_ACCOUNT {
+            pool.execute(namespaced(account, SESSION_SCHEMA).as_str()).await?;
+        }
+
         // Load home DC from DB, default to DC 2
-        let home_dc: i32 = sqlx::query_scalar("SELECT dc_id FROM tg_dc_home LIMIT 1")
+        let home_dc: i32 = sqlx::query_scalar(&namespaced(account, "SELECT dc_id FROM tg_dc_home LIMIT 1"))
             .fetch_optional(&pool)
             .await?
             .unwrap_or(DEFAULT_DC);
 
         // Load DC options from DB
-        let rows = sqlx::query_as::<_, (i32, String, String, Option<Vec<u8>>)>(
+        let rows = sqlx::query_as::<_, (i32, String, String, Option<Vec<u8>>)>(&namespaced(
+            account,
             "SELECT dc_id, ipv4, ipv6, auth_key FROM tg_dc_option",
-        )
+        ))
         .fetch_all(&pool)
         .await?;
 
@@ -146,9 +178,14 @@ impl SqlxSession {
 
         Ok(Self {
             pool,
+            account: account.to_string(),
             cache: Mutex::new(Cache { home_dc, dc_options }),
         })
     }
+
+    fn sql(&self, sql: &str) -> String {
+        namespaced(&self.account, sql)
+    }
 }
 
 impl Session for SqlxSession {
@@ -159,10 +196,13 @@ impl Session for SqlxSession {
     fn set_home_dc_id(&self, dc_id: i32) -> BoxFuture<'_, ()> {
         self.cache.lock().unwrap().home_dc = dc_id;
         Box::pin(async move {
-            if let Err(e) = sqlx::query("DELETE FROM tg_dc_home").execute(&self.pool).await {
+            if let Err(e) = sqlx::query(&self.sql("DELETE FROM tg_dc_home"))
+                .execute(&self.pool)
+                .await
+            {
                 warn!(error = %e, "failed to clear tg_dc_home");
             }
-            if let Err(e) = sqlx::query("INSERT INTO tg_dc_home (dc_id) VALUES (?)")
+            if let Err(e) = sqlx::query(&self.sql("INSERT INTO tg_dc_home (dc_id) VALUES (?)"))
                 .bind(dc_id)
                 .execute(&self.pool)
                 .await
@@ -191,14 +231,15 @@ impl Session for SqlxSession {
         let dc_option = dc_option.clone();
         Box::pin(async move {
             let auth_key_bytes = dc_option.auth_key.map(|k| k.to_vec());
-            if let Err(e) =
-                sqlx::query("INSERT OR REPLACE INTO tg_dc_option (dc_id, ipv4, ipv6, auth_key) VALUES (?, ?, ?, ?)")
-                    .bind(dc_option.id)
-                    .bind(dc_option.ipv4.to_string())
-                    .bind(dc_option.ipv6.to_string())
-                    .bind(auth_key_bytes)
-                    .execute(&self.pool)
-                    .await
+            if let Err(e) = sqlx::query(
+                &self.sql("INSERT OR REPLACE INTO tg_dc_option (dc_id, ipv4, ipv6, auth_key) VALUES (?, ?, ?, ?)"),
+            )
+            .bind(dc_option.id)
+            .bind(dc_option.ipv4.to_string())
+            .bind(dc_option.ipv6.to_string())
+            .bind(auth_key_bytes)
+            .execute(&self.pool)
+            .await
             {
                 warn!(error = %e, dc_id = dc_option.id, "failed to persist DC option");
             }
@@ -209,7 +250,7 @@ impl Session for SqlxSession {
         Box::pin(async move {
             let row = if peer.kind() == PeerKind::UserSelf {
                 match sqlx::query_as::<_, (i64, Option<i64>, Option<i64>)>(
-                    "SELECT peer_id, hash, subtype FROM tg_peer_info WHERE subtype & ? != 0 LIMIT 1",
+                    &self.sql("SELECT peer_id, hash, subtype FROM tg_peer_info WHERE subtype & ? != 0 LIMIT 1"),
                 )
                 .bind(PeerSubtype::UserSelf as i64)
                 .fetch_optional(&self.pool)
@@ -223,7 +264,7 @@ impl Session for SqlxSession {
                 }
             } else {
                 match sqlx::query_as::<_, (i64, Option<i64>, Option<i64>)>(
-                    "SELECT peer_id, hash, subtype FROM tg_peer_info WHERE peer_id = ? LIMIT 1",
+                    &self.sql("SELECT peer_id, hash, subtype FROM tg_peer_info WHERE peer_id = ? LIMIT 1"),
                 )
                 .bind(peer.bot_api_dialog_id())
                 .fetch_optional(&self.pool)
@@ -288,12 +329,13 @@ impl Session for SqlxSession {
             let peer_id = peer.id().bot_api_dialog_id();
             let hash: Option<i64> = peer.auth().map(|a| a.hash());
 
-            if let Err(e) = sqlx::query("INSERT OR REPLACE INTO tg_peer_info (peer_id, hash, subtype) VALUES (?, ?, ?)")
-                .bind(peer_id)
-                .bind(hash)
-                .bind(subtype)
-                .execute(&self.pool)
-                .await
+            if let Err(e) =
+                sqlx::query(&self.sql("INSERT OR REPLACE INTO tg_peer_info (peer_id, hash, subtype) VALUES (?, ?, ?)"))
+                    .bind(peer_id)
+                    .bind(hash)
+                    .bind(subtype)
+                    .execute(&self.pool)
+                    .await
             {
                 warn!(error = %e, peer_id, "failed to cache peer");
             }
@@ -303,7 +345,7 @@ impl Session for SqlxSession {
     fn updates_state(&self) -> BoxFuture<'_, UpdatesState> {
         Box::pin(async move {
             let primary = match sqlx::query_as::<_, (i32, i32, i32, i32)>(
-                "SELECT pts, qts, date, seq FROM tg_update_state LIMIT 1",
+                &self.sql("SELECT pts, qts, date, seq FROM tg_update_state LIMIT 1"),
             )
             .fetch_optional(&self.pool)
             .await
@@ -326,7 +368,7 @@ impl Session for SqlxSession {
                 None => UpdatesState::default(),
             };
 
-            let channels = match sqlx::query_as::<_, (i64, i32)>("SELECT peer_id, pts FROM tg_channel_state")
+            let channels = match sqlx::query_as::<_, (i64, i32)>(&self.sql("SELECT peer_id, pts FROM tg_channel_state"))
                 .fetch_all(&self.pool)
                 .await
             {
@@ -347,50 +389,58 @@ impl Session for SqlxSession {
         Box::pin(async move {
             match update {
                 UpdateState::All(updates_state) => {
-                    if let Err(e) = sqlx::query("DELETE FROM tg_update_state").execute(&self.pool).await {
-                        warn!(error = %e, "failed to clear update state");
-                    }
-                    if let Err(e) = sqlx::query("INSERT INTO tg_update_state (pts, qts, date, seq) VALUES (?, ?, ?, ?)")
-                        .bind(updates_state.pts)
-                        .bind(updates_state.qts)
-                        .bind(updates_state.date)
-                        .bind(updates_state.seq)
+                    if let Err(e) = sqlx::query(&self.sql("DELETE FROM tg_update_state"))
                         .execute(&self.pool)
                         .await
+                    {
+                        warn!(error = %e, "failed to clear update state");
+                    }
+                    if let Err(e) =
+                        sqlx::query(&self.sql("INSERT INTO tg_update_state (pts, qts, date, seq) VALUES (?, ?, ?, ?)"))
+                            .bind(updates_state.pts)
+                            .bind(updates_state.qts)
+                            .bind(updates_state.date)
+                            .bind(updates_state.seq)
+                            .execute(&self.pool)
+                            .await
                     {
                         warn!(error = %e, "failed to persist update state");
                     }
 
-                    if let Err(e) = sqlx::query("DELETE FROM tg_channel_state").execute(&self.pool).await {
+                    if let Err(e) = sqlx::query(&self.sql("DELETE FROM tg_channel_state"))
+                        .execute(&self.pool)
+                        .await
+                    {
                         warn!(error = %e, "failed to clear channel states");
                     }
                     for channel in updates_state.channels {
-                        if let Err(e) = sqlx::query("INSERT INTO tg_channel_state (peer_id, pts) VALUES (?, ?)")
-                            .bind(channel.id)
-                            .bind(channel.pts)
-                            .execute(&self.pool)
-                            .await
+                        if let Err(e) =
+                            sqlx::query(&self.sql("INSERT INTO tg_channel_state (peer_id, pts) VALUES (?, ?)"))
+                                .bind(channel.id)
+                                .bind(channel.pts)
+                                .execute(&self.pool)
+                                .await
                         {
                             warn!(error = %e, peer_id = channel.id, "failed to persist channel state");
                         }
                     }
                 }
                 UpdateState::Primary { pts, date, seq } => {
-                    let exists = sqlx::query_scalar::<_, i32>("SELECT 1 FROM tg_update_state LIMIT 1")
+                    let exists = sqlx::query_scalar::<_, i32>(&self.sql("SELECT 1 FROM tg_update_state LIMIT 1"))
                         .fetch_optional(&self.pool)
                         .await
                         .unwrap_or(None)
                         .is_some();
 
                     let result = if exists {
-                        sqlx::query("UPDATE tg_update_state SET pts = ?, date = ?, seq = ?")
+                        sqlx::query(&self.sql("UPDATE tg_update_state SET pts = ?, date = ?, seq = ?"))
                             .bind(pts)
                             .bind(date)
                             .bind(seq)
                             .execute(&self.pool)
                             .await
                     } else {
-                        sqlx::query("INSERT INTO tg_update_state (pts, qts, date, seq) VALUES (?, 0, ?, ?)")
+                        sqlx::query(&self.sql("INSERT INTO tg_update_state (pts, qts, date, seq) VALUES (?, 0, ?, ?)"))
                             .bind(pts)
                             .bind(date)
                             .bind(seq)
@@ -402,19 +452,19 @@ impl Session for SqlxSession {
                     }
                 }
                 UpdateState::Secondary { qts } => {
-                    let exists = sqlx::query_scalar::<_, i32>("SELECT 1 FROM tg_update_state LIMIT 1")
+                    let exists = sqlx::query_scalar::<_, i32>(&self.sql("SELECT 1 FROM tg_update_state LIMIT 1"))
                         .fetch_optional(&self.pool)
                         .await
                         .unwrap_or(None)
                         .is_some();
 
                     let result = if exists {
-                        sqlx::query("UPDATE tg_update_state SET qts = ?")
+                        sqlx::query(&self.sql("UPDATE tg_update_state SET qts = ?"))
                             .bind(qts)
                             .execute(&self.pool)
                             .await
                     } else {
-                        sqlx::query("INSERT INTO tg_update_state (pts, qts, date, seq) VALUES (0, ?, 0, 0)")
+                        sqlx::query(&self.sql("INSERT INTO tg_update_state (pts, qts, date, seq) VALUES (0, ?, 0, 0)"))
                             .bind(qts)
                             .execute(&self.pool)
                             .await
@@ -424,11 +474,12 @@ impl Session for SqlxSession {
                     }
                 }
                 UpdateState::Channel { id, pts } => {
-                    if let Err(e) = sqlx::query("INSERT OR REPLACE INTO tg_channel_state (peer_id, pts) VALUES (?, ?)")
-                        .bind(id)
-                        .bind(pts)
-                        .execute(&self.pool)
-                        .await
+                    if let Err(e) =
+                        sqlx::query(&self.sql("INSERT OR REPLACE INTO tg_channel_state (peer_id, pts) VALUES (?, ?)"))
+                            .bind(id)
+                            .bind(pts)
+                            .execute(&self.pool)
+                            .await
                     {
                         warn!(error = %e, peer_id = id, "failed to persist channel state");
                     }
@@ -0,0 +1,182 @@
+//! Translation delivery: after an article is stored, a separate model call translates it into
+//! each of the channel's `translate` languages. Translations are stored next to the original
+//! and served with `?lang=` (see docs/specs/translation.md).
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use reqwest::header::USER_AGENT;
+use serde_json::json;
+use sqlx::SqlitePool;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::config::{Config, OutputChannelConfig};
+use crate::models::{ArticleTranslation, GeneratedArticle};
+use crate::tagging::ChatResponse;
+use crate::{generate, http_client, language, store};
+
+/// A whole digest comes back in one reply, which takes a while on slower models.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// pail's own footer, kept out of the translation and put back after it.
+const SESSION_SUFFIX: &str = "\n\n---\n\n[opencode session](";
+
+/// Translate a stored article into each of the channel's `translate` languages. Failures are
+/// logged per language; the original article is served either way. Cancelling stops the
+/// request in flight and skips the remaining languages.
+pub async fn translate_article(
+    pool: &SqlitePool,
+    config: &Config,
+    channel_config: &OutputChannelConfig,
+    article: &GeneratedArticle,
+    cancel: &CancellationToken,
+) {
+    for lang in &channel_config.translate {
+        let result = tokio::select! {
+            result = async {
+                let translation = translate(config, article, lang).await?;
+                store::upsert_article_translation(pool, &translation).await
+            } => result,
+            _ = cancel.cancelled() => {
+                info!(channel = %channel_config.slug, lang, "translation cancelled, serving the original only");
+                return;
+            }
+        };
+        match result {
+            Ok(()) => info!(channel = %channel_config.slug, lang, "article translated"),
+            Err(e) => warn!(channel = %channel_config.slug, lang, "translation failed: {e:#}"),
+        }
+    }
+}
+
+async fn translate(config: &Config, article: &GeneratedArticle, lang: &str) -> Result<ArticleTranslation> {
+    let translation_config = &config.translation;
+    let api_url = translation_config
+        .api_url
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("[translation].api_url is not configured"))?;
+    let model = translation_config
+        .model
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("[translation].model is not configured"))?;
+    let url = format!("{}/chat/completions", api_url.trim_end_matches('/'));
+
+    let (body, suffix) = match article.body_markdown.split_once(SESSION_SUFFIX) {
+        Some((body, link)) => (body, Some(format!("{SESSION_SUFFIX}{link}"))),
+        None => (article.body_markdown.as_str(), None),
+    };
+    let language = language::name(lang).unwrap_or(lang);
+    let instructions = format!(
+        "You translate news digests into {language}. Translate the article's title and text. Keep the \
+         Markdown structure, links and URLs exactly as they are; leave names of people, projects and \
+         publications as they are unless they have an established {language} form. Don't add, drop or \
+         summarize anything.\n\n\
+         Reply with the translated article only, in the same form as the input: the title as a \
+         `# ` heading on the first line, then a blank line, then the text."
+    );
+    let input = format!("# {}\n\n{}", article.title, body.trim());
+
+    let client = http_client::with_proxy(reqwest::Client::builder(), config.network.proxy.as_deref())
+        .and_then(|builder| builder.timeout(REQUEST_TIMEOUT).build())
+        .context("building HTTP client")?;
+    let mut request = client
+        .post(&url)
+        .header(USER_AGENT, concat!("pail/", env!("CARGO_PKG_VERSION")))
+        .json(&json!({
+            "model": model,
+            "temperature": 0,
+            "messages": [
+                { "role": "system", "content": instructions },
+                { "role": "user", "content": input },
+            ],
+        }));
+    if let Some(ref key) = translation_config.api_key {
+        request = request.bearer_auth(key);
+    }
+
+    let response = request.send().await.context("sending translation request")?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("translation API returned {status}: {body}");
+    }
+    let parsed: ChatResponse = response.json().await.context("parsing translation response")?;
+    let content = parsed
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|c| c.message.content)
+        .ok_or_else(|| anyhow::anyhow!("translation API returned no message"))?;
+
+    let (title, mut body_markdown) = parse_reply(&content)?;
+    if let Some(suffix) = suffix {
+        body_markdown.push_str(&suffix);
+    }
+    Ok(ArticleTranslation {
+        article_id: article.id.clone(),
+        lang: lang.to_string(),
+        body_html: generate::markdown_to_html(&body_markdown),
+        title,
+        body_markdown,
+        model: model.to_string(),
+        created_at: Utc::now(),
+    })
+}
+
+/// Title and text of the reply: a `# ` heading first, the rest after it. Models that wrap the
+/// reply in a code fence get it unwrapped.
+fn parse_reply(content: &str) -> Result<(String, String)> {
+    let mut content = content.trim();
+    if let Some(fenced) = content.strip_prefix("```") {
+        let inner = fenced.split_once('\n').map_or("", |(_, rest)| rest);
+        content = inner.trim_end().strip_suffix("```").unwrap_or(inner).trim();
+    }
+    let (first, rest) = content.split_once('\n').unwrap_or((content, ""));
+    let Some(title) = first.strip_prefix("# ") else {
+        anyhow::bail!("translation reply doesn't start with a title heading: {first}");
+    };
+    let (title, body) = (title.trim(), rest.trim());
+    if title.is_empty() || body.is_empty() {
+        anyhow::bail!("translation reply has an empty title or text");
+    }
+    Ok((title.to_string(), body.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reply_splits_title_and_text() {
+        let (title, body) = parse_reply("# Заголовок\n\nПерший абзац.\n\nДругий абзац.\n").unwrap();
+        assert_eq!(title, "Заголовок");
+        assert_eq!(body, "Перший абзац.\n\nДругий абзац.");
+    }
+
+    #[test]
+    fn test_parse_reply_unwraps_code_fence() {
+        let (title, body) = parse_reply("```markdown\n# Titel\n\nText.\n```").unwrap();
+        assert_eq!(title, "Titel");
+        assert_eq!(body, "Text.");
+
+        let (title, body) = parse_reply("```\n# Titel\n\nText.").unwrap();
+        assert_eq!(title, "Titel");
+        assert_eq!(body, "Text.");
+    }
+
+    #[test]
+    fn test_parse_reply_rejects_missing_heading() {
+        assert!(parse_reply("Titel\n\nText.").is_err());
+        assert!(parse_reply("## Titel\n\nText.").is_err());
+    }
+
+    #[test]
+    fn test_parse_reply_rejects_empty_title_or_text() {
+        assert!(parse_reply("# \n\nText.").is_err());
+        assert!(parse_reply("# Titel").is_err());
+        assert!(parse_reply("# Titel\n\n   ").is_err());
+        assert!(parse_reply("").is_err());
+    }
+}
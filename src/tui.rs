@@ -8,7 +8,6 @@ use inquire::{InquireError, MultiSelect, Select};
 
 use grammers_client::Client;
 
-use crate::config::{load_config, validate_config};
 use crate::config_edit::{self, NewSource, TgSourceInfo};
 use crate::telegram::{TgConnection, TgDialog, TgFolder};
 
@@ -427,6 +426,7 @@ async fn apply_selection(ctx: &ApplyContext<'_>, client: &Client) -> Result<()>
                     sources_to_add.push(NewSource {
                         name: unique.clone(),
                         source_type: "telegram_folder".to_string(),
+                        url: None,
                         tg_username: None,
                         tg_id: None,
                         tg_folder_name: Some(folder_name.clone()),
@@ -452,6 +452,7 @@ async fn apply_selection(ctx: &ApplyContext<'_>, client: &Client) -> Result<()>
                     sources_to_add.push(NewSource {
                         name: unique.clone(),
                         source_type: dialog.chat_type.config_type().to_string(),
+                        url: None,
                         tg_username: dialog.username.clone(),
                         tg_id: Some(dialog.tg_id),
                         tg_folder_name: None,
@@ -536,7 +537,7 @@ async fn apply_selection(ctx: &ApplyContext<'_>, client: &Client) -> Result<()>
     }
 
     let new_content = config_edit::render(&doc);
-    write_with_validation(ctx.config_path, &content, &new_content)?;
+    config_edit::write_with_validation(ctx.config_path, &content, &new_content)?;
 
     println!("Config updated.");
     Ok(())
@@ -571,46 +572,3 @@ fn is_cancel(e: &anyhow::Error) -> bool {
     e.downcast_ref::<InquireError>()
         .is_some_and(|ie| matches!(ie, InquireError::OperationCanceled | InquireError::OperationInterrupted))
 }
-
-/// Write new content to config, validate, rollback on failure, and show diff.
-fn write_with_validation(config_path: &Path, original: &str, new_content: &str) -> Result<()> {
-    std::fs::write(config_path, new_content).context("writing config file")?;
-
-    match load_config(config_path).and_then(|cfg| validate_config(&cfg).map(|()| cfg)) {
-        Ok(_) => {
-            show_diff(original, new_content);
-            Ok(())
-        }
-        Err(e) => {
-            std::fs::write(config_path, original).context("restoring config backup")?;
-            Err(e).context("config validation failed after write — restored original")
-        }
-    }
-}
-
-/// Show a simple diff between old and new content.
-fn show_diff(old: &str, new: &str) {
-    let old_lines: Vec<&str> = old.lines().collect();
-    let new_lines: Vec<&str> = new.lines().collect();
-
-    let mut has_changes = false;
-
-    for line in &new_lines {
-        if !old_lines.contains(line) {
-            if !has_changes {
-                println!("\nChanges:");
-                has_changes = true;
-            }
-            println!("  + {line}");
-        }
-    }
-    for line in &old_lines {
-        if !new_lines.contains(line) {
-            if !has_changes {
-                println!("\nChanges:");
-                has_changes = true;
-            }
-            println!("  - {line}");
-        }
-    }
-}
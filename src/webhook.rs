@@ -0,0 +1,210 @@
+//! `webhook` sources: items pushed to `POST /ingest/<slug>` (see docs/specs/webhook-sources.md).
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::models::{ContentItem, Source};
+
+/// Items accepted per request.
+pub const MAX_ITEMS_PER_REQUEST: usize = 100;
+
+/// One pushed item. At least one of `body`, `title` or `url` must be set.
+#[derive(Deserialize)]
+struct PushedItem {
+    title: Option<String>,
+    #[serde(alias = "text", alias = "content")]
+    body: Option<String>,
+    url: Option<String>,
+    author: Option<String>,
+    /// RFC 3339; defaults to the time of the request.
+    date: Option<DateTime<Utc>>,
+    /// Stable ID for deduplication; defaults to a hash of title, body and URL.
+    id: Option<String>,
+    #[serde(default)]
+    metadata: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Payload {
+    One(PushedItem),
+    Many(Vec<PushedItem>),
+}
+
+/// Turn a request body into items: JSON (one item or an array) when `is_json`, else the whole
+/// body as the text of one item. Errors are messages for the `400` response.
+pub fn parse_payload(
+    source: &Source,
+    is_json: bool,
+    body: &[u8],
+    now: DateTime<Utc>,
+) -> Result<Vec<ContentItem>, String> {
+    let pushed = if is_json {
+        match serde_json::from_slice::<Payload>(body) {
+            Ok(Payload::One(item)) => vec![item],
+            Ok(Payload::Many(items)) => items,
+            Err(e) => return Err(format!("invalid JSON payload: {e}")),
+        }
+    } else {
+        let text = std::str::from_utf8(body).map_err(|_| "body is not valid UTF-8".to_string())?;
+        vec![PushedItem {
+            title: None,
+            body: Some(text.to_string()),
+            url: None,
+            author: None,
+            date: None,
+            id: None,
+            metadata: serde_json::Map::new(),
+        }]
+    };
+    if pushed.is_empty() {
+        return Err("no items in payload".to_string());
+    }
+    if pushed.len() > MAX_ITEMS_PER_REQUEST {
+        return Err(format!(
+            "{} items in one request, at most {MAX_ITEMS_PER_REQUEST} allowed",
+            pushed.len()
+        ));
+    }
+
+    pushed
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let non_empty = |v: Option<String>| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+            let title = non_empty(item.title);
+            let body = non_empty(item.body).unwrap_or_default();
+            let url = non_empty(item.url);
+            if title.is_none() && body.is_empty() && url.is_none() {
+                return Err(format!("item {i}: needs at least one of 'body', 'title' or 'url'"));
+            }
+            if let Some(ref url) = url
+                && !(url.starts_with("http://") || url.starts_with("https://"))
+            {
+                return Err(format!("item {i}: 'url' must be an http(s) URL"));
+            }
+            let dedup_key = match non_empty(item.id) {
+                Some(id) => format!("webhook:{id}"),
+                None => {
+                    let mut hasher = Sha256::new();
+                    for part in [title.as_deref(), Some(body.as_str()), url.as_deref()] {
+                        hasher.update(part.unwrap_or_default().as_bytes());
+                        hasher.update([0]);
+                    }
+                    format!("webhook:{:x}", hasher.finalize())
+                }
+            };
+            // A bare link is fetched like a feed link (prefetch); anything with text is the content itself
+            let content_type = if body.is_empty() && url.is_some() {
+                "link"
+            } else {
+                "text"
+            };
+            Ok(ContentItem {
+                id: Uuid::new_v4().to_string(),
+                source_id: source.id.clone(),
+                ingested_at: now,
+                original_date: item.date.unwrap_or(now).min(now),
+                content_type: content_type.to_string(),
+                title,
+                body,
+                url,
+                author: non_empty(item.author),
+                metadata: serde_json::Value::Object(item.metadata).to_string(),
+                dedup_key,
+                upstream_changed: false,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source() -> Source {
+        Source {
+            id: "src-1".to_string(),
+            source_type: "webhook".to_string(),
+            name: "Hook".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-03-01T12:00:00Z").unwrap().to_utc()
+    }
+
+    #[test]
+    fn test_parse_payload_single_object() {
+        let body = br#"{"title": " Hello ", "text": "World", "url": "https://example.com/a",
+            "author": "", "id": "42", "date": "2026-02-28T08:00:00Z", "metadata": {"k": 1}}"#;
+        let items = parse_payload(&source(), true, body, now()).unwrap();
+        assert_eq!(items.len(), 1);
+        let item = &items[0];
+        assert_eq!(item.source_id, "src-1");
+        assert_eq!(item.title.as_deref(), Some("Hello"));
+        assert_eq!(item.body, "World");
+        assert_eq!(item.url.as_deref(), Some("https://example.com/a"));
+        assert_eq!(item.author, None);
+        assert_eq!(item.dedup_key, "webhook:42");
+        assert_eq!(item.content_type, "text");
+        assert_eq!(item.original_date.to_rfc3339(), "2026-02-28T08:00:00+00:00");
+        assert_eq!(item.metadata, r#"{"k":1}"#);
+    }
+
+    #[test]
+    fn test_parse_payload_array_and_defaults() {
+        let body = br#"[{"url": "https://example.com/b"}, {"content": "note", "date": "2030-01-01T00:00:00Z"}]"#;
+        let items = parse_payload(&source(), true, body, now()).unwrap();
+        assert_eq!(items.len(), 2);
+        // A bare link is prefetched like a feed link
+        assert_eq!(items[0].content_type, "link");
+        assert_eq!(items[0].original_date, now());
+        // Dates in the future are clamped to the request time
+        assert_eq!(items[1].content_type, "text");
+        assert_eq!(items[1].original_date, now());
+        assert_eq!(items[1].metadata, "{}");
+        assert!(items[1].dedup_key.starts_with("webhook:"));
+        assert_ne!(items[0].dedup_key, items[1].dedup_key);
+    }
+
+    #[test]
+    fn test_parse_payload_hash_key_is_stable() {
+        let body = br#"{"title": "T", "body": "B"}"#;
+        let a = parse_payload(&source(), true, body, now()).unwrap();
+        let b = parse_payload(&source(), true, br#"{"title": " T", "body": "B "}"#, now()).unwrap();
+        assert_eq!(a[0].dedup_key, b[0].dedup_key);
+        // Field boundaries are part of the hash
+        let c = parse_payload(&source(), true, br#"{"title": "TB"}"#, now()).unwrap();
+        assert_ne!(a[0].dedup_key, c[0].dedup_key);
+    }
+
+    #[test]
+    fn test_parse_payload_plain_text() {
+        let items = parse_payload(&source(), false, "  just text\n".as_bytes(), now()).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].body, "just text");
+        assert_eq!(items[0].title, None);
+        assert!(parse_payload(&source(), false, &[0xff, 0xfe], now()).is_err());
+    }
+
+    #[test]
+    fn test_parse_payload_rejects() {
+        let reject = |body: &[u8]| parse_payload(&source(), true, body, now()).unwrap_err();
+        assert!(reject(b"{").starts_with("invalid JSON payload"));
+        assert_eq!(reject(b"[]"), "no items in payload");
+        assert_eq!(
+            reject(br#"[{"title": "ok"}, {"body": "  "}]"#),
+            "item 1: needs at least one of 'body', 'title' or 'url'"
+        );
+        assert_eq!(
+            reject(br#"{"url": "ftp://example.com/x"}"#),
+            "item 0: 'url' must be an http(s) URL"
+        );
+        let many = format!("[{}]", vec![r#"{"body": "x"}"#; MAX_ITEMS_PER_REQUEST + 1].join(","));
+        assert_eq!(reject(many.as_bytes()), "101 items in one request, at most 100 allowed");
+    }
+}
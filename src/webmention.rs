@@ -0,0 +1,408 @@
+//! Outgoing Webmentions: with a channel's `webmention`, each published article queues a
+//! Webmention to every page it links to, and the daemon sends them with retries, so cited
+//! authors can discover the coverage (see docs/specs/webmention.md).
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use pulldown_cmark::{Event, Parser, Tag};
+use reqwest::header::{CONTENT_TYPE, LINK, USER_AGENT};
+use reqwest::redirect::Policy;
+use reqwest::{Method, Url};
+use scraper::{Html, Selector};
+use sqlx::SqlitePool;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+use crate::config::{Config, OutputChannelConfig};
+use crate::error::PublicUrlError;
+use crate::models::Webmention;
+use crate::{http_client, store};
+
+/// How often the queue is checked for due Webmentions.
+const SEND_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Webmentions sent per check; the rest wait for the next one.
+const SEND_BATCH: i64 = 50;
+
+/// Attempts before a Webmention is given up as `failed`.
+const MAX_ATTEMPTS: i64 = 6;
+
+/// Wait after the first failed attempt; it doubles with each further one (10m, 20m, ... 2h40m).
+const RETRY_BASE: Duration = Duration::from_secs(600);
+
+/// Per-request timeout of endpoint discovery and sending.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Links queued per article; a digest linking more than this is cut at the limit.
+const MAX_TARGETS: usize = 100;
+
+/// Discovery pages larger than this aren't searched for a `<link>`; the `Link` header still counts.
+const MAX_DISCOVERY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Public URL of an article's page, the `source` of its Webmentions.
+pub fn article_url(public_url: &str, article_id: &str) -> String {
+    format!("{}/article/{article_id}", public_url.trim_end_matches('/'))
+}
+
+/// Queue Webmentions to the pages a published article links to, if its channel has
+/// `webmention`. Links to pail's own pages are left out. Best-effort: failures are logged.
+pub async fn enqueue(
+    pool: &SqlitePool,
+    config: &Config,
+    channel_config: &OutputChannelConfig,
+    article_id: &str,
+    body_markdown: &str,
+) {
+    let Some(ref public_url) = config.pail.public_url else {
+        return;
+    };
+    if !channel_config.webmention {
+        return;
+    }
+    let own_host = Url::parse(public_url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string));
+    let mut targets: Vec<String> = Vec::new();
+    for url in linked_urls(body_markdown) {
+        let Ok(parsed) = Url::parse(&url) else {
+            continue;
+        };
+        if parsed.host_str().is_none() || parsed.host_str().map(str::to_string) == own_host {
+            continue;
+        }
+        if !targets.contains(&url) {
+            targets.push(url);
+        }
+    }
+    targets.truncate(MAX_TARGETS);
+    if targets.is_empty() {
+        return;
+    }
+    match store::enqueue_webmentions(pool, article_id, &targets).await {
+        Ok(queued) => info!(article_id, queued, "queued webmentions"),
+        Err(e) => error!(article_id, "failed to queue webmentions: {e:#}"),
+    }
+}
+
+/// The http(s) link targets of a Markdown body, in order. Images aren't links.
+fn linked_urls(markdown: &str) -> Vec<String> {
+    Parser::new(markdown)
+        .filter_map(|event| match event {
+            Event::Start(Tag::Link { dest_url, .. })
+                if dest_url.starts_with("https://") || dest_url.starts_with("http://") =>
+            {
+                Some(dest_url.to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Send due Webmentions until cancelled. Idles while no channel has `webmention`.
+pub async fn send_loop(pool: SqlitePool, config_rx: watch::Receiver<Arc<Config>>, cancel: CancellationToken) {
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => return,
+            _ = tokio::time::sleep(SEND_INTERVAL) => {}
+        }
+
+        let config = config_rx.borrow().clone();
+        let Some(ref public_url) = config.pail.public_url else {
+            continue;
+        };
+        if let Err(e) = send_due(&pool, &config, public_url, &cancel).await {
+            error!("sending webmentions failed: {e:#}");
+        }
+    }
+}
+
+async fn send_due(pool: &SqlitePool, config: &Config, public_url: &str, cancel: &CancellationToken) -> Result<()> {
+    let due = store::get_due_webmentions(pool, Utc::now(), SEND_BATCH).await?;
+    if due.is_empty() {
+        return Ok(());
+    }
+    // Targets and endpoints come from content: redirects are followed by `send_public`, which
+    // checks every hop
+    let client = http_client::with_proxy(
+        reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .redirect(Policy::none()),
+        config.network.proxy.as_deref(),
+    )
+    .and_then(|builder| builder.build())
+    .context("building HTTP client")?;
+    let user_agent = http_client::crawler_user_agent(&config.network);
+
+    for mut webmention in due {
+        if cancel.is_cancelled() {
+            break;
+        }
+        let source = article_url(public_url, &webmention.article_id);
+        let outcome = send(&client, &user_agent, &source, &mut webmention).await;
+        webmention.attempts += 1;
+        match outcome {
+            Ok(Sent::Accepted) => {
+                debug!(url = %webmention.target, "webmention sent");
+                webmention.status = "sent".to_string();
+                webmention.sent_at = Some(Utc::now());
+                webmention.last_error = None;
+            }
+            Ok(Sent::NoEndpoint) => {
+                debug!(url = %webmention.target, "no webmention endpoint");
+                webmention.status = "no_endpoint".to_string();
+                webmention.last_error = None;
+            }
+            Ok(Sent::Rejected(reason)) => {
+                warn!(url = %webmention.target, "webmention rejected: {reason}");
+                webmention.status = "failed".to_string();
+                webmention.last_error = Some(reason);
+            }
+            Err(e) => {
+                let error = format!("{e:#}");
+                if webmention.attempts >= MAX_ATTEMPTS {
+                    warn!(url = %webmention.target, attempts = webmention.attempts, "webmention failed, giving up: {error}");
+                    webmention.status = "failed".to_string();
+                } else {
+                    debug!(url = %webmention.target, attempts = webmention.attempts, "webmention failed, will retry: {error}");
+                    let wait = RETRY_BASE * 2u32.pow(webmention.attempts as u32 - 1);
+                    webmention.next_attempt_at = Utc::now() + chrono::Duration::from_std(wait).unwrap_or_default();
+                }
+                webmention.last_error = Some(error);
+            }
+        }
+        store::update_webmention(pool, &webmention).await?;
+    }
+    Ok(())
+}
+
+/// How an attempt ended, short of an error worth retrying.
+enum Sent {
+    Accepted,
+    /// The target doesn't advertise an endpoint: nothing to send, ever.
+    NoEndpoint,
+    /// The endpoint refused the Webmention (4xx), or the target or endpoint isn't a public
+    /// address; retrying won't change its mind.
+    Rejected(String),
+}
+
+/// Discover the target's endpoint if needed and post the Webmention. A target or endpoint that
+/// isn't a public address, or redirects too often, is rejected rather than retried.
+async fn send(client: &reqwest::Client, user_agent: &str, source: &str, webmention: &mut Webmention) -> Result<Sent> {
+    match try_send(client, user_agent, source, webmention).await {
+        Err(e)
+            if matches!(
+                e.downcast_ref::<PublicUrlError>(),
+                Some(
+                    PublicUrlError::InvalidUrl(_)
+                        | PublicUrlError::NotPublic { .. }
+                        | PublicUrlError::TooManyRedirects(_)
+                )
+            ) =>
+        {
+            Ok(Sent::Rejected(format!("{e:#}")))
+        }
+        outcome => outcome,
+    }
+}
+
+/// Discover the target's endpoint (once; it's kept for retries) and post the Webmention.
+async fn try_send(
+    client: &reqwest::Client,
+    user_agent: &str,
+    source: &str,
+    webmention: &mut Webmention,
+) -> Result<Sent> {
+    let endpoint = match webmention.endpoint {
+        Some(ref endpoint) => endpoint.clone(),
+        None => match discover_endpoint(client, user_agent, &webmention.target).await? {
+            Some(endpoint) => {
+                webmention.endpoint = Some(endpoint.clone());
+                endpoint
+            }
+            None => return Ok(Sent::NoEndpoint),
+        },
+    };
+    let form = [("source", source), ("target", webmention.target.as_str())];
+    let response = http_client::send_public(client, Method::POST, &endpoint, |request| {
+        request.header(USER_AGENT, user_agent).form(&form)
+    })
+    .await
+    .with_context(|| format!("posting to {endpoint}"))?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(Sent::Accepted)
+    } else if status.is_client_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+        let body = response.text().await.unwrap_or_default();
+        let body: String = body.chars().take(200).collect();
+        Ok(Sent::Rejected(format!("{endpoint} returned {status}: {}", body.trim())))
+    } else {
+        anyhow::bail!("{endpoint} returned {status}")
+    }
+}
+
+/// The target's Webmention endpoint: the first `rel="webmention"` of its `Link` headers, else
+/// of its HTML `<link>` and `<a>` elements, resolved against the final (redirected) URL. Every
+/// hop must be a public address.
+async fn discover_endpoint(client: &reqwest::Client, user_agent: &str, target: &str) -> Result<Option<String>> {
+    let response = http_client::send_public(client, Method::GET, target, |request| {
+        request.header(USER_AGENT, user_agent)
+    })
+    .await
+    .with_context(|| format!("fetching {target}"))?;
+    let status = response.status();
+    if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        anyhow::bail!("{target} returned {status}");
+    }
+    if !status.is_success() {
+        return Ok(None);
+    }
+    let base = response.url().clone();
+
+    let from_header = response
+        .headers()
+        .get_all(LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find_map(endpoint_from_link_header)
+        .map(str::to_string);
+    if let Some(href) = from_header {
+        return Ok(base.join(&href).ok().map(String::from));
+    }
+
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("html"));
+    if !is_html
+        || response
+            .content_length()
+            .is_some_and(|len| len as usize > MAX_DISCOVERY_BYTES)
+    {
+        return Ok(None);
+    }
+    let body = response.text().await.with_context(|| format!("reading {target}"))?;
+    if body.len() > MAX_DISCOVERY_BYTES {
+        return Ok(None);
+    }
+    Ok(endpoint_from_html(&body).and_then(|href| base.join(&href).ok().map(String::from)))
+}
+
+/// The URL of the first link with `rel="webmention"` in a `Link` header value
+/// (`<https://example.org/wm>; rel="webmention", <...>; rel="other"`).
+fn endpoint_from_link_header(value: &str) -> Option<&str> {
+    let mut rest = value;
+    while let Some(start) = rest.find('<') {
+        let end = start + rest[start..].find('>')?;
+        let url = &rest[start + 1..end];
+        let params_end = rest[end..].find('<').map_or(rest.len(), |i| end + i);
+        let is_webmention = rest[end + 1..params_end].split([';', ',']).any(|param| {
+            param.split_once('=').is_some_and(|(key, rels)| {
+                key.trim().eq_ignore_ascii_case("rel")
+                    && rels
+                        .trim()
+                        .trim_matches('"')
+                        .split_whitespace()
+                        .any(|rel| rel.eq_ignore_ascii_case("webmention"))
+            })
+        });
+        if is_webmention {
+            return Some(url);
+        }
+        rest = &rest[params_end..];
+    }
+    None
+}
+
+/// The `href` of the first `<link>` or `<a>` with `rel="webmention"`, in document order. An
+/// empty `href` is the page itself.
+fn endpoint_from_html(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("link[href], a[href]").expect("valid selector");
+    document.select(&selector).find_map(|element| {
+        let rel = element.value().attr("rel")?;
+        rel.split_whitespace()
+            .any(|r| r.eq_ignore_ascii_case("webmention"))
+            .then(|| element.value().attr("href").unwrap_or_default().to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_from_link_header() {
+        assert_eq!(
+            endpoint_from_link_header(r#"<https://example.org/wm>; rel="webmention""#),
+            Some("https://example.org/wm")
+        );
+        assert_eq!(
+            endpoint_from_link_header(r#"<https://example.org/a>; rel="other", </wm>; rel="webmention""#),
+            Some("/wm")
+        );
+        // One of several space-separated rels, unquoted, any case
+        assert_eq!(
+            endpoint_from_link_header(r#"<https://example.org/wm>; rel="me WebMention""#),
+            Some("https://example.org/wm")
+        );
+        assert_eq!(endpoint_from_link_header("</wm>; rel=webmention"), Some("/wm"));
+        assert_eq!(
+            endpoint_from_link_header(r#"<https://example.org/a>; rel="other""#),
+            None
+        );
+        assert_eq!(
+            endpoint_from_link_header(r#"<https://example.org/webmention>; rel="nofollow""#),
+            None
+        );
+        assert_eq!(endpoint_from_link_header(""), None);
+    }
+
+    #[test]
+    fn test_endpoint_from_html_takes_the_first_in_document_order() {
+        let html = r#"<html><head><link rel="stylesheet" href="/style.css"></head><body>
+            <a href="/about">About</a>
+            <a rel="webmention" href="/first">first</a>
+            <link rel="webmention" href="/second">
+            </body></html>"#;
+        assert_eq!(endpoint_from_html(html).as_deref(), Some("/first"));
+    }
+
+    #[test]
+    fn test_endpoint_from_html_rels_and_empty_href() {
+        let html = r#"<html><head><link rel="me Webmention" href="https://wm.example.org/endpoint"></head></html>"#;
+        assert_eq!(
+            endpoint_from_html(html).as_deref(),
+            Some("https://wm.example.org/endpoint")
+        );
+
+        // An empty href is the page itself
+        let html = r#"<html><head><link rel="webmention" href=""></head></html>"#;
+        assert_eq!(endpoint_from_html(html).as_deref(), Some(""));
+
+        // Elements without an href don't count
+        let html = r#"<html><body><a rel="webmention">no href</a></body></html>"#;
+        assert_eq!(endpoint_from_html(html), None);
+        assert_eq!(endpoint_from_html("<html><body><p>nothing</p></body></html>"), None);
+    }
+
+    #[test]
+    fn test_linked_urls() {
+        let markdown = "A [post](https://example.org/post) and [another](http://example.com/x), \
+                        ![image](https://example.org/img.png), [relative](/article/1), \
+                        [mail](mailto:a@example.org) and <https://example.net/auto>.";
+        assert_eq!(
+            linked_urls(markdown),
+            vec![
+                "https://example.org/post",
+                "http://example.com/x",
+                "https://example.net/auto"
+            ]
+        );
+        assert!(linked_urls("No links here.").is_empty());
+    }
+}